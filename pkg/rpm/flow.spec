@@ -18,7 +18,6 @@ URL:		https://github.com/viseztrance/%{name}
 BuildRequires: rust
 BuildRequires: cargo
 BuildRequires: ncurses-devel 
-BuildRequires: readline-devel
 
 %description
 A tail like tool that can group and filter out data.
@@ -59,7 +58,6 @@ URL:		https://github.com/viseztrance/%{name}
 BuildRequires: rust
 BuildRequires: cargo
 BuildRequires: ncurses-devel 
-BuildRequires: readline-devel
 
 %description
 A tail like tool that can group and filter out data.
@@ -21,11 +21,9 @@ extern crate flow;
 
 use docopt::Docopt;
 
-use flow::utils::args::Args;
+use flow::utils::args::{Args, USAGE};
 use flow::utils::settings::Settings;
 
-const USAGE: &'static str = include_str!("../etc/usage.txt");
-
 fn main() {
     let args: Args = Docopt::new(USAGE)
         .and_then(|d| d.decode())
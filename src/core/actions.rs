@@ -0,0 +1,140 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::io;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use regex::{Captures, Regex};
+use rustc_serialize::{Decodable, Decoder};
+
+/// Command run on a line matching a pattern, with the captured groups
+/// substituted into it.
+#[derive(Clone)]
+pub struct QuickAction {
+    pub name: String,
+    pub regex: Regex,
+    pub command: String,
+    /// Interactive commands take over the terminal until they exit, others
+    /// run in the background.
+    pub interactive: bool,
+}
+
+impl QuickAction {
+    /// The command to run for the given line, or `None` when the line
+    /// doesn't match.
+    pub fn command_for(&self, text: &str) -> Option<String> {
+        self.regex.captures(text).map(|captures| expand(&self.command, &captures))
+    }
+}
+
+/// Actions applying to the given line, along with their commands.
+pub fn available<'a>(actions: &'a [QuickAction], text: &str) -> Vec<(&'a QuickAction, String)> {
+    actions.iter()
+        .filter_map(|action| action.command_for(text).map(|command| (action, command)))
+        .collect()
+}
+
+/// Substitutes `$1`, `${name}` and `$0` with the matching groups, quoted for
+/// the shell. `$$` stands for a literal `$` and missing groups are empty.
+pub fn expand(template: &str, captures: &Captures) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(position) = rest.find('$') {
+        result.push_str(&rest[..position]);
+        rest = &rest[position + 1..];
+
+        if rest.starts_with('$') {
+            result.push('$');
+            rest = &rest[1..];
+            continue;
+        }
+
+        let (name, length) = if rest.starts_with('{') {
+            match rest.find('}') {
+                Some(end) => (&rest[1..end], end + 1),
+                None => ("", 0),
+            }
+        } else {
+            let end = rest.find(|value: char| !value.is_digit(10)).unwrap_or(rest.len());
+            (&rest[..end], end)
+        };
+
+        if length == 0 {
+            result.push('$');
+            continue;
+        }
+
+        let value = match name.parse::<usize>() {
+            Ok(index) => captures.get(index),
+            Err(_) => captures.name(name),
+        };
+        result.push_str(&shell_quote(value.map_or("", |group| group.as_str())));
+        rest = &rest[length..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Wraps the value in single quotes, so that the shell passes it as is.
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Starts the command in the background, detached from the terminal.
+pub fn spawn(command: &str) -> io::Result<()> {
+    let mut child = Command::new("sh").arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    // Reaped in the background so that it doesn't linger as a zombie
+    thread::spawn(move || child.wait());
+
+    Ok(())
+}
+
+/// Runs the command attached to the terminal, waiting for it to exit.
+pub fn run(command: &str) -> io::Result<bool> {
+    Command::new("sh").arg("-c").arg(command).status().map(|status| status.success())
+}
+
+impl Decodable for QuickAction {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<QuickAction, D::Error> {
+        decoder.read_struct("QuickAction", 4, |d| {
+            let name = d.read_struct_field("name", 0, |d| d.read_str())?;
+            let pattern = d.read_struct_field("pattern", 1, |d| d.read_str())?;
+            let command = d.read_struct_field("command", 2, |d| d.read_str())?;
+            let interactive = d.read_struct_field("interactive", 3, |d| Option::<bool>::decode(d))?;
+
+            let regex = Regex::new(&pattern)
+                .map_err(|e| d.error(&format!("invalid action `pattern` regex - {}", e)))?;
+
+            Ok(QuickAction {
+                name: name,
+                regex: regex,
+                command: command,
+                interactive: interactive.unwrap_or(false),
+            })
+        })
+    }
+}
@@ -0,0 +1,146 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cmp::min;
+
+use regex::Regex;
+use rustc_serialize::{Decodable, Decoder};
+
+use core::buffer::Buffer;
+use core::line::{LineCollection, Parser};
+
+/// Milliseconds between two jumps to a fired alert, so that an alert firing
+/// over and over doesn't keep switching tabs.
+pub const JUMP_INTERVAL: u64 = 10_000;
+/// Minutes jumps are snoozed for.
+pub const SNOOZE_MINUTES: u64 = 10;
+
+/// Pattern of a tab that's reported when new lines of the tab match it.
+#[derive(Clone)]
+pub struct Alert {
+    pub regex: Regex,
+    /// Switches to the tab and scrolls to the line when it fires on a tab
+    /// that isn't shown.
+    pub jump: bool,
+}
+
+impl Decodable for Alert {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Alert, D::Error> {
+        decoder.read_struct("Alert", 2, |d| {
+            let pattern = d.read_struct_field("pattern", 0, |d| d.read_str())?;
+            let jump = d.read_struct_field("jump", 1, |d| Option::<bool>::decode(d))?;
+
+            let regex = Regex::new(&pattern)
+                .map_err(|e| d.error(&format!("invalid alert `pattern` regex - {}", e)))?;
+
+            Ok(Alert {
+                regex: regex,
+                jump: jump.unwrap_or(false),
+            })
+        })
+    }
+}
+
+/// An alert matching a line.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Firing {
+    /// Index of the alert in its tab.
+    pub alert: usize,
+    pub line_id: usize,
+}
+
+/// Alerts fired by the lines of the tab received after the given id,
+/// newest first.
+pub fn fired(buffer: &Buffer, lines: &LineCollection, after: usize) -> Vec<Firing> {
+    fired_including(buffer, lines, after, &[])
+}
+
+/// Same as `fired`, the lines with the given ids received before being
+/// checked too, as partial lines are once completed.
+pub fn fired_including(buffer: &Buffer,
+                       lines: &LineCollection,
+                       after: usize,
+                       completed: &[usize])
+                       -> Vec<Firing> {
+    let alerts = &buffer.filter.alerts;
+    if alerts.is_empty() {
+        return vec![];
+    }
+
+    let oldest = completed.iter().map(|id| id - 1).fold(after, min);
+    lines.entries
+        .iter()
+        .parse(buffer.filter.clone())
+        .take_while(|line| line.id > oldest)
+        .filter(|line| line.id > after || completed.contains(&line.id))
+        .filter(|line| !line.partial)
+        .filter_map(|line| {
+            alerts.iter()
+                .position(|alert| alert.regex.is_match(&line.content_without_ansi))
+                .map(|index| {
+                    Firing {
+                        alert: index,
+                        line_id: line.id,
+                    }
+                })
+        })
+        .collect()
+}
+
+/// When fired alerts may switch tabs: not too often, and not while snoozed.
+#[derive(Default)]
+pub struct JumpGuard {
+    last_jump_at: Option<u64>,
+    snoozed_until: Option<u64>,
+}
+
+impl JumpGuard {
+    pub fn may_jump(&self, now: u64) -> bool {
+        let rested = self.last_jump_at.map_or(true, |at| now.saturating_sub(at) >= JUMP_INTERVAL);
+        rested && self.snoozed_until(now).is_none()
+    }
+
+    pub fn jumped(&mut self, now: u64) {
+        self.last_jump_at = Some(now);
+    }
+
+    /// Stops jumping for the given minutes.
+    pub fn snooze(&mut self, now: u64, minutes: u64) {
+        self.snoozed_until = Some(now + minutes * 60_000);
+    }
+
+    pub fn resume(&mut self) {
+        self.snoozed_until = None;
+    }
+
+    /// When jumps resume, while snoozed.
+    pub fn snoozed_until(&self, now: u64) -> Option<u64> {
+        self.snoozed_until.filter(|&until| until > now)
+    }
+
+    /// Forgets a snooze that ran out, returning whether there was one.
+    pub fn expire(&mut self, now: u64) -> bool {
+        match self.snoozed_until {
+            Some(until) if until <= now => {
+                self.snoozed_until = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
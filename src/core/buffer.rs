@@ -16,63 +16,215 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::cmp::{min, max};
 use std::cell::Cell;
 
+use time;
+
 use core::line::{Line, LineCollection, Parser as LineParser};
 use core::filter::Filter;
+use core::highlights::Highlights;
+use core::rewrite::{RewriteRule, rewrite};
+use ui::viewport::{Anchor, LineAnchor};
 
 static DEFAULT_REVERSE_INDEX: usize = 0;
-static MAX_LINES_RENDERED: usize = 2_000;
+static DEFAULT_MAX_ROWS: usize = 2_000;
+
+#[derive(Clone)]
+pub struct ClearMarker {
+    pub position: usize,
+    pub line: Line,
+}
+
+/// Where each tab was left, restored once it's selected again.
+#[derive(Clone, Copy)]
+pub struct TabState {
+    pub reverse_index: usize,
+    pub anchor: Anchor,
+    pub highlighted_line_id: Option<usize>,
+    pub highlighted_match: usize,
+    pub top_line_id: Option<usize>,
+    /// Keeps the screen on the same lines while stepping through matches,
+    /// instead of following new content.
+    pub pinned: Option<LineAnchor>,
+    /// Set when the screen is resized while another tab is selected, as the
+    /// scroll position no longer matches the wrapped lines.
+    pub needs_rewrap: bool,
+}
+
+impl TabState {
+    fn default() -> TabState {
+        TabState {
+            reverse_index: DEFAULT_REVERSE_INDEX,
+            anchor: Anchor::Bottom,
+            highlighted_line_id: None,
+            highlighted_match: 0,
+            top_line_id: None,
+            pinned: None,
+            needs_rewrap: false,
+        }
+    }
+}
 
 pub struct Buffer {
     pub filter: Filter,
-    pub reverse_index: Cell<usize>,
+    pub state: Cell<TabState>,
+    pub clear_marker: Option<ClearMarker>,
+    /// Lines are shown as received, without any of the display features.
+    pub show_raw: bool,
+    /// Lines the tab was frozen with, shown instead of the ones received.
+    pub frozen: Option<LineCollection>,
 }
 
 impl Buffer {
     pub fn new(filter: Filter) -> Buffer {
         Buffer {
             filter: filter,
-            reverse_index: Cell::new(DEFAULT_REVERSE_INDEX),
+            state: Cell::new(TabState::default()),
+            clear_marker: None,
+            show_raw: false,
+            frozen: None,
         }
     }
 
-    pub fn with_lines<'a>(&'a self, lines: &'a LineCollection) -> BufferLines<'a> {
-        BufferLines::new(self, lines)
+    /// Read-only copy of the tab as it shows the given lines, under another
+    /// name. It keeps the filter, rules and rewrites, but raises no alerts.
+    pub fn freeze(&self, name: String, lines: &LineCollection) -> Buffer {
+        let mut filter = self.filter.clone();
+        filter.name = name;
+        filter.alerts.clear();
+
+        Buffer {
+            filter: filter,
+            state: Cell::new(TabState::default()),
+            clear_marker: self.clear_marker.clone(),
+            show_raw: self.show_raw,
+            frozen: Some(self.lines(lines).frozen_copy()),
+        }
+    }
+
+    /// Lines shown by the tab, which are the ones received unless frozen.
+    pub fn lines<'a>(&'a self, received: &'a LineCollection) -> &'a LineCollection {
+        self.frozen.as_ref().unwrap_or(received)
+    }
+
+    pub fn update_state<F>(&self, callback: F)
+        where F: FnOnce(&mut TabState)
+    {
+        let mut state = self.state.get();
+        callback(&mut state);
+        self.state.set(state);
+    }
+
+    pub fn reverse_index(&self) -> usize {
+        self.state.get().reverse_index
+    }
+
+    pub fn set_reverse_index(&self, value: usize) {
+        self.update_state(|state| state.reverse_index = value);
     }
 
-    pub fn increment_reverse_index(&self, value: i32, max_value: usize) {
-        self.set_reverse_index(self.reverse_index.get() as i32 + value, max_value);
+    pub fn add_rewrite(&mut self, rule: RewriteRule) {
+        self.filter.rewrites.push(rule);
+        self.show_raw = false;
     }
 
-    pub fn set_reverse_index(&self, value: i32, max_value: usize) {
-        self.reverse_index.set(min(max(0, value) as usize, max_value));
+    /// The line as shown on screen, after applying the rewrite rules. Colors
+    /// are dropped from lines changed by a rule. While showing raw lines,
+    /// it's the line as received instead.
+    pub fn display_line(&self, line: &Line) -> Line {
+        if self.show_raw {
+            return line.as_received();
+        }
+        if self.filter.rewrites.is_empty() {
+            return line.clone();
+        }
+
+        match rewrite(&self.filter.rewrites, &line.content_without_ansi) {
+            Some(text) => {
+                let mut rewritten = Line::with_id(text, line.id);
+                rewritten.received_at = line.received_at;
+                rewritten.wrapper = line.wrapper.clone();
+                rewritten.partial = line.partial;
+                rewritten.source = line.source;
+                rewritten.number = line.number;
+                rewritten
+            }
+            None => line.clone(),
+        }
+    }
+
+    /// Hides every line received so far, leaving a marker in their place.
+    pub fn clear(&mut self, lines: &LineCollection) {
+        let timestamp = time::strftime("%H:%M:%S", &time::now()).unwrap();
+
+        let position = self.lines(lines).received();
+        self.clear_marker = Some(ClearMarker {
+            position: position,
+            line: Line::new(format!("--- buffer cleared at {} ---", timestamp)),
+        });
+        self.reset_reverse_index();
+    }
+
+    pub fn with_lines<'a>(&'a self, lines: &'a LineCollection) -> BufferLines<'a> {
+        BufferLines::new(self, self.lines(lines), &lines.highlights)
     }
 
     pub fn is_scrolled(&self) -> bool {
-        self.reverse_index.get() != DEFAULT_REVERSE_INDEX
+        self.reverse_index() != DEFAULT_REVERSE_INDEX
     }
 
     pub fn reset_reverse_index(&self) {
-        self.reverse_index.set(DEFAULT_REVERSE_INDEX);
+        self.set_reverse_index(DEFAULT_REVERSE_INDEX);
     }
 }
 
 pub struct BufferLines<'a> {
     lines: &'a LineCollection,
+    /// Those of the lines received, so that frozen tabs follow changes.
+    highlights: &'a Highlights,
     pub buffer: &'a Buffer,
     pub width: usize,
+    /// Estimated rows of the newest lines iterated over.
+    pub max_rows: usize,
 }
 
 impl<'a> BufferLines<'a> {
-    fn new(buffer: &'a Buffer, lines: &'a LineCollection) -> BufferLines<'a> {
+    fn new(buffer: &'a Buffer,
+           lines: &'a LineCollection,
+           highlights: &'a Highlights)
+           -> BufferLines<'a> {
         BufferLines {
             buffer: buffer,
             lines: lines,
+            highlights: highlights,
             width: 0,
+            max_rows: DEFAULT_MAX_ROWS,
         }
     }
+
+    pub fn highlights(&self) -> &Highlights {
+        self.highlights
+    }
+
+    /// Every line shown by the tab, oldest first, as displayed. Unlike
+    /// iterating, this isn't limited to what fits on screen.
+    pub fn all(&self) -> impl Iterator<Item = Line> + 'a {
+        let skipped = match self.buffer.clear_marker {
+            Some(ref marker) => self.lines.entries_before(marker.position),
+            None => 0,
+        };
+
+        let mut lines = self.lines
+            .entries
+            .iter()
+            .skip(skipped)
+            .parse(self.buffer.filter.clone())
+            .collect::<Vec<_>>();
+        lines.reverse();
+
+        let buffer = self.buffer;
+        lines.into_iter().map(move |line| buffer.display_line(line))
+    }
 }
 
 impl<'a> IntoIterator for &'a BufferLines<'a> {
@@ -84,15 +236,25 @@ impl<'a> IntoIterator for &'a BufferLines<'a> {
 
         let height_within_boundary = |line: &&Line| -> bool {
             estimated_height += line.guess_height(self.width);
-            estimated_height <= MAX_LINES_RENDERED
+            estimated_height <= self.max_rows
+        };
+
+        let skipped = match self.buffer.clear_marker {
+            Some(ref marker) => self.lines.entries_before(marker.position),
+            None => 0,
         };
 
         let mut lines = self.lines
             .entries
             .iter()
+            .skip(skipped)
             .parse(self.buffer.filter.clone())
             .take_while(height_within_boundary)
             .collect::<Vec<_>>();
+
+        if let Some(ref marker) = self.buffer.clear_marker {
+            lines.push(&marker.line);
+        }
         lines.reverse();
         lines.into_iter()
     }
@@ -113,10 +275,52 @@ impl BufferCollection {
         }
     }
 
+    pub fn iter(&self) -> ::std::slice::Iter<Buffer> {
+        self.items.iter()
+    }
+
     pub fn selected_item(&self) -> &Buffer {
         self.items.get(self.index).unwrap()
     }
 
+    pub fn selected_item_mut(&mut self) -> &mut Buffer {
+        self.items.get_mut(self.index).unwrap()
+    }
+
+    pub fn inactive_items(&self) -> Vec<&Buffer> {
+        self.items.iter().enumerate().filter(|&(i, _)| i != self.index).map(|(_, item)| item).collect()
+    }
+
+    pub fn push(&mut self, filter: Filter) {
+        self.items.push(Buffer::new(filter));
+    }
+
+    pub fn selected(&self) -> usize {
+        self.index
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.index = index.min(self.items.len() - 1);
+    }
+
+    /// Adds a tab after the others.
+    pub fn push_buffer(&mut self, buffer: Buffer) {
+        self.items.push(buffer);
+    }
+
+    /// Drops the selected tab, selecting the one before it. The last tab
+    /// left is kept.
+    pub fn remove_selected(&mut self) {
+        if self.items.len() > 1 {
+            self.items.remove(self.index);
+            self.index = self.index.saturating_sub(1);
+        }
+    }
+
+    pub fn select_last(&mut self) {
+        self.index = self.items.len() - 1;
+    }
+
     pub fn select_previous(&mut self) {
         if self.index > 0 {
             self.index -= 1;
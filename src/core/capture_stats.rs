@@ -0,0 +1,134 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/// Numbers captured by the first group of a search regex, such as
+/// `duration=(\d+)ms`, across the lines of a tab.
+pub struct CaptureStats {
+    /// Sorted in ascending order.
+    values: Vec<f64>,
+    /// Captures that weren't numbers.
+    pub failures: usize,
+}
+
+impl CaptureStats {
+    pub fn new<'a, I>(captures: I) -> CaptureStats
+        where I: IntoIterator<Item = &'a str>
+    {
+        let mut values = vec![];
+        let mut failures = 0;
+
+        for capture in captures {
+            match capture.trim().parse::<f64>() {
+                Ok(value) if value.is_finite() => values.push(value),
+                _ => failures += 1,
+            }
+        }
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        CaptureStats {
+            values: values,
+            failures: failures,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.values.first().cloned()
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.values.last().cloned()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        if self.values.is_empty() {
+            None
+        } else {
+            Some(self.values.iter().sum::<f64>() / self.values.len() as f64)
+        }
+    }
+
+    /// Nearest-rank percentile, `percent` being between 0 and 100.
+    pub fn percentile(&self, percent: f64) -> Option<f64> {
+        if self.values.is_empty() {
+            return None;
+        }
+
+        let rank = (percent / 100.0 * self.values.len() as f64).ceil() as usize;
+        Some(self.values[rank.max(1).min(self.values.len()) - 1])
+    }
+
+    /// Number of values within each of `buckets` equal ranges between the
+    /// smallest and the largest value.
+    pub fn histogram(&self, buckets: usize) -> Vec<(f64, usize)> {
+        let (min, max) = match (self.min(), self.max()) {
+            (Some(min), Some(max)) if buckets > 0 => (min, max),
+            _ => return vec![],
+        };
+
+        let step = (max - min) / buckets as f64;
+        let mut counts = vec![0; buckets];
+        for value in &self.values {
+            let index = if step == 0.0 { 0 } else { ((value - min) / step) as usize };
+            counts[index.min(buckets - 1)] += 1;
+        }
+
+        counts.into_iter().enumerate().map(|(i, count)| (min + step * i as f64, count)).collect()
+    }
+
+    /// Rows of the overlay, ending with a histogram whose bars are up to
+    /// `bar_width` columns wide.
+    pub fn describe(&self, buckets: usize, bar_width: usize) -> Vec<String> {
+        let mut rows = vec![format!("count {}", self.count())];
+        if self.failures > 0 {
+            rows.push(format!("not numbers {}", self.failures));
+        }
+
+        if let (Some(min), Some(max), Some(mean)) = (self.min(), self.max(), self.mean()) {
+            rows.push(format!("min {}  max {}  mean {}",
+                              format_number(min),
+                              format_number(max),
+                              format_number(mean)));
+            rows.push(format!("p50 {}  p95 {}  p99 {}",
+                              format_number(self.percentile(50.0).unwrap()),
+                              format_number(self.percentile(95.0).unwrap()),
+                              format_number(self.percentile(99.0).unwrap())));
+        }
+
+        let histogram = self.histogram(buckets);
+        let largest = histogram.iter().map(|&(_, count)| count).max().unwrap_or(0);
+        for (start, count) in histogram {
+            let bar = (count * bar_width + largest - 1) / largest;
+            rows.push(format!("{:>8} {} {}", format_number(start), "█".repeat(bar), count));
+        }
+
+        rows
+    }
+}
+
+/// Whole numbers without decimals, others with up to two.
+pub fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.2}", value)
+    }
+}
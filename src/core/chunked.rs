@@ -0,0 +1,78 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cmp::min;
+use std::ops::Range;
+
+/// Lines searched between two reads of the keyboard.
+pub const DEFAULT_CHUNK_SIZE: usize = 500;
+
+/// Work over many items done a chunk at a time, so that input is read in
+/// between and the work can be given up halfway. Searches go through the
+/// rendered lines this way, and so can other passes over the whole buffer.
+#[derive(Clone, Debug)]
+pub struct ChunkedPass {
+    total: usize,
+    done: usize,
+    chunk_size: usize,
+}
+
+impl ChunkedPass {
+    pub fn new(total: usize, chunk_size: usize) -> ChunkedPass {
+        ChunkedPass {
+            total: total,
+            done: 0,
+            chunk_size: chunk_size.max(1),
+        }
+    }
+
+    /// Whether the items are too many to go through at once.
+    pub fn is_needed(total: usize, chunk_size: usize) -> bool {
+        total > chunk_size
+    }
+
+    /// Items to work on next, none once they all were.
+    pub fn next_chunk(&mut self) -> Option<Range<usize>> {
+        if self.is_finished() {
+            return None;
+        }
+
+        let start = self.done;
+        self.done = min(start + self.chunk_size, self.total);
+        Some(start..self.done)
+    }
+
+    /// Accounts for items added or dropped since the pass started.
+    pub fn set_total(&mut self, total: usize) {
+        self.total = total;
+        self.done = min(self.done, total);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.done >= self.total
+    }
+
+    /// Share of the items gone through, as a percentage.
+    pub fn percent(&self) -> usize {
+        if self.total == 0 {
+            100
+        } else {
+            self.done * 100 / self.total
+        }
+    }
+}
@@ -0,0 +1,99 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use regex::Regex;
+
+lazy_static! {
+    static ref COMPARISON: Regex =
+        Regex::new(r"^(>=|<=|>|<)\s*(\d+(?:\.\d+)?)(ms|s|m|h)?$").unwrap();
+    static ref NUMBER: Regex = Regex::new(r"^-?\d+(?:\.\d+)?").unwrap();
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Operator {
+    Above,
+    AtLeast,
+    Below,
+    AtMost,
+}
+
+/// Numeric comparison a field value is searched with, as in `duration:>500ms`.
+/// Durations are compared in milliseconds, values without a unit as they are.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Comparison {
+    operator: Operator,
+    value: f64,
+}
+
+impl Comparison {
+    pub fn parse(text: &str) -> Option<Comparison> {
+        COMPARISON.captures(text).map(|captures| {
+            let operator = match &captures[1] {
+                ">=" => Operator::AtLeast,
+                "<=" => Operator::AtMost,
+                ">" => Operator::Above,
+                _ => Operator::Below,
+            };
+            let value = captures[2].parse::<f64>().unwrap();
+            let unit = captures.get(3).map_or("", |unit| unit.as_str());
+
+            Comparison {
+                operator: operator,
+                value: value * milliseconds_in(unit).unwrap(),
+            }
+        })
+    }
+
+    pub fn holds(&self, text: &str) -> bool {
+        let value = match number(text) {
+            Some(value) => value,
+            None => return false,
+        };
+
+        match self.operator {
+            Operator::Above => value > self.value,
+            Operator::AtLeast => value >= self.value,
+            Operator::Below => value < self.value,
+            Operator::AtMost => value <= self.value,
+        }
+    }
+}
+
+/// Number at the start of a value, such as `1523.4` of `1523.4ms`.
+pub fn number_prefix(text: &str) -> Option<&str> {
+    NUMBER.find(text.trim_start()).map(|found| found.as_str())
+}
+
+// A value followed by a duration unit is measured in milliseconds
+fn number(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let prefix = number_prefix(text)?;
+    let unit = milliseconds_in(text[prefix.len()..].trim())?;
+
+    prefix.parse::<f64>().ok().map(|value| value * unit)
+}
+
+fn milliseconds_in(unit: &str) -> Option<f64> {
+    match unit {
+        "" | "ms" => Some(1.0),
+        "s" => Some(1_000.0),
+        "m" => Some(60_000.0),
+        "h" => Some(3_600_000.0),
+        _ => None,
+    }
+}
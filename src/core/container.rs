@@ -0,0 +1,111 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::HashMap;
+
+use rustc_serialize::json::Json;
+
+/// Lines of a source looked at before deciding whether it's wrapped.
+pub const SAMPLE_LINES: usize = 5;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// What a container runtime wrapped a line of output with, as in
+/// `{"log":"started\n","stream":"stdout","time":"2016-03-01T14:32:05Z"}`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Wrapper {
+    /// The line as received.
+    pub raw: String,
+    pub stream: Stream,
+    pub time: Option<String>,
+}
+
+/// Splits a wrapped line into the line logged and its wrapper.
+pub fn unwrap(text: &str) -> Option<(String, Wrapper)> {
+    if !text.starts_with('{') {
+        return None;
+    }
+
+    let json = match Json::from_str(text) {
+        Ok(value) => value,
+        Err(_) => return None,
+    };
+    let log = match json.find("log").and_then(Json::as_string) {
+        Some(value) => value.trim_end_matches(|c| c == '\n' || c == '\r').to_string(),
+        None => return None,
+    };
+    let stream = match json.find("stream").and_then(Json::as_string) {
+        Some("stderr") => Stream::Stderr,
+        _ => Stream::Stdout,
+    };
+
+    Some((log,
+          Wrapper {
+        raw: text.to_string(),
+        stream: stream,
+        time: json.find("time").and_then(Json::as_string).map(str::to_string),
+    }))
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Detection {
+    /// Lines seen so far, all of them wrapped.
+    Sampling(usize),
+    Wrapped,
+    Plain,
+}
+
+/// Tells apart, for each source, whether its lines are wrapped by looking
+/// at the first few. Sources found to be plain aren't parsed anymore, while
+/// lines of wrapped ones that fail to unwrap are kept as received.
+pub struct Unwrapper {
+    sources: HashMap<usize, Detection>,
+}
+
+impl Unwrapper {
+    pub fn new() -> Unwrapper {
+        Unwrapper { sources: HashMap::new() }
+    }
+
+    pub fn unwrap(&mut self, source: usize, text: &str) -> Option<(String, Wrapper)> {
+        let detection = self.sources.entry(source).or_insert(Detection::Sampling(0));
+        if *detection == Detection::Plain {
+            return None;
+        }
+
+        let unwrapped = unwrap(text);
+        if let Detection::Sampling(count) = *detection {
+            *detection = match unwrapped {
+                None => Detection::Plain,
+                Some(_) if count + 1 >= SAMPLE_LINES => Detection::Wrapped,
+                Some(_) => Detection::Sampling(count + 1),
+            };
+        }
+
+        unwrapped
+    }
+
+    /// Whether the source was found to be wrapped.
+    pub fn is_wrapped(&self, source: usize) -> bool {
+        self.sources.get(&source) == Some(&Detection::Wrapped)
+    }
+}
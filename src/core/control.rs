@@ -0,0 +1,195 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+use libc;
+use rustc_serialize::json::Json;
+
+const CONTROL_THREAD_NAME: &'static str = "control";
+/// How long a command waits for the ui thread to answer it.
+const REPLY_TIMEOUT_SECONDS: u64 = 5;
+
+/// What scripts and editors can ask for over the control socket, one JSON
+/// object a line, as in `{"cmd":"goto","line":1234}`.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
+    Search(String),
+    /// Scrolls to the line at the position, starting from 1.
+    Goto(usize),
+    SelectTab(String),
+    Status,
+}
+
+impl Command {
+    pub fn parse(text: &str) -> Result<Command, String> {
+        let json = Json::from_str(text).map_err(|error| format!("Invalid JSON - {}", error))?;
+        let object = json.as_object().ok_or_else(|| "Expected a JSON object".to_string())?;
+        let string = |key: &str| {
+            object.get(key)
+                .and_then(Json::as_string)
+                .map(str::to_string)
+                .ok_or_else(|| format!("Expected a string `{}`", key))
+        };
+
+        match object.get("cmd").and_then(Json::as_string) {
+            Some("search") => string("q").map(Command::Search),
+            Some("goto") => {
+                match object.get("line").and_then(Json::as_u64) {
+                    Some(value) if value > 0 => Ok(Command::Goto(value as usize)),
+                    _ => Err("Expected a positive number `line`".to_string()),
+                }
+            }
+            Some("select_tab") => string("name").map(Command::SelectTab),
+            Some("status") => Ok(Command::Status),
+            Some(name) => Err(format!("Unknown command `{}`", name)),
+            None => Err("Expected a string `cmd`".to_string()),
+        }
+    }
+}
+
+/// A command handed over to the ui thread, which answers it there.
+pub struct Request {
+    pub command: Command,
+    reply: Sender<Json>,
+}
+
+impl Request {
+    pub fn new(command: Command, reply: Sender<Json>) -> Request {
+        Request {
+            command: command,
+            reply: reply,
+        }
+    }
+
+    /// Answers with `"ok": true` along with the fields, or with the error.
+    pub fn answer(self, result: Result<Vec<(&str, Json)>, String>) {
+        let _ = self.reply.send(reply(result));
+    }
+}
+
+fn reply(result: Result<Vec<(&str, Json)>, String>) -> Json {
+    let mut object = BTreeMap::new();
+    match result {
+        Ok(fields) => {
+            object.insert("ok".to_string(), Json::Boolean(true));
+            for (key, value) in fields {
+                object.insert(key.to_string(), value);
+            }
+        }
+        Err(message) => {
+            object.insert("ok".to_string(), Json::Boolean(false));
+            object.insert("error".to_string(), Json::String(message));
+        }
+    }
+    Json::Object(object)
+}
+
+/// Unix socket taking commands, only accessible to the user running flow.
+/// It's removed once dropped.
+pub struct ControlSocket {
+    pub path: PathBuf,
+}
+
+impl ControlSocket {
+    /// Listens on the path, handing every command received over as a request
+    /// and calling `notify` for the ui thread to pick it up. A socket left
+    /// over by a flow that's gone is replaced.
+    pub fn bind<F>(path: &Path, requests: Sender<Request>, notify: F) -> io::Result<ControlSocket>
+        where F: Fn() + Send + Sync + 'static
+    {
+        if path.exists() {
+            if UnixStream::connect(path).is_ok() {
+                return Err(io::Error::new(io::ErrorKind::AddrInUse, "already in use"));
+            }
+            fs::remove_file(path)?;
+        }
+
+        // Created private, rather than restricted once others could connect
+        let umask = unsafe { libc::umask(0o177) };
+        let bound = UnixListener::bind(path);
+        unsafe {
+            libc::umask(umask);
+        }
+        let listener = bound?;
+
+        let notify = Arc::new(notify);
+        thread::Builder::new()
+            .name(CONTROL_THREAD_NAME.to_string())
+            .spawn(move || {
+                for stream in listener.incoming().filter_map(Result::ok) {
+                    let requests = requests.clone();
+                    let notify = notify.clone();
+                    thread::spawn(move || serve(stream, &requests, &*notify));
+                }
+            })?;
+
+        Ok(ControlSocket { path: path.to_path_buf() })
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// Answers the commands of a connection in order, until it's closed
+fn serve<F: Fn()>(stream: UnixStream, requests: &Sender<Request>, notify: &F) {
+    let mut writer = match stream.try_clone() {
+        Ok(value) => value,
+        Err(_) => return,
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(value) => value,
+            Err(_) => return,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let answer = match Command::parse(&line) {
+            Ok(command) => {
+                let (sender, receiver) = mpsc::channel();
+                if requests.send(Request::new(command, sender)).is_err() {
+                    return;
+                }
+                notify();
+
+                let timeout = Duration::from_secs(REPLY_TIMEOUT_SECONDS);
+                receiver.recv_timeout(timeout)
+                    .unwrap_or_else(|_| reply(Err("No answer from flow".to_string())))
+            }
+            Err(message) => reply(Err(message)),
+        };
+
+        if writeln!(writer, "{}", answer).is_err() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,90 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use core::buffer::Buffer;
+use core::filter::Filter;
+use core::line::{LineCollection, SearchPattern};
+use core::timestamp::format_minute;
+
+/// Occurrences of a pattern in the lines of a file, as the search counts
+/// them in the tab of the filter.
+#[derive(Debug, Default, PartialEq)]
+pub struct Count {
+    pub total: usize,
+    /// Occurrences by the minute their line was logged at, for lines having
+    /// a timestamp.
+    pub minutes: BTreeMap<i64, usize>,
+}
+
+impl Count {
+    /// Lines go through the same parsing as when following them, container
+    /// runtime logs being unwrapped when asked for.
+    pub fn from_lines<I, S>(lines: I,
+                            filter: Filter,
+                            pattern: &SearchPattern,
+                            unwrap: bool)
+                            -> Count
+        where I: IntoIterator<Item = S>,
+              S: Into<Arc<str>>
+    {
+        let lines = lines.into_iter().map(|line| (0, line.into())).collect::<Vec<(_, Arc<str>)>>();
+        let mut collection = LineCollection::new(lines.len());
+        collection.set_unwrap(unwrap);
+        collection.extend(lines);
+
+        let buffer = Buffer::new(filter);
+        let mut count = Count::default();
+        for line in buffer.with_lines(&collection).all() {
+            let found = line.matches_for(pattern).len();
+            if found == 0 {
+                continue;
+            }
+
+            count.total += found;
+            if let Some(value) = line.timestamp() {
+                *count.minutes.entry(value - value.rem_euclid(60_000)).or_insert(0) += found;
+            }
+        }
+
+        count
+    }
+}
+
+/// Tab separated rows of the count of each file, followed by their total.
+/// Counts by minute follow the count of their file when asked for.
+pub fn report(counts: &[(String, Count)], per_minute: bool) -> String {
+    let mut rows = vec![];
+    for &(ref name, ref count) in counts {
+        rows.push(format!("{}\t{}", name, count.total));
+        if per_minute {
+            for (minute, value) in &count.minutes {
+                rows.push(format!("{}\t{}\t{}", name, format_minute(*minute), value));
+            }
+        }
+    }
+    rows.push(format!("total\t{}", total(counts)));
+
+    rows.join("\n")
+}
+
+pub fn total(counts: &[(String, Count)]) -> usize {
+    counts.iter().map(|&(_, ref count)| count.total).sum()
+}
@@ -0,0 +1,101 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::io::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender};
+use std::time::Duration;
+
+use time;
+
+use core::runner::RUNNING;
+
+/// Entries waiting to be written, past which new ones are dropped.
+const CAPACITY: usize = 4096;
+
+/// Path to write the debug log to, when not given with `--debug-log`.
+pub const ENV_VARIABLE: &'static str = "FLOW_DEBUG_LOG";
+
+lazy_static! {
+    static ref ENABLED: AtomicBool = AtomicBool::new(false);
+    static ref DROPPED: AtomicUsize = AtomicUsize::new(0);
+    static ref SENDER: Mutex<Option<SyncSender<String>>> = Mutex::new(None);
+}
+
+/// Checked by `debug_log!` before formatting anything.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Starts queueing entries, which are then read from the receiver.
+pub fn enable() -> Receiver<String> {
+    let (sender, receiver) = sync_channel(CAPACITY);
+    *SENDER.lock().unwrap() = Some(sender);
+    ENABLED.store(true, Ordering::Relaxed);
+
+    receiver
+}
+
+pub fn disable() {
+    ENABLED.store(false, Ordering::Relaxed);
+    *SENDER.lock().unwrap() = None;
+}
+
+/// Entries dropped as the writer fell behind.
+pub fn dropped() -> usize {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+/// Queues an entry without ever waiting on the writer, dropping it when the
+/// queue is full.
+pub fn write(target: &str, message: String) {
+    let now = time::now();
+    let entry = format!("{}.{:03} [{}] {}",
+                        time::strftime("%Y-%m-%dT%H:%M:%S", &now).unwrap(),
+                        now.tm_nsec / 1_000_000,
+                        target,
+                        message);
+
+    if let Some(ref sender) = *SENDER.lock().unwrap() {
+        if sender.try_send(entry).is_err() {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Writes entries as they're queued, until flow quits or logging stops.
+pub fn write_until_stopped<W: Write>(receiver: Receiver<String>, output: &mut W) {
+    loop {
+        match receiver.recv_timeout(Duration::from_millis(100)) {
+            Ok(entry) => {
+                let _ = writeln!(output, "{}", entry);
+            }
+            Err(RecvTimeoutError::Timeout) if running!() => {}
+            Err(_) => break,
+        }
+    }
+
+    for entry in receiver.try_iter() {
+        let _ = writeln!(output, "{}", entry);
+    }
+    if dropped() > 0 {
+        let _ = writeln!(output, "{} entries were dropped", dropped());
+    }
+    let _ = output.flush();
+}
@@ -0,0 +1,440 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use regex::{self, Regex};
+
+use core::runner::{self, RUNNING};
+use core::tail::Tail;
+use core::error::{self, FlowError};
+use core::health::{Health, HealthBoard, HealthChange};
+
+/// Files matching the pattern are followed up to this count.
+pub static MAX_SOURCES: usize = 32;
+
+const SCAN_INTERVAL: u64 = 1_000;
+
+/// A path whose file name may contain `*`, `?` or `[...]` wildcards.
+pub struct GlobPattern {
+    pub directory: PathBuf,
+    name: Regex,
+}
+
+impl GlobPattern {
+    pub fn is_glob(value: &str) -> bool {
+        value.contains(|c| c == '*' || c == '?' || c == '[')
+    }
+
+    pub fn parse(value: &str) -> Result<GlobPattern, String> {
+        let path = Path::new(value);
+        let directory = match path.parent() {
+            Some(parent) if parent.as_os_str().is_empty() => PathBuf::from("."),
+            Some(parent) => parent.to_path_buf(),
+            None => PathBuf::from("."),
+        };
+
+        if GlobPattern::is_glob(&directory.to_string_lossy()) {
+            return Err(format!("Only the file name of `{}` may contain wildcards", value));
+        }
+
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let pattern = format!("^{}$", glob_to_regex(&name));
+
+        match Regex::new(&pattern) {
+            Ok(regex) => {
+                Ok(GlobPattern {
+                    directory: directory,
+                    name: regex,
+                })
+            }
+            Err(_) => Err(format!("Invalid file pattern `{}`", value)),
+        }
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        path.file_name().map_or(false, |name| self.name.is_match(&name.to_string_lossy()))
+    }
+
+    /// Regular files currently matching, sorted by path.
+    pub fn scan(&self) -> Vec<PathBuf> {
+        let entries = match fs::read_dir(&self.directory) {
+            Ok(value) => value,
+            Err(_) => return vec![],
+        };
+
+        let mut paths = entries.filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && self.matches(path))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        paths
+    }
+}
+
+fn glob_to_regex(value: &str) -> String {
+    let mut result = String::new();
+    // Characters within the `[...]` class being read, if any
+    let mut class: Option<String> = None;
+
+    for c in value.chars() {
+        match class.take() {
+            Some(mut members) => {
+                if c == ']' && !members.is_empty() && members != "!" {
+                    let negated = members.starts_with('!');
+                    let members = if negated { &members[1..] } else { &members[..] };
+                    result.push_str(&format!("[{}{}]",
+                                             if negated { "^" } else { "" },
+                                             members.replace('\\', "\\\\").replace('[', "\\[")));
+                } else {
+                    members.push(c);
+                    class = Some(members);
+                }
+            }
+            None => {
+                match c {
+                    '*' => result.push_str("[^/]*"),
+                    '?' => result.push_str("[^/]"),
+                    '[' => class = Some(String::new()),
+                    _ => result.push_str(&regex::escape(&c.to_string())),
+                }
+            }
+        }
+    }
+
+    // An unclosed class is taken literally
+    if let Some(members) = class {
+        result.push_str(&regex::escape(&format!("[{}", members)));
+    }
+
+    result
+}
+
+#[derive(PartialEq, Debug)]
+pub enum SourceChange {
+    Added(PathBuf),
+    Removed(PathBuf),
+    /// Matching, but not followed as `MAX_SOURCES` was reached.
+    Skipped(PathBuf),
+}
+
+impl SourceChange {
+    pub fn describe(&self) -> String {
+        match *self {
+            SourceChange::Added(ref path) => format!("Following new file `{}`", path.display()),
+            SourceChange::Removed(ref path) => {
+                format!("Stopped following removed file `{}`", path.display())
+            }
+            SourceChange::Skipped(ref path) => {
+                format!("Not following `{}` - at most {} files are followed",
+                        path.display(),
+                        MAX_SOURCES)
+            }
+        }
+    }
+
+    /// A single message telling about every change, counting them by kind
+    /// when there's more than one.
+    pub fn summarize(changes: &[SourceChange]) -> Option<String> {
+        if changes.len() < 2 {
+            return changes.first().map(SourceChange::describe);
+        }
+
+        let (mut added, mut removed, mut skipped) = (0, 0, 0);
+        for change in changes {
+            match *change {
+                SourceChange::Added(_) => added += 1,
+                SourceChange::Removed(_) => removed += 1,
+                SourceChange::Skipped(_) => skipped += 1,
+            }
+        }
+
+        let files = |count: usize| if count == 1 { "file" } else { "files" };
+        let mut parts = vec![];
+        if added > 0 {
+            parts.push(format!("following {} new {}", added, files(added)));
+        }
+        if removed > 0 {
+            parts.push(format!("stopped following {} removed {}", removed, files(removed)));
+        }
+        if skipped > 0 {
+            parts.push(format!("not following {} more {} - at most {} files are followed",
+                               skipped,
+                               files(skipped),
+                               MAX_SOURCES));
+        }
+
+        let message = parts.join(", ");
+        Some(message[..1].to_uppercase() + &message[1..])
+    }
+}
+
+/// Follows every file matching a glob pattern, picking up files created
+/// later on and dropping the ones that are removed.
+pub struct Discovery {
+    pattern: GlobPattern,
+    tails: BTreeMap<PathBuf, Tail>,
+    skipped: HashSet<PathBuf>,
+    /// Matching, but couldn't be opened. Retried on every scan.
+    failed: HashSet<PathBuf>,
+    /// Files left out by the first scan, told about once watching.
+    unreported: Vec<SourceChange>,
+    errors: Vec<FlowError>,
+    /// Told apart in the lines read, kept when a file is removed and
+    /// followed again.
+    source_ids: BTreeMap<PathBuf, usize>,
+    limit: usize,
+}
+
+impl Discovery {
+    /// Starts following the files matching at startup.
+    pub fn new(pattern: GlobPattern, limit: usize) -> Discovery {
+        let mut discovery = Discovery {
+            pattern: pattern,
+            tails: BTreeMap::new(),
+            skipped: HashSet::new(),
+            failed: HashSet::new(),
+            unreported: vec![],
+            errors: vec![],
+            source_ids: BTreeMap::new(),
+            limit: limit,
+        };
+        // The files followed are shown from the start, unlike the ones left out
+        discovery.unreported = discovery.refresh()
+            .into_iter()
+            .filter(|change| match *change {
+                SourceChange::Skipped(_) => true,
+                _ => false,
+            })
+            .collect();
+
+        discovery
+    }
+
+    /// Files left out by the first scan, as they weren't returned by it.
+    pub fn take_unreported(&mut self) -> Vec<SourceChange> {
+        self.unreported.drain(..).collect()
+    }
+
+    pub fn paths(&self) -> Vec<&PathBuf> {
+        self.tails.keys().collect()
+    }
+
+    /// File name of every source id given so far, including removed files.
+    pub fn source_names(&self) -> Vec<(usize, String)> {
+        self.source_ids
+            .iter()
+            .map(|(path, id)| {
+                let name = path.file_name().map_or_else(|| path.display().to_string(),
+                                                        |value| value.to_string_lossy().into_owned());
+                (*id, name)
+            })
+            .collect()
+    }
+
+    /// Rescans the directory, returning what changed since the last scan.
+    pub fn refresh(&mut self) -> Vec<SourceChange> {
+        let found = self.pattern.scan();
+        let mut changes = vec![];
+
+        let removed = self.tails
+            .keys()
+            .filter(|path| !found.contains(path))
+            .cloned()
+            .collect::<Vec<_>>();
+        for path in removed {
+            self.tails.remove(&path);
+            debug_log!("discovery", "removed path={:?} source={}", path, self.source_ids[&path]);
+            changes.push(SourceChange::Removed(path));
+        }
+        self.skipped.retain(|path| found.contains(path));
+        self.failed.retain(|path| found.contains(path));
+
+        for path in found {
+            if self.tails.contains_key(&path) {
+                continue;
+            }
+
+            // Skipped files are picked up once there's room, but only
+            // reported the first time
+            if self.tails.len() >= self.limit {
+                if self.skipped.insert(path.clone()) {
+                    changes.push(SourceChange::Skipped(path));
+                }
+                continue;
+            }
+
+            match Tail::open(&path.to_string_lossy()) {
+                Ok(tail) => {
+                    self.skipped.remove(&path);
+                    self.failed.remove(&path);
+                    let next_id = self.source_ids.len() + 1;
+                    self.source_ids.entry(path.clone()).or_insert(next_id);
+                    self.tails.insert(path.clone(), tail);
+                    debug_log!("discovery",
+                               "added path={:?} source={}",
+                               path,
+                               self.source_ids[&path]);
+                    changes.push(SourceChange::Added(path));
+                }
+                Err(error) => {
+                    if self.failed.insert(path) {
+                        self.errors.push(error);
+                    }
+                }
+            }
+        }
+
+        changes
+    }
+
+    /// The last lines of every followed file, one file after the other.
+    pub fn read_lines(&mut self, lines: usize) -> Vec<Arc<str>> {
+        without_sources(self.read_sourced_lines(lines))
+    }
+
+    /// Lines appended to any of the files since they were last read.
+    pub fn read_to_end(&mut self) -> Vec<Arc<str>> {
+        without_sources(self.read_sourced_to_end())
+    }
+
+    /// Same as `read_lines`, along with the id of the file of each line.
+    pub fn read_sourced_lines(&mut self, lines: usize) -> Vec<(usize, Arc<str>)> {
+        self.read_each(|tail| tail.read_lines(lines))
+    }
+
+    /// Same as `read_to_end`, along with the id of the file of each line.
+    pub fn read_sourced_to_end(&mut self) -> Vec<(usize, Arc<str>)> {
+        self.read_each(Tail::read_to_end)
+    }
+
+    /// Line each file is in the middle of writing, as `Tail::partial` tells.
+    pub fn partials(&mut self) -> Vec<(usize, String)> {
+        let source_ids = &self.source_ids;
+
+        self.tails
+            .iter_mut()
+            .filter_map(|(path, tail)| tail.partial().map(|line| (source_ids[path], line)))
+            .collect()
+    }
+
+    fn read_each<F>(&mut self, mut read: F) -> Vec<(usize, Arc<str>)>
+        where F: FnMut(&mut Tail) -> Vec<Arc<str>>
+    {
+        let source_ids = &self.source_ids;
+
+        self.tails
+            .iter_mut()
+            .flat_map(|(path, tail)| {
+                let id = source_ids[path];
+                read(tail).into_iter().map(move |line| (id, line))
+            })
+            .collect()
+    }
+
+    /// Files that couldn't be opened or read since last asked, each
+    /// reported once until it recovers.
+    pub fn take_errors(&mut self) -> Vec<FlowError> {
+        let mut errors = self.errors.drain(..).collect::<Vec<_>>();
+        errors.extend(self.tails.values_mut().filter_map(|tail| tail.take_error()));
+
+        errors
+    }
+
+    /// State of every file matching so far, and of the directory while no
+    /// file is followed.
+    pub fn health(&self) -> Vec<HealthChange> {
+        let directory = self.pattern.directory.to_string_lossy();
+        let mut states = vec![if self.tails.is_empty() {
+            HealthChange::new(&directory,
+                              Health::Waiting,
+                              format!("Waiting for matching files in `{}`", directory))
+        } else {
+            HealthChange::new(&directory,
+                              Health::Active,
+                              format!("Following matching files in `{}`", directory))
+        }];
+
+        states.extend(self.tails.values().map(Tail::health));
+        for path in &self.failed {
+            let source = path.to_string_lossy();
+            states.push(HealthChange::new(&source,
+                                          Health::Errored,
+                                          format!("`{}` couldn't be opened, retrying", source)));
+        }
+        for path in self.source_ids.keys() {
+            if !self.tails.contains_key(path) && !self.failed.contains(path) {
+                let source = path.to_string_lossy();
+                states.push(HealthChange::new(&source,
+                                              Health::Ended,
+                                              format!("`{}` was removed", source)));
+            }
+        }
+
+        states
+    }
+
+    pub fn watch<F, N>(&mut self, callback: F, notify: N)
+        where F: Fn(Vec<(usize, Arc<str>)>, Vec<(usize, String)>),
+              N: Fn(Vec<SourceChange>, Vec<(usize, String)>)
+    {
+        let mut scanned_at = Instant::now();
+        let mut health = HealthBoard::default();
+
+        let unreported = self.take_unreported();
+        if !unreported.is_empty() {
+            notify(unreported, self.source_names());
+        }
+
+        while running!() {
+            let lines = self.read_sourced_to_end();
+            callback(lines, self.partials());
+
+            if scanned_at.elapsed() >= Duration::from_millis(SCAN_INTERVAL) {
+                scanned_at = Instant::now();
+
+                let changes = self.refresh();
+                if !changes.is_empty() {
+                    // Lines of new files are read from the start, once their
+                    // names are known
+                    let lines = self.read_sourced_to_end();
+                    notify(changes, self.source_names());
+                    callback(lines, self.partials());
+                }
+            }
+
+            for error in self.take_errors() {
+                error::report(error);
+            }
+            runner::publish_health(health.update(self.health()));
+
+            sleep(Duration::from_millis(50));
+        }
+    }
+}
+
+fn without_sources(lines: Vec<(usize, Arc<str>)>) -> Vec<Arc<str>> {
+    lines.into_iter().map(|(_, line)| line).collect()
+}
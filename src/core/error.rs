@@ -0,0 +1,150 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use time;
+
+use core::runner::WAKEUP;
+
+/// Most recent errors kept around for review.
+pub static ERROR_LOG_CAPACITY: usize = 50;
+
+lazy_static! {
+    static ref PENDING: Mutex<Vec<FlowError>> = Mutex::new(vec![]);
+}
+
+/// Failures worth telling about, as opposed to quitting over.
+#[derive(Clone, PartialEq, Debug)]
+pub enum FlowError {
+    NotFound(PathBuf),
+    PermissionDenied(PathBuf),
+    Io(PathBuf, String),
+    InvalidRegex(String, String),
+    Config(String),
+    /// A quick action's command couldn't be run.
+    Command(String, String),
+    ReaderFailed(String),
+    /// A source changed state, logged along with the errors.
+    SourceState(String),
+    /// The control socket couldn't be listened on.
+    ControlSocket(PathBuf, String),
+}
+
+impl FlowError {
+    pub fn from_io(path: &Path, error: &io::Error) -> FlowError {
+        match error.kind() {
+            io::ErrorKind::NotFound => FlowError::NotFound(path.to_path_buf()),
+            io::ErrorKind::PermissionDenied => FlowError::PermissionDenied(path.to_path_buf()),
+            _ => FlowError::Io(path.to_path_buf(), error.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for FlowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FlowError::NotFound(ref path) => write!(f, "`{}` doesn't exist", path.display()),
+            FlowError::PermissionDenied(ref path) => {
+                write!(f, "`{}` couldn't be read - permission denied", path.display())
+            }
+            FlowError::Io(ref path, ref message) => {
+                write!(f, "`{}` couldn't be read - {}", path.display(), message)
+            }
+            FlowError::InvalidRegex(ref pattern, ref message) => {
+                write!(f, "Invalid pattern `{}` - {}", pattern, message)
+            }
+            FlowError::Config(ref message) => write!(f, "{}", message),
+            FlowError::Command(ref name, ref message) => {
+                write!(f, "`{}` couldn't be started - {}", name, message)
+            }
+            FlowError::ReaderFailed(ref message) => write!(f, "Reading stopped - {}", message),
+            FlowError::SourceState(ref message) => write!(f, "{}", message),
+            FlowError::ControlSocket(ref path, ref message) => {
+                write!(f, "Control socket `{}` couldn't be opened - {}", path.display(), message)
+            }
+        }
+    }
+}
+
+/// Queues an error for the ui thread, which shows it once it wakes up.
+pub fn report(error: FlowError) {
+    PENDING.lock().unwrap().push(error);
+    WAKEUP.notify();
+}
+
+/// Errors reported since last asked, oldest first.
+pub fn take_reported() -> Vec<FlowError> {
+    PENDING.lock().unwrap().drain(..).collect()
+}
+
+pub struct LoggedError {
+    pub timestamp: String,
+    pub error: FlowError,
+}
+
+/// Errors shown so far, kept for reviewing the ones that flashed by.
+pub struct ErrorLog {
+    entries: VecDeque<LoggedError>,
+    capacity: usize,
+}
+
+impl ErrorLog {
+    pub fn new(capacity: usize) -> ErrorLog {
+        ErrorLog {
+            entries: VecDeque::new(),
+            capacity: capacity,
+        }
+    }
+
+    pub fn push(&mut self, error: FlowError) {
+        let timestamp = time::strftime("%H:%M:%S", &time::now()).unwrap();
+        self.push_at(timestamp, error);
+    }
+
+    pub fn push_at(&mut self, timestamp: String, error: FlowError) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LoggedError {
+            timestamp: timestamp,
+            error: error,
+        });
+    }
+
+    pub fn last(&self) -> Option<&LoggedError> {
+        self.entries.back()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// One row per error, newest first.
+    pub fn rows(&self) -> Vec<String> {
+        self.entries.iter().rev().map(|entry| format!("{} {}", entry.timestamp, entry.error)).collect()
+    }
+}
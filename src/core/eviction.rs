@@ -0,0 +1,230 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::{HashMap, VecDeque};
+
+use core::line::Line;
+use core::range::LineRange;
+
+/// Marked lines are warned about once this many more lines would drop them,
+/// as a share of the lines stored.
+const WARNING_SHARE: usize = 10;
+/// Lines set aside at most, the oldest ones going first past this many.
+pub const MAX_PRESERVED: usize = 1000;
+
+/// Picks the stored lines to drop once there are more than are kept.
+pub trait EvictionPolicy {
+    /// Removes `excess` lines, returning them. Lines are ordered by id and
+    /// must stay that way.
+    fn evict(&self, entries: &mut VecDeque<Line>, excess: usize) -> Vec<Line>;
+
+    /// Ids of the lines `evict` would remove, leaving them stored.
+    fn would_evict(&self, entries: &VecDeque<Line>, excess: usize) -> Vec<usize>;
+}
+
+/// Drops the oldest lines, whichever source they came from.
+pub struct Fifo;
+
+impl EvictionPolicy for Fifo {
+    fn evict(&self, entries: &mut VecDeque<Line>, excess: usize) -> Vec<Line> {
+        let excess = excess.min(entries.len());
+
+        entries.drain(..excess).collect()
+    }
+
+    fn would_evict(&self, entries: &VecDeque<Line>, excess: usize) -> Vec<usize> {
+        entries.iter().take(excess).map(|line| line.id).collect()
+    }
+}
+
+/// Keeps at least `reserved` lines of every source, so that a busy one
+/// doesn't push out the history of a quiet one. The oldest lines of sources
+/// over their share go first, and only once none are left does eviction fall
+/// back to the oldest lines overall.
+pub struct SourceFairness {
+    pub reserved: usize,
+}
+
+impl SourceFairness {
+    pub fn new(reserved: usize) -> SourceFairness {
+        SourceFairness { reserved: reserved }
+    }
+
+    /// Whether each of the entries is among the `excess` ones to drop.
+    fn dropped(&self, entries: &VecDeque<Line>, excess: usize) -> Vec<bool> {
+        let mut counts = HashMap::new();
+        for line in entries.iter() {
+            *counts.entry(line.source).or_insert(0) += 1;
+        }
+
+        let mut dropped = vec![false; entries.len()];
+        let mut remaining = excess;
+
+        for (index, line) in entries.iter().enumerate() {
+            if remaining == 0 {
+                break;
+            }
+
+            let count = counts.get_mut(&line.source).unwrap();
+            if *count > self.reserved {
+                *count -= 1;
+                dropped[index] = true;
+                remaining -= 1;
+            }
+        }
+
+        for value in &mut dropped {
+            if remaining == 0 {
+                break;
+            }
+            if !*value {
+                *value = true;
+                remaining -= 1;
+            }
+        }
+
+        dropped
+    }
+}
+
+impl EvictionPolicy for SourceFairness {
+    fn evict(&self, entries: &mut VecDeque<Line>, excess: usize) -> Vec<Line> {
+        let dropped = self.dropped(entries, excess);
+        let mut evicted = vec![];
+        let mut kept = VecDeque::with_capacity(entries.len());
+        for (line, is_dropped) in entries.drain(..).zip(dropped) {
+            if is_dropped {
+                evicted.push(line);
+            } else {
+                kept.push_back(line);
+            }
+        }
+        *entries = kept;
+
+        evicted
+    }
+
+    fn would_evict(&self, entries: &VecDeque<Line>, excess: usize) -> Vec<usize> {
+        entries.iter()
+            .zip(self.dropped(entries, excess))
+            .filter(|&(_, is_dropped)| is_dropped)
+            .map(|(line, _)| line.id)
+            .collect()
+    }
+}
+
+/// What happens to marked lines about to be evicted.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Protection {
+    /// Warns once they're among the next lines to go.
+    Warn,
+    /// Warns as well, and sets them aside once they're gone.
+    Preserve,
+    Ignore,
+}
+
+impl Protection {
+    pub fn parse(text: &str) -> Option<Protection> {
+        match text {
+            "warn" => Some(Protection::Warn),
+            "preserve" => Some(Protection::Preserve),
+            "ignore" => Some(Protection::Ignore),
+            _ => None,
+        }
+    }
+}
+
+/// Lines the user marked or pinned, which eviction doesn't drop unnoticed.
+pub struct ProtectedLines {
+    pub policy: Protection,
+    ranges: Vec<LineRange>,
+    /// Ids of the lines already warned about.
+    warned: Vec<usize>,
+    /// Marked lines kept after being evicted, oldest first.
+    pub preserved: Vec<Line>,
+}
+
+impl ProtectedLines {
+    pub fn new(policy: Protection) -> ProtectedLines {
+        ProtectedLines {
+            policy: policy,
+            ranges: vec![],
+            warned: vec![],
+            preserved: vec![],
+        }
+    }
+
+    /// Replaces the lines protected, a single line being a range of its own.
+    pub fn set(&mut self, ranges: Vec<LineRange>) {
+        self.ranges = ranges;
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        self.ranges.iter().any(|range| range.contains(id))
+    }
+
+    /// Number of protected lines that the lines incoming, and a few more,
+    /// would have the eviction policy drop, and that weren't warned about yet.
+    pub fn endangered(&mut self,
+                      entries: &VecDeque<Line>,
+                      capacity: usize,
+                      incoming: usize,
+                      eviction: &dyn EvictionPolicy)
+                      -> usize {
+        if self.policy == Protection::Ignore || self.ranges.is_empty() {
+            return 0;
+        }
+
+        // Lines aren't necessarily evicted oldest first, but stay ordered by id
+        let (front, back) = entries.as_slices();
+        let stored = |slice: &[Line], id: usize| {
+            slice.binary_search_by(|line| line.id.cmp(&id)).is_ok()
+        };
+        self.warned.retain(|&id| stored(front, id) || stored(back, id));
+
+        let margin = (capacity / WARNING_SHARE).max(1);
+        let room = capacity.saturating_sub(entries.len());
+        let mut count = 0;
+        for id in eviction.would_evict(entries, (margin + incoming).saturating_sub(room)) {
+            if self.contains(id) && !self.warned.contains(&id) {
+                self.warned.push(id);
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Sets aside the protected lines among the ones evicted, returning how
+    /// many were.
+    pub fn evicted(&mut self, lines: &[Line]) -> usize {
+        if self.policy != Protection::Preserve {
+            return 0;
+        }
+
+        let kept = lines.iter()
+            .filter(|line| self.contains(line.id))
+            .cloned()
+            .collect::<Vec<_>>();
+        let count = kept.len();
+        self.preserved.extend(kept);
+
+        let excess = self.preserved.len().saturating_sub(MAX_PRESERVED);
+        self.preserved.drain(..excess);
+        count
+    }
+}
@@ -0,0 +1,137 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use regex::{self, Regex};
+
+use core::line::SearchPattern;
+use core::query::Syntax;
+
+// Words kept by the candidate matching the start of lines
+static LEADING_WORDS: usize = 3;
+
+lazy_static! {
+    /// Parts of a line likely to change between lines of the same kind.
+    static ref VARIABLE: Regex = Regex::new(concat!(
+        r"[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}|",
+        r"0x[0-9a-fA-F]+|",
+        r"\b[0-9a-fA-F]{8,}\b|",
+        r"\d+"
+    )).unwrap();
+}
+
+/// Search that could hide lines like a given one, from the most precise.
+#[derive(PartialEq, Debug)]
+pub struct Candidate {
+    pub label: &'static str,
+    /// Search text, a regex between slashes.
+    pub text: String,
+}
+
+impl Candidate {
+    /// The search, reading the text as the regex it is.
+    pub fn pattern(&self) -> SearchPattern {
+        let syntax = Syntax { regex: true, ..Syntax::default() };
+        SearchPattern::new(&self.text, false).with_syntax(syntax)
+    }
+}
+
+/// Searches hiding the line: its exact text, its text with numbers, hex
+/// values and UUIDs matching any value, and its first few words likewise.
+/// Those that would match the same as a more precise one are left out.
+pub fn candidates(line: &str) -> Vec<Candidate> {
+    if line.trim().is_empty() {
+        return vec![];
+    }
+
+    let mut candidates = vec![Candidate {
+                                  label: "exact",
+                                  text: format!("/^{}$/", regex::escape(line)),
+                              }];
+
+    let wildcarded = format!("/^{}$/", wildcard(line));
+    if wildcarded != candidates[0].text {
+        candidates.push(Candidate {
+            label: "any numbers",
+            text: wildcarded,
+        });
+    }
+
+    // The anchored candidates keep trailing whitespace, which must match too
+    let trimmed = line.trim_end();
+    if let Some(end) = leading_words_end(trimmed) {
+        candidates.push(Candidate {
+            label: "first words",
+            text: format!("/^{}/", wildcard(&trimmed[..end])),
+        });
+    }
+
+    candidates
+}
+
+/// The text as a regex, with its variable parts matching any value of
+/// their kind.
+pub fn wildcard(text: &str) -> String {
+    let mut result = String::new();
+    let mut last = 0;
+
+    for found in VARIABLE.find_iter(text) {
+        let value = found.as_str();
+        let replacement = if value.len() == 36 && value.contains('-') {
+            "[0-9a-fA-F-]{36}"
+        } else if value.starts_with("0x") {
+            "0x[0-9a-fA-F]+"
+        } else if value.chars().all(|c| c.is_digit(10)) {
+            r"\d+"
+        } else if value.chars().any(|c| c.is_digit(10)) {
+            "[0-9a-fA-F]+"
+        } else {
+            // Hex looking words without digits are likely words
+            continue;
+        };
+
+        result.push_str(&regex::escape(&text[last..found.start()]));
+        result.push_str(replacement);
+        last = found.end();
+    }
+
+    result.push_str(&regex::escape(&text[last..]));
+    result
+}
+
+// End of the leading words, when the line has more than those. Trailing
+// whitespace was trimmed, so whitespace after a word means more follow.
+fn leading_words_end(line: &str) -> Option<usize> {
+    let mut words = 0;
+    let mut in_word = false;
+
+    for (offset, value) in line.char_indices() {
+        if value.is_whitespace() {
+            if in_word {
+                words += 1;
+                if words == LEADING_WORDS {
+                    return Some(offset);
+                }
+            }
+            in_word = false;
+        } else {
+            in_word = true;
+        }
+    }
+
+    None
+}
@@ -0,0 +1,316 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use time;
+
+use core::highlights::Highlights;
+use core::line::{Line, SearchPattern};
+use ui::color;
+use ui::styled::{self, TextStyle};
+
+static GROUP_SEPARATOR: &'static str = "--";
+
+// Colors standing for the terminal's own in HTML exports
+static HTML_FOREGROUND: &'static str = "#e5e5e5";
+static HTML_BACKGROUND: &'static str = "#000000";
+
+/// Values accepted by `--export-format`.
+pub static EXPORT_FORMATS: &'static [&'static str] = &["plain", "ansi", "html"];
+
+/// How exported and saved lines are written, as passed to `--export-format`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ExportFormat {
+    Plain,
+    /// Colors and highlights as escape codes, reproduced by `cat`.
+    Ansi,
+    /// Colors and highlights as styled spans, for sharing.
+    Html,
+}
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Option<ExportFormat> {
+        match value {
+            "plain" => Some(ExportFormat::Plain),
+            "ansi" => Some(ExportFormat::Ansi),
+            "html" => Some(ExportFormat::Html),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            ExportFormat::Html => "html",
+            _ => "txt",
+        }
+    }
+
+    /// Content of the line, styled as on screen unless plain.
+    pub fn render(&self, line: &Line, highlights: &Highlights) -> String {
+        let runs = || {
+            let spans = highlights.spans(&line.content_without_ansi);
+            styled::style_runs(line, &spans)
+        };
+
+        match *self {
+            ExportFormat::Plain => line.content_without_ansi.to_string(),
+            ExportFormat::Ansi => render_ansi(&runs()),
+            ExportFormat::Html => render_html(&runs()),
+        }
+    }
+
+    /// Text written around the lines, escaped for HTML.
+    pub fn escape<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match *self {
+            ExportFormat::Html => Cow::Owned(escape_html(text)),
+            _ => Cow::Borrowed(text),
+        }
+    }
+
+    pub fn write_header<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        match *self {
+            ExportFormat::Html => {
+                writeln!(out,
+                         "<!DOCTYPE html>\n<meta charset=\"utf-8\">\n<pre style=\"color:{};\
+                          background-color:{}\">",
+                         HTML_FOREGROUND,
+                         HTML_BACKGROUND)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn write_footer<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        match *self {
+            ExportFormat::Html => writeln!(out, "</pre>"),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn render_ansi(runs: &[(TextStyle, String)]) -> String {
+    let mut result = String::new();
+    let mut current = TextStyle::default();
+
+    for &(style, ref text) in runs {
+        if style != current {
+            result.push_str(&format!("\x1b[{}m", sgr_codes(&style).join(";")));
+            current = style;
+        }
+        result.push_str(text);
+    }
+
+    if current != TextStyle::default() {
+        result.push_str("\x1b[0m");
+    }
+
+    result
+}
+
+/// Codes setting the style from scratch, starting with a reset.
+fn sgr_codes(style: &TextStyle) -> Vec<String> {
+    let mut codes = vec!["0".to_string()];
+    let attributes = [(style.bold, "1"),
+                      (style.italic, "3"),
+                      (style.underline, "4"),
+                      (style.reverse, "7"),
+                      (style.strikethrough, "9")];
+    codes.extend(attributes.iter().filter(|&&(on, _)| on).map(|&(_, code)| code.to_string()));
+
+    if let Some(value) = style.foreground {
+        codes.push(color_code(value, 30));
+    }
+    if let Some(value) = style.background {
+        codes.push(color_code(value, 40));
+    }
+
+    codes
+}
+
+fn color_code(color: i16, base: i16) -> String {
+    match color {
+        0..=7 => (base + color).to_string(),
+        8..=15 => (base + 60 + color - 8).to_string(),
+        _ => format!("{};5;{}", base + 8, color),
+    }
+}
+
+fn render_html(runs: &[(TextStyle, String)]) -> String {
+    let mut result = String::new();
+
+    for &(style, ref text) in runs {
+        if style == TextStyle::default() {
+            result.push_str(&escape_html(text));
+        } else {
+            let span = format!("<span style=\"{}\">{}</span>", css(&style), escape_html(text));
+            result.push_str(&span);
+        }
+    }
+
+    result
+}
+
+fn css(style: &TextStyle) -> String {
+    let hex = |value: Option<i16>, fallback: &str| {
+        value.and_then(color::hex).unwrap_or_else(|| fallback.to_string())
+    };
+    let mut foreground = hex(style.foreground, HTML_FOREGROUND);
+    let mut background = hex(style.background, HTML_BACKGROUND);
+    if style.reverse {
+        ::std::mem::swap(&mut foreground, &mut background);
+    }
+
+    let mut properties = vec![];
+    if style.foreground.is_some() || style.reverse {
+        properties.push(format!("color:{}", foreground));
+    }
+    if style.background.is_some() || style.reverse {
+        properties.push(format!("background-color:{}", background));
+    }
+    if style.bold {
+        properties.push("font-weight:bold".to_string());
+    }
+    if style.italic {
+        properties.push("font-style:italic".to_string());
+    }
+
+    let decorations = [(style.underline, "underline"), (style.strikethrough, "line-through")];
+    let decoration = decorations.iter()
+        .filter(|&&(on, _)| on)
+        .map(|&(_, name)| name)
+        .collect::<Vec<_>>();
+    if !decoration.is_empty() {
+        properties.push(format!("text-decoration:{}", decoration.join(" ")));
+    }
+
+    properties.join(";")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Writes the lines containing the pattern in the format of `grep -n`, as
+/// `source:number:content`. Each line is named after the file it came from,
+/// as `source_name` tells for its source id, and numbered among the lines
+/// received from that file. With context, up to that many lines around each
+/// match are written as `source-number-content` and separate groups are
+/// divided by `--`. Lines are painted with the highlights, unless plain.
+/// Returns the number of matching lines.
+pub fn write_matches<W, I, N>(out: &mut W,
+                              source_name: N,
+                              lines: I,
+                              pattern: &SearchPattern,
+                              context: usize,
+                              format: ExportFormat,
+                              highlights: &Highlights)
+                              -> io::Result<usize>
+    where W: Write,
+          I: IntoIterator<Item = Line>,
+          N: Fn(usize) -> String
+{
+    let source = |line: &Line| source_name(line.source);
+    let mut before: VecDeque<(usize, Line)> = VecDeque::with_capacity(context);
+    let mut after_remaining = 0;
+    let mut last_written: Option<usize> = None;
+    let mut count = 0;
+
+    for (position, line) in lines.into_iter().enumerate() {
+        if pattern.is_match(&line.content_without_ansi) {
+            count += 1;
+
+            for (previous_position, previous) in before.drain(..) {
+                write_separator(out, last_written, previous_position, context)?;
+                write_line(out, &source(&previous), &previous, '-', format, highlights)?;
+                last_written = Some(previous_position);
+            }
+
+            write_separator(out, last_written, position, context)?;
+            write_line(out, &source(&line), &line, ':', format, highlights)?;
+            last_written = Some(position);
+            after_remaining = context;
+        } else if after_remaining > 0 {
+            after_remaining -= 1;
+            write_line(out, &source(&line), &line, '-', format, highlights)?;
+            last_written = Some(position);
+        } else if context > 0 {
+            if before.len() == context {
+                before.pop_front();
+            }
+            before.push_back((position, line));
+        }
+    }
+
+    Ok(count)
+}
+
+fn write_separator<W: Write>(out: &mut W,
+                             last_written: Option<usize>,
+                             position: usize,
+                             context: usize)
+                             -> io::Result<()> {
+    match last_written {
+        Some(last) if context > 0 && position > last + 1 => writeln!(out, "{}", GROUP_SEPARATOR),
+        _ => Ok(()),
+    }
+}
+
+fn write_line<W: Write>(out: &mut W,
+                        source: &str,
+                        line: &Line,
+                        separator: char,
+                        format: ExportFormat,
+                        highlights: &Highlights)
+                        -> io::Result<()> {
+    writeln!(out,
+             "{}{}{}{}{}",
+             format.escape(source),
+             separator,
+             line.number,
+             separator,
+             format.render(line, highlights))
+}
+
+/// Exports the matches to a timestamped file within the current directory,
+/// returning its path along with the number of matching lines.
+pub fn save<I, N>(source_name: N,
+                  lines: I,
+                  pattern: &SearchPattern,
+                  context: usize,
+                  format: ExportFormat,
+                  highlights: &Highlights)
+                  -> io::Result<(String, usize)>
+    where I: IntoIterator<Item = Line>,
+          N: Fn(usize) -> String
+{
+    let timestamp = time::strftime("%Y%m%d-%H%M%S", &time::now()).unwrap();
+    let path = format!("flow-matches-{}.{}", timestamp, format.extension());
+
+    let mut file = BufWriter::new(File::create(&path)?);
+    format.write_header(&mut file)?;
+    let count =
+        write_matches(&mut file, source_name, lines, pattern, context, format, highlights)?;
+    format.write_footer(&mut file)?;
+    file.flush()?;
+
+    Ok((path, count))
+}
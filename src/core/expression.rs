@@ -0,0 +1,356 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cmp::Reverse;
+use std::fmt;
+
+use core::query::{CompiledQuery, Syntax};
+
+/// Where and why a search expression couldn't be parsed.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ParseError {
+    /// Offset within the expression, in characters.
+    pub position: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.position + 1)
+    }
+}
+
+/// Terms combined with `AND`, `OR` and `NOT`, as in
+/// `error AND (payment OR checkout) NOT healthcheck`.
+///
+/// A term is the text between operators and parentheses, spaces included, and
+/// is looked for like any search, so `/\d+ms/` is a regex. Quoting it, as in
+/// `"a (b)"`, keeps operators and parentheses within it.
+#[derive(Debug)]
+pub enum Expression {
+    Term(CompiledQuery),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+}
+
+impl Expression {
+    pub fn is_match(&self, haystack: &str) -> bool {
+        match *self {
+            Expression::Term(ref query) => query.is_found(haystack),
+            Expression::And(ref left, ref right) => {
+                left.is_match(haystack) && right.is_match(haystack)
+            }
+            Expression::Or(ref left, ref right) => {
+                left.is_match(haystack) || right.is_match(haystack)
+            }
+            Expression::Not(ref expression) => !expression.is_match(haystack),
+        }
+    }
+
+    /// Why the first of its terms that isn't searched as typed is looked for
+    /// literally.
+    pub fn invalid(&self) -> Option<String> {
+        match *self {
+            Expression::Term(ref query) => query.invalid(),
+            Expression::And(ref left, ref right) |
+            Expression::Or(ref left, ref right) => left.invalid().or_else(|| right.invalid()),
+            Expression::Not(ref expression) => expression.invalid(),
+        }
+    }
+
+    /// Occurrences of the terms that aren't negated, in order and without
+    /// overlaps. A match without any spans the whole line, as with `NOT x`.
+    pub fn occurrences<'a>(&self, haystack: &'a str) -> Vec<(usize, &'a str)> {
+        if !self.is_match(haystack) {
+            return vec![];
+        }
+
+        let mut found = vec![];
+        self.collect_positive(haystack, false, &mut found);
+        found.sort_by_key(|&(offset, value)| (offset, Reverse(value.len())));
+
+        let mut result: Vec<(usize, &str)> = vec![];
+        for (offset, value) in found {
+            if result.last().map_or(true, |&(start, text)| offset >= start + text.len()) {
+                result.push((offset, value));
+            }
+        }
+
+        if result.is_empty() {
+            result.push((0, haystack));
+        }
+        result
+    }
+
+    fn collect_positive<'a>(&self,
+                            haystack: &'a str,
+                            negated: bool,
+                            result: &mut Vec<(usize, &'a str)>) {
+        match *self {
+            Expression::Term(ref query) => {
+                if !negated {
+                    result.extend(query.occurrences(haystack));
+                }
+            }
+            Expression::And(ref left, ref right) |
+            Expression::Or(ref left, ref right) => {
+                left.collect_positive(haystack, negated, result);
+                right.collect_positive(haystack, negated, result);
+            }
+            Expression::Not(ref expression) => {
+                expression.collect_positive(haystack, !negated, result)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Word,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Lexeme {
+    token: Token,
+    start: usize,
+    end: usize,
+}
+
+/// Parses text using any of the operators as an expression, and returns
+/// `None` for any other text, which is searched for as a single term. Without
+/// expressions on, operators are searched for like other words.
+pub fn parse(text: &str,
+             whole_word: bool,
+             syntax: Syntax)
+             -> Option<Result<Expression, ParseError>> {
+    if !syntax.expressions {
+        return None;
+    }
+
+    let lexemes = lex(text);
+    let is_expression = lexemes.iter().any(|lexeme| match lexeme.token {
+        Token::And | Token::Or | Token::Not => true,
+        _ => false,
+    });
+
+    if !is_expression {
+        return None;
+    }
+
+    let mut parser = Parser {
+        text: text,
+        lexemes: lexemes,
+        index: 0,
+        whole_word: whole_word,
+        syntax: syntax,
+    };
+
+    Some(parser.parse())
+}
+
+fn lex(text: &str) -> Vec<Lexeme> {
+    let mut lexemes = vec![];
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((start, value)) = chars.next() {
+        let token = match value {
+            _ if value.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '"' => {
+                let end = text[start + 1..].find('"').map_or(text.len(), |i| start + i + 2);
+                while chars.peek().map_or(false, |&(offset, _)| offset < end) {
+                    chars.next();
+                }
+                lexemes.push(Lexeme { token: Token::Word, start: start, end: end });
+                continue;
+            }
+            _ => {
+                let end = word_end(text, start);
+                while chars.peek().map_or(false, |&(offset, _)| offset < end) {
+                    chars.next();
+                }
+                let token = match &text[start..end] {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Word,
+                };
+                lexemes.push(Lexeme { token: token, start: start, end: end });
+                continue;
+            }
+        };
+
+        lexemes.push(Lexeme { token: token, start: start, end: start + 1 });
+    }
+
+    lexemes
+}
+
+// A regex runs up to a closing slash followed by a separator, so that it can
+// hold spaces and parentheses, while other words end at either.
+fn word_end(text: &str, start: usize) -> usize {
+    let is_separator = |value: char| value.is_whitespace() || value == '(' || value == ')';
+
+    if text[start..].starts_with('/') {
+        let mut escaped = false;
+        for (offset, value) in text[start..].char_indices().skip(1) {
+            if value == '/' && !escaped {
+                let end = start + offset + 1;
+                let next = text[end..].chars().next();
+                if next.map_or(true, |next| next.is_whitespace() || next == ')') {
+                    return end;
+                }
+            }
+            escaped = value == '\\' && !escaped;
+        }
+    }
+
+    text[start..].find(is_separator).map_or(text.len(), |offset| start + offset)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    lexemes: Vec<Lexeme>,
+    index: usize,
+    whole_word: bool,
+    syntax: Syntax,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(&mut self) -> Result<Expression, ParseError> {
+        let expression = self.parse_or()?;
+
+        match self.peek() {
+            Some(lexeme) if lexeme.token == Token::Close => {
+                Err(self.error(Some(lexeme), "unexpected )"))
+            }
+            Some(lexeme) => Err(self.error(Some(lexeme), "expected AND, OR or NOT")),
+            None => Ok(expression),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expression, ParseError> {
+        let mut expression = self.parse_and()?;
+
+        while self.next_if(Token::Or) {
+            let right = self.parse_and()?;
+            expression = Expression::Or(Box::new(expression), Box::new(right));
+        }
+
+        Ok(expression)
+    }
+
+    // A `NOT` between terms excludes what follows, as in `error NOT healthcheck`.
+    fn parse_and(&mut self) -> Result<Expression, ParseError> {
+        let mut expression = self.parse_unary()?;
+
+        loop {
+            let right = if self.next_if(Token::And) {
+                self.parse_unary()?
+            } else if self.next_if(Token::Not) {
+                Expression::Not(Box::new(self.parse_unary()?))
+            } else {
+                return Ok(expression);
+            };
+
+            expression = Expression::And(Box::new(expression), Box::new(right));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression, ParseError> {
+        let lexeme = match self.peek() {
+            Some(value) => value,
+            None => return Err(self.error(None, "expected a term")),
+        };
+
+        match lexeme.token {
+            Token::Not => {
+                self.index += 1;
+                Ok(Expression::Not(Box::new(self.parse_unary()?)))
+            }
+            Token::Open => {
+                self.index += 1;
+                let expression = self.parse_or()?;
+                if self.next_if(Token::Close) {
+                    Ok(expression)
+                } else {
+                    let next = self.peek();
+                    Err(self.error(next, "expected )"))
+                }
+            }
+            Token::Word => self.parse_term(),
+            _ => Err(self.error(Some(lexeme), "expected a term")),
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<Expression, ParseError> {
+        let first = self.lexemes[self.index];
+        let mut end = first.end;
+
+        self.index += 1;
+        while let Some(lexeme) = self.peek() {
+            if lexeme.token != Token::Word {
+                break;
+            }
+            end = lexeme.end;
+            self.index += 1;
+        }
+
+        let mut text = &self.text[first.start..end];
+        if text.starts_with('"') && end == first.end {
+            if text.len() < 2 || !text.ends_with('"') {
+                return Err(self.error(Some(first), "unclosed quote"));
+            }
+            text = &text[1..text.len() - 1];
+        }
+
+        if text.is_empty() {
+            return Err(self.error(Some(first), "empty term"));
+        }
+
+        Ok(Expression::Term(CompiledQuery::term(text, self.whole_word, self.syntax)))
+    }
+
+    fn peek(&self) -> Option<Lexeme> {
+        self.lexemes.get(self.index).cloned()
+    }
+
+    fn next_if(&mut self, token: Token) -> bool {
+        let found = self.peek().map_or(false, |lexeme| lexeme.token == token);
+        if found {
+            self.index += 1;
+        }
+        found
+    }
+
+    fn error(&self, lexeme: Option<Lexeme>, message: &'static str) -> ParseError {
+        let offset = lexeme.map_or(self.text.len(), |value| value.start);
+
+        ParseError {
+            position: self.text[..offset].chars().count(),
+            message: message,
+        }
+    }
+}
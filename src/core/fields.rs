@@ -0,0 +1,59 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use regex::Regex;
+
+lazy_static! {
+    static ref KEY_VALUE: Regex = Regex::new(r#"([A-Za-z_][\w.-]*)=("[^"]*"|[^\s,;]+)"#).unwrap();
+    static ref JSON_MEMBER: Regex = Regex::new(
+        r#""([^"\\]+)"\s*:\s*("(?:[^"\\]|\\.)*"|[^\s,}\]]+)"#
+    ).unwrap();
+}
+
+/// Value of a field found within a line, as a byte range of the line.
+/// Quotes around the value are left out.
+#[derive(Clone, PartialEq, Debug)]
+pub struct FieldSpan {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Fields of the line: members of a JSON object, or `key=value` pairs.
+pub fn find(text: &str) -> Vec<FieldSpan> {
+    let is_json = text.trim_start().starts_with('{');
+    let matcher: &Regex = if is_json { &JSON_MEMBER } else { &KEY_VALUE };
+
+    matcher.captures_iter(text)
+        .map(|captures| {
+            let value = captures.get(2).unwrap();
+            let quoted = value.as_str().len() > 1 && value.as_str().starts_with('"');
+            let (start, end) = if quoted {
+                (value.start() + 1, value.end() - 1)
+            } else {
+                (value.start(), value.end())
+            };
+
+            FieldSpan {
+                name: captures[1].to_string(),
+                start: start,
+                end: end,
+            }
+        })
+        .collect()
+}
@@ -19,6 +19,10 @@
 use regex::Regex;
 use rustc_serialize::{Decodable, Decoder};
 
+use core::alert::Alert;
+use core::rewrite::RewriteRule;
+use core::line::SearchPattern;
+
 #[derive(Clone, PartialEq, Debug)]
 pub enum Constraint {
     Start,
@@ -56,6 +60,39 @@ pub struct Filter {
     pub content: Option<Regex>,
     pub start: Option<BoundaryFilter>,
     pub end: Option<BoundaryFilter>,
+    pub rewrites: Vec<RewriteRule>,
+    /// Searches kept as rules, each keeping or hiding the lines it matches.
+    pub line_rules: Vec<LineRule>,
+    /// Patterns reported when new lines of the tab match them.
+    pub alerts: Vec<Alert>,
+}
+
+/// A search turned into a rule of the tab.
+#[derive(Clone)]
+pub struct LineRule {
+    pub text: String,
+    pub pattern: SearchPattern,
+    /// Hides the matching lines, instead of showing only them.
+    pub exclude: bool,
+    pub enabled: bool,
+    /// What the rule was added from, as shown in the rules panel.
+    pub origin: &'static str,
+}
+
+impl LineRule {
+    pub fn from_search(text: &str, pattern: SearchPattern, exclude: bool) -> LineRule {
+        LineRule {
+            text: text.to_string(),
+            pattern: pattern,
+            exclude: exclude,
+            enabled: true,
+            origin: "search",
+        }
+    }
+
+    pub fn allows(&self, text: &str) -> bool {
+        !self.enabled || self.pattern.is_match(text) != self.exclude
+    }
 }
 
 pub enum ParserResult {
@@ -291,6 +328,11 @@ impl<'a> ContentEndConstraintsParser<'a> {
 }
 
 impl Filter {
+    /// Whether the line rules let the text through.
+    pub fn allows(&self, text: &str) -> bool {
+        self.line_rules.iter().all(|rule| rule.allows(text))
+    }
+
     fn determine_constraints(&self) -> Vec<Constraint> {
         let mut constraints = vec![];
 
@@ -338,9 +380,18 @@ impl Decodable for Filter {
         decoder.read_struct("Filter", 2, |d| {
             let filter = Filter {
                 name: try!(d.read_struct_field("name", 0, |d| d.read_str())),
-                content: field_to_regex(d, "contains", 1),
-                start: regex_to_boundary(field_to_regex(d, "starts_with", 2)),
-                end: regex_to_boundary(field_to_regex(d, "ends_with", 3)),
+                content: try!(field_to_regex(d, "contains", 1)),
+                start: regex_to_boundary(try!(field_to_regex(d, "starts_with", 2))),
+                end: regex_to_boundary(try!(field_to_regex(d, "ends_with", 3))),
+                rewrites: try!(d.read_struct_field("rewrite", 4, |d| {
+                        <Option<Vec<RewriteRule>>>::decode(d)
+                    }))
+                    .unwrap_or_default(),
+                line_rules: vec![],
+                alerts: try!(d.read_struct_field("alert", 5, |d| {
+                        <Option<Vec<Alert>>>::decode(d)
+                    }))
+                    .unwrap_or_default(),
             };
 
             Ok(filter)
@@ -348,10 +399,19 @@ impl Decodable for Filter {
     }
 }
 
-fn field_to_regex<D: Decoder>(decoder: &mut D, name: &str, idx: usize) -> Option<Regex> {
-    match decoder.read_struct_field(name, idx, |d| d.read_str()) {
-        Ok(val) => Some(Regex::new(&val).unwrap()),
-        Err(_) => None,
+fn field_to_regex<D: Decoder>(decoder: &mut D,
+                              name: &str,
+                              idx: usize)
+                              -> Result<Option<Regex>, D::Error> {
+    let value = try!(decoder.read_struct_field(name, idx, |d| Option::<String>::decode(d)));
+
+    match value {
+        Some(val) => {
+            Regex::new(&val)
+                .map(Some)
+                .map_err(|e| decoder.error(&format!("invalid `{}` regex - {}", name, e)))
+        }
+        None => Ok(None),
     }
 }
 
@@ -17,83 +17,735 @@
  */
 
 use std::sync::{Arc, Mutex};
+use std::sync::mpsc::Receiver;
 use std::sync::atomic::Ordering;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::cmp::max;
+use std::cell::RefCell;
+use std::{io, thread};
 
-use time;
+use time::{self, Duration};
+use ncurses::{A_BOLD, A_REVERSE, A_UNDERLINE, KEY_RESIZE, LINES, COLS};
+use rustc_serialize::json::Json;
 
-use ui::readline;
-use utils::settings::Settings;
-use ui::frame::Frame;
-use ui::event::{Event, QueuedEvent, Direction, SearchAction, Offset};
+use utils::settings::{self, Settings};
+use utils::profile::{Profiles, DEFAULT_PROFILE};
+use utils::ansi_decoder::{self, AnsiStr};
+use utils::wrap;
+use ui::frame::{Frame, NORMAL_HIGHLIGHT_COLOR};
+use ui::input::{Input, Key, read_key, poll_key};
+use ui::event::{Event, QueuedEvent, Direction, SearchAction, RulesAction, PatternsAction,
+                ReplayAction, Offset, LineAction};
 use ui::navigation::State as NavigationState;
-use ui::search::State as QueryState;
+use ui::search::Query;
+use ui::prompt::{PromptKind, PromptAction, PromptEvent};
 use ui::highlighter::Highlight;
+use ui::momentum::{Momentum, KeyRepeat};
+use ui::viewport::Anchor;
+use ui::layout::Widget;
+use ui::rules::RuleRow;
+use ui::patterns::PatternRow;
+use ui::snapshot;
+use ui::color;
+use ui::chords::{Chords, Chord, Placement};
+use ui::dismiss::Layer;
+use ui::quick_find::QUICK_FIND_TIMEOUT;
+use ui::flash::Flash;
+use ui::sparkline;
+use ui::size_probe::{self, SizeProbe};
+use ui::status::Swatch;
+use ui::content::{validate_buffer_rows, DEFAULT_BUFFER_ROWS};
+use core::actions::{self, QuickAction};
+use core::line::{Line, SearchPattern};
+use core::search_history::SearchHistory;
+use core::capture_stats::CaptureStats;
+use core::chunked::{ChunkedPass, DEFAULT_CHUNK_SIZE};
+use core::quit::{QuitGuard, Decision};
+use core::alert::{self, JumpGuard, SNOOZE_MINUTES};
+use core::sampling::{self, Sampler};
+use core::health::{Health, HealthBoard, HealthChange};
+use core::timestamp::{self, format_duration, DEFAULT_GAP_THRESHOLD};
 
-use core::runner::RUNNING;
+use core::discovery::SourceChange;
+use core::runner::{self, RUNNING, WAKEUP};
 use core::line::LineCollection;
-use core::buffer::BufferCollection;
-use ext::signal::{self, SIGQUIT};
+use core::eviction::{Protection, SourceFairness};
+use core::buffer::{Buffer, BufferCollection};
+use core::rewrite::{self, RewriteRule};
+use core::replay::Replay;
+use core::session::{Recorder, Player, Step};
+use core::export::{self, ExportFormat};
+use core::exclusion::{self, Candidate};
+use core::metrics::{METRICS, MetricsSnapshot};
+use core::error::{self, ErrorLog, FlowError, ERROR_LOG_CAPACITY};
+use core::range::{self, LineRange, RangeAction, RangeStats};
+use core::regex_tester::{self, RegexUse, PREVIEW_LINES};
+use core::line_info;
+use core::filter::{Filter, LineRule};
+use core::pattern_stats;
+use core::highlights::HighlightPattern;
+use core::undo::{self, Change, History, Setting, TabRules};
+use core::control::{Command, Request};
 
 const NANOSECONDS_IN_A_MILISECOND: u64 = 1_000_000;
 
+// Once nothing happens for a while, input is waited on for longer periods.
+// Incoming lines and signals interrupt the wait, so only timers are delayed.
+const IDLE_THRESHOLD: u64 = 3_000;
+const IDLE_POLL_TIMEOUT: i32 = 1_000;
+/// Longest wait for the next entry of a session being played back, in milliseconds.
+const PLAYBACK_POLL_INTERVAL: u64 = 100;
+
+// Pauses between replayed lines longer than this are skipped on request.
+const REPLAY_GAP_THRESHOLD: i64 = 10_000;
+
+const METRICS_REFRESH_INTERVAL: u64 = 1_000;
+
+// Block characters the ingestion rates of the last minute are drawn with
+const SPARKLINE_CELLS: usize = 12;
+
+// Milliseconds pressing the keys keeping a search as a rule again takes it back
+const KEEP_UNDO_TIMEOUT: u64 = 3_000;
+
+// Histogram rows and the width of their bars, in the capture stats overlay
+const STATS_BUCKETS: usize = 8;
+const STATS_BAR_WIDTH: usize = 20;
+
+/// Search going through the rendered lines a chunk at a time.
+struct SearchPass {
+    chunks: ChunkedPass,
+    query: Query,
+    started_at: u64,
+    was_truncated: bool,
+    /// Lines received meanwhile are shown once the search is done.
+    lines_arrived: bool,
+}
+
 pub struct Flow {
     frame: Frame,
     lines: LineCollection,
     buffers: BufferCollection,
     queue: HashMap<QueuedEvent, u64>,
+    wheel: Momentum,
+    key_repeat: KeyRepeat,
+    last_activity_at: u64,
+    replay: Option<Arc<Mutex<Replay>>>,
+    /// Commands received on the control socket, answered between events.
+    control: Option<Receiver<Request>>,
+    /// Writes what the event loop receives, for playing it back.
+    recorder: Option<Recorder>,
+    /// Recorded session substituting live input while played back.
+    player: Option<Player>,
+    metrics: (MetricsSnapshot, u64),
+    actions: Vec<QuickAction>,
+    /// Actions offered for the line they were triggered on, while choosing.
+    offered_actions: Vec<(QuickAction, String)>,
+    /// Searches offered for hiding lines like the one they were derived from.
+    offered_exclusions: Vec<Candidate>,
+    /// Request to quit waiting for its confirmation.
+    quit_guard: QuitGuard,
+    /// When fired alerts may switch to their tab.
+    alert_jumps: JumpGuard,
+    size_probe: SizeProbe,
+    sampler: Sampler,
+    /// State of the sources, shown next to every tab.
+    health: HealthBoard,
+    /// Search last kept as a rule, whether it hides its matches and when, in
+    /// milliseconds.
+    kept_search: Option<(String, bool, u64)>,
+    /// Changes of what's shown, for undoing them.
+    history: History,
+    search_pass: Option<SearchPass>,
+    /// A long search was stopped, and isn't done again as lines arrive.
+    search_stopped: bool,
+    errors: ErrorLog,
+    /// Regex accepted in the tester, while choosing what to keep it as.
+    tested_regex: Option<String>,
+    /// First line of a range being marked.
+    range_mark: Option<usize>,
+    /// Second the sparkline was last drawn at.
+    sparkline_second: i64,
+    /// Whether lines with terminal control sequences were reported yet.
+    sanitized_reported: bool,
+    /// Whether lines were left out of the buffer since last reported.
+    left_out_reported: bool,
+    /// Input path, as given, naming the lines in exports.
+    source_name: String,
+    export_format: ExportFormat,
+    profiles: Profiles,
+    profile: String,
+    /// Buffer size given on the command line, used whatever the profile.
+    buffer_lines_arg: Option<usize>,
 }
 
 impl Flow {
     pub fn new(settings: Settings) -> Flow {
+        let mut frame = Frame::new(settings.menu_item_names());
+        color::apply_theme(&settings.theme);
+        color::set_highlight_palette(settings.highlight_colors);
+        frame.content.set_buffer_rows(settings.buffer_lines);
+        frame.content.dim_after = settings.dim_lines_after.map(Duration::seconds);
+        *frame.content.flash.borrow_mut() = Flash::new(settings.flash_new_lines);
+        frame.content.continuation_prefix = settings.continuation_prefix.clone();
+        frame.content.scrolloff = settings.scrolloff;
+        frame.set_blank_folding(settings.fold_blank_lines);
+        if settings.vim_keymap {
+            frame.chords = Some(RefCell::new(Chords::default()));
+        }
+
+        let mut lines = LineCollection::new(settings.max_lines_count);
+        lines.set_unwrap(settings.unwrap);
+        lines.protected.policy = settings.protected_lines;
+        if let Some(value) = settings.min_lines_per_source {
+            lines.set_eviction(Box::new(SourceFairness::new(value)));
+        }
+        for source in &settings.highlights {
+            lines.highlights.toggle(source).unwrap();
+        }
+
         Flow {
-            frame: Frame::new(settings.menu_item_names()),
-            lines: LineCollection::new(settings.max_lines_count),
+            frame: frame,
+            lines: lines,
             buffers: BufferCollection::from_filters(settings.filters),
             queue: HashMap::new(),
+            wheel: Momentum::new(settings.wheel_scroll_lines),
+            key_repeat: KeyRepeat::new(settings.key_repeat_acceleration),
+            last_activity_at: 0,
+            replay: None,
+            control: None,
+            recorder: None,
+            player: None,
+            metrics: (MetricsSnapshot::default(), 0),
+            actions: settings.actions,
+            offered_actions: vec![],
+            offered_exclusions: vec![],
+            quit_guard: QuitGuard::default(),
+            alert_jumps: JumpGuard::default(),
+            sampler: Sampler::new(settings.sample_every, settings.sample_target_rate),
+            health: HealthBoard::default(),
+            size_probe: SizeProbe::default(),
+            kept_search: None,
+            history: History::default(),
+            search_pass: None,
+            search_stopped: false,
+            errors: ErrorLog::new(ERROR_LOG_CAPACITY),
+            tested_regex: None,
+            range_mark: None,
+            sparkline_second: 0,
+            sanitized_reported: false,
+            left_out_reported: false,
+            source_name: settings.path_to_target_file,
+            export_format: settings.export_format,
+            profiles: settings.profiles,
+            profile: settings.profile,
+            buffer_lines_arg: settings.buffer_lines_arg,
+        }
+    }
+
+    pub fn attach_replay(&mut self, replay: Option<Arc<Mutex<Replay>>>) {
+        self.replay = replay;
+        self.update_replay_status();
+    }
+
+    pub fn attach_control(&mut self, requests: Receiver<Request>) {
+        self.control = Some(requests);
+    }
+
+    pub fn attach_session(&mut self, recorder: Option<Recorder>, player: Option<Player>) {
+        self.recorder = recorder;
+        let (width, height) = (self.frame.width, self.frame.height);
+        self.record(|recorder, now| recorder.start(width, height, now));
+
+        if let Some(ref header) = player.as_ref().map(|value| value.header.clone()) {
+            if (header.columns, header.rows) != (width, height) {
+                self.show_message(format!("Recorded in a {}x{} terminal, playing back in {}x{}",
+                                          header.columns,
+                                          header.rows,
+                                          width,
+                                          height));
+            }
         }
+        if self.player.is_none() && player.is_some() {
+            let playing = session_health(Health::Active, "Playing a recorded session back");
+            self.apply_health(vec![playing]);
+        }
+        self.player = player;
     }
 
-    pub fn init(&self) {
-        readline::use_history();
-        readline::read_history();
+    pub fn init(&mut self) {
+        self.frame.navigation.search.history = SearchHistory::load();
+        self.show_profile();
+        self.update_swatches();
         self.frame.render();
     }
 
     pub fn terminate(&self) {
-        readline::write_history();
+        self.frame.navigation.search.history.save();
         self.frame.destroy();
     }
 
-    pub fn process(&mut self, lines: Arc<Mutex<Vec<String>>>) {
+    pub fn process(&mut self, lines: Arc<Mutex<Vec<(usize, Arc<str>)>>>) {
         while running!() {
-            match self.frame.watch() {
+            let event = self.next_event(&lines);
+
+            match event {
+                // Keeps the hits of the last minute current
+                Event::Other if self.frame.navigation.state == NavigationState::Patterns => {
+                    self.refresh_patterns()
+                }
+                Event::Other => {}
+                _ => self.record_activity(),
+            }
+
+            self.expire_quit_request();
+            self.expire_alert_snooze();
+
+            match event {
+                Event::ScrollContents(Offset::Line(_)) | Event::Other => {}
+                _ => self.key_repeat.reset(),
+            }
+
+            match event {
                 Event::SelectMenuItem(direction) => self.select_menu_item(direction),
+                Event::ScrollContents(Offset::Line(direction)) => {
+                    let time = time::precise_time_ns() / NANOSECONDS_IN_A_MILISECOND;
+                    let step = self.key_repeat.step(direction, time) as i32;
+                    self.scroll(Offset::Line(direction * step));
+                }
                 Event::ScrollContents(offset) => self.scroll(offset),
+                Event::Wheel(direction, y, x) => self.wheel(direction, y, x),
                 Event::Navigation(state) => {
                     if self.frame.navigation.change_state(state) {
                         match self.frame.navigation.state {
-                            NavigationState::Search => readline::move_cursor(),
-                            NavigationState::Menu => self.reset_view(),
+                            NavigationState::Search => {
+                                self.frame.navigation.search.active = true;
+                                self.frame.register_dismissal(Layer::SearchHighlights, true);
+                                self.frame.navigation.search.move_cursor();
+                            }
+                            NavigationState::Menu => self.reset_view_or_redo_search(),
+                            NavigationState::Rules => self.refresh_rules(),
+                            NavigationState::Patterns => self.refresh_patterns(),
+                            NavigationState::Prompt => {}
                         }
+
+                        let in_rules = self.frame.navigation.state == NavigationState::Rules;
+                        self.frame.register_dismissal(Layer::Rules, in_rules);
+                        let in_patterns = self.frame.navigation.state == NavigationState::Patterns;
+                        self.frame.register_dismissal(Layer::Patterns, in_patterns);
                     }
                 }
                 Event::Search(action) => self.handle_search(action),
+                Event::Dismiss => self.dismiss(),
+                Event::ClearBuffer => self.clear_buffer(),
+                Event::Snapshot => self.save_snapshot(),
+                Event::FreezeTab => self.freeze_tab(),
+                Event::CloseTab => self.close_tab(),
+                Event::ToggleRaw => {
+                    self.toggle_raw();
+                    let tab = self.buffers.selected();
+                    self.history.record(undo::Step::toggle(Setting::Raw(tab)));
+                }
+                Event::OnLine(action, line_id) => self.act_on_line(action, line_id),
+                Event::ClearHighlights => {
+                    let before = self.lines.highlights.patterns().to_vec();
+                    self.lines.highlights.clear();
+                    self.reset_view_or_redo_search();
+                    if !before.is_empty() {
+                        let change = Change::Highlights(before, vec![]);
+                        self.history.record(undo::Step::new("cleared highlights".to_string(),
+                                                            vec![change]));
+                    }
+                }
+                Event::ToggleHighlight(index) => self.toggle_highlight_at(index),
+                Event::SnoozeAlerts => self.snooze_alerts(),
+                Event::ToggleSampling => self.toggle_sampling(),
+                Event::ShowPreserved => self.show_preserved(),
+                Event::Undo => self.undo(false),
+                Event::Redo => self.undo(true),
+                Event::ToggleAnchor => {
+                    let buffer = self.buffers.selected_item();
+                    buffer.update_state(|state| {
+                        state.anchor = match state.anchor {
+                            Anchor::Bottom => Anchor::Top,
+                            Anchor::Top => Anchor::Bottom,
+                        };
+                    });
+                    self.frame.set_anchor_indicator(buffer.state.get().anchor);
+                }
+                Event::ToggleInvisibles => self.switch(Setting::Invisibles),
+                Event::ToggleBlankFolding => self.switch(Setting::BlankFolding),
+                Event::ToggleRuler => self.switch(Setting::Ruler),
+                Event::ToggleColumnCursor => {
+                    self.toggle_setting(Setting::ColumnCursor);
+                    self.history.record(undo::Step::toggle(Setting::ColumnCursor));
+                }
+                Event::ToggleSidebar => {
+                    self.frame.toggle_sidebar();
+                    self.resize();
+                }
+                Event::ToggleDebugOverlay => {
+                    self.frame.debug_overlay.toggle();
+                    self.frame.register_dismissal(Layer::DebugOverlay, self.frame.debug_overlay.is_visible());
+                    self.refresh_metrics(true);
+                }
+                Event::ToggleCaptureStats => self.toggle_capture_stats(),
+                Event::ReviewErrors => self.review_errors(),
+                Event::NextGap => self.next_gap(),
+                Event::ActOnRange => {
+                    if self.frame.content.line_range.get().is_some() {
+                        self.open_prompt(PromptKind::RangeAction, "");
+                    } else {
+                        self.show_message("Mark the first and last line of a range with m, or \
+                                           pick times with T"
+                            .to_string());
+                    }
+                }
+                Event::Inspect(y, x) => {
+                    if let Some(index) = self.frame.navigation.status.highlight_at(y, x) {
+                        self.toggle_highlight_at(index);
+                    } else {
+                        let reverse_index = self.buffers.selected_item().reverse_index();
+                        if let Some(message) = self.frame.describe_position(reverse_index, y, x) {
+                            self.show_message(message);
+                        }
+                    }
+                }
+                Event::Paste(text) => {
+                    self.edit_search(SearchAction::Edit(PromptAction::Paste(text)))
+                }
+                Event::OpenPrompt(kind) => self.open_prompt(kind, ""),
+                Event::Prompt(action) => self.handle_prompt(action),
+                Event::Rules(action) => self.handle_rules(action),
+                Event::Patterns(action) => self.handle_patterns(action),
+                Event::Replay(action) => self.handle_replay(action),
+                Event::Chord(chord) => {
+                    self.show_pending_keys(None);
+                    self.handle_chord(chord);
+                }
+                Event::PendingChord(keys) => self.show_pending_keys(keys),
                 Event::Resize => self.resize(),
-                Event::Quit => self.quit(),
+                Event::CancelSearch => self.cancel_search(),
+                Event::Quit => self.quit(false),
+                Event::ForceQuit => self.quit(true),
                 _ if !self.queue.is_empty() => self.execute_queue(),
                 _ => {
+                    for error in error::take_reported() {
+                        self.report_error(error);
+                    }
+
+                    if let Some(message) = SourceChange::summarize(&runner::take_source_changes()) {
+                        self.show_message(message);
+                    }
+                    self.apply_health(runner::take_health_changes());
+
                     let mut mutex_guarded_lines = lines.lock().unwrap();
+                    let partial_lines = runner::take_partial_lines();
                     if !mutex_guarded_lines.is_empty() {
-                        let pending_lines = mutex_guarded_lines.drain(..).collect();
+                        let pending_lines = mutex_guarded_lines.drain(..).collect::<Vec<_>>();
+                        self.record(|recorder, now| recorder.record_lines(&pending_lines, now));
                         self.append_incoming_lines(pending_lines);
+                        self.update_replay_status();
+                        self.record_activity();
+                    }
+                    drop(mutex_guarded_lines);
+                    if let Some(partial_lines) = partial_lines {
+                        self.show_partial_lines(partial_lines);
+                    }
+                    self.answer_control_requests();
+
+                    self.refresh_metrics(false);
+                    self.refresh_sparkline();
+                    let now = time::precise_time_ns() / NANOSECONDS_IN_A_MILISECOND;
+                    let reverse_index = self.buffers.selected_item().reverse_index();
+                    self.frame.expire_quick_find(reverse_index, now);
+                    if self.frame.content.flash.borrow_mut().expire(now) {
+                        self.reset_view_or_redo_search();
                     }
+                    self.probe_size(now);
                 }
             };
+
+            self.refresh_sidebar();
+            self.refresh_source_indicator();
+        }
+    }
+
+    fn next_event(&mut self, lines: &Arc<Mutex<Vec<(usize, Arc<str>)>>>) -> Event {
+        if runner::take_interrupt() {
+            return self.interrupt();
+        }
+        if let Some(event) = self.play_next(lines) {
+            return event;
+        }
+
+        if self.is_idle() && !WAKEUP.wait(IDLE_POLL_TIMEOUT) {
+            return Event::Other;
+        }
+
+        let (input, key) = if self.search_pass.is_some() { poll_key() } else { read_key() };
+        self.record(|recorder, now| recorder.record_input(&input, key, now));
+        self.interpret(input)
+    }
+
+    fn interpret(&self, input: Input) -> Event {
+        if self.search_pass.is_some() && input == Input::Kb(Key::Escape, None) {
+            return Event::CancelSearch;
+        }
+
+        match self.frame.interpret(input) {
+            Event::OnLine(action, None) => {
+                Event::OnLine(action, self.target_line().map(|line| line.id))
+            }
+            event => event,
+        }
+    }
+
+    // Recorded lines are handed over as the reader would, and ingested before
+    // any input recorded after them is played.
+    fn play_next(&mut self, lines: &Arc<Mutex<Vec<(usize, Arc<str>)>>>) -> Option<Event> {
+        let now = time::precise_time_ns() / NANOSECONDS_IN_A_MILISECOND;
+        let step = match self.player {
+            Some(_) if !lines.lock().unwrap().is_empty() => return Some(Event::Other),
+            Some(ref mut player) => player.next(now),
+            None => return None,
+        };
+
+        match step {
+            Step::Input(input, _) => Some(self.interpret(input)),
+            Step::Lines(batch) => {
+                let batch = batch.into_iter().map(|(source, text)| (source, Arc::from(text)));
+                lines.lock().unwrap().extend(batch);
+                Some(Event::Other)
+            }
+            Step::Wait(remaining) => {
+                let delay = remaining.min(PLAYBACK_POLL_INTERVAL);
+                thread::sleep(::std::time::Duration::from_millis(delay));
+                Some(Event::Other)
+            }
+            Step::Finished => {
+                self.player = None;
+                self.apply_health(vec![session_health(Health::Ended, "Session played back")]);
+                self.show_message("Session played back, keys are live again".to_string());
+                None
+            }
+        }
+    }
+
+    fn record<F>(&mut self, write: F)
+        where F: FnOnce(&mut Recorder, u64) -> io::Result<()>
+    {
+        let now = time::precise_time_ns() / NANOSECONDS_IN_A_MILISECOND;
+        let result = match self.recorder {
+            Some(ref mut recorder) => write(recorder, now),
+            None => return,
+        };
+
+        if let Err(error) = result {
+            self.recorder = None;
+            self.show_message(format!("Stopped recording the session - {}", error));
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        let elapsed = (time::precise_time_ns() - self.last_activity_at) / NANOSECONDS_IN_A_MILISECOND;
+        self.queue.is_empty() && elapsed > IDLE_THRESHOLD
+    }
+
+    /// Clears the request to quit once it's left unconfirmed.
+    fn expire_quit_request(&mut self) {
+        let now = time::precise_time_ns() / NANOSECONDS_IN_A_MILISECOND;
+        if self.quit_guard.expire(now) {
+            self.frame.navigation.status.set_message(None);
+            self.frame.navigation.render_status();
+        }
+    }
+
+    /// Shows that alerts may jump again once a snooze ran out.
+    fn expire_alert_snooze(&mut self) {
+        let now = time::precise_time_ns() / NANOSECONDS_IN_A_MILISECOND;
+        if self.alert_jumps.expire(now) {
+            self.frame.set_snoozed_indicator(false);
+            self.show_message("Alerts jump to their tab again".to_string());
+        }
+    }
+
+    fn snooze_alerts(&mut self) {
+        let now = time::precise_time_ns() / NANOSECONDS_IN_A_MILISECOND;
+        let snoozed = self.alert_jumps.snoozed_until(now).is_none();
+
+        if snoozed {
+            self.alert_jumps.snooze(now, SNOOZE_MINUTES);
+            self.show_message(format!("Alerts won't jump to their tab for {} minutes, A to resume",
+                                      SNOOZE_MINUTES));
+        } else {
+            self.alert_jumps.resume();
+            self.show_message("Alerts jump to their tab again".to_string());
+        }
+        self.frame.set_snoozed_indicator(snoozed);
+    }
+
+    fn toggle_sampling(&mut self) {
+        if self.sampler.toggle() {
+            self.show_message("Lines matching alerts are always shown, O shows every line again"
+                .to_string());
+        } else {
+            self.show_message("Every line is shown again, lines skipped until now are gone"
+                .to_string());
+        }
+        self.refresh_sampling_banner();
+    }
+
+    fn refresh_sampling_banner(&self) {
+        self.frame.navigation.status.set_banner(self.sampler.banner());
+        self.frame.navigation.render_status();
+    }
+
+    /// Drops the text of the lines sampling skips, the ones matching an alert
+    /// of any tab being kept. Sampling is offered once lines arrive too fast.
+    fn sample(&mut self,
+              pending_lines: Vec<(usize, Arc<str>)>)
+              -> Vec<(usize, Option<Arc<str>>)> {
+        let rate = self.sampler.arrived(time::get_time().sec, pending_lines.len());
+        if rate.map_or(false, |rate| self.sampler.should_offer(rate)) {
+            self.show_message(format!("{} lines a second arriving, O shows only some of them",
+                                      sampling::grouped(rate.unwrap_or(0))));
+        }
+        if !self.sampler.active {
+            return pending_lines.into_iter().map(|(source, text)| (source, Some(text))).collect();
+        }
+
+        let alerts = self.buffers
+            .iter()
+            .flat_map(|buffer| buffer.filter.alerts.iter().map(|alert| &alert.regex))
+            .collect::<Vec<_>>();
+        let sampler = &mut self.sampler;
+        let pending_lines = pending_lines.into_iter()
+            .map(|(source, text)| {
+                let important = !alerts.is_empty() && {
+                    let text = text.strip_ansi();
+                    alerts.iter().any(|regex| regex.is_match(&text))
+                };
+                (source, if sampler.admit(important) { Some(text) } else { None })
+            })
+            .collect();
+
+        self.refresh_sampling_banner();
+        pending_lines
+    }
+
+    /// Reports the alerts fired by the lines received after the given id,
+    /// returning the tab and line to jump to when one of them jumps.
+    fn check_alerts(&mut self, after: usize, completed: &[usize]) -> Option<(usize, usize)> {
+        let now = time::precise_time_ns() / NANOSECONDS_IN_A_MILISECOND;
+        let selected = self.buffers.selected();
+        let may_jump = self.alert_jumps.may_jump(now) && self.search_pass.is_none() &&
+                       self.frame.navigation.state == NavigationState::Menu;
+        let mut message = None;
+        let mut target = None;
+
+        for (index, buffer) in self.buffers.iter().enumerate() {
+            let firings = alert::fired_including(buffer, &self.lines, after, completed);
+            let alerts = &buffer.filter.alerts;
+
+            let jumping = firings.iter()
+                .find(|firing| alerts[firing.alert].jump)
+                .filter(|_| may_jump && index != selected && target.is_none());
+            let firing = match jumping.or_else(|| firings.first()) {
+                Some(firing) => firing,
+                None => continue,
+            };
+
+            let pattern = alerts[firing.alert].regex.as_str();
+            if jumping.is_some() {
+                target = Some((index, firing.line_id));
+                message = Some(format!("Alert `{}` fired on {}, A snoozes jumps",
+                                       pattern,
+                                       buffer.filter.name));
+            } else if target.is_none() {
+                message = Some(format!("Alert `{}` fired on {}", pattern, buffer.filter.name));
+            }
+        }
+
+        if let Some(message) = message {
+            self.show_message(message);
+        }
+        target
+    }
+
+    /// Switches to the tab an alert fired on, scrolling to the line.
+    fn jump_to_alert(&mut self, index: usize, line_id: usize) {
+        let now = time::precise_time_ns() / NANOSECONDS_IN_A_MILISECOND;
+        self.alert_jumps.jumped(now);
+
+        self.select_tab(index);
+        self.scroll_to_line_id(line_id);
+    }
+
+    /// The first Ctrl-C asks for confirmation, the second one quits.
+    fn interrupt(&mut self) -> Event {
+        let now = time::precise_time_ns() / NANOSECONDS_IN_A_MILISECOND;
+        if self.quit_guard.is_pending(now) {
+            return Event::ForceQuit;
+        }
+
+        self.quit_guard.hold(now);
+        self.show_message("Press Ctrl-C again or q to quit".to_string());
+        Event::Other
+    }
+
+    fn record_activity(&mut self) {
+        self.last_activity_at = time::precise_time_ns();
+    }
+
+    /// Scrolls the widget under the pointer, the content being the fallback.
+    /// Over the menu the wheel cycles through the tabs.
+    fn wheel(&mut self, direction: i32, y: i32, x: i32) {
+        match self.frame.widget_at(y, x) {
+            Some(Widget::Rules) => {
+                self.handle_rules(if direction > 0 {
+                    RulesAction::Previous
+                } else {
+                    RulesAction::Next
+                })
+            }
+            Some(Widget::Patterns) => {
+                self.handle_patterns(if direction > 0 {
+                    PatternsAction::Previous
+                } else {
+                    PatternsAction::Next
+                })
+            }
+            Some(Widget::Sidebar) => self.frame.sidebar.scroll(direction),
+            Some(Widget::Navigation) if self.frame.navigation.state == NavigationState::Menu => {
+                self.select_menu_item(if direction > 0 {
+                    Direction::Left
+                } else {
+                    Direction::Right
+                })
+            }
+            Some(Widget::Overlay) | Some(Widget::Navigation) => {}
+            _ => {
+                let time = time::precise_time_ns() / NANOSECONDS_IN_A_MILISECOND;
+                let step = self.wheel.step(direction, time) as i32;
+                self.scroll(Offset::Line(direction * step));
+            }
         }
     }
 
     fn select_menu_item(&mut self, direction: Direction) {
+        // Left and right move the column cursor while it's shown
+        if self.frame.column_cursor.column.get().is_some() {
+            let offset = match direction {
+                Direction::Left => -1,
+                Direction::Right => 1,
+            };
+            self.frame.move_column_cursor(offset);
+            self.describe_column();
+            return self.frame.scroll(self.buffers.selected_item().reverse_index());
+        }
+
+        self.save_tab_state();
+
         match direction {
             Direction::Left => {
                 self.frame.select_left_menu_item();
@@ -104,141 +756,2310 @@ impl Flow {
                 self.buffers.select_next();
             }
         };
+
+        self.restore_tab_state();
+        if let Some(description) = self.health.describe() {
+            self.show_message(description);
+        }
+    }
+
+    fn select_tab(&mut self, index: usize) {
+        self.save_tab_state();
+        self.frame.select_menu_item_at(index);
+        self.buffers.select(index);
+        self.restore_tab_state();
+    }
+
+    fn save_tab_state(&self) {
+        let buffer = self.buffers.selected_item();
+        let content_state = self.frame.content.state.borrow();
+        let top_line_id = self.top_line_id();
+
+        buffer.update_state(|state| {
+            state.highlighted_line_id = content_state.highlighted_line_id;
+            state.highlighted_match = content_state.highlighted_match;
+            state.top_line_id = top_line_id;
+        });
+    }
+
+    /// Id of the line shown on the top row of the screen.
+    fn top_line_id(&self) -> Option<usize> {
+        let buffer = self.buffers.selected_item();
+
+        self.frame
+            .line_index_at(buffer.reverse_index(), 0)
+            .map(|index| self.frame.rendered_lines[index].line.id)
+    }
+
+    /// Scrolls so that the line is on the top row, when it's rendered.
+    fn scroll_to_line_id(&mut self, id: usize) {
+        let buffer = self.buffers.selected_item();
+        let rendered_lines = &self.frame.rendered_lines;
+
+        if let Some(index) = rendered_lines.index_of_id(id) {
+            let row = rendered_lines.height_up_to_index(index);
+            let mut viewport = self.frame.viewport(buffer.reverse_index());
+            viewport.scroll_to_row(row);
+
+            buffer.set_reverse_index(viewport.reverse_index);
+            self.frame.scroll(viewport.reverse_index);
+        }
+    }
+
+    fn restore_tab_state(&mut self) {
+        let tab_state = self.buffers.selected_item().state.get();
+
+        {
+            let mut content_state = self.frame.content.state.borrow_mut();
+            content_state.highlighted_line_id = tab_state.highlighted_line_id;
+            content_state.highlighted_match = tab_state.highlighted_match;
+        }
+
         self.reset_view();
+
+        // Lines were wrapped differently when the position was saved
+        if tab_state.needs_rewrap {
+            if let Some(id) = tab_state.top_line_id {
+                self.scroll_to_line_id(id);
+            }
+            self.buffers.selected_item().update_state(|state| state.needs_rewrap = false);
+        }
+
+        if self.frame.navigation.search.is_active() {
+            self.perform_search(Highlight::Current);
+        }
+
+        self.frame.set_anchor_indicator(tab_state.anchor);
+        self.frame.set_pinned_indicator(tab_state.pinned.is_some());
+        self.frame.set_raw_indicator(self.buffers.selected_item().show_raw);
     }
 
     fn scroll(&mut self, offset: Offset) {
         let buffer = self.buffers.selected_item();
+        let mut viewport = self.frame.viewport(buffer.reverse_index());
 
         match offset {
-            Offset::Line(value) => {
-                buffer.increment_reverse_index(value, self.frame.max_scroll_value());
-            }
+            Offset::Line(value) => viewport.scroll_by(value as i64),
             Offset::Viewport(value) => {
-                buffer.increment_reverse_index(value * self.frame.height - 4,
-                                               self.frame.max_scroll_value());
-            }
-            Offset::Top => {
-                buffer.reverse_index.set(self.frame.max_scroll_value() as usize);
-            }
-            Offset::Bottom => {
-                buffer.reset_reverse_index();
+                // Keep a few rows from the previous page in sight
+                let page_height = max(1, viewport.visible_height as i64 - 3);
+                viewport.scroll_by(value as i64 * page_height);
             }
+            Offset::Top => viewport.scroll_to_top(),
+            Offset::Bottom => viewport.scroll_to_bottom(),
         };
 
-        self.frame.scroll(buffer.reverse_index.get() as i32);
+        buffer.set_reverse_index(viewport.reverse_index);
+        self.frame.scroll(viewport.reverse_index);
+
+        if buffer.state.get().pinned.is_some() {
+            self.pin_view();
+        }
+    }
+
+    /// Stops following new content, keeping the lines on screen in place
+    /// until the search is cleared.
+    fn pin_view(&self) {
+        let buffer = self.buffers.selected_item();
+        let viewport = self.frame.viewport(buffer.reverse_index());
+        let anchor = viewport.line_anchor(self.frame.rendered_lines.line_heights());
+
+        buffer.update_state(|state| state.pinned = anchor);
+        self.frame.set_pinned_indicator(anchor.is_some());
+    }
+
+    fn unpin_view(&self, message: Option<&str>) {
+        self.buffers.selected_item().update_state(|state| state.pinned = None);
+        self.frame.set_pinned_indicator(false);
+
+        if let Some(text) = message {
+            self.show_message(text.to_string());
+        }
     }
 
     fn handle_search(&mut self, action: SearchAction) {
         match action {
-            SearchAction::ReadInput(keys) => {
-                if self.frame.navigation.search.input_field.read(keys) == QueryState::Changed {
+            SearchAction::Edit(action) => {
+                let before = self.frame.navigation.search.input_field.text();
+                if self.frame.navigation.search.edit(&action) {
+                    self.enqueue(QueuedEvent::PerformSearch, 20);
+                    self.record_search_edit(before);
+                }
+            }
+            SearchAction::Replace(text) => {
+                let before = self.frame.navigation.search.input_field.text();
+                if self.frame.navigation.search.set_text(&text) {
+                    self.enqueue(QueuedEvent::PerformSearch, 20);
+                    self.record_search_edit(before);
+                }
+            }
+            SearchAction::Recall(recall) => {
+                let before = self.frame.navigation.search.input_field.text();
+                if self.frame.navigation.search.recall(recall) {
                     self.enqueue(QueuedEvent::PerformSearch, 20);
+                    self.record_search_edit(before);
                 }
             }
             SearchAction::FindNextMatch => {
-                readline::add_history();
+                self.frame.navigation.search.remember();
                 self.frame.navigation.search.options.next = true;
                 self.perform_search(Highlight::Next);
+                self.pin_view();
                 let pending = QueuedEvent::Unhighlight(SearchAction::FindNextMatch);
                 self.enqueue(pending, 250);
             }
             SearchAction::FindPreviousMatch => {
-                readline::add_history();
+                self.frame.navigation.search.remember();
                 self.frame.navigation.search.options.previous = true;
                 self.perform_search(Highlight::Previous);
+                self.pin_view();
                 let pending = QueuedEvent::Unhighlight(SearchAction::FindPreviousMatch);
                 self.enqueue(pending, 250);
             }
             SearchAction::ToggleFilterMode => {
                 self.frame.navigation.search.toggle_filter();
                 self.perform_search(Highlight::VisibleOrLast);
+                self.history.record(undo::Step::toggle(Setting::FilterMode));
+            }
+            SearchAction::ToggleWholeWord => {
+                self.frame.navigation.search.toggle_whole_word();
+                self.perform_search(Highlight::VisibleOrLast);
+                self.enqueue(QueuedEvent::CountMatches, 100);
+                self.history.record(undo::Step::toggle(Setting::WholeWord));
+            }
+            SearchAction::ToggleFieldFallback => {
+                self.frame.navigation.search.toggle_field_fallback();
+                self.perform_search(Highlight::VisibleOrLast);
+                self.enqueue(QueuedEvent::CountMatches, 100);
+                self.history.record(undo::Step::toggle(Setting::FieldFallback));
+            }
+            SearchAction::ToggleRegex => {
+                self.frame.navigation.search.toggle_regex();
+                self.perform_search(Highlight::VisibleOrLast);
+                self.enqueue(QueuedEvent::CountMatches, 100);
+                self.history.record(undo::Step::toggle(Setting::Regex));
             }
+            SearchAction::ToggleExpressions => {
+                self.frame.navigation.search.toggle_expressions();
+                self.perform_search(Highlight::VisibleOrLast);
+                self.enqueue(QueuedEvent::CountMatches, 100);
+                self.history.record(undo::Step::toggle(Setting::Expressions));
+            }
+            SearchAction::KeepAsRule(exclude) => self.keep_search(exclude),
         }
     }
 
-    fn resize(&mut self) {
-        self.frame.resize();
-        self.reset_view_or_redo_search();
+    /// Makes the edit of the search a step that can be undone, along with the
+    /// edits made right before it.
+    fn record_search_edit(&mut self, before: String) {
+        let after = self.frame.navigation.search.input_field.text();
+        self.history.record_search(before, after);
     }
 
-    fn append_incoming_lines(&mut self, pending_lines: Vec<String>) {
-        let count = pending_lines.len();
-        self.lines.extend(pending_lines);
+    /// Turns the search into a rule of the tab, showing only the lines it
+    /// matches or hiding them, and clears it. Pressing the same keys again
+    /// shortly after, before searching anew, takes the rule back.
+    fn keep_search(&mut self, exclude: bool) {
+        let now = time::precise_time_ns() / NANOSECONDS_IN_A_MILISECOND;
+        let is_active = self.frame.navigation.search.is_active();
 
-        if self.frame.navigation.state == NavigationState::Search {
-            let mut state = self.frame.content.state.borrow_mut();
-            let new_highlighted_line = state.highlighted_line as i32 - count as i32;
-            if new_highlighted_line >= 0 {
-                state.highlighted_line -= count;
+        match self.kept_search.take() {
+            Some((text, excluded, kept_at)) if excluded == exclude && !is_active &&
+                                               now - kept_at <= KEEP_UNDO_TIMEOUT => {
+                return self.undo_keep_search(&text, exclude);
             }
+            _ => {}
         }
 
-        self.reset_view_or_redo_search();
+        let query = match self.frame.navigation.search.build_query(Highlight::Current) {
+            Some(value) => value,
+            None => return self.show_message("Search for the lines to keep first".to_string()),
+        };
 
-        if self.buffers.selected_item().is_scrolled() {
-            let offset = self.frame.rendered_lines.last_lines_height(count);
-            self.scroll(Offset::Line(offset));
-        }
-    }
+        let before = self.tab_rules();
+        let rule = LineRule::from_search(&query.text, query.pattern, exclude);
+        self.buffers.selected_item_mut().filter.line_rules.push(rule);
+        self.kept_search = Some((query.text.clone(), exclude, now));
 
-    fn reset_view(&mut self) {
-        let buffer = self.buffers.selected_item();
-        self.frame.print(&mut buffer.with_lines(&self.lines), None);
-    }
+        let tab = self.buffers.selected();
+        let changes = vec![Change::Rules(tab, before, self.tab_rules()),
+                           Change::Search(query.text.clone(), String::new())];
+        self.history.record(undo::Step::new(kept_description(&query.text, exclude), changes));
 
-    fn reset_view_or_redo_search(&mut self) {
+        self.frame.navigation.search.set_text("");
+        self.frame.content.truncated_highlights.set(0);
+        self.frame.navigation.menu.set_match_counts(None);
+        self.unpin_view(None);
         self.reset_view();
+        self.update_swatches();
+        self.frame.navigation.search.render();
 
-        if self.frame.navigation.state == NavigationState::Search {
-            self.perform_search(Highlight::Current);
+        let verb = if exclude { "Hiding" } else { "Showing only" };
+        self.show_message(format!("{} lines matching `{}`, press again to undo", verb, query.text));
+    }
+
+    fn undo_keep_search(&mut self, text: &str, exclude: bool) {
+        let description = kept_description(text, exclude);
+        if self.history.last().map_or(false, |step| step.description == description) {
+            self.undo(false);
+            self.show_message(format!("Searching for `{}` again", text));
         }
     }
 
-    fn enqueue(&mut self, event: QueuedEvent, offset_time: u64) {
-        let entry = self.queue.entry(event).or_insert(0);
-        *entry = time::precise_time_ns() + offset_time * NANOSECONDS_IN_A_MILISECOND;
+    /// Takes back the last step done, or does again the last one undone.
+    fn undo(&mut self, redo: bool) {
+        let result = if redo { self.history.redo() } else { self.history.undo() };
+        let (description, changes) = match result {
+            Some(value) => value,
+            None => {
+                let verb = if redo { "redo" } else { "undo" };
+                return self.show_message(format!("Nothing to {}", verb));
+            }
+        };
+
+        for change in changes {
+            self.apply_change(change);
+        }
+        self.reset_view_or_redo_search();
+        self.refresh_rules();
+
+        let verb = if redo { "Redid" } else { "Undid" };
+        self.show_message(format!("{}: {}", verb, description));
     }
 
-    fn execute_queue(&mut self) {
-        let current_time = time::precise_time_ns();
-        let events = self.queue
-            .iter()
-            .filter(|&(_, due_at)| *due_at < current_time)
-            .map(|(event, _)| event.clone())
-            .collect::<Vec<QueuedEvent>>();
+    fn apply_change(&mut self, change: Change) {
+        if let Some(tab) = change.tab() {
+            if tab != self.buffers.selected() {
+                self.select_tab(tab);
+            }
+        }
 
-        for event in events {
-            self.queue.remove(&event);
-            match event {
-                QueuedEvent::PerformSearch => self.perform_search(Highlight::VisibleOrLast),
-                QueuedEvent::Unhighlight(action) => {
-                    match action {
-                        SearchAction::FindNextMatch => {
-                            self.frame.navigation.search.options.next = false;
-                        }
-                        SearchAction::FindPreviousMatch => {
-                            self.frame.navigation.search.options.previous = false;
-                        }
-                        _ => unreachable!(),
-                    }
-                    self.frame.navigation.render();
-                    readline::move_cursor();
+        match change {
+            Change::Rules(_, _, after) => {
+                let filter = &mut self.buffers.selected_item_mut().filter;
+                filter.rewrites = after.rewrites;
+                filter.line_rules = after.line_rules;
+            }
+            Change::Highlights(_, after) => self.lines.highlights.replace(after),
+            Change::Search(_, after) => {
+                self.frame.navigation.search.set_text(&after);
+                self.enqueue(QueuedEvent::CountMatches, 100);
+            }
+            Change::Toggle(setting) => self.toggle_setting(setting),
+        }
+    }
+
+    fn toggle_setting(&mut self, setting: Setting) {
+        match setting {
+            Setting::Invisibles => self.frame.toggle_invisibles(),
+            Setting::BlankFolding => {
+                let active = !self.frame.content.fold_blank_lines.get();
+                self.frame.set_blank_folding(active);
+            }
+            Setting::Ruler => self.frame.toggle_ruler(),
+            Setting::Raw(_) => self.toggle_raw(),
+            Setting::FilterMode => self.frame.navigation.search.toggle_filter(),
+            Setting::WholeWord => self.frame.navigation.search.toggle_whole_word(),
+            Setting::FieldFallback => self.frame.navigation.search.toggle_field_fallback(),
+            Setting::Regex => self.frame.navigation.search.toggle_regex(),
+            Setting::Expressions => self.frame.navigation.search.toggle_expressions(),
+            Setting::ColumnCursor => {
+                if self.frame.toggle_column_cursor() {
+                    self.describe_column();
                 }
+                self.frame.scroll(self.buffers.selected_item().reverse_index());
             }
         }
     }
 
-    fn perform_search(&mut self, highlight: Highlight) {
-        let buffer = self.buffers.selected_item();
-        let query = self.frame.navigation.search.build_query(highlight);
-        self.frame.print(&mut buffer.with_lines(&self.lines), query);
-        self.frame.navigation.search.render();
+    /// Switches a setting of the screen, as a step that can be undone.
+    fn switch(&mut self, setting: Setting) {
+        self.toggle_setting(setting);
+        self.reset_view_or_redo_search();
+        self.history.record(undo::Step::toggle(setting));
     }
 
-    fn quit(&self) {
-        unsafe {
-            signal::raise(SIGQUIT);
+    /// Rules of the selected tab, as they are before or after a change.
+    fn tab_rules(&self) -> TabRules {
+        let filter = &self.buffers.selected_item().filter;
+        TabRules {
+            rewrites: filter.rewrites.clone(),
+            line_rules: filter.line_rules.clone(),
         }
     }
+
+    /// Records a change of the rules of the selected tab from the ones given.
+    fn record_rules(&mut self, description: String, before: TabRules) {
+        let tab = self.buffers.selected();
+        let change = Change::Rules(tab, before, self.tab_rules());
+        self.history.record(undo::Step::new(description, vec![change]));
+    }
+
+    /// Edits the search input, opening it first if needed.
+    fn edit_search(&mut self, action: SearchAction) {
+        if self.frame.navigation.change_state(NavigationState::Search) {
+            self.frame.navigation.search.active = true;
+            self.frame.register_dismissal(Layer::SearchHighlights, true);
+            self.frame.navigation.search.move_cursor();
+        }
+        self.handle_search(action);
+    }
+
+    fn open_prompt(&mut self, kind: PromptKind, text: &str) {
+        self.frame.navigation.prompt.open(kind, text);
+        self.frame.navigation.change_state(NavigationState::Prompt);
+        self.frame.register_dismissal(Layer::Prompt, true);
+
+        if kind == PromptKind::RegexTester {
+            self.preview_regex();
+            self.frame.regex_tester.show();
+            self.frame.navigation.prompt.render();
+        }
+    }
+
+    fn handle_prompt(&mut self, action: PromptAction) {
+        let kind = match self.frame.navigation.prompt.kind {
+            Some(value) => value,
+            None => return,
+        };
+
+        match self.frame.navigation.prompt.apply(action) {
+            Some(PromptEvent::Submitted(text)) => {
+                self.close_prompt(kind);
+                match kind {
+                    PromptKind::GotoLine => self.goto_line(&text),
+                    PromptKind::GotoTime => self.goto_time(&text),
+                    PromptKind::Rewrite => self.add_rewrite(&text),
+                    PromptKind::EditRewrite(index) => self.edit_rewrite(index, &text),
+                    PromptKind::ChooseAction => self.choose_action(&text),
+                    PromptKind::ChooseExclusion => self.choose_exclusion(&text),
+                    PromptKind::Highlight => self.toggle_highlight(&text),
+                    PromptKind::ExportMatches => self.export_matches(&text),
+                    PromptKind::BufferLines => self.set_buffer_lines(&text),
+                    PromptKind::GapThreshold => self.set_gap_threshold(&text),
+                    PromptKind::RangeTimes => self.select_time_range(&text),
+                    PromptKind::RangeAction => self.act_on_range(&text),
+                    PromptKind::RegexTester => self.accept_regex(text),
+                    PromptKind::UseRegex => self.use_tested_regex(&text),
+                    PromptKind::Profile => self.switch_profile(&text),
+                    PromptKind::QuickFind => self.keep_quick_find(&text),
+                    PromptKind::Search => {}
+                }
+            }
+            Some(PromptEvent::Cancelled) if kind == PromptKind::QuickFind => {
+                self.close_prompt(kind);
+                self.find_on_screen("");
+            }
+            Some(PromptEvent::Cancelled) => {
+                self.close_prompt(kind);
+                self.offered_actions.clear();
+                self.offered_exclusions.clear();
+                self.tested_regex = None;
+            }
+            Some(PromptEvent::Changed) if kind == PromptKind::RegexTester => {
+                self.preview_regex();
+                self.frame.regex_tester.render();
+                self.frame.navigation.prompt.render();
+            }
+            Some(PromptEvent::Changed) if kind == PromptKind::QuickFind => {
+                let text = self.frame.navigation.prompt.text();
+                self.find_on_screen(&text);
+                self.frame.navigation.prompt.render();
+            }
+            Some(PromptEvent::Changed) | None => {}
+        }
+    }
+
+    fn close_prompt(&mut self, kind: PromptKind) {
+        self.frame.navigation.prompt.close();
+
+        // Rules are edited from the rules panel, which is shown again
+        let state = match kind {
+            PromptKind::EditRewrite(_) => NavigationState::Rules,
+            _ => NavigationState::Menu,
+        };
+        self.frame.navigation.change_state(state);
+        self.frame.register_dismissal(Layer::Prompt, false);
+
+        if kind == PromptKind::RegexTester {
+            self.frame.regex_tester.hide();
+        }
+    }
+
+    /// Tries the regex being typed on the latest lines of the tab.
+    fn preview_regex(&self) {
+        let rendered_lines = &self.frame.rendered_lines;
+        let skipped = rendered_lines.len().saturating_sub(PREVIEW_LINES);
+        let lines = (skipped..rendered_lines.len())
+            .map(|index| rendered_lines[index].line.content_without_ansi.to_string())
+            .collect();
+
+        self.frame.regex_tester.update(&self.frame.navigation.prompt.text(), lines);
+    }
+
+    fn accept_regex(&mut self, text: String) {
+        match regex_tester::compile(&text) {
+            Ok(_) => {
+                self.tested_regex = Some(text);
+                self.open_prompt(PromptKind::UseRegex, "");
+            }
+            Err(error) => self.report_error(error),
+        }
+    }
+
+    fn use_tested_regex(&mut self, text: &str) {
+        let source = match self.tested_regex.take() {
+            Some(value) => value,
+            None => return,
+        };
+
+        match RegexUse::parse(text) {
+            Some(RegexUse::Search) => {
+                let mut flags = self.frame.navigation.search.flags();
+                flags.regex = true;
+                self.frame.navigation.search.set_flags(flags);
+                self.edit_search(SearchAction::Replace(regex_tester::delimited(&source)));
+            }
+            Some(RegexUse::Filter) => self.add_filter_tab(&source),
+            Some(RegexUse::Highlight) => self.toggle_highlight(&regex_tester::delimited(&source)),
+            None => {
+                self.tested_regex = Some(source);
+                self.open_prompt(PromptKind::UseRegex, text);
+            }
+        }
+    }
+
+    /// Opens a tab showing only the lines matching the regex.
+    fn add_filter_tab(&mut self, source: &str) {
+        let name = regex_tester::delimited(source);
+        let regex = match regex_tester::compile(source) {
+            Ok(value) => value,
+            Err(error) => return self.report_error(error),
+        };
+
+        self.save_tab_state();
+        self.buffers.push(Filter {
+            name: name.clone(),
+            content: Some(regex),
+            start: None,
+            end: None,
+            rewrites: vec![],
+            line_rules: vec![],
+            alerts: vec![],
+        });
+        self.buffers.select_last();
+        self.frame.add_menu_item(&name);
+        self.reset_view_or_redo_search();
+    }
+
+    /// Opens a read-only copy of the tab, named after the time it was taken.
+    fn freeze_tab(&mut self) {
+        let timestamp = time::strftime("%H:%M:%S", &time::now()).unwrap();
+        let name = format!("{} @ {}", self.buffers.selected_item().filter.name, timestamp);
+        let frozen = self.buffers.selected_item().freeze(name.clone(), &self.lines);
+
+        self.save_tab_state();
+        self.buffers.push_buffer(frozen);
+        self.buffers.select_last();
+        self.frame.add_menu_item(&name);
+        self.reset_view_or_redo_search();
+        self.show_message("Froze the tab along with its rules, X closes it".to_string());
+    }
+
+    /// Closes a frozen tab, freeing its lines.
+    fn close_tab(&mut self) {
+        if self.buffers.selected_item().frozen.is_none() {
+            return self.show_message("Only frozen tabs can be closed".to_string());
+        }
+
+        self.history.tab_closed(self.buffers.selected());
+        self.buffers.remove_selected();
+        self.frame.remove_menu_item();
+        self.restore_tab_state();
+    }
+
+    /// Marks matches on screen only, as they're typed, leaving the search as is.
+    fn find_on_screen(&self, text: &str) -> usize {
+        let pattern = if text.is_empty() { None } else { Some(SearchPattern::new(text, false)) };
+        let reverse_index = self.buffers.selected_item().reverse_index();
+
+        self.frame.find_on_screen(reverse_index, pattern.as_ref())
+    }
+
+    fn keep_quick_find(&self, text: &str) {
+        let found = self.find_on_screen(text);
+        if found == 0 {
+            return;
+        }
+
+        let now = time::precise_time_ns() / NANOSECONDS_IN_A_MILISECOND;
+        self.frame.quick_find.expires_at.set(Some(now + QUICK_FIND_TIMEOUT * 1_000));
+        self.show_message(format!("{} on screen", found));
+    }
+
+    fn goto_line(&mut self, text: &str) {
+        match text.trim().parse::<usize>() {
+            Ok(value) if value > 0 => self.scroll_to_line(value),
+            _ => {}
+        };
+    }
+
+    fn scroll_to_line(&mut self, number: usize) {
+        let rendered_lines = &self.frame.rendered_lines;
+        let index = (number - 1).min(rendered_lines.len().saturating_sub(1));
+        let row = rendered_lines.height_up_to_index(index);
+
+        self.scroll_to_row(row);
+    }
+
+    fn scroll_to_row(&mut self, row: usize) {
+        let buffer = self.buffers.selected_item();
+        let mut viewport = self.frame.viewport(buffer.reverse_index());
+        viewport.scroll_to_row(row);
+
+        buffer.set_reverse_index(viewport.reverse_index);
+        self.frame.scroll(viewport.reverse_index);
+    }
+
+    /// Scrolls to the first line logged at or after the given time.
+    fn goto_time(&mut self, text: &str) {
+        let target = match timestamp::parse_target(text, timestamp::local_now()) {
+            Ok(value) => value,
+            Err(error) => return self.show_message(error),
+        };
+
+        let found = {
+            let rendered_lines = &self.frame.rendered_lines;
+            let timestamp_at = |index: usize| rendered_lines[index].line.timestamp();
+
+            timestamp::first_at_or_after(rendered_lines.len(), timestamp_at, target)
+                .map(|index| (index, index == 0 &&
+                                    timestamp_at(0).map_or(true, |value| timestamp::on_day_of(value, target) > target)))
+        };
+
+        match found {
+            Some((_, true)) => {
+                self.scroll(Offset::Top);
+                self.show_message("That's before the oldest line kept, showing the top".to_string());
+            }
+            Some((index, false)) => self.scroll_to_line(index + 1),
+            None => {
+                self.scroll(Offset::Bottom);
+                self.show_message(format!("No lines at or after `{}`", text.trim()));
+            }
+        }
+    }
+
+    fn set_gap_threshold(&mut self, text: &str) {
+        let message = match timestamp::parse_duration(text) {
+            Ok(0) => {
+                self.frame.content.gap_threshold.set(None);
+                "No longer marking silences".to_string()
+            }
+            Ok(value) => {
+                self.frame.content.gap_threshold.set(Some(value));
+                format!("Marking silences over {}, press }} to jump to the next one",
+                        format_duration(value))
+            }
+            Err(error) => return self.show_message(error),
+        };
+
+        self.reset_view_or_redo_search();
+        self.show_message(message);
+    }
+
+    /// Scrolls to the first silence marked below the top of the screen,
+    /// marking them from now on if they weren't.
+    fn next_gap(&mut self) {
+        if self.frame.content.gap_threshold.get().is_none() {
+            self.frame.content.gap_threshold.set(Some(DEFAULT_GAP_THRESHOLD));
+            self.reset_view_or_redo_search();
+        }
+        let threshold = self.frame.content.gap_threshold.get().unwrap_or(DEFAULT_GAP_THRESHOLD);
+
+        let top_row = self.frame.viewport(self.buffers.selected_item().reverse_index()).top_row();
+        match self.frame.rendered_lines.next_gap(top_row) {
+            Some((row, gap)) => {
+                self.scroll_to_row(row);
+                self.show_message(format!("{} silence", format_duration(gap)));
+            }
+            None => {
+                self.show_message(format!("No silences over {} below", format_duration(threshold)))
+            }
+        }
+    }
+
+    /// Applies the action to the line targeted when its key was read, unless
+    /// that line has left the buffer since.
+    fn act_on_line(&mut self, action: LineAction, line_id: Option<usize>) {
+        let id = match line_id {
+            Some(value) => value,
+            None => return,
+        };
+        let line = match self.frame.rendered_lines.line_by_id(id) {
+            Some(value) => value.clone(),
+            None => return self.show_message(format!("Line {} is no longer in the buffer", id)),
+        };
+
+        match action {
+            LineAction::QuickAction => self.offer_actions(&line),
+            LineAction::ShowRaw => self.show_raw_line(&line),
+            LineAction::MarkRange => self.mark_range(&line),
+            LineAction::Exclude => self.offer_exclusions(&line),
+        }
+    }
+
+    /// Marks the first line of a range, or its last one when the first was
+    /// already marked.
+    fn mark_range(&mut self, line: &Line) {
+        match self.range_mark.take() {
+            Some(start) => self.set_range(Some(LineRange::new(start, line.id))),
+            None => {
+                self.range_mark = Some(line.id);
+                self.frame.register_dismissal(Layer::Range, true);
+                self.show_message(format!("Range starts at line {}, press m again on its last line",
+                                          line.id));
+            }
+        }
+    }
+
+    fn set_range(&mut self, range: Option<LineRange>) {
+        self.frame.content.line_range.set(range);
+        self.frame.register_dismissal(Layer::Range, range.is_some());
+        self.reset_view_or_redo_search();
+
+        if let Some(value) = range {
+            self.show_message(format!("Selected {}, press M to act on them", value.describe()));
+        }
+    }
+
+    /// Selects the lines logged between two times, such as `14:30 15:00`.
+    fn select_time_range(&mut self, text: &str) {
+        let now = timestamp::local_now();
+        let targets = text.split_whitespace()
+            .map(|value| timestamp::parse_target(value, now))
+            .collect::<Vec<_>>();
+        let (from, to) = match targets.as_slice() {
+            [Ok(from), Ok(to)] => ((*from).min(*to), (*from).max(*to)),
+            [Err(error), _] | [_, Err(error)] => return self.show_message(error.clone()),
+            _ => return self.show_message("Expected two times, as in `14:30 15:00`".to_string()),
+        };
+
+        let found = {
+            let rendered_lines = &self.frame.rendered_lines;
+            let timestamp_at = |index: usize| rendered_lines[index].line.timestamp();
+            let len = rendered_lines.len();
+
+            let first = timestamp::first_at_or_after(len, &timestamp_at, from);
+            let after_last =
+                timestamp::first_at_or_after(len, &timestamp_at, to + 1).unwrap_or(len);
+
+            match first {
+                Some(index) if index < after_last => {
+                    Some(LineRange::new(rendered_lines[index].line.id,
+                                        rendered_lines[after_last - 1].line.id))
+                }
+                _ => None,
+            }
+        };
+
+        match found {
+            Some(range) => {
+                self.range_mark = None;
+                self.set_range(Some(range));
+            }
+            None => self.show_message(format!("No lines between `{}`", text.trim())),
+        }
+    }
+
+    /// Lines of the current tab within the range, oldest first.
+    fn range_lines(&self, range: LineRange) -> Vec<Line> {
+        let buffer_lines = self.buffers.selected_item().with_lines(&self.lines);
+
+        buffer_lines.all().filter(|line| range.contains(line.id)).collect()
+    }
+
+    fn act_on_range(&mut self, text: &str) {
+        let range = match self.frame.content.line_range.get() {
+            Some(value) => value,
+            None => return,
+        };
+        let action = match RangeAction::parse(text) {
+            Ok(value) => value,
+            Err(error) => return self.show_message(error),
+        };
+        let lines = self.range_lines(range);
+
+        let message = match action {
+            RangeAction::Save => {
+                match range::save(&lines, self.export_format, &self.lines.highlights) {
+                    Ok(path) => format!("Saved {} lines to {}", lines.len(), path),
+                    Err(error) => format!("Saving the range failed - {}", error),
+                }
+            }
+            RangeAction::CountMatches => {
+                let query = if self.frame.navigation.search.is_active() {
+                    self.frame.navigation.search.build_query(Highlight::Current)
+                } else {
+                    None
+                };
+
+                match query {
+                    Some(query) => {
+                        let (matches, matching_lines) =
+                            range::count_matches(&lines, &query.pattern);
+                        format!("{} matches on {} of {} lines",
+                                matches,
+                                matching_lines,
+                                lines.len())
+                    }
+                    None => "Search for something to count its matches".to_string(),
+                }
+            }
+            RangeAction::Stats => RangeStats::new(&lines).describe(),
+            RangeAction::Pipe(command) => {
+                let result = self.frame.suspend(|| range::pipe(&command, &lines));
+                self.reset_view_or_redo_search();
+
+                match result {
+                    Ok(true) => return,
+                    Ok(false) => format!("`{}` failed", command),
+                    Err(error) => {
+                        return self.report_error(FlowError::Command(command, error.to_string()))
+                    }
+                }
+            }
+        };
+        self.show_message(message);
+    }
+
+    /// Keeps the range on the lines still stored, dropping it once they're
+    /// all gone.
+    fn clamp_range(&mut self) {
+        let range = match self.frame.content.line_range.get() {
+            Some(value) => value,
+            None => return,
+        };
+        let oldest = match self.lines.entries.front() {
+            Some(line) => line.id,
+            None => return,
+        };
+
+        match range.clamp(oldest) {
+            Some(value) => self.frame.content.line_range.set(Some(value)),
+            None => {
+                self.set_range(None);
+                self.show_message("The selected range was dropped, as its lines are no longer kept"
+                    .to_string());
+            }
+        }
+    }
+
+    /// The line actions apply to: the one holding the current match, or the
+    /// last one on screen.
+    fn target_line(&self) -> Option<Line> {
+        let rendered_lines = &self.frame.rendered_lines;
+
+        let highlighted_id = self.frame.content.state.borrow().highlighted_line_id;
+        let highlighted = highlighted_id.and_then(|id| rendered_lines.index_of_id(id))
+            .filter(|_| self.frame.navigation.search.is_active());
+
+        let index = highlighted.or_else(|| {
+            let viewport = self.frame.viewport(self.buffers.selected_item().reverse_index());
+            viewport.visible_rows().end.checked_sub(1).and_then(|row| rendered_lines.index_at_row(row))
+        });
+
+        index.map(|value| rendered_lines[value].line.clone())
+    }
+
+    fn describe_source(&self, source: usize) -> String {
+        if source == 0 {
+            self.source_name.clone()
+        } else {
+            runner::source_name(source).unwrap_or_else(|| format!("file {}", source))
+        }
+    }
+
+    /// Names the file of the line at the top of the screen in the status
+    /// bar, when lines come from several files.
+    fn refresh_source_indicator(&self) {
+        let rendered_lines = &self.frame.rendered_lines;
+        let source = self.frame
+            .line_index_at(self.buffers.selected_item().reverse_index(), 0)
+            .map(|index| rendered_lines[index].line.source)
+            .filter(|&value| value != 0)
+            .map(|value| self.describe_source(value));
+
+        if self.frame.navigation.status.set_source(source) {
+            self.frame.navigation.render_status();
+        }
+    }
+
+    /// Describes the current line in the sidebar, once it changed.
+    fn refresh_sidebar(&self) {
+        if !self.frame.sidebar.visible {
+            return;
+        }
+
+        let line = self.target_line();
+        let line_id = line.as_ref().map(|value| value.id);
+        if line_id.is_some() && line_id == self.frame.sidebar.line_id.get() {
+            return;
+        }
+
+        let rows = match line {
+            Some(ref value) => {
+                let source_name = self.describe_source(value.source);
+                let filters = self.buffers.iter().map(|buffer| &buffer.filter).collect::<Vec<_>>();
+
+                line_info::describe(value, &source_name, &filters, &self.lines.highlights)
+            }
+            None => vec![],
+        };
+        self.frame.sidebar.set_rows(line_id, rows);
+        self.frame.sidebar.render();
+    }
+
+    /// Tells the column under the cursor, and the character of the current
+    /// line found there when it isn't wrapped.
+    fn describe_column(&self) {
+        let column = match self.frame.column_cursor.column.get() {
+            Some(value) => value,
+            None => return,
+        };
+
+        let found = self.target_line().and_then(|line| {
+            wrap::char_at_column(&line.content_without_ansi, column)
+        });
+        let message = match found {
+            Some(value) => {
+                format!("Column {}: `{}` (U+{:04X})",
+                        column + 1,
+                        value.escape_default(),
+                        value as u32)
+            }
+            None => format!("Column {}: past the end of the line", column + 1),
+        };
+        self.show_message(message);
+    }
+
+    /// Shows the line as received, control characters included, for lines
+    /// that had terminal control sequences removed.
+    fn show_raw_line(&self, line: &Line) {
+        let message = match (&line.raw, &line.wrapper) {
+            (&Some(ref raw), _) => ansi_decoder::escape_control(raw),
+            (&None, &Some(ref wrapper)) => ansi_decoder::escape_control(&wrapper.raw),
+            (&None, &None) => "No control sequences were removed from this line".to_string(),
+        };
+        self.show_message(message);
+    }
+
+    fn offer_actions(&mut self, line: &Line) {
+        let mut offered = actions::available(&self.actions, &line.content_without_ansi)
+            .into_iter()
+            .map(|(action, command)| (action.clone(), command))
+            .collect::<Vec<_>>();
+
+        match offered.len() {
+            0 => self.show_message("No quick action for this line".to_string()),
+            1 => {
+                let (action, command) = offered.remove(0);
+                self.run_action(&action, &command);
+            }
+            _ => {
+                let choices = offered.iter()
+                    .enumerate()
+                    .map(|(i, &(ref action, _))| format!("{} {}", i + 1, action.name))
+                    .collect::<Vec<_>>();
+
+                self.offered_actions = offered;
+                self.open_prompt(PromptKind::ChooseAction, "");
+                self.show_message(choices.join(" | "));
+            }
+        }
+    }
+
+    fn choose_action(&mut self, text: &str) {
+        let offered = ::std::mem::replace(&mut self.offered_actions, vec![]);
+
+        match text.trim().parse::<usize>().ok().and_then(|number| offered.get(number.wrapping_sub(1))) {
+            Some(&(ref action, ref command)) => self.run_action(action, command),
+            None => self.show_message(format!("No action numbered `{}`", text.trim())),
+        }
+    }
+
+    /// Offers searches hiding lines like this one, along with the number of
+    /// lines of the tab each would hide.
+    fn offer_exclusions(&mut self, line: &Line) {
+        let candidates = exclusion::candidates(&line.content_without_ansi);
+        if candidates.is_empty() {
+            return self.show_message("Blank lines are folded with B instead".to_string());
+        }
+
+        let lines = self.buffers.selected_item().with_lines(&self.lines).all().collect::<Vec<_>>();
+        let choices = candidates.iter()
+            .enumerate()
+            .map(|(i, candidate)| {
+                let pattern = candidate.pattern();
+                let hidden = lines.iter().filter(|line| line.contains(&pattern)).count();
+                format!("{} {} (hides {})", i + 1, candidate.label, hidden)
+            })
+            .collect::<Vec<_>>();
+
+        self.offered_exclusions = candidates;
+        self.open_prompt(PromptKind::ChooseExclusion, "");
+        self.frame.navigation.prompt.set_hint(choices.join(" | "));
+    }
+
+    /// Adds the chosen search as a rule hiding its matches.
+    fn choose_exclusion(&mut self, text: &str) {
+        let offered = ::std::mem::replace(&mut self.offered_exclusions, vec![]);
+        let number = text.trim().parse::<usize>().unwrap_or(0);
+        let candidate = match offered.get(number.wrapping_sub(1)) {
+            Some(value) => value,
+            None => return self.show_message(format!("No choice numbered `{}`", text.trim())),
+        };
+
+        let mut rule = LineRule::from_search(&candidate.text, candidate.pattern(), true);
+        rule.origin = "line";
+        let before = self.tab_rules();
+        self.buffers.selected_item_mut().filter.line_rules.push(rule);
+        self.record_rules(kept_description(&candidate.text, true), before);
+
+        self.reset_view();
+        self.update_swatches();
+        self.show_message(format!("Hiding lines matching `{}`, F lists the rules", candidate.text));
+    }
+
+    fn run_action(&mut self, action: &QuickAction, command: &str) {
+        let message = if action.interactive {
+            match self.frame.suspend(|| actions::run(command)) {
+                Ok(true) => None,
+                Ok(false) => Some(format!("`{}` failed", action.name)),
+                Err(error) => {
+                    self.report_error(FlowError::Command(action.name.clone(), error.to_string()));
+                    None
+                }
+            }
+        } else {
+            match actions::spawn(command) {
+                Ok(_) => Some(format!("Started `{}`", action.name)),
+                Err(error) => {
+                    self.report_error(FlowError::Command(action.name.clone(), error.to_string()));
+                    None
+                }
+            }
+        };
+
+        if action.interactive {
+            self.reset_view_or_redo_search();
+        }
+        if let Some(text) = message {
+            self.show_message(text);
+        }
+    }
+
+    fn toggle_highlight(&mut self, text: &str) {
+        let before = self.lines.highlights.patterns().to_vec();
+        match self.lines.highlights.toggle(text) {
+            Ok(added) => {
+                self.reset_view_or_redo_search();
+                let verb = if added { "added" } else { "removed" };
+                self.record_highlights(format!("{} highlight `{}`", verb, text), before);
+            }
+            Err(error) => self.report_error(error),
+        }
+    }
+
+    fn toggle_highlight_at(&mut self, index: usize) {
+        let before = self.lines.highlights.patterns().to_vec();
+        match self.lines.highlights.toggle_enabled(index) {
+            Some(enabled) => {
+                self.reset_view_or_redo_search();
+                let verb = if enabled { "resumed" } else { "paused" };
+                let description = format!("{} highlight `{}`", verb, before[index].source);
+                self.record_highlights(description, before);
+            }
+            None => self.show_message(format!("There's no highlight {}", index + 1)),
+        }
+    }
+
+    fn record_highlights(&mut self, description: String, before: Vec<HighlightPattern>) {
+        let change = Change::Highlights(before, self.lines.highlights.patterns().to_vec());
+        self.history.record(undo::Step::new(description, vec![change]));
+    }
+
+    /// Shows the search and the persistent highlights in the status bar, in
+    /// the colors they're painted with.
+    fn update_swatches(&self) {
+        let search = &self.frame.navigation.search;
+        let mut swatches = vec![];
+
+        if search.is_active() {
+            if let Some(query) = search.build_query(Highlight::Current) {
+                swatches.push(Swatch {
+                    text: query.text,
+                    attr: color::pair_attr(NORMAL_HIGHLIGHT_COLOR, A_REVERSE()),
+                    highlight: None,
+                    enabled: true,
+                });
+            }
+        }
+
+        for (i, pattern) in self.lines.highlights.patterns().iter().enumerate() {
+            let color = color::highlight_color(i).calculate_id();
+            swatches.push(Swatch {
+                text: pattern.source.clone(),
+                attr: color::pair_attr(color, A_UNDERLINE() | A_BOLD()),
+                highlight: Some(i),
+                enabled: pattern.enabled,
+            });
+        }
+
+        self.frame.navigation.status.set_swatches(swatches);
+        self.frame.navigation.render_status();
+    }
+
+    fn add_rewrite(&mut self, text: &str) {
+        match RewriteRule::parse(text) {
+            Ok(rule) => {
+                let before = self.tab_rules();
+                self.buffers.selected_item_mut().add_rewrite(rule);
+                self.record_rules(format!("added rewrite `{}`", text), before);
+                self.frame.set_raw_indicator(false);
+                self.reset_view_or_redo_search();
+            }
+            Err(error) => self.show_message(format!("Invalid rewrite - {}", error)),
+        }
+    }
+
+    fn edit_rewrite(&mut self, index: usize, text: &str) {
+        match RewriteRule::parse(text) {
+            Ok(mut rule) => {
+                let before = self.tab_rules();
+                if let Some(current) = self.buffers.selected_item_mut().filter.rewrites.get_mut(index) {
+                    rule.enabled = current.enabled;
+                    *current = rule;
+                }
+                self.record_rules(format!("edited rewrite `{}`", text), before);
+                self.reset_view_or_redo_search();
+            }
+            Err(error) => self.show_message(format!("Invalid rewrite - {}", error)),
+        }
+
+        self.refresh_rules();
+    }
+
+    fn handle_rules(&mut self, action: RulesAction) {
+        let selected = self.frame.navigation.rules.selected();
+        let rewrite_count = self.buffers.selected_item().filter.rewrites.len();
+        if rewrite_count + self.buffers.selected_item().filter.line_rules.len() == 0 {
+            return;
+        }
+
+        match action {
+            RulesAction::Previous => self.frame.navigation.rules.select(selected.saturating_sub(1)),
+            RulesAction::Next => self.frame.navigation.rules.select(selected + 1),
+            RulesAction::Edit if selected >= rewrite_count => {
+                return self.show_message("Only rewrites can be edited, search again instead"
+                    .to_string());
+            }
+            RulesAction::Edit => {
+                let text = self.buffers.selected_item().filter.rewrites[selected].source();
+                self.open_prompt(PromptKind::EditRewrite(selected), &text);
+                return;
+            }
+            _ => {
+                let before = self.tab_rules();
+                if let Some(description) = self.update_rule(action, selected) {
+                    self.record_rules(description, before);
+                }
+                self.reset_view_or_redo_search();
+            }
+        }
+
+        self.refresh_rules();
+    }
+
+    /// Changes the rule at the index, describing what was done if anything.
+    fn update_rule(&mut self, action: RulesAction, index: usize) -> Option<String> {
+        let filter = &mut self.buffers.selected_item_mut().filter;
+        let rules = &self.frame.navigation.rules;
+
+        // Line rules are listed after the rewrites, and only move among themselves
+        let rewrite_count = filter.rewrites.len();
+        if index >= rewrite_count {
+            let line_rules = &mut filter.line_rules;
+            let position = index - rewrite_count;
+            let text = line_rules[position].text.clone();
+
+            let verb = match action {
+                RulesAction::Toggle => {
+                    line_rules[position].enabled = !line_rules[position].enabled;
+                    if line_rules[position].enabled { "enabled" } else { "disabled" }
+                }
+                RulesAction::Delete => {
+                    line_rules.remove(position);
+                    "removed"
+                }
+                RulesAction::MoveUp if position > 0 => {
+                    line_rules.swap(position, position - 1);
+                    rules.select(index - 1);
+                    "moved"
+                }
+                RulesAction::MoveDown if position + 1 < line_rules.len() => {
+                    line_rules.swap(position, position + 1);
+                    rules.select(index + 1);
+                    "moved"
+                }
+                _ => return None,
+            };
+            return Some(format!("{} rule `{}`", verb, text));
+        }
+
+        let rewrites = &mut filter.rewrites;
+        let text = rewrites[index].source();
+        let verb = match action {
+            RulesAction::Toggle => {
+                rewrites[index].enabled = !rewrites[index].enabled;
+                if rewrites[index].enabled { "enabled" } else { "disabled" }
+            }
+            RulesAction::Delete => {
+                rewrites.remove(index);
+                "removed"
+            }
+            RulesAction::MoveUp if index > 0 => {
+                rewrites.swap(index, index - 1);
+                rules.select(index - 1);
+                "moved"
+            }
+            RulesAction::MoveDown if index + 1 < rewrites.len() => {
+                rewrites.swap(index, index + 1);
+                rules.select(index + 1);
+                "moved"
+            }
+            _ => return None,
+        };
+        Some(format!("{} rewrite `{}`", verb, text))
+    }
+
+    fn refresh_rules(&mut self) {
+        let buffer = self.buffers.selected_item();
+        let mut buffer_lines = buffer.with_lines(&self.lines);
+        buffer_lines.width = self.frame.width as usize;
+        buffer_lines.max_rows = self.frame.content.buffer_rows();
+
+        let rewrites = &buffer.filter.rewrites;
+        let texts = (&buffer_lines).into_iter().map(|line| &*line.content_without_ansi);
+        let hits = rewrite::count_hits(rewrites, texts);
+
+        let mut rows = rewrites.iter()
+            .zip(hits)
+            .map(|(rule, hits)| {
+                RuleRow {
+                    kind: "rewrite",
+                    pattern: rule.source(),
+                    hits: hits,
+                    enabled: rule.enabled,
+                    origin: None,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Counted among every line received, as the tab only shows what they let through
+        rows.extend(buffer.filter.line_rules.iter().map(|rule| {
+            RuleRow {
+                kind: if rule.exclude { "exclude" } else { "include" },
+                pattern: rule.text.clone(),
+                hits: buffer.lines(&self.lines)
+                    .entries
+                    .iter()
+                    .filter(|line| rule.pattern.is_match(&line.content_without_ansi))
+                    .count(),
+                enabled: rule.enabled,
+                origin: Some(rule.origin),
+            }
+        }));
+
+        let title = format!("Rules for {}", buffer.filter.name);
+        self.frame.navigation.rules.set_rows(title, rows);
+
+        if self.frame.navigation.state == NavigationState::Rules {
+            self.frame.navigation.rules.render();
+        }
+    }
+
+    fn handle_patterns(&mut self, action: PatternsAction) {
+        let panel = &self.frame.navigation.patterns;
+        let selected = panel.selected();
+        let now = pattern_stats::now_millis();
+
+        let sorted = self.lines.pattern_stats.sorted(panel.sort(), now);
+        let (source, last_hit) = match sorted.get(selected) {
+            Some(entry) => (entry.source.clone(), entry.last_hit),
+            None => return,
+        };
+
+        match action {
+            PatternsAction::Previous => panel.select(selected.saturating_sub(1)),
+            PatternsAction::Next => panel.select(selected + 1),
+            PatternsAction::Sort(column) => panel.set_sort(column),
+            PatternsAction::Reset => self.lines.pattern_stats.reset(&source),
+            PatternsAction::ResetAll => self.lines.pattern_stats.reset_all(),
+            PatternsAction::Jump => {
+                let line_id = match last_hit {
+                    Some(hit) => hit.line_id,
+                    None => return self.show_message(format!("`{}` wasn't found yet", source)),
+                };
+
+                self.close_patterns();
+                match self.frame.rendered_lines.index_of_id(line_id) {
+                    Some(index) => {
+                        let row = self.frame.rendered_lines.height_up_to_index(index);
+                        self.scroll_to_row(row);
+                    }
+                    None => {
+                        let message = format!("Line {} is no longer shown in this tab", line_id);
+                        self.show_message(message)
+                    }
+                }
+                return;
+            }
+        }
+
+        self.refresh_patterns();
+    }
+
+    fn refresh_patterns(&mut self) {
+        let now = pattern_stats::now_millis();
+        let format_time = |at: i64| {
+            let time = time::at(time::Timespec::new(at / 1_000, 0));
+            time::strftime("%H:%M:%S", &time).unwrap()
+        };
+
+        self.lines.pattern_stats.sync(&self.lines.highlights);
+        let panel = &self.frame.navigation.patterns;
+        let rows = self.lines
+            .pattern_stats
+            .sorted(panel.sort(), now)
+            .into_iter()
+            .map(|entry| {
+                PatternRow {
+                    source: entry.source.clone(),
+                    total: entry.total,
+                    recent: entry.recent(now),
+                    last_hit: entry.last_hit.map(|hit| format_time(hit.at)),
+                }
+            })
+            .collect();
+        panel.set_rows(rows);
+
+        if self.frame.navigation.state == NavigationState::Patterns {
+            panel.render();
+        }
+    }
+
+    fn close_patterns(&mut self) {
+        self.frame.navigation.change_state(NavigationState::Menu);
+        self.frame.register_dismissal(Layer::Patterns, false);
+        self.reset_view_or_redo_search();
+    }
+
+    /// Switches the tab between lines as received and lines with every display
+    /// feature applied, keeping the line at the top of the screen in place.
+    fn toggle_raw(&mut self) {
+        let top_line_id = self.top_line_id();
+        let raw = {
+            let buffer = self.buffers.selected_item_mut();
+            buffer.show_raw = !buffer.show_raw;
+            buffer.show_raw
+        };
+
+        self.frame.set_raw_indicator(raw);
+        self.reset_view();
+        if let Some(id) = top_line_id {
+            self.scroll_to_line_id(id);
+        }
+        if self.frame.navigation.search.is_active() {
+            self.perform_search(Highlight::Current);
+        }
+
+        let message = if raw { "Showing lines as received" } else { "Showing processed lines" };
+        self.show_message(message.to_string());
+    }
+
+    fn handle_replay(&mut self, action: ReplayAction) {
+        if let Some(ref replay) = self.replay {
+            let mut replay = replay.lock().unwrap();
+
+            match action {
+                ReplayAction::TogglePause => replay.toggle_pause(),
+                ReplayAction::Faster => replay.faster(),
+                ReplayAction::Slower => replay.slower(),
+                ReplayAction::SkipGap => replay.skip_gap(REPLAY_GAP_THRESHOLD),
+            };
+        }
+
+        self.update_replay_status();
+    }
+
+    fn update_replay_status(&self) {
+        if let Some(ref replay) = self.replay {
+            let text = replay.lock().unwrap().status_text();
+            self.frame.navigation.status.set_progress(Some(text));
+            self.frame.navigation.render_status();
+        }
+    }
+
+    fn refresh_metrics(&mut self, force: bool) {
+        if !self.frame.debug_overlay.is_visible() {
+            return;
+        }
+
+        let now = time::precise_time_ns() / NANOSECONDS_IN_A_MILISECOND;
+        let elapsed = now - self.metrics.1;
+        if !force && elapsed < METRICS_REFRESH_INTERVAL {
+            return;
+        }
+
+        let snapshot = METRICS.snapshot();
+        self.frame.debug_overlay.set_rows(snapshot.describe(&self.metrics.0, elapsed));
+        self.frame.debug_overlay.render();
+        self.metrics = (snapshot, now);
+    }
+
+    /// Redraws the status bar sparkline once per second, leaving the
+    /// content alone.
+    fn refresh_sparkline(&mut self) {
+        let second = time::get_time().sec;
+        if second == self.sparkline_second {
+            return;
+        }
+        self.sparkline_second = second;
+
+        let text = sparkline::render(&METRICS.rates(second), SPARKLINE_CELLS);
+        let value = if text.trim().is_empty() { None } else { Some(text) };
+
+        self.frame.navigation.status.set_sparkline(value);
+        self.frame.navigation.render_status();
+    }
+
+    fn handle_chord(&mut self, chord: Chord) {
+        match chord {
+            Chord::Lines(value) => self.scroll(Offset::Line(value)),
+            Chord::Top => self.scroll(Offset::Top),
+            Chord::Bottom => self.scroll(Offset::Bottom),
+            Chord::GotoLine(number) => self.scroll_to_line(number),
+            Chord::Reposition(placement) => self.reposition(placement),
+        }
+    }
+
+    /// Scrolls so that the line holding the current match is shown at the
+    /// given place on screen.
+    fn reposition(&mut self, placement: Placement) {
+        let line_id = self.frame.content.state.borrow().highlighted_line_id;
+        let index = match line_id.and_then(|id| self.frame.rendered_lines.index_of_id(id)) {
+            Some(value) if self.frame.navigation.search.is_active() => value,
+            _ => return self.show_message("No current match to reposition around".to_string()),
+        };
+
+        let row = self.frame.rendered_lines.height_up_to_index(index);
+        let height = self.frame.rendered_lines[index].height;
+
+        let buffer = self.buffers.selected_item();
+        let mut viewport = self.frame.viewport(buffer.reverse_index());
+        let visible_height = viewport.visible_height;
+
+        match placement {
+            Placement::Top => viewport.scroll_to_row(row),
+            Placement::Center => viewport.scroll_to_row((row + height / 2).saturating_sub(visible_height / 2)),
+            Placement::Bottom => viewport.scroll_to_row((row + height).saturating_sub(visible_height)),
+        }
+
+        buffer.set_reverse_index(viewport.reverse_index);
+        self.frame.scroll(viewport.reverse_index);
+    }
+
+    fn show_pending_keys(&self, keys: Option<String>) {
+        self.frame.navigation.status.set_message(keys);
+        self.frame.navigation.render_status();
+    }
+
+    /// Logs the error and shows it until dismissed or replaced.
+    fn report_error(&mut self, error: FlowError) {
+        let message = format!("{} (! to review)", error);
+        self.errors.push(error);
+
+        self.frame.show_error(&message);
+        self.frame.register_dismissal(Layer::Errors, true);
+        if self.frame.error_overlay.is_visible() {
+            self.frame.error_overlay.set_rows(self.errors.rows());
+            self.frame.error_overlay.render();
+        }
+    }
+
+    /// Marks the tabs with the state of the sources, logging the changes
+    /// other than sources becoming active for the first time.
+    fn apply_health(&mut self, changes: Vec<HealthChange>) {
+        if changes.is_empty() {
+            return;
+        }
+
+        for change in changes {
+            let known = self.health.knows(&change.source);
+            if self.health.update(vec![change.clone()]).is_empty() ||
+               (!known && change.health == Health::Active) {
+                continue;
+            }
+            self.errors.push(FlowError::SourceState(format!("Source {} - {}",
+                                                            change.health.name(),
+                                                            change.detail)));
+        }
+
+        self.frame.set_source_health(self.health.overall());
+        if self.frame.error_overlay.is_visible() {
+            self.frame.error_overlay.set_rows(self.errors.rows());
+            self.frame.error_overlay.render();
+        }
+    }
+
+    fn review_errors(&mut self) {
+        if self.errors.is_empty() && !self.frame.error_overlay.is_visible() {
+            return self.show_message("No errors so far".to_string());
+        }
+
+        self.frame.error_overlay.set_rows(self.errors.rows());
+        self.frame.error_overlay.toggle();
+        self.frame.error_overlay.render();
+        self.frame.register_dismissal(Layer::Errors, self.frame.error_overlay.is_visible());
+    }
+
+    fn show_message(&self, message: String) {
+        self.frame.navigation.status.set_message(Some(message));
+        self.frame.navigation.render_status();
+    }
+
+    /// Resizes when the terminal changed size without saying so.
+    fn probe_size(&mut self, now: u64) {
+        if self.player.is_some() {
+            return;
+        }
+
+        let measured = self.size_probe.check(now, (LINES(), COLS()), size_probe::measure);
+        if let Some((rows, columns)) = measured {
+            debug_log!("resize", "missed rows={} cols={}", rows, columns);
+            self.record(|recorder, now| recorder.record_input(&Input::Resize, KEY_RESIZE, now));
+            self.resize();
+        }
+    }
+
+    fn resize(&mut self) {
+        for buffer in self.buffers.inactive_items() {
+            buffer.update_state(|state| state.needs_rewrap = true);
+        }
+
+        let started_at = time::precise_time_ns();
+        self.frame.resize();
+        self.reset_view_or_redo_search();
+        self.frame.render();
+        debug_log!("resize",
+                   "width={} height={} micros={}",
+                   self.frame.width,
+                   self.frame.height,
+                   (time::precise_time_ns() - started_at) / 1_000);
+    }
+
+    fn append_incoming_lines(&mut self, pending_lines: Vec<(usize, Arc<str>)>) {
+        let (completed, pending_lines) = self.complete_partial_lines(pending_lines);
+        let pending_lines = self.sample(pending_lines);
+        if pending_lines.is_empty() && completed.is_empty() {
+            return;
+        }
+
+        // Completed lines are checked for alerts along with the new ones
+        let count = pending_lines.iter().filter(|&&(_, ref text)| text.is_some()).count();
+        let received = self.lines.received();
+        if !pending_lines.is_empty() {
+            let protected = self.protected_ranges();
+            self.lines.protected.set(protected);
+            self.warn_of_eviction(count);
+            self.lines.extend(pending_lines);
+        }
+        self.clamp_range();
+
+        if self.lines.sanitized > 0 && !self.sanitized_reported {
+            self.sanitized_reported = true;
+            self.show_message("Terminal control sequences were removed from some lines, press E to \
+                               inspect one"
+                .to_string());
+        }
+        // Sampling may have skipped every line
+        if count == 0 && completed.is_empty() {
+            return;
+        }
+
+        let alert_target = self.check_alerts(received, &completed);
+
+        if let Some(ref mut pass) = self.search_pass {
+            pass.lines_arrived = true;
+            return;
+        }
+
+        // Frozen tabs don't show new lines
+        let frozen = self.buffers.selected_item().frozen.is_some();
+        if !frozen {
+            self.reset_view_or_redo_search();
+        }
+
+        if self.frame.navigation.search.is_active() {
+            self.enqueue(QueuedEvent::CountMatches, 250);
+        }
+
+        match self.frame.navigation.state {
+            NavigationState::Rules => self.refresh_rules(),
+            NavigationState::Patterns => self.refresh_patterns(),
+            _ => {}
+        }
+
+        if !frozen {
+            self.follow_new_lines(count);
+        }
+
+        if let Some((index, line_id)) = alert_target {
+            self.jump_to_alert(index, line_id);
+        }
+    }
+
+    /// Puts lines completing the partial ones shown before in their place,
+    /// returning the ids completed along with the rest.
+    fn complete_partial_lines(&mut self,
+                              pending_lines: Vec<(usize, Arc<str>)>)
+                              -> (Vec<usize>, Vec<(usize, Arc<str>)>) {
+        let mut completed = vec![];
+        let mut rest = vec![];
+
+        for (source, text) in pending_lines {
+            match self.lines.partial_id(source) {
+                Some(id) if self.lines.complete_partial(source, text.clone()) => {
+                    completed.push(id);
+                }
+                _ => rest.push((source, text)),
+            }
+        }
+
+        (completed, rest)
+    }
+
+    /// Shows the lines sources are in the middle of writing, or what more
+    /// was written of them.
+    fn show_partial_lines(&mut self, partial_lines: BTreeMap<usize, String>) {
+        let added = self.lines.show_partials(partial_lines);
+
+        if let Some(ref mut pass) = self.search_pass {
+            pass.lines_arrived = true;
+            return;
+        }
+        if self.buffers.selected_item().frozen.is_none() {
+            self.reset_view_or_redo_search();
+            self.follow_new_lines(added);
+        }
+    }
+
+    /// Lines marked, in a range or being marked, and those the tabs showing
+    /// received lines are pinned to.
+    fn protected_ranges(&self) -> Vec<LineRange> {
+        let pinned = self.buffers
+            .iter()
+            .filter(|buffer| buffer.frozen.is_none())
+            .filter_map(|buffer| buffer.state.get().pinned)
+            .map(|anchor| LineRange::new(anchor.line_id, anchor.line_id));
+        let marked = self.range_mark.map(|id| LineRange::new(id, id));
+
+        pinned.chain(marked).chain(self.frame.content.line_range.get()).collect()
+    }
+
+    /// Tells once about marked or pinned lines that the lines incoming, or
+    /// the next few ones, will evict.
+    fn warn_of_eviction(&mut self, incoming: usize) {
+        let count = self.lines.endangered(incoming);
+        if count == 0 {
+            return;
+        }
+
+        let noun = if count == 1 { "line" } else { "lines" };
+        let message = match self.lines.protected.policy {
+            Protection::Preserve => {
+                format!("About to lose {} marked {}, they'll be set aside for V to show",
+                        count,
+                        noun)
+            }
+            _ => format!("About to lose {} marked {} - save or snapshot?", count, noun),
+        };
+        self.show_message(message);
+    }
+
+    /// Opens the marked lines set aside once evicted as a frozen tab.
+    fn show_preserved(&mut self) {
+        if self.lines.protected.preserved.is_empty() {
+            return self.show_message("No marked lines were set aside".to_string());
+        }
+
+        let name = "Set aside".to_string();
+        let mut buffer = Buffer::new(Filter {
+            name: name.clone(),
+            content: None,
+            start: None,
+            end: None,
+            rewrites: vec![],
+            line_rules: vec![],
+            alerts: vec![],
+        });
+        buffer.frozen = Some(self.lines.preserved_copy());
+
+        self.save_tab_state();
+        self.buffers.push_buffer(buffer);
+        self.buffers.select_last();
+        self.frame.add_menu_item(&name);
+        self.reset_view_or_redo_search();
+        self.show_message("Marked lines set aside before being evicted, X closes them".to_string());
+    }
+
+    /// Carries out the commands received on the control socket, as if typed.
+    fn answer_control_requests(&mut self) {
+        let requests = match self.control {
+            Some(ref receiver) => receiver.try_iter().collect::<Vec<_>>(),
+            None => return,
+        };
+
+        for request in requests {
+            let result = match request.command.clone() {
+                Command::Search(text) => {
+                    self.edit_search(SearchAction::Replace(text));
+                    Ok(vec![])
+                }
+                Command::Goto(number) => {
+                    self.scroll_to_line(number);
+                    Ok(vec![])
+                }
+                Command::SelectTab(name) => {
+                    match self.buffers.iter().position(|buffer| buffer.filter.name == name) {
+                        Some(index) => {
+                            self.select_tab(index);
+                            Ok(vec![])
+                        }
+                        None => Err(format!("No tab named `{}`", name)),
+                    }
+                }
+                Command::Status => Ok(self.control_status()),
+            };
+            request.answer(result);
+        }
+    }
+
+    /// Lines and matches of every tab, along with the search and where the
+    /// selected tab is scrolled to.
+    fn control_status(&self) -> Vec<(&'static str, Json)> {
+        let query = self.frame.navigation.search.build_query(Highlight::Current)
+            .filter(|_| self.frame.navigation.search.is_active());
+        let counts = query.as_ref().map(|value| self.match_counts(&value.pattern));
+
+        let tabs = self.buffers
+            .iter()
+            .enumerate()
+            .map(|(index, buffer)| {
+                let mut tab = BTreeMap::new();
+                let lines = buffer.with_lines(&self.lines).all().count();
+                let matches = counts.as_ref().map_or(Json::Null, |values| {
+                    Json::U64(values[index] as u64)
+                });
+                tab.insert("name".to_string(), Json::String(buffer.filter.name.clone()));
+                tab.insert("lines".to_string(), Json::U64(lines as u64));
+                tab.insert("matches".to_string(), matches);
+                Json::Object(tab)
+            })
+            .collect();
+
+        let buffer = self.buffers.selected_item();
+        let top_line = self.top_line_id().map_or(Json::Null, |id| Json::U64(id as u64));
+        let search = query.map_or(Json::Null, |value| Json::String(value.text));
+
+        vec![("tab", Json::String(buffer.filter.name.clone())),
+             ("tabs", Json::Array(tabs)),
+             ("stored", Json::U64(self.lines.len() as u64)),
+             ("received", Json::U64(self.lines.received() as u64)),
+             ("search", search),
+             ("top_line", top_line),
+             ("following", Json::Boolean(!buffer.is_scrolled()))]
+    }
+
+    /// Keeps the screen where it was, or on the newest lines, once `count`
+    /// lines were added.
+    fn follow_new_lines(&mut self, count: usize) {
+        let rows = self.frame.rendered_lines.last_lines_height(count);
+        let buffer = self.buffers.selected_item();
+        let mut viewport = self.frame.viewport(buffer.reverse_index());
+
+        let pinned = buffer.state.get().pinned;
+        let restored = pinned.map_or(false, |anchor| {
+            viewport.scroll_to_anchor(&anchor, self.frame.rendered_lines.line_heights())
+        });
+        if !restored {
+            if pinned.is_some() {
+                self.unpin_view(Some("Pinned lines were evicted, following new lines again"));
+            }
+            viewport.rows_appended(rows, buffer.state.get().anchor);
+        }
+
+        buffer.set_reverse_index(viewport.reverse_index);
+        self.frame.scroll(viewport.reverse_index);
+
+        self.flash_new_lines(count);
+    }
+
+    /// Flashes the lines just received that are in sight, redrawing them.
+    fn flash_new_lines(&mut self, count: usize) {
+        let now = time::precise_time_ns() / NANOSECONDS_IN_A_MILISECOND;
+        if !self.frame.content.flash.borrow_mut().arrive(count, now) {
+            return;
+        }
+
+        let received = self.lines.received();
+        let visible = {
+            let viewport = self.frame.viewport(self.buffers.selected_item().reverse_index());
+            let rendered_lines = &self.frame.rendered_lines;
+
+            ((received + 1).saturating_sub(count)..received + 1)
+                .filter(|&id| {
+                    rendered_lines.index_of_id(id).map_or(false, |index| {
+                        viewport.is_row_visible(rendered_lines.height_up_to_index(index))
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+
+        if !visible.is_empty() {
+            self.frame.content.flash.borrow_mut().start(&visible, now);
+            self.reset_view_or_redo_search();
+        }
+    }
+
+    fn reset_view(&mut self) {
+        let buffer = self.buffers.selected_item();
+        self.frame.print(&mut buffer.with_lines(&self.lines), None);
+    }
+
+    fn reset_view_or_redo_search(&mut self) {
+        self.search_pass = None;
+        self.frame.navigation.search.set_progress(None);
+        self.reset_view();
+
+        if self.frame.navigation.search.is_active() && !self.search_stopped {
+            self.perform_search(Highlight::Current);
+        } else {
+            self.update_swatches();
+        }
+        self.report_left_out_lines();
+    }
+
+    /// Tells once that the oldest lines don't fit in the buffer, until they
+    /// all fit again.
+    fn report_left_out_lines(&mut self) {
+        let left_out = self.frame.content.left_out.get();
+        if left_out == 0 {
+            self.left_out_reported = false;
+        } else if !self.left_out_reported {
+            self.left_out_reported = true;
+            self.show_message(format!("The oldest {} lines don't fit in {} buffer lines and \
+                                       aren't shown, press b to keep more",
+                                      left_out,
+                                      self.frame.content.buffer_rows()));
+        }
+    }
+
+    fn clear_search(&mut self) {
+        self.search_stopped = false;
+        if self.search_pass.take().is_some() {
+            self.end_search_progress();
+        }
+
+        if self.frame.navigation.search.is_active() {
+            self.frame.navigation.search.active = false;
+            self.frame.content.truncated_highlights.set(0);
+            self.frame.register_dismissal(Layer::SearchHighlights, false);
+            self.frame.navigation.menu.set_match_counts(None);
+            self.update_swatches();
+            self.frame.navigation.render();
+            self.reset_view();
+        }
+
+        let buffer = self.buffers.selected_item();
+        if buffer.state.get().pinned.is_some() {
+            self.unpin_view(None);
+            if buffer.state.get().anchor == Anchor::Bottom {
+                self.scroll(Offset::Bottom);
+            }
+        }
+    }
+
+    /// Closes whatever is on top, as Escape never quits.
+    fn dismiss(&mut self) {
+        let layer = self.frame.dismissals.borrow().top();
+
+        match layer {
+            Some(Layer::Prompt) => {
+                if let Some(kind) = self.frame.navigation.prompt.kind {
+                    self.close_prompt(kind);
+                }
+            }
+            Some(Layer::Rules) => {
+                self.frame.navigation.change_state(NavigationState::Menu);
+                self.frame.register_dismissal(Layer::Rules, false);
+                self.reset_view_or_redo_search();
+            }
+            Some(Layer::Patterns) => self.close_patterns(),
+            Some(Layer::Errors) => {
+                if self.frame.error_overlay.is_visible() {
+                    self.frame.error_overlay.toggle();
+                }
+                self.frame.register_dismissal(Layer::Errors, false);
+                self.frame.navigation.render();
+            }
+            Some(Layer::DebugOverlay) => {
+                self.frame.debug_overlay.toggle();
+                self.frame.register_dismissal(Layer::DebugOverlay, false);
+            }
+            Some(Layer::CaptureStats) => self.toggle_capture_stats(),
+            Some(Layer::Ruler) => {
+                self.frame.toggle_ruler();
+                self.reset_view_or_redo_search();
+            }
+            Some(Layer::ColumnCursor) => {
+                self.frame.toggle_column_cursor();
+                self.frame.scroll(self.buffers.selected_item().reverse_index());
+            }
+            Some(Layer::QuickFind) => {
+                self.find_on_screen("");
+            }
+            Some(Layer::PinnedView) => {
+                self.unpin_view(None);
+                if self.buffers.selected_item().state.get().anchor == Anchor::Bottom {
+                    self.scroll(Offset::Bottom);
+                }
+            }
+            Some(Layer::Range) => {
+                self.range_mark = None;
+                self.set_range(None);
+            }
+            Some(Layer::SearchHighlights) => self.clear_search(),
+            None => {}
+        }
+    }
+
+    fn clear_buffer(&mut self) {
+        self.buffers.selected_item_mut().clear(&self.lines);
+        self.reset_view_or_redo_search();
+        self.count_matches();
+    }
+
+    fn save_snapshot(&mut self) {
+        let query = if self.frame.navigation.search.is_active() {
+            self.frame.navigation.search.build_query(Highlight::Current).map(|query| query.pattern)
+        } else {
+            None
+        };
+
+        let viewport = self.frame.viewport(self.buffers.selected_item().reverse_index());
+        let rows = snapshot::capture(&self.frame.rendered_lines,
+                                     &viewport,
+                                     self.frame.width as usize,
+                                     self.frame.content.continuation_width(),
+                                     query.as_ref());
+
+        let message = match snapshot::save(&rows) {
+            Ok(path) => format!("Snapshot saved to {}", path),
+            Err(error) => format!("Snapshot failed - {}", error),
+        };
+        self.show_message(message);
+    }
+
+    /// Replaces the tabs, highlights, colors and buffer size with those of
+    /// the profile. Received lines are kept, being shared by every tab.
+    fn switch_profile(&mut self, text: &str) {
+        let name = text.trim();
+        if name.is_empty() {
+            let active = self.profile.as_str();
+            let names = self.profiles
+                .names()
+                .iter()
+                .map(|&value| {
+                    if value == active { format!("{}*", value) } else { value.to_string() }
+                })
+                .collect::<Vec<_>>();
+            return self.show_message(format!("Profiles: {}", names.join(", ")));
+        }
+
+        let profile = match self.profiles.resolve(name) {
+            Ok(value) => value,
+            Err(message) => return self.show_message(message),
+        };
+
+        let rows = self.buffer_lines_arg
+            .or_else(|| profile.buffer_lines.map(|value| value as usize))
+            .unwrap_or(DEFAULT_BUFFER_ROWS);
+        self.frame.content.set_buffer_rows(rows);
+
+        self.buffers = BufferCollection::from_filters(profile.filters.clone().unwrap_or_default());
+        self.history.clear();
+        self.frame.set_menu_items(&profile.menu_item_names());
+
+        self.lines.highlights.clear();
+        for source in profile.highlights.iter().flat_map(|values| values.iter()) {
+            self.lines.highlights.toggle(source).unwrap();
+        }
+
+        self.frame.reset_colors();
+        color::apply_theme(&settings::theme_pairs(&profile));
+        color::set_highlight_palette(settings::highlight_colors(&profile));
+
+        self.profile = name.to_string();
+        self.show_profile();
+        self.reset_view_or_redo_search();
+        self.frame.render();
+    }
+
+    fn show_profile(&self) {
+        let shown = if self.profile == DEFAULT_PROFILE { None } else { Some(self.profile.clone()) };
+
+        self.frame.navigation.status.set_profile(shown);
+        self.frame.navigation.render_status();
+    }
+
+    /// Changes how many rows of history are kept on screen. Stored lines
+    /// are kept, and shrinking drops the oldest rows right away.
+    fn set_buffer_lines(&mut self, text: &str) {
+        let result = match text.trim().parse::<i64>() {
+            Ok(value) => validate_buffer_rows(value),
+            Err(_) => Err(format!("Invalid number of buffer lines `{}`", text.trim())),
+        };
+
+        let message = match result {
+            Ok(rows) => {
+                self.frame.content.set_buffer_rows(rows);
+                self.reset_view_or_redo_search();
+                format!("Keeping {} buffer lines", rows)
+            }
+            Err(message) => message,
+        };
+        self.show_message(message);
+    }
+
+    /// Writes the lines of the current tab matching the search, grep style.
+    fn export_matches(&mut self, text: &str) {
+        let context = match text.trim() {
+            "" => 0,
+            value => {
+                match value.parse::<usize>() {
+                    Ok(number) => number,
+                    Err(_) => {
+                        self.show_message(format!("Invalid number of context lines `{}`", value));
+                        return;
+                    }
+                }
+            }
+        };
+
+        let query = if self.frame.navigation.search.is_active() {
+            self.frame.navigation.search.build_query(Highlight::Current)
+        } else {
+            None
+        };
+        let pattern = match query {
+            Some(value) => value.pattern,
+            None => {
+                self.show_message("Search for something to export its matches".to_string());
+                return;
+            }
+        };
+
+        let buffer_lines = self.buffers.selected_item().with_lines(&self.lines);
+        let message = match export::save(|source| self.describe_source(source),
+                                         buffer_lines.all(),
+                                         &pattern,
+                                         context,
+                                         self.export_format,
+                                         &self.lines.highlights) {
+            Ok((path, count)) => format!("Exported {} matching lines to {}", count, path),
+            Err(error) => format!("Export failed - {}", error),
+        };
+        self.show_message(message);
+    }
+
+    /// Shows stats over the numbers the search regex captures on every line
+    /// of the current tab, computed once each time the overlay is opened.
+    fn toggle_capture_stats(&mut self) {
+        if self.frame.stats_overlay.is_visible() {
+            self.frame.stats_overlay.toggle();
+            self.frame.register_dismissal(Layer::CaptureStats, false);
+            return;
+        }
+
+        let query = if self.frame.navigation.search.is_active() {
+            self.frame.navigation.search.build_query(Highlight::Current)
+        } else {
+            None
+        };
+        let pattern = match query {
+            Some(ref value) if value.pattern.has_captures() => &value.pattern,
+            _ => {
+                self.show_message("Search with a regex capturing a number, as in \
+                                   /duration=(\\d+)ms/ with Alt + R on, to see its stats"
+                    .to_string());
+                return;
+            }
+        };
+
+        let buffer = self.buffers.selected_item();
+        let lines = buffer.with_lines(&self.lines).all().collect::<Vec<_>>();
+        let stats = CaptureStats::new(lines.iter()
+            .flat_map(|line| pattern.captures(&line.content_without_ansi)));
+
+        self.frame.stats_overlay.set_rows(stats.describe(STATS_BUCKETS, STATS_BAR_WIDTH));
+        self.frame.stats_overlay.toggle();
+        self.frame.stats_overlay.render();
+        self.frame.register_dismissal(Layer::CaptureStats, true);
+    }
+
+    fn count_matches(&mut self) {
+        if !self.frame.navigation.search.is_active() || self.search_stopped {
+            return;
+        }
+        if self.search_pass.is_some() {
+            return self.enqueue(QueuedEvent::CountMatches, 100);
+        }
+
+        let query = self.frame.navigation.search.build_query(Highlight::Current).unwrap();
+        let counts = self.match_counts(&query.pattern).into_iter().map(Some).collect::<Vec<_>>();
+
+        self.show_match_counts(&counts);
+    }
+
+    /// Matches of the pattern within the lines kept on screen by each tab.
+    fn match_counts(&self, pattern: &SearchPattern) -> Vec<usize> {
+        let width = self.frame.width as usize;
+
+        self.buffers
+            .iter()
+            .map(|buffer| {
+                let mut buffer_lines = buffer.with_lines(&self.lines);
+                buffer_lines.width = width;
+                buffer_lines.max_rows = self.frame.content.buffer_rows();
+                (&buffer_lines)
+                    .into_iter()
+                    .map(|line| buffer.display_line(line).matches_for(pattern).len())
+                    .sum()
+            })
+            .collect()
+    }
+
+    fn show_match_counts(&mut self, counts: &[Option<usize>]) {
+        self.frame.navigation.menu.set_match_counts(Some(counts));
+
+        if self.frame.navigation.state == NavigationState::Menu {
+            self.frame.navigation.menu.refresh();
+            self.frame.navigation.render_status();
+        }
+    }
+
+    fn enqueue(&mut self, event: QueuedEvent, offset_time: u64) {
+        let entry = self.queue.entry(event).or_insert(0);
+        *entry = time::precise_time_ns() + offset_time * NANOSECONDS_IN_A_MILISECOND;
+    }
+
+    fn execute_queue(&mut self) {
+        let current_time = time::precise_time_ns();
+        let events = self.queue
+            .iter()
+            .filter(|&(_, due_at)| *due_at < current_time)
+            .map(|(event, _)| event.clone())
+            .collect::<Vec<QueuedEvent>>();
+
+        for event in events {
+            self.queue.remove(&event);
+            match event {
+                QueuedEvent::PerformSearch => {
+                    self.perform_search(Highlight::VisibleOrLast);
+
+                    let pending_counts = vec![None; self.frame.navigation.menu.len()];
+                    self.show_match_counts(&pending_counts);
+                    self.enqueue(QueuedEvent::CountMatches, 100);
+                }
+                QueuedEvent::ContinueSearch => self.continue_search(),
+                QueuedEvent::CountMatches => self.count_matches(),
+                QueuedEvent::Unhighlight(action) => {
+                    match action {
+                        SearchAction::FindNextMatch => {
+                            self.frame.navigation.search.options.next = false;
+                        }
+                        SearchAction::FindPreviousMatch => {
+                            self.frame.navigation.search.options.previous = false;
+                        }
+                        _ => unreachable!(),
+                    }
+                    if self.frame.navigation.state == NavigationState::Search {
+                        self.frame.navigation.render();
+                        self.frame.navigation.search.move_cursor();
+                    }
+                }
+            }
+        }
+    }
+
+    fn perform_search(&mut self, highlight: Highlight) {
+        let moves_to_match = highlight == Highlight::Next || highlight == Highlight::Previous;
+        if moves_to_match && self.search_pass.is_some() {
+            // Matches aren't all known yet
+            return;
+        }
+        if highlight != Highlight::Current {
+            self.search_stopped = false;
+        }
+        self.search_pass = None;
+
+        let buffer = self.buffers.selected_item();
+        let query = self.frame.navigation.search.build_query(highlight);
+        let was_truncated = self.frame.content.truncated_highlights.get() > 0;
+        let started_at = time::precise_time_ns();
+
+        if let Some(query) = query.as_ref().filter(|query| !query.filter && !moves_to_match) {
+            let count = self.frame.rendered_lines.len();
+            if ChunkedPass::is_needed(count, DEFAULT_CHUNK_SIZE) {
+                self.search_pass = Some(SearchPass {
+                    chunks: ChunkedPass::new(count, DEFAULT_CHUNK_SIZE),
+                    query: query.clone(),
+                    started_at: started_at,
+                    was_truncated: was_truncated,
+                    lines_arrived: false,
+                });
+                return self.continue_search();
+            }
+        }
+
+        let text = query.as_ref().map(|value| value.text.clone());
+        self.frame.print(&mut buffer.with_lines(&self.lines), query);
+        self.finish_search(text, started_at, was_truncated);
+    }
+
+    /// Searches the next chunk of the rendered lines, reading input before
+    /// going on with the one after.
+    fn continue_search(&mut self) {
+        let (query, lines, percent) = match self.search_pass {
+            Some(ref mut pass) => {
+                pass.chunks.set_total(self.frame.rendered_lines.len());
+                match pass.chunks.next_chunk() {
+                    Some(lines) => (pass.query.clone(), lines, pass.chunks.percent()),
+                    None => return,
+                }
+            }
+            None => return,
+        };
+
+        let buffer = self.buffers.selected_item();
+        self.frame.search_lines(&mut buffer.with_lines(&self.lines), query, lines);
+
+        let finished = self.search_pass.as_ref().map_or(true, |pass| pass.chunks.is_finished());
+        if !finished {
+            self.frame.navigation.search.set_progress(Some(percent));
+            self.frame.navigation.status.set_progress(Some(format!("searching… {}%", percent)));
+            self.render_search_progress();
+            return self.enqueue(QueuedEvent::ContinueSearch, 0);
+        }
+
+        let pass = self.search_pass.take().unwrap();
+        self.end_search_progress();
+        self.finish_search(Some(pass.query.text), pass.started_at, pass.was_truncated);
+        if pass.lines_arrived {
+            self.reset_view_or_redo_search();
+        }
+    }
+
+    /// Stops a long search, leaving the matches found so far painted.
+    fn cancel_search(&mut self) {
+        let percent = match self.search_pass.take() {
+            Some(pass) => pass.chunks.percent(),
+            None => return,
+        };
+
+        self.search_stopped = true;
+        self.frame.navigation.menu.set_match_counts(None);
+        self.end_search_progress();
+        self.update_swatches();
+        self.show_message(format!("Search stopped at {}%, it's done again once changed",
+                                  percent));
+    }
+
+    fn render_search_progress(&self) {
+        if self.frame.navigation.state == NavigationState::Search {
+            self.frame.navigation.search.render();
+        } else {
+            self.frame.navigation.render_status();
+        }
+    }
+
+    fn end_search_progress(&self) {
+        self.frame.navigation.search.set_progress(None);
+        self.frame.navigation.status.set_progress(None);
+        self.update_replay_status();
+        self.render_search_progress();
+    }
+
+    fn finish_search(&mut self, text: Option<String>, started_at: u64, was_truncated: bool) {
+        let elapsed = time::precise_time_ns() - started_at;
+        METRICS.record_search(elapsed);
+        debug_log!("search",
+                   "query={:?} micros={} matches_found={}",
+                   text,
+                   elapsed / 1_000,
+                   self.frame.navigation.search.matches_found);
+
+        let truncated = self.frame.content.truncated_highlights.get();
+        if truncated > 0 && !was_truncated {
+            self.show_message(format!("Highlights truncated, {} runs of matches on crowded rows \
+                                       aren't painted",
+                                      truncated));
+        }
+
+        self.update_swatches();
+        if self.frame.navigation.state == NavigationState::Search {
+            self.frame.navigation.search.render();
+        }
+    }
+
+    /// Ends the event loop, unless quitting would lose marks and isn't
+    /// confirmed or forced.
+    fn quit(&mut self, force: bool) {
+        let unsaved = if self.range_mark.is_some() {
+            Some("A range is being marked")
+        } else if self.frame.content.line_range.get().is_some() {
+            Some("A range is marked")
+        } else {
+            None
+        };
+
+        let now = time::precise_time_ns() / NANOSECONDS_IN_A_MILISECOND;
+        match self.quit_guard.request(force, unsaved, now) {
+            Decision::Quit => runner::stop(),
+            Decision::Confirm(message) => self.show_message(message),
+        }
+    }
+}
+
+/// What keeping a search as a rule did, the same words telling the step
+/// apart when it's taken back by pressing the keys again.
+fn kept_description(text: &str, exclude: bool) -> String {
+    let verb = if exclude { "hid" } else { "showed only" };
+    format!("{} lines matching `{}`", verb, text)
+}
+
+fn session_health(health: Health, detail: &str) -> HealthChange {
+    HealthChange::new("session", health, detail.to_string())
 }
@@ -0,0 +1,113 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::BTreeMap;
+
+/// State of a source, from the least to the most worrying.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Health {
+    /// The stream ended, as replays do once played back.
+    Ended,
+    Active,
+    /// Not read yet, such as a file that's missing or being retried.
+    Waiting,
+    Errored,
+}
+
+impl Health {
+    pub fn glyph(&self) -> &'static str {
+        match *self {
+            Health::Ended => "○",
+            Health::Active => "●",
+            Health::Waiting => "◌",
+            Health::Errored => "✗",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Health::Ended => "ended",
+            Health::Active => "active",
+            Health::Waiting => "waiting",
+            Health::Errored => "failing",
+        }
+    }
+}
+
+/// A source changing state, as published by the reader.
+#[derive(Clone, PartialEq, Debug)]
+pub struct HealthChange {
+    /// Path of the file, or what else is read.
+    pub source: String,
+    pub health: Health,
+    pub detail: String,
+}
+
+impl HealthChange {
+    pub fn new(source: &str, health: Health, detail: String) -> HealthChange {
+        HealthChange {
+            source: source.to_string(),
+            health: health,
+            detail: detail,
+        }
+    }
+}
+
+/// Last known state of every source.
+#[derive(Default)]
+pub struct HealthBoard {
+    sources: BTreeMap<String, (Health, String)>,
+}
+
+impl HealthBoard {
+    /// Records the states, returning the ones that changed.
+    pub fn update(&mut self, states: Vec<HealthChange>) -> Vec<HealthChange> {
+        states.into_iter()
+            .filter(|change| {
+                let state = (change.health, change.detail.clone());
+                self.sources.insert(change.source.clone(), state.clone()) != Some(state)
+            })
+            .collect()
+    }
+
+    pub fn knows(&self, source: &str) -> bool {
+        self.sources.contains_key(source)
+    }
+
+    /// The most worrying state of any source.
+    pub fn overall(&self) -> Option<Health> {
+        self.sources.values().map(|&(health, _)| health).max()
+    }
+
+    /// What the sources are doing, telling about the most worrying one.
+    pub fn describe(&self) -> Option<String> {
+        let overall = self.overall()?;
+        let (_, detail) = self.sources.values().find(|&&(health, _)| health == overall)?;
+        let count = self.sources.values().filter(|&&(health, _)| health == overall).count();
+
+        if self.sources.len() == 1 {
+            Some(detail.clone())
+        } else {
+            Some(format!("{} of {} sources {} - {}",
+                         count,
+                         self.sources.len(),
+                         overall.name(),
+                         detail))
+        }
+    }
+}
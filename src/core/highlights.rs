@@ -0,0 +1,354 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::ops::Deref;
+use std::sync::Arc;
+
+use aho_corasick::{AcAutomaton, Automaton, FullAcAutomaton};
+use regex::{self, Regex, RegexSet};
+
+use core::error::FlowError;
+
+/// Patterns starting with this tint every line they're found on.
+pub const LINE_PREFIX: &'static str = "line:";
+
+/// What a highlight paints: its occurrences, or the whole of the lines
+/// holding one, across the width of the screen.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Scope {
+    Match,
+    Line,
+}
+
+/// Occurrence of a highlight pattern, as a byte range of the line without
+/// colors.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct HighlightSpan {
+    pub pattern: usize,
+    pub start: usize,
+    pub end: usize,
+    pub scope: Scope,
+}
+
+/// How a line is painted, from the bottom up: tinted by the first pattern
+/// scoped to whole lines, then the occurrences of the patterns scoped to
+/// matches. The search is painted last, winning over both on its matches.
+pub struct Layers<'a> {
+    pub tint: Option<usize>,
+    pub spans: Vec<&'a HighlightSpan>,
+}
+
+impl<'a> Layers<'a> {
+    pub fn new(spans: &'a [HighlightSpan]) -> Layers<'a> {
+        Layers {
+            tint: spans.iter().find(|span| span.scope == Scope::Line).map(|span| span.pattern),
+            spans: spans.iter().filter(|span| span.scope == Scope::Match).collect(),
+        }
+    }
+}
+
+/// Highlight spans of a line, shared by its copies. Most lines have none, which
+/// takes no allocation.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct SharedSpans(Option<Arc<[HighlightSpan]>>);
+
+impl SharedSpans {
+    pub fn new(spans: Vec<HighlightSpan>) -> SharedSpans {
+        if spans.is_empty() {
+            SharedSpans(None)
+        } else {
+            SharedSpans(Some(Arc::from(spans)))
+        }
+    }
+}
+
+impl Deref for SharedSpans {
+    type Target = [HighlightSpan];
+
+    fn deref(&self) -> &[HighlightSpan] {
+        match self.0 {
+            Some(ref spans) => spans,
+            None => &[],
+        }
+    }
+}
+
+impl PartialEq<SharedSpans> for Vec<HighlightSpan> {
+    fn eq(&self, other: &SharedSpans) -> bool {
+        self[..] == other[..]
+    }
+}
+
+#[derive(Clone)]
+pub struct HighlightPattern {
+    /// The pattern as typed: plain text, or a regex between slashes.
+    pub source: String,
+    /// Disabled patterns keep their place, and color, but aren't looked for.
+    pub enabled: bool,
+    pub scope: Scope,
+    regex: Regex,
+    literal: Option<String>,
+}
+
+impl HighlightPattern {
+    /// Plain text or a regex between slashes, tinting whole lines when
+    /// prefixed with `line:`.
+    pub fn parse(source: &str) -> Result<HighlightPattern, FlowError> {
+        let (scope, text) = if source.starts_with(LINE_PREFIX) {
+            (Scope::Line, &source[LINE_PREFIX.len()..])
+        } else {
+            (Scope::Match, source)
+        };
+
+        let is_regex = text.len() > 2 && text.starts_with('/') && text.ends_with('/');
+        if text.is_empty() {
+            return Err(FlowError::InvalidRegex(source.to_string(), "empty pattern".to_string()));
+        }
+
+        let (pattern, literal) = if is_regex {
+            (text[1..text.len() - 1].to_string(), None)
+        } else {
+            (regex::escape(text), Some(text.to_string()))
+        };
+
+        match Regex::new(&pattern) {
+            Ok(value) => {
+                Ok(HighlightPattern {
+                    source: source.to_string(),
+                    enabled: true,
+                    scope: scope,
+                    regex: value,
+                    literal: literal,
+                })
+            }
+            Err(error) => Err(FlowError::InvalidRegex(source.to_string(), error.to_string())),
+        }
+    }
+}
+
+/// Patterns highlighted on every line, as opposed to the search which only
+/// applies while it's active. Lines matching none of them, which are most of
+/// them, are rejected with a single scan.
+#[derive(Clone)]
+pub struct Highlights {
+    patterns: Vec<HighlightPattern>,
+    /// Finds every plain pattern in one pass over the line, without going
+    /// through the regex engine.
+    literals: Option<FullAcAutomaton<String>>,
+    /// Index of the pattern each literal of the automaton comes from.
+    literal_patterns: Vec<usize>,
+    /// Alternation of every regex pattern, rejecting most lines in a single scan.
+    any: Option<Regex>,
+    set: Option<RegexSet>,
+    /// Index of the pattern each regex of the set comes from.
+    enabled: Vec<usize>,
+    /// Changes whenever patterns are added or removed, telling apart the
+    /// spans computed for an older set of patterns.
+    pub version: usize,
+}
+
+impl Highlights {
+    pub fn new() -> Highlights {
+        Highlights {
+            patterns: vec![],
+            literals: None,
+            literal_patterns: vec![],
+            any: None,
+            set: None,
+            enabled: vec![],
+            version: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub fn sources(&self) -> Vec<&str> {
+        self.patterns.iter().map(|pattern| pattern.source.as_str()).collect()
+    }
+
+    pub fn patterns(&self) -> &[HighlightPattern] {
+        &self.patterns
+    }
+
+    /// Stops or resumes looking for the pattern at `index`, returning whether
+    /// it's now enabled.
+    pub fn toggle_enabled(&mut self, index: usize) -> Option<bool> {
+        let enabled = match self.patterns.get_mut(index) {
+            Some(pattern) => {
+                pattern.enabled = !pattern.enabled;
+                pattern.enabled
+            }
+            None => return None,
+        };
+
+        self.rebuild();
+        Some(enabled)
+    }
+
+    /// Adds the pattern, or removes it when already present. Returns whether
+    /// it was added.
+    pub fn toggle(&mut self, source: &str) -> Result<bool, FlowError> {
+        if let Some(index) = self.patterns.iter().position(|pattern| pattern.source == source) {
+            self.patterns.remove(index);
+            self.rebuild();
+            return Ok(false);
+        }
+
+        self.patterns.push(HighlightPattern::parse(source)?);
+        self.rebuild();
+        Ok(true)
+    }
+
+    pub fn clear(&mut self) {
+        self.patterns.clear();
+        self.rebuild();
+    }
+
+    /// Puts back patterns as returned by `patterns`, as when undoing.
+    pub fn replace(&mut self, patterns: Vec<HighlightPattern>) {
+        self.patterns = patterns;
+        self.rebuild();
+    }
+
+    fn rebuild(&mut self) {
+        let (literal_patterns, enabled) = (0..self.patterns.len())
+            .filter(|&i| self.patterns[i].enabled)
+            .partition::<Vec<_>, _>(|&i| self.patterns[i].literal.is_some());
+
+        self.literals = if literal_patterns.is_empty() {
+            None
+        } else {
+            let literals = literal_patterns.iter()
+                .map(|&index| self.patterns[index].literal.clone().unwrap());
+            Some(AcAutomaton::new(literals).into_full())
+        };
+        self.literal_patterns = literal_patterns;
+        self.enabled = enabled;
+
+        let sources = self.enabled
+            .iter()
+            .map(|&index| self.patterns[index].regex.as_str().to_string())
+            .collect::<Vec<_>>();
+
+        if sources.is_empty() {
+            self.any = None;
+            self.set = None;
+        } else {
+            let alternation = sources.iter().map(|value| format!("(?:{})", value)).collect::<Vec<_>>();
+            self.any = Regex::new(&alternation.join("|")).ok();
+            self.set = RegexSet::new(sources).ok();
+        }
+        self.version += 1;
+    }
+
+    /// Patterns found in the text, in order, without locating every
+    /// occurrence of them.
+    pub fn hits(&self, text: &str) -> Vec<usize> {
+        let mut hits = match self.literals {
+            Some(ref literals) => {
+                literals.find_overlapping(text)
+                    .map(|found| self.literal_patterns[found.pati])
+                    .collect()
+            }
+            None => vec![],
+        };
+
+        if let (&Some(ref any), &Some(ref set)) = (&self.any, &self.set) {
+            if any.is_match(text) {
+                hits.extend(set.matches(text)
+                    .into_iter()
+                    .map(|i| self.enabled[i])
+                    .filter(|&index| {
+                        let regex = &self.patterns[index].regex;
+                        regex.find_iter(text).any(|found| found.start() != found.end())
+                    }));
+            }
+        }
+
+        hits.sort();
+        hits.dedup();
+        hits
+    }
+
+    /// Every occurrence of each pattern, ordered by pattern.
+    pub fn spans(&self, text: &str) -> Vec<HighlightSpan> {
+        let mut spans = self.literal_spans(text);
+
+        let set = match (&self.any, &self.set) {
+            (&Some(ref any), &Some(ref set)) if any.is_match(text) => set,
+            _ => return spans,
+        };
+
+        for index in set.matches(text).into_iter().map(|i| self.enabled[i]) {
+            let pattern = &self.patterns[index];
+            let span = |(start, end)| {
+                HighlightSpan {
+                    pattern: index,
+                    start: start,
+                    end: end,
+                    scope: pattern.scope,
+                }
+            };
+
+            spans.extend(pattern.regex
+                .find_iter(text)
+                .filter(|found| found.start() != found.end())
+                .map(|found| span((found.start(), found.end()))));
+        }
+
+        if !self.literal_patterns.is_empty() && !self.enabled.is_empty() {
+            spans.sort_by_key(|span| span.pattern);
+        }
+        spans
+    }
+
+    /// Occurrences of the plain patterns, found in a single pass. Like
+    /// `str::match_indices`, occurrences of a pattern don't overlap.
+    fn literal_spans(&self, text: &str) -> Vec<HighlightSpan> {
+        let literals = match self.literals {
+            Some(ref value) => value,
+            None => return vec![],
+        };
+
+        let mut spans = vec![];
+        // Where the last occurrence of each pattern ended, once one is found
+        let mut ends = vec![];
+        for found in literals.find_overlapping(text) {
+            if ends.is_empty() {
+                ends = vec![0; self.literal_patterns.len()];
+            }
+            if found.start < ends[found.pati] {
+                continue;
+            }
+
+            ends[found.pati] = found.end;
+            let index = self.literal_patterns[found.pati];
+            spans.push(HighlightSpan {
+                pattern: index,
+                start: found.start,
+                end: found.end,
+                scope: self.patterns[index].scope,
+            });
+        }
+
+        spans.sort_by_key(|span| span.pattern);
+        spans
+    }
+}
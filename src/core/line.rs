@@ -17,55 +17,414 @@
  */
 
 use std::cmp::max;
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
 use std::iter::{Rev, DoubleEndedIterator};
-
-use unicode_width::UnicodeWidthStr;
+use time::{self, Timespec};
+use regex::{self, Regex};
+use memchr::memchr3;
 
 use core::filter::{Filter, Parser as FilterParser, Constraint, ParserResult as FilterParserResult};
-use utils::ansi_decoder::{ComponentCollection, AnsiStr};
+use utils::ansi_decoder::{self, ComponentCollection, AnsiStr};
+use utils::invisibles::display_width;
+use core::metrics::METRICS;
+use core::highlights::Highlights;
+use core::pattern_stats::PatternStats;
+use core::eviction::{EvictionPolicy, Fifo, ProtectedLines, Protection};
+use core::fields::{self, FieldSpan};
+use core::query::{CompiledQuery, Syntax};
+use core::comparison::Comparison;
+use core::expression::ParseError;
+use core::container::{Stream, Unwrapper, Wrapper};
+use core::timestamp::parse_timestamp;
+
+lazy_static! {
+    static ref FIELD_QUERY: Regex = Regex::new(r"^([A-Za-z_][\w.-]*):(.+)$").unwrap();
+}
+
+/// Follows the text of lines still being written.
+pub const PARTIAL_MARKER: &'static str = "…";
 
+/// A received line. Copies are cheap, as they share the text and its spans.
+///
+/// It's the only line type: the one read from the input, stored in the
+/// collection, filtered by tabs and printed on screen.
 #[derive(Clone)]
 pub struct Line {
-    pub content_without_ansi: String,
-    pub components: Option<ComponentCollection>,
+    /// Sequence number assigned when the line is received, starting from 1.
+    /// Lines that didn't come from the source have it set to 0.
+    pub id: usize,
+    /// Id of the file the line was read from, 0 when following a single one.
+    pub source: usize,
+    /// Sequence number among the lines received from its source, starting
+    /// from 1. Equal to the id when following a single file.
+    pub number: usize,
+    pub content_without_ansi: Arc<str>,
+    pub components: Option<Arc<ComponentCollection>>,
     pub width: usize,
+    pub received_at: Timespec,
+    /// Content as received, kept for lines that had terminal control
+    /// sequences removed. Shared by the copies of the line.
+    pub raw: Option<Arc<str>>,
+    /// JSON a container runtime wrapped the line in, when unwrapped.
+    pub wrapper: Option<Arc<Wrapper>>,
+    /// Still being written by its source. Ends with `PARTIAL_MARKER` and is
+    /// left out of searches until the rest of it arrives.
+    pub partial: bool,
 }
 
 impl Line {
     pub fn new(content: String) -> Line {
-        let has_ansi = content.has_ansi_escape_sequence();
+        Line::with_id(content, 0)
+    }
 
-        let (content_without_ansi, components) = if has_ansi {
-            (content.strip_ansi(), Some(content.to_components()))
+    /// A line taking the text as it is, unless it has escape sequences to
+    /// remove.
+    pub fn with_id<T: Into<Arc<str>>>(content: T, id: usize) -> Line {
+        let content = content.into();
+        let mut raw = None;
+        // The 8-bit CSI is found by its last byte, which other characters may
+        // end with as well
+        let controls = memchr3(0x1b, 0x07, 0x9b, content.as_bytes()).is_some();
+        let sanitized = if controls {
+            let (sanitized, stripped) = ansi_decoder::sanitize(&content);
+            if stripped {
+                raw = Some(content.clone());
+                Some(sanitized)
+            } else {
+                None
+            }
         } else {
-            (content, None)
+            None
+        };
+
+        let text = sanitized.as_ref().map_or(&*content, String::as_str);
+        let (content_without_ansi, components) = if controls && text.has_ansi_escape_sequence() {
+            (Arc::from(text.strip_ansi()), Some(Arc::new(text.to_components())))
+        } else {
+            (sanitized.map_or(content, Arc::from), None)
         };
 
         Line {
-            width: content_without_ansi.width(),
+            id: id,
+            source: 0,
+            number: id,
+            width: display_width(&content_without_ansi),
             content_without_ansi: content_without_ansi,
             components: components,
+            received_at: time::get_time(),
+            raw: raw,
+            wrapper: None,
+            partial: false,
         }
     }
 
+    /// The line as it arrived: the JSON a container runtime wrapped it in, or
+    /// its control characters rendered literally. Colors are dropped.
+    pub fn as_received(&self) -> Line {
+        let text = match (&self.wrapper, &self.raw) {
+            (&Some(ref wrapper), _) => wrapper.raw.clone(),
+            (&None, &Some(ref raw)) => ansi_decoder::escape_control(raw),
+            (&None, &None) => self.content_without_ansi.to_string(),
+        };
+
+        let mut line = Line::with_id(text, self.id);
+        line.source = self.source;
+        line.number = self.number;
+        line.received_at = self.received_at;
+        line.partial = self.partial;
+        line
+    }
+
+    /// Milliseconds represented by the time the line was logged at, taken
+    /// from its wrapper when it has one.
+    pub fn timestamp(&self) -> Option<i64> {
+        self.wrapper
+            .as_ref()
+            .and_then(|wrapper| wrapper.time.as_ref())
+            .and_then(|time| parse_timestamp(time))
+            .or_else(|| parse_timestamp(&self.content_without_ansi))
+    }
+
+    pub fn is_stderr(&self) -> bool {
+        self.wrapper.as_ref().map_or(false, |wrapper| wrapper.stream == Stream::Stderr)
+    }
+
+    /// When the line was received, in milliseconds since the epoch.
+    pub fn received_millis(&self) -> i64 {
+        self.received_at.sec * 1_000 + self.received_at.nsec as i64 / 1_000_000
+    }
+
     pub fn guess_height(&self, container_width: usize) -> usize {
         max(1,
             (self.width as f32 / container_width as f32).ceil() as usize)
     }
 
-    pub fn matches_for(&self, text: &str) -> Vec<(usize, &str)> {
-        self.content_without_ansi.match_indices(text).collect()
+    pub fn matches_for(&self, pattern: &SearchPattern) -> Vec<(usize, &str)> {
+        if self.partial {
+            return vec![];
+        }
+        pattern.find(&self.content_without_ansi)
     }
 
-    pub fn contains(&self, text: &str) -> bool {
-        self.content_without_ansi.contains(text)
+    pub fn contains(&self, pattern: &SearchPattern) -> bool {
+        !self.partial && pattern.is_match(&self.content_without_ansi)
+    }
+}
+
+/// What a search looks for within each line.
+///
+/// Text prefixed with `!` looks for the lines that don't contain the rest of
+/// it, each one counting as a single match spanning the whole line. A leading
+/// `\!` searches for a literal `!`. With regexes on, text between slashes, as
+/// in `/\d+ms/`, is a regex, and is looked for literally while it isn't a
+/// valid one.
+///
+/// A field name followed by a colon, as in `msg:timeout`, only searches the
+/// value of that field, and `duration:>500ms` only the values above 500ms.
+/// Lines without that field are searched for the whole text, so that
+/// `http://host` still finds URLs. Quoting it, as in `"at:12"`, always
+/// searches for the text between the quotes.
+///
+/// With expressions on, terms combined with `AND`, `OR` and `NOT`, as in
+/// `error AND (db OR cache)`, are searched as an `Expression`.
+#[derive(Clone, Debug)]
+pub struct SearchPattern {
+    pub text: String,
+    pub inverse: bool,
+    /// Only matches occurrences that aren't part of a longer word.
+    pub whole_word: bool,
+    pub field: Option<String>,
+    /// Searches lines without any field for the value alone, when searching
+    /// a field.
+    pub field_fallback: bool,
+    pub syntax: Syntax,
+    query: Arc<CompiledQuery>,
+    /// Query for the field name, colon and value, in lines without the field.
+    unfielded: Option<Arc<CompiledQuery>>,
+}
+
+/// Where a field search looks within a line.
+enum Scope<'a> {
+    Fields(Vec<FieldSpan>),
+    Full(&'a CompiledQuery),
+}
+
+impl PartialEq for SearchPattern {
+    fn eq(&self, other: &SearchPattern) -> bool {
+        self.text == other.text && self.inverse == other.inverse &&
+        self.whole_word == other.whole_word && self.field == other.field &&
+        self.field_fallback == other.field_fallback && self.syntax == other.syntax
+    }
+}
+
+impl SearchPattern {
+    pub fn new(query: &str, whole_word: bool) -> SearchPattern {
+        let (text, inverse) = if query.starts_with("\\!") {
+            (&query[1..], false)
+        } else if query.starts_with('!') && query.len() > 1 {
+            (&query[1..], true)
+        } else {
+            (query, false)
+        };
+
+        let quoted = text.len() > 1 && text.starts_with('"') && text.ends_with('"');
+        let (text, field) = if quoted && FIELD_QUERY.is_match(&text[1..text.len() - 1]) {
+            (&text[1..text.len() - 1], None)
+        } else {
+            match FIELD_QUERY.captures(text) {
+                Some(captures) => {
+                    (captures.get(2).unwrap().as_str(), Some(captures[1].to_string()))
+                }
+                None => (text, None),
+            }
+        };
+
+        let query = compile(text, field.is_some(), whole_word, Syntax::default());
+        let unfielded = field.as_ref().map(|name| {
+            let text = format!("{}:{}", name, text);
+            Arc::new(CompiledQuery::new(&text, whole_word, Syntax::default()))
+        });
+
+        SearchPattern {
+            text: text.to_string(),
+            inverse: inverse,
+            whole_word: whole_word,
+            field: field,
+            field_fallback: false,
+            syntax: Syntax::default(),
+            query: Arc::new(query),
+            unfielded: unfielded,
+        }
+    }
+
+    /// Reads the text with more than plain text, as regexes between slashes.
+    pub fn with_syntax(mut self, syntax: Syntax) -> SearchPattern {
+        if self.syntax != syntax {
+            self.syntax = syntax;
+            let query = compile(&self.text, self.field.is_some(), self.whole_word, syntax);
+            self.query = Arc::new(query);
+            self.unfielded = self.field.as_ref().map(|name| {
+                let text = format!("{}:{}", name, self.text);
+                Arc::new(CompiledQuery::new(&text, self.whole_word, syntax))
+            });
+        }
+        self
+    }
+
+    pub fn with_field_fallback(mut self, value: bool) -> SearchPattern {
+        self.field_fallback = value;
+        self
+    }
+
+    /// Matches regardless of case. Expressions can't, their terms being
+    /// regexes starting with `(?i)` instead.
+    pub fn ignoring_case(mut self) -> Result<SearchPattern, String> {
+        let regex = Syntax { regex: true, ..Syntax::default() };
+        if let Some(ref name) = self.field {
+            let term = format!("/(?i){}/", regex::escape(&format!("{}:{}", name, self.text)));
+            self.unfielded = Some(Arc::new(CompiledQuery::term(&term, self.whole_word, regex)));
+        }
+        if self.field.is_some() && Comparison::parse(&self.text).is_some() {
+            return Ok(self);
+        }
+        if self.query.is_expression() {
+            return Err(format!("Case can't be ignored by the expression `{}`, use `/(?i)...` \
+                                terms instead",
+                               self.text));
+        }
+
+        let term = if self.query.is_regex() {
+            self.text[1..self.text.len() - 1].to_string()
+        } else {
+            regex::escape(&self.text)
+        };
+        let term = format!("/(?i){}/", term);
+        self.query = Arc::new(CompiledQuery::term(&term, self.whole_word, regex));
+        Ok(self)
+    }
+
+    pub fn find<'a>(&self, haystack: &'a str) -> Vec<(usize, &'a str)> {
+        if self.inverse {
+            if self.occurs_in(haystack) {
+                vec![]
+            } else {
+                vec![(0, haystack)]
+            }
+        } else {
+            self.occurrences(haystack)
+        }
+    }
+
+    /// Why the text isn't a valid expression, when it's searched literally.
+    pub fn error(&self) -> Option<&ParseError> {
+        self.query.error()
+    }
+
+    /// Why the text, or part of it, is searched literally: an expression
+    /// that doesn't parse, or a regex that doesn't compile.
+    pub fn invalid(&self) -> Option<String> {
+        self.query.invalid()
+    }
+
+    pub fn has_captures(&self) -> bool {
+        !self.inverse && self.query.has_captures()
+    }
+
+    /// First group captured by each occurrence, within the searched field
+    /// when there's one.
+    pub fn captures<'a>(&self, haystack: &'a str) -> Vec<&'a str> {
+        if self.inverse {
+            return vec![];
+        }
+
+        match self.scope(haystack) {
+            Scope::Full(query) => query.captures(haystack),
+            Scope::Fields(found) => {
+                found.iter()
+                    .flat_map(|field| self.query.captures(&haystack[field.start..field.end]))
+                    .collect()
+            }
+        }
+    }
+
+    pub fn is_match(&self, haystack: &str) -> bool {
+        self.occurs_in(haystack) != self.inverse
+    }
+
+    fn occurs_in(&self, haystack: &str) -> bool {
+        match self.scope(haystack) {
+            Scope::Full(query) => query.is_found(haystack),
+            Scope::Fields(found) => {
+                found.iter().any(|field| self.query.is_found(&haystack[field.start..field.end]))
+            }
+        }
+    }
+
+    fn occurrences<'a>(&self, haystack: &'a str) -> Vec<(usize, &'a str)> {
+        let found = match self.scope(haystack) {
+            Scope::Full(query) => return query.occurrences(haystack),
+            Scope::Fields(found) => found,
+        };
+
+        let mut result = vec![];
+        for field in &found {
+            let value = &haystack[field.start..field.end];
+            for (offset, text) in self.query.occurrences(value) {
+                result.push((field.start + offset, text));
+            }
+        }
+        result
+    }
+
+    // Lines holding the field are searched within its values. The others are
+    // searched for the whole text, or for the value alone in lines without
+    // any field when falling back on them.
+    fn scope(&self, haystack: &str) -> Scope {
+        let (name, unfielded) = match (&self.field, &self.unfielded) {
+            (&Some(ref name), &Some(ref unfielded)) => (name, unfielded),
+            _ => return Scope::Full(&self.query),
+        };
+
+        let found = fields::find(haystack);
+        if found.is_empty() && self.field_fallback {
+            return Scope::Full(&self.query);
+        }
+
+        let found = found.into_iter().filter(|field| field.name == *name).collect::<Vec<_>>();
+        if found.is_empty() { Scope::Full(unfielded) } else { Scope::Fields(found) }
+    }
+}
+
+// Field values can be compared, as in `duration:>500ms`
+fn compile(text: &str, is_field: bool, whole_word: bool, syntax: Syntax) -> CompiledQuery {
+    match Comparison::parse(text) {
+        Some(comparison) if is_field => CompiledQuery::comparison(comparison),
+        _ => CompiledQuery::new(text, whole_word, syntax),
     }
 }
 
 pub struct LineCollection {
     pub entries: VecDeque<Line>,
     capacity: usize,
+    received: usize,
+    bytes: usize,
+    /// Lines received with terminal control sequences removed.
+    pub sanitized: usize,
+    /// Applied to lines as they're received. Lines stored before a change
+    /// are brought up to date once they're displayed.
+    pub highlights: Highlights,
+    /// Lines each highlight pattern was found on, as they're received.
+    pub pattern_stats: PatternStats,
+    eviction: Box<dyn EvictionPolicy>,
+    /// Marked lines, warned about before being evicted.
+    pub protected: ProtectedLines,
+    /// Unwraps lines of container runtimes, unless turned off.
+    unwrapper: Option<Unwrapper>,
+    /// Id of the partial line shown for each source still writing it.
+    partials: BTreeMap<usize, usize>,
+    /// Lines received from each source.
+    source_counts: HashMap<usize, usize>,
 }
 
 impl LineCollection {
@@ -73,31 +432,304 @@ impl LineCollection {
         LineCollection {
             entries: VecDeque::new(),
             capacity: capacity,
+            received: 0,
+            bytes: 0,
+            sanitized: 0,
+            highlights: Highlights::new(),
+            pattern_stats: PatternStats::new(),
+            eviction: Box::new(Fifo),
+            protected: ProtectedLines::new(Protection::Warn),
+            unwrapper: Some(Unwrapper::new()),
+            partials: BTreeMap::new(),
+            source_counts: HashMap::new(),
+        }
+    }
+
+    /// Copy of the stored lines that nothing is added to or evicted from.
+    /// Lines share their text with the ones copied.
+    pub fn frozen_copy(&self) -> LineCollection {
+        LineCollection {
+            entries: self.entries.clone(),
+            capacity: self.capacity,
+            received: self.received,
+            bytes: self.bytes,
+            sanitized: self.sanitized,
+            highlights: self.highlights.clone(),
+            pattern_stats: PatternStats::new(),
+            eviction: Box::new(Fifo),
+            protected: ProtectedLines::new(Protection::Ignore),
+            unwrapper: None,
+            partials: BTreeMap::new(),
+            source_counts: self.source_counts.clone(),
+        }
+    }
+
+    /// Collection of the marked lines set aside once evicted, to be shown
+    /// on their own.
+    pub fn preserved_copy(&self) -> LineCollection {
+        let mut lines = LineCollection::new(self.protected.preserved.len());
+        for line in &self.protected.preserved {
+            lines.bytes += line.content_without_ansi.len();
+            lines.entries.push_back(line.clone());
         }
+        lines.received = lines.entries.back().map_or(0, |line| line.id);
+        lines.highlights = self.highlights.clone();
+        lines.protected.policy = Protection::Ignore;
+        lines.unwrapper = None;
+        lines
+    }
+
+    /// Whether lines wrapped in JSON by container runtimes are unwrapped.
+    pub fn set_unwrap(&mut self, enabled: bool) {
+        self.unwrapper = if enabled { Some(Unwrapper::new()) } else { None };
+    }
+
+    /// Changes which lines are dropped once over capacity.
+    pub fn set_eviction(&mut self, eviction: Box<dyn EvictionPolicy>) {
+        self.eviction = eviction;
     }
 
     fn clear_excess(&mut self) {
-        while self.entries.len() > self.capacity {
-            self.entries.pop_front();
+        let excess = self.entries.len().saturating_sub(self.capacity);
+        if excess == 0 {
+            return;
+        }
+
+        let mut sources = vec![];
+        let evicted = self.eviction.evict(&mut self.entries, excess);
+        for line in &evicted {
+            self.bytes -= line.content_without_ansi.len();
+            if !sources.contains(&line.source) {
+                sources.push(line.source);
+            }
         }
+        self.protected.evicted(&evicted);
+        METRICS.record_dropped(excess);
+        debug_log!("eviction",
+                   "evicted={} stored={} sources={:?}",
+                   excess,
+                   self.entries.len(),
+                   sources);
     }
 
     pub fn len(&self) -> usize {
         self.entries.len()
     }
 
-    fn add(&mut self, item: String) {
-        self.entries.push_back(Line::new(item));
+    /// Protected lines newly among the next ones to be evicted, once the
+    /// lines incoming are added.
+    pub fn endangered(&mut self, incoming: usize) -> usize {
+        self.protected.endangered(&self.entries, self.capacity, incoming, &*self.eviction)
+    }
+
+    /// Total number of lines ever added, including the ones already evicted.
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    /// Number of stored entries that were received before the given position.
+    pub fn entries_before(&self, position: usize) -> usize {
+        // Lines aren't necessarily evicted oldest first, but stay ordered by id
+        let (front, back) = self.entries.as_slices();
+        let count = |slice: &[Line]| match slice.binary_search_by(|line| line.id.cmp(&position)) {
+            Ok(index) => index + 1,
+            Err(index) => index,
+        };
+
+        count(front) + count(back)
+    }
+
+    /// Shows the lines sources are in the middle of writing, each in place of
+    /// the one shown for its source before. Partial lines of the sources left
+    /// out are kept as they are. Returns the number of lines added.
+    pub fn show_partials(&mut self, partials: BTreeMap<usize, String>) -> usize {
+        let settled = self.partials
+            .keys()
+            .filter(|source| !partials.contains_key(source))
+            .cloned()
+            .collect::<Vec<_>>();
+        for source in settled {
+            let id = self.partials.remove(&source).unwrap();
+            if let Some(index) = self.position(id) {
+                self.entries[index].partial = false;
+            }
+        }
+
+        let mut added = 0;
+        for (source, text) in partials {
+            let text = Arc::<str>::from(format!("{}{}", text, PARTIAL_MARKER));
+            if self.amend(source, text.clone(), true) {
+                continue;
+            }
+
+            self.received += 1;
+            let mut line = Line::with_id(text, self.received);
+            line.source = source;
+            line.number = self.count_source(source);
+            line.partial = true;
+            self.bytes += line.content_without_ansi.len();
+            self.entries.push_back(line);
+            self.partials.insert(source, self.received);
+            added += 1;
+        }
+        self.clear_excess();
+
+        added
+    }
+
+    /// Id of the partial line shown for the source, if any.
+    pub fn partial_id(&self, source: usize) -> Option<usize> {
+        self.partials.get(&source).cloned()
+    }
+
+    /// Puts the complete line in place of the partial one shown for its
+    /// source. False when there's none, or it was evicted since.
+    pub fn complete_partial(&mut self, source: usize, content: Arc<str>) -> bool {
+        let amended = self.amend(source, content, false);
+        self.partials.remove(&source);
+
+        amended
+    }
+
+    fn amend(&mut self, source: usize, content: Arc<str>, partial: bool) -> bool {
+        let index = match self.partials.get(&source).and_then(|&id| self.position(id)) {
+            Some(value) => value,
+            None => return false,
+        };
+        if partial && *self.entries[index].content_without_ansi == *content {
+            return true;
+        }
+
+        // Only complete lines are unwrapped, as wrappers are split otherwise
+        let id = self.entries[index].id;
+        let mut line = if partial {
+            let mut line = Line::with_id(content, id);
+            line.source = source;
+            line
+        } else {
+            self.build(source, content, id)
+        };
+        line.partial = partial;
+        line.number = self.entries[index].number;
+        line.received_at = self.entries[index].received_at;
+        if !partial {
+            self.record_pattern_hits(&line);
+            if line.raw.is_some() {
+                self.sanitized += 1;
+            }
+        }
+
+        self.bytes -= self.entries[index].content_without_ansi.len();
+        self.bytes += line.content_without_ansi.len();
+        self.entries[index] = line;
+
+        true
+    }
+
+    /// Index of the stored line with the given id.
+    fn position(&self, id: usize) -> Option<usize> {
+        self.entries.iter().rposition(|line| line.id == id)
+    }
+
+    fn add(&mut self, source: usize, item: Arc<str>) {
+        self.received += 1;
+
+        let mut line = self.build(source, item, self.received);
+        line.number = self.count_source(source);
+        self.record_pattern_hits(&line);
+        if line.raw.is_some() {
+            self.sanitized += 1;
+        }
+        self.bytes += line.content_without_ansi.len();
+        self.entries.push_back(line);
+    }
+
+    /// Gives the line sampling left out its id and number, without keeping it.
+    fn skip(&mut self, source: usize) {
+        self.received += 1;
+        self.count_source(source);
+    }
+
+    /// Counts one more line of the source, returning its number.
+    fn count_source(&mut self, source: usize) -> usize {
+        let count = self.source_counts.entry(source).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    fn build(&mut self, source: usize, item: Arc<str>, id: usize) -> Line {
+        let unwrapped = self.unwrapper.as_mut().and_then(|unwrapper| unwrapper.unwrap(source, &item));
+        let mut line = match unwrapped {
+            Some((log, wrapper)) => {
+                let mut line = Line::with_id(log, id);
+                line.wrapper = Some(Arc::new(wrapper));
+                line
+            }
+            None => Line::with_id(item, id),
+        };
+        line.source = source;
+
+        line
+    }
+
+    fn record_pattern_hits(&mut self, line: &Line) {
+        if self.highlights.is_empty() {
+            return;
+        }
+
+        let hits = self.highlights.hits(&line.content_without_ansi);
+        if !hits.is_empty() {
+            self.pattern_stats.sync(&self.highlights);
+            self.pattern_stats.record(&hits, line.id, line.received_millis());
+        }
     }
 }
 
 impl Extend<String> for LineCollection {
     fn extend<T: IntoIterator<Item = String>>(&mut self, iter: T) {
-        for item in iter {
-            self.add(item);
+        self.extend(iter.into_iter().map(|item| (0, Arc::from(item))));
+    }
+}
+
+impl Extend<(usize, String)> for LineCollection {
+    fn extend<T: IntoIterator<Item = (usize, String)>>(&mut self, iter: T) {
+        self.extend(iter.into_iter().map(|(source, item)| (source, Arc::from(item))));
+    }
+}
+
+/// Lines along with the id of the file they were read from, their text
+/// being stored as it is.
+impl Extend<(usize, Arc<str>)> for LineCollection {
+    fn extend<T: IntoIterator<Item = (usize, Arc<str>)>>(&mut self, iter: T) {
+        self.extend(iter.into_iter().map(|(source, item)| (source, Some(item))));
+    }
+}
+
+/// Lines left out by sampling come without text. They still take up an id
+/// and a number, so that those of the lines kept are the same as unsampled.
+impl Extend<(usize, Option<Arc<str>>)> for LineCollection {
+    fn extend<T: IntoIterator<Item = (usize, Option<Arc<str>>)>>(&mut self, iter: T) {
+        let mut added = 0;
+
+        for (source, item) in iter {
+            match item {
+                Some(item) => {
+                    self.add(source, item);
+                    added += 1;
+                }
+                None => self.skip(source),
+            }
         }
 
         self.clear_excess();
+
+        METRICS.record_batch(added);
+        METRICS.set_buffer_bytes(self.bytes);
+        debug_log!("ingestion",
+                   "lines={} stored={} bytes={}",
+                   added,
+                   self.entries.len(),
+                   self.bytes);
     }
 }
 
@@ -184,12 +816,18 @@ impl<'a, I> Iterator for ParserState<'a, I>
     type Item = I::Item;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.parser.constraints.is_empty() {
-            self.handle_empty()
-        } else if self.parser.constraints == vec![Constraint::Content] {
-            self.handle_content()
-        } else {
-            self.handle_boundaries()
+        loop {
+            let line = if self.parser.constraints.is_empty() {
+                self.handle_empty()
+            } else if self.parser.constraints == vec![Constraint::Content] {
+                self.handle_content()
+            } else {
+                self.handle_boundaries()
+            }?;
+
+            if self.parser.filter.allows(&line.content_without_ansi) {
+                return Some(line);
+            }
         }
     }
 }
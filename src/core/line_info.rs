@@ -0,0 +1,119 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use regex::Regex;
+use rustc_serialize::json::Json;
+use time::{self, Timespec};
+
+use core::line::Line;
+use core::filter::Filter;
+use core::highlights::Highlights;
+use core::fields;
+use core::timestamp::DAY;
+
+lazy_static! {
+    pub static ref LEVEL: Regex = Regex::new(
+        r"(?i)\b(fatal|critical|crit|error|err|warning|warn|notice|info|debug|trace)\b"
+    ).unwrap();
+}
+
+/// Severity named by the first level keyword found in the line.
+pub fn detect_level(text: &str) -> Option<&'static str> {
+    LEVEL.captures(text).map(|captures| {
+        match captures[1].to_lowercase().as_str() {
+            "fatal" | "critical" | "crit" => "FATAL",
+            "error" | "err" => "ERROR",
+            "warning" | "warn" => "WARN",
+            "notice" | "info" => "INFO",
+            "debug" => "DEBUG",
+            _ => "TRACE",
+        }
+    })
+}
+
+/// Fields of a JSON object line, or its `key=value` pairs otherwise. Nested
+/// values are kept as JSON.
+pub fn extract_fields(text: &str) -> Vec<(String, String)> {
+    let trimmed = text.trim();
+
+    if trimmed.starts_with('{') {
+        if let Ok(Json::Object(object)) = Json::from_str(trimmed) {
+            return object.into_iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        Json::String(value) => value,
+                        other => other.to_string(),
+                    };
+                    (key, value)
+                })
+                .collect();
+        }
+    }
+
+    fields::find(text)
+        .into_iter()
+        .map(|field| (field.name, text[field.start..field.end].to_string()))
+        .collect()
+}
+
+fn format_millis(value: i64, format: &str) -> String {
+    let spec = Timespec::new(value.div_euclid(1_000), (value.rem_euclid(1_000) * 1_000_000) as i32);
+    time::strftime(format, &time::at_utc(spec)).unwrap()
+}
+
+/// Everything known about a line, as labelled values.
+pub fn describe(line: &Line,
+                source_name: &str,
+                filters: &[&Filter],
+                highlights: &Highlights)
+                -> Vec<(&'static str, String)> {
+    let text = &*line.content_without_ansi;
+    let mut rows = vec![];
+
+    rows.push(("source", source_name.to_string()));
+    rows.push(("line", line.id.to_string()));
+    rows.push(("arrived", time::strftime("%H:%M:%S", &time::at(line.received_at)).unwrap()));
+    if let Some(value) = line.timestamp() {
+        let format = if value >= DAY { "%Y-%m-%d %H:%M:%S" } else { "%H:%M:%S" };
+        rows.push(("timestamp", format_millis(value, format)));
+    }
+    if let Some(level) = detect_level(text) {
+        rows.push(("level", level.to_string()));
+    }
+    rows.push(("bytes", text.len().to_string()));
+
+    for filter in filters {
+        if filter.content.as_ref().map_or(false, |regex| regex.is_match(text)) {
+            rows.push(("filter", filter.name.clone()));
+        }
+    }
+
+    let sources = highlights.sources();
+    let mut matched = highlights.spans(text).iter().map(|span| span.pattern).collect::<Vec<_>>();
+    matched.sort();
+    matched.dedup();
+    for pattern in matched {
+        rows.push(("highlight", sources[pattern].to_string()));
+    }
+
+    for (key, value) in extract_fields(text) {
+        rows.push(("field", format!("{}={}", key, value)));
+    }
+
+    rows
+}
@@ -0,0 +1,241 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use regex::{Captures, Regex};
+use rustc_serialize::{Decodable, Decoder};
+
+use core::discovery::GlobPattern;
+
+pub const FORMATS: &'static [&'static str] = &["postgres-slow", "mysql-slow"];
+
+lazy_static! {
+    static ref POSTGRES_DURATION: Regex = Regex::new(r"duration: (\d+(?:\.\d+)?) ms").unwrap();
+    /// Messages adding to the previous one of the same statement.
+    static ref POSTGRES_DETAIL: Regex =
+        Regex::new(r"^\S.*?\b(?:DETAIL|HINT|CONTEXT|STATEMENT|QUERY|LOCATION):  ").unwrap();
+    static ref MYSQL_QUERY_TIME: Regex = Regex::new(r"Query_time: (\d+(?:\.\d+)?)").unwrap();
+}
+
+/// How the lines of a source are grouped into records, each of them shown
+/// as a single line.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LogFormat {
+    /// Statements logged by `log_min_duration_statement`, continued on lines
+    /// indented with a tab.
+    PostgresSlow,
+    /// Entries of the MySQL slow query log, starting with `# Time:` or
+    /// `# User@Host:` comments followed by the statement.
+    MysqlSlow,
+}
+
+impl LogFormat {
+    pub fn parse(name: &str) -> Option<LogFormat> {
+        match name {
+            "postgres-slow" => Some(LogFormat::PostgresSlow),
+            "mysql-slow" => Some(LogFormat::MysqlSlow),
+            _ => None,
+        }
+    }
+
+    /// Whether the line starts a record, given the lines of the one being read.
+    pub fn starts_record(&self, line: &str, pending: &[Arc<str>]) -> bool {
+        match *self {
+            LogFormat::PostgresSlow => {
+                !line.starts_with(char::is_whitespace) && !POSTGRES_DETAIL.is_match(line)
+            }
+            LogFormat::MysqlSlow => {
+                let after_time = pending.len() == 1 && pending[0].starts_with("# Time:");
+                line.starts_with("# Time:") || line.starts_with("# User@Host:") && !after_time
+            }
+        }
+    }
+
+    /// The lines of a record joined by spaces, the time taken by the statement
+    /// being written as a `duration=<milliseconds>ms` field.
+    pub fn fold(&self, lines: &[Arc<str>]) -> String {
+        let text = lines.iter()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        match *self {
+            LogFormat::PostgresSlow => {
+                POSTGRES_DURATION.replace(&text, "duration=${1}ms").into_owned()
+            }
+            LogFormat::MysqlSlow => {
+                MYSQL_QUERY_TIME.replace(&text, |captures: &Captures| {
+                        let seconds = captures[1].parse::<f64>().unwrap();
+                        format!("duration={}ms", (seconds * 1_000_000.0).round() / 1_000.0)
+                    })
+                    .into_owned()
+            }
+        }
+    }
+}
+
+/// Format of the files whose name matches, from the `[[sources]]` of the
+/// config. The name may contain wildcards, as in `postgresql-*.log`.
+#[derive(Clone, Debug)]
+pub struct SourceFormat {
+    pub file: String,
+    pub format: LogFormat,
+}
+
+impl SourceFormat {
+    pub fn applies_to(&self, path: &str) -> bool {
+        GlobPattern::parse(&self.file)
+            .ok()
+            .map_or(false, |pattern| pattern.matches(Path::new(path)))
+    }
+}
+
+impl Decodable for SourceFormat {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<SourceFormat, D::Error> {
+        decoder.read_struct("SourceFormat", 2, |d| {
+            let file = d.read_struct_field("file", 0, |d| d.read_str())?;
+            let name = d.read_struct_field("format", 1, |d| d.read_str())?;
+
+            if let Err(message) = GlobPattern::parse(&file) {
+                return Err(d.error(&format!("invalid source `file` - {}", message)));
+            }
+            let format = LogFormat::parse(&name).ok_or_else(|| {
+                d.error(&format!("unknown source format `{}`, expected {}",
+                                 name,
+                                 FORMATS.join(" or ")))
+            })?;
+
+            Ok(SourceFormat {
+                file: file,
+                format: format,
+            })
+        })
+    }
+}
+
+/// Groups the lines of sources having a format into records. A record is
+/// complete once the next one starts, or once its source has nothing more to
+/// read, since records are written at once.
+pub struct Folder {
+    sources: Vec<SourceFormat>,
+    /// Format of each source id, once its name is known.
+    formats: HashMap<usize, Option<LogFormat>>,
+    /// Lines of the record each source is in the middle of.
+    pending: HashMap<usize, Vec<Arc<str>>>,
+}
+
+impl Folder {
+    pub fn new(sources: Vec<SourceFormat>) -> Folder {
+        Folder {
+            sources: sources,
+            formats: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Lines read at once from the sources, with completed records in place
+    /// of their lines. Records of sources that had nothing more to read are
+    /// completed first.
+    pub fn fold<N>(&mut self, lines: Vec<(usize, Arc<str>)>, name: N) -> Vec<(usize, Arc<str>)>
+        where N: Fn(usize) -> Option<String>
+    {
+        if self.sources.is_empty() {
+            return lines;
+        }
+
+        let read = lines.iter().map(|&(source, _)| source).collect::<HashSet<_>>();
+        let mut folded = self.complete(|source| !read.contains(&source));
+
+        for (source, line) in lines {
+            let format = match self.format(source, &name) {
+                Some(value) => value,
+                None => {
+                    folded.push((source, line));
+                    continue;
+                }
+            };
+
+            let pending = self.pending.entry(source).or_insert_with(Vec::new);
+            let starts = format.starts_record(&line, pending);
+            if starts && !pending.is_empty() {
+                folded.push((source, Arc::from(format.fold(pending))));
+                pending.clear();
+            }
+
+            // Lines before the first record are kept as they are
+            if starts || !pending.is_empty() {
+                pending.push(line);
+            } else {
+                folded.push((source, line));
+            }
+        }
+
+        folded
+    }
+
+    /// Whether lines of the source are folded into records. Told before its
+    /// name is known as well, while there are formats to pick from.
+    pub fn folds(&self, source: usize) -> bool {
+        self.formats.get(&source).map_or(!self.sources.is_empty(), Option::is_some)
+    }
+
+    /// Records still being read, as they are.
+    pub fn finish(&mut self) -> Vec<(usize, Arc<str>)> {
+        self.complete(|_| true)
+    }
+
+    fn complete<F>(&mut self, predicate: F) -> Vec<(usize, Arc<str>)>
+        where F: Fn(usize) -> bool
+    {
+        let mut sources = self.pending
+            .iter()
+            .filter(|&(source, lines)| !lines.is_empty() && predicate(*source))
+            .map(|(source, _)| *source)
+            .collect::<Vec<_>>();
+        sources.sort();
+
+        sources.into_iter()
+            .filter_map(|source| {
+                let format = self.formats.get(&source).cloned().and_then(|value| value)?;
+                let lines = self.pending.remove(&source)?;
+                Some((source, Arc::from(format.fold(&lines))))
+            })
+            .collect()
+    }
+
+    fn format<N>(&mut self, source: usize, name: &N) -> Option<LogFormat>
+        where N: Fn(usize) -> Option<String>
+    {
+        if let Some(value) = self.formats.get(&source) {
+            return *value;
+        }
+
+        // Looked up again until the name of the source is known
+        let name = name(source)?;
+        let format = self.sources
+            .iter()
+            .find(|source| source.applies_to(&name))
+            .map(|source| source.format);
+        self.formats.insert(source, format);
+        format
+    }
+}
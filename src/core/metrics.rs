@@ -0,0 +1,223 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::process;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::sleep;
+use std::time::Duration;
+
+use rustc_serialize::json;
+use time;
+
+use core::runner::RUNNING;
+
+const METRICS_FILE_INTERVAL: u64 = 5_000;
+
+/// Seconds of ingestion rates kept around.
+pub const RATE_HISTORY_SECONDS: usize = 60;
+
+lazy_static! {
+    pub static ref METRICS: Metrics = Metrics::new();
+}
+
+/// Counters updated by the ingestion and render paths. Relaxed ordering is
+/// enough, as they're only ever read for reporting.
+pub struct Metrics {
+    lines_received: AtomicUsize,
+    lines_dropped: AtomicUsize,
+    max_batch_size: AtomicUsize,
+    renders: AtomicUsize,
+    last_search_micros: AtomicUsize,
+    buffer_bytes: AtomicUsize,
+    rates: Mutex<RateHistory>,
+}
+
+impl Metrics {
+    fn new() -> Metrics {
+        Metrics {
+            lines_received: AtomicUsize::new(0),
+            lines_dropped: AtomicUsize::new(0),
+            max_batch_size: AtomicUsize::new(0),
+            renders: AtomicUsize::new(0),
+            last_search_micros: AtomicUsize::new(0),
+            buffer_bytes: AtomicUsize::new(0),
+            rates: Mutex::new(RateHistory::new(RATE_HISTORY_SECONDS)),
+        }
+    }
+
+    pub fn record_batch(&self, size: usize) {
+        self.lines_received.fetch_add(size, Ordering::Relaxed);
+        self.rates.lock().unwrap().record(time::get_time().sec, size);
+
+        if size > self.max_batch_size.load(Ordering::Relaxed) {
+            self.max_batch_size.store(size, Ordering::Relaxed);
+        }
+    }
+
+    /// Lines evicted once the maximum amount of stored lines is reached.
+    pub fn record_dropped(&self, count: usize) {
+        self.lines_dropped.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub fn record_render(&self) {
+        self.renders.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_search(&self, nanoseconds: u64) {
+        self.last_search_micros.store((nanoseconds / 1_000) as usize, Ordering::Relaxed);
+    }
+
+    pub fn set_buffer_bytes(&self, bytes: usize) {
+        self.buffer_bytes.store(bytes, Ordering::Relaxed);
+    }
+
+    /// Lines received during each of the last seconds, up to `second`.
+    pub fn rates(&self, second: i64) -> Vec<usize> {
+        let mut rates = self.rates.lock().unwrap();
+        rates.advance(second);
+
+        rates.counts()
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            timestamp: time::get_time().sec,
+            lines_received: self.lines_received.load(Ordering::Relaxed),
+            lines_dropped: self.lines_dropped.load(Ordering::Relaxed),
+            max_batch_size: self.max_batch_size.load(Ordering::Relaxed),
+            renders: self.renders.load(Ordering::Relaxed),
+            last_search_micros: self.last_search_micros.load(Ordering::Relaxed),
+            buffer_bytes: self.buffer_bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Per second counts over a sliding window, oldest first.
+pub struct RateHistory {
+    counts: VecDeque<usize>,
+    second: i64,
+}
+
+impl RateHistory {
+    pub fn new(seconds: usize) -> RateHistory {
+        RateHistory {
+            counts: vec![0; seconds].into_iter().collect(),
+            second: 0,
+        }
+    }
+
+    pub fn record(&mut self, second: i64, count: usize) {
+        self.advance(second);
+
+        if let Some(current) = self.counts.back_mut() {
+            *current += count;
+        }
+    }
+
+    /// Moves the window forward, counting nothing for the skipped seconds.
+    pub fn advance(&mut self, second: i64) {
+        if second <= self.second {
+            return;
+        }
+
+        let steps = ((second - self.second) as usize).min(self.counts.len());
+        for _ in 0..steps {
+            self.counts.pop_front();
+            self.counts.push_back(0);
+        }
+        self.second = second;
+    }
+
+    pub fn counts(&self) -> Vec<usize> {
+        self.counts.iter().cloned().collect()
+    }
+}
+
+#[derive(RustcEncodable, Clone, Copy, Default, PartialEq, Debug)]
+pub struct MetricsSnapshot {
+    pub timestamp: i64,
+    pub lines_received: usize,
+    pub lines_dropped: usize,
+    pub max_batch_size: usize,
+    pub renders: usize,
+    pub last_search_micros: usize,
+    pub buffer_bytes: usize,
+}
+
+impl MetricsSnapshot {
+    /// Rows shown by the debug overlay. Rates are computed against a previous
+    /// snapshot taken `elapsed` milliseconds earlier.
+    pub fn describe(&self, previous: &MetricsSnapshot, elapsed: u64) -> Vec<String> {
+        let per_second = |current: usize, before: usize| {
+            if elapsed == 0 {
+                0.0
+            } else {
+                current.saturating_sub(before) as f64 * 1_000.0 / elapsed as f64
+            }
+        };
+
+        vec![format!("lines received  {:>10} ({:.1}/s)",
+                     self.lines_received,
+                     per_second(self.lines_received, previous.lines_received)),
+             format!("lines dropped   {:>10}", self.lines_dropped),
+             format!("max batch size  {:>10}", self.max_batch_size),
+             format!("renders         {:>10} ({:.1}/s)",
+                     self.renders,
+                     per_second(self.renders, previous.renders)),
+             format!("last search     {:>10} us", self.last_search_micros),
+             format!("buffer size     {:>10} bytes", self.buffer_bytes)]
+    }
+
+    pub fn to_json(&self) -> String {
+        json::encode(self).unwrap()
+    }
+}
+
+pub fn open_file(path: &str) -> File {
+    match OpenOptions::new().create(true).append(true).open(path) {
+        Ok(value) => value,
+        Err(message) => {
+            let message = format!("`{}` couldn't be opened - {}", path, message);
+            critical_quit!(message);
+        }
+    }
+}
+
+/// Appends a snapshot of the metrics to the file every few seconds.
+pub fn write_periodically(mut file: File) {
+    let mut elapsed = 0;
+    while running!() {
+        sleep(Duration::from_millis(100));
+        elapsed += 100;
+
+        if elapsed >= METRICS_FILE_INTERVAL {
+            elapsed = 0;
+            append_snapshot(&mut file);
+        }
+    }
+
+    append_snapshot(&mut file);
+}
+
+fn append_snapshot(file: &mut File) {
+    let _ = writeln!(file, "{}", METRICS.snapshot().to_json());
+}
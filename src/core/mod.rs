@@ -18,7 +18,41 @@
 
 pub mod flow;
 pub mod tail;
+pub mod discovery;
+pub mod replay;
 pub mod line;
 pub mod buffer;
+pub mod eviction;
 pub mod filter;
+pub mod rewrite;
 pub mod runner;
+pub mod wakeup;
+pub mod metrics;
+pub mod actions;
+pub mod highlights;
+pub mod pattern_stats;
+pub mod timestamp;
+pub mod export;
+pub mod error;
+pub mod range;
+pub mod regex_tester;
+pub mod line_info;
+pub mod fields;
+pub mod query;
+pub mod expression;
+pub mod capture_stats;
+pub mod debug_log;
+pub mod container;
+pub mod session;
+pub mod chunked;
+pub mod quit;
+pub mod alert;
+pub mod search_history;
+pub mod exclusion;
+pub mod sampling;
+pub mod health;
+pub mod count;
+pub mod comparison;
+pub mod log_format;
+pub mod undo;
+pub mod control;
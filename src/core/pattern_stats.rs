@@ -0,0 +1,173 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::VecDeque;
+
+use time;
+
+use core::highlights::Highlights;
+
+/// Hits older than this are no longer counted as recent, in milliseconds.
+pub const RECENT_WINDOW: i64 = 60_000;
+
+/// Milliseconds since the epoch, as hits are timed.
+pub fn now_millis() -> i64 {
+    let now = time::get_time();
+    now.sec * 1_000 + now.nsec as i64 / 1_000_000
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SortColumn {
+    Total,
+    Recent,
+    Last,
+}
+
+impl SortColumn {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            SortColumn::Total => "total hits",
+            SortColumn::Recent => "hits in the last minute",
+            SortColumn::Last => "most recent hit",
+        }
+    }
+}
+
+/// The most recent line a pattern was found on.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct LastHit {
+    pub line_id: usize,
+    /// When the line was received, in milliseconds since the epoch.
+    pub at: i64,
+}
+
+/// Lines a highlight pattern was found on since it was added or its counters
+/// were reset.
+#[derive(Clone, Debug)]
+pub struct PatternHits {
+    pub source: String,
+    pub total: usize,
+    pub last_hit: Option<LastHit>,
+    recent: VecDeque<i64>,
+}
+
+impl PatternHits {
+    fn new(source: &str) -> PatternHits {
+        PatternHits {
+            source: source.to_string(),
+            total: 0,
+            last_hit: None,
+            recent: VecDeque::new(),
+        }
+    }
+
+    fn record(&mut self, line_id: usize, at: i64) {
+        self.total += 1;
+        self.last_hit = Some(LastHit {
+            line_id: line_id,
+            at: at,
+        });
+        self.recent.push_back(at);
+
+        while self.recent.front().map_or(false, |value| *value <= at - RECENT_WINDOW) {
+            self.recent.pop_front();
+        }
+    }
+
+    /// Hits within the minute before `now`.
+    pub fn recent(&self, now: i64) -> usize {
+        self.recent.iter().filter(|value| **value > now - RECENT_WINDOW).count()
+    }
+
+    fn reset(&mut self) {
+        *self = PatternHits::new(&self.source);
+    }
+}
+
+/// Hit counters of each highlight pattern, updated as lines are received.
+pub struct PatternStats {
+    entries: Vec<PatternHits>,
+    version: usize,
+}
+
+impl PatternStats {
+    pub fn new() -> PatternStats {
+        PatternStats {
+            entries: vec![],
+            version: 0,
+        }
+    }
+
+    /// Follows the patterns as they're added or removed, keeping the counters
+    /// of the ones still present.
+    pub fn sync(&mut self, highlights: &Highlights) {
+        if self.version == highlights.version {
+            return;
+        }
+
+        let mut previous = self.entries.drain(..).collect::<Vec<_>>();
+        self.entries = highlights.sources()
+            .into_iter()
+            .map(|source| match previous.iter().position(|entry| entry.source == source) {
+                Some(index) => previous.swap_remove(index),
+                None => PatternHits::new(source),
+            })
+            .collect();
+        self.version = highlights.version;
+    }
+
+    /// Counts a line on which the patterns at the given indices were found.
+    pub fn record(&mut self, patterns: &[usize], line_id: usize, at: i64) {
+        for index in patterns {
+            if let Some(entry) = self.entries.get_mut(*index) {
+                entry.record(line_id, at);
+            }
+        }
+    }
+
+    pub fn reset(&mut self, source: &str) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.source == source) {
+            entry.reset();
+        }
+    }
+
+    pub fn reset_all(&mut self) {
+        for entry in &mut self.entries {
+            entry.reset();
+        }
+    }
+
+    /// Counters ordered by the column, highest first. Ties keep the order the
+    /// patterns were added in.
+    pub fn sorted(&self, column: SortColumn, now: i64) -> Vec<&PatternHits> {
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+
+        match column {
+            SortColumn::Total => entries.sort_by(|a, b| b.total.cmp(&a.total)),
+            SortColumn::Recent => entries.sort_by(|a, b| b.recent(now).cmp(&a.recent(now))),
+            SortColumn::Last => {
+                entries.sort_by(|a, b| {
+                    let at = |entry: &PatternHits| entry.last_hit.map(|hit| hit.at);
+                    at(b).cmp(&at(a))
+                })
+            }
+        }
+
+        entries
+    }
+}
@@ -0,0 +1,267 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::str::MatchIndices;
+use std::vec::IntoIter;
+use regex::{Regex, Matches};
+
+use core::expression::{self, Expression, ParseError};
+use core::comparison::{self, Comparison};
+
+#[derive(Debug)]
+enum Matcher {
+    Literal(String),
+    Regex(Regex),
+    Expression(Box<Expression>),
+    Comparison(Comparison),
+}
+
+/// What the text of a search is read with besides plain text, each part
+/// turned on from the search bar. Off by default, so that typing slashes
+/// finds them.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct Syntax {
+    /// Text between slashes is a regex.
+    pub regex: bool,
+    /// Terms are combined with `AND`, `OR` and `NOT`.
+    pub expressions: bool,
+}
+
+/// What a search matches text with, built once each time the query changes
+/// and shared by every line it's tried on.
+#[derive(Debug)]
+pub struct CompiledQuery {
+    matcher: Matcher,
+    whole_word: bool,
+    /// Why text using operators isn't a valid expression.
+    error: Option<ParseError>,
+    /// Why text between slashes isn't a valid regex.
+    regex_error: Option<String>,
+}
+
+impl CompiledQuery {
+    /// With expressions on, text using `AND`, `OR` or `NOT` is parsed as an
+    /// expression, and looked for literally while it isn't a valid one.
+    pub fn new(text: &str, whole_word: bool, syntax: Syntax) -> CompiledQuery {
+        match expression::parse(text, whole_word, syntax) {
+            Some(Ok(expression)) => {
+                CompiledQuery {
+                    matcher: Matcher::Expression(Box::new(expression)),
+                    whole_word: whole_word,
+                    error: None,
+                    regex_error: None,
+                }
+            }
+            Some(Err(error)) => {
+                CompiledQuery {
+                    matcher: Matcher::Literal(text.to_string()),
+                    whole_word: whole_word,
+                    error: Some(error),
+                    regex_error: None,
+                }
+            }
+            None => CompiledQuery::term(text, whole_word, syntax),
+        }
+    }
+
+    /// A single term. With regexes on, text between slashes is compiled as a
+    /// regex, and looked for literally while it isn't a valid one.
+    pub fn term(text: &str, whole_word: bool, syntax: Syntax) -> CompiledQuery {
+        let is_regex = syntax.regex && text.len() > 2 && text.starts_with('/') &&
+                       text.ends_with('/');
+        let regex = if is_regex { Some(Regex::new(&text[1..text.len() - 1])) } else { None };
+
+        let (matcher, regex_error) = match regex {
+            Some(Ok(regex)) => (Matcher::Regex(regex), None),
+            Some(Err(error)) => (Matcher::Literal(text.to_string()), Some(error.to_string())),
+            None => (Matcher::Literal(text.to_string()), None),
+        };
+
+        CompiledQuery {
+            matcher: matcher,
+            whole_word: whole_word,
+            error: None,
+            regex_error: regex_error,
+        }
+    }
+
+    /// Matches field values the comparison holds for.
+    pub fn comparison(comparison: Comparison) -> CompiledQuery {
+        CompiledQuery {
+            matcher: Matcher::Comparison(comparison),
+            whole_word: false,
+            error: None,
+            regex_error: None,
+        }
+    }
+
+    pub fn is_regex(&self) -> bool {
+        match self.matcher {
+            Matcher::Regex(_) => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_expression(&self) -> bool {
+        match self.matcher {
+            Matcher::Expression(_) => true,
+            _ => false,
+        }
+    }
+
+    pub fn error(&self) -> Option<&ParseError> {
+        self.error.as_ref()
+    }
+
+    /// Why the query, or a term of its expression, is looked for literally
+    /// rather than as typed.
+    pub fn invalid(&self) -> Option<String> {
+        if let Some(ref error) = self.error {
+            return Some(format!("Invalid expression: {}", error));
+        }
+        if let Some(ref error) = self.regex_error {
+            return Some(format!("Invalid regex: {}", error));
+        }
+
+        match self.matcher {
+            Matcher::Expression(ref expression) => expression.invalid(),
+            _ => None,
+        }
+    }
+
+    /// Whether a regex captures a group that can be extracted. Comparisons
+    /// capture the number compared.
+    pub fn has_captures(&self) -> bool {
+        match self.matcher {
+            Matcher::Regex(ref regex) => regex.captures_len() > 1,
+            Matcher::Comparison(_) => true,
+            _ => false,
+        }
+    }
+
+    /// First group captured by each occurrence of a regex.
+    pub fn captures<'a>(&self, haystack: &'a str) -> Vec<&'a str> {
+        let regex = match self.matcher {
+            Matcher::Regex(ref regex) => regex,
+            Matcher::Comparison(ref comparison) if comparison.holds(haystack) => {
+                return comparison::number_prefix(haystack).into_iter().collect();
+            }
+            _ => return vec![],
+        };
+
+        regex.captures_iter(haystack)
+            .filter(|captures| {
+                let found = captures.get(0).unwrap();
+                !self.whole_word ||
+                is_word_boundary(haystack, found.start(), found.as_str(), true) &&
+                is_word_boundary(haystack, found.end(), found.as_str(), false)
+            })
+            .filter_map(|captures| captures.get(1).map(|value| value.as_str()))
+            .collect()
+    }
+
+    pub fn occurrences<'a>(&self, haystack: &'a str) -> Vec<(usize, &'a str)> {
+        self.iter(haystack).collect()
+    }
+
+    /// Stops at the first occurrence, without collecting any.
+    pub fn is_found(&self, haystack: &str) -> bool {
+        if let Matcher::Expression(ref expression) = self.matcher {
+            return expression.is_match(haystack);
+        }
+
+        self.iter(haystack).next().is_some()
+    }
+
+    fn iter<'q, 'a>(&'q self, haystack: &'a str) -> Occurrences<'q, 'a> {
+        let found = match self.matcher {
+            Matcher::Literal(ref text) => Found::Literal(haystack.match_indices(text.as_str())),
+            Matcher::Regex(ref regex) => Found::Regex(regex.find_iter(haystack)),
+            Matcher::Expression(ref expression) => {
+                Found::Spans(expression.occurrences(haystack).into_iter())
+            }
+            Matcher::Comparison(ref comparison) => {
+                let found = if comparison.holds(haystack) { vec![(0, haystack)] } else { vec![] };
+                Found::Spans(found.into_iter())
+            }
+        };
+
+        Occurrences {
+            found: found,
+            haystack: haystack,
+            whole_word: self.whole_word,
+        }
+    }
+}
+
+enum Found<'q, 'a> {
+    Literal(MatchIndices<'a, &'q str>),
+    Regex(Matches<'q, 'a>),
+    /// Found by the terms of an expression, each already a whole word if needed,
+    /// or values a comparison holds for.
+    Spans(IntoIter<(usize, &'a str)>),
+}
+
+struct Occurrences<'q, 'a> {
+    found: Found<'q, 'a>,
+    haystack: &'a str,
+    whole_word: bool,
+}
+
+impl<'q, 'a> Iterator for Occurrences<'q, 'a> {
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<(usize, &'a str)> {
+        loop {
+            let (offset, value) = match self.found {
+                Found::Literal(ref mut found) => found.next()?,
+                Found::Regex(ref mut found) => {
+                    let found = found.next()?;
+                    // Only empty lines are found by matching nothing, as with `/^$/`
+                    if found.start() == found.end() && !self.haystack.is_empty() {
+                        continue;
+                    }
+                    (found.start(), found.as_str())
+                }
+                Found::Spans(ref mut found) => return found.next(),
+            };
+
+            if !self.whole_word ||
+               is_word_boundary(self.haystack, offset, value, true) &&
+               is_word_boundary(self.haystack, offset + value.len(), value, false) {
+                return Some((offset, value));
+            }
+        }
+    }
+}
+
+fn is_word_character(value: char) -> bool {
+    value.is_alphanumeric() || value == '_'
+}
+
+// Like `\b`, a boundary is only required next to word characters of the
+// searched text, so that searching for `-v` still finds `a -v`.
+fn is_word_boundary(haystack: &str, offset: usize, text: &str, at_start: bool) -> bool {
+    let (inner, outer) = if at_start {
+        (text.chars().next(), haystack[..offset].chars().next_back())
+    } else {
+        (text.chars().next_back(), haystack[offset..].chars().next())
+    };
+
+    !inner.map_or(false, is_word_character) || !outer.map_or(false, is_word_character)
+}
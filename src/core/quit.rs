@@ -0,0 +1,74 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/// Milliseconds a request to quit waits to be confirmed.
+pub const CONFIRM_TIMEOUT: u64 = 3_000;
+
+#[derive(PartialEq, Debug)]
+pub enum Decision {
+    Quit,
+    /// The request has to be repeated, for the given reason.
+    Confirm(String),
+}
+
+/// When to quit. A request quits right away unless something would be
+/// lost, in which case it's repeated to confirm it. Forced requests never
+/// wait.
+#[derive(Default)]
+pub struct QuitGuard {
+    requested_at: Option<u64>,
+}
+
+impl QuitGuard {
+    /// Decides on a request to quit, given what quitting would lose.
+    pub fn request(&mut self, force: bool, unsaved: Option<&str>, now: u64) -> Decision {
+        if force || self.is_pending(now) {
+            return Decision::Quit;
+        }
+
+        match unsaved {
+            Some(reason) => {
+                self.requested_at = Some(now);
+                Decision::Confirm(format!("{}, press q again to quit", reason))
+            }
+            None => Decision::Quit,
+        }
+    }
+
+    /// Waits for a confirmation whatever there is to lose, as the first
+    /// Ctrl-C does.
+    pub fn hold(&mut self, now: u64) {
+        self.requested_at = Some(now);
+    }
+
+    pub fn is_pending(&self, now: u64) -> bool {
+        self.requested_at.map_or(false, |at| now.saturating_sub(at) <= CONFIRM_TIMEOUT)
+    }
+
+    /// Forgets a request left unconfirmed for too long, returning whether
+    /// there was one.
+    pub fn expire(&mut self, now: u64) -> bool {
+        match self.requested_at {
+            Some(_) if !self.is_pending(now) => {
+                self.requested_at = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,196 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process::{Command, Stdio};
+
+use time;
+
+use core::export::ExportFormat;
+use core::highlights::Highlights;
+use core::line::{Line, SearchPattern};
+use core::timestamp::format_duration;
+
+/// Lines between two marked ones, both included. Lines are referred to by
+/// id, so that the range stays put as new lines arrive.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl LineRange {
+    /// Lines may be marked in either order.
+    pub fn new(first: usize, second: usize) -> LineRange {
+        LineRange {
+            start: first.min(second),
+            end: first.max(second),
+        }
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        id >= self.start && id <= self.end
+    }
+
+    /// What's left of the range once lines older than `oldest` are dropped,
+    /// or `None` when all of them are.
+    pub fn clamp(&self, oldest: usize) -> Option<LineRange> {
+        if self.end < oldest {
+            None
+        } else {
+            Some(LineRange::new(self.start.max(oldest), self.end))
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!("lines {} to {}", self.start, self.end)
+    }
+}
+
+pub enum RangeAction {
+    Save,
+    CountMatches,
+    Stats,
+    Pipe(String),
+}
+
+impl RangeAction {
+    pub fn parse(text: &str) -> Result<RangeAction, String> {
+        let text = text.trim();
+
+        match text {
+            "s" | "save" => Ok(RangeAction::Save),
+            "c" | "count" => Ok(RangeAction::CountMatches),
+            "i" | "info" => Ok(RangeAction::Stats),
+            _ if text.starts_with('|') && text.len() > 1 => {
+                Ok(RangeAction::Pipe(text[1..].trim().to_string()))
+            }
+            _ => Err(format!("Unknown range action `{}`, expected s, c, i or |command", text)),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub struct RangeStats {
+    pub lines: usize,
+    pub bytes: usize,
+    /// Between the first and last line, when both could be timed.
+    pub duration: Option<i64>,
+}
+
+impl RangeStats {
+    /// Lines are timed by their timestamps, falling back to when they were
+    /// received when none of them has one.
+    pub fn new(lines: &[Line]) -> RangeStats {
+        let parsed = lines.iter().filter_map(Line::timestamp);
+        let (first, last) = parsed.fold((None, None), |(first, _), value| {
+            (first.or(Some(value)), Some(value))
+        });
+
+        let duration = match (first, last, lines.first(), lines.last()) {
+            (Some(first), Some(last), _, _) => Some(last - first),
+            (None, None, Some(first), Some(last)) => {
+                Some(last.received_millis() - first.received_millis())
+            }
+            _ => None,
+        };
+
+        RangeStats {
+            lines: lines.len(),
+            bytes: lines.iter().map(|line| line.content_without_ansi.len() + 1).sum(),
+            duration: duration,
+        }
+    }
+
+    pub fn lines_per_second(&self) -> Option<f64> {
+        match self.duration {
+            Some(value) if value > 0 => Some(self.lines as f64 * 1_000.0 / value as f64),
+            _ => None,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let mut parts = vec![format!("{} lines", self.lines), format!("{} bytes", self.bytes)];
+
+        if let Some(value) = self.duration {
+            parts.push(format!("over {}", format_duration(value)));
+        }
+        if let Some(value) = self.lines_per_second() {
+            parts.push(format!("{:.1} lines/sec", value));
+        }
+
+        parts.join(", ")
+    }
+}
+
+/// Occurrences of the pattern along with the number of lines holding them.
+pub fn count_matches(lines: &[Line], pattern: &SearchPattern) -> (usize, usize) {
+    lines.iter().fold((0, 0), |(matches, matching_lines), line| {
+        match pattern.find(&line.content_without_ansi).len() {
+            0 => (matches, matching_lines),
+            count => (matches + count, matching_lines + 1),
+        }
+    })
+}
+
+pub fn write_lines<W: Write>(out: &mut W, lines: &[Line]) -> io::Result<()> {
+    for line in lines {
+        writeln!(out, "{}", line.content_without_ansi)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the lines to a timestamped file within the current directory,
+/// painted with the highlights unless plain.
+pub fn save(lines: &[Line], format: ExportFormat, highlights: &Highlights) -> io::Result<String> {
+    let timestamp = time::strftime("%Y%m%d-%H%M%S", &time::now()).unwrap();
+    let path = format!("flow-range-{}.{}", timestamp, format.extension());
+
+    let mut file = BufWriter::new(File::create(&path)?);
+    format.write_header(&mut file)?;
+    for line in lines {
+        writeln!(file, "{}", format.render(line, highlights))?;
+    }
+    format.write_footer(&mut file)?;
+    file.flush()?;
+
+    Ok(path)
+}
+
+/// Runs the command attached to the terminal with the lines as its input,
+/// waiting for it to exit.
+pub fn pipe(command: &str, lines: &[Line]) -> io::Result<bool> {
+    let mut child = Command::new("sh").arg("-c").arg(command).stdin(Stdio::piped()).spawn()?;
+
+    // Dropped once written, so that the command sees the end of its input
+    if let Some(mut stdin) = child.stdin.take() {
+        match write_lines(&mut stdin, lines) {
+            // Commands may stop reading early, as `head` does
+            Err(ref error) if error.kind() == io::ErrorKind::BrokenPipe => {}
+            Err(error) => {
+                let _ = child.wait();
+                return Err(error);
+            }
+            Ok(()) => {}
+        }
+    }
+
+    child.wait().map(|status| status.success())
+}
@@ -0,0 +1,96 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use regex::Regex;
+
+use core::error::FlowError;
+
+/// Most recent lines the regex is tried on.
+pub const PREVIEW_LINES: usize = 20;
+
+pub fn compile(source: &str) -> Result<Regex, FlowError> {
+    if source.is_empty() {
+        return Err(FlowError::InvalidRegex(String::new(), "empty pattern".to_string()));
+    }
+
+    Regex::new(source)
+        .map_err(|error| FlowError::InvalidRegex(source.to_string(), error.to_string()))
+}
+
+/// Where a regex matches a line, as byte ranges. Capture groups are kept
+/// apart from whole matches so that they can be underlined.
+#[derive(Default, PartialEq, Debug)]
+pub struct Preview {
+    pub matches: Vec<(usize, usize)>,
+    pub groups: Vec<(usize, usize)>,
+}
+
+impl Preview {
+    pub fn new(regex: &Regex, text: &str) -> Preview {
+        let mut preview = Preview::default();
+
+        for captures in regex.captures_iter(text) {
+            for (i, group) in captures.iter().enumerate() {
+                let group = match group {
+                    Some(value) if value.start() != value.end() => value,
+                    _ => continue,
+                };
+
+                if i == 0 {
+                    preview.matches.push((group.start(), group.end()));
+                } else {
+                    preview.groups.push((group.start(), group.end()));
+                }
+            }
+        }
+
+        preview
+    }
+
+    pub fn in_match(&self, offset: usize) -> bool {
+        self.matches.iter().any(|&(start, end)| offset >= start && offset < end)
+    }
+
+    pub fn in_group(&self, offset: usize) -> bool {
+        self.groups.iter().any(|&(start, end)| offset >= start && offset < end)
+    }
+}
+
+/// What a tested regex is kept as.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RegexUse {
+    Search,
+    Filter,
+    Highlight,
+}
+
+impl RegexUse {
+    pub fn parse(text: &str) -> Option<RegexUse> {
+        match text.trim() {
+            "s" | "search" => Some(RegexUse::Search),
+            "f" | "filter" => Some(RegexUse::Filter),
+            "h" | "highlight" => Some(RegexUse::Highlight),
+            _ => None,
+        }
+    }
+}
+
+/// The regex between slashes, as searches and highlights take it.
+pub fn delimited(source: &str) -> String {
+    format!("/{}/", source)
+}
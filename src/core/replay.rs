@@ -0,0 +1,232 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::Ordering;
+use std::thread::sleep;
+use std::time::Duration;
+
+use time;
+
+use core::runner::{self, RUNNING};
+use core::health::{Health, HealthBoard, HealthChange};
+use core::timestamp::{TIMESTAMP, parse_timestamp};
+use core::error::FlowError;
+
+/// What the state of a replay is published as.
+const HEALTH_SOURCE: &'static str = "replay";
+const SPEEDS: [f64; 4] = [0.5, 1.0, 2.0, 4.0];
+const TICK_INTERVAL: u64 = 20;
+
+/// Plays back a static log as if it was being written, pacing lines by their
+/// timestamps. Logs without timestamps are played at a fixed rate.
+pub struct Replay {
+    lines: Vec<String>,
+    timestamps: Vec<Option<i64>>,
+    timed: bool,
+    rate: usize,
+    position: usize,
+    last_timestamp: Option<i64>,
+    last_label: Option<String>,
+    next_due: Option<u64>,
+    skip_threshold: Option<i64>,
+    speed: usize,
+    paused: bool,
+}
+
+impl Replay {
+    pub fn new(lines: Vec<String>, rate: usize) -> Replay {
+        let timestamps = lines.iter().map(|line| parse_timestamp(line)).collect::<Vec<_>>();
+
+        Replay {
+            timed: timestamps.iter().any(Option::is_some),
+            timestamps: timestamps,
+            lines: lines,
+            rate: if rate == 0 { 1 } else { rate },
+            position: 0,
+            last_timestamp: None,
+            last_label: None,
+            next_due: None,
+            skip_threshold: None,
+            speed: 1,
+            paused: false,
+        }
+    }
+
+    pub fn from_path(file_path: &str, rate: usize) -> Replay {
+        Replay::load(file_path, rate).unwrap_or_else(|error| {
+            critical_quit!(error);
+        })
+    }
+
+    pub fn load(file_path: &str, rate: usize) -> Result<Replay, FlowError> {
+        let mut contents = String::new();
+        let result = File::open(file_path).and_then(|mut file| file.read_to_string(&mut contents));
+
+        if let Err(error) = result {
+            return Err(FlowError::from_io(Path::new(file_path), &error));
+        }
+
+        Ok(Replay::new(contents.lines().map(|line| line.to_string()).collect(), rate))
+    }
+
+    /// Lines due by `now`, in milliseconds.
+    pub fn tick(&mut self, now: u64) -> Vec<String> {
+        let mut emitted = vec![];
+
+        if let Some(threshold) = self.skip_threshold.take() {
+            while self.position < self.lines.len() {
+                emitted.push(self.emit());
+
+                if self.gap() > threshold {
+                    break;
+                }
+            }
+
+            self.next_due = Some(now);
+        }
+
+        if self.paused {
+            return emitted;
+        }
+
+        let mut due = self.next_due.unwrap_or(now);
+        while self.position < self.lines.len() && due <= now {
+            emitted.push(self.emit());
+            due += self.delay();
+        }
+
+        self.next_due = Some(due);
+        emitted
+    }
+
+    /// Makes the next tick play every line up to the next pause longer than
+    /// `threshold` milliseconds, continuing right after it.
+    pub fn skip_gap(&mut self, threshold: i64) {
+        if self.timed {
+            self.skip_threshold = Some(threshold);
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.next_due = None;
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(SPEEDS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.lines.len()
+    }
+
+    /// Active until every line was played.
+    pub fn health(&self) -> HealthChange {
+        if self.is_finished() {
+            HealthChange::new(HEALTH_SOURCE, Health::Ended, "Replay finished".to_string())
+        } else {
+            HealthChange::new(HEALTH_SOURCE, Health::Active, "Replaying".to_string())
+        }
+    }
+
+    pub fn percentage(&self) -> usize {
+        if self.lines.is_empty() {
+            100
+        } else {
+            self.position * 100 / self.lines.len()
+        }
+    }
+
+    /// Replay position, as shown in the status bar.
+    pub fn status_text(&self) -> String {
+        let mut parts = vec!["replay".to_string()];
+
+        if let Some(ref label) = self.last_label {
+            parts.push(label.clone());
+        }
+        parts.push(format!("{}%", self.percentage()));
+        parts.push(format!("{}x", SPEEDS[self.speed]));
+
+        if self.paused {
+            parts.push("paused".to_string());
+        }
+
+        parts.join(" ")
+    }
+
+    fn emit(&mut self) -> String {
+        let line = self.lines[self.position].clone();
+
+        if let Some(value) = self.timestamps[self.position] {
+            self.last_timestamp = Some(value);
+            self.last_label = TIMESTAMP.find(&line).map(|found| found.as_str().to_string());
+        }
+        self.position += 1;
+
+        line
+    }
+
+    // Time between the last played timestamp and the next line. Lines without
+    // one, such as stack traces, follow the previous line right away.
+    fn gap(&self) -> i64 {
+        match (self.last_timestamp, self.timestamps.get(self.position)) {
+            (Some(last), Some(&Some(next))) => (next - last).max(0),
+            _ => 0,
+        }
+    }
+
+    fn delay(&self) -> u64 {
+        if self.position >= self.lines.len() {
+            return 0;
+        }
+
+        let speed = SPEEDS[self.speed];
+        if self.timed {
+            (self.gap() as f64 / speed) as u64
+        } else {
+            (1000.0 / self.rate as f64 / speed) as u64
+        }
+    }
+}
+
+/// Feeds the replayed lines to the callback until the application stops.
+pub fn play<F>(replay: &Arc<Mutex<Replay>>, callback: F)
+    where F: Fn(Vec<String>)
+{
+    let mut health = HealthBoard::default();
+
+    while running!() {
+        let now = time::precise_time_ns() / 1_000_000;
+        let (lines, state) = {
+            let mut replay = replay.lock().unwrap();
+            (replay.tick(now), replay.health())
+        };
+        callback(lines);
+        runner::publish_health(health.update(vec![state]));
+        sleep(Duration::from_millis(TICK_INTERVAL));
+    }
+}
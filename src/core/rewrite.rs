@@ -0,0 +1,119 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use regex::Regex;
+use rustc_serialize::{Decodable, Decoder};
+
+static DELIMITERS: [char; 5] = ['/', '#', '|', '!', '~'];
+
+/// Changes how a line is displayed, leaving the stored line untouched.
+#[derive(Clone)]
+pub struct RewriteRule {
+    pub regex: Regex,
+    pub template: String,
+    pub enabled: bool,
+}
+
+impl RewriteRule {
+    /// Parses rules typed as `/pattern/replacement/`, where the first
+    /// character acts as the delimiter.
+    pub fn parse(text: &str) -> Result<RewriteRule, String> {
+        let delimiter = match text.chars().next() {
+            Some(value) if !value.is_alphanumeric() && !value.is_whitespace() => value,
+            _ => return Err("expected /pattern/replacement/".to_string()),
+        };
+
+        let parts = text[delimiter.len_utf8()..].split(delimiter).collect::<Vec<_>>();
+        if parts.len() < 2 || parts.len() > 3 || parts.get(2).map_or(false, |rest| !rest.is_empty()) {
+            return Err("expected /pattern/replacement/".to_string());
+        }
+
+        match Regex::new(parts[0]) {
+            Ok(regex) => {
+                Ok(RewriteRule {
+                    regex: regex,
+                    template: parts[1].to_string(),
+                    enabled: true,
+                })
+            }
+            Err(error) => Err(error.to_string()),
+        }
+    }
+
+    /// The rule as it would be typed, using a delimiter missing from both parts.
+    pub fn source(&self) -> String {
+        let pattern = self.regex.as_str();
+        let delimiter = DELIMITERS.iter()
+            .find(|value| !pattern.contains(**value) && !self.template.contains(**value))
+            .unwrap_or(&DELIMITERS[0]);
+
+        format!("{d}{}{d}{}{d}", pattern, self.template, d = delimiter)
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        self.regex.replace_all(text, self.template.as_str()).into_owned()
+    }
+}
+
+/// Applies every enabled rule in order, returning `None` when the text is
+/// unchanged.
+pub fn rewrite(rules: &[RewriteRule], text: &str) -> Option<String> {
+    let result = rules.iter()
+        .filter(|rule| rule.enabled)
+        .fold(text.to_string(), |current, rule| rule.apply(&current));
+
+    if result != text { Some(result) } else { None }
+}
+
+/// Number of lines each rule applies to, as the rules are stacked.
+pub fn count_hits<'a, I>(rules: &[RewriteRule], texts: I) -> Vec<usize>
+    where I: IntoIterator<Item = &'a str>
+{
+    let mut hits = vec![0; rules.len()];
+
+    for text in texts {
+        let mut current = text.to_string();
+
+        for (i, rule) in rules.iter().enumerate().filter(|&(_, rule)| rule.enabled) {
+            if rule.regex.is_match(&current) {
+                hits[i] += 1;
+                current = rule.apply(&current);
+            }
+        }
+    }
+
+    hits
+}
+
+impl Decodable for RewriteRule {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<RewriteRule, D::Error> {
+        decoder.read_struct("RewriteRule", 2, |d| {
+            let pattern = d.read_struct_field("pattern", 0, |d| d.read_str())?;
+            let template = d.read_struct_field("replace", 1, |d| d.read_str())?;
+
+            let regex = Regex::new(&pattern)
+                .map_err(|e| d.error(&format!("invalid rewrite `pattern` regex - {}", e)))?;
+
+            Ok(RewriteRule {
+                regex: regex,
+                template: template,
+                enabled: true,
+            })
+        })
+    }
+}
@@ -16,50 +16,363 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::sync::{Arc, Mutex};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::thread;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::sync::{mpsc, Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::any::Any;
+use std::{env, panic, process, thread};
+
+use libc;
+use ncurses::endwin;
+use time;
 
 use ext::signal::{self, SIGINT, SIGQUIT};
 use utils::settings::Settings;
 use core::tail::Tail;
+use core::discovery::{Discovery, GlobPattern, SourceChange, MAX_SOURCES};
+use core::replay::{self, Replay};
+use core::session::{Recorder, Player};
+use core::error::{self, FlowError};
+use core::health::HealthChange;
+use core::log_format::Folder;
+use core::metrics;
+use core::debug_log;
 use core::flow::Flow;
+use core::wakeup::Wakeup;
+use core::control::ControlSocket;
+use core::quit::CONFIRM_TIMEOUT;
+use ui::input::disable_bracketed_paste;
+use ui::color;
+
+const UI_THREAD_NAME: &'static str = "ui";
+const READER_THREAD_NAME: &'static str = "reader";
+const METRICS_THREAD_NAME: &'static str = "metrics";
+const DEBUG_LOG_THREAD_NAME: &'static str = "debug-log";
+
+// Set to either `ui` or `reader` to verify that the terminal is restored
+// and the failure is reported when the matching thread panics.
+const DEBUG_PANIC_VARIABLE: &'static str = "FLOW_DEBUG_PANIC";
 
 lazy_static! {
     pub static ref RUNNING: AtomicBool = AtomicBool::new(true);
+    pub static ref WAKEUP: Wakeup = Wakeup::new();
+    static ref INTERRUPTED: AtomicBool = AtomicBool::new(false);
+    /// Milliseconds at which Ctrl-C was last pressed, 0 before that.
+    static ref INTERRUPTED_AT: AtomicU64 = AtomicU64::new(0);
+    static ref SOURCE_CHANGES: Mutex<Vec<SourceChange>> = Mutex::new(vec![]);
+    static ref HEALTH_CHANGES: Mutex<Vec<HealthChange>> = Mutex::new(vec![]);
+    static ref SOURCE_NAMES: Mutex<BTreeMap<usize, String>> = Mutex::new(BTreeMap::new());
+    static ref PARTIAL_LINES: Mutex<Option<BTreeMap<usize, String>>> = Mutex::new(None);
+}
+
+enum Source {
+    Tail(Tail),
+    Glob(Discovery),
+    Replay(Arc<Mutex<Replay>>),
+    /// A recorded session, whose lines are played back by the ui thread.
+    Session,
 }
 
 pub fn execute(settings: Settings) {
     catch_signal();
+    catch_panic();
 
-    let mut tail = Tail::new(settings.path_to_target_file.clone());
+    let no_color = env::var("NO_COLOR").ok();
+    let is_tty = unsafe { libc::isatty(libc::STDOUT_FILENO) == 1 };
+    color::set_enabled(color::use_colors(settings.color, no_color.as_ref().map(String::as_str), is_tty));
+
+    if settings.replay && GlobPattern::is_glob(&settings.path_to_target_file) {
+        critical_quit!("Only a single file can be replayed");
+    }
+
+    let player = if settings.replay {
+        Player::load(&settings.path_to_target_file).unwrap_or_else(|error| {
+            critical_quit!(error);
+        })
+    } else {
+        None
+    };
 
-    let lines = Arc::new(Mutex::new(tail.read_lines(settings.last_lines_count)));
+    let recorder = settings.record.as_ref().map(|path| {
+        Recorder::create(path, settings.hash_recorded_lines).unwrap_or_else(|error| {
+            critical_quit!(error);
+        })
+    });
+
+    let mut source = if player.is_some() {
+        Source::Session
+    } else if settings.replay {
+        let replay = Replay::from_path(&settings.path_to_target_file, settings.replay_rate);
+        Source::Replay(Arc::new(Mutex::new(replay)))
+    } else if GlobPattern::is_glob(&settings.path_to_target_file) {
+        let pattern = GlobPattern::parse(&settings.path_to_target_file).unwrap_or_else(|message| {
+            critical_quit!(message);
+        });
+        Source::Glob(Discovery::new(pattern, MAX_SOURCES))
+    } else {
+        Source::Tail(Tail::new(settings.path_to_target_file.clone()))
+    };
+
+    let initial_lines = match source {
+        Source::Tail(ref mut tail) => unsourced(tail.read_lines(settings.last_lines_count)),
+        Source::Glob(ref mut discovery) => {
+            record_source_names(discovery.source_names());
+            discovery.read_sourced_lines(settings.last_lines_count)
+        }
+        Source::Replay(_) | Source::Session => vec![],
+    };
+
+    let target = settings.path_to_target_file.clone();
+    let mut folder = Folder::new(settings.sources.clone());
+    let mut initial_lines = folder.fold(initial_lines, |id| format_name(&target, id));
+    initial_lines.extend(folder.finish());
+    let lines = Arc::new(Mutex::new(initial_lines));
+
+    let replay = match source {
+        Source::Replay(ref value) => Some(value.clone()),
+        Source::Tail(_) | Source::Glob(_) | Source::Session => None,
+    };
 
     let reader_lines = lines.clone();
-    let reader_thread = thread::spawn(move || {
-        tail.watch(|data| reader_lines.lock().unwrap().extend(data));
+    let reader_thread = thread::Builder::new()
+        .name(READER_THREAD_NAME.to_string())
+        .spawn(move || {
+            let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                trigger_debug_panic(READER_THREAD_NAME);
+
+                let folder = RefCell::new(folder);
+                let published = RefCell::new(BTreeMap::new());
+                let callback = |data: Vec<(usize, Arc<str>)>, partials: Vec<(usize, String)>| {
+                    let mut folder = folder.borrow_mut();
+                    let data = folder.fold(data, |id| format_name(&target, id));
+                    // Records are folded once whole, so their lines are only
+                    // shown once complete
+                    let partials = partials.into_iter()
+                        .filter(|&(source, _)| !folder.folds(source))
+                        .collect::<BTreeMap<_, _>>();
+                    let changed = *published.borrow() != partials;
+
+                    if !data.is_empty() || changed {
+                        // Handed over along with the lines read before them
+                        let mut lines = reader_lines.lock().unwrap();
+                        lines.extend(data);
+                        if changed {
+                            *PARTIAL_LINES.lock().unwrap() = Some(partials.clone());
+                            *published.borrow_mut() = partials;
+                        }
+                        drop(lines);
+                        WAKEUP.notify();
+                    }
+                };
+
+                match source {
+                    Source::Tail(ref mut tail) => {
+                        tail.watch(|data, partial| {
+                            let partials = partial.into_iter().map(|text| (0, text)).collect();
+                            callback(unsourced(data), partials)
+                        })
+                    }
+                    Source::Glob(ref mut discovery) => {
+                        discovery.watch(callback, |changes, names| {
+                            record_source_names(names);
+                            SOURCE_CHANGES.lock().unwrap().extend(changes);
+                            WAKEUP.notify();
+                        })
+                    }
+                    Source::Replay(ref replay) => {
+                        replay::play(replay, |data| {
+                            callback(unsourced(data.into_iter().map(Arc::from).collect()), vec![])
+                        })
+                    }
+                    Source::Session => {}
+                }
+            }));
+
+            if let Err(payload) = result {
+                error::report(FlowError::ReaderFailed(panic_message(&*payload)));
+            }
+        })
+        .unwrap();
+
+    let metrics_thread = settings.metrics_file.as_ref().map(|path| {
+        let file = metrics::open_file(path);
+
+        thread::Builder::new()
+            .name(METRICS_THREAD_NAME.to_string())
+            .spawn(move || metrics::write_periodically(file))
+            .unwrap()
     });
 
-    let consumer_lines = lines.clone();
-    let consumer_thread = thread::spawn(move || {
-        let mut flow = Flow::new(settings);
-        flow.init();
-        flow.process(consumer_lines);
-        flow.terminate();
+    let debug_log_thread = settings.debug_log.as_ref().map(|path| {
+        let mut file = metrics::open_file(path);
+        let receiver = debug_log::enable();
+
+        thread::Builder::new()
+            .name(DEBUG_LOG_THREAD_NAME.to_string())
+            .spawn(move || debug_log::write_until_stopped(receiver, &mut file))
+            .unwrap()
     });
 
+    // Commands are only taken once the ui thread handles them, and the
+    // socket is removed as this returns
+    let (requests, control_requests) = mpsc::channel();
+    let _control_socket = settings.control_socket.as_ref().and_then(|path| {
+        ControlSocket::bind(path, requests, || WAKEUP.notify())
+            .map_err(|error| {
+                error::report(FlowError::ControlSocket(path.clone(), error.to_string()))
+            })
+            .ok()
+    });
+
+    let consumer_lines = lines.clone();
+    let consumer_thread = thread::Builder::new()
+        .name(UI_THREAD_NAME.to_string())
+        .spawn(move || {
+            let mut flow = Flow::new(settings);
+            flow.attach_replay(replay);
+            flow.attach_session(recorder, player);
+            flow.attach_control(control_requests);
+            flow.init();
+            trigger_debug_panic(UI_THREAD_NAME);
+            flow.process(consumer_lines);
+            flow.terminate();
+        })
+        .unwrap();
+
     let _ = reader_thread.join();
     let _ = consumer_thread.join();
+    if let Some(thread) = metrics_thread {
+        let _ = thread.join();
+    }
+    if let Some(thread) = debug_log_thread {
+        let _ = thread.join();
+    }
+}
+
+/// Lines of a single source, which has the id 0.
+fn unsourced(lines: Vec<Arc<str>>) -> Vec<(usize, Arc<str>)> {
+    lines.into_iter().map(|line| (0, line)).collect()
+}
+
+// Name the format of a source is chosen by: the file followed, or the name
+// of the matching file a source id was given to
+fn format_name(target: &str, id: usize) -> Option<String> {
+    if GlobPattern::is_glob(target) {
+        source_name(id)
+    } else {
+        Some(target.to_string())
+    }
+}
+
+/// Files started or stopped being followed since last asked.
+pub fn take_source_changes() -> Vec<SourceChange> {
+    SOURCE_CHANGES.lock().unwrap().drain(..).collect()
+}
+
+/// Lines still being written, by source, when they changed since last
+/// asked. Taken while holding the lines read, which come before them.
+pub fn take_partial_lines() -> Option<BTreeMap<usize, String>> {
+    PARTIAL_LINES.lock().unwrap().take()
+}
+
+/// Hands sources changing state over to the ui thread.
+pub fn publish_health(changes: Vec<HealthChange>) {
+    if !changes.is_empty() {
+        HEALTH_CHANGES.lock().unwrap().extend(changes);
+        WAKEUP.notify();
+    }
+}
+
+/// Sources that changed state since last asked, oldest first.
+pub fn take_health_changes() -> Vec<HealthChange> {
+    HEALTH_CHANGES.lock().unwrap().drain(..).collect()
+}
+
+fn record_source_names(names: Vec<(usize, String)>) {
+    SOURCE_NAMES.lock().unwrap().extend(names);
+}
+
+/// File name of a followed file, by the id its lines were given.
+pub fn source_name(id: usize) -> Option<String> {
+    SOURCE_NAMES.lock().unwrap().get(&id).cloned()
+}
+
+fn catch_panic() {
+    let default_hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        match thread::current().name() {
+            // Reported by the ui thread, as printing now would garble the screen.
+            Some(READER_THREAD_NAME) => {}
+            Some(UI_THREAD_NAME) => {
+                disable_bracketed_paste();
+                endwin();
+                if env::var_os("RUST_BACKTRACE").is_none() {
+                    env::set_var("RUST_BACKTRACE", "1");
+                }
+                default_hook(info);
+                process::exit(101);
+            }
+            _ => default_hook(info),
+        }
+    }));
+}
+
+fn trigger_debug_panic(thread_name: &str) {
+    if env::var(DEBUG_PANIC_VARIABLE).ok().as_ref().map(String::as_str) == Some(thread_name) {
+        panic!("Panic triggered through {}", DEBUG_PANIC_VARIABLE);
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown error".to_string()
+    }
+}
+
+/// Whether Ctrl-C was pressed since last asked, for the ui thread to ask
+/// for confirmation. A second press quits from the signal handler.
+pub fn take_interrupt() -> bool {
+    INTERRUPTED.swap(false, Ordering::Relaxed)
+}
+
+/// Ends every loop, so that the threads finish and the terminal is restored
+/// once, as `execute` returns.
+pub fn stop() {
+    running!(false);
+    WAKEUP.notify();
 }
 
 fn catch_signal() {
     extern "C" fn callback(_: u32) {
-        running!(false);
+        stop();
     };
 
+    // A second Ctrl-C quits from here, even while the ui thread is too busy
+    // to pick the first one up
+    extern "C" fn interrupt(_: u32) {
+        let now = time::precise_time_ns() / 1_000_000;
+        let previous = INTERRUPTED_AT.swap(now, Ordering::Relaxed);
+        if previous > 0 && now.saturating_sub(previous) <= CONFIRM_TIMEOUT {
+            stop();
+        }
+
+        INTERRUPTED.store(true, Ordering::Relaxed);
+        WAKEUP.notify();
+    };
+
+    // Created upfront, as the signal handler can't allocate
+    let _ = &*WAKEUP;
+    let _ = &*INTERRUPTED;
+    let _ = &*INTERRUPTED_AT;
+
     unsafe {
-        signal::signal(SIGINT, callback);
+        signal::signal(SIGINT, interrupt);
         signal::signal(SIGQUIT, callback);
     }
 }
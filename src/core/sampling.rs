@@ -0,0 +1,140 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/// Lines a second above which sampling is offered.
+pub const OFFER_RATE: usize = 5_000;
+/// Lines a second shown while sampling, unless the ratio is fixed.
+pub const DEFAULT_TARGET_RATE: usize = 1_000;
+
+/// Shows one of every few lines of streams too fast to follow, the ratio
+/// following the rate lines arrive at unless fixed by the config.
+pub struct Sampler {
+    pub active: bool,
+    fixed: Option<usize>,
+    target_rate: usize,
+    every: usize,
+    counter: usize,
+    /// Lines left out since sampling was first turned on.
+    pub skipped: usize,
+    offered: bool,
+    second: i64,
+    second_count: usize,
+}
+
+impl Sampler {
+    pub fn new(fixed: Option<usize>, target_rate: usize) -> Sampler {
+        Sampler {
+            active: false,
+            fixed: fixed,
+            target_rate: target_rate.max(1),
+            every: fixed.unwrap_or(1).max(1),
+            counter: 0,
+            skipped: 0,
+            offered: false,
+            second: 0,
+            second_count: 0,
+        }
+    }
+
+    /// One line of every this many is shown.
+    pub fn every(&self) -> usize {
+        self.every
+    }
+
+    /// Counts the lines arriving during the given second. Returns the rate
+    /// of the previous second once it's over, the ratio being adapted to it.
+    pub fn arrived(&mut self, second: i64, count: usize) -> Option<usize> {
+        let rate = if second != self.second {
+            let rate = if second == self.second + 1 { self.second_count } else { 0 };
+            self.second = second;
+            self.second_count = 0;
+            Some(rate)
+        } else {
+            None
+        };
+        self.second_count += count;
+
+        if let (Some(rate), None) = (rate, self.fixed) {
+            self.every = ((rate + self.target_rate - 1) / self.target_rate).max(1);
+        }
+        rate
+    }
+
+    /// Whether sampling should be offered at the given rate, only the first
+    /// time it goes over `OFFER_RATE`.
+    pub fn should_offer(&mut self, rate: usize) -> bool {
+        if self.active || self.offered || rate <= OFFER_RATE {
+            return false;
+        }
+
+        self.offered = true;
+        true
+    }
+
+    /// Whether the next line is shown. Important lines always are, without
+    /// counting towards the ratio.
+    pub fn admit(&mut self, important: bool) -> bool {
+        if !self.active || important {
+            return true;
+        }
+
+        let shown = self.counter % self.every == 0;
+        self.counter = self.counter.wrapping_add(1);
+        if !shown {
+            self.skipped += 1;
+        }
+        shown
+    }
+
+    /// Turns sampling on or off, returning whether it's now on.
+    pub fn toggle(&mut self) -> bool {
+        self.active = !self.active;
+        self.offered = true;
+        self.counter = 0;
+        self.active
+    }
+
+    /// Shown for as long as sampling is on, and after that once lines were
+    /// skipped, since they're gone for good.
+    pub fn banner(&self) -> Option<String> {
+        if self.active {
+            Some(format!("sampling 1/{} — {} lines skipped",
+                         self.every,
+                         grouped(self.skipped)))
+        } else if self.skipped > 0 {
+            Some(format!("sampled — {} lines skipped earlier are gone", grouped(self.skipped)))
+        } else {
+            None
+        }
+    }
+}
+
+/// Digits grouped by thousands, as in `49,321`.
+pub fn grouped(value: usize) -> String {
+    let digits = value.to_string();
+    let mut result = String::new();
+
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            result.push(',');
+        }
+        result.push(digit);
+    }
+
+    result
+}
@@ -0,0 +1,313 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::env;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use unicode_width::UnicodeWidthStr;
+
+/// First line of history files keeping the options of each search, with
+/// line breaks and backslashes in the text escaped. Files without it hold one
+/// search per line, as written by readline.
+pub const VERSION_HEADER: &'static str = "#flow-history 3";
+
+/// Header of the files written before the text was escaped.
+const UNESCAPED_HEADER: &'static str = "#flow-history 2";
+
+const HISTORY_FILENAME: &'static str = ".flow_history";
+const MAX_HISTORY_LINES: usize = 1000;
+const MIN_HISTORY_LINE_WIDTH: usize = 2;
+
+/// Search bar options a search was made with, restored once it's recalled.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct SearchFlags {
+    pub filter: bool,
+    pub whole_word: bool,
+    pub field_fallback: bool,
+    pub regex: bool,
+    pub expressions: bool,
+}
+
+impl SearchFlags {
+    /// Names of the options that are on, as shown next to a recalled search.
+    pub fn badges(&self) -> Vec<&'static str> {
+        let mut badges = vec![];
+        if self.filter {
+            badges.push("filter");
+        }
+        if self.whole_word {
+            badges.push("word");
+        }
+        if self.field_fallback {
+            badges.push("unparsed");
+        }
+        if self.regex {
+            badges.push("regex");
+        }
+        if self.expressions {
+            badges.push("expr");
+        }
+
+        badges
+    }
+
+    fn encode(&self) -> String {
+        let flags = [(self.filter, 'm'),
+                     (self.whole_word, 'w'),
+                     (self.field_fallback, 'u'),
+                     (self.regex, 'r'),
+                     (self.expressions, 'e')];
+        let encoded = flags.iter().filter(|&&(on, _)| on).map(|&(_, c)| c).collect::<String>();
+
+        if encoded.is_empty() { "-".to_string() } else { encoded }
+    }
+
+    fn decode(value: &str) -> SearchFlags {
+        SearchFlags {
+            filter: value.contains('m'),
+            whole_word: value.contains('w'),
+            field_fallback: value.contains('u'),
+            regex: value.contains('r'),
+            expressions: value.contains('e'),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct HistoryEntry {
+    pub text: String,
+    pub flags: SearchFlags,
+}
+
+impl HistoryEntry {
+    pub fn new(text: &str, flags: SearchFlags) -> HistoryEntry {
+        HistoryEntry {
+            text: text.to_string(),
+            flags: flags,
+        }
+    }
+}
+
+/// Reads a history file, either versioned or one search per line.
+pub fn parse(contents: &str) -> Vec<HistoryEntry> {
+    let mut lines = contents.lines();
+    let escaped = match lines.next() {
+        Some(VERSION_HEADER) => true,
+        Some(UNESCAPED_HEADER) => false,
+        _ => {
+            return contents.lines()
+                .filter(|line| !line.is_empty())
+                .map(|line| HistoryEntry::new(line, SearchFlags::default()))
+                .collect()
+        }
+    };
+
+    lines.filter_map(|line| {
+            let mut parts = line.splitn(2, '\t');
+            match (parts.next(), parts.next()) {
+                (Some(flags), Some(text)) if !text.is_empty() => {
+                    let text = if escaped { unescape(text) } else { text.to_string() };
+                    Some(HistoryEntry::new(&text, SearchFlags::decode(flags)))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Writes the last `max` entries as a versioned history file.
+pub fn format(entries: &[HistoryEntry], max: usize) -> String {
+    let skipped = entries.len().saturating_sub(max);
+    let mut contents = format!("{}\n", VERSION_HEADER);
+
+    for entry in entries.iter().skip(skipped) {
+        contents.push_str(&format!("{}\t{}\n", entry.flags.encode(), escape(&entry.text)));
+    }
+
+    contents
+}
+
+/// Keeps a search on a single line of the history file.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+/// Searches made so far, brought back into the search input. Moving back
+/// through them keeps what was being typed, to return to past the newest.
+#[derive(Default)]
+pub struct SearchHistory {
+    entries: Vec<HistoryEntry>,
+    /// Index of the recalled entry, the number of entries when none is.
+    position: usize,
+    /// The input as it was before recalling started.
+    draft: Option<HistoryEntry>,
+    /// Text looked for by repeated history searches.
+    needle: Option<String>,
+}
+
+impl SearchHistory {
+    pub fn new(entries: Vec<HistoryEntry>) -> SearchHistory {
+        SearchHistory {
+            position: entries.len(),
+            entries: entries,
+            draft: None,
+            needle: None,
+        }
+    }
+
+    /// Reads the history kept in the user's home, if any.
+    pub fn load() -> SearchHistory {
+        let mut contents = String::new();
+        if let Ok(mut file) = File::open(history_file_path()) {
+            if file.read_to_string(&mut contents).is_err() {
+                contents.clear();
+            }
+        }
+
+        SearchHistory::new(parse(&contents))
+    }
+
+    pub fn save(&self) {
+        let contents = format(&self.entries, MAX_HISTORY_LINES);
+
+        if let Ok(mut file) = File::create(history_file_path()) {
+            let _ = file.write_all(contents.as_bytes());
+        }
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Adds the search, with the options it was made with. Made again with
+    /// other options, it's updated in place. Recalling starts over.
+    pub fn add(&mut self, text: &str, flags: SearchFlags) {
+        self.position = self.entries.len();
+        self.draft = None;
+        self.needle = None;
+
+        if text.width() <= MIN_HISTORY_LINE_WIDTH {
+            return;
+        }
+        if let Some(last) = self.entries.last_mut() {
+            if last.text == text {
+                last.flags = flags;
+                return;
+            }
+        }
+
+        self.entries.push(HistoryEntry::new(text, flags));
+        self.position = self.entries.len();
+    }
+
+    /// The search before the recalled one, keeping `current` to come back to.
+    pub fn previous(&mut self, current: &HistoryEntry) -> Option<HistoryEntry> {
+        self.needle = None;
+        if self.position == 0 {
+            return None;
+        }
+
+        self.keep_draft(current);
+        self.position -= 1;
+        self.entries.get(self.position).cloned()
+    }
+
+    /// The search after the recalled one, or what was typed before recalling.
+    pub fn next(&mut self) -> Option<HistoryEntry> {
+        self.needle = None;
+        if self.position >= self.entries.len() {
+            return None;
+        }
+
+        self.position += 1;
+        match self.entries.get(self.position) {
+            Some(entry) => Some(entry.clone()),
+            None => self.draft.take(),
+        }
+    }
+
+    /// The latest search before the recalled one containing the text of
+    /// `current`. Repeated, it looks further back for that same text.
+    pub fn search_back(&mut self, current: &HistoryEntry) -> Option<HistoryEntry> {
+        let needle = self.needle.clone().unwrap_or_else(|| current.text.clone());
+        let found = self.entries[..self.position]
+            .iter()
+            .rposition(|entry| entry.text.contains(&needle) && entry.text != current.text);
+
+        found.map(|position| {
+            self.keep_draft(current);
+            self.needle = Some(needle);
+            self.position = position;
+            self.entries[position].clone()
+        })
+    }
+
+    /// The text was edited, so the next history search looks for it instead.
+    pub fn stop_searching(&mut self) {
+        self.needle = None;
+    }
+
+    /// The recalled search, as long as `text` still shows it unchanged.
+    pub fn shown(&self, text: &str) -> Option<&HistoryEntry> {
+        self.entries.get(self.position).filter(|entry| entry.text == text)
+    }
+
+    fn keep_draft(&mut self, current: &HistoryEntry) {
+        if self.position == self.entries.len() {
+            self.draft = Some(current.clone());
+        }
+    }
+}
+
+fn history_file_path() -> PathBuf {
+    let mut path = env::home_dir().unwrap();
+    path.push(HISTORY_FILENAME);
+
+    path
+}
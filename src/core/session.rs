@@ -0,0 +1,283 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::char;
+use std::collections::VecDeque;
+use std::collections::hash_map::RandomState;
+use std::fs::File;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use regex::Regex;
+use rustc_serialize::json;
+use unicode_width::UnicodeWidthChar;
+
+use core::error::FlowError;
+use core::line_info::LEVEL;
+use core::timestamp::TIMESTAMP;
+use ui::input::Input;
+
+lazy_static! {
+    static ref ESCAPE: Regex = Regex::new(r"\x1b\[[0-?]*[ -/]*[@-~]|\x1b[@-_]").unwrap();
+}
+
+/// Marks the first line of a recording, telling it apart from a log file.
+const MAGIC: &'static str = "flow-session";
+const VERSION: u32 = 2;
+
+/// Written as the first line of a recording.
+#[derive(RustcEncodable, RustcDecodable, Clone, PartialEq, Debug)]
+pub struct Header {
+    pub format: String,
+    pub version: u32,
+    /// Terminal size the session was recorded in.
+    pub columns: i32,
+    pub rows: i32,
+    /// Lines were recorded hashed rather than as is.
+    pub hashed: bool,
+}
+
+/// What the event loop received, in milliseconds since recording started.
+#[derive(RustcEncodable, RustcDecodable, Clone, PartialEq, Debug)]
+pub enum Entry {
+    /// A key, a mouse event, a paste or a resize, along with the raw key code.
+    Input(u64, Input, i32),
+    /// A batch of lines, as ingested, along with the ids of their files.
+    Lines(u64, Vec<(usize, String)>),
+}
+
+/// Writes what the event loop receives to a file, one JSON entry per line.
+pub struct Recorder {
+    writer: BufWriter<File>,
+    started_at: u64,
+    hash_lines: bool,
+    /// Keys the hashes of the lines, different for every recording.
+    salt: RandomState,
+}
+
+impl Recorder {
+    pub fn create(path: &str, hash_lines: bool) -> Result<Recorder, FlowError> {
+        let file = File::create(path).map_err(|error| FlowError::from_io(Path::new(path), &error))?;
+
+        Ok(Recorder {
+            writer: BufWriter::new(file),
+            started_at: 0,
+            hash_lines: hash_lines,
+            salt: RandomState::new(),
+        })
+    }
+
+    /// Writes the header, timing entries from `now`, in milliseconds.
+    pub fn start(&mut self, columns: i32, rows: i32, now: u64) -> io::Result<()> {
+        let header = Header {
+            format: MAGIC.to_string(),
+            version: VERSION,
+            columns: columns,
+            rows: rows,
+            hashed: self.hash_lines,
+        };
+        self.started_at = now;
+
+        self.write_line(&json::encode(&header).unwrap())
+    }
+
+    pub fn record_input(&mut self, input: &Input, key: i32, now: u64) -> io::Result<()> {
+        match *input {
+            Input::None => Ok(()),
+            _ => {
+                let entry = Entry::Input(now - self.started_at, input.clone(), key);
+                self.write_line(&json::encode(&entry).unwrap())
+            }
+        }
+    }
+
+    pub fn record_lines(&mut self, lines: &[(usize, Arc<str>)], now: u64) -> io::Result<()> {
+        let lines = lines.iter()
+            .map(|&(source, ref text)| {
+                let text = if self.hash_lines {
+                    hash_line(text, &self.salt)
+                } else {
+                    text.to_string()
+                };
+                (source, text)
+            })
+            .collect();
+        let entry = Entry::Lines(now - self.started_at, lines);
+
+        self.write_line(&json::encode(&entry).unwrap())
+    }
+
+    // Flushed right away, so that a session that ends in a crash is kept.
+    fn write_line(&mut self, text: &str) -> io::Result<()> {
+        writeln!(self.writer, "{}", text)?;
+        self.writer.flush()
+    }
+}
+
+/// Replaces every word with characters derived from its hash, keeping spaces
+/// and punctuation, so that lines wrap and align as before. Letters keep their
+/// case, digits stay digits and wide characters stay wide. Escape sequences,
+/// the timestamp and the level of the line are kept as they are, for colors,
+/// gaps and levels to show when played back.
+///
+/// Repeated words are replaced alike, as long as the salt is the same.
+pub fn hash_line(text: &str, salt: &RandomState) -> String {
+    let mut kept = ESCAPE.find_iter(text)
+        .chain(TIMESTAMP.find(text))
+        .chain(LEVEL.find(text))
+        .map(|found| found.start()..found.end())
+        .collect::<Vec<_>>();
+
+    let mut result = String::with_capacity(text.len());
+    let mut word = String::new();
+
+    for (offset, value) in text.char_indices().chain(Some((text.len(), '\n'))) {
+        if value.is_alphanumeric() && !is_within(&kept, offset) {
+            word.push(value);
+            continue;
+        }
+
+        if !word.is_empty() {
+            result.push_str(&hash_word(&word, salt));
+            word.clear();
+        }
+        if offset < text.len() {
+            result.push(value);
+        }
+    }
+
+    result
+}
+
+fn is_within(ranges: &[Range<usize>], offset: usize) -> bool {
+    ranges.iter().any(|range| range.start <= offset && offset < range.end)
+}
+
+fn hash_word(word: &str, salt: &RandomState) -> String {
+    let mut hasher = salt.build_hasher();
+    word.hash(&mut hasher);
+    let mut hash = hasher.finish();
+
+    word.chars()
+        .map(|value| {
+            let replaced = alike(value, hash);
+            hash = hash.rotate_right(5) ^ 0x9e37_79b9_7f4a_7c15;
+            replaced
+        })
+        .collect()
+}
+
+// Picks a character of the same kind and width, wide ones among CJK ideographs
+fn alike(value: char, hash: u64) -> char {
+    let pick = |first: u8, count: u64| (first + (hash % count) as u8) as char;
+
+    if value.width() == Some(2) {
+        char::from_u32(0x4e00 + (hash % 0x5000) as u32).unwrap()
+    } else if value.is_numeric() {
+        pick(b'0', 10)
+    } else if value.is_uppercase() {
+        pick(b'A', 26)
+    } else {
+        pick(b'a', 26)
+    }
+}
+
+/// What the event loop gets next while playing a recording back.
+#[derive(PartialEq, Debug)]
+pub enum Step {
+    Input(Input, i32),
+    Lines(Vec<(usize, String)>),
+    /// Milliseconds until the next entry is due.
+    Wait(u64),
+    Finished,
+}
+
+/// Plays the entries of a recording back in the order they were recorded,
+/// each one no sooner than it was received.
+pub struct Player {
+    pub header: Header,
+    entries: VecDeque<Entry>,
+    started_at: Option<u64>,
+}
+
+impl Player {
+    pub fn new(header: Header, entries: Vec<Entry>) -> Player {
+        Player {
+            header: header,
+            entries: entries.into_iter().collect(),
+            started_at: None,
+        }
+    }
+
+    /// Reads a recording, returning `None` for files that aren't one, such as
+    /// logs to be replayed, whatever their encoding.
+    pub fn load(path: &str) -> Result<Option<Player>, FlowError> {
+        let to_error = |error: io::Error| FlowError::from_io(Path::new(path), &error);
+        let mut reader = BufReader::new(File::open(path).map_err(&to_error)?);
+
+        let mut first_line = vec![];
+        reader.read_until(b'\n', &mut first_line).map_err(&to_error)?;
+        let first_line = String::from_utf8(first_line).unwrap_or_default();
+        let header = match json::decode::<Header>(first_line.trim_end()) {
+            Ok(ref header) if header.format == MAGIC => header.clone(),
+            _ => return Ok(None),
+        };
+
+        if header.version != VERSION {
+            return Err(FlowError::Io(Path::new(path).to_path_buf(),
+                                     format!("recorded with format version {}", header.version)));
+        }
+
+        let mut entries = vec![];
+        for (index, line) in reader.lines().enumerate() {
+            let invalid = |error: String| {
+                FlowError::Io(Path::new(path).to_path_buf(),
+                              format!("line {} is not a valid entry - {}", index + 2, error))
+            };
+            let line = line.map_err(|error| invalid(error.to_string()))?;
+            entries.push(json::decode(&line).map_err(|error| invalid(error.to_string()))?);
+        }
+
+        Ok(Some(Player::new(header, entries)))
+    }
+
+    /// The next entry when due by `now`, in milliseconds. Timing starts with
+    /// the first call.
+    pub fn next(&mut self, now: u64) -> Step {
+        let started_at = *self.started_at.get_or_insert(now);
+        let elapsed = now - started_at;
+
+        let due = match self.entries.front() {
+            Some(&Entry::Input(at, _, _)) |
+            Some(&Entry::Lines(at, _)) => at,
+            None => return Step::Finished,
+        };
+
+        if due > elapsed {
+            return Step::Wait(due - elapsed);
+        }
+
+        match self.entries.pop_front().unwrap() {
+            Entry::Input(_, input, key) => Step::Input(input, key),
+            Entry::Lines(_, lines) => Step::Lines(lines),
+        }
+    }
+}
@@ -18,56 +18,205 @@
 
 use std::io::prelude::{Read, Seek};
 use std::fs::File;
-use std::io::SeekFrom;
+use std::io::{self, ErrorKind, SeekFrom};
+use std::path::PathBuf;
 use std::process;
+use std::str;
+use std::sync::Arc;
 use std::sync::atomic::Ordering;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::thread::sleep;
 
-use core::runner::RUNNING;
+use core::runner::{self, RUNNING};
+use core::error::{self, FlowError};
+use core::health::{Health, HealthBoard, HealthChange};
+
+/// Milliseconds a line being written waits for the rest of it before it's
+/// shown as it is.
+pub const PARTIAL_TIMEOUT: u64 = 250;
+/// Milliseconds without anything written after which the line being written
+/// is taken as complete, as the last line of a file without a newline is.
+pub const SETTLE_TIMEOUT: u64 = 5_000;
 
 pub struct Tail {
     file: File,
+    path: PathBuf,
     start_of_file_reached: bool,
+    /// Bytes read after the last newline, waiting for the rest of their line.
+    pending: Vec<u8>,
+    /// When bytes were last read.
+    read_at: Instant,
+    /// Whether the pending line was handed out as a partial one.
+    partial_shown: bool,
+    /// Why the last read failed, so that a failure is only reported once.
+    failure: Option<FlowError>,
+    error: Option<FlowError>,
 }
 
 impl Tail {
     pub fn new(file_path: String) -> Tail {
-        let file_handle = match File::open(&file_path) {
+        match Tail::open(&file_path) {
             Ok(value) => value,
-            Err(message) => {
-                let message = format!("`{}` couldn't be opened - {}", file_path, message);
-                critical_quit!(message);
+            Err(error) => {
+                critical_quit!(error);
             }
-        };
+        }
+    }
+
+    pub fn open(file_path: &str) -> Result<Tail, FlowError> {
+        let path = PathBuf::from(file_path);
+        let file_handle = File::open(&path).map_err(|error| FlowError::from_io(&path, &error))?;
 
-        Tail {
+        Ok(Tail {
             file: file_handle,
+            path: path,
             start_of_file_reached: false,
-        }
+            pending: vec![],
+            read_at: Instant::now(),
+            partial_shown: false,
+            failure: None,
+            error: None,
+        })
     }
 
+    /// Hands over the lines read every 50ms, along with the line still being
+    /// written, if any.
     pub fn watch<F>(&mut self, callback: F)
-        where F: Fn(Vec<String>)
+        where F: Fn(Vec<Arc<str>>, Option<String>)
     {
+        let mut health = HealthBoard::default();
+
         while running!() {
-            callback(self.read_to_end());
+            let lines = self.read_to_end();
+            callback(lines, self.partial());
+            if let Some(error) = self.take_error() {
+                error::report(error);
+            }
+            runner::publish_health(health.update(vec![self.health()]));
             sleep(Duration::from_millis(50));
         }
     }
 
-    pub fn read_lines(&mut self, lines: usize) -> Vec<String> {
+    /// Failing while reads fail, active otherwise.
+    pub fn health(&self) -> HealthChange {
+        let source = self.path.to_string_lossy();
+
+        match self.failure {
+            Some(ref error) => HealthChange::new(&source, Health::Errored, error.to_string()),
+            None => HealthChange::new(&source, Health::Active, format!("Tailing `{}`", source)),
+        }
+    }
+
+    /// The first error of the current run of failed reads, if not yet taken.
+    pub fn take_error(&mut self) -> Option<FlowError> {
+        self.error.take()
+    }
+
+    pub fn read_lines(&mut self, lines: usize) -> Vec<Arc<str>> {
         let estimated_required_bytes = lines * 512;
         self.read_lines_conditionally(estimated_required_bytes, lines)
     }
 
-    pub fn read_to_end(&mut self) -> Vec<String> {
-        let mut buffer = String::new();
-        let _ = self.file.read_to_string(&mut buffer);
-        buffer.lines().map(|x| x.to_string()).collect()
+    pub fn read_to_end(&mut self) -> Vec<Arc<str>> {
+        self.read_to_end_at(Instant::now())
+    }
+
+    /// Same as `read_to_end`, as if called at the given time.
+    pub fn read_to_end_at(&mut self, now: Instant) -> Vec<Arc<str>> {
+        match self.read_available(now) {
+            Ok(lines) => {
+                if self.failure.is_some() {
+                    debug_log!("tail", "recovered path={:?}", self.path);
+                }
+                self.failure = None;
+                lines
+            }
+            Err(error) => {
+                if self.failure.is_none() {
+                    debug_log!("tail",
+                               "failing path={:?} error={:?}",
+                               self.path,
+                               error.to_string());
+                    self.error = Some(error.clone());
+                }
+                self.failure = Some(error);
+                vec![]
+            }
+        }
+    }
+
+    /// Complete lines read since the last call. Text after the last newline
+    /// is kept until the rest of its line is read, or nothing more was read
+    /// for `SETTLE_TIMEOUT`.
+    fn read_available(&mut self, now: Instant) -> Result<Vec<Arc<str>>, FlowError> {
+        match self.file.read_to_end(&mut self.pending) {
+            Ok(0) if idle_for(self.read_at, now, SETTLE_TIMEOUT) => self.settle_pending(),
+            Ok(0) => Ok(vec![]),
+            Ok(_) => {
+                self.read_at = now;
+                self.take_complete_lines()
+            }
+            Err(error) => Err(FlowError::from_io(&self.path, &error)),
+        }
     }
 
-    fn read_bytes_from_file_end(&mut self, bytes: usize) -> Vec<String> {
+    /// The line being written, once nothing was read for `PARTIAL_TIMEOUT`.
+    /// Once shown, it's handed out as it grows until its newline is read.
+    pub fn partial(&mut self) -> Option<String> {
+        self.partial_at(Instant::now())
+    }
+
+    /// Same as `partial`, as if called at the given time.
+    pub fn partial_at(&mut self, now: Instant) -> Option<String> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        if !self.partial_shown && !idle_for(self.read_at, now, PARTIAL_TIMEOUT) {
+            return None;
+        }
+
+        // A character may be cut short by the write
+        let valid = match str::from_utf8(&self.pending) {
+            Ok(text) => text,
+            Err(error) => str::from_utf8(&self.pending[..error.valid_up_to()]).unwrap(),
+        };
+        self.partial_shown = true;
+        Some(valid.trim_end_matches('\r').to_string())
+    }
+
+    // Each line is copied once, straight from the buffer into the text its
+    // copies share. The buffer is kept for the next read.
+    fn take_complete_lines(&mut self) -> Result<Vec<Arc<str>>, FlowError> {
+        let end = match self.pending.iter().rposition(|&byte| byte == b'\n') {
+            Some(index) => index + 1,
+            None => return Ok(vec![]),
+        };
+        self.partial_shown = false;
+
+        let lines = match str::from_utf8(&self.pending[..end]) {
+            Ok(text) => Ok(text.lines().map(Arc::from).collect()),
+            Err(_) => {
+                let error = io::Error::new(ErrorKind::InvalidData,
+                                           "stream did not contain valid UTF-8");
+                Err(FlowError::from_io(&self.path, &error))
+            }
+        };
+        self.pending.drain(..end);
+
+        lines
+    }
+
+    /// The line being written, taken as complete.
+    fn settle_pending(&mut self) -> Result<Vec<Arc<str>>, FlowError> {
+        if self.pending.is_empty() {
+            return Ok(vec![]);
+        }
+
+        self.pending.push(b'\n');
+        self.take_complete_lines()
+    }
+
+    fn read_bytes_from_file_end(&mut self, bytes: usize) -> Vec<Arc<str>> {
         let file_size = self.read_file_size();
         let mut seekable_bytes = bytes;
         if bytes > file_size {
@@ -75,14 +224,19 @@ impl Tail {
             seekable_bytes = file_size;
         }
         let _ = self.file.seek(SeekFrom::End(-(seekable_bytes as i64)));
-        self.read_to_end()
+        self.pending.clear();
+
+        // The last line of the file is complete, newline or not
+        let mut lines = self.read_to_end();
+        lines.extend(self.settle_pending().unwrap_or_default());
+        lines
     }
 
     fn read_file_size(&self) -> usize {
         *(&self.file.metadata().unwrap().len()) as usize
     }
 
-    fn read_lines_conditionally(&mut self, bytes: usize, target_lines: usize) -> Vec<String> {
+    fn read_lines_conditionally(&mut self, bytes: usize, target_lines: usize) -> Vec<Arc<str>> {
         let buffer = self.read_bytes_from_file_end(bytes);
 
         match self.excess_lines_at_beggining_of_buffer(&buffer, target_lines) {
@@ -95,7 +249,7 @@ impl Tail {
     }
 
     fn excess_lines_at_beggining_of_buffer(&self,
-                                           buffer: &[String],
+                                           buffer: &[Arc<str>],
                                            target_lines: usize)
                                            -> Option<usize> {
         let count = buffer.len();
@@ -108,3 +262,7 @@ impl Tail {
         }
     }
 }
+
+fn idle_for(since: Instant, now: Instant, milliseconds: u64) -> bool {
+    now.saturating_duration_since(since) >= Duration::from_millis(milliseconds)
+}
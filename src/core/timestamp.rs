@@ -0,0 +1,269 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use regex::{Captures, Regex};
+use time;
+
+pub const DAY: i64 = 86_400_000;
+
+lazy_static! {
+    pub static ref TIMESTAMP: Regex = Regex::new(
+        r"(?:(\d{4})-(\d\d)-(\d\d)[T ])?(\d\d):(\d\d):(\d\d)(?:[.,](\d{1,9}))?"
+    ).unwrap();
+    static ref TIME_OF_DAY: Regex = Regex::new(r"^(\d\d?):(\d\d)(?::(\d\d))?$").unwrap();
+    static ref RELATIVE: Regex = Regex::new(r"^-(\d+)([smhd])$").unwrap();
+    static ref ZONE: Regex = Regex::new(r"^(?:Z|[+-]\d\d:?\d\d)?$").unwrap();
+}
+
+/// Milliseconds represented by the first timestamp found in the line. Lines
+/// having only the time of day are measured from midnight.
+pub fn parse_timestamp(line: &str) -> Option<i64> {
+    TIMESTAMP.captures(line).map(|captures| {
+        let days = match (number(&captures, 1), number(&captures, 2), number(&captures, 3)) {
+            (Some(year), Some(month), Some(day)) => days_from_civil(year, month, day),
+            _ => 0,
+        };
+        let fraction = captures.get(7).map_or(0, |value| {
+            let digits = format!("{:0<3}", &value.as_str()[..value.as_str().len().min(3)]);
+            digits.parse::<i64>().unwrap()
+        });
+
+        let seconds = days * 86_400 + number(&captures, 4).unwrap() * 3_600 +
+                      number(&captures, 5).unwrap() * 60 +
+                      number(&captures, 6).unwrap();
+
+        seconds * 1_000 + fraction
+    })
+}
+
+fn number(captures: &Captures, index: usize) -> Option<i64> {
+    captures.get(index).and_then(|value| value.as_str().parse().ok())
+}
+
+// Days since the Unix epoch for the given date of the proleptic Gregorian calendar
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+// Year, month and day of the given days since the Unix epoch, the inverse of
+// `days_from_civil`
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = if days >= 0 { days } else { days - 146_096 } / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 -
+                       day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+/// The minute of a parsed timestamp, as `2016-03-01 14:32`, or as `14:32`
+/// for timestamps having only the time of day.
+pub fn format_minute(value: i64) -> String {
+    let days = value.div_euclid(DAY);
+    let minutes = value.rem_euclid(DAY) / 60_000;
+    let time = format!("{:02}:{:02}", minutes / 60, minutes % 60);
+
+    if days == 0 {
+        return time;
+    }
+
+    let (year, month, day) = civil_from_days(days);
+    format!("{}-{:02}-{:02} {}", year, month, day, time)
+}
+
+/// Current local time, in the same form as parsed timestamps.
+pub fn local_now() -> i64 {
+    let now = time::now();
+    let days = days_from_civil(now.tm_year as i64 + 1900, now.tm_mon as i64 + 1, now.tm_mday as i64);
+    let seconds = now.tm_hour as i64 * 3_600 + now.tm_min as i64 * 60 + now.tm_sec as i64;
+
+    (days * 86_400 + seconds) * 1_000 + now.tm_nsec as i64 / 1_000_000
+}
+
+/// Parses a time to jump to: a time of day such as `14:32` or `14:32:05`,
+/// taken as today, a full timestamp like `2016-03-01T14:32:05`, or a time
+/// relative to `now` such as `-5m`. A leading `@` or `ts` is ignored.
+pub fn parse_target(input: &str, now: i64) -> Result<i64, String> {
+    let mut text = input.trim();
+    for prefix in &[":ts", "ts", "@"] {
+        if text.starts_with(prefix) {
+            text = text[prefix.len()..].trim_start();
+            break;
+        }
+    }
+
+    if let Some(captures) = RELATIVE.captures(text) {
+        let unit = match &captures[2] {
+            "s" => 1_000,
+            "m" => 60_000,
+            "h" => 3_600_000,
+            _ => DAY,
+        };
+        return number(&captures, 1)
+            .and_then(|value| value.checked_mul(unit))
+            .and_then(|value| now.checked_sub(value))
+            .ok_or_else(|| format!("`{}` is too far back", text));
+    }
+
+    if let Some(captures) = TIME_OF_DAY.captures(text) {
+        let (hours, minutes) = (number(&captures, 1).unwrap(), number(&captures, 2).unwrap());
+        let seconds = number(&captures, 3).unwrap_or(0);
+        if hours > 23 || minutes > 59 || seconds > 59 {
+            return Err(format!("`{}` isn't a valid time", text));
+        }
+
+        return Ok(now - now.rem_euclid(DAY) + (hours * 3_600 + minutes * 60 + seconds) * 1_000);
+    }
+
+    // The time zone is ignored, like it is for the lines
+    match TIMESTAMP.captures(text) {
+        Some(ref captures) if captures.get(1).is_some() && captures.get(0).unwrap().start() == 0 &&
+                              ZONE.is_match(&text[captures.get(0).unwrap().end()..]) => {
+            Ok(parse_timestamp(text).unwrap())
+        }
+        _ => Err(format!("`{}` isn't a time, expected 14:32, 2016-03-01T14:32:05 or -5m", text)),
+    }
+}
+
+/// Places a timestamp having only the time of day on the day of `other`.
+pub fn on_day_of(value: i64, other: i64) -> i64 {
+    if value < DAY {
+        other - other.rem_euclid(DAY) + value
+    } else {
+        value
+    }
+}
+
+/// Index of the first line at or after `target`, or `None` when every line is
+/// older. Lines are probed by binary search, so their timestamps are expected
+/// to increase. Lines without one, such as stack traces, take the timestamp
+/// of the closest line above them. Lines having only a time of day are taken
+/// as being on the same day as the target.
+pub fn first_at_or_after<F>(len: usize, timestamp_at: F, target: i64) -> Option<usize>
+    where F: Fn(usize) -> Option<i64>
+{
+    let probe = |index: usize| -> Option<i64> {
+        (0..index + 1)
+            .rev()
+            .filter_map(|i| timestamp_at(i))
+            .next()
+            .map(|value| on_day_of(value, target))
+    };
+
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let middle = low + (high - low) / 2;
+
+        match probe(middle) {
+            Some(value) if value >= target => high = middle,
+            _ => low = middle + 1,
+        }
+    }
+
+    if low < len { Some(low) } else { None }
+}
+
+/// Silences shorter than this aren't marked, unless changed at runtime.
+pub const DEFAULT_GAP_THRESHOLD: i64 = 60_000;
+
+/// Parses a duration such as `90`, `90s`, `5m` or `2h`, seconds being the
+/// default unit, into milliseconds.
+pub fn parse_duration(input: &str) -> Result<i64, String> {
+    let text = input.trim();
+    let (digits, unit) = match text.char_indices().last() {
+        Some((index, 's')) => (&text[..index], 1_000),
+        Some((index, 'm')) => (&text[..index], 60_000),
+        Some((index, 'h')) => (&text[..index], 3_600_000),
+        _ => (text, 1_000),
+    };
+
+    match digits.parse::<i64>() {
+        Ok(value) if value >= 0 => {
+            value.checked_mul(unit).ok_or_else(|| format!("`{}` is too long a duration", text))
+        }
+        _ => Err(format!("`{}` isn't a duration, expected 90, 90s, 5m or 2h", text)),
+    }
+}
+
+/// Shortest readable form of a duration, as in `4m 12s` or `2h 5m`.
+pub fn format_duration(milliseconds: i64) -> String {
+    let seconds = milliseconds / 1_000;
+    let parts = [(seconds / 86_400, "d"),
+                 (seconds % 86_400 / 3_600, "h"),
+                 (seconds % 3_600 / 60, "m"),
+                 (seconds % 60, "s")];
+
+    let start = match parts.iter().position(|&(value, _)| value > 0) {
+        Some(value) => value,
+        None => return "0s".to_string(),
+    };
+
+    parts[start..(start + 2).min(parts.len())]
+        .iter()
+        .filter(|&&(value, _)| value > 0)
+        .map(|&(value, unit)| format!("{}{}", value, unit))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Follows lines from the oldest, telling the silence preceding each one when
+/// it's longer than the threshold. Lines are timed by their timestamp, lines
+/// without one belonging with the line above. Until a timestamp is found,
+/// the arrival time is used instead.
+pub struct GapTracker {
+    threshold: i64,
+    parsed: Option<i64>,
+    arrival: Option<i64>,
+}
+
+impl GapTracker {
+    pub fn new(threshold: i64) -> GapTracker {
+        GapTracker {
+            threshold: threshold,
+            parsed: None,
+            arrival: None,
+        }
+    }
+
+    pub fn advance(&mut self, parsed: Option<i64>, arrival: i64) -> Option<i64> {
+        let gap = match parsed {
+            Some(value) => {
+                let gap = self.parsed.map(|last| value - on_day_of(last, value));
+                self.parsed = Some(value);
+                gap
+            }
+            None if self.parsed.is_some() => None,
+            None => self.arrival.map(|last| arrival - last),
+        };
+        self.arrival = Some(arrival);
+
+        gap.filter(|value| *value > self.threshold)
+    }
+}
@@ -0,0 +1,229 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use core::filter::LineRule;
+use core::highlights::HighlightPattern;
+use core::rewrite::RewriteRule;
+
+/// Steps kept for undoing, the oldest ones being forgotten past this many.
+pub const MAX_STEPS: usize = 100;
+
+/// What a key switches on and off.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Setting {
+    Invisibles,
+    BlankFolding,
+    Ruler,
+    /// Lines of the tab at the index shown as received.
+    Raw(usize),
+    FilterMode,
+    WholeWord,
+    FieldFallback,
+    Regex,
+    Expressions,
+    ColumnCursor,
+}
+
+impl Setting {
+    fn describe(&self) -> &'static str {
+        match *self {
+            Setting::Invisibles => "invisible characters",
+            Setting::BlankFolding => "blank line folding",
+            Setting::Ruler => "the ruler",
+            Setting::Raw(_) => "lines as received",
+            Setting::FilterMode => "filtering by the search",
+            Setting::WholeWord => "whole word search",
+            Setting::FieldFallback => "searching lines without fields",
+            Setting::Regex => "regex search",
+            Setting::Expressions => "search expressions",
+            Setting::ColumnCursor => "the column cursor",
+        }
+    }
+}
+
+/// Rewrites and line rules of a tab.
+#[derive(Clone)]
+pub struct TabRules {
+    pub rewrites: Vec<RewriteRule>,
+    pub line_rules: Vec<LineRule>,
+}
+
+/// A change of what's shown, as opposed to the lines received, which can't
+/// be taken back. Changes replacing state keep it as it was and became.
+#[derive(Clone)]
+pub enum Change {
+    /// Rules of the tab at the index.
+    Rules(usize, TabRules, TabRules),
+    Highlights(Vec<HighlightPattern>, Vec<HighlightPattern>),
+    /// Text of the search.
+    Search(String, String),
+    Toggle(Setting),
+}
+
+impl Change {
+    /// The change taking this one back.
+    pub fn reversed(&self) -> Change {
+        match *self {
+            Change::Rules(tab, ref before, ref after) => {
+                Change::Rules(tab, after.clone(), before.clone())
+            }
+            Change::Highlights(ref before, ref after) => {
+                Change::Highlights(after.clone(), before.clone())
+            }
+            Change::Search(ref before, ref after) => Change::Search(after.clone(), before.clone()),
+            Change::Toggle(setting) => Change::Toggle(setting),
+        }
+    }
+
+    /// Index of the tab the change applies to, when it applies to a single one.
+    pub fn tab(&self) -> Option<usize> {
+        match *self {
+            Change::Rules(tab, _, _) |
+            Change::Toggle(Setting::Raw(tab)) => Some(tab),
+            _ => None,
+        }
+    }
+
+    fn move_tab(&mut self, index: usize) {
+        match *self {
+            Change::Rules(ref mut tab, _, _) |
+            Change::Toggle(Setting::Raw(ref mut tab)) => *tab = index,
+            _ => {}
+        }
+    }
+}
+
+/// Changes made by a single key, along with what they did, as in
+/// "removed rule `healthcheck`".
+pub struct Step {
+    pub description: String,
+    pub changes: Vec<Change>,
+}
+
+impl Step {
+    pub fn new(description: String, changes: Vec<Change>) -> Step {
+        Step {
+            description: description,
+            changes: changes,
+        }
+    }
+
+    /// Switching a setting is described by what it switched.
+    pub fn toggle(setting: Setting) -> Step {
+        Step::new(format!("toggled {}", setting.describe()), vec![Change::Toggle(setting)])
+    }
+
+    /// Editing the search is described by the text it ended up with.
+    pub fn search(before: String, after: String) -> Step {
+        let description = if after.is_empty() {
+            "cleared the search".to_string()
+        } else {
+            format!("searched `{}`", after)
+        };
+
+        Step::new(description, vec![Change::Search(before, after)])
+    }
+}
+
+/// Steps that can be undone, and those undone that can be done again until
+/// something else changes.
+#[derive(Default)]
+pub struct History {
+    done: Vec<Step>,
+    undone: Vec<Step>,
+    /// The last step done is the search being edited.
+    editing: bool,
+}
+
+impl History {
+    pub fn record(&mut self, step: Step) {
+        self.done.push(step);
+        if self.done.len() > MAX_STEPS {
+            self.done.remove(0);
+        }
+        self.undone.clear();
+        self.editing = false;
+    }
+
+    /// Records an edit of the search. Edits made one after the other make a
+    /// single step, taken back at once, which is dropped once the text is
+    /// back to what it was.
+    pub fn record_search(&mut self, mut before: String, after: String) {
+        if before == after {
+            return;
+        }
+        if self.editing {
+            let first = self.done.pop().and_then(|step| step.changes.into_iter().next());
+            if let Some(Change::Search(text, _)) = first {
+                before = text;
+            }
+        }
+
+        self.editing = false;
+        if before != after {
+            self.record(Step::search(before, after));
+            self.editing = true;
+        }
+    }
+
+    /// The last step done, to be described, along with the changes taking
+    /// it back in the order they're applied.
+    pub fn undo(&mut self) -> Option<(String, Vec<Change>)> {
+        self.editing = false;
+        let step = self.done.pop()?;
+        let changes = step.changes.iter().rev().map(Change::reversed).collect();
+        let description = step.description.clone();
+        self.undone.push(step);
+
+        Some((description, changes))
+    }
+
+    /// The last step undone, along with its changes.
+    pub fn redo(&mut self) -> Option<(String, Vec<Change>)> {
+        self.editing = false;
+        let step = self.undone.pop()?;
+        let result = (step.description.clone(), step.changes.clone());
+        self.done.push(step);
+
+        Some(result)
+    }
+
+    pub fn last(&self) -> Option<&Step> {
+        self.done.last()
+    }
+
+    /// Forgets the steps of a closed tab, those of the tabs after it moving
+    /// along with them.
+    pub fn tab_closed(&mut self, index: usize) {
+        for steps in vec![&mut self.done, &mut self.undone] {
+            steps.retain(|step| step.changes.iter().all(|change| change.tab() != Some(index)));
+            for change in steps.iter_mut().flat_map(|step| step.changes.iter_mut()) {
+                if let Some(tab) = change.tab().filter(|&tab| tab > index) {
+                    change.move_tab(tab - 1);
+                }
+            }
+        }
+    }
+
+    /// Forgets every step, as when the tabs are replaced.
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+        self.editing = false;
+    }
+}
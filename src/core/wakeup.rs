@@ -0,0 +1,88 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use libc::{self, c_int, c_void, pollfd, POLLIN, STDIN_FILENO, O_NONBLOCK, F_GETFL, F_SETFL};
+
+/// Self-pipe letting other threads interrupt the ui thread while it waits
+/// for keyboard input.
+pub struct Wakeup {
+    read_fd: c_int,
+    write_fd: c_int,
+}
+
+impl Wakeup {
+    pub fn new() -> Wakeup {
+        let mut fds = [0 as c_int; 2];
+
+        unsafe {
+            assert!(libc::pipe(fds.as_mut_ptr()) == 0, "Unable to create wakeup pipe");
+            for fd in &fds {
+                let flags = libc::fcntl(*fd, F_GETFL);
+                libc::fcntl(*fd, F_SETFL, flags | O_NONBLOCK);
+            }
+        }
+
+        Wakeup {
+            read_fd: fds[0],
+            write_fd: fds[1],
+        }
+    }
+
+    /// Safe to call from signal handlers.
+    pub fn notify(&self) {
+        let byte = 1u8;
+        unsafe {
+            libc::write(self.write_fd, &byte as *const u8 as *const c_void, 1);
+        }
+    }
+
+    /// Blocks until stdin has data, a notification arrives or the timeout
+    /// expires. Returns whether there's input to be read.
+    pub fn wait(&self, timeout: i32) -> bool {
+        let mut fds = [pollfd {
+                           fd: STDIN_FILENO,
+                           events: POLLIN,
+                           revents: 0,
+                       },
+                       pollfd {
+                           fd: self.read_fd,
+                           events: POLLIN,
+                           revents: 0,
+                       }];
+
+        let result = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as _, timeout) };
+        if result <= 0 {
+            return false;
+        }
+
+        if fds[1].revents & POLLIN != 0 {
+            self.drain();
+        }
+
+        fds[0].revents != 0
+    }
+
+    fn drain(&self) {
+        let mut buffer = [0u8; 64];
+        unsafe {
+            while libc::read(self.read_fd,
+                             buffer.as_mut_ptr() as *mut c_void,
+                             buffer.len()) > 0 {}
+        }
+    }
+}
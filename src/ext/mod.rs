@@ -17,4 +17,4 @@
  */
 
 pub mod signal;
-pub mod readline;
+pub mod terminal;
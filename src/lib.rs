@@ -20,6 +20,8 @@ extern crate lazy_static;
 
 extern crate libc;
 extern crate regex;
+extern crate aho_corasick;
+extern crate memchr;
 extern crate time;
 extern crate toml;
 extern crate rustc_serialize;
@@ -21,16 +21,23 @@ macro_rules! running {
     ($val: expr) => (RUNNING.store($val, Ordering::Relaxed));
 }
 
+/// Prints the message to stdout and exits, successfully unless it couldn't
+/// be written. A closed pipe doesn't count, see `utils::output`.
 macro_rules! quit {
     ($msg: expr) => {
-        println!("{}", $msg);
-        process::exit(0)
+        match $crate::utils::output::print_line(&format!("{}", $msg)) {
+            Ok(()) => process::exit(0),
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1)
+            }
+        }
     };
 }
 
 macro_rules! critical_quit {
     ($msg: expr) => {
-        println!("{}", $msg);
+        eprintln!("{}", $msg);
         process::exit(1)
     };
 }
@@ -43,3 +50,13 @@ macro_rules! assert_quit {
         }
     }
 }
+
+/// Appends an entry to the debug log, when enabled, e.g.
+/// `debug_log!("search", "query={:?} micros={}", text, micros)`.
+macro_rules! debug_log {
+    ($target: expr, $($arg: tt)+) => {
+        if $crate::core::debug_log::is_enabled() {
+            $crate::core::debug_log::write($target, format!($($arg)+));
+        }
+    };
+}
@@ -0,0 +1,123 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cmp::min;
+use std::i32;
+
+use ui::input::{Input, Key};
+
+/// Time after which a pending key sequence is dropped, in milliseconds.
+pub const CHORD_TIMEOUT: u64 = 1_000;
+
+/// Counts above this are taken as it, so that they fit a line offset.
+pub const MAX_COUNT: usize = i32::MAX as usize;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Placement {
+    Top,
+    Center,
+    Bottom,
+}
+
+/// Commands of the vim keymap, counts already applied.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Chord {
+    /// Positive values scroll towards older content, like `Offset::Line`.
+    Lines(i32),
+    Top,
+    Bottom,
+    GotoLine(usize),
+    Reposition(Placement),
+}
+
+#[derive(PartialEq, Debug)]
+pub enum ChordResult {
+    /// The input isn't part of a chord and should be handled as usual.
+    Unhandled,
+    /// Keys typed so far, waiting for the rest of the sequence.
+    Pending(String),
+    Cancelled,
+    Complete(Chord),
+}
+
+/// Collects multi-key sequences such as `gg`, `zt` or `15j`.
+#[derive(Default)]
+pub struct Chords {
+    pending: String,
+    started_at: u64,
+}
+
+impl Chords {
+    pub fn feed(&mut self, input: &Input, now: u64) -> ChordResult {
+        let expired = !self.pending.is_empty() && now - self.started_at > CHORD_TIMEOUT;
+        if expired {
+            self.pending.clear();
+        }
+
+        let value = match *input {
+            Input::Kb(Key::Char(value), None) => value,
+            Input::None if expired => return ChordResult::Cancelled,
+            Input::None => return ChordResult::Unhandled,
+            _ if self.pending.is_empty() => return ChordResult::Unhandled,
+            _ => return self.cancel(),
+        };
+
+        let mut sequence = self.pending.clone();
+        sequence.push(value);
+
+        let keys_offset = sequence.find(|value: char| !value.is_digit(10)).unwrap_or(sequence.len());
+        let (count, keys) = sequence.split_at(keys_offset);
+        if count.starts_with('0') {
+            return ChordResult::Unhandled;
+        }
+
+        let count = if count.is_empty() {
+            None
+        } else {
+            Some(min(count.parse().unwrap_or(MAX_COUNT), MAX_COUNT))
+        };
+        let lines = count.unwrap_or(1) as i32;
+
+        let chord = match keys {
+            "" | "g" | "z" => {
+                if self.pending.is_empty() {
+                    self.started_at = now;
+                }
+                self.pending = sequence.clone();
+                return ChordResult::Pending(sequence);
+            }
+            "j" => Chord::Lines(-lines),
+            "k" => Chord::Lines(lines),
+            "gg" => count.map_or(Chord::Top, Chord::GotoLine),
+            "G" => count.map_or(Chord::Bottom, Chord::GotoLine),
+            "zt" => Chord::Reposition(Placement::Top),
+            "zz" => Chord::Reposition(Placement::Center),
+            "zb" => Chord::Reposition(Placement::Bottom),
+            _ if self.pending.is_empty() => return ChordResult::Unhandled,
+            _ => return self.cancel(),
+        };
+
+        self.pending.clear();
+        ChordResult::Complete(chord)
+    }
+
+    fn cancel(&mut self) -> ChordResult {
+        self.pending.clear();
+        ChordResult::Cancelled
+    }
+}
@@ -16,13 +16,83 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use ncurses::*;
 
+lazy_static! {
+    static ref ENABLED: AtomicBool = AtomicBool::new(true);
+    static ref REGISTRY: Mutex<PairRegistry> = Mutex::new(PairRegistry::new(FIRST_DYNAMIC_PAIR, 256, 8));
+    static ref HIGHLIGHT_PALETTE: Mutex<Vec<ColorPair>> = Mutex::new(default_highlight_palette());
+}
+
+/// Pairs below this one are the fixed ones used by the interface.
+static FIRST_DYNAMIC_PAIR: i16 = 16;
+
+static COLOR_NAMES: [&'static str; 8] = ["black", "red", "green", "yellow", "blue", "magenta",
+                                         "cyan", "white"];
+
+/// Values accepted by `--color`.
+pub static COLOR_MODES: &'static [&'static str] = &["auto", "always", "never"];
+
+/// When to use colors, as passed to `--color`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    pub fn parse(value: &str) -> Option<ColorMode> {
+        match value {
+            "auto" => Some(ColorMode::Auto),
+            "always" => Some(ColorMode::Always),
+            "never" => Some(ColorMode::Never),
+            _ => None,
+        }
+    }
+}
+
+/// Decides whether colors are used. An explicit `--color` always wins,
+/// otherwise a non-empty `NO_COLOR` turns them off, and they're only used
+/// when writing to a terminal.
+pub fn use_colors(mode: ColorMode, no_color: Option<&str>, is_tty: bool) -> bool {
+    match mode {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => no_color.map_or(true, str::is_empty) && is_tty,
+    }
+}
+
+/// Records the decision, consulted whenever something is painted. Without
+/// colors, attributes such as reverse video or underline are used instead.
+pub fn set_enabled(value: bool) {
+    ENABLED.store(value, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Attribute painting with the given color pair, or the fallback attribute
+/// when colors are off.
+pub fn pair_attr(pair_id: i16, fallback: attr_t) -> attr_t {
+    if is_enabled() {
+        COLOR_PAIR(pair_id)
+    } else {
+        fallback
+    }
+}
+
 // A negative value is interpreted as the default (original) color.
 // For color pair generation, when unsigned it must also be different
 // than the 8 colors already defined.
 pub static COLOR_DEFAULT: i16 = -9;
 
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct ColorPair {
     pub foreground: i16,
     pub background: i16,
@@ -49,33 +119,240 @@ impl ColorPair {
         ColorPair::new(COLOR_DEFAULT, COLOR_DEFAULT)
     }
 
-    fn calculate_id(&self) -> i16 {
-        100 + self.foreground.abs() * 10 + self.background.abs()
+    /// Parses `<foreground>` or `<foreground> on <background>`, the background
+    /// being the terminal's own when left out.
+    pub fn parse(value: &str) -> Result<ColorPair, String> {
+        let mut parts = value.split(" on ");
+        let foreground = parse_color(parts.next().unwrap_or(""))?;
+        let background = match parts.next() {
+            Some(name) => parse_color(name)?,
+            None => COLOR_DEFAULT,
+        };
+
+        if parts.next().is_some() {
+            return Err(format!("Invalid color `{}` - expected `<color>` or `<color> on <color>`",
+                               value));
+        }
+
+        Ok(ColorPair::new(foreground, background))
     }
 
-    fn init(&self) {
-        init_pair(self.calculate_id(), self.foreground, self.background);
+    /// Id of the pair, initialized the first time it's used.
+    pub fn calculate_id(&self) -> i16 {
+        let mut registry = REGISTRY.lock().unwrap();
+        let (id, is_new) = registry.lookup(self.foreground, self.background);
+
+        if is_new {
+            let (foreground, background) = registry.available(self.foreground, self.background);
+            init_pair(id, foreground, background);
+        }
+
+        id
     }
 
     pub fn to_attr(&self) -> attr_t {
-        COLOR_PAIR(self.calculate_id())
+        pair_attr(self.calculate_id(), A_NORMAL())
+    }
+}
+
+/// Parses a color name such as `red`, `bright-blue` or `default`, or one of
+/// the 256 indexed colors as `color208`.
+pub fn parse_color(value: &str) -> Result<i16, String> {
+    let name = value.trim().to_lowercase();
+
+    if name == "default" {
+        return Ok(COLOR_DEFAULT);
+    }
+
+    if name.starts_with("color") {
+        return match name["color".len()..].parse::<i16>() {
+            Ok(index) if index >= 0 && index < 256 => Ok(index),
+            _ => Err(format!("Invalid color `{}` - indexed colors go from color0 to color255", value)),
+        };
+    }
+
+    let (base, offset) = if name.starts_with("bright-") {
+        (&name["bright-".len()..], 8)
+    } else {
+        (&name[..], 0)
+    };
+
+    match COLOR_NAMES.iter().position(|known| *known == base) {
+        Some(index) => Ok(index as i16 + offset),
+        None => Err(format!("Unknown color `{}`", value)),
+    }
+}
+
+/// Approximate RGB value of an indexed color, as defined by xterm.
+fn rgb(color: i16) -> Option<(i32, i32, i32)> {
+    static BASIC: [(i32, i32, i32); 16] = [(0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0),
+                                           (0, 0, 238), (205, 0, 205), (0, 205, 205),
+                                           (229, 229, 229), (127, 127, 127), (255, 0, 0),
+                                           (0, 255, 0), (255, 255, 0), (92, 92, 255),
+                                           (255, 0, 255), (0, 255, 255), (255, 255, 255)];
+
+    let level = |value: i16| if value == 0 { 0 } else { 55 + value as i32 * 40 };
+
+    match color {
+        0..=15 => Some(BASIC[color as usize]),
+        16..=231 => {
+            let index = color - 16;
+            Some((level(index / 36), level(index / 6 % 6), level(index % 6)))
+        }
+        232..=255 => {
+            let gray = 8 + (color as i32 - 232) * 10;
+            Some((gray, gray, gray))
+        }
+        _ => None,
     }
 }
 
+fn distance(first: i16, second: i16) -> i32 {
+    match (rgb(first), rgb(second)) {
+        (Some(a), Some(b)) => (a.0 - b.0).pow(2) + (a.1 - b.1).pow(2) + (a.2 - b.2).pow(2),
+        (None, None) => 0,
+        _ => 3 * 255 * 255,
+    }
+}
+
+/// Hands out color pair ids on demand, sharing one between identical
+/// foreground and background combinations. Once the terminal runs out of
+/// pairs, the closest one already handed out is reused.
+pub struct PairRegistry {
+    pairs: HashMap<(i16, i16), i16>,
+    next_id: i16,
+    pair_limit: i16,
+    color_limit: i16,
+}
+
+impl PairRegistry {
+    pub fn new(first_id: i16, pair_limit: i16, color_limit: i16) -> PairRegistry {
+        PairRegistry {
+            pairs: HashMap::new(),
+            next_id: first_id,
+            pair_limit: pair_limit,
+            color_limit: color_limit,
+        }
+    }
+
+    /// Id of the pair, along with whether it was just allocated and still
+    /// needs to be initialized.
+    pub fn lookup(&mut self, foreground: i16, background: i16) -> (i16, bool) {
+        if let Some(id) = self.pairs.get(&(foreground, background)) {
+            return (*id, false);
+        }
+
+        if self.next_id < self.pair_limit {
+            let id = self.next_id;
+            self.next_id += 1;
+            self.pairs.insert((foreground, background), id);
+
+            return (id, true);
+        }
+
+        let nearest = self.pairs
+            .iter()
+            .min_by_key(|&(&(other_foreground, other_background), _)| {
+                distance(foreground, other_foreground) + distance(background, other_background)
+            })
+            .map(|(_, id)| *id);
+
+        (nearest.unwrap_or(0), false)
+    }
+
+    /// Closest colors the terminal can display, e.g. the basic ones for
+    /// indexed colors on an 8 color terminal.
+    pub fn available(&self, foreground: i16, background: i16) -> (i16, i16) {
+        (self.nearest_color(foreground), self.nearest_color(background))
+    }
+
+    fn nearest_color(&self, color: i16) -> i16 {
+        if color < self.color_limit {
+            return color;
+        }
+
+        (0..self.color_limit.min(16)).min_by_key(|other| distance(color, *other)).unwrap_or(color)
+    }
+}
+
+/// Prepares pair allocation for the terminal's limits. Pairs are only
+/// initialized as they're used.
 pub fn generate_pairs() {
-    let colors = [COLOR_BLACK,
-                  COLOR_RED,
-                  COLOR_GREEN,
-                  COLOR_YELLOW,
-                  COLOR_BLUE,
-                  COLOR_MAGENTA,
-                  COLOR_CYAN,
-                  COLOR_WHITE,
-                  COLOR_DEFAULT];
-
-    for foreground in &colors {
-        for background in &colors {
-            ColorPair::new(*foreground, *background).init();
+    let pair_limit = COLOR_PAIRS().min(i16::max_value() as i32) as i16;
+    let color_limit = COLORS().max(8).min(256) as i16;
+
+    *REGISTRY.lock().unwrap() = PairRegistry::new(FIRST_DYNAMIC_PAIR, pair_limit, color_limit);
+}
+
+/// Overrides the colors of the fixed interface pairs.
+pub fn apply_theme(pairs: &[(i16, ColorPair)]) {
+    let registry = REGISTRY.lock().unwrap();
+
+    for &(id, pair) in pairs {
+        let (foreground, background) = registry.available(pair.foreground, pair.background);
+        init_pair(id, foreground, background);
+    }
+}
+
+fn default_highlight_palette() -> Vec<ColorPair> {
+    vec![ColorPair::new(COLOR_BLACK, COLOR_CYAN),
+         ColorPair::new(COLOR_BLACK, COLOR_GREEN),
+         ColorPair::new(COLOR_WHITE, COLOR_MAGENTA),
+         ColorPair::new(COLOR_WHITE, COLOR_RED),
+         ColorPair::new(COLOR_WHITE, COLOR_BLUE)]
+}
+
+/// Colors the persistent highlights cycle through, the default ones when
+/// none are given.
+pub fn set_highlight_palette(pairs: Vec<ColorPair>) {
+    *HIGHLIGHT_PALETTE.lock().unwrap() = if pairs.is_empty() {
+        default_highlight_palette()
+    } else {
+        pairs
+    };
+}
+
+pub fn highlight_color(index: usize) -> ColorPair {
+    let palette = HIGHLIGHT_PALETTE.lock().unwrap();
+    palette[index % palette.len()]
+}
+
+/// Color of the lines a container wrote to stderr.
+pub fn stderr_color() -> ColorPair {
+    ColorPair::new(COLOR_RED, COLOR_DEFAULT)
+}
+
+/// Indexed color as `#rrggbb`, for HTML.
+pub fn hex(color: i16) -> Option<String> {
+    rgb(color).map(|(red, green, blue)| format!("#{:02x}{:02x}{:02x}", red, green, blue))
+}
+
+/// Interface colors that can be changed in the config file.
+#[derive(RustcDecodable, Clone, Default)]
+pub struct Theme {
+    pub bar: Option<String>,
+    pub status: Option<String>,
+    pub error: Option<String>,
+    pub search_match: Option<String>,
+    pub current_match: Option<String>,
+}
+
+impl Theme {
+    /// The configured colors, by the id of the pair they replace.
+    pub fn pairs(&self) -> Result<Vec<(i16, ColorPair)>, String> {
+        let entries = [(1, &self.bar),
+                       (3, &self.status),
+                       (4, &self.error),
+                       (5, &self.search_match),
+                       (6, &self.current_match)];
+
+        let mut pairs = vec![];
+        for &(id, value) in &entries {
+            if let Some(ref value) = *value {
+                pairs.push((id, ColorPair::parse(value)?));
+            }
         }
+
+        Ok(pairs)
     }
 }
@@ -0,0 +1,61 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cell::{Cell, RefCell};
+use std::ops::Range;
+
+use ncurses::*;
+
+/// Highlighted column of the content, for checking whether fixed width
+/// fields line up. Only the cells under it are changed, by flipping their
+/// reverse attribute, so that rows don't have to be printed again.
+#[derive(Default)]
+pub struct ColumnCursor {
+    pub column: Cell<Option<usize>>,
+    /// Pad cells flipped, along with what they held afterwards.
+    marked: RefCell<Vec<(i32, i32, chtype)>>,
+}
+
+impl ColumnCursor {
+    /// Flips the given columns of a pad row.
+    pub fn mark(&self, window: WINDOW, row: i32, columns: Range<usize>) {
+        let mut marked = self.marked.borrow_mut();
+
+        for column in columns {
+            let column = column as i32;
+            flip(window, row, column);
+            marked.push((row, column, mvwinch(window, row, column)));
+        }
+    }
+
+    /// Flips back the cells marked before, unless they were printed over.
+    pub fn unmark(&self, window: WINDOW) {
+        for (row, column, value) in self.marked.borrow_mut().drain(..) {
+            if mvwinch(window, row, column) == value {
+                flip(window, row, column);
+            }
+        }
+    }
+}
+
+fn flip(window: WINDOW, row: i32, column: i32) {
+    let attributes = mvwinch(window, row, column) & A_ATTRIBUTES();
+    let pair = PAIR_NUMBER((attributes & A_COLOR()) as i32) as i16;
+
+    mvwchgat(window, row, column, 1, (attributes & !A_COLOR()) ^ A_REVERSE(), pair);
+}
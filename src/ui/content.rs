@@ -16,51 +16,322 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::cmp::min;
+use std::ops::Range;
+use std::rc::Rc;
 
 use ncurses::*;
+use time::{self, Duration, Timespec};
+use unicode_width::UnicodeWidthStr;
 
 use ui::color::COLOR_DEFAULT;
-use ui::rendered_line::MatchedLine;
+use ui::flash::Flash;
+use ui::highlighter::DisplayedText;
+use ui::rendered_line::{MatchedLine, RenderedLineCollection, WrappedRows};
+use ui::viewport::Viewport;
+use core::line::{Line, SearchPattern};
+use core::range::LineRange;
+use utils::clusters::clusters;
+use utils::wrap::{wrap_indented, DEFAULT_CONTINUATION_PREFIX};
 
-static WINDOW_HEIGHT: i32 = 2500;
+/// Rows worth of the newest lines kept for scrolling, unless changed through
+/// `--buffer-lines`. Older lines aren't rendered.
+pub static DEFAULT_BUFFER_ROWS: usize = 2500;
+pub static MIN_BUFFER_ROWS: usize = 100;
+pub static MAX_BUFFER_ROWS: usize = 1_000_000;
 
+/// Rows printed into the pad above and below the screen, so that scrolling
+/// by less doesn't print them again.
+pub static PAD_MARGIN: usize = 200;
+/// Pad dimensions are stored as shorts by ncurses.
+static MAX_PAD_HEIGHT: usize = 32_000;
+
+pub fn validate_buffer_rows(value: i64) -> Result<usize, String> {
+    if value < MIN_BUFFER_ROWS as i64 || value > MAX_BUFFER_ROWS as i64 {
+        Err(format!("Buffer lines must be between {} and {}, got {}",
+                    MIN_BUFFER_ROWS,
+                    MAX_BUFFER_ROWS,
+                    value))
+    } else {
+        Ok(value as usize)
+    }
+}
+
+/// Content rows printed into the pad for the viewport: the visible ones and
+/// up to `PAD_MARGIN` more on each side, starting where a line starts.
+pub fn pad_rows(rendered_lines: &RenderedLineCollection, viewport: &Viewport) -> Range<usize> {
+    let visible_rows = viewport.visible_rows();
+    let wanted = visible_rows.start.saturating_sub(PAD_MARGIN);
+    let start = rendered_lines.index_at_row(wanted)
+        .map_or(wanted, |index| rendered_lines.height_up_to_index(index));
+
+    start..min(visible_rows.end + PAD_MARGIN, viewport.content_height).max(start)
+}
+
+/// Pad row the screen starts at, for a content row of a pad starting at
+/// `start`. A row past the pad, within a line taller than it, shows the last
+/// rows the pad holds rather than its first.
+pub fn screen_pad_row(row: usize, start: usize, pad_height: usize, screen_rows: usize) -> i32 {
+    let offset = row.saturating_sub(start);
+
+    if offset < pad_height { offset as i32 } else { pad_height.saturating_sub(screen_rows) as i32 }
+}
+
+/// Lines are printed into a pad holding only the rows around the screen,
+/// `painted` telling which content rows those are.
 pub struct Content {
     pub window: WINDOW,
     pub state: RefCell<State>,
+    buffer_rows: Cell<usize>,
+    /// Content rows printed into the pad, starting at its first row. None
+    /// until lines are printed again.
+    painted: Cell<Option<(usize, usize)>>,
+    /// Pattern whose matches are painted over the lines, while searching.
+    pub search_pattern: RefCell<Option<SearchPattern>>,
+    pub show_invisibles: Cell<bool>,
+    /// Lines are printed as received, without any of the display features.
+    pub raw: Cell<bool>,
+    /// Runs of blank lines are printed as a single row.
+    pub fold_blank_lines: Cell<bool>,
+    /// Lines received longer than this ago are dimmed, when set.
+    pub dim_after: Option<Duration>,
+    dim_cutoff: Cell<Option<Timespec>>,
+    /// New lines briefly shown in reverse video.
+    pub flash: RefCell<Flash>,
+    /// Dimmed at the start of wrapped rows, empty for none.
+    pub continuation_prefix: String,
+    /// Rows kept visible above and below the current match when moving to
+    /// another one.
+    pub scrolloff: usize,
+    /// Silences longer than this many milliseconds are marked, when set.
+    pub gap_threshold: Cell<Option<i64>>,
+    /// Lines marked as a range, shown between marker rows.
+    pub line_range: Cell<Option<LineRange>>,
+    /// Runs of search matches left unpainted on crowded rows, since the
+    /// search was last drawn.
+    pub truncated_highlights: Cell<usize>,
+    /// Oldest lines of the tab left out when last printed, their rows being
+    /// past the buffer.
+    pub left_out: Cell<usize>,
+    /// Offsets of the displayed text of the line being printed that start a
+    /// row, the next one last, and how much of it was printed.
+    row_starts: RefCell<Vec<usize>>,
+    printed: Cell<usize>,
+    /// Rows the line being printed wraps onto, when already known.
+    pub line_rows: RefCell<Option<Rc<WrappedRows>>>,
 }
 
 impl Content {
     pub fn new(width: i32) -> Content {
         Content {
-            window: newpad(WINDOW_HEIGHT, width),
+            window: newpad((2 * PAD_MARGIN) as i32, width),
             state: RefCell::new(State::default()),
+            buffer_rows: Cell::new(DEFAULT_BUFFER_ROWS),
+            painted: Cell::new(None),
+            search_pattern: RefCell::new(None),
+            show_invisibles: Cell::new(false),
+            raw: Cell::new(false),
+            fold_blank_lines: Cell::new(false),
+            dim_after: None,
+            dim_cutoff: Cell::new(None),
+            flash: RefCell::new(Flash::default()),
+            continuation_prefix: DEFAULT_CONTINUATION_PREFIX.to_string(),
+            scrolloff: 0,
+            gap_threshold: Cell::new(None),
+            line_range: Cell::new(None),
+            truncated_highlights: Cell::new(0),
+            left_out: Cell::new(0),
+            row_starts: RefCell::new(vec![]),
+            printed: Cell::new(0),
+            line_rows: RefCell::new(None),
         }
     }
 
+    /// Forgets the printed rows, so that nothing is drawn until the pad is
+    /// prepared for new ones.
     pub fn clear(&self) {
         wclear(self.window);
+        self.painted.set(None);
     }
 
     pub fn resize(&self, width: i32) {
-        wresize(self.window, WINDOW_HEIGHT, width);
-        wrefresh(self.window);
+        wresize(self.window, getmaxy(self.window), width);
+        self.painted.set(None);
+    }
+
+    pub fn buffer_rows(&self) -> usize {
+        self.buffer_rows.get()
+    }
+
+    pub fn set_buffer_rows(&self, rows: usize) {
+        self.buffer_rows.set(rows);
+    }
+
+    /// Clears the pad to print the given content rows into, growing it when
+    /// a line taller than the margin starts above the screen.
+    pub fn prepare(&self, rows: Range<usize>) {
+        let height = min(rows.len(), MAX_PAD_HEIGHT) as i32;
+        if height > getmaxy(self.window) {
+            wresize(self.window, height, getmaxx(self.window));
+        }
+
+        wclear(self.window);
+        self.painted.set(Some((rows.start, rows.end)));
+    }
+
+    /// Whether the pad holds all the given content rows.
+    pub fn holds(&self, rows: &Range<usize>) -> bool {
+        match self.painted.get() {
+            Some((start, end)) => start <= rows.start && rows.end <= end,
+            None => false,
+        }
+    }
+
+    /// Pad row a content row is printed on, when the pad holds it.
+    pub fn pad_row(&self, row: usize) -> Option<i32> {
+        let start = self.painted.get()?.0;
+
+        if row >= start && row - start < getmaxy(self.window) as usize {
+            Some((row - start) as i32)
+        } else {
+            None
+        }
+    }
+
+    /// Pad row to show the screen from, given its first content row.
+    pub fn screen_row(&self, row: usize, screen_rows: i32) -> i32 {
+        screen_pad_row(row,
+                       self.pad_top(),
+                       getmaxy(self.window) as usize,
+                       screen_rows.max(0) as usize)
+    }
+
+    /// Content row of the first row of the pad.
+    pub fn pad_top(&self) -> usize {
+        self.painted.get().map_or(0, |(start, _)| start)
     }
 
-    pub fn height(&self) -> i32 {
+    /// Moves the cursor to the start of a content row, returning false when
+    /// the pad doesn't hold it.
+    pub fn move_to(&self, row: usize) -> bool {
+        match self.pad_row(row) {
+            Some(value) => wmove(self.window, value, 0) != ERR,
+            None => false,
+        }
+    }
+
+    pub fn continuation_width(&self) -> usize {
+        self.continuation_prefix.width()
+    }
+
+    /// Called before printing each line, which starts on a row of its own.
+    /// Rows break where they were wrapped, unless that's left to be done.
+    pub fn start_line(&self, line: &Line) {
+        let starts = match *self.line_rows.borrow() {
+            Some(ref rows) => rows.rows.iter().skip(1).rev().map(|row| row.start).collect(),
+            None => {
+                let width = getmaxx(self.window) as usize;
+                let displayed = DisplayedText::new(&line.content_without_ansi,
+                                                   self.reveals_invisibles());
+                wrap_indented(&displayed.text, width, self.continuation_width())
+                    .iter()
+                    .skip(1)
+                    .rev()
+                    .map(|row| row.start)
+                    .collect()
+            }
+        };
+
+        *self.row_starts.borrow_mut() = starts;
+        self.printed.set(0);
+    }
+
+    /// Prints part of the current line, starting the rows it wraps onto
+    /// with the continuation prefix.
+    pub fn print_text(&self, text: &str) {
+        let mut row_starts = self.row_starts.borrow_mut();
+        let printed = self.printed.get();
+        self.printed.set(printed + text.len());
+
+        if row_starts.last().map_or(true, |&next| next >= printed + text.len()) {
+            wprintw(self.window, text);
+            return;
+        }
+
+        let mut start = 0;
+        for (offset, _) in clusters(text) {
+            let starts_row = row_starts.last().map_or(false, |&next| next <= printed + offset);
+            if starts_row {
+                while row_starts.last().map_or(false, |&next| next <= printed + offset) {
+                    row_starts.pop();
+                }
+                wprintw(self.window, &text[start..offset]);
+                start = offset;
+                self.print_continuation();
+            }
+        }
+
+        wprintw(self.window, &text[start..]);
+    }
+
+    fn print_continuation(&self) {
+        let (mut x, mut y) = (0, 0);
+        getyx(self.window, &mut y, &mut x);
+        // Rows left short by a wide character aren't wrapped by ncurses
+        if x > 0 {
+            wmove(self.window, y + 1, 0);
+        }
+
+        let (mut attrs, mut pair) = (0, 0);
+        wattr_get(self.window, &mut attrs, &mut pair);
+        wattr_set(self.window, A_DIM(), 0);
+        wprintw(self.window, &self.continuation_prefix);
+        wattr_set(self.window, attrs, pair);
+    }
+
+    /// Content row the cursor is on.
+    pub fn cursor_row(&self) -> usize {
         let mut current_x: i32 = 0;
         let mut current_y: i32 = 0;
         getyx(self.window, &mut current_y, &mut current_x);
 
-        current_y
+        self.pad_top() + current_y as usize
+    }
+
+    /// Moves to the row after a line started on pad row `start_y`. When its
+    /// last row was filled ncurses is already there, and a newline would
+    /// leave a blank row that `wrap` doesn't count.
+    pub fn end_line(&self, start_y: i32) {
+        let (mut x, mut y) = (0, 0);
+        getyx(self.window, &mut y, &mut x);
+
+        if x > 0 || y == start_y {
+            wclrtoeol(self.window);
+            wmove(self.window, y + 1, 0);
+        }
     }
 
-    pub fn calculate_height_change<F>(&self, callback: F) -> i32
-        where F: Fn()
-    {
-        let initial_height = self.height();
-        callback();
-        self.height() - initial_height
+    /// Recomputes the arrival time before which lines are dimmed. Called once
+    /// per redraw, so that the check made for every printed line is cheap.
+    pub fn update_dim_cutoff(&self) {
+        self.dim_cutoff.set(self.dim_after.map(|value| time::get_time() - value));
+    }
+
+    /// Invisible characters are left alone when printing lines as received.
+    pub fn reveals_invisibles(&self) -> bool {
+        self.show_invisibles.get() && !self.raw.get()
+    }
+
+    pub fn is_dimmed(&self, line: &Line) -> bool {
+        if self.raw.get() {
+            return false;
+        }
+
+        match self.dim_cutoff.get() {
+            Some(cutoff) => line.received_at < cutoff,
+            None => false,
+        }
     }
 
     pub fn highlighted_line(&self) -> MatchedLine {
@@ -76,6 +347,7 @@ pub struct State {
     pub background: i16,
     pub highlighted_line: usize,
     pub highlighted_match: usize,
+    pub highlighted_line_id: Option<usize>,
 }
 
 impl State {
@@ -86,6 +358,7 @@ impl State {
             background: COLOR_DEFAULT,
             highlighted_line: 0,
             highlighted_match: 0,
+            highlighted_line_id: None,
         }
     }
 
@@ -0,0 +1,111 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cell::{Cell, RefCell};
+
+use ncurses::*;
+
+use ui::rules::fit;
+use ui::color;
+
+static TITLE_COLOR_PAIR_ID: i16 = 1;
+static TITLE: &'static str = "Debug metrics";
+static WIDTH: i32 = 44;
+
+/// Overlay in the top right corner showing internal metrics, or any other
+/// list of rows.
+pub struct DebugOverlay {
+    pub window: WINDOW,
+    panel: PANEL,
+    title: &'static str,
+    width: i32,
+    rows: RefCell<Vec<String>>,
+    visible: Cell<bool>,
+}
+
+impl DebugOverlay {
+    pub fn new() -> DebugOverlay {
+        DebugOverlay::with_title(TITLE, WIDTH)
+    }
+
+    pub fn with_title(title: &'static str, width: i32) -> DebugOverlay {
+        let window = newwin(1, 1, 0, 0);
+        let panel = new_panel(window);
+        hide_panel(panel);
+
+        DebugOverlay {
+            window: window,
+            panel: panel,
+            title: title,
+            width: width,
+            rows: RefCell::new(vec![]),
+            visible: Cell::new(false),
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible.get()
+    }
+
+    pub fn toggle(&self) {
+        self.visible.set(!self.visible.get());
+
+        if self.visible.get() {
+            show_panel(self.panel);
+        } else {
+            hide_panel(self.panel);
+        }
+        update_panels();
+        doupdate();
+    }
+
+    pub fn set_rows(&self, rows: Vec<String>) {
+        *self.rows.borrow_mut() = rows;
+    }
+
+    pub fn render(&self) {
+        if !self.visible.get() {
+            return;
+        }
+
+        let rows = self.rows.borrow();
+        let width = self.width.min(COLS());
+
+        wresize(self.window, rows.len() as i32 + 1, width);
+        mvwin(self.window, 0, COLS() - width);
+        werase(self.window);
+
+        wattron(self.window, color::pair_attr(TITLE_COLOR_PAIR_ID, A_BOLD()));
+        mvwprintw(self.window, 0, 0, &fit(&format!(" {}", self.title), width));
+        wattroff(self.window, color::pair_attr(TITLE_COLOR_PAIR_ID, A_BOLD()));
+
+        for (i, row) in rows.iter().enumerate() {
+            mvwprintw(self.window, i as i32 + 1, 0, &fit(&format!(" {}", row), width));
+        }
+
+        wrefresh(self.window);
+    }
+
+    /// Draws the overlay again after the content was refreshed underneath.
+    pub fn refresh(&self) {
+        if self.visible.get() {
+            touchwin(self.window);
+            wrefresh(self.window);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/// Transient things Escape dismisses, from the first to go to the last.
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub enum Layer {
+    Prompt,
+    Errors,
+    Rules,
+    Patterns,
+    DebugOverlay,
+    CaptureStats,
+    Ruler,
+    ColumnCursor,
+    QuickFind,
+    PinnedView,
+    Range,
+    SearchHighlights,
+}
+
+impl Layer {
+    /// Shown in the status bar while it's the next one to be dismissed.
+    pub fn hint(&self) -> &'static str {
+        match *self {
+            Layer::Prompt => "esc: cancel",
+            Layer::Errors => "esc: dismiss errors",
+            Layer::Rules => "esc: close rules",
+            Layer::Patterns => "esc: close highlight hits",
+            Layer::DebugOverlay => "esc: close overlay",
+            Layer::CaptureStats => "esc: close stats",
+            Layer::Ruler => "esc: hide ruler",
+            Layer::ColumnCursor => "esc: hide column cursor",
+            Layer::QuickFind => "esc: clear find",
+            Layer::PinnedView => "esc: unpin",
+            Layer::Range => "esc: clear range",
+            Layer::SearchHighlights => "esc: clear search",
+        }
+    }
+}
+
+/// Stack of what's currently open, kept in dismissal order so that Escape
+/// always closes the topmost one and never quits.
+#[derive(Default)]
+pub struct Dismissals {
+    layers: Vec<Layer>,
+}
+
+impl Dismissals {
+    /// Registers a layer as open or closed.
+    pub fn set(&mut self, layer: Layer, active: bool) {
+        self.layers.retain(|value| *value != layer);
+
+        if active {
+            let position = self.layers.iter().position(|value| *value > layer).unwrap_or(self.layers.len());
+            self.layers.insert(position, layer);
+        }
+    }
+
+    pub fn top(&self) -> Option<Layer> {
+        self.layers.first().cloned()
+    }
+
+    pub fn hint(&self) -> Option<&'static str> {
+        self.top().map(|layer| layer.hint())
+    }
+}
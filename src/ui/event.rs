@@ -17,8 +17,11 @@
  */
 
 use ui::input::*;
-use ui::readline;
 use ui::navigation::State as NavigationState;
+use ui::prompt::{PromptKind, PromptAction};
+use ui::search::Recall;
+use ui::chords::Chord;
+use core::pattern_stats::SortColumn;
 
 pub enum Direction {
     Left,
@@ -27,12 +30,31 @@ pub enum Direction {
 
 #[derive(Clone, Hash, PartialEq, Eq)]
 pub enum SearchAction {
-    ReadInput(Vec<i32>),
+    Edit(PromptAction),
+    /// Puts other text in place of the search.
+    Replace(String),
+    Recall(Recall),
     ToggleFilterMode,
+    ToggleWholeWord,
+    ToggleFieldFallback,
+    ToggleRegex,
+    ToggleExpressions,
+    /// Keeps the search as a rule of the tab, hiding its matches when set.
+    KeepAsRule(bool),
     FindNextMatch,
     FindPreviousMatch,
 }
 
+/// Operations on the line actions apply to.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LineAction {
+    QuickAction,
+    ShowRaw,
+    MarkRange,
+    /// Offers rules hiding lines like this one.
+    Exclude,
+}
+
 pub enum Offset {
     Line(i32),
     Viewport(i32),
@@ -42,42 +64,123 @@ pub enum Offset {
 
 pub enum Event {
     ScrollContents(Offset),
+    Wheel(i32, i32, i32),
+    Inspect(i32, i32),
     SelectMenuItem(Direction),
     Navigation(NavigationState),
     Search(SearchAction),
+    Dismiss,
+    ClearBuffer,
+    Snapshot,
+    /// Copies the tab into a read-only one that new lines don't change.
+    FreezeTab,
+    CloseTab,
+    ToggleRaw,
+    ToggleAnchor,
+    /// Id of the targeted line, filled in as soon as the key is read so that
+    /// lines arriving before it's handled can't shift it.
+    OnLine(LineAction, Option<usize>),
+    ClearHighlights,
+    /// Stops or resumes highlighting a pattern, by its position.
+    ToggleHighlight(usize),
+    /// Stops or resumes alerts jumping to the tab they fired on.
+    SnoozeAlerts,
+    ToggleSampling,
+    /// Opens the marked lines set aside once evicted.
+    ShowPreserved,
+    /// Takes back the last change of what's shown.
+    Undo,
+    Redo,
+    ToggleInvisibles,
+    ToggleBlankFolding,
+    ToggleRuler,
+    ToggleSidebar,
+    ToggleColumnCursor,
+    ToggleDebugOverlay,
+    ToggleCaptureStats,
+    ReviewErrors,
+    NextGap,
+    ActOnRange,
+    Paste(String),
+    OpenPrompt(PromptKind),
+    Prompt(PromptAction),
+    Rules(RulesAction),
+    Patterns(PatternsAction),
+    Replay(ReplayAction),
+    Chord(Chord),
+    PendingChord(Option<String>),
     Resize,
+    /// Stops a search done a chunk at a time.
+    CancelSearch,
+    /// Quits, once confirmed when there's something to lose.
     Quit,
+    /// Quits without asking.
+    ForceQuit,
     Other,
 }
 
+pub enum RulesAction {
+    Previous,
+    Next,
+    Toggle,
+    Edit,
+    Delete,
+    MoveUp,
+    MoveDown,
+}
+
+pub enum PatternsAction {
+    Previous,
+    Next,
+    Sort(SortColumn),
+    Jump,
+    Reset,
+    ResetAll,
+}
+
+pub enum ReplayAction {
+    TogglePause,
+    Faster,
+    Slower,
+    SkipGap,
+}
+
 #[derive(Clone, Hash, PartialEq, Eq)]
 pub enum QueuedEvent {
     Unhighlight(SearchAction),
     PerformSearch,
+    /// Searches the next chunk of a long search.
+    ContinueSearch,
+    CountMatches,
 }
 
 pub struct EventBuilder {
     input: Input,
-    key: i32,
 }
 
 impl EventBuilder {
-    pub fn new(input: Input, key: i32) -> EventBuilder {
-        EventBuilder {
-            input: input,
-            key: key,
-        }
+    pub fn new(input: Input) -> EventBuilder {
+        EventBuilder { input: input }
     }
 
     pub fn construct(&self, current_navigation_state: &NavigationState) -> Event {
-        let mut result = self.create_global_event();
+        let result = match *current_navigation_state {
+            // Cursor movement keys edit the prompt instead of scrolling
+            NavigationState::Prompt => {
+                self.create_prompt_event().or_else(|| self.create_global_event())
+            }
+            NavigationState::Rules => {
+                self.create_rules_event().or_else(|| self.create_global_event())
+            }
+            NavigationState::Patterns => {
+                self.create_patterns_event().or_else(|| self.create_global_event())
+            }
+            NavigationState::Menu => self.create_global_event().or_else(|| self.create_menu_event()),
+            NavigationState::Search => {
+                self.create_global_event().or_else(|| self.create_search_event())
+            }
+        };
 
-        if result.is_none() {
-            result = match *current_navigation_state {
-                NavigationState::Menu => self.create_menu_event(),
-                NavigationState::Search => self.create_search_event(),
-            };
-        }
         result.unwrap_or(Event::Other)
     }
 
@@ -89,11 +192,131 @@ impl EventBuilder {
             Input::Kb(Key::Char('F'), Some(Modifier::Ctrl)) => {
                 Some(Event::Navigation(NavigationState::Search))
             }
+            Input::Kb(Key::Char('G'), Some(Modifier::Ctrl)) => {
+                Some(Event::OpenPrompt(PromptKind::GotoLine))
+            }
+            Input::Kb(Key::Char('@'), None) => Some(Event::OpenPrompt(PromptKind::GotoTime)),
+            Input::Kb(Key::Char('f'), None) => Some(Event::OpenPrompt(PromptKind::QuickFind)),
+            Input::Kb(Key::Char('i'), None) => Some(Event::ToggleInvisibles),
+            Input::Kb(Key::Char('B'), None) => Some(Event::ToggleBlankFolding),
+            Input::Kb(Key::Char('c'), None) => Some(Event::ToggleRuler),
+            Input::Kb(Key::Char('I'), None) => Some(Event::ToggleSidebar),
+            Input::Kb(Key::Char('|'), None) => Some(Event::ToggleColumnCursor),
+            Input::Kb(Key::Char('D'), None) => Some(Event::ToggleDebugOverlay),
+            Input::Kb(Key::Char('%'), None) => Some(Event::ToggleCaptureStats),
+            Input::Kb(Key::Char('E'), None) => Some(Event::OnLine(LineAction::ShowRaw, None)),
+            Input::Kb(Key::Char('!'), None) => Some(Event::ReviewErrors),
+            Input::Kb(Key::Char('}'), None) => Some(Event::NextGap),
+            Input::Kb(Key::Char('S'), None) => Some(Event::OpenPrompt(PromptKind::GapThreshold)),
+            Input::Kb(Key::Char('m'), None) => Some(Event::OnLine(LineAction::MarkRange, None)),
+            Input::Kb(Key::Char('M'), None) => Some(Event::ActOnRange),
+            Input::Kb(Key::Char('T'), None) => Some(Event::OpenPrompt(PromptKind::RangeTimes)),
+            Input::Kb(Key::Char('t'), None) => Some(Event::OpenPrompt(PromptKind::RegexTester)),
+            Input::Kb(Key::Char('p'), None) => Some(Event::OpenPrompt(PromptKind::Profile)),
+            Input::Kb(Key::Char('w'), None) => Some(Event::OpenPrompt(PromptKind::ExportMatches)),
+            Input::Kb(Key::Char('b'), None) => Some(Event::OpenPrompt(PromptKind::BufferLines)),
+            Input::Kb(Key::Char('L'), Some(Modifier::Ctrl)) => Some(Event::ClearBuffer),
+            Input::Kb(Key::Char('s'), None) => Some(Event::Snapshot),
+            Input::Kb(Key::Char('C'), None) => Some(Event::FreezeTab),
+            Input::Kb(Key::Char('X'), None) => Some(Event::CloseTab),
+            Input::Kb(Key::Char('R'), None) => Some(Event::OpenPrompt(PromptKind::Rewrite)),
+            Input::Kb(Key::Char('r'), None) => Some(Event::ToggleRaw),
+            Input::Kb(Key::Char('a'), None) => Some(Event::ToggleAnchor),
+            Input::Kb(Key::Char('x'), None) => Some(Event::OnLine(LineAction::QuickAction, None)),
+            Input::Kb(Key::Char('N'), None) => Some(Event::OnLine(LineAction::Exclude, None)),
+            Input::Kb(Key::Char('h'), None) => Some(Event::OpenPrompt(PromptKind::Highlight)),
+            Input::Kb(Key::Char('H'), None) => Some(Event::ClearHighlights),
+            Input::Kb(Key::Char(value), Some(Modifier::Alt(_))) if value >= '1' && value <= '9' => {
+                Some(Event::ToggleHighlight(value as usize - '1' as usize))
+            }
+            Input::Kb(Key::Char('A'), None) => Some(Event::SnoozeAlerts),
+            Input::Kb(Key::Char('O'), None) => Some(Event::ToggleSampling),
+            Input::Kb(Key::Char('V'), None) => Some(Event::ShowPreserved),
+            Input::Kb(Key::Char('u'), None) => Some(Event::Undo),
+            Input::Kb(Key::Char('R'), Some(Modifier::Ctrl)) => Some(Event::Redo),
+            Input::Kb(Key::Char('F'), None) => Some(Event::Navigation(NavigationState::Rules)),
+            Input::Kb(Key::Char('P'), None) => Some(Event::Navigation(NavigationState::Patterns)),
+            Input::Kb(Key::Char(' '), None) => Some(Event::Replay(ReplayAction::TogglePause)),
+            Input::Kb(Key::Char('>'), None) => Some(Event::Replay(ReplayAction::Faster)),
+            Input::Kb(Key::Char('<'), None) => Some(Event::Replay(ReplayAction::Slower)),
+            Input::Kb(Key::Char(']'), None) => Some(Event::Replay(ReplayAction::SkipGap)),
+            Input::Kb(Key::Escape, None) => Some(Event::Dismiss),
             Input::Kb(Key::Char('q'), None) => Some(Event::Quit),
+            Input::Kb(Key::Char('Q'), None) => Some(Event::ForceQuit),
+            Input::Paste(ref text) => Some(Event::Paste(sanitize_paste(text))),
             _ => None,
         }
     }
 
+    fn create_rules_event(&self) -> Option<Event> {
+        let action = match self.input {
+            Input::Kb(Key::Up, None) => RulesAction::Previous,
+            Input::Kb(Key::Down, None) => RulesAction::Next,
+            Input::Kb(Key::Char(' '), None) => RulesAction::Toggle,
+            Input::Kb(Key::Char('e'), None) => RulesAction::Edit,
+            Input::Kb(Key::Char('d'), None) => RulesAction::Delete,
+            Input::Kb(Key::Char('K'), None) => RulesAction::MoveUp,
+            Input::Kb(Key::Char('J'), None) => RulesAction::MoveDown,
+            Input::Kb(Key::Char('F'), None) |
+            Input::Kb(Key::Escape, None) => return Some(Event::Navigation(NavigationState::Menu)),
+            Input::Kb(Key::Char('q'), None) => return Some(Event::Quit),
+            Input::Kb(Key::Char('Q'), None) => return Some(Event::ForceQuit),
+            _ => return None,
+        };
+
+        Some(Event::Rules(action))
+    }
+
+    fn create_patterns_event(&self) -> Option<Event> {
+        let action = match self.input {
+            Input::Kb(Key::Up, None) => PatternsAction::Previous,
+            Input::Kb(Key::Down, None) => PatternsAction::Next,
+            Input::Kb(Key::Char('t'), None) => PatternsAction::Sort(SortColumn::Total),
+            Input::Kb(Key::Char('m'), None) => PatternsAction::Sort(SortColumn::Recent),
+            Input::Kb(Key::Char('l'), None) => PatternsAction::Sort(SortColumn::Last),
+            Input::Kb(Key::Char('M'), Some(Modifier::Ctrl)) => PatternsAction::Jump,
+            Input::Kb(Key::Char('r'), None) => PatternsAction::Reset,
+            Input::Kb(Key::Char('R'), None) => PatternsAction::ResetAll,
+            Input::Kb(Key::Char('P'), None) |
+            Input::Kb(Key::Escape, None) => return Some(Event::Navigation(NavigationState::Menu)),
+            Input::Kb(Key::Char('q'), None) => return Some(Event::Quit),
+            Input::Kb(Key::Char('Q'), None) => return Some(Event::ForceQuit),
+            _ => return None,
+        };
+
+        Some(Event::Patterns(action))
+    }
+
+    fn create_prompt_event(&self) -> Option<Event> {
+        self.create_prompt_action().map(Event::Prompt)
+    }
+
+    /// Editing keys, shared by the prompts and the search bar.
+    fn create_prompt_action(&self) -> Option<PromptAction> {
+        let action = match self.input {
+            Input::Kb(Key::Left, None) => PromptAction::Left,
+            Input::Kb(Key::Right, None) => PromptAction::Right,
+            Input::Kb(Key::Home, None) => PromptAction::Home,
+            Input::Kb(Key::End, None) => PromptAction::End,
+            Input::Kb(Key::Delete, None) => PromptAction::Delete,
+            Input::Kb(Key::Char('A'), Some(Modifier::Ctrl)) => PromptAction::Home,
+            Input::Kb(Key::Char('E'), Some(Modifier::Ctrl)) => PromptAction::End,
+            Input::Kb(Key::Backspace, None) |
+            Input::Kb(Key::Char('?'), Some(Modifier::Ctrl)) |
+            Input::Kb(Key::Char('H'), Some(Modifier::Ctrl)) => PromptAction::Backspace,
+            Input::Kb(Key::Char('U'), Some(Modifier::Ctrl)) => PromptAction::KillToStart,
+            Input::Kb(Key::Char('W'), Some(Modifier::Ctrl)) => PromptAction::KillWord,
+            Input::Kb(Key::Char('J'), Some(Modifier::Ctrl)) |
+            Input::Kb(Key::Char('M'), Some(Modifier::Ctrl)) => PromptAction::Submit,
+            Input::Kb(Key::Escape, None) => PromptAction::Cancel,
+            Input::Paste(ref text) => PromptAction::Paste(sanitize_paste(text)),
+            Input::Kb(Key::Char(value), None) if !value.is_control() => PromptAction::Insert(value),
+            _ => return None,
+        };
+
+        Some(action)
+    }
+
     fn create_search_event(&self) -> Option<Event> {
         match self.input {
             Input::Kb(Key::Char('n'), Some(Modifier::Alt(_))) => {
@@ -105,10 +328,42 @@ impl EventBuilder {
             Input::Kb(Key::Char('m'), Some(Modifier::Alt(_))) => {
                 Some(Event::Search(SearchAction::ToggleFilterMode))
             }
-            Input::Kb(Key::Escape, None) if !readline::is_history() => {
-                Some(Event::Navigation(NavigationState::Menu))
+            Input::Kb(Key::Char('w'), Some(Modifier::Alt(_))) => {
+                Some(Event::Search(SearchAction::ToggleWholeWord))
+            }
+            Input::Kb(Key::Char('u'), Some(Modifier::Alt(_))) => {
+                Some(Event::Search(SearchAction::ToggleFieldFallback))
+            }
+            Input::Kb(Key::Char('r'), Some(Modifier::Alt(_))) => {
+                Some(Event::Search(SearchAction::ToggleRegex))
+            }
+            Input::Kb(Key::Char('e'), Some(Modifier::Alt(_))) => {
+                Some(Event::Search(SearchAction::ToggleExpressions))
+            }
+            Input::Kb(Key::Char('k'), Some(Modifier::Alt(_))) => {
+                Some(Event::Search(SearchAction::KeepAsRule(false)))
+            }
+            Input::Kb(Key::Char('x'), Some(Modifier::Alt(_))) => {
+                Some(Event::Search(SearchAction::KeepAsRule(true)))
+            }
+            Input::Kb(Key::Escape, None) => Some(Event::Navigation(NavigationState::Menu)),
+            Input::Kb(Key::Char('P'), Some(Modifier::Ctrl)) => {
+                Some(Event::Search(SearchAction::Recall(Recall::Previous)))
+            }
+            Input::Kb(Key::Char('N'), Some(Modifier::Ctrl)) => {
+                Some(Event::Search(SearchAction::Recall(Recall::Next)))
+            }
+            Input::Kb(Key::Char('R'), Some(Modifier::Ctrl)) => {
+                Some(Event::Search(SearchAction::Recall(Recall::Matching)))
+            }
+            _ => {
+                match self.create_prompt_action() {
+                    Some(PromptAction::Submit) |
+                    Some(PromptAction::Cancel) |
+                    None => None,
+                    Some(action) => Some(Event::Search(SearchAction::Edit(action))),
+                }
             }
-            _ => self.create_input_event(),
         }
     }
 
@@ -120,41 +375,10 @@ impl EventBuilder {
             Input::Kb(Key::PageDown, None) => Some(Event::ScrollContents(Offset::Viewport(-1))),
             Input::Kb(Key::Home, None) => Some(Event::ScrollContents(Offset::Top)),
             Input::Kb(Key::End, None) => Some(Event::ScrollContents(Offset::Bottom)),
+            Input::Wheel(direction, y, x) => Some(Event::Wheel(direction, y, x)),
+            Input::Click(row, column) => Some(Event::Inspect(row, column)),
             Input::Resize => Some(Event::Resize),
             _ => None,
         }
     }
-
-    fn create_input_event(&self) -> Option<Event> {
-        match self.input {
-            Input::Kb(Key::Left, None) => {
-                Some(Event::Search(SearchAction::ReadInput(KEY_LEFT_SEQ.to_vec())))
-            }
-            Input::Kb(Key::Right, None) => {
-                Some(Event::Search(SearchAction::ReadInput(KEY_RIGHT_SEQ.to_vec())))
-            }
-            Input::Kb(Key::Home, None) => {
-                Some(Event::Search(SearchAction::ReadInput(KEY_HOME_SEQ.to_vec())))
-            }
-            Input::Kb(Key::End, None) => {
-                Some(Event::Search(SearchAction::ReadInput(KEY_END_SEQ.to_vec())))
-            }
-            Input::Kb(Key::Delete, None) => {
-                let mut keys = KEY_RIGHT_SEQ.to_vec();
-                keys.extend(KEY_BACKSPACE_SEQ.to_vec());
-                Some(Event::Search(SearchAction::ReadInput(keys)))
-            }
-            Input::Kb(Key::Backspace, None) => {
-                Some(Event::Search(SearchAction::ReadInput(KEY_BACKSPACE_SEQ.to_vec())))
-            }
-            Input::Kb(_, ref modifier) => {
-                let mut keys = vec![self.key];
-                if let Some(Modifier::Alt(value)) = *modifier {
-                    keys.push(value)
-                };
-                Some(Event::Search(SearchAction::ReadInput(keys)))
-            }
-            _ => None,
-        }
-    }
 }
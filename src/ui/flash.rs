@@ -0,0 +1,75 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::VecDeque;
+
+/// Milliseconds a new line stays flashed for.
+pub const FLASH_DURATION: u64 = 1_000;
+/// Milliseconds over which the arrival rate is measured.
+const RATE_WINDOW: u64 = 60_000;
+
+/// Briefly marks lines arriving on a quiet log, so that a single one doesn't
+/// go unnoticed. Busy logs aren't flashed, as everything would be.
+#[derive(Default)]
+pub struct Flash {
+    /// Lines flashed while fewer than this many arrive a minute, when set.
+    max_rate: Option<usize>,
+    /// Batches of lines received within the rate window, with their time.
+    arrivals: VecDeque<(u64, usize)>,
+    /// Ids of the lines flashed, along with when they stop being.
+    effects: Vec<(usize, u64)>,
+}
+
+impl Flash {
+    pub fn new(max_rate: Option<usize>) -> Flash {
+        Flash { max_rate: max_rate, ..Flash::default() }
+    }
+
+    /// Records `count` lines arriving at `now`, returning whether they're to
+    /// be flashed, which they aren't when lines arrive too fast.
+    pub fn arrive(&mut self, count: usize, now: u64) -> bool {
+        let max_rate = match self.max_rate {
+            Some(value) => value,
+            None => return false,
+        };
+
+        while self.arrivals.front().map_or(false, |&(time, _)| time + RATE_WINDOW <= now) {
+            self.arrivals.pop_front();
+        }
+        self.arrivals.push_back((now, count));
+
+        self.arrivals.iter().map(|&(_, count)| count).sum::<usize>() <= max_rate
+    }
+
+    /// Flashes the lines with the given ids, from `now` on.
+    pub fn start(&mut self, ids: &[usize], now: u64) {
+        self.effects.extend(ids.iter().map(|&id| (id, now + FLASH_DURATION)));
+    }
+
+    pub fn is_flashed(&self, id: usize) -> bool {
+        self.effects.iter().any(|&(flashed, _)| flashed == id)
+    }
+
+    /// Drops the effects that ran out, returning whether any did.
+    pub fn expire(&mut self, now: u64) -> bool {
+        let count = self.effects.len();
+        self.effects.retain(|&(_, expires_at)| expires_at > now);
+
+        self.effects.len() != count
+    }
+}
@@ -16,98 +16,607 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use std::cell::RefCell;
+use std::ops::Range;
+
 use ncurses::*;
+use time;
 
 use core::buffer::BufferLines;
-use ui::readline;
+use ext::terminal::resizeterm;
+use core::line::SearchPattern;
+use core::health::Health;
 use ui::color;
-use ui::input::read_key;
+use ui::input::{Input, read_key, enable_mouse, enable_bracketed_paste, disable_bracketed_paste};
 use ui::event::{EventBuilder, Event};
-use ui::navigation::{Navigation, HEIGHT as NAVIGATION_HEIGHT};
-use ui::content::Content;
-use ui::printer::LinesPrinter;
+use ui::navigation::{Navigation, State as NavigationState};
+use ui::content::{self, Content};
+use ui::printer::{LinesPrinter, folded_text};
 use ui::search::Query;
 use ui::rendered_line::RenderedLineCollection;
+use ui::viewport::{Viewport, Anchor};
+use ui::layout::{Area, Layout, Widget, WidgetMap};
+use ui::placeholder::Placeholder;
+use ui::ruler::Ruler;
+use ui::debug_overlay::DebugOverlay;
+use ui::regex_tester::RegexTester;
+use ui::sidebar::{self, Sidebar};
+use ui::column_cursor::ColumnCursor;
+use ui::quick_find::{self, QuickFind};
+use ui::chords::{Chords, ChordResult};
+use ui::dismiss::{Dismissals, Layer};
+use ui::highlighter::{DisplayedText, LineHighlighter};
+use ui::size_probe;
+use utils::wrap::{char_index_at_indented, glyph_at_indented, line_column};
 
 pub static NORMAL_HIGHLIGHT_COLOR: i16 = 5;
 pub static CURRENT_HIGHLIGHT_COLOR: i16 = 6;
+pub static ERROR_COLOR: i16 = 4;
+
+static INVISIBLES_INDICATOR: &'static str = "invisibles";
+static TOP_ANCHOR_INDICATOR: &'static str = "top";
+static PINNED_INDICATOR: &'static str = "pinned";
+static FOLDED_INDICATOR: &'static str = "folded blanks";
+static RAW_INDICATOR: &'static str = "raw";
+static SNOOZED_INDICATOR: &'static str = "alerts snoozed";
+static ERRORS_TITLE: &'static str = "Errors and source changes (newest first)";
+static ERRORS_WIDTH: i32 = 72;
+static STATS_TITLE: &'static str = "Captured numbers";
+static STATS_WIDTH: i32 = 48;
 
 pub struct Frame {
+    /// Columns of the content, the sidebar excluded.
     pub width: i32,
     pub height: i32,
+    pub layout: Layout,
     pub rendered_lines: RenderedLineCollection,
     pub initial_rendered_lines: Option<RenderedLineCollection>,
     pub navigation: Navigation,
     pub content: Content,
+    pub ruler: Ruler,
+    pub debug_overlay: DebugOverlay,
+    pub error_overlay: DebugOverlay,
+    pub stats_overlay: DebugOverlay,
+    pub regex_tester: RegexTester,
+    pub sidebar: Sidebar,
+    pub column_cursor: ColumnCursor,
+    pub quick_find: QuickFind,
+    /// Multi-key sequences are only read with the vim keymap.
+    pub chords: Option<RefCell<Chords>>,
+    pub dismissals: RefCell<Dismissals>,
+    /// Areas of the widgets, built again when the layout changes.
+    widgets: RefCell<WidgetMap>,
+    placeholder: Placeholder,
 }
 
 impl Frame {
     pub fn new(menu_item_names: Vec<String>) -> Frame {
         // Init order is important
         env_init();
-        readline::init();
         ncurses_init();
         color::generate_pairs();
 
+        let layout = Layout::new(COLS(), LINES(), false);
+
         Frame {
-            width: COLS(),
-            height: LINES(),
+            width: layout.width,
+            height: layout.height,
+            layout: layout,
             rendered_lines: RenderedLineCollection::default(),
             initial_rendered_lines: None,
-            navigation: Navigation::new(LINES() - NAVIGATION_HEIGHT, 0, &menu_item_names),
-            content: Content::new(COLS()),
+            navigation: Navigation::new(layout.navigation_offset(), 0, &menu_item_names),
+            content: Content::new(layout.width),
+            ruler: Ruler::new(),
+            debug_overlay: DebugOverlay::new(),
+            error_overlay: DebugOverlay::with_title(ERRORS_TITLE, ERRORS_WIDTH),
+            stats_overlay: DebugOverlay::with_title(STATS_TITLE, STATS_WIDTH),
+            regex_tester: RegexTester::new(),
+            sidebar: Sidebar::new(),
+            column_cursor: ColumnCursor::default(),
+            quick_find: QuickFind::default(),
+            chords: None,
+            dismissals: RefCell::new(Dismissals::default()),
+            widgets: RefCell::new(WidgetMap::new(&layout)),
+            placeholder: Placeholder::new(),
         }
     }
 
     pub fn render(&self) {
-        readline::render("Search:", self.navigation.search.input_field.window);
-
         self.navigation.render();
+        self.cover_if_too_small();
+    }
+
+    /// Shows the placeholder over anything drawn while the terminal is too
+    /// small, returning whether it did.
+    fn cover_if_too_small(&self) -> bool {
+        let too_small = self.layout.is_too_small();
+        if too_small {
+            self.placeholder.render(self.layout.width, self.layout.height);
+        }
+
+        too_small
+    }
+
+    pub fn show_error(&self, message: &str) {
+        let window = self.navigation.menu.window;
+
+        werase(window);
+        wbkgd(window, color::pair_attr(ERROR_COLOR, A_REVERSE() | A_BOLD()));
+        mvwprintw(window, 0, 1, message);
+        wrefresh(window);
     }
 
     pub fn select_left_menu_item(&self) {
-        self.navigation.menu.select(REQ_LEFT_ITEM);
+        self.navigation.menu.select_previous();
+        self.navigation.render_status();
     }
 
     pub fn select_right_menu_item(&self) {
-        self.navigation.menu.select(REQ_RIGHT_ITEM);
+        self.navigation.menu.select_next();
+        self.navigation.render_status();
+    }
+
+    pub fn select_menu_item_at(&self, index: usize) {
+        self.navigation.menu.select(index);
+        self.navigation.render_status();
+    }
+
+    /// Replaces the tabs of the menu, selecting the first one.
+    pub fn set_menu_items(&mut self, names: &[String]) {
+        self.navigation.menu.set_items(names);
+        self.navigation.render_status();
+    }
+
+    /// Brings back the interface colors set up at startup, before a theme
+    /// is applied again.
+    pub fn reset_colors(&self) {
+        init_interface_pairs();
+    }
+
+    /// Appends a tab to the menu and selects it.
+    pub fn add_menu_item(&mut self, name: &str) {
+        self.navigation.menu.add_item(name);
+        self.navigation.render_status();
+    }
+
+    /// Drops the selected tab from the menu, selecting the one before it.
+    pub fn remove_menu_item(&mut self) {
+        self.navigation.menu.remove_selected();
+        self.navigation.render_status();
+    }
+
+    pub fn toggle_invisibles(&self) {
+        let active = !self.content.show_invisibles.get();
+
+        self.content.show_invisibles.set(active);
+        self.navigation.status.toggle_indicator(INVISIBLES_INDICATOR, active);
+        self.navigation.render_status();
+    }
+
+    pub fn set_blank_folding(&self, active: bool) {
+        self.content.fold_blank_lines.set(active);
+        self.navigation.status.toggle_indicator(FOLDED_INDICATOR, active);
+        self.navigation.render_status();
+    }
+
+    pub fn set_anchor_indicator(&self, anchor: Anchor) {
+        self.navigation.status.toggle_indicator(TOP_ANCHOR_INDICATOR, anchor == Anchor::Top);
+        self.navigation.render_status();
+    }
+
+    pub fn set_raw_indicator(&self, raw: bool) {
+        self.navigation.status.toggle_indicator(RAW_INDICATOR, raw);
+        self.navigation.render_status();
+    }
+
+    pub fn set_snoozed_indicator(&self, snoozed: bool) {
+        self.navigation.status.toggle_indicator(SNOOZED_INDICATOR, snoozed);
+        self.navigation.render_status();
+    }
+
+    pub fn set_source_health(&self, health: Option<Health>) {
+        self.navigation.menu.set_health(health);
+    }
+
+    pub fn set_pinned_indicator(&self, pinned: bool) {
+        self.navigation.status.toggle_indicator(PINNED_INDICATOR, pinned);
+        self.register_dismissal(Layer::PinnedView, pinned);
+    }
+
+    pub fn toggle_ruler(&mut self) {
+        self.ruler.visible = !self.ruler.visible;
+        self.layout = self.layout_for(self.layout.width, self.height);
+        self.widgets = RefCell::new(WidgetMap::new(&self.layout));
+        self.register_dismissal(Layer::Ruler, self.ruler.visible);
+    }
+
+    /// Shows or hides the sidebar. The content is narrower while it's shown,
+    /// so lines have to be wrapped again with `resize`.
+    pub fn toggle_sidebar(&mut self) {
+        self.sidebar.visible = !self.sidebar.visible;
+        self.sidebar.line_id.set(None);
+        if !self.sidebar.visible {
+            werase(self.sidebar.window);
+            wrefresh(self.sidebar.window);
+        }
+    }
+
+    fn layout_for(&self, width: i32, height: i32) -> Layout {
+        let sidebar_cols = if self.sidebar.visible { sidebar::WIDTH } else { 0 };
+
+        Layout::new(width, height, self.ruler.visible).with_sidebar(sidebar_cols)
+    }
+
+    /// Records whether something Escape should dismiss is open, updating the
+    /// hint on what it does next.
+    pub fn register_dismissal(&self, layer: Layer, active: bool) {
+        let mut dismissals = self.dismissals.borrow_mut();
+        dismissals.set(layer, active);
+
+        self.navigation.status.set_hint(dismissals.hint());
+        self.navigation.render_status();
+    }
+
+    /// Topmost widget at the given screen cell, overlays included.
+    pub fn widget_at(&self, y: i32, x: i32) -> Option<Widget> {
+        let mut widgets = self.widgets.borrow_mut();
+        widgets.hide(Widget::Rules);
+        widgets.hide(Widget::Patterns);
+        widgets.hide(Widget::Overlay);
+
+        match self.navigation.state {
+            NavigationState::Rules => {
+                widgets.show(Widget::Rules, window_area(self.navigation.rules.window))
+            }
+            NavigationState::Patterns => {
+                widgets.show(Widget::Patterns, window_area(self.navigation.patterns.window))
+            }
+            _ => {}
+        }
+        if self.regex_tester.is_visible() {
+            widgets.show(Widget::Overlay, window_area(self.regex_tester.window));
+        }
+        for overlay in &[&self.debug_overlay, &self.error_overlay, &self.stats_overlay] {
+            if overlay.is_visible() {
+                widgets.show(Widget::Overlay, window_area(overlay.window));
+            }
+        }
+
+        widgets.widget_at(y, x)
+    }
+
+    /// Index of the rendered line shown on the given row of the content.
+    pub fn line_index_at(&self, reverse_index: usize, row: usize) -> Option<usize> {
+        self.rendered_lines.index_at_row(self.viewport(reverse_index).top_row() + row)
+    }
+
+    /// Column, character index and byte offset of the position at the given
+    /// screen coordinates, within the line printed there.
+    pub fn describe_position(&self, reverse_index: usize, y: i32, x: i32) -> Option<String> {
+        let row = match self.layout.content_row(y) {
+            Some(value) if x >= 0 => value,
+            _ => return None,
+        };
+
+        let content_row = self.viewport(reverse_index).top_row() + row;
+        let index = match self.line_index_at(reverse_index, row) {
+            Some(value) => value,
+            None => return None,
+        };
+        let line_row = content_row - self.rendered_lines.height_up_to_index(index);
+
+        let text = &self.rendered_lines[index].line.content_without_ansi;
+        let displayed = DisplayedText::new(text, self.content.reveals_invisibles());
+        let char_index = match char_index_at_indented(&displayed.text,
+                                                      self.width as usize,
+                                                      self.content.continuation_width(),
+                                                      line_row,
+                                                      x as usize) {
+            Some(value) => value,
+            None => return None,
+        };
+        let offset = text.char_indices().nth(char_index).map_or(text.len(), |(value, _)| value);
+
+        Some(format!("column {}, char {}, byte {}",
+                     line_column(text, offset),
+                     char_index,
+                     offset))
+    }
+
+    /// Shows or hides the column cursor, starting at the first column.
+    pub fn toggle_column_cursor(&self) -> bool {
+        let active = self.column_cursor.column.get().is_none();
+
+        self.column_cursor.column.set(if active { Some(0) } else { None });
+        self.register_dismissal(Layer::ColumnCursor, active);
+        active
+    }
+
+    /// Moves the column cursor by the given number of columns, returning
+    /// the column it ends up at.
+    pub fn move_column_cursor(&self, offset: i32) -> Option<usize> {
+        let column = self.column_cursor.column.get().map(|value| {
+            (value as i32 + offset).max(0).min(self.width - 1) as usize
+        });
+
+        self.column_cursor.column.set(column);
+        column
+    }
+
+    // Columns taken by the glyph under the column cursor, on every visible row
+    fn mark_column(&self, viewport: &Viewport, column: usize) {
+        let window = self.content.window;
+
+        for content_row in viewport.visible_rows() {
+            let glyph = self.rendered_lines.index_at_row(content_row).and_then(|index| {
+                let text = &self.rendered_lines[index].line.content_without_ansi;
+                let displayed = DisplayedText::new(text, self.content.reveals_invisibles());
+                let line_row = content_row - self.rendered_lines.height_up_to_index(index);
+
+                glyph_at_indented(&displayed.text,
+                                  self.width as usize,
+                                  self.content.continuation_width(),
+                                  line_row,
+                                  column)
+            });
+            let columns = glyph.map_or(column..column + 1, |(_, columns)| columns);
+
+            if let Some(row) = self.content.pad_row(content_row) {
+                self.column_cursor.mark(window, row, columns);
+            }
+        }
+    }
+
+    /// Marks the matches of a find limited to the screen, returning how many
+    /// were found. Without a pattern, the marks are cleared.
+    pub fn find_on_screen(&self, reverse_index: usize, pattern: Option<&SearchPattern>) -> usize {
+        let window = self.content.window;
+        self.quick_find.clear(window);
+
+        let found = match pattern {
+            Some(pattern) => {
+                let viewport = self.viewport(reverse_index);
+                let found = quick_find::visible_matches(&self.rendered_lines,
+                                                        &viewport,
+                                                        pattern,
+                                                        self.width as usize,
+                                                        self.content.continuation_width(),
+                                                        self.content.reveals_invisibles());
+                for &(row, ref columns) in found.iter().flat_map(|segments| segments.iter()) {
+                    if let Some(row) = self.content.pad_row(row) {
+                        self.quick_find.mark(window, row, columns.clone());
+                    }
+                }
+                self.quick_find.top_row.set(Some(viewport.top_row()));
+                found.len()
+            }
+            None => 0,
+        };
+
+        self.register_dismissal(Layer::QuickFind, pattern.is_some());
+        self.scroll(reverse_index);
+        found
+    }
+
+    /// Clears the quick find marks once they're due, by `now` in milliseconds.
+    pub fn expire_quick_find(&self, reverse_index: usize, now: u64) {
+        if self.quick_find.expires_at.get().map_or(false, |value| value <= now) {
+            self.find_on_screen(reverse_index, None);
+        }
+    }
+
+    /// Hands the terminal over to the callback, restoring the screen after.
+    pub fn suspend<F, T>(&self, callback: F) -> T
+        where F: FnOnce() -> T
+    {
+        disable_bracketed_paste();
+        def_prog_mode();
+        endwin();
+
+        let result = callback();
+
+        reset_prog_mode();
+        enable_bracketed_paste();
+        refresh();
+        self.render();
+
+        result
     }
 
     pub fn destroy(&self) {
-        self.navigation.destroy();
+        disable_bracketed_paste();
         endwin();
-        readline::terminate();
     }
 
+    /// Lays the widgets out for the new terminal size. The navigation bar is
+    /// drawn by `render`, once the content under it has been printed again.
     pub fn resize(&mut self) {
-        getmaxyx(stdscr(), &mut self.height, &mut self.width);
+        if let Some((rows, columns)) = size_probe::measure() {
+            if (rows, columns) != (LINES(), COLS()) {
+                unsafe {
+                    resizeterm(rows, columns);
+                }
+            }
+        }
+
+        let (mut height, mut width) = (0, 0);
+        getmaxyx(stdscr(), &mut height, &mut width);
+
+        self.layout = self.layout_for(width, height);
+        self.widgets = RefCell::new(WidgetMap::new(&self.layout));
+        self.width = self.layout.content_width;
+        self.height = self.layout.height;
+        if !self.layout.is_too_small() {
+            self.placeholder.hide();
+        }
 
         self.content.resize(self.width);
-        self.navigation.resize(self.width, self.content_height());
+        self.ruler.resize(self.width);
+        self.navigation.resize(self.layout.width, self.layout.navigation_offset());
+        self.sidebar.resize(self.layout.content_offset(),
+                            self.width,
+                            self.layout.content_rows,
+                            self.layout.sidebar_cols);
+        self.sidebar.line_id.set(None);
+        self.regex_tester.resize(self.layout.navigation_offset());
     }
 
     pub fn print(&mut self, buffer_lines: &mut BufferLines, query: Option<Query>) {
         buffer_lines.width = self.width as usize;
+        buffer_lines.max_rows = self.content.buffer_rows();
 
         LinesPrinter::new(self, buffer_lines, query).draw();
-        self.scroll(buffer_lines.buffer.reverse_index.get() as i32);
+        self.scroll(buffer_lines.buffer.reverse_index());
     }
 
-    pub fn scroll(&self, reversed_offset: i32) {
-        let offset = self.rendered_lines.height() - self.height + NAVIGATION_HEIGHT -
-                     reversed_offset;
-        prefresh(self.content.window,
-                 offset,
-                 0,
-                 0,
-                 0,
-                 self.content_height() - 1,
-                 self.width);
+    /// Searches some of the rendered lines, for searches done a chunk at a
+    /// time. Matches found so far stay painted.
+    pub fn search_lines(&mut self,
+                        buffer_lines: &mut BufferLines,
+                        query: Query,
+                        lines: Range<usize>) {
+        buffer_lines.width = self.width as usize;
+        buffer_lines.max_rows = self.content.buffer_rows();
+
+        LinesPrinter::new(self, buffer_lines, Some(query)).searching(lines).draw();
+        self.scroll(buffer_lines.buffer.reverse_index());
+    }
+
+    pub fn viewport(&self, reverse_index: usize) -> Viewport {
+        Viewport::new(reverse_index,
+                      self.content_height() as usize,
+                      self.rendered_lines.height())
+    }
+
+    /// Prints the lines around the viewport into the pad, painting the
+    /// matches of the search over the ones having any.
+    pub fn paint(&self, viewport: &Viewport) {
+        let rows = content::pad_rows(&self.rendered_lines, viewport);
+        self.content.prepare(rows.clone());
+
+        let pattern = self.content.search_pattern.borrow();
+        let truncated = self.content.truncated_highlights.get();
+        let mut row = 0;
+
+        for rendered_line in &self.rendered_lines.entries {
+            if row >= rows.end {
+                break;
+            }
+            if row >= rows.start {
+                rendered_line.print(&self.content, row);
+                match *pattern {
+                    Some(ref pattern) if rendered_line.found_matches.is_some() => {
+                        rendered_line.highlight(pattern, &self.content, self.width, row);
+                    }
+                    _ => {}
+                }
+            }
+            row += rendered_line.height;
+        }
+
+        // Already counted when the lines were searched
+        self.content.truncated_highlights.set(truncated);
+    }
+
+    /// Paints the match the search is on, with the given color.
+    pub fn highlight_match(&self, pattern: &SearchPattern, color: i16) {
+        let state = self.content.state.borrow();
+        let rendered_line = match self.rendered_lines.entries.get(state.highlighted_line) {
+            Some(value) => value,
+            None => return,
+        };
+        let match_count = rendered_line.found_matches.as_ref().map_or(0, |spans| spans.len());
+        if state.highlighted_match >= match_count {
+            return;
+        }
+
+        let accumulated_height = self.rendered_lines.height_up_to_index(state.highlighted_line);
+        let highlighter = LineHighlighter::new(&self.content,
+                                               &rendered_line.line,
+                                               self.width,
+                                               color);
+        if rendered_line.is_folded() {
+            highlighter.print_folded(&folded_text(rendered_line.folded), accumulated_height);
+        } else {
+            let rows = rendered_line.wrapped_rows(self.width as usize,
+                                                  self.content.continuation_width(),
+                                                  self.content.reveals_invisibles());
+            highlighter.with_rows(rows)
+                .print_single_match(pattern, state.highlighted_match, accumulated_height);
+        }
+    }
+
+    pub fn scroll(&self, reverse_index: usize) {
+        if self.cover_if_too_small() {
+            return;
+        }
+
+        let viewport = self.viewport(reverse_index);
+        let offset = self.layout.content_offset();
+
+        if self.quick_find.top_row.get().map_or(false, |row| row != viewport.top_row()) {
+            self.quick_find.clear(self.content.window);
+            self.register_dismissal(Layer::QuickFind, false);
+        }
+
+        self.column_cursor.unmark(self.content.window);
+        if !self.content.holds(&viewport.visible_rows()) {
+            self.paint(&viewport);
+            if let Some(ref pattern) = *self.content.search_pattern.borrow() {
+                if self.navigation.search.matches_found {
+                    self.highlight_match(pattern, CURRENT_HIGHLIGHT_COLOR);
+                }
+            }
+        }
+        if let Some(column) = self.column_cursor.column.get() {
+            self.mark_column(&viewport, column);
+        }
+
+        if self.layout.content_rows > 0 {
+            prefresh(self.content.window,
+                     self.content.screen_row(viewport.top_row(), self.layout.content_rows),
+                     0,
+                     offset,
+                     0,
+                     offset + self.layout.content_rows - 1,
+                     self.width - 1);
+        }
+        if self.layout.ruler_rows > 0 {
+            self.ruler.render();
+        }
+
+        match self.navigation.state {
+            NavigationState::Rules => self.navigation.rules.refresh(),
+            NavigationState::Patterns => self.navigation.patterns.refresh(),
+            _ => {}
+        }
+        self.debug_overlay.refresh();
+        self.error_overlay.refresh();
+        self.stats_overlay.refresh();
+        self.regex_tester.refresh();
     }
 
     pub fn watch(&self) -> Event {
-        let (input, key) = read_key();
-        EventBuilder::new(input, key).construct(&self.navigation.state)
+        let (input, _) = read_key();
+        self.interpret(input)
+    }
+
+    /// Event for input that was read, or is being played back.
+    pub fn interpret(&self, input: Input) -> Event {
+        if let Some(ref chords) = self.chords {
+            if self.navigation.state == NavigationState::Menu {
+                let now = time::precise_time_ns() / 1_000_000;
+
+                match chords.borrow_mut().feed(&input, now) {
+                    ChordResult::Complete(chord) => return Event::Chord(chord),
+                    ChordResult::Pending(keys) => return Event::PendingChord(Some(keys)),
+                    ChordResult::Cancelled => return Event::PendingChord(None),
+                    ChordResult::Unhandled => {}
+                }
+            }
+        }
+
+        EventBuilder::new(input).construct(&self.navigation.state)
     }
 
     pub fn reset(&mut self) {
@@ -117,12 +626,8 @@ impl Frame {
         self.navigation.search.matches_found = false;
     }
 
-    pub fn max_scroll_value(&self) -> usize {
-        (self.rendered_lines.height() - self.content_height()) as usize
-    }
-
     pub fn content_height(&self) -> i32 {
-        self.height - NAVIGATION_HEIGHT
+        self.layout.content_rows
     }
 
     pub fn replace_rendered_lines(&mut self, rendered_lines: RenderedLineCollection) {
@@ -148,7 +653,12 @@ fn ncurses_init() {
     curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE);
     halfdelay(1);
     keypad(stdscr(), true);
+    enable_mouse();
+    enable_bracketed_paste();
+    init_interface_pairs();
+}
 
+fn init_interface_pairs() {
     init_pair(1, COLOR_WHITE, COLOR_BLUE);
     init_pair(2, COLOR_BLACK, COLOR_YELLOW);
     init_pair(3, COLOR_YELLOW, COLOR_BLUE);
@@ -156,3 +666,11 @@ fn ncurses_init() {
     init_pair(5, COLOR_BLACK, COLOR_WHITE);
     init_pair(6, COLOR_BLACK, COLOR_YELLOW);
 }
+
+fn window_area(window: WINDOW) -> Area {
+    let (mut top, mut left, mut height, mut width) = (0, 0, 0, 0);
+    getbegyx(window, &mut top, &mut left);
+    getmaxyx(window, &mut height, &mut width);
+
+    Area::new(top, left, height, width)
+}
@@ -16,17 +16,29 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::cell::RefMut;
+use std::borrow::Cow;
+use std::cell::{Cell, RefMut};
+use std::ops::Range;
+use std::rc::Rc;
 
-use unicode_width::UnicodeWidthStr;
 use ncurses::*;
 
-use core::line::Line;
-use ui::printer::Viewport;
-use ui::content::State as ContentState;
-use ui::rendered_line::RenderedLineCollection;
+use core::line::{Line, SearchPattern};
+use ui::viewport::Viewport;
+use ui::content::{Content, State as ContentState};
+use utils::invisibles::{symbol_for, trailing_whitespace_offset, NUL_SYMBOL};
+use utils::offset_map::OffsetMap;
+use utils::clusters;
+use utils::wrap::{wrap_indented, column_at, columns_at, RowBreaks};
+use ui::rendered_line::{RenderedLineCollection, WrappedRows};
+use ui::color;
+use ui::frame::{NORMAL_HIGHLIGHT_COLOR, CURRENT_HIGHLIGHT_COLOR};
 
-#[derive(PartialEq)]
+/// Runs of matches painted on a row at most, past which they're counted but
+/// left alone, as a dense pattern can match every other character.
+pub const MAX_PAINTED_PER_ROW: usize = 200;
+
+#[derive(Clone, Copy, PartialEq)]
 pub enum Highlight {
     VisibleOrLast,
     Next,
@@ -36,60 +48,376 @@ pub enum Highlight {
 
 pub struct LineHighlighter<'a> {
     line: &'a Line,
+    content: &'a Content,
     window: WINDOW,
     container_width: i32,
+    /// Column continuation rows start at.
+    indent: usize,
     color_pair_id: i16,
+    show_invisibles: bool,
+    /// Runs of matches left unpainted on crowded rows, added up across lines.
+    truncated: &'a Cell<usize>,
+    /// Where the line wraps, when already known.
+    rows: Option<Rc<WrappedRows>>,
 }
 
 impl<'a> LineHighlighter<'a> {
-    pub fn new(window: WINDOW,
+    pub fn new(content: &'a Content,
                line: &'a Line,
                container_width: i32,
                color_pair_id: i16)
                -> LineHighlighter<'a> {
         LineHighlighter {
             line: line,
-            window: window,
+            content: content,
+            window: content.window,
             container_width: container_width,
+            indent: content.continuation_width(),
             color_pair_id: color_pair_id,
+            show_invisibles: content.reveals_invisibles(),
+            truncated: &content.truncated_highlights,
+            rows: None,
+        }
+    }
+
+    /// Uses the rows the line was wrapped onto, computed for the same width
+    /// and display options, instead of wrapping it again.
+    pub fn with_rows(mut self, rows: Rc<WrappedRows>) -> LineHighlighter<'a> {
+        self.rows = Some(rows);
+        self
+    }
+
+    fn rows(&self, displayed: &DisplayedText) -> Cow<[Range<usize>]> {
+        match self.rows {
+            Some(ref rows) => Cow::Borrowed(&rows.rows),
+            None => {
+                let width = self.container_width as usize;
+                let indent = RowBreaks::new(width, self.indent).indent();
+                Cow::Owned(wrap_indented(&displayed.text, width, indent))
+            }
+        }
+    }
+
+    pub fn print(&self,
+                 pattern: &SearchPattern,
+                 accumulated_height: usize,
+                 line_height: usize)
+                 -> Vec<MatchSpan> {
+        let displayed = DisplayedText::new(&self.line.content_without_ansi, self.show_invisibles);
+        let matches = self.line.matches_for(pattern);
+
+        let painted = painted_matches_on_rows(&displayed,
+                                              &matches,
+                                              &self.rows(&displayed),
+                                              self.container_width as usize,
+                                              self.indent);
+        self.paint(&displayed, &painted.segments, accumulated_height);
+        self.truncated.set(self.truncated.get() + painted.truncated);
+
+        self.content.move_to(accumulated_height + line_height);
+
+        painted.spans
+    }
+
+    /// Paints the row a run of blank lines is folded into, as a single match.
+    pub fn print_folded(&self, text: &str, offset_y: usize) -> MatchSpan {
+        if let Some(y) = self.content.pad_row(offset_y) {
+            let attr = self.attr();
+            wattron(self.window, attr);
+            mvwprintw(self.window, y, 0, text);
+            wattroff(self.window, attr);
+        }
+
+        MatchSpan {
+            start: ByteOffset(0),
+            end: ByteOffset(self.line.content_without_ansi.len()),
+            row: RowOffset(0),
+        }
+    }
+
+    pub fn print_single_match(&self, pattern: &SearchPattern, index: usize, offset_y: usize) {
+        let displayed = DisplayedText::new(&self.line.content_without_ansi, self.show_invisibles);
+        let matches = self.line.matches_for(pattern);
+
+        let painted = painted_matches_on_rows(&displayed,
+                                              &matches[index..index + 1],
+                                              &self.rows(&displayed),
+                                              self.container_width as usize,
+                                              self.indent);
+        self.paint(&displayed, &painted.segments, offset_y);
+    }
+
+    /// Paints the given byte range of the line, starting at row `offset_y`.
+    pub fn print_range(&self, range: Range<usize>, offset_y: usize) {
+        let displayed = DisplayedText::new(&self.line.content_without_ansi, self.show_invisibles);
+        let displayed_range = displayed.range(range);
+        let segments = match_segments_on_rows(&displayed.text,
+                                              displayed_range,
+                                              &self.rows(&displayed),
+                                              self.container_width as usize,
+                                              self.indent);
+
+        self.paint(&displayed, &segments, offset_y);
+    }
+
+    /// Paints the segments of the line starting on content row `offset_y`,
+    /// leaving out rows the pad doesn't hold.
+    fn paint(&self, displayed: &DisplayedText, segments: &[MatchSegment], offset_y: usize) {
+        let attr = self.attr();
+        wattron(self.window, attr);
+        for segment in segments {
+            if let Some(y) = self.content.pad_row(offset_y + segment.row) {
+                mvwprintw(self.window,
+                          y,
+                          segment.column as i32,
+                          &displayed.text[segment.range.clone()]);
+            }
         }
+        wattroff(self.window, attr);
+    }
+
+    /// Without colors, the current match is shown in bold reverse video,
+    /// other matches in reverse video and persistent highlights underlined.
+    fn attr(&self) -> attr_t {
+        let fallback = if self.color_pair_id == CURRENT_HIGHLIGHT_COLOR {
+            A_REVERSE() | A_BOLD()
+        } else if self.color_pair_id == NORMAL_HIGHLIGHT_COLOR {
+            A_REVERSE()
+        } else {
+            A_UNDERLINE() | A_BOLD()
+        };
+
+        color::pair_attr(self.color_pair_id, fallback)
     }
+}
+
+/// A single occurrence of the search pattern, as a byte range of the line
+/// without colors. Matches are counted and navigated by occurrence, however
+/// many rows it's painted on.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MatchSpan {
+    pub start: ByteOffset,
+    pub end: ByteOffset,
+    /// Wrapped row of the line the occurrence starts on.
+    pub row: RowOffset,
+}
+
+/// Position within the text of a line, in bytes.
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub struct ByteOffset(pub usize);
+
+/// Rows below the first one a line is printed on. Only rows add up with
+/// rows, never with positions within the text.
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub struct RowOffset(pub usize);
+
+/// Occurrences of the pattern within the line wrapped at the given width.
+pub fn match_spans(line: &Line,
+                   pattern: &SearchPattern,
+                   width: usize,
+                   show_invisibles: bool)
+                   -> Vec<MatchSpan> {
+    let displayed = DisplayedText::new(&line.content_without_ansi, show_invisibles);
+
+    painted_matches(&displayed, &line.matches_for(pattern), width, 0).spans
+}
+
+/// Where the matches of a line are painted. Matches that touch or overlap
+/// are painted as a single run, so that drawing takes a call per run rather
+/// than per match, and at most `MAX_PAINTED_PER_ROW` runs per row.
+pub struct PaintedMatches {
+    pub spans: Vec<MatchSpan>,
+    pub segments: Vec<MatchSegment>,
+    /// Runs left unpainted on crowded rows.
+    pub truncated: usize,
+}
+
+pub fn painted_matches(displayed: &DisplayedText,
+                       matches: &[(usize, &str)],
+                       width: usize,
+                       indent: usize)
+                       -> PaintedMatches {
+    let rows = wrap_indented(&displayed.text, width, RowBreaks::new(width, indent).indent());
+
+    painted_matches_on_rows(displayed, matches, &rows, width, indent)
+}
+
+/// Same as `painted_matches`, for text already wrapped onto `rows`.
+pub fn painted_matches_on_rows(displayed: &DisplayedText,
+                               matches: &[(usize, &str)],
+                               rows: &[Range<usize>],
+                               width: usize,
+                               indent: usize)
+                               -> PaintedMatches {
+    let indent = RowBreaks::new(width, indent).indent();
+    let boundaries = clusters::boundaries(&displayed.text);
+    let ranges = matches.iter()
+        .map(|&(offset, value)| {
+            clusters::snap_to(&boundaries, displayed.range(offset..offset + value.len()))
+        })
+        .collect::<Vec<_>>();
 
-    pub fn print(&self, text: &str, accumulated_height: i32, line_height: i32) -> Vec<usize> {
-        let mut locations = vec![];
+    let spans = matches.iter()
+        .zip(ranges.iter())
+        .map(|(&(offset, value), range)| {
+            MatchSpan {
+                start: ByteOffset(offset),
+                end: ByteOffset(offset + value.len()),
+                row: RowOffset(rows.iter().position(|row| row.end > range.start).unwrap_or(0)),
+            }
+        })
+        .collect();
 
-        let matches = &self.line.matches_for(text);
+    let mut segments = vec![];
+    let mut truncated = 0;
+    let runs = merge_ranges(ranges);
+    let mut first = 0;
 
-        for &(offset_x, value) in matches {
-            let location = self.handle_match(offset_x as i32, accumulated_height, value);
-            locations.push(location);
+    for (i, row) in rows.iter().enumerate() {
+        while first < runs.len() && runs[first].end <= row.start {
+            first += 1;
         }
 
-        wmove(self.window, accumulated_height + line_height, 0);
+        let on_row = runs[first..].iter().take_while(|run| run.start < row.end).count();
+        let painted = &runs[first..first + on_row.min(MAX_PAINTED_PER_ROW)];
+        truncated += on_row - painted.len();
+
+        let starts = painted.iter().map(|run| row.start.max(run.start)).collect::<Vec<_>>();
+        let start_column = if i > 0 { indent } else { 0 };
+        let columns = columns_at(&displayed.text, row, &starts, width, start_column);
+
+        for ((run, start), column) in painted.iter().zip(starts).zip(columns) {
+            segments.push(MatchSegment {
+                row: i,
+                column: column,
+                range: start..row.end.min(run.end),
+            });
+        }
+    }
 
-        locations
+    PaintedMatches {
+        spans: spans,
+        segments: segments,
+        truncated: truncated,
     }
+}
+
+/// Sorts the ranges, merging the ones that touch or overlap. Empty ranges
+/// are dropped, as there's nothing to paint.
+pub fn merge_ranges(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.retain(|range| range.start < range.end);
+    ranges.sort_by_key(|range| range.start);
 
-    pub fn print_single_match(&self, text: &str, index: usize, offset_y: i32) {
-        let (offset_x, value) = self.line.matches_for(text)[index];
-        self.handle_match(offset_x as i32, offset_y, value);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        if let Some(last) = merged.last_mut() {
+            if range.start <= last.end {
+                last.end = last.end.max(range.end);
+                continue;
+            }
+        }
+        merged.push(range);
     }
 
-    fn handle_match(&self, mut offset_x: i32, mut offset_y: i32, value: &str) -> usize {
-        let initial_offset_y = offset_y;
+    merged
+}
+
+/// Part of a match printed on a single row.
+#[derive(PartialEq, Debug)]
+pub struct MatchSegment {
+    pub row: usize,
+    pub column: usize,
+    pub range: Range<usize>,
+}
+
+/// Text as printed on screen, along with where each part of the original
+/// text ended up. Matches are always found within the original text and only
+/// painted through the offset map.
+pub struct DisplayedText {
+    pub text: String,
+    pub offsets: OffsetMap,
+}
+
+impl DisplayedText {
+    pub fn new(text: &str, show_invisibles: bool) -> DisplayedText {
+        let trailing_offset = if show_invisibles { trailing_whitespace_offset(text) } else { 0 };
+        let mut displayed = String::with_capacity(text.len());
+        let mut offsets = OffsetMap::new();
 
-        offset_x = self.line.content_without_ansi.split_at(offset_x as usize).0.width() as i32;
-        offset_y += offset_x / self.container_width;
-        offset_x %= self.container_width;
+        for (i, value) in text.chars().enumerate() {
+            let symbol = if show_invisibles {
+                symbol_for(value, i >= trailing_offset)
+            } else if value == '\0' {
+                Some(NUL_SYMBOL)
+            } else {
+                None
+            };
 
-        wattron(self.window, COLOR_PAIR(self.color_pair_id));
-        mvwprintw(self.window, offset_y, offset_x, value);
-        wattroff(self.window, COLOR_PAIR(self.color_pair_id));
+            let length = displayed.len();
+            match symbol {
+                Some(symbol) => displayed.push_str(symbol),
+                None => displayed.push(value),
+            }
+            offsets.push(value.len_utf8(), displayed.len() - length);
+        }
+
+        DisplayedText {
+            text: displayed,
+            offsets: offsets,
+        }
+    }
 
-        (offset_y - initial_offset_y) as usize
+    /// Displayed range showing the given range of the original text.
+    pub fn range(&self, range: Range<usize>) -> Range<usize> {
+        self.offsets.map(range.start)..self.offsets.map(range.end)
     }
 }
 
+/// Splits a match spanning the given range of the displayed text into the
+/// segments printed on each of the wrapped rows. A match partly covering a
+/// cluster of characters printed as a single glyph is widened to all of it.
+pub fn match_segments(displayed: &str, range: Range<usize>, width: usize) -> Vec<MatchSegment> {
+    match_segments_indented(displayed, range, width, 0)
+}
+
+/// Same as `match_segments`, with rows after the first starting past the
+/// continuation prefix taking up `indent` columns.
+pub fn match_segments_indented(displayed: &str,
+                               range: Range<usize>,
+                               width: usize,
+                               indent: usize)
+                               -> Vec<MatchSegment> {
+    let rows = wrap_indented(displayed, width, RowBreaks::new(width, indent).indent());
+
+    match_segments_on_rows(displayed, range, &rows, width, indent)
+}
+
+/// Same as `match_segments_indented`, for text already wrapped onto `rows`.
+pub fn match_segments_on_rows(displayed: &str,
+                              range: Range<usize>,
+                              rows: &[Range<usize>],
+                              width: usize,
+                              indent: usize)
+                              -> Vec<MatchSegment> {
+    let indent = RowBreaks::new(width, indent).indent();
+    let range = clusters::snap(displayed, range);
+
+    rows.iter()
+        .enumerate()
+        .filter(|&(_, row)| row.start < range.end && row.end > range.start)
+        .map(|(i, row)| {
+            let start = row.start.max(range.start);
+            let start_column = if i > 0 { indent } else { 0 };
+
+            MatchSegment {
+                row: i,
+                column: column_at(displayed, row, start, width, start_column),
+                range: start..row.end.min(range.end),
+            }
+        })
+        .collect()
+}
+
 pub struct State<'a> {
     state: RefMut<'a, ContentState>,
     rendered_lines: &'a RenderedLineCollection,
@@ -114,6 +442,9 @@ impl<'a> State<'a> {
             Highlight::Next => self.handle_next(),
             Highlight::Previous => self.handle_previous(),
         }
+
+        let line_id = self.rendered_lines[self.state.highlighted_line].line.id;
+        self.state.highlighted_line_id = Some(line_id);
     }
 
     fn handle_visible_or_last(&mut self) {
@@ -121,11 +452,15 @@ impl<'a> State<'a> {
             .viewport_match(&self.viewport)
             .unwrap_or(self.rendered_lines.last_match());
 
-        self.state.highlighted_line = self.rendered_lines.len() - matched_line.line - 1;
+        self.state.highlighted_line = matched_line.line;
         self.state.highlighted_match = matched_line.match_index;
     }
 
     fn handle_next(&mut self) {
+        if !self.is_highlighted_line_valid() {
+            return self.handle_visible_or_last();
+        }
+
         let rendered_line = &self.rendered_lines[self.state.highlighted_line];
         if self.state.highlighted_match < rendered_line.match_count() - 1 {
             self.state.highlighted_match += 1;
@@ -141,7 +476,9 @@ impl<'a> State<'a> {
     }
 
     fn handle_previous(&mut self) {
-        if self.state.highlighted_match > 0 {
+        if !self.is_highlighted_line_valid() {
+            self.handle_visible_or_last();
+        } else if self.state.highlighted_match > 0 {
             self.state.highlighted_match -= 1;
         } else if self.state.highlighted_line > 0 {
             let matched_line_opt = self.rendered_lines
@@ -153,4 +490,9 @@ impl<'a> State<'a> {
             }
         }
     }
+
+    fn is_highlighted_line_valid(&self) -> bool {
+        self.state.highlighted_line < self.rendered_lines.len() &&
+        self.rendered_lines[self.state.highlighted_line].found_matches.is_some()
+    }
 }
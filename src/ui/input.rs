@@ -16,17 +16,19 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::char;
+use std::{char, str};
+use std::io::{self, Write};
 
 use ncurses::*;
 
 pub static ESCAPE_CODE: i32 = 27;
-pub static KEY_LEFT_SEQ: [i32; 3] = [27, 91, 68];
-pub static KEY_RIGHT_SEQ: [i32; 3] = [27, 91, 67];
-pub static KEY_HOME_SEQ: [i32; 3] = [27, 91, 72];
-pub static KEY_END_SEQ: [i32; 3] = [27, 91, 70];
-pub static KEY_BACKSPACE_SEQ: [i32; 1] = [127];
 
+static BRACKETED_PASTE_ON: &'static str = "\x1b[?2004h";
+static BRACKETED_PASTE_OFF: &'static str = "\x1b[?2004l";
+static PASTE_START_SEQ: &'static str = "200~";
+static PASTE_END_SEQ: &'static str = "\x1b[201~";
+
+#[derive(RustcEncodable, RustcDecodable, Clone, PartialEq, Debug)]
 pub enum Key {
     Left,
     Right,
@@ -44,13 +46,19 @@ pub enum Key {
     Other,
 }
 
+#[derive(RustcEncodable, RustcDecodable, Clone, PartialEq, Debug)]
 pub enum Modifier {
     Alt(i32),
     Ctrl,
 }
 
+#[derive(RustcEncodable, RustcDecodable, Clone, PartialEq, Debug)]
 pub enum Input {
     Kb(Key, Option<Modifier>),
+    Paste(String),
+    /// Direction, screen row and column of the pointer.
+    Wheel(i32, i32, i32),
+    Click(i32, i32),
     Resize,
     None,
 }
@@ -61,6 +69,7 @@ pub fn read_key() -> (Input, i32) {
     let input = match key {
         ERR => Input::None,
         KEY_RESIZE => Input::Resize,
+        KEY_MOUSE => read_mouse(),
         KEY_LEFT => Input::Kb(Key::Left, None),
         KEY_RIGHT => Input::Kb(Key::Right, None),
         KEY_UP => Input::Kb(Key::Up, None),
@@ -77,15 +86,40 @@ pub fn read_key() -> (Input, i32) {
     (input, key)
 }
 
+/// Reads a key if one was pressed, without waiting, while work is done in
+/// between reads.
+pub fn poll_key() -> (Input, i32) {
+    // Half-delay mode takes precedence over the window's delay
+    cbreak();
+    nodelay(stdscr(), true);
+    let result = read_key();
+    nodelay(stdscr(), false);
+    halfdelay(1);
+
+    result
+}
+
 fn parse_key_code(code: i32) -> Input {
     let mut modifier = None;
     let mut pending = code;
 
+    if pending >= 0xC0 && pending <= 0xF7 {
+        return match read_utf8_char(pending) {
+            Some(value) => Input::Kb(Key::Char(value), None),
+            None => Input::Kb(Key::Other, None),
+        };
+    }
+
     if pending == ESCAPE_CODE {
         let new_code = wgetch(stdscr());
         if new_code == ERR {
             return Input::Kb(Key::Escape, None);
         }
+        if new_code == '[' as i32 {
+            if let Some(text) = read_bracketed_paste() {
+                return Input::Paste(text);
+            }
+        }
         pending = new_code;
         modifier = Some(Modifier::Alt(pending));
     } else {
@@ -102,3 +136,104 @@ fn parse_key_code(code: i32) -> Input {
         _ => Input::Kb(Key::Other, None),
     }
 }
+
+// Multi-byte characters arrive one byte per getch call.
+fn read_utf8_char(first_byte: i32) -> Option<char> {
+    let length = (!(first_byte as u8)).leading_zeros() as usize;
+    let mut bytes = vec![first_byte as u8];
+
+    for _ in 1..length {
+        let code = wgetch(stdscr());
+        if code == ERR || code & 0xC0 != 0x80 {
+            return None;
+        }
+        bytes.push(code as u8);
+    }
+
+    str::from_utf8(&bytes).ok().and_then(|value| value.chars().next())
+}
+
+pub fn enable_mouse() {
+    mousemask((BUTTON1_CLICKED | BUTTON4_PRESSED | BUTTON5_PRESSED) as mmask_t, None);
+    mouseinterval(0);
+}
+
+fn read_mouse() -> Input {
+    let mut event = MEVENT {
+        id: 0,
+        x: 0,
+        y: 0,
+        z: 0,
+        bstate: 0,
+    };
+
+    if getmouse(&mut event) != OK {
+        return Input::None;
+    }
+
+    if event.bstate & BUTTON4_PRESSED as mmask_t != 0 {
+        Input::Wheel(1, event.y, event.x)
+    } else if event.bstate & BUTTON5_PRESSED as mmask_t != 0 {
+        Input::Wheel(-1, event.y, event.x)
+    } else if event.bstate & BUTTON1_CLICKED as mmask_t != 0 {
+        Input::Click(event.y, event.x)
+    } else {
+        Input::None
+    }
+}
+
+pub fn enable_bracketed_paste() {
+    print!("{}", BRACKETED_PASTE_ON);
+    let _ = io::stdout().flush();
+}
+
+pub fn disable_bracketed_paste() {
+    print!("{}", BRACKETED_PASTE_OFF);
+    let _ = io::stdout().flush();
+}
+
+// Pasted text is wrapped between `ESC [200~` and `ESC [201~`. Anything else
+// following `ESC [` is pushed back to be read as a regular key.
+fn read_bracketed_paste() -> Option<String> {
+    let mut codes = vec![];
+
+    for expected in PASTE_START_SEQ.bytes() {
+        let code = wgetch(stdscr());
+        if code != ERR {
+            codes.push(code);
+        }
+
+        if code != expected as i32 {
+            for code in codes.iter().rev() {
+                ungetch(*code);
+            }
+            return None;
+        }
+    }
+
+    let mut bytes = vec![];
+
+    while !bytes.ends_with(PASTE_END_SEQ.as_bytes()) {
+        match wgetch(stdscr()) {
+            ERR => break,
+            code if code <= 0xFF => bytes.push(code as u8),
+            _ => {}
+        }
+    }
+
+    if bytes.ends_with(PASTE_END_SEQ.as_bytes()) {
+        let length = bytes.len() - PASTE_END_SEQ.len();
+        bytes.truncate(length);
+    }
+
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Pasted text is always inserted on a single line, with control characters
+/// such as newlines or tabs converted to spaces.
+pub fn sanitize_paste(text: &str) -> String {
+    text.trim_end_matches(|value| value == '\n' || value == '\r')
+        .chars()
+        .map(|value| if value.is_control() { ' ' } else { value })
+        .collect()
+}
@@ -0,0 +1,188 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use ui::navigation::HEIGHT as NAVIGATION_HEIGHT;
+
+pub const MIN_CONTENT_WIDTH: i32 = 20;
+/// Smallest terminal laid out, a placeholder being shown in smaller ones.
+pub const MIN_WIDTH: i32 = 20;
+pub const MIN_HEIGHT: i32 = 4;
+
+/// Rows of the screen given to each widget, from the top: the ruler, the
+/// content and the navigation bar, and the columns the sidebar takes on the
+/// right of the content. Terminals may report sizes down to zero while being
+/// resized, so every dimension is kept within the screen.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Layout {
+    pub width: i32,
+    pub height: i32,
+    pub ruler_rows: i32,
+    pub content_rows: i32,
+    pub content_width: i32,
+    pub sidebar_cols: i32,
+}
+
+impl Layout {
+    /// The ruler is only shown while at least one content row is left below it.
+    pub fn new(width: i32, height: i32, ruler_visible: bool) -> Layout {
+        let width = width.max(1);
+        let height = height.max(NAVIGATION_HEIGHT);
+        let available = height - NAVIGATION_HEIGHT;
+        let ruler_rows = if ruler_visible && available > 1 { 1 } else { 0 };
+
+        Layout {
+            width: width,
+            height: height,
+            ruler_rows: ruler_rows,
+            content_rows: available - ruler_rows,
+            content_width: width,
+            sidebar_cols: 0,
+        }
+    }
+
+    /// The sidebar is only shown while the content keeps `MIN_CONTENT_WIDTH`
+    /// columns next to it.
+    pub fn with_sidebar(mut self, cols: i32) -> Layout {
+        self.sidebar_cols = if self.width - cols >= MIN_CONTENT_WIDTH { cols.max(0) } else { 0 };
+        self.content_width = self.width - self.sidebar_cols;
+        self
+    }
+
+    /// Whether the terminal is too small to be laid out at all.
+    pub fn is_too_small(&self) -> bool {
+        self.width < MIN_WIDTH || self.height < MIN_HEIGHT
+    }
+
+    /// First screen row of the content.
+    pub fn content_offset(&self) -> i32 {
+        self.ruler_rows
+    }
+
+    /// Screen row of the navigation bar.
+    pub fn navigation_offset(&self) -> i32 {
+        self.height - NAVIGATION_HEIGHT
+    }
+
+    /// Row of the content shown at the given screen row, if any.
+    pub fn content_row(&self, y: i32) -> Option<usize> {
+        let row = y - self.content_offset();
+
+        if row >= 0 && row < self.content_rows {
+            Some(row as usize)
+        } else {
+            None
+        }
+    }
+}
+
+/// Widgets the pointer may be over, overlays included.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Widget {
+    Ruler,
+    Content,
+    Sidebar,
+    Navigation,
+    Rules,
+    Patterns,
+    Overlay,
+}
+
+/// Rectangle of the screen taken by a widget.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Area {
+    pub top: i32,
+    pub left: i32,
+    pub height: i32,
+    pub width: i32,
+}
+
+impl Area {
+    pub fn new(top: i32, left: i32, height: i32, width: i32) -> Area {
+        Area {
+            top: top,
+            left: left,
+            height: height,
+            width: width,
+        }
+    }
+
+    pub fn contains(&self, row: i32, column: i32) -> bool {
+        row >= self.top && row < self.top + self.height && column >= self.left &&
+        column < self.left + self.width
+    }
+}
+
+/// Areas of the widgets on screen, the ones shown last being on top.
+#[derive(Clone, PartialEq, Debug)]
+pub struct WidgetMap {
+    areas: Vec<(Widget, Area)>,
+}
+
+impl WidgetMap {
+    pub fn new(layout: &Layout) -> WidgetMap {
+        let mut map = WidgetMap { areas: vec![] };
+
+        if layout.ruler_rows > 0 {
+            map.show(Widget::Ruler, Area::new(0, 0, layout.ruler_rows, layout.width));
+        }
+        map.show(Widget::Content,
+                 Area::new(layout.content_offset(), 0, layout.content_rows, layout.content_width));
+        if layout.sidebar_cols > 0 {
+            map.show(Widget::Sidebar,
+                     Area::new(layout.content_offset(),
+                               layout.content_width,
+                               layout.content_rows,
+                               layout.sidebar_cols));
+        }
+        map.show(Widget::Navigation,
+                 Area::new(layout.navigation_offset(), 0, NAVIGATION_HEIGHT, layout.width));
+
+        map
+    }
+
+    /// Puts the widget on top of the others, moving it if it was shown already.
+    pub fn show(&mut self, widget: Widget, area: Area) {
+        self.hide(widget);
+        self.areas.push((widget, area));
+    }
+
+    pub fn hide(&mut self, widget: Widget) {
+        self.areas.retain(|&(shown, _)| shown != widget);
+    }
+
+    /// Topmost widget at the given screen cell.
+    pub fn widget_at(&self, row: i32, column: i32) -> Option<Widget> {
+        self.areas
+            .iter()
+            .rev()
+            .find(|&&(_, area)| area.contains(row, column))
+            .map(|&(widget, _)| widget)
+    }
+}
+
+/// First row to show of a list scrolled from `offset` so that the selected
+/// row stays within the `visible` rows.
+pub fn scroll_to_show(offset: usize, selected: usize, visible: usize) -> usize {
+    if visible == 0 || selected < offset {
+        selected
+    } else if selected >= offset + visible {
+        selected + 1 - visible
+    } else {
+        offset
+    }
+}
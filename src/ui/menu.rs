@@ -16,53 +16,166 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use std::cell::Cell;
+use std::ops::Range;
+
 use ncurses::*;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use core::health::Health;
+use ui::color::{self, ColorPair, COLOR_DEFAULT};
+
+static PENDING_COUNT_LABEL: &'static str = "…";
+static ELLIPSIS: &'static str = "…";
+static LEFT_OVERFLOW_MARKER: &'static str = "‹";
+static RIGHT_OVERFLOW_MARKER: &'static str = "›";
+/// Widest a tab name is shown, longer ones losing their middle.
+pub const MAX_NAME_WIDTH: usize = 24;
 
+/// Row of tabs, scrolled sideways so that the selected one is always shown.
+/// Tabs hidden past either edge are marked with `‹` or `›`.
 pub struct Menu {
     pub window: WINDOW,
     panel: PANEL,
-    object: MENU,
-    items: Vec<ITEM>,
+    names: Vec<String>,
+    counts: Option<Vec<Option<usize>>>,
+    /// State of the sources, which every tab reads from.
+    health: Cell<Option<Health>>,
+    selected: Cell<usize>,
+    /// First tab shown, as scrolled to last.
+    first_visible: Cell<usize>,
+    width: Cell<i32>,
 }
 
 impl Menu {
     pub fn new(position_x: i32, position_y: i32, item_names: &[String]) -> Menu {
-        let window = newwin(0, 0, position_x, position_y);
-
-        let mut items = vec![];
-
-        for name in item_names {
-            items.push(new_item(format!(" {} ", name), String::new()));
-        }
+        let window = newwin(1, COLS(), position_x, position_y);
 
         Menu {
             window: window,
             panel: new_panel(window),
-            object: new_menu(&mut items),
-            items: items,
+            names: item_names.to_vec(),
+            counts: None,
+            health: Cell::new(None),
+            selected: Cell::new(0),
+            first_visible: Cell::new(0),
+            width: Cell::new(COLS()),
+        }
+    }
+
+    /// Appends the match count of each item to its label, a `None` count
+    /// being shown as pending. Items without matches are dimmed.
+    pub fn set_match_counts(&mut self, counts: Option<&[Option<usize>]>) {
+        self.counts = counts.map(|values| values.to_vec());
+        self.render();
+    }
+
+    /// Marks every label with a glyph colored after the state of the sources.
+    pub fn set_health(&self, health: Option<Health>) {
+        self.health.set(health);
+        self.render();
+    }
+
+    pub fn set_items(&mut self, names: &[String]) {
+        self.names = names.to_vec();
+        self.selected.set(0);
+        self.first_visible.set(0);
+        self.set_match_counts(None);
+    }
+
+    pub fn add_item(&mut self, name: &str) {
+        self.names.push(name.to_string());
+        self.selected.set(self.names.len() - 1);
+        self.set_match_counts(None);
+    }
+
+    /// Drops the selected item, selecting the one before it.
+    pub fn remove_selected(&mut self) {
+        if self.names.len() > 1 {
+            self.names.remove(self.selected.get());
+            self.selected.set(self.selected.get().saturating_sub(1));
+            self.first_visible.set(self.first_visible.get().min(self.selected.get()));
         }
+        self.set_match_counts(None);
     }
 
-    pub fn select(&self, item: i32) {
-        menu_driver(self.object, item);
-        pos_menu_cursor(self.object);
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected.get()
+    }
+
+    pub fn select(&self, index: usize) {
+        self.selected.set(index.min(self.names.len().saturating_sub(1)));
+    }
+
+    pub fn select_previous(&self) {
+        self.selected.set(self.selected.get().saturating_sub(1));
+        self.render();
+    }
+
+    pub fn select_next(&self) {
+        self.selected.set((self.selected.get() + 1).min(self.names.len().saturating_sub(1)));
+        self.render();
+    }
+
+    pub fn refresh(&self) {
         wrefresh(self.window);
     }
 
+    pub fn resize(&self, container_width: i32, offset: i32) {
+        self.width.set(container_width.max(1));
+        wresize(self.window, 1, container_width.max(1));
+        mvwin(self.window, offset, 0);
+    }
+
     pub fn render(&self) {
-        set_menu_win(self.object, self.window);
-        set_menu_sub(self.object, derwin(self.window, 0, 0, 0, 0));
-
-        menu_opts_off(self.object, O_SHOWDESC);
-        set_menu_mark(self.object, "");
-        set_menu_fore(self.object, COLOR_PAIR(1));
-        set_menu_back(self.object, COLOR_PAIR(2));
-        set_menu_format(self.object, 1, self.items.len() as i32);
-        post_menu(self.object);
-
-        refresh();
-        wbkgd(self.window, COLOR_PAIR(2));
-        wrefresh(self.window);
+        let labels = self.labels();
+        let widths = labels.iter().map(|label| label.width()).collect::<Vec<_>>();
+        let width = self.width.get() as usize;
+        let visible = visible_items(&widths, self.selected.get(), self.first_visible.get(), width);
+        self.first_visible.set(visible.start);
+
+        werase(self.window);
+        wbkgd(self.window, color::pair_attr(2, A_NORMAL()));
+
+        let mut column = 0;
+        if visible.start > 0 {
+            mvwprintw(self.window, 0, 0, LEFT_OVERFLOW_MARKER);
+            column = 1;
+        }
+
+        for index in visible.clone() {
+            let attr = if index == self.selected.get() {
+                color::pair_attr(1, A_REVERSE())
+            } else if self.count(index) == Some(Some(0)) {
+                color::pair_attr(2, A_NORMAL()) | A_DIM()
+            } else {
+                color::pair_attr(2, A_NORMAL())
+            };
+
+            wattron(self.window, attr);
+            mvwprintw(self.window, 0, column as i32, &labels[index]);
+            wattroff(self.window, attr);
+            if let Some(health) = self.health.get() {
+                let attr = health_attr(health);
+                wattron(self.window, attr);
+                mvwprintw(self.window, 0, (column + widths[index] - 2) as i32, health.glyph());
+                wattroff(self.window, attr);
+            }
+            column += widths[index] + 1;
+        }
+
+        if visible.end < labels.len() {
+            mvwprintw(self.window, 0, width as i32 - 1, RIGHT_OVERFLOW_MARKER);
+        }
+
+        // The search bar takes the row while the menu is hidden
+        if !panel_hidden(self.panel) {
+            wrefresh(self.window);
+        }
     }
 
     pub fn show(&self) {
@@ -73,13 +186,113 @@ impl Menu {
         hide_panel(self.panel);
     }
 
-    pub fn destroy(&self) {
-        unpost_menu(self.object);
+    fn count(&self, index: usize) -> Option<Option<usize>> {
+        self.counts.as_ref().and_then(|values| values.get(index).cloned())
+    }
+
+    fn labels(&self) -> Vec<String> {
+        let glyph = self.health.get().map(|health| health.glyph());
+
+        (0..self.names.len())
+            .map(|index| {
+                let label = label(&self.names[index], self.count(index));
+                match glyph {
+                    Some(glyph) => format!("{}{} ", label, glyph),
+                    None => label,
+                }
+            })
+            .collect()
+    }
+}
+
+// Health glyphs keep the terminal's background, standing out of the bar
+fn health_attr(health: Health) -> attr_t {
+    let foreground = match health {
+        Health::Active => COLOR_GREEN,
+        Health::Waiting => COLOR_YELLOW,
+        Health::Errored => COLOR_RED,
+        // Bright black, falling back to the closest color on 8 color terminals
+        Health::Ended => 8,
+    };
+
+    ColorPair::new(foreground, COLOR_DEFAULT).to_attr()
+}
+
+/// Label of a tab, along with its match count when there's one.
+pub fn label(name: &str, count: Option<Option<usize>>) -> String {
+    let name = middle_ellipsize(name, MAX_NAME_WIDTH);
+
+    match count {
+        Some(Some(value)) => format!(" {}·{} ", name, value),
+        Some(None) => format!(" {}·{} ", name, PENDING_COUNT_LABEL),
+        None => format!(" {} ", name),
+    }
+}
+
+/// Shortens the text to `max_width` columns by replacing its middle with an
+/// ellipsis, so that both the start and the end of names stay recognizable.
+pub fn middle_ellipsize(text: &str, max_width: usize) -> String {
+    if text.width() <= max_width || max_width == 0 {
+        return text.to_string();
+    }
+
+    let available = max_width - 1;
+    let head = prefix_within(text.chars(), (available + 1) / 2);
+    let tail = prefix_within(text.chars().rev(), available / 2).chars().rev().collect::<String>();
+
+    head + ELLIPSIS + &tail
+}
+
+fn prefix_within<I>(chars: I, width: usize) -> String
+    where I: Iterator<Item = char>
+{
+    let mut used_width = 0;
+
+    chars.take_while(|value| {
+            used_width += value.width().unwrap_or(0);
+            used_width <= width
+        })
+        .collect()
+}
 
-        for item in &self.items {
-            free_item(*item);
+/// Items shown on a row `width` columns wide, given the width of each one.
+/// The row stays scrolled from `first` when that shows the selected item,
+/// and is otherwise scrolled just enough to. Items are one column apart, and
+/// a column is kept for each overflow marker.
+pub fn visible_items(widths: &[usize],
+                     selected: usize,
+                     first: usize,
+                     width: usize)
+                     -> Range<usize> {
+    if widths.is_empty() {
+        return 0..0;
+    }
+
+    let selected = selected.min(widths.len() - 1);
+    let mut start = first.min(selected);
+
+    loop {
+        let end = fitting_end(widths, start, width);
+        if selected < end || start == selected {
+            return start..end.max(start + 1);
         }
+        start += 1;
+    }
+}
+
+// End of the items fitting from `start` on, the first one always counting
+fn fitting_end(widths: &[usize], start: usize, width: usize) -> usize {
+    let mut used = if start > 0 { 1 } else { 0 };
+    let mut end = start;
 
-        free_menu(self.object);
+    while end < widths.len() {
+        let marker = if end + 1 < widths.len() { 1 } else { 0 };
+        if end > start && used + widths[end] + marker > width {
+            break;
+        }
+        used += widths[end] + 1;
+        end += 1;
     }
+
+    end
 }
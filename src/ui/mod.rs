@@ -17,14 +17,34 @@
  */
 
 pub mod frame;
+pub mod layout;
+pub mod placeholder;
 pub mod content;
 pub mod navigation;
 pub mod menu;
 pub mod search;
+pub mod prompt;
+pub mod status;
 pub mod input;
 pub mod event;
-pub mod readline;
 pub mod color;
 pub mod printer;
 pub mod highlighter;
 pub mod rendered_line;
+pub mod viewport;
+pub mod momentum;
+pub mod snapshot;
+pub mod rules;
+pub mod patterns;
+pub mod regex_tester;
+pub mod sidebar;
+pub mod column_cursor;
+pub mod quick_find;
+pub mod flash;
+pub mod ruler;
+pub mod debug_overlay;
+pub mod chords;
+pub mod dismiss;
+pub mod sparkline;
+pub mod size_probe;
+pub mod styled;
@@ -0,0 +1,122 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cmp::{min, max};
+
+// Milliseconds between two wheel events for them to be considered consecutive
+static STREAK_INTERVAL: u64 = 120;
+static MAX_MULTIPLIER: usize = 4;
+
+// Milliseconds between two presses of an arrow key for them to be repeats of
+// the key being held
+static REPEAT_INTERVAL: u64 = 50;
+// Repeats after which the step grows to the next one
+static REPEATS_PER_STEP: usize = 10;
+static REPEAT_STEPS: [usize; 3] = [1, 5, 25];
+
+/// Grows the amount scrolled when wheel events follow each other quickly,
+/// decaying back to the base step once they slow down.
+pub struct Momentum {
+    base_step: usize,
+    multiplier: usize,
+    last_direction: i32,
+    last_event_at: Option<u64>,
+}
+
+impl Momentum {
+    pub fn new(base_step: usize) -> Momentum {
+        Momentum {
+            base_step: max(1, base_step),
+            multiplier: 1,
+            last_direction: 0,
+            last_event_at: None,
+        }
+    }
+
+    /// Rows to scroll for an event in the given direction, `time` being
+    /// expressed in milliseconds.
+    pub fn step(&mut self, direction: i32, time: u64) -> usize {
+        self.multiplier = match self.last_event_at {
+            Some(last) if direction == self.last_direction => {
+                let elapsed = time.saturating_sub(last);
+                if elapsed <= STREAK_INTERVAL {
+                    min(self.multiplier + 1, MAX_MULTIPLIER)
+                } else {
+                    let decay = (elapsed / STREAK_INTERVAL) as usize;
+                    max(1, self.multiplier.saturating_sub(decay))
+                }
+            }
+            _ => 1,
+        };
+
+        self.last_direction = direction;
+        self.last_event_at = Some(time);
+
+        self.base_step * self.multiplier
+    }
+
+    pub fn reset(&mut self) {
+        self.multiplier = 1;
+        self.last_event_at = None;
+    }
+}
+
+/// Grows the lines scrolled by an arrow key while it's held down, going back
+/// to a single line once it's let go.
+pub struct KeyRepeat {
+    enabled: bool,
+    /// Repeats in a row, the first press being none.
+    repeats: usize,
+    last_direction: i32,
+    last_event_at: Option<u64>,
+}
+
+impl KeyRepeat {
+    pub fn new(enabled: bool) -> KeyRepeat {
+        KeyRepeat {
+            enabled: enabled,
+            repeats: 0,
+            last_direction: 0,
+            last_event_at: None,
+        }
+    }
+
+    /// Lines to scroll for a press in the given direction, `time` being
+    /// expressed in milliseconds.
+    pub fn step(&mut self, direction: i32, time: u64) -> usize {
+        if !self.enabled {
+            return 1;
+        }
+
+        self.repeats = match self.last_event_at {
+            Some(last) if direction == self.last_direction &&
+                          time.saturating_sub(last) <= REPEAT_INTERVAL => self.repeats + 1,
+            _ => 0,
+        };
+
+        self.last_direction = direction;
+        self.last_event_at = Some(time);
+
+        REPEAT_STEPS[min(self.repeats / REPEATS_PER_STEP, REPEAT_STEPS.len() - 1)]
+    }
+
+    pub fn reset(&mut self) {
+        self.repeats = 0;
+        self.last_event_at = None;
+    }
+}
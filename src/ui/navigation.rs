@@ -20,6 +20,10 @@ use ncurses::*;
 
 use ui::menu::Menu;
 use ui::search::Search;
+use ui::prompt::Prompt;
+use ui::status::Status;
+use ui::rules::RulesPanel;
+use ui::patterns::PatternsPanel;
 
 pub static HEIGHT: i32 = 1;
 
@@ -27,11 +31,18 @@ pub static HEIGHT: i32 = 1;
 pub enum State {
     Menu,
     Search,
+    Prompt,
+    Rules,
+    Patterns,
 }
 
 pub struct Navigation {
     pub menu: Menu,
     pub search: Search,
+    pub prompt: Prompt,
+    pub status: Status,
+    pub rules: RulesPanel,
+    pub patterns: PatternsPanel,
     pub state: State,
 }
 
@@ -40,6 +51,10 @@ impl Navigation {
         Navigation {
             menu: Menu::new(position_x, position_y, menu_item_names),
             search: Search::new(position_x, position_y),
+            prompt: Prompt::new(position_x, position_y),
+            status: Status::new(position_x),
+            rules: RulesPanel::new(),
+            patterns: PatternsPanel::new(),
             state: State::Menu,
         }
     }
@@ -47,11 +62,18 @@ impl Navigation {
     pub fn render(&self) {
         self.search.render();
         self.menu.render();
+        self.prompt.render();
         self.handle_visibility();
     }
 
-    pub fn destroy(&self) {
-        self.menu.destroy();
+    pub fn render_status(&self) {
+        if self.state == State::Menu {
+            // Redraws what a shorter status leaves uncovered
+            self.menu.render();
+            self.status.show();
+            update_panels();
+            doupdate();
+        }
     }
 
     pub fn change_state(&mut self, new_state: State) -> bool {
@@ -66,21 +88,55 @@ impl Navigation {
 
     pub fn resize(&self, container_width: i32, offset: i32) {
         self.search.resize(container_width, offset);
-        mvwin(self.menu.window, offset, 0);
-
-        self.render();
+        self.prompt.resize(container_width, offset);
+        self.status.resize(container_width, offset);
+        self.rules.resize(offset);
+        self.patterns.resize(offset);
+        self.menu.resize(container_width, offset);
     }
 
     fn handle_visibility(&self) {
         match self.state {
             State::Menu => {
                 self.search.hide();
+                self.prompt.hide();
+                self.rules.hide();
+                self.patterns.hide();
                 self.menu.show();
+                self.status.show();
             }
             State::Search => {
                 self.menu.hide();
+                self.prompt.hide();
+                self.status.hide();
+                self.rules.hide();
+                self.patterns.hide();
                 self.search.show();
             }
+            State::Prompt => {
+                self.menu.hide();
+                self.search.hide();
+                self.status.hide();
+                self.rules.hide();
+                self.patterns.hide();
+                self.prompt.show();
+            }
+            State::Rules => {
+                self.search.hide();
+                self.prompt.hide();
+                self.status.hide();
+                self.patterns.hide();
+                self.menu.show();
+                self.rules.show();
+            }
+            State::Patterns => {
+                self.search.hide();
+                self.prompt.hide();
+                self.status.hide();
+                self.rules.hide();
+                self.menu.show();
+                self.patterns.show();
+            }
         }
 
         update_panels();
@@ -0,0 +1,175 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cell::{Cell, RefCell};
+
+use ncurses::*;
+
+use core::pattern_stats::SortColumn;
+use ui::color;
+use ui::layout::scroll_to_show;
+use ui::rules::fit;
+
+static TITLE_COLOR_PAIR_ID: i16 = 1;
+static HINT: &'static str = "t/m/l sort  enter jump  r reset  R reset all  P close";
+static EMPTY_TEXT: &'static str = "No highlight patterns yet, press h in the menu to add one";
+
+pub struct PatternRow {
+    pub source: String,
+    pub total: usize,
+    pub recent: usize,
+    /// Time of the most recent hit, as `HH:MM:SS`.
+    pub last_hit: Option<String>,
+}
+
+impl PatternRow {
+    fn text(&self) -> String {
+        format!("{:>8} {:>8} {:>9}  {}",
+                self.total,
+                self.recent,
+                self.last_hit.as_ref().map_or("-", |value| value.as_str()),
+                self.source)
+    }
+}
+
+fn header(sort: SortColumn) -> String {
+    let mark = |column: SortColumn, name: &str| {
+        if column == sort { format!("{}*", name) } else { name.to_string() }
+    };
+
+    format!("{:>8} {:>8} {:>9}  pattern",
+            mark(SortColumn::Total, "total"),
+            mark(SortColumn::Recent, "last min"),
+            mark(SortColumn::Last, "last hit"))
+}
+
+/// Overlay listing how often each highlight pattern was found.
+pub struct PatternsPanel {
+    pub window: WINDOW,
+    panel: PANEL,
+    rows: RefCell<Vec<PatternRow>>,
+    selected: Cell<usize>,
+    offset: Cell<usize>,
+    sort: Cell<SortColumn>,
+    container_height: Cell<i32>,
+}
+
+impl PatternsPanel {
+    pub fn new() -> PatternsPanel {
+        let window = newwin(1, 1, 0, 0);
+
+        PatternsPanel {
+            window: window,
+            panel: new_panel(window),
+            rows: RefCell::new(vec![]),
+            selected: Cell::new(0),
+            offset: Cell::new(0),
+            sort: Cell::new(SortColumn::Total),
+            container_height: Cell::new(LINES() - 1),
+        }
+    }
+
+    pub fn set_rows(&self, rows: Vec<PatternRow>) {
+        let last_index = rows.len().saturating_sub(1);
+
+        *self.rows.borrow_mut() = rows;
+        self.selected.set(self.selected.get().min(last_index));
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected.get()
+    }
+
+    pub fn select(&self, index: usize) {
+        let last_index = self.rows.borrow().len().saturating_sub(1);
+        self.selected.set(index.min(last_index));
+    }
+
+    pub fn sort(&self) -> SortColumn {
+        self.sort.get()
+    }
+
+    pub fn set_sort(&self, column: SortColumn) {
+        self.sort.set(column);
+        self.selected.set(0);
+    }
+
+    pub fn render(&self) {
+        let rows = self.rows.borrow();
+        let height = (rows.len().max(1) as i32 + 3).min(self.container_height.get()).max(1);
+        let width = COLS();
+
+        wresize(self.window, height, width);
+        werase(self.window);
+
+        let title = format!(" Highlight hits, by {}", self.sort.get().name());
+        wattron(self.window, color::pair_attr(TITLE_COLOR_PAIR_ID, A_BOLD()));
+        mvwprintw(self.window, 0, 0, &fit(&title, width));
+        wattroff(self.window, color::pair_attr(TITLE_COLOR_PAIR_ID, A_BOLD()));
+
+        if rows.is_empty() {
+            mvwprintw(self.window, 1, 1, &fit(EMPTY_TEXT, width - 1));
+        } else {
+            wattron(self.window, A_BOLD());
+            mvwprintw(self.window, 1, 0, &fit(&format!(" {}", header(self.sort.get())), width));
+            wattroff(self.window, A_BOLD());
+        }
+
+        let visible = (height - 3).max(0) as usize;
+        let offset = scroll_to_show(self.offset.get(), self.selected.get(), visible)
+            .min(rows.len().saturating_sub(visible));
+        self.offset.set(offset);
+
+        for (i, row) in rows.iter().enumerate().skip(offset).take(visible) {
+            let is_selected = i == self.selected.get();
+            if is_selected {
+                wattron(self.window, A_REVERSE());
+            }
+            let y = (i - offset) as i32 + 2;
+            mvwprintw(self.window, y, 0, &fit(&format!(" {}", row.text()), width));
+            if is_selected {
+                wattroff(self.window, A_REVERSE());
+            }
+        }
+
+        wattron(self.window, A_DIM());
+        mvwprintw(self.window, height - 1, 1, &fit(HINT, width - 1));
+        wattroff(self.window, A_DIM());
+
+        wrefresh(self.window);
+    }
+
+    /// Draws the panel again after the content was refreshed underneath.
+    pub fn refresh(&self) {
+        touchwin(self.window);
+        wrefresh(self.window);
+    }
+
+    pub fn resize(&self, container_height: i32) {
+        self.container_height.set(container_height);
+    }
+
+    pub fn show(&self) {
+        self.render();
+        show_panel(self.panel);
+    }
+
+    pub fn hide(&self) {
+        hide_panel(self.panel);
+    }
+}
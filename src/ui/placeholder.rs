@@ -0,0 +1,61 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use ncurses::*;
+
+use ui::layout::{MIN_WIDTH, MIN_HEIGHT};
+
+/// Covers the whole screen of terminals too small to be laid out, in place
+/// of widgets that would be drawn over each other.
+pub struct Placeholder {
+    window: WINDOW,
+    panel: PANEL,
+}
+
+impl Placeholder {
+    pub fn new() -> Placeholder {
+        let window = newwin(1, 1, 0, 0);
+        let panel = new_panel(window);
+        hide_panel(panel);
+
+        Placeholder {
+            window: window,
+            panel: panel,
+        }
+    }
+
+    pub fn render(&self, width: i32, height: i32) {
+        wresize(self.window, height.max(1), width.max(1));
+        mvwin(self.window, 0, 0);
+        werase(self.window);
+        // Wraps onto the next rows, as far as they go
+        mvwprintw(self.window, 0, 0, &message());
+        show_panel(self.panel);
+        update_panels();
+        doupdate();
+    }
+
+    pub fn hide(&self) {
+        hide_panel(self.panel);
+        update_panels();
+    }
+}
+
+pub fn message() -> String {
+    format!("terminal too small (need ≥ {}×{})", MIN_WIDTH, MIN_HEIGHT)
+}
@@ -16,16 +16,28 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use std::cmp::max;
+use std::rc::Rc;
+use std::ops::Range;
+
 use ncurses::*;
 
 use core::line::Line;
+use core::highlights::{HighlightSpan, Layers, SharedSpans};
 use core::buffer::BufferLines;
 use utils::ansi_decoder::{Component, Style};
+use utils::invisibles::{symbol_for, trailing_whitespace_offset, escape_nul};
 use ui::frame::{Frame, NORMAL_HIGHLIGHT_COLOR, CURRENT_HIGHLIGHT_COLOR};
-use ui::color::ColorPair;
+use ui::color::{self, ColorPair};
 use ui::content::Content;
 use ui::search::Query;
-use ui::highlighter::{Highlight, LineHighlighter, State as HighlightState};
+use ui::highlighter::{Highlight, LineHighlighter, DisplayedText, State as HighlightState};
+use core::metrics::METRICS;
+use core::line::SearchPattern;
+use core::timestamp::GapTracker;
+use core::range::LineRange;
+use ui::rendered_line::{Marker, WrappedRows};
+use utils::wrap::wrap_indented;
 
 pub trait Print {
     fn print(&self, content: &Content);
@@ -33,18 +45,149 @@ pub trait Print {
 
 impl Print for Line {
     fn print(&self, content: &Content) {
-        match self.components {
+        if content.raw.get() {
+            content.start_line(self);
+            print_line(self, content);
+            return;
+        }
+
+        let dimmed = content.is_dimmed(self);
+        if dimmed {
+            wattron(content.window, A_DIM());
+        }
+        let stderr = if self.is_stderr() { stderr_attr() } else { 0 };
+        let flashed = if content.flash.borrow().is_flashed(self.id) { A_REVERSE() } else { 0 };
+        wattron(content.window, stderr | flashed);
+
+        content.start_line(self);
+        print_line(self, content);
+
+        wattroff(content.window, stderr | flashed);
+        if dimmed {
+            wattroff(content.window, A_DIM());
+        }
+    }
+}
+
+/// Lines a container wrote to stderr are shown in red, or bold without colors.
+fn stderr_attr() -> attr_t {
+    if color::is_enabled() {
+        color::stderr_color().to_attr()
+    } else {
+        A_BOLD()
+    }
+}
+
+/// Prints the marker on a row of its own, below the line just printed.
+pub fn print_marker(content: &Content, marker: &Marker) {
+    wattron(content.window, A_DIM());
+    wprintw(content.window, &format!("{}\n", marker.text()));
+    wattroff(content.window, A_DIM());
+}
+
+pub fn folded_text(count: usize) -> String {
+    format!("⋮ ({} blank lines)", count)
+}
+
+/// Prints a run of blank lines as a single dimmed row.
+pub fn print_folded(content: &Content, count: usize) {
+    wattron(content.window, A_DIM());
+    wprintw(content.window, &format!("{}\n", folded_text(count)));
+    wattroff(content.window, A_DIM());
+}
+
+pub fn is_blank(line: &Line) -> bool {
+    line.content_without_ansi.trim().is_empty()
+}
+
+/// Pairs each line with the number of blank lines folded into it. Runs of
+/// blank lines are reduced to their first line, the others having none.
+/// Blank lines found by the search are left out of the runs, so that every
+/// match can be moved to.
+pub fn fold_blank_runs(lines: Vec<Line>, search: Option<&SearchPattern>) -> Vec<(Line, usize)> {
+    let foldable = |line: &Line| {
+        is_blank(line) && !search.map_or(false, |pattern| line.contains(pattern))
+    };
+    let mut folded: Vec<(Line, usize)> = vec![];
+
+    for line in lines {
+        if foldable(&line) {
+            if let Some(&mut (ref previous, ref mut count)) = folded.last_mut() {
+                if *count > 0 {
+                    *count += 1;
+                    continue;
+                }
+                if foldable(previous) {
+                    *count = 2;
+                    continue;
+                }
+            }
+        }
+
+        folded.push((line, 0));
+    }
+
+    folded
+}
+
+/// Paints the persistent highlight spans over the line just printed, from
+/// the given row.
+pub fn print_highlights(line: &Line, spans: &[HighlightSpan], content: &Content, start_row: usize) {
+    let end_row = content.cursor_row();
+    let width = getmaxx(content.window);
+
+    let rows = content.line_rows.borrow().clone();
+    let layers = Layers::new(spans);
+
+    if let Some(pattern) = layers.tint {
+        print_tint(content, pattern, start_row..end_row);
+    }
+    for span in layers.spans {
+        let color = color::highlight_color(span.pattern).calculate_id();
+        let mut highlighter = LineHighlighter::new(content, line, width, color);
+        if let Some(ref rows) = rows {
+            highlighter = highlighter.with_rows(rows.clone());
+        }
+
+        highlighter.print_range(span.start..span.end, start_row);
+    }
+
+    content.move_to(end_row);
+}
+
+/// Colors every cell of the rows, up to the right edge, with the colors of
+/// the pattern. Without colors, the rows are shown in bold.
+fn print_tint(content: &Content, pattern: usize, rows: Range<usize>) {
+    let (attr, pair) = if color::is_enabled() {
+        (0, color::highlight_color(pattern).calculate_id())
+    } else {
+        (A_BOLD(), 0)
+    };
+
+    for row in rows {
+        if let Some(y) = content.pad_row(row) {
+            mvwchgat(content.window, y, 0, -1, attr, pair);
+        }
+    }
+}
+
+fn print_line(line: &Line, content: &Content) {
+    let start_y = getcury(content.window);
+
+    if content.reveals_invisibles() {
+        print_with_invisibles(line, content);
+    } else {
+        match line.components {
             Some(ref value) => {
                 for component in &value.items {
                     component.print(content);
                 }
-                waddch(content.window, '\n' as chtype);
-            }
-            None => {
-                wprintw(content.window, &format!("{}\n", self.content_without_ansi));
             }
-        };
+            None => content.print_text(&escape_nul(&line.content_without_ansi)),
+        }
     }
+
+    content.end_line(start_y);
 }
 
 impl Print for Component {
@@ -54,7 +197,7 @@ impl Print for Component {
                 value.print(content);
             }
             Component::Content(ref value) => {
-                wprintw(content.window, value);
+                content.print_text(&escape_nul(value));
             }
         };
     }
@@ -95,30 +238,112 @@ impl Print for Style {
     }
 }
 
-#[derive(Copy, Clone)]
-pub struct Viewport {
-    pub reverse_index: usize,
-    pub visible_height: usize,
-}
+fn print_with_invisibles(line: &Line, content: &Content) {
+    let trailing_offset = trailing_whitespace_offset(&line.content_without_ansi);
+
+    match line.components {
+        Some(ref value) => {
+            let mut char_offset = 0;
 
-impl Viewport {
-    fn new(reverse_index: usize, visible_height: usize) -> Viewport {
-        Viewport {
-            reverse_index: reverse_index,
-            visible_height: visible_height,
+            for component in &value.items {
+                match *component {
+                    Component::Style(style) => style.print(content),
+                    Component::Content(ref text) => {
+                        print_revealed_text(content, text, char_offset, trailing_offset);
+                        char_offset += text.chars().count();
+                    }
+                }
+            }
         }
+        None => print_revealed_text(content, &line.content_without_ansi, 0, trailing_offset),
     }
+}
+
+fn print_revealed_text(content: &Content, text: &str, char_offset: usize, trailing_offset: usize) {
+    let mut pending = String::new();
 
-    pub fn limit(&self) -> usize {
-        self.reverse_index + self.visible_height
+    for (i, value) in text.chars().enumerate() {
+        match symbol_for(value, char_offset + i >= trailing_offset) {
+            Some(symbol) => {
+                content.print_text(&pending);
+                pending.clear();
+
+                wattron(content.window, A_DIM());
+                content.print_text(symbol);
+                wattroff(content.window, A_DIM());
+            }
+            None => pending.push(value),
+        }
     }
+
+    content.print_text(&pending);
+}
+
+/// Rows the line takes once printed, unlike `Line::guess_height` counting the
+/// continuation prefix and wide characters moved to the next row.
+pub fn line_height(line: &Line, width: usize, indent: usize, show_invisibles: bool) -> usize {
+    let displayed = DisplayedText::new(&line.content_without_ansi, show_invisibles);
+
+    wrap_indented(&displayed.text, width, indent).len()
+}
+
+/// Number of lines, from the oldest, to leave out so that the rest take at
+/// most `rows`.
+pub fn lines_over(heights: &[usize], rows: usize) -> usize {
+    let mut total = 0;
+    let kept = heights.iter()
+        .rev()
+        .take_while(|&&height| {
+            total += height;
+            total <= rows
+        })
+        .count();
+
+    heights.len() - kept
+}
+
+/// Markers going above each line, below the previous one where the cursor
+/// is, followed by those going below the last line.
+pub fn line_markers(lines: &[(Line, usize)],
+                    gap_threshold: Option<i64>,
+                    range: Option<LineRange>)
+                    -> Vec<Vec<Marker>> {
+    let mut gaps = gap_threshold.map(GapTracker::new);
+    let mut in_range = false;
+
+    let mut markers = lines.iter()
+        .map(|&(ref line, _)| {
+            let gap = gaps.as_mut().and_then(|tracker| {
+                tracker.advance(line.timestamp(), line.received_millis())
+            });
+            let was_in_range = in_range;
+            in_range = range.map_or(false, |value| value.contains(line.id));
+
+            let mut markers = vec![];
+            if was_in_range && !in_range {
+                markers.push(Marker::RangeEnd);
+            }
+            if let Some(value) = gap {
+                markers.push(Marker::Gap(value));
+            }
+            if in_range && !was_in_range {
+                markers.push(Marker::RangeStart);
+            }
+            markers
+        })
+        .collect::<Vec<_>>();
+
+    markers.push(if in_range { vec![Marker::RangeEnd] } else { vec![] });
+    markers
 }
 
 pub struct LinesPrinter<'a> {
     frame: &'a mut Frame,
-    height: i32,
+    height: usize,
     buffer_lines: &'a BufferLines<'a>,
     query: Option<Query>,
+    /// Rendered lines searched, all of them when none.
+    search_lines: Option<Range<usize>>,
 }
 
 impl<'a> LinesPrinter<'a> {
@@ -131,15 +356,36 @@ impl<'a> LinesPrinter<'a> {
             height: 0,
             buffer_lines: lines,
             query: query,
+            search_lines: None,
         }
     }
 
+    /// Searches only the given rendered lines, for searches done a chunk at
+    /// a time. The current match is picked once the last one is searched.
+    pub fn searching(mut self, lines: Range<usize>) -> LinesPrinter<'a> {
+        self.search_lines = Some(lines);
+        self
+    }
+
     pub fn draw(&mut self) {
+        METRICS.record_render();
+        self.frame.content.update_dim_cutoff();
+        self.frame.content.raw.set(self.buffer_lines.buffer.show_raw);
+        *self.frame.content.search_pattern.borrow_mut() =
+            self.query.as_ref().map(|query| query.pattern.clone());
+
         if self.query.is_some() {
             if self.query.as_ref().unwrap().filter {
+                if self.unfolds_matches() {
+                    self.handle_print();
+                }
                 self.handle_filter()
             } else {
-                if self.frame.initial_rendered_lines.is_some() {
+                let is_first_chunk = self.search_lines
+                    .as_ref()
+                    .map_or(true, |lines| lines.start == 0);
+                if (self.frame.initial_rendered_lines.is_some() || self.unfolds_matches()) &&
+                   is_first_chunk {
                     self.handle_print();
                 }
                 self.handle_search();
@@ -149,17 +395,91 @@ impl<'a> LinesPrinter<'a> {
         }
     }
 
+    // Blank lines found by a new search are printed again, out of their runs
+    fn unfolds_matches(&self) -> bool {
+        let highlight = self.query.as_ref().map(|query| query.highlight);
+        self.frame.content.fold_blank_lines.get() && !self.frame.content.raw.get() &&
+        (highlight == Some(Highlight::VisibleOrLast) || highlight == Some(Highlight::Current))
+    }
+
     fn handle_print(&mut self) {
+        // Lines that haven't changed keep wrapping where they did
+        let wrapped = self.frame.rendered_lines.wrapped_rows_by_id();
         self.frame.reset();
         self.height = 0;
 
-        for line in self.buffer_lines {
-            let actual_height = self.frame.content.calculate_height_change(|| {
-                line.print(&self.frame.content);
-            });
+        let raw = self.frame.content.raw.get();
+        let gap_threshold = self.frame.content.gap_threshold.get().filter(|_| !raw);
+        let range = self.frame.content.line_range.get().filter(|_| !raw);
+
+        let lines = self.buffer_lines
+            .into_iter()
+            .map(|line| self.buffer_lines.buffer.display_line(line))
+            .collect::<Vec<_>>();
+        let lines = if self.frame.content.fold_blank_lines.get() && !raw {
+            fold_blank_runs(lines, self.query.as_ref().map(|query| &query.pattern))
+        } else {
+            lines.into_iter().map(|line| (line, 0)).collect()
+        };
+        let width = self.frame.width as usize;
+        let indent = self.frame.content.continuation_width();
+        let show_invisibles = self.frame.content.reveals_invisibles();
+        let rows = lines.iter()
+            .map(|&(ref line, folded)| {
+                if folded > 0 {
+                    return None;
+                }
+                Some(match wrapped.get(&line.id) {
+                    Some(rows) if rows.fits(line, width, indent, show_invisibles) => rows.clone(),
+                    _ => Rc::new(WrappedRows::new(line, width, indent, show_invisibles)),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // Markers take a row of the buffer each
+        let mut markers = line_markers(&lines, gap_threshold, range);
+        let last_markers = markers.pop().unwrap_or_default();
+        let heights = rows.iter()
+            .map(|rows| rows.as_ref().map_or(1, |rows| rows.rows.len()))
+            .collect::<Vec<_>>();
+        let rows_taken = heights.iter()
+            .zip(&markers)
+            .map(|(height, markers)| height + markers.len())
+            .collect::<Vec<_>>();
+        let rows_left = self.frame.content.buffer_rows() - last_markers.len();
+        let skipped = lines_over(&rows_taken, rows_left);
+        let left_out = lines[..skipped].iter().map(|&(_, folded)| max(folded, 1)).sum();
+        self.frame.content.left_out.set(left_out);
+
+        for ((((line, folded), height), rows), markers) in
+            lines.into_iter().zip(heights).zip(rows).zip(markers).skip(skipped) {
+            self.add_markers(markers);
+
+            self.height += height;
+            match rows {
+                Some(rows) => {
+                    // Only the lines kept are looked at, which are few
+                    let spans = self.buffer_lines.highlights().spans(&line.content_without_ansi);
+                    self.frame.rendered_lines.create_wrapped(line, rows, SharedSpans::new(spans))
+                }
+                None => self.frame.rendered_lines.create_folded(line, folded),
+            }
+        }
+
+        self.add_markers(last_markers);
+
+        let viewport = self.frame.viewport(self.buffer_lines.buffer.reverse_index());
+        self.frame.paint(&viewport);
+    }
+
+    fn add_markers(&mut self, markers: Vec<Marker>) {
+        if self.frame.rendered_lines.is_empty() {
+            return;
+        }
 
-            self.height += actual_height;
-            self.frame.rendered_lines.create(line.clone(), actual_height, None);
+        for marker in markers {
+            self.frame.rendered_lines.add_marker(marker);
+            self.height += 1;
         }
     }
 
@@ -167,14 +487,21 @@ impl<'a> LinesPrinter<'a> {
         let query = self.query.as_ref().unwrap();
 
         if query.highlight == Highlight::VisibleOrLast || query.highlight == Highlight::Current {
-            self.frame.navigation.search.matches_found = false;
-            self.height = 0;
+            let count = self.frame.rendered_lines.len();
+            let lines = self.search_lines.clone().unwrap_or(0..count);
+            if lines.start == 0 {
+                self.frame.navigation.search.matches_found = false;
+                self.frame.content.truncated_highlights.set(0);
+            }
+            self.height = self.frame.rendered_lines.height_up_to_index(lines.start);
 
             for rendered_line in self.frame
                 .rendered_lines
                 .entries
-                .iter_mut() {
-                if rendered_line.search(&query.text,
+                .iter_mut()
+                .skip(lines.start)
+                .take(lines.len()) {
+                if rendered_line.search(&query.pattern,
                                         &self.frame.content,
                                         self.frame.width,
                                         self.height) {
@@ -183,13 +510,18 @@ impl<'a> LinesPrinter<'a> {
 
                 self.height += rendered_line.height;
             }
-            if query.highlight == Highlight::Current && self.highlight_doesnt_require_update() {
-                self.highlight_current_item(&query.text, CURRENT_HIGHLIGHT_COLOR);
+            if lines.end < count {
+                return;
+            }
+            if query.highlight == Highlight::Current && self.restore_highlighted_match() {
+                self.highlight_current_item(&query.pattern, CURRENT_HIGHLIGHT_COLOR);
             } else if self.frame.navigation.search.matches_found {
                 self.update_current_and_highlight_item();
             }
         } else if self.frame.navigation.search.matches_found {
-            self.highlight_current_item(&query.text, NORMAL_HIGHLIGHT_COLOR);
+            if self.restore_highlighted_match() {
+                self.highlight_current_item(&query.pattern, NORMAL_HIGHLIGHT_COLOR);
+            }
             self.update_current_and_highlight_item();
         }
     }
@@ -199,18 +531,19 @@ impl<'a> LinesPrinter<'a> {
 
         if query.highlight == Highlight::VisibleOrLast || query.highlight == Highlight::Current {
             self.frame.content.clear();
+            self.frame.content.truncated_highlights.set(0);
             self.height = 0;
 
             let mut filtered_rendered_lines = self.frame
                 .initial_rendered_lines
                 .as_mut()
                 .unwrap_or(&mut self.frame.rendered_lines)
-                .matching(&query.text);
+                .matching(&query.pattern);
             self.frame.navigation.search.matches_found = !filtered_rendered_lines.is_empty();
 
+            // Nothing is drawn while the pad is cleared, only matches found
             for rendered_line in filtered_rendered_lines.entries.iter_mut() {
-                rendered_line.print(&self.frame.content, self.height);
-                rendered_line.found_matches = rendered_line.highlight(&query.text,
+                rendered_line.found_matches = rendered_line.highlight(&query.pattern,
                                                                       &self.frame.content,
                                                                       self.frame.width,
                                                                       self.height);
@@ -219,45 +552,42 @@ impl<'a> LinesPrinter<'a> {
             }
 
             self.frame.replace_rendered_lines(filtered_rendered_lines);
+            let viewport = self.frame.viewport(self.buffer_lines.buffer.reverse_index());
+            self.frame.paint(&viewport);
 
-            if query.highlight == Highlight::Current && self.highlight_doesnt_require_update() {
-                self.highlight_current_item(&query.text, CURRENT_HIGHLIGHT_COLOR);
+            if query.highlight == Highlight::Current && self.restore_highlighted_match() {
+                self.highlight_current_item(&query.pattern, CURRENT_HIGHLIGHT_COLOR);
             } else if self.frame.navigation.search.matches_found {
                 self.update_current_and_highlight_item();
             }
         } else if self.frame.navigation.search.matches_found {
-            self.highlight_current_item(&query.text, NORMAL_HIGHLIGHT_COLOR);
+            if self.restore_highlighted_match() {
+                self.highlight_current_item(&query.pattern, NORMAL_HIGHLIGHT_COLOR);
+            }
             self.update_current_and_highlight_item();
         }
     }
 
     fn update_current_and_highlight_item(&self) {
         let query = self.query.as_ref().unwrap();
-        let viewport = Viewport::new(self.buffer_lines.buffer.reverse_index.get(),
-                                     self.frame.content_height() as usize);
+        let viewport = self.frame.viewport(self.buffer_lines.buffer.reverse_index());
 
         HighlightState::new(self.frame.content.state.borrow_mut(),
                             &self.frame.rendered_lines,
                             viewport)
             .update(&query.highlight);
-        self.highlight_current_item(&query.text, CURRENT_HIGHLIGHT_COLOR);
+        self.highlight_current_item(&query.pattern, CURRENT_HIGHLIGHT_COLOR);
 
         let matched_line = self.frame.content.highlighted_line();
-        if !self.frame.rendered_lines.is_match_in_viewport(matched_line, viewport) {
+        if !self.frame.rendered_lines.is_match_in_viewport(matched_line, &viewport) {
             self.update_scroll_position();
+        } else if query.highlight == Highlight::Next || query.highlight == Highlight::Previous {
+            self.keep_scroll_margin();
         }
     }
 
-    fn highlight_current_item(&self, text: &str, color: i16) {
-        let state = self.frame.content.state.borrow();
-        let line = &self.frame.rendered_lines[state.highlighted_line].line;
-
-        let accumulated_height = self.frame
-            .rendered_lines
-            .height_up_to_index(state.highlighted_line);
-        let highlighter =
-            LineHighlighter::new(self.frame.content.window, line, self.frame.width, color);
-        highlighter.print_single_match(text, state.highlighted_match, accumulated_height);
+    fn highlight_current_item(&self, pattern: &SearchPattern, color: i16) {
+        self.frame.highlight_match(pattern, color);
     }
 
     fn update_scroll_position(&self) {
@@ -265,13 +595,43 @@ impl<'a> LinesPrinter<'a> {
         let index = self.frame
             .rendered_lines
             .buffer_reverse_index(state.highlighted_line, state.highlighted_match);
-        self.buffer_lines
-            .buffer
-            .set_reverse_index(index - self.frame.height / 2, self.frame.max_scroll_value());
+        let buffer = self.buffer_lines.buffer;
+
+        let mut viewport = self.frame.viewport(buffer.reverse_index());
+        viewport.scroll_to(index - self.frame.height as i64 / 2);
+        buffer.set_reverse_index(viewport.reverse_index);
     }
 
-    fn highlight_doesnt_require_update(&self) -> bool {
+    /// Scrolls early when moving to a match near the edge of the screen, so
+    /// that the rows around it stay in sight.
+    fn keep_scroll_margin(&self) {
         let state = self.frame.content.state.borrow();
-        self.frame.rendered_lines[state.highlighted_line].found_matches.is_some()
+        let row = self.frame
+            .rendered_lines
+            .match_row(state.highlighted_line, state.highlighted_match);
+        let buffer = self.buffer_lines.buffer;
+
+        let mut viewport = self.frame.viewport(buffer.reverse_index());
+        viewport.scroll_with_margin(row, self.frame.content.scrolloff);
+        buffer.set_reverse_index(viewport.reverse_index);
+    }
+
+    /// Points the highlighted match at the line it was on before the lines
+    /// were rendered again, as their positions shift when new ones arrive.
+    fn restore_highlighted_match(&self) -> bool {
+        let mut state = self.frame.content.state.borrow_mut();
+        let line_id = match state.highlighted_line_id {
+            Some(value) => value,
+            None => return false,
+        };
+
+        match self.frame.rendered_lines.resolve_match(line_id, state.highlighted_match) {
+            Some(matched_line) => {
+                state.highlighted_line = matched_line.line;
+                state.highlighted_match = matched_line.match_index;
+                true
+            }
+            None => false,
+        }
     }
 }
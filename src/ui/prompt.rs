@@ -0,0 +1,335 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use unicode_width::UnicodeWidthChar;
+use ncurses::*;
+
+use ui::color;
+
+static PROMPT_COLOR_PAIR_ID: i16 = 1;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PromptKind {
+    GotoLine,
+    GotoTime,
+    Rewrite,
+    EditRewrite(usize),
+    ChooseAction,
+    ChooseExclusion,
+    Highlight,
+    ExportMatches,
+    BufferLines,
+    GapThreshold,
+    RangeTimes,
+    RangeAction,
+    RegexTester,
+    UseRegex,
+    Profile,
+    QuickFind,
+    /// Labels the search bar, whose text is edited the way prompts are.
+    Search,
+}
+
+impl PromptKind {
+    pub fn label(&self) -> &'static str {
+        match *self {
+            PromptKind::GotoLine => "Go to line:",
+            PromptKind::GotoTime => "Go to time (14:32, 2016-03-01T14:32:05 or -5m):",
+            PromptKind::Rewrite |
+            PromptKind::EditRewrite(_) => "Rewrite /pattern/replacement/:",
+            PromptKind::ChooseAction => "Action number:",
+            PromptKind::ChooseExclusion => "Hide lines like this one, number:",
+            PromptKind::Highlight => "Highlight text or /regex/:",
+            PromptKind::ExportMatches => "Export matches with context lines (empty for none):",
+            PromptKind::BufferLines => "Buffer lines:",
+            PromptKind::GapThreshold => "Mark silences longer than (90s, 5m, 0 to stop):",
+            PromptKind::RangeTimes => "Select lines between (14:30 15:00):",
+            PromptKind::RangeAction => "Range: s save, c count matches, i info, |command:",
+            PromptKind::RegexTester => "Test regex:",
+            PromptKind::UseRegex => "Keep regex as: s search, f filter tab, h highlight:",
+            PromptKind::Profile => "Switch to profile (empty to list them):",
+            PromptKind::QuickFind => "Find on screen:",
+            PromptKind::Search => "Search:",
+        }
+    }
+}
+
+#[derive(Clone, Hash, PartialEq, Eq)]
+pub enum PromptAction {
+    Insert(char),
+    Paste(String),
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+    KillToStart,
+    KillWord,
+    Submit,
+    Cancel,
+}
+
+#[derive(PartialEq, Debug)]
+pub enum PromptEvent {
+    Changed,
+    Submitted(String),
+    Cancelled,
+}
+
+#[derive(Default)]
+pub struct PromptBuffer {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl PromptBuffer {
+    pub fn new(text: &str) -> PromptBuffer {
+        let chars = text.chars().collect::<Vec<_>>();
+
+        PromptBuffer {
+            cursor: chars.len(),
+            chars: chars,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.chars.iter().cloned().collect()
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    pub fn apply(&mut self, action: &PromptAction) -> Option<PromptEvent> {
+        let changed = match *action {
+            PromptAction::Insert(value) => {
+                self.insert(value);
+                true
+            }
+            PromptAction::Paste(ref text) => {
+                self.insert_str(text);
+                !text.is_empty()
+            }
+            PromptAction::Backspace => self.delete_backward(),
+            PromptAction::Delete => self.delete_forward(),
+            PromptAction::Left => {
+                self.cursor = self.cursor.saturating_sub(1);
+                false
+            }
+            PromptAction::Right => {
+                self.cursor = (self.cursor + 1).min(self.chars.len());
+                false
+            }
+            PromptAction::Home => {
+                self.cursor = 0;
+                false
+            }
+            PromptAction::End => {
+                self.cursor = self.chars.len();
+                false
+            }
+            PromptAction::KillToStart => self.kill_to_start(),
+            PromptAction::KillWord => self.kill_word(),
+            PromptAction::Submit => return Some(PromptEvent::Submitted(self.text())),
+            PromptAction::Cancel => return Some(PromptEvent::Cancelled),
+        };
+
+        if changed {
+            Some(PromptEvent::Changed)
+        } else {
+            None
+        }
+    }
+
+    pub fn insert(&mut self, value: char) {
+        self.chars.insert(self.cursor, value);
+        self.cursor += 1;
+    }
+
+    pub fn insert_str(&mut self, text: &str) {
+        for value in text.chars() {
+            self.insert(value);
+        }
+    }
+
+    pub fn delete_backward(&mut self) -> bool {
+        if self.cursor == 0 {
+            return false;
+        }
+
+        self.cursor -= 1;
+        self.chars.remove(self.cursor);
+        true
+    }
+
+    pub fn delete_forward(&mut self) -> bool {
+        if self.cursor == self.chars.len() {
+            return false;
+        }
+
+        self.chars.remove(self.cursor);
+        true
+    }
+
+    pub fn kill_to_start(&mut self) -> bool {
+        let changed = self.cursor > 0;
+        self.chars.drain(..self.cursor);
+        self.cursor = 0;
+        changed
+    }
+
+    pub fn kill_word(&mut self) -> bool {
+        let mut start = self.cursor;
+
+        while start > 0 && self.chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        while start > 0 && !self.chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+
+        let changed = start != self.cursor;
+        self.chars.drain(start..self.cursor);
+        self.cursor = start;
+        changed
+    }
+
+    /// Display width of the text preceding the cursor.
+    pub fn cursor_width(&self) -> usize {
+        self.chars[..self.cursor].iter().map(|value| value.width().unwrap_or(0)).sum()
+    }
+
+    /// Text to be displayed within `width` cells, scrolled so that the cursor
+    /// is always visible. Returns the visible text along with the cursor column.
+    pub fn visible_text(&self, width: usize) -> (String, usize) {
+        let cursor_width = self.cursor_width();
+        let mut skipped_width = 0;
+        let mut skipped_chars = 0;
+
+        while cursor_width - skipped_width >= width && skipped_chars < self.cursor {
+            skipped_width += self.chars[skipped_chars].width().unwrap_or(0);
+            skipped_chars += 1;
+        }
+
+        let mut visible_width = 0;
+        let text = self.chars[skipped_chars..]
+            .iter()
+            .take_while(|value| {
+                visible_width += value.width().unwrap_or(0);
+                visible_width <= width
+            })
+            .cloned()
+            .collect();
+
+        (text, cursor_width - skipped_width)
+    }
+}
+
+pub struct Prompt {
+    pub window: WINDOW,
+    pub kind: Option<PromptKind>,
+    buffer: PromptBuffer,
+    /// Shown dimmed after the text, such as the choices to pick from.
+    hint: Option<String>,
+    panel: PANEL,
+}
+
+impl Prompt {
+    pub fn new(position_x: i32, position_y: i32) -> Prompt {
+        let window = newwin(0, 0, position_x, position_y);
+
+        Prompt {
+            window: window,
+            kind: None,
+            buffer: PromptBuffer::default(),
+            hint: None,
+            panel: new_panel(window),
+        }
+    }
+
+    pub fn open(&mut self, kind: PromptKind, text: &str) {
+        self.kind = Some(kind);
+        self.buffer = PromptBuffer::new(text);
+        self.hint = None;
+    }
+
+    pub fn set_hint(&mut self, hint: String) {
+        self.hint = Some(hint);
+        self.render();
+    }
+
+    pub fn text(&self) -> String {
+        self.buffer.text()
+    }
+
+    pub fn close(&mut self) {
+        self.kind = None;
+        self.buffer = PromptBuffer::default();
+    }
+
+    pub fn apply(&mut self, action: PromptAction) -> Option<PromptEvent> {
+        let event = self.buffer.apply(&action);
+        self.render();
+
+        event
+    }
+
+    pub fn render(&self) {
+        let label = match self.kind {
+            Some(kind) => kind.label(),
+            None => return,
+        };
+
+        let width = getmaxx(self.window) - label.len() as i32 - 2;
+        let (text, cursor_column) = self.buffer.visible_text(width.max(1) as usize);
+
+        werase(self.window);
+        wbkgd(self.window, color::pair_attr(PROMPT_COLOR_PAIR_ID, A_REVERSE()));
+        mvwprintw(self.window, 0, 1, &format!("{} {}", label, text));
+        if let Some(ref hint) = self.hint {
+            let available = (getmaxx(self.window) - getcurx(self.window) - 3).max(0) as usize;
+            let hint = hint.chars().take(available).collect::<String>();
+            wattron(self.window, A_DIM());
+            wprintw(self.window, &format!("  {}", hint));
+            wattroff(self.window, A_DIM());
+        }
+        wmove(self.window, 0, label.len() as i32 + 2 + cursor_column as i32);
+        wrefresh(self.window);
+    }
+
+    pub fn resize(&self, container_width: i32, offset: i32) {
+        wresize(self.window, 1, container_width);
+        mvwin(self.window, offset, 0);
+    }
+
+    pub fn show(&self) {
+        self.render();
+        curs_set(CURSOR_VISIBILITY::CURSOR_VERY_VISIBLE);
+        show_panel(self.panel);
+    }
+
+    pub fn hide(&self) {
+        curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE);
+        hide_panel(self.panel);
+    }
+}
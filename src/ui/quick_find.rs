@@ -0,0 +1,136 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cell::{Cell, RefCell};
+use std::ops::Range;
+
+use ncurses::*;
+
+use core::line::SearchPattern;
+use ui::highlighter::{DisplayedText, match_segments_on_rows};
+use ui::rendered_line::RenderedLineCollection;
+use ui::viewport::Viewport;
+use utils::invisibles::display_width;
+
+/// Seconds quick find matches stay marked after the prompt is submitted.
+pub const QUICK_FIND_TIMEOUT: u64 = 5;
+
+/// A find limited to the rows on screen, kept apart from the search so that
+/// neither disturbs the other. As with the column cursor, only the cells of
+/// each match are changed, and put back once the screen scrolls or the find
+/// expires.
+#[derive(Default)]
+pub struct QuickFind {
+    /// Content row at the top of the screen when matches were marked.
+    pub top_row: Cell<Option<usize>>,
+    /// When the marks are cleared, in milliseconds.
+    pub expires_at: Cell<Option<u64>>,
+    /// Pad cells marked, along with what they held before and after.
+    marked: RefCell<Vec<(i32, i32, chtype, chtype)>>,
+}
+
+impl QuickFind {
+    pub fn is_active(&self) -> bool {
+        self.top_row.get().is_some()
+    }
+
+    /// Underlines and emboldens the given columns of a pad row.
+    pub fn mark(&self, window: WINDOW, row: i32, columns: Range<usize>) {
+        let mut marked = self.marked.borrow_mut();
+
+        for column in columns {
+            let column = column as i32;
+            let before = mvwinch(window, row, column);
+            let attributes = before & A_ATTRIBUTES();
+            let pair = PAIR_NUMBER((attributes & A_COLOR()) as i32) as i16;
+
+            mvwchgat(window,
+                     row,
+                     column,
+                     1,
+                     (attributes & !A_COLOR()) | A_UNDERLINE() | A_BOLD(),
+                     pair);
+            marked.push((row, column, before, mvwinch(window, row, column)));
+        }
+    }
+
+    /// Puts back the cells marked before, unless they were printed over.
+    pub fn clear(&self, window: WINDOW) {
+        for (row, column, before, after) in self.marked.borrow_mut().drain(..) {
+            if mvwinch(window, row, column) == after {
+                let attributes = before & A_ATTRIBUTES();
+                let pair = PAIR_NUMBER((attributes & A_COLOR()) as i32) as i16;
+                mvwchgat(window, row, column, 1, attributes & !A_COLOR(), pair);
+            }
+        }
+
+        self.top_row.set(None);
+        self.expires_at.set(None);
+    }
+}
+
+/// Content rows and columns taken by each occurrence of the pattern, on each
+/// visible row it's shown on. Folded runs of blank lines are skipped.
+pub fn visible_matches(rendered_lines: &RenderedLineCollection,
+                       viewport: &Viewport,
+                       pattern: &SearchPattern,
+                       width: usize,
+                       indent: usize,
+                       show_invisibles: bool)
+                       -> Vec<Vec<(usize, Range<usize>)>> {
+    let rows = viewport.visible_rows();
+    let mut found = vec![];
+    let mut start_row = 0;
+
+    for entry in &rendered_lines.entries {
+        let end_row = start_row + entry.height;
+        if end_row > rows.start && start_row < rows.end && !entry.is_folded() {
+            let displayed = DisplayedText::new(&entry.line.content_without_ansi, show_invisibles);
+            let wrapped = entry.wrapped_rows(width, indent, show_invisibles);
+
+            for (offset, value) in entry.line.matches_for(pattern) {
+                let range = displayed.range(offset..offset + value.len());
+                let segments = match_segments_on_rows(&displayed.text,
+                                                      range,
+                                                      &wrapped.rows,
+                                                      width,
+                                                      indent)
+                    .into_iter()
+                    .map(|segment| {
+                        let columns = display_width(&displayed.text[segment.range]);
+                        (start_row + segment.row, segment.column..segment.column + columns)
+                    })
+                    .filter(|&(row, ref columns)| {
+                        rows.start <= row && row < rows.end && columns.start < columns.end
+                    })
+                    .collect::<Vec<_>>();
+
+                if !segments.is_empty() {
+                    found.push(segments);
+                }
+            }
+        }
+
+        if end_row >= rows.end {
+            break;
+        }
+        start_row = end_row;
+    }
+
+    found
+}
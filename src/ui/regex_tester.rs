@@ -0,0 +1,163 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cell::{Cell, RefCell};
+
+use ncurses::*;
+use unicode_width::UnicodeWidthChar;
+
+use core::regex_tester::{self, Preview, PREVIEW_LINES};
+use ui::color;
+use ui::frame::{NORMAL_HIGHLIGHT_COLOR, ERROR_COLOR};
+use ui::rules::fit;
+
+static TITLE_COLOR_PAIR_ID: i16 = 1;
+static EMPTY_TITLE: &'static str = "Type a regex to try it on the latest lines";
+
+/// Overlay over the lower part of the content, previewing where the regex
+/// being typed matches the latest lines. Capture groups are underlined.
+pub struct RegexTester {
+    pub window: WINDOW,
+    panel: PANEL,
+    title: RefCell<(String, bool)>,
+    lines: RefCell<Vec<(String, Preview)>>,
+    container_height: Cell<i32>,
+}
+
+impl RegexTester {
+    pub fn new() -> RegexTester {
+        let window = newwin(1, 1, 0, 0);
+        let panel = new_panel(window);
+        hide_panel(panel);
+
+        RegexTester {
+            window: window,
+            panel: panel,
+            title: RefCell::new((EMPTY_TITLE.to_string(), false)),
+            lines: RefCell::new(vec![]),
+            container_height: Cell::new(LINES() - 1),
+        }
+    }
+
+    /// Tries the regex on the given lines, oldest first. Compile errors are
+    /// shown in the title, with the lines left as they are.
+    pub fn update(&self, source: &str, lines: Vec<String>) {
+        let regex = if source.is_empty() { None } else { Some(regex_tester::compile(source)) };
+        let mut previews = vec![];
+        let mut found = 0;
+
+        for text in lines.into_iter().rev().take(PREVIEW_LINES).rev() {
+            let preview = match regex {
+                Some(Ok(ref value)) => Preview::new(value, &text),
+                _ => Preview::default(),
+            };
+            found += preview.matches.len();
+            previews.push((text, preview));
+        }
+
+        *self.title.borrow_mut() = match regex {
+            None => (EMPTY_TITLE.to_string(), false),
+            Some(Ok(_)) => {
+                (format!("{} matches in the last {} lines  enter to keep", found, previews.len()),
+                 false)
+            }
+            Some(Err(error)) => (error.to_string(), true),
+        };
+        *self.lines.borrow_mut() = previews;
+    }
+
+    pub fn render(&self) {
+        let lines = self.lines.borrow();
+        let height = (lines.len() as i32 + 1).min(self.container_height.get()).max(1);
+        let width = COLS();
+
+        wresize(self.window, height, width);
+        mvwin(self.window, self.container_height.get() - height, 0);
+        werase(self.window);
+
+        let (ref title, is_error) = *self.title.borrow();
+        let title_attr = if is_error {
+            color::pair_attr(ERROR_COLOR, A_REVERSE() | A_BOLD())
+        } else {
+            color::pair_attr(TITLE_COLOR_PAIR_ID, A_BOLD())
+        };
+        wattron(self.window, title_attr);
+        mvwprintw(self.window, 0, 0, &fit(&format!(" {}", title), width));
+        wattroff(self.window, title_attr);
+
+        let skipped = lines.len().saturating_sub((height - 1) as usize);
+        for (i, &(ref text, ref preview)) in lines.iter().skip(skipped).enumerate() {
+            wmove(self.window, i as i32 + 1, 0);
+            self.print_line(text, preview, width as usize);
+        }
+
+        wrefresh(self.window);
+    }
+
+    fn print_line(&self, text: &str, preview: &Preview, width: usize) {
+        let mut used_width = 0;
+
+        for (offset, value) in text.char_indices() {
+            let value = if value.is_control() { ' ' } else { value };
+            let value_width = value.width().unwrap_or(0);
+            if used_width + value_width >= width {
+                break;
+            }
+            used_width += value_width;
+
+            let mut attr = A_NORMAL();
+            if preview.in_match(offset) {
+                attr |= color::pair_attr(NORMAL_HIGHLIGHT_COLOR, A_REVERSE());
+            }
+            if preview.in_group(offset) {
+                attr |= A_UNDERLINE();
+            }
+
+            wattron(self.window, attr);
+            waddstr(self.window, &value.to_string());
+            wattroff(self.window, attr);
+        }
+    }
+
+    /// Draws the overlay again after the content was refreshed underneath.
+    pub fn refresh(&self) {
+        if !panel_hidden(self.panel) {
+            touchwin(self.window);
+            wrefresh(self.window);
+        }
+    }
+
+    pub fn resize(&self, container_height: i32) {
+        self.container_height.set(container_height);
+    }
+
+    pub fn is_visible(&self) -> bool {
+        !panel_hidden(self.panel)
+    }
+
+    pub fn show(&self) {
+        self.render();
+        show_panel(self.panel);
+    }
+
+    pub fn hide(&self) {
+        hide_panel(self.panel);
+        update_panels();
+        doupdate();
+    }
+}
@@ -16,44 +16,138 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::ops::Index;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::{Index, Range};
+use std::rc::Rc;
+use std::sync::Arc;
 
-use ncurses::wmove;
+use ncurses::getmaxx;
 
-use core::line::Line;
+use core::highlights::SharedSpans;
+use core::line::{Line, SearchPattern};
 use ui::content::Content;
 use ui::frame::NORMAL_HIGHLIGHT_COLOR;
-use ui::printer::{Print, Viewport};
-use ui::highlighter::LineHighlighter;
+use ui::printer::{Print, print_marker, print_folded, folded_text, print_highlights};
+use ui::viewport::Viewport;
+use ui::highlighter::{DisplayedText, LineHighlighter, MatchSpan};
+use utils::wrap::wrap_indented;
+use core::timestamp::format_duration;
+
+/// Row of its own printed between lines, which isn't part of either.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Marker {
+    /// Silence until the next line, in milliseconds.
+    Gap(i64),
+    RangeStart,
+    RangeEnd,
+}
+
+impl Marker {
+    pub fn text(&self) -> String {
+        match *self {
+            Marker::Gap(value) => format!("··· {} silence ···", format_duration(value)),
+            Marker::RangeStart => "─── range start ───".to_string(),
+            Marker::RangeEnd => "─── range end ───".to_string(),
+        }
+    }
+}
+
+/// Where a line wraps on screen, as byte ranges of the text it's displayed
+/// with, along with what they were computed for. Only offsets are kept, the
+/// text being shared with the line.
+#[derive(Debug)]
+pub struct WrappedRows {
+    text: Arc<str>,
+    width: usize,
+    indent: usize,
+    show_invisibles: bool,
+    pub rows: Vec<Range<usize>>,
+}
+
+impl WrappedRows {
+    pub fn new(line: &Line, width: usize, indent: usize, show_invisibles: bool) -> WrappedRows {
+        let displayed = DisplayedText::new(&line.content_without_ansi, show_invisibles);
+
+        WrappedRows {
+            text: line.content_without_ansi.clone(),
+            width: width,
+            indent: indent,
+            show_invisibles: show_invisibles,
+            rows: wrap_indented(&displayed.text, width, indent),
+        }
+    }
+
+    /// Whether the rows still apply to the line, as printed with the given
+    /// width, continuation prefix and invisibles.
+    pub fn fits(&self, line: &Line, width: usize, indent: usize, show_invisibles: bool) -> bool {
+        Arc::ptr_eq(&self.text, &line.content_without_ansi) && self.width == width &&
+        self.indent == indent && self.show_invisibles == show_invisibles
+    }
+}
 
 #[derive(Clone)]
 pub struct RenderedLine {
     pub line: Line,
-    pub height: i32,
-    pub found_matches: Option<Vec<usize>>,
+    pub height: usize,
+    /// One span per occurrence of the search pattern, if the line matches.
+    pub found_matches: Option<Vec<MatchSpan>>,
+    /// Printed below the line, one row each, and counted in its height.
+    pub markers: Vec<Marker>,
+    /// Blank lines shown as a single row starting with this one, when more
+    /// than one.
+    pub folded: usize,
+    /// Occurrences of the persistent highlights, found as the line is
+    /// rendered rather than for every line received.
+    pub highlights: SharedSpans,
+    /// Where the line last wrapped, reused while printing and highlighting it.
+    wrapped: RefCell<Option<Rc<WrappedRows>>>,
 }
 
 impl RenderedLine {
-    fn new(line: Line, height: i32, found_matches: Option<Vec<usize>>) -> RenderedLine {
+    fn new(line: Line, height: usize, found_matches: Option<Vec<MatchSpan>>) -> RenderedLine {
         RenderedLine {
             line: line,
             height: height,
             found_matches: found_matches,
+            markers: vec![],
+            folded: 0,
+            highlights: SharedSpans::default(),
+            wrapped: RefCell::new(None),
         }
     }
 
+    /// Rows the line wraps onto, computed again once the width, the
+    /// continuation prefix or whether invisibles are shown changes.
+    pub fn wrapped_rows(&self,
+                        width: usize,
+                        indent: usize,
+                        show_invisibles: bool)
+                        -> Rc<WrappedRows> {
+        let mut wrapped = self.wrapped.borrow_mut();
+        if let Some(ref rows) = *wrapped {
+            if rows.fits(&self.line, width, indent, show_invisibles) {
+                return rows.clone();
+            }
+        }
+
+        let rows = Rc::new(WrappedRows::new(&self.line, width, indent, show_invisibles));
+        *wrapped = Some(rows.clone());
+        rows
+    }
+
     pub fn search(&mut self,
-                  text: &str,
+                  pattern: &SearchPattern,
                   content: &Content,
                   container_width: i32,
-                  accumulated_height: i32)
+                  accumulated_height: usize)
                   -> bool {
-        let is_match = self.line.contains(text);
+        let is_match = self.line.contains(pattern);
         let mut found_matches = None;
 
         if is_match {
             self.print(content, accumulated_height);
-            found_matches = self.highlight(text, content, container_width, accumulated_height);
+            found_matches = self.highlight(pattern, content, container_width, accumulated_height);
         }
 
         if self.update_found_matches(found_matches) && !is_match {
@@ -64,24 +158,59 @@ impl RenderedLine {
     }
 
     pub fn highlight(&self,
-                     text: &str,
+                     pattern: &SearchPattern,
                      content: &Content,
                      container_width: i32,
-                     accumulated_height: i32)
-                     -> Option<Vec<usize>> {
-        let highlighter = LineHighlighter::new(content.window,
+                     accumulated_height: usize)
+                     -> Option<Vec<MatchSpan>> {
+        let highlighter = LineHighlighter::new(content,
                                                &self.line,
                                                container_width,
                                                NORMAL_HIGHLIGHT_COLOR);
-        Some(highlighter.print(text, accumulated_height, self.height))
+        if self.is_folded() {
+            let span = highlighter.print_folded(&folded_text(self.folded), accumulated_height);
+            content.move_to(accumulated_height + self.height);
+
+            return Some(vec![span]);
+        }
+
+        let rows = self.wrapped_rows(container_width as usize,
+                                     content.continuation_width(),
+                                     content.reveals_invisibles());
+        Some(highlighter.with_rows(rows).print(pattern, accumulated_height, self.height))
     }
 
-    pub fn print(&self, content: &Content, accumulated_height: i32) {
-        wmove(content.window, accumulated_height, 0);
-        self.line.print(content);
+    /// Prints the line starting on the given content row, when the pad
+    /// holds it.
+    pub fn print(&self, content: &Content, accumulated_height: usize) {
+        if !content.move_to(accumulated_height) {
+            return;
+        }
+
+        if self.is_folded() {
+            print_folded(content, self.folded);
+        } else {
+            let rows = self.wrapped_rows(getmaxx(content.window) as usize,
+                                         content.continuation_width(),
+                                         content.reveals_invisibles());
+            *content.line_rows.borrow_mut() = Some(rows);
+            let start_row = content.cursor_row();
+            self.line.print(content);
+            if !self.highlights.is_empty() && !content.raw.get() {
+                print_highlights(&self.line, &self.highlights, content, start_row);
+            }
+            *content.line_rows.borrow_mut() = None;
+        }
+        for marker in &self.markers {
+            print_marker(content, marker);
+        }
     }
 
-    pub fn update_found_matches(&mut self, found_matches: Option<Vec<usize>>) -> bool {
+    pub fn is_folded(&self) -> bool {
+        self.folded > 1
+    }
+
+    pub fn update_found_matches(&mut self, found_matches: Option<Vec<MatchSpan>>) -> bool {
         if self.found_matches != found_matches {
             self.found_matches = found_matches;
 
@@ -94,6 +223,15 @@ impl RenderedLine {
     pub fn match_count(&self) -> usize {
         self.found_matches.as_ref().unwrap().len()
     }
+
+    /// Drops the markers, along with the rows they took up.
+    fn without_markers(&self) -> RenderedLine {
+        let mut entry = self.clone();
+        entry.height -= entry.markers.len();
+        entry.markers.clear();
+
+        entry
+    }
 }
 
 #[derive(Clone)]
@@ -106,22 +244,78 @@ impl RenderedLineCollection {
         RenderedLineCollection { entries: vec![] }
     }
 
-    pub fn create(&mut self, line: Line, height: i32, found_matches: Option<Vec<usize>>) {
+    pub fn create(&mut self, line: Line, height: usize, found_matches: Option<Vec<MatchSpan>>) {
         let entry = RenderedLine::new(line, height, found_matches);
         self.entries.push(entry);
     }
 
-    pub fn matching(&mut self, text: &str) -> RenderedLineCollection {
+    /// Adds a line already wrapped, taking up as many rows, along with the
+    /// highlights found on it.
+    pub fn create_wrapped(&mut self,
+                          line: Line,
+                          rows: Rc<WrappedRows>,
+                          highlights: SharedSpans) {
+        let mut entry = RenderedLine::new(line, rows.rows.len(), None);
+        entry.highlights = highlights;
+        *entry.wrapped.borrow_mut() = Some(rows);
+        self.entries.push(entry);
+    }
+
+    /// Where each line wrapped, by id, for the lines rendered next to reuse.
+    pub fn wrapped_rows_by_id(&self) -> HashMap<usize, Rc<WrappedRows>> {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.wrapped.borrow().clone().map(|rows| (entry.line.id, rows)))
+            .collect()
+    }
+
+    /// Adds the first of `count` blank lines, shown as a single row.
+    pub fn create_folded(&mut self, line: Line, count: usize) {
+        let mut entry = RenderedLine::new(line, 1, None);
+        entry.folded = count;
+        self.entries.push(entry);
+    }
+
+    pub fn matching(&mut self, pattern: &SearchPattern) -> RenderedLineCollection {
         RenderedLineCollection {
             entries: self.entries
                 .iter()
-                .filter(|entry| entry.line.contains(text))
-                .map(|entry| entry.clone())
+                .filter(|entry| entry.line.contains(pattern))
+                .map(|entry| entry.without_markers())
                 .collect::<Vec<_>>(),
         }
     }
 
-    pub fn height(&self) -> i32 {
+    /// Adds a marker below the last line, taking up one more row.
+    pub fn add_marker(&mut self, marker: Marker) {
+        if let Some(entry) = self.entries.last_mut() {
+            entry.markers.push(marker);
+            entry.height += 1;
+        }
+    }
+
+    /// Row of the first silence marker below `row`, along with its length.
+    pub fn next_gap(&self, row: usize) -> Option<(usize, i64)> {
+        let mut accumulated_height = 0;
+
+        for entry in &self.entries {
+            accumulated_height += entry.height;
+            let first_marker_row = accumulated_height - entry.markers.len();
+
+            for (i, marker) in entry.markers.iter().enumerate() {
+                match *marker {
+                    Marker::Gap(value) if first_marker_row + i > row => {
+                        return Some((first_marker_row + i, value))
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        None
+    }
+
+    pub fn height(&self) -> usize {
         self.entries.iter().height()
     }
 
@@ -137,52 +331,72 @@ impl RenderedLineCollection {
         self.entries.is_empty()
     }
 
-    pub fn buffer_reverse_index(&self, line_index: usize, match_index: usize) -> i32 {
-        let offset = self.entries[line_index].found_matches.as_ref().unwrap()[match_index];
-        self.entries.iter().skip(line_index).height() - offset as i32
+    pub fn buffer_reverse_index(&self, line_index: usize, match_index: usize) -> i64 {
+        let span = self.entries[line_index].found_matches.as_ref().unwrap()[match_index];
+        self.entries.iter().skip(line_index).height() as i64 - span.row.0 as i64
     }
 
-    pub fn height_up_to_index(&self, index: usize) -> i32 {
-        self.entries.iter().take(index).height()
+    /// Content row the match starts on.
+    pub fn match_row(&self, line_index: usize, match_index: usize) -> usize {
+        let span = self.entries[line_index].found_matches.as_ref().unwrap()[match_index];
+        self.height_up_to_index(line_index) + span.row.0
     }
 
-    pub fn last_lines_height(&self, count: usize) -> i32 {
-        self.entries.iter().rev().take(count).height()
+    pub fn height_up_to_index(&self, index: usize) -> usize {
+        self.entries.iter().take(index).height()
     }
 
-    pub fn is_match_in_viewport(&self, matched_line: MatchedLine, viewport: Viewport) -> bool {
-        let limit = viewport.limit();
-        let accumulated_height = self.entries.iter().skip(matched_line.line).height() as usize;
-        let line = &self.entries[matched_line.line];
+    /// Index of the line printed on the given row.
+    pub fn index_at_row(&self, row: usize) -> Option<usize> {
+        let mut accumulated_height = 0;
 
-        if accumulated_height >= viewport.reverse_index {
-            for height in line.found_matches.as_ref().unwrap().iter() {
-                if accumulated_height + height <= limit {
-                    return true;
-                }
-            }
-        }
+        self.entries.iter().position(|entry| {
+            accumulated_height += entry.height;
+            accumulated_height > row
+        })
+    }
 
-        false
+    pub fn index_of_id(&self, line_id: usize) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.line.id == line_id)
     }
 
-    pub fn viewport_match(&self, viewport: &Viewport) -> Option<MatchedLine> {
-        let mut accumulated_height = 0;
-        let limit = viewport.limit();
+    pub fn line_by_id(&self, line_id: usize) -> Option<&Line> {
+        self.index_of_id(line_id).map(|index| &self.entries[index].line)
+    }
 
-        for (i, line) in self.entries.iter().rev().enumerate() {
-            if accumulated_height >= viewport.reverse_index && line.found_matches.is_some() {
-                for (j, height) in line.found_matches.as_ref().unwrap().iter().enumerate() {
-                    if accumulated_height + height <= limit {
-                        return Some(MatchedLine::new(i, j));
-                    }
-                }
-            }
+    /// Id and height of each line, as used by viewport anchors.
+    pub fn line_heights<'a>(&'a self) -> impl Iterator<Item = (usize, usize)> + 'a {
+        self.entries.iter().map(|entry| (entry.line.id, entry.height))
+    }
 
-            accumulated_height += line.height as usize;
+    pub fn last_lines_height(&self, count: usize) -> usize {
+        self.entries.iter().rev().take(count).height()
+    }
 
-            if accumulated_height >= limit {
-                break;
+    /// Whether the row the match starts on is on screen. The rest of a match
+    /// wrapped onto several rows may not be.
+    pub fn is_match_in_viewport(&self, matched_line: MatchedLine, viewport: &Viewport) -> bool {
+        viewport.is_row_visible(self.match_row(matched_line.line, matched_line.match_index))
+    }
+
+    /// The first match starting on screen on the lowest line having one.
+    pub fn viewport_match(&self, viewport: &Viewport) -> Option<MatchedLine> {
+        let lines = viewport.line_range_visible(self.entries.iter().map(|entry| entry.height));
+        let mut line_row = self.height_up_to_index(lines.end);
+
+        for index in lines.rev() {
+            let entry = &self.entries[index];
+            line_row -= entry.height;
+
+            let spans = match entry.found_matches {
+                Some(ref spans) => spans,
+                None => continue,
+            };
+            let visible = spans.iter()
+                .position(|span| viewport.is_row_visible(line_row + span.row.0));
+
+            if let Some(match_index) = visible {
+                return Some(MatchedLine::new(index, match_index));
             }
         }
 
@@ -192,12 +406,42 @@ impl RenderedLineCollection {
     pub fn last_match(&self) -> MatchedLine {
         self.entries
             .iter()
-            .rev()
             .enumerate()
+            .rev()
             .find_match()
             .unwrap()
     }
 
+    /// Position of the match found on the line having the given id. When the
+    /// line is gone, falls back to the next surviving match, or the last one.
+    pub fn resolve_match(&self, line_id: usize, match_index: usize) -> Option<MatchedLine> {
+        let mut matching = self.entries
+            .iter()
+            .enumerate()
+            .filter(|&(_, entry)| entry.found_matches.is_some());
+
+        let found = matching.find(|&(_, entry)| entry.line.id >= line_id);
+
+        let (index, entry) = match found {
+            Some(value) => value,
+            None => {
+                let last = self.entries.iter().rposition(|entry| entry.found_matches.is_some());
+                match last {
+                    Some(index) => (index, &self.entries[index]),
+                    None => return None,
+                }
+            }
+        };
+
+        let match_index = if entry.line.id == line_id {
+            match_index.min(entry.match_count() - 1)
+        } else {
+            0
+        };
+
+        Some(MatchedLine::new(index, match_index))
+    }
+
     pub fn next_match(&self, current_index: usize) -> Option<MatchedLine> {
         self.entries
             .iter()
@@ -217,17 +461,18 @@ impl RenderedLineCollection {
 }
 
 trait Height {
-    fn height(self) -> i32;
+    fn height(self) -> usize;
 }
 
 impl<'a, I> Height for I
     where I: Iterator<Item = &'a RenderedLine>
 {
-    fn height(self) -> i32 {
+    fn height(self) -> usize {
         self.fold(0, |sum, current| sum + current.height)
     }
 }
 
+#[derive(PartialEq, Debug)]
 pub struct MatchedLine {
     pub line: usize,
     pub match_index: usize,
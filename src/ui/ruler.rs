@@ -0,0 +1,74 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use ncurses::*;
+
+use ui::color;
+
+static RULER_COLOR_PAIR_ID: i16 = 3;
+
+/// A row of column markers pinned above the content.
+pub struct Ruler {
+    pub window: WINDOW,
+    pub visible: bool,
+}
+
+impl Ruler {
+    pub fn new() -> Ruler {
+        Ruler {
+            window: newwin(1, COLS(), 0, 0),
+            visible: false,
+        }
+    }
+
+    pub fn render(&self) {
+        if !self.visible {
+            return;
+        }
+
+        werase(self.window);
+        wbkgd(self.window, color::pair_attr(RULER_COLOR_PAIR_ID, A_DIM()));
+        mvwprintw(self.window, 0, 0, &ruler_text(getmaxx(self.window) as usize));
+        wrefresh(self.window);
+    }
+
+    pub fn resize(&self, container_width: i32) {
+        wresize(self.window, 1, container_width);
+    }
+}
+
+/// Markers for `width` columns, counted from 0: every tenth column is labeled
+/// with its number and every fifth one marked with a `+`.
+pub fn ruler_text(width: usize) -> String {
+    let mut text = String::with_capacity(width);
+
+    while text.len() < width {
+        let column = text.len();
+
+        if column % 10 == 0 {
+            text.push_str(&column.to_string());
+        } else if column % 5 == 0 {
+            text.push('+');
+        } else {
+            text.push('.');
+        }
+    }
+
+    text.truncate(width);
+    text
+}
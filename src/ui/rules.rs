@@ -0,0 +1,174 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cell::{Cell, RefCell};
+
+use ncurses::*;
+use unicode_width::UnicodeWidthChar;
+
+use ui::color;
+use ui::layout::scroll_to_show;
+
+static TITLE_COLOR_PAIR_ID: i16 = 1;
+static HINT: &'static str = "space toggle  e edit  d delete  K/J move  F close";
+static EMPTY_TEXT: &'static str = "No rules yet, press R in the menu to add one";
+
+pub struct RuleRow {
+    pub kind: &'static str,
+    pub pattern: String,
+    pub hits: usize,
+    pub enabled: bool,
+    /// What the rule was added from, when not typed in as one.
+    pub origin: Option<&'static str>,
+}
+
+impl RuleRow {
+    fn text(&self) -> String {
+        let state = if self.enabled { "x" } else { " " };
+        let origin = self.origin.map(|value| format!("  (from {})", value)).unwrap_or_default();
+
+        format!("[{}] {:<8} {:>6} hits  {}{}",
+                state,
+                self.kind,
+                self.hits,
+                self.pattern,
+                origin)
+    }
+}
+
+/// Overlay listing the rules applied to the selected tab.
+pub struct RulesPanel {
+    pub window: WINDOW,
+    panel: PANEL,
+    title: RefCell<String>,
+    rows: RefCell<Vec<RuleRow>>,
+    selected: Cell<usize>,
+    offset: Cell<usize>,
+    container_height: Cell<i32>,
+}
+
+impl RulesPanel {
+    pub fn new() -> RulesPanel {
+        let window = newwin(1, 1, 0, 0);
+
+        RulesPanel {
+            window: window,
+            panel: new_panel(window),
+            title: RefCell::new(String::new()),
+            rows: RefCell::new(vec![]),
+            selected: Cell::new(0),
+            offset: Cell::new(0),
+            container_height: Cell::new(LINES() - 1),
+        }
+    }
+
+    pub fn set_rows(&self, title: String, rows: Vec<RuleRow>) {
+        let last_index = rows.len().saturating_sub(1);
+
+        *self.title.borrow_mut() = title;
+        *self.rows.borrow_mut() = rows;
+        self.selected.set(self.selected.get().min(last_index));
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected.get()
+    }
+
+    pub fn select(&self, index: usize) {
+        let last_index = self.rows.borrow().len().saturating_sub(1);
+        self.selected.set(index.min(last_index));
+    }
+
+    pub fn render(&self) {
+        let rows = self.rows.borrow();
+        let height = (rows.len().max(1) as i32 + 2).min(self.container_height.get()).max(1);
+        let width = COLS();
+
+        wresize(self.window, height, width);
+        werase(self.window);
+
+        wattron(self.window, color::pair_attr(TITLE_COLOR_PAIR_ID, A_BOLD()));
+        mvwprintw(self.window, 0, 0, &fit(&format!(" {}", *self.title.borrow()), width));
+        wattroff(self.window, color::pair_attr(TITLE_COLOR_PAIR_ID, A_BOLD()));
+
+        if rows.is_empty() {
+            mvwprintw(self.window, 1, 1, &fit(EMPTY_TEXT, width - 1));
+        }
+
+        let visible = (height - 2).max(0) as usize;
+        let offset = scroll_to_show(self.offset.get(), self.selected.get(), visible)
+            .min(rows.len().saturating_sub(visible));
+        self.offset.set(offset);
+
+        for (i, row) in rows.iter().enumerate().skip(offset).take(visible) {
+            let is_selected = i == self.selected.get();
+            if is_selected {
+                wattron(self.window, A_REVERSE());
+            }
+            let y = (i - offset) as i32 + 1;
+            mvwprintw(self.window, y, 0, &fit(&format!(" {}", row.text()), width));
+            if is_selected {
+                wattroff(self.window, A_REVERSE());
+            }
+        }
+
+        wattron(self.window, A_DIM());
+        mvwprintw(self.window, height - 1, 1, &fit(HINT, width - 1));
+        wattroff(self.window, A_DIM());
+
+        wrefresh(self.window);
+    }
+
+    /// Draws the panel again after the content was refreshed underneath.
+    pub fn refresh(&self) {
+        touchwin(self.window);
+        wrefresh(self.window);
+    }
+
+    pub fn resize(&self, container_height: i32) {
+        self.container_height.set(container_height);
+    }
+
+    pub fn show(&self) {
+        self.render();
+        show_panel(self.panel);
+    }
+
+    pub fn hide(&self) {
+        hide_panel(self.panel);
+    }
+}
+
+// Pads or truncates the text to fill the given width, leaving the last column
+// empty so that the cursor doesn't move past the window
+pub fn fit(text: &str, width: i32) -> String {
+    let width = (width - 1).max(0) as usize;
+    let mut result = String::new();
+    let mut used_width = 0;
+
+    for value in text.chars() {
+        let value_width = value.width().unwrap_or(0);
+        if used_width + value_width > width {
+            break;
+        }
+        used_width += value_width;
+        result.push(value);
+    }
+
+    result + &" ".repeat(width - used_width)
+}
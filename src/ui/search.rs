@@ -16,29 +16,53 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 use regex::Regex;
 use ncurses::*;
+use unicode_width::UnicodeWidthStr;
 
-use ui::readline;
 use ui::highlighter::Highlight;
-
-static OPTIONS_WIDTH: i32 = 31;
+use ui::color;
+use ui::prompt::{PromptAction, PromptBuffer, PromptKind};
+use core::line::SearchPattern;
+use core::query::Syntax;
+use core::search_history::{HistoryEntry, SearchFlags, SearchHistory};
+
+static OPTIONS_WIDTH: i32 = 69;
+static MIN_INPUT_WIDTH: i32 = 20;
 static WITH_MATCHES_COLOR_PAIR_ID: i16 = 1;
 static NO_MATCHES_COLOR_PAIR_ID: i16 = 4;
 
+#[derive(Clone)]
 pub struct Query {
     pub text: String,
+    pub pattern: SearchPattern,
     pub filter: bool,
     pub highlight: Highlight,
 }
 
+/// Ways of bringing back an earlier search.
+#[derive(Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Recall {
+    Previous,
+    Next,
+    /// The latest search containing the text typed so far.
+    Matching,
+}
+
 pub struct Search {
     pub window: WINDOW,
     pub options: Options,
     pub input_field: InputField,
     pub matches_found: bool,
+    pub active: bool,
+    pub history: SearchHistory,
+    /// Reused by every search until the text or the options change, so that
+    /// the query is compiled once rather than on each frame.
+    compiled: RefCell<Option<(String, SearchPattern)>>,
+    /// How far a search done a chunk at a time got, as a percentage.
+    progress: Cell<Option<usize>>,
     panel: PANEL,
 }
 
@@ -52,17 +76,84 @@ impl Search {
             input_field: InputField::new(window),
             panel: new_panel(window),
             matches_found: false,
+            active: false,
+            history: SearchHistory::default(),
+            compiled: RefCell::new(None),
+            progress: Cell::new(None),
         }
     }
 
     pub fn render(&self) {
-        let color_pair = COLOR_PAIR(self.color_pair_id());
+        let fallback = if self.color_pair_id() == NO_MATCHES_COLOR_PAIR_ID {
+            A_REVERSE() | A_BOLD()
+        } else {
+            A_REVERSE()
+        };
+        let color_pair = color::pair_attr(self.color_pair_id(), fallback);
 
         wbkgd(self.window, color_pair);
-        self.input_field.render(color_pair);
-        self.options.render(color_pair);
+        let text = self.input_field.text();
+        // Options a recalled search was made with
+        let badges = self.history.shown(&text).map_or(vec![], |entry| entry.flags.badges());
+        self.input_field.render(color_pair, &badges);
+        match (self.error(), self.progress.get()) {
+            (Some(error), _) => self.options.render_text(&error, color_pair),
+            (None, Some(percent)) => {
+                let text = format!("searching… {}% (esc: stop)", percent);
+                self.options.render_text(&text, color_pair);
+            }
+            (None, None) => self.options.render(color_pair),
+        }
         wrefresh(self.window);
-        readline::move_cursor();
+        self.move_cursor();
+    }
+
+    pub fn move_cursor(&self) {
+        self.input_field.move_cursor();
+    }
+
+    /// Edits the text, returning whether it changed.
+    pub fn edit(&mut self, action: &PromptAction) -> bool {
+        self.history.stop_searching();
+        let changed = self.input_field.apply(action);
+        self.render();
+
+        changed
+    }
+
+    /// Replaces the text, as when searching for something from elsewhere.
+    pub fn set_text(&mut self, text: &str) -> bool {
+        self.history.stop_searching();
+        let changed = self.input_field.set_text(text);
+        self.render();
+
+        changed
+    }
+
+    /// Brings an earlier search back along with its options, returning
+    /// whether the search changed.
+    pub fn recall(&mut self, recall: Recall) -> bool {
+        let current = HistoryEntry::new(&self.input_field.text(), self.flags());
+        let entry = match recall {
+            Recall::Previous => self.history.previous(&current),
+            Recall::Next => self.history.next(),
+            Recall::Matching => self.history.search_back(&current),
+        };
+
+        match entry {
+            Some(entry) => {
+                self.input_field.set_text(&entry.text);
+                self.set_flags(entry.flags);
+                entry != current
+            }
+            None => false,
+        }
+    }
+
+    /// Keeps the search in the history, with the options it's made with.
+    pub fn remember(&mut self) {
+        let flags = self.flags();
+        self.history.add(&self.input_field.text(), flags);
     }
 
     pub fn resize(&self, container_width: i32, offset: i32) {
@@ -76,19 +167,105 @@ impl Search {
         if self.input_field.is_empty() {
             None
         } else {
+            let text = self.input_field.text();
+
             Some(Query {
-                text: self.input_field.text.borrow().clone(),
+                pattern: self.pattern(&text),
+                text: text,
                 filter: self.options.filter,
                 highlight: highlight,
             })
         }
     }
 
+    fn pattern(&self, text: &str) -> SearchPattern {
+        let mut compiled = self.compiled.borrow_mut();
+
+        if let Some((ref previous, ref pattern)) = *compiled {
+            if previous == text && pattern.whole_word == self.options.whole_word &&
+               pattern.field_fallback == self.options.field_fallback &&
+               pattern.syntax == self.options.syntax() {
+                return pattern.clone();
+            }
+        }
+
+        let pattern = SearchPattern::new(text, self.options.whole_word)
+            .with_field_fallback(self.options.field_fallback)
+            .with_syntax(self.options.syntax());
+        *compiled = Some((text.to_string(), pattern.clone()));
+        pattern
+    }
+
+    /// Why the text isn't a valid expression, shown in place of the options.
+    fn error(&self) -> Option<String> {
+        if self.input_field.is_empty() {
+            return None;
+        }
+
+        let compiled = self.compiled.borrow();
+        match *compiled {
+            Some((ref text, ref pattern)) if *text == self.input_field.text() => {
+                pattern.error().map(|error| error.to_string())
+            }
+            _ => None,
+        }
+    }
+
+    /// Shown in place of the options while a long search goes on.
+    pub fn set_progress(&self, percent: Option<usize>) {
+        self.progress.set(percent);
+    }
+
+    /// The query keeps being applied after leaving the search bar, until cleared.
+    pub fn is_active(&self) -> bool {
+        self.active && !self.input_field.is_empty()
+    }
+
     pub fn toggle_filter(&mut self) {
         self.options.filter = !self.options.filter;
         self.render();
     }
 
+    pub fn toggle_whole_word(&mut self) {
+        self.options.whole_word = !self.options.whole_word;
+        self.render();
+    }
+
+    pub fn toggle_field_fallback(&mut self) {
+        self.options.field_fallback = !self.options.field_fallback;
+        self.render();
+    }
+
+    pub fn toggle_regex(&mut self) {
+        self.options.regex = !self.options.regex;
+        self.render();
+    }
+
+    pub fn toggle_expressions(&mut self) {
+        self.options.expressions = !self.options.expressions;
+        self.render();
+    }
+
+    /// Options kept along with the search in the history.
+    pub fn flags(&self) -> SearchFlags {
+        SearchFlags {
+            filter: self.options.filter,
+            whole_word: self.options.whole_word,
+            field_fallback: self.options.field_fallback,
+            regex: self.options.regex,
+            expressions: self.options.expressions,
+        }
+    }
+
+    pub fn set_flags(&mut self, flags: SearchFlags) {
+        self.options.filter = flags.filter;
+        self.options.whole_word = flags.whole_word;
+        self.options.field_fallback = flags.field_fallback;
+        self.options.regex = flags.regex;
+        self.options.expressions = flags.expressions;
+        self.render();
+    }
+
     pub fn show(&self) {
         self.render();
         curs_set(CURSOR_VISIBILITY::CURSOR_VERY_VISIBLE);
@@ -101,65 +278,98 @@ impl Search {
     }
 
     fn color_pair_id(&self) -> i16 {
-        if !self.matches_found && self.input_field.text.borrow().len() > 0 {
+        if !self.matches_found && !self.input_field.is_empty() ||
+           self.error().is_some() {
             NO_MATCHES_COLOR_PAIR_ID
         } else {
             WITH_MATCHES_COLOR_PAIR_ID
         }
     }
 }
-#[derive(PartialEq)]
-pub enum State {
-    Changed,
-    Unchanged,
+/// Columns of the input field and of the options, the options giving way to
+/// the input on narrow terminals.
+pub fn field_widths(container_width: i32) -> (i32, i32) {
+    let options = OPTIONS_WIDTH.min(container_width - MIN_INPUT_WIDTH).max(1);
+
+    ((container_width - options).max(1), options)
 }
 
 pub struct InputField {
     pub window: WINDOW,
-    text: RefCell<String>,
+    buffer: PromptBuffer,
 }
 
 impl InputField {
     fn new(parent_window: WINDOW) -> InputField {
-        let window = derwin(parent_window, 1, COLS() - OPTIONS_WIDTH, 0, 1);
+        let window = derwin(parent_window, 1, field_widths(COLS()).0, 0, 1);
         syncok(window, true);
 
         InputField {
             window: window,
-            text: RefCell::new(String::new()),
+            buffer: PromptBuffer::default(),
         }
     }
 
-    fn render(&self, color_pair: attr_t) {
-        wclear(self.window);
+    fn render(&self, color_pair: attr_t, badges: &[&str]) {
+        let label = PromptKind::Search.label();
+        let (text, _) = self.buffer.visible_text(self.text_width());
+
+        werase(self.window);
         wbkgd(self.window, color_pair);
         wattron(self.window, color_pair);
+        wprintw(self.window, &format!("{} {}", label, text));
+
+        if !badges.is_empty() && !text.is_empty() {
+            wattron(self.window, A_DIM());
+            wprintw(self.window, &format!("  [{}]", badges.join("] [")));
+            wattroff(self.window, A_DIM());
+        }
+    }
+
+    fn move_cursor(&self) {
+        let (_, cursor_column) = self.buffer.visible_text(self.text_width());
+        let label_width = PromptKind::Search.label().width() + 1;
+
+        curs_set(CURSOR_VISIBILITY::CURSOR_VERY_VISIBLE);
+        wmove(self.window, 0, (label_width + cursor_column) as i32);
+        wrefresh(self.window);
+    }
+
+    /// Columns left for the text after the label, keeping one for the cursor.
+    fn text_width(&self) -> usize {
+        let label_width = PromptKind::Search.label().width() as i32 + 1;
+
+        (getmaxx(self.window) - label_width - 1).max(1) as usize
     }
 
     fn resize(&self, container_width: i32, offset: i32) {
-        wresize(self.window, 1, container_width - OPTIONS_WIDTH);
+        wresize(self.window, 1, field_widths(container_width).0);
         mvwin(self.window, offset, 1);
         wrefresh(self.window);
     }
 
-    pub fn read(&self, keys: Vec<i32>) -> State {
-        for key in keys {
-            readline::forward_input(key);
+    /// Edits the text, returning whether it changed. Submitting and
+    /// cancelling are up to the search bar.
+    fn apply(&mut self, action: &PromptAction) -> bool {
+        match *action {
+            PromptAction::Submit | PromptAction::Cancel => false,
+            _ => self.buffer.apply(action).is_some(),
         }
+    }
 
-        let pending_text = readline::read_buffer().to_string();
-        let mut current_text = self.text.borrow_mut();
+    fn set_text(&mut self, text: &str) -> bool {
+        let changed = self.buffer.text() != text;
+        self.buffer = PromptBuffer::new(text);
 
-        if *current_text == pending_text {
-            State::Unchanged
-        } else {
-            *current_text = pending_text;
-            State::Changed
-        }
+        changed
+    }
+
+    pub fn text(&self) -> String {
+        self.buffer.text()
     }
 
     fn is_empty(&self) -> bool {
-        self.text.borrow().is_empty()
+        self.buffer.is_empty()
     }
 }
 
@@ -168,32 +378,65 @@ pub struct Options {
     pub next: bool,
     pub previous: bool,
     filter: bool,
+    whole_word: bool,
+    /// Field searches look through lines without fields in full.
+    field_fallback: bool,
+    /// Text between slashes is a regex.
+    regex: bool,
+    /// `AND`, `OR` and `NOT` combine terms.
+    expressions: bool,
 }
 
 impl Options {
     fn new(parent_window: WINDOW) -> Options {
+        let options_width = field_widths(COLS()).1;
+
         Options {
-            window: derwin(parent_window, 1, OPTIONS_WIDTH, 0, COLS() - OPTIONS_WIDTH),
+            window: derwin(parent_window, 1, options_width, 0, COLS() - options_width),
             next: false,
             previous: false,
             filter: false,
+            whole_word: false,
+            field_fallback: false,
+            regex: false,
+            expressions: false,
+        }
+    }
+
+    fn syntax(&self) -> Syntax {
+        Syntax {
+            regex: self.regex,
+            expressions: self.expressions,
         }
     }
 
     fn render(&self, color_pair: attr_t) {
         wclear(self.window);
-        readline::handle_redisplay();
         wbkgd(self.window, color_pair);
         wprintw(self.window, "  ");
 
         self.print_label("[N]ext", self.next, color_pair);
         self.print_label("[P]rev", self.previous, color_pair);
         self.print_label("Filter [M]ode", self.filter, color_pair);
+        self.print_label("[W]ord", self.whole_word, color_pair);
+        self.print_label("[U]nparsed", self.field_fallback, color_pair);
+        self.print_label("[R]egex", self.regex, color_pair);
+        self.print_label("[E]xpr", self.expressions, color_pair);
+    }
+
+    fn render_text(&self, text: &str, color_pair: attr_t) {
+        wclear(self.window);
+        wbkgd(self.window, color_pair);
+
+        let width = (getmaxx(self.window) - 3).max(0) as usize;
+        wprintw(self.window, &format!("  {}", text.chars().take(width).collect::<String>()));
     }
 
     fn resize(&self, container_width: i32) {
-        wresize(self.window, 1, OPTIONS_WIDTH);
-        mvderwin(self.window, 0, container_width - OPTIONS_WIDTH);
+        let width = field_widths(container_width).1;
+
+        wresize(self.window, 1, width);
+        mvderwin(self.window, 0, container_width - width);
         wrefresh(self.window);
     }
 
@@ -202,10 +445,16 @@ impl Options {
             static ref SHORTCUT_MATCHER: Regex = Regex::new(r"(.*)?(\[(\w)\])(.*)?").unwrap();
         }
 
+        // Labels past the edge of narrow terminals are left out
+        let width = " / ".len() + text.len() - 2;
+        if getcurx(self.window) + width as i32 > getmaxx(self.window) {
+            return;
+        }
+
         wprintw(self.window, " / ");
 
         if active {
-            wattron(self.window, COLOR_PAIR(2));
+            wattron(self.window, color::pair_attr(2, A_BOLD()));
         }
 
         for (i, capture) in SHORTCUT_MATCHER.captures(text).unwrap().iter().skip(1).enumerate() {
@@ -0,0 +1,140 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cell::{Cell, RefCell};
+
+use ncurses::*;
+
+use ui::color;
+use ui::rules::fit;
+
+static TITLE_COLOR_PAIR_ID: i16 = 1;
+pub static WIDTH: i32 = 30;
+
+/// Column on the right of the content describing the current line. Values
+/// too long for it continue on the following rows.
+pub struct Sidebar {
+    pub window: WINDOW,
+    pub visible: bool,
+    rows: RefCell<Vec<(&'static str, String)>>,
+    /// Line the rows describe, so that they're only built when it changes.
+    pub line_id: Cell<Option<usize>>,
+    /// Rows scrolled past with the wheel, for values that don't fit.
+    scroll: Cell<usize>,
+}
+
+impl Sidebar {
+    pub fn new() -> Sidebar {
+        Sidebar {
+            window: newwin(1, 1, 0, 0),
+            visible: false,
+            rows: RefCell::new(vec![]),
+            line_id: Cell::new(None),
+            scroll: Cell::new(0),
+        }
+    }
+
+    pub fn set_rows(&self, line_id: Option<usize>, rows: Vec<(&'static str, String)>) {
+        if line_id != self.line_id.get() {
+            self.scroll.set(0);
+        }
+        self.line_id.set(line_id);
+        *self.rows.borrow_mut() = rows;
+    }
+
+    pub fn resize(&self, offset_y: i32, offset_x: i32, height: i32, width: i32) {
+        wresize(self.window, height.max(1), width.max(1));
+        mvwin(self.window, offset_y, offset_x);
+    }
+
+    /// Scrolls the rows up for a positive direction, never past the last one.
+    pub fn scroll(&self, direction: i32) {
+        let (mut height, mut width) = (0, 0);
+        getmaxyx(self.window, &mut height, &mut width);
+
+        let total = self.lines(width).len();
+        let last = total.saturating_sub((height - 1).max(0) as usize);
+        let scroll = if direction > 0 {
+            self.scroll.get().saturating_sub(direction as usize)
+        } else {
+            self.scroll.get() + (-direction) as usize
+        };
+
+        self.scroll.set(scroll.min(last));
+        self.render();
+    }
+
+    pub fn render(&self) {
+        if !self.visible {
+            return;
+        }
+
+        let (mut height, mut width) = (0, 0);
+        getmaxyx(self.window, &mut height, &mut width);
+        werase(self.window);
+        mvwvline(self.window, 0, 0, ACS_VLINE(), height);
+
+        wattron(self.window, color::pair_attr(TITLE_COLOR_PAIR_ID, A_BOLD()));
+        mvwprintw(self.window, 0, 1, &fit(" Line", width));
+        wattroff(self.window, color::pair_attr(TITLE_COLOR_PAIR_ID, A_BOLD()));
+
+        if self.rows.borrow().is_empty() {
+            mvwprintw(self.window, 1, 2, &fit("No line", width - 2));
+        }
+
+        let visible = (height - 1).max(0) as usize;
+        for (y, &(is_label, ref text)) in self.lines(width)
+            .iter()
+            .skip(self.scroll.get())
+            .take(visible)
+            .enumerate() {
+            let y = y as i32 + 1;
+            if is_label {
+                wattron(self.window, A_DIM());
+                mvwprintw(self.window, y, 2, &fit(text, width - 2));
+                wattroff(self.window, A_DIM());
+            } else {
+                mvwprintw(self.window, y, 3, &fit(text, width - 3));
+            }
+        }
+
+        wrefresh(self.window);
+    }
+
+    // Rows as printed, labels followed by the chunks of their values.
+    fn lines(&self, width: i32) -> Vec<(bool, String)> {
+        let mut lines = vec![];
+
+        for &(label, ref value) in self.rows.borrow().iter() {
+            lines.push((true, label.to_string()));
+            for chunk in chunks(value, (width - 4).max(1) as usize) {
+                lines.push((false, chunk));
+            }
+        }
+
+        lines
+    }
+}
+
+fn chunks(text: &str, size: usize) -> Vec<String> {
+    let chars = text.chars()
+        .map(|value| if value.is_control() { ' ' } else { value })
+        .collect::<Vec<_>>();
+
+    chars.chunks(size).map(|chunk| chunk.iter().collect()).collect()
+}
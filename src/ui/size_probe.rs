@@ -0,0 +1,79 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::mem;
+
+use libc::{self, winsize};
+
+// Milliseconds between two looks at the size of the terminal
+pub static PROBE_INTERVAL: u64 = 500;
+
+/// Notices the terminal changing size without a resize being signaled, as
+/// happens within some multiplexers and over serial lines.
+#[derive(Default)]
+pub struct SizeProbe {
+    last_probe_at: Option<u64>,
+    /// Size differing from the one in use, as seen by the last probe.
+    pending: Option<(i32, i32)>,
+}
+
+impl SizeProbe {
+    /// Size to resize to, once `measure` reports the same size differing
+    /// from `current` on two probes in a row. `now` is in milliseconds.
+    pub fn check<F>(&mut self, now: u64, current: (i32, i32), measure: F) -> Option<(i32, i32)>
+        where F: FnOnce() -> Option<(i32, i32)>
+    {
+        if let Some(last) = self.last_probe_at {
+            if now.saturating_sub(last) < PROBE_INTERVAL {
+                return None;
+            }
+        }
+        self.last_probe_at = Some(now);
+
+        let measured = match measure() {
+            Some(size) if size != current => size,
+            _ => {
+                self.pending = None;
+                return None;
+            }
+        };
+
+        if self.pending == Some(measured) {
+            self.pending = None;
+            Some(measured)
+        } else {
+            self.pending = Some(measured);
+            None
+        }
+    }
+}
+
+/// Rows and columns of the terminal as reported by the kernel, trusted over
+/// the size ncurses last read.
+pub fn measure() -> Option<(i32, i32)> {
+    for &fd in &[libc::STDOUT_FILENO, libc::STDIN_FILENO] {
+        let mut size: winsize = unsafe { mem::zeroed() };
+        let result = unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) };
+
+        if result == 0 && size.ws_row > 0 && size.ws_col > 0 {
+            return Some((size.ws_row as i32, size.ws_col as i32));
+        }
+    }
+
+    None
+}
@@ -0,0 +1,99 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::fs::File;
+use std::io::{self, Write};
+
+use time;
+
+use core::line::SearchPattern;
+use ui::rendered_line::RenderedLineCollection;
+use ui::viewport::Viewport;
+use utils::wrap::wrap_indented;
+use utils::invisibles::escape_nul;
+
+static MATCH_MARKER: &'static str = ">>> ";
+static NO_MATCH_MARKER: &'static str = "    ";
+
+/// Text of the rows within the viewport, wrapped the same way as on screen
+/// but without the continuation prefix taking up `indent` columns. When a
+/// query is given, rows holding part of a match are marked.
+pub fn capture(rendered_lines: &RenderedLineCollection,
+               viewport: &Viewport,
+               width: usize,
+               indent: usize,
+               query: Option<&SearchPattern>)
+               -> Vec<String> {
+    let visible_rows = viewport.visible_rows();
+    let mut result = vec![];
+    let mut row = 0;
+
+    for rendered_line in &rendered_lines.entries {
+        let height = rendered_line.height;
+
+        if row + height > visible_rows.start && row < visible_rows.end {
+            let text = &rendered_line.line.content_without_ansi;
+            let ranges = wrap_indented(text, width, indent);
+            let matches = match query {
+                Some(pattern) if !pattern.text.is_empty() => {
+                    pattern.find(text)
+                        .into_iter()
+                        .map(|(offset, found)| offset..offset + found.len())
+                        .collect()
+                }
+                _ => vec![],
+            };
+
+            for i in 0..height {
+                if !(row + i >= visible_rows.start && row + i < visible_rows.end) {
+                    continue;
+                }
+
+                let row_text = ranges.get(i).map_or("", |range| &text[range.clone()]);
+                let line = match query {
+                    Some(_) => {
+                        let is_match = ranges.get(i).map_or(false, |range| {
+                            matches.iter().any(|found| found.start < range.end && found.end > range.start)
+                        });
+                        let marker = if is_match { MATCH_MARKER } else { NO_MATCH_MARKER };
+                        format!("{}{}", marker, escape_nul(row_text))
+                    }
+                    None => escape_nul(row_text).into_owned(),
+                };
+                result.push(line.trim_end().to_string());
+            }
+        }
+
+        row += height;
+    }
+
+    result
+}
+
+/// Writes the rows to a timestamped file within the current directory.
+pub fn save(rows: &[String]) -> io::Result<String> {
+    let timestamp = time::strftime("%Y%m%d-%H%M%S", &time::now()).unwrap();
+    let path = format!("flow-snapshot-{}.txt", timestamp);
+
+    let mut file = File::create(&path)?;
+    for row in rows {
+        writeln!(file, "{}", row)?;
+    }
+
+    Ok(path)
+}
@@ -0,0 +1,45 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+static LEVELS: [char; 5] = ['▁', '▂', '▃', '▅', '▇'];
+
+/// Draws the values as `cells` block characters, each one averaging an
+/// equal share of them, scaled to the largest. Cells without any value are
+/// left blank.
+pub fn render(values: &[usize], cells: usize) -> String {
+    if values.is_empty() || cells == 0 {
+        return String::new();
+    }
+
+    let per_cell = (values.len() + cells - 1) / cells;
+    let averages = values.chunks(per_cell)
+        .map(|chunk| chunk.iter().sum::<usize>() as f64 / chunk.len() as f64)
+        .collect::<Vec<_>>();
+    let max = averages.iter().cloned().fold(0.0, f64::max);
+
+    averages.iter()
+        .map(|value| {
+            if *value == 0.0 {
+                ' '
+            } else {
+                let level = (value / max * (LEVELS.len() - 1) as f64).round() as usize;
+                LEVELS[level]
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,269 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cell::{Cell, RefCell};
+use std::ops::Range;
+
+use ncurses::*;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use ui::color;
+
+static STATUS_COLOR_PAIR_ID: i16 = 3;
+/// Columns a pattern takes up at most in its swatch.
+pub static MAX_SWATCH_WIDTH: usize = 16;
+
+/// Pattern shown in the color it's highlighted with.
+pub struct Swatch {
+    pub text: String,
+    pub attr: attr_t,
+    /// Index of the persistent highlight, none for the search.
+    pub highlight: Option<usize>,
+    pub enabled: bool,
+}
+
+impl Swatch {
+    fn label(&self) -> String {
+        format!(" {} ", truncate(&self.text, MAX_SWATCH_WIDTH))
+    }
+}
+
+/// Shortens the text to at most `width` columns, ending with an ellipsis
+/// when cut.
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+
+    let mut result = String::new();
+    let mut used_width = 0;
+    for value in text.chars() {
+        let value_width = value.width().unwrap_or(0);
+        if used_width + value_width + 1 > width {
+            break;
+        }
+        used_width += value_width;
+        result.push(value);
+    }
+
+    result + "…"
+}
+
+pub struct Status {
+    pub window: WINDOW,
+    panel: PANEL,
+    offset: Cell<i32>,
+    container_width: Cell<i32>,
+    indicators: RefCell<Vec<&'static str>>,
+    message: RefCell<Option<String>>,
+    progress: RefCell<Option<String>>,
+    banner: RefCell<Option<String>>,
+    hint: Cell<Option<&'static str>>,
+    sparkline: RefCell<Option<String>>,
+    profile: RefCell<Option<String>>,
+    source: RefCell<Option<String>>,
+    swatches: RefCell<Vec<Swatch>>,
+    /// Screen columns each swatch was last drawn at.
+    swatch_columns: RefCell<Vec<Range<i32>>>,
+}
+
+impl Status {
+    pub fn new(position_x: i32) -> Status {
+        let window = newwin(1, 1, position_x, 0);
+
+        Status {
+            window: window,
+            panel: new_panel(window),
+            offset: Cell::new(position_x),
+            container_width: Cell::new(COLS()),
+            indicators: RefCell::new(vec![]),
+            message: RefCell::new(None),
+            progress: RefCell::new(None),
+            banner: RefCell::new(None),
+            hint: Cell::new(None),
+            sparkline: RefCell::new(None),
+            profile: RefCell::new(None),
+            source: RefCell::new(None),
+            swatches: RefCell::new(vec![]),
+            swatch_columns: RefCell::new(vec![]),
+        }
+    }
+
+    pub fn toggle_indicator(&self, name: &'static str, active: bool) {
+        let mut indicators = self.indicators.borrow_mut();
+        indicators.retain(|value| *value != name);
+
+        if active {
+            indicators.push(name);
+        }
+    }
+
+    pub fn set_message(&self, message: Option<String>) {
+        *self.message.borrow_mut() = message;
+    }
+
+    /// Ongoing state, such as the replay position, shown ahead of messages.
+    pub fn set_progress(&self, progress: Option<String>) {
+        *self.progress.borrow_mut() = progress;
+    }
+
+    /// Lasting notice, such as lines being sampled, shown after the progress.
+    pub fn set_banner(&self, banner: Option<String>) {
+        *self.banner.borrow_mut() = banner;
+    }
+
+    /// What Escape does next, shown after everything else.
+    pub fn set_hint(&self, hint: Option<&'static str>) {
+        self.hint.set(hint);
+    }
+
+    /// Profile switched to, shown along with the indicators.
+    pub fn set_profile(&self, profile: Option<String>) {
+        *self.profile.borrow_mut() = profile;
+    }
+
+    /// File the line at the top of the screen came from, when following
+    /// several files. Returns whether it changed.
+    pub fn set_source(&self, source: Option<String>) -> bool {
+        let changed = *self.source.borrow() != source;
+        *self.source.borrow_mut() = source;
+
+        changed
+    }
+
+    /// The search and the persistent highlights, shown before everything
+    /// else in their own colors.
+    pub fn set_swatches(&self, swatches: Vec<Swatch>) {
+        *self.swatches.borrow_mut() = swatches;
+    }
+
+    /// Persistent highlight whose swatch was drawn at the given screen
+    /// position.
+    pub fn highlight_at(&self, row: i32, column: i32) -> Option<usize> {
+        if row != self.offset.get() {
+            return None;
+        }
+
+        let columns = self.swatch_columns.borrow();
+        let index = columns.iter().position(|range| range.start <= column && column < range.end);
+        index.and_then(|index| self.swatches.borrow()[index].highlight)
+    }
+
+    /// Recent ingestion rates, shown first.
+    pub fn set_sparkline(&self, sparkline: Option<String>) {
+        *self.sparkline.borrow_mut() = sparkline;
+    }
+
+    pub fn text(&self) -> String {
+        let mut parts = vec![];
+
+        if let Some(ref sparkline) = *self.sparkline.borrow() {
+            parts.push(sparkline.clone());
+        }
+
+        if let Some(ref progress) = *self.progress.borrow() {
+            parts.push(progress.clone());
+        }
+
+        if let Some(ref banner) = *self.banner.borrow() {
+            parts.push(format!("[{}]", banner));
+        }
+
+        if let Some(ref message) = *self.message.borrow() {
+            parts.push(message.clone());
+        }
+
+        if let Some(ref source) = *self.source.borrow() {
+            parts.push(format!("[file: {}]", source));
+        }
+
+        if let Some(ref profile) = *self.profile.borrow() {
+            parts.push(format!("[profile: {}]", profile));
+        }
+
+        for indicator in self.indicators.borrow().iter() {
+            parts.push(format!("[{}]", indicator));
+        }
+
+        if let Some(hint) = self.hint.get() {
+            parts.push(format!("({})", hint));
+        }
+
+        parts.join(" ")
+    }
+
+    pub fn render(&self) {
+        let text = self.text();
+        let swatches = self.swatches.borrow();
+        if self.is_empty() {
+            return;
+        }
+
+        let labels = swatches.iter().map(|swatch| swatch.label()).collect::<Vec<_>>();
+        let swatches_width = labels.iter().map(|label| label.width() as i32 + 1).sum::<i32>();
+        let container_width = self.container_width.get();
+        let width = (swatches_width + text.width() as i32 + 2).min(container_width);
+        let start = container_width - width;
+
+        wresize(self.window, 1, width);
+        mvwin(self.window, self.offset.get(), start);
+        werase(self.window);
+        let background = color::pair_attr(STATUS_COLOR_PAIR_ID, A_REVERSE());
+        wbkgd(self.window, background);
+
+        let mut columns = self.swatch_columns.borrow_mut();
+        columns.clear();
+        wmove(self.window, 0, 1);
+        for (swatch, label) in swatches.iter().zip(labels) {
+            let column = getcurx(self.window);
+            let attr = if swatch.enabled { swatch.attr } else { A_DIM() };
+            // Swatches keep their own colors, instead of the reversed background's
+            wbkgdset(self.window, 0);
+            wattron(self.window, attr);
+            wprintw(self.window, &label);
+            wattroff(self.window, attr);
+            wbkgdset(self.window, background);
+            wprintw(self.window, " ");
+            columns.push(start + column..start + column + label.width() as i32);
+        }
+        wprintw(self.window, &text);
+        wrefresh(self.window);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.text().is_empty() && self.swatches.borrow().is_empty()
+    }
+
+    pub fn resize(&self, container_width: i32, offset: i32) {
+        self.container_width.set(container_width);
+        self.offset.set(offset);
+    }
+
+    pub fn show(&self) {
+        if self.is_empty() {
+            self.hide();
+        } else {
+            self.render();
+            show_panel(self.panel);
+        }
+    }
+
+    pub fn hide(&self) {
+        hide_panel(self.panel);
+    }
+}
@@ -0,0 +1,129 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use core::line::Line;
+use core::highlights::{HighlightSpan, Layers};
+use ui::color::{self, ColorPair};
+use utils::ansi_decoder::{Component, Style};
+
+/// Colors and attributes a run of text is shown with. Colors are left out
+/// when they're the terminal's own.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct TextStyle {
+    pub foreground: Option<i16>,
+    pub background: Option<i16>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub reverse: bool,
+    pub strikethrough: bool,
+}
+
+impl TextStyle {
+    /// Applies a decoded escape code, the way it's applied on screen.
+    pub fn apply(&mut self, style: &Style) {
+        match *style {
+            Style::Attribute(id, _, active) => {
+                match id {
+                    1 => self.bold = active,
+                    2 => self.italic = active,
+                    3 => self.underline = active,
+                    4 => self.reverse = active,
+                    5 => self.strikethrough = active,
+                    _ => {}
+                }
+            }
+            Style::Color(foreground, background) => {
+                if let Some(value) = foreground {
+                    self.foreground = own_color(value);
+                }
+                if let Some(value) = background {
+                    self.background = own_color(value);
+                }
+            }
+            Style::Reset => *self = TextStyle::default(),
+        }
+    }
+
+    /// Replaces both colors, as highlights do.
+    pub fn paint(&mut self, pair: ColorPair) {
+        self.foreground = own_color(pair.foreground);
+        self.background = own_color(pair.background);
+    }
+}
+
+fn own_color(value: i16) -> Option<i16> {
+    if value < 0 { None } else { Some(value) }
+}
+
+/// Runs of the text of a line along with the style each is shown in: that
+/// of its escape codes, the color of stderr lines, and the colors of the
+/// highlight spans painted over them, a whole line tint first.
+pub fn style_runs(line: &Line, highlights: &[HighlightSpan]) -> Vec<(TextStyle, String)> {
+    let mut style = TextStyle::default();
+    if line.is_stderr() {
+        style.paint(color::stderr_color());
+    }
+
+    let mut chars = vec![];
+    let mut offset = 0;
+    let mut push_text = |text: &str, style: TextStyle| {
+        for (index, value) in text.char_indices() {
+            chars.push((offset + index, style, value));
+        }
+        offset += text.len();
+    };
+
+    match line.components {
+        Some(ref value) => {
+            for component in &value.items {
+                match *component {
+                    Component::Style(value) => style.apply(value),
+                    Component::Content(ref text) => push_text(text, style),
+                }
+            }
+        }
+        None => push_text(&line.content_without_ansi, style),
+    }
+
+    let layers = Layers::new(highlights);
+    if let Some(pattern) = layers.tint {
+        let pair = color::highlight_color(pattern);
+        for &mut (_, ref mut style, _) in chars.iter_mut() {
+            style.paint(pair);
+        }
+    }
+    for span in layers.spans {
+        let pair = color::highlight_color(span.pattern);
+        for &mut (offset, ref mut style, _) in chars.iter_mut() {
+            if offset >= span.start && offset < span.end {
+                style.paint(pair);
+            }
+        }
+    }
+
+    let mut runs: Vec<(TextStyle, String)> = vec![];
+    for (_, style, value) in chars {
+        match runs.last_mut() {
+            Some(&mut (ref last, ref mut text)) if *last == style => text.push(value),
+            _ => runs.push((style, value.to_string())),
+        }
+    }
+
+    runs
+}
@@ -0,0 +1,200 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cmp::min;
+use std::ops::Range;
+
+/// Which end of the content keeps its place as new lines arrive.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Anchor {
+    Bottom,
+    Top,
+}
+
+/// Position of the screen relative to a line, identified by its id, so that
+/// it can be restored after lines are added or dropped around it.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct LineAnchor {
+    pub line_id: usize,
+    /// Rows of the line scrolled past the top of the screen.
+    pub offset: usize,
+}
+
+/// The visible slice of the rendered content. Content is anchored to the bottom
+/// of the screen, so the position is kept as the number of rows between the
+/// last rendered row and the last visible one.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Viewport {
+    pub reverse_index: usize,
+    pub visible_height: usize,
+    pub content_height: usize,
+}
+
+impl Viewport {
+    pub fn new(reverse_index: usize, visible_height: usize, content_height: usize) -> Viewport {
+        let mut viewport = Viewport {
+            reverse_index: 0,
+            visible_height: visible_height,
+            content_height: content_height,
+        };
+        viewport.scroll_to(reverse_index as i64);
+
+        viewport
+    }
+
+    pub fn max_reverse_index(&self) -> usize {
+        self.content_height.saturating_sub(self.visible_height)
+    }
+
+    /// Positive values scroll towards older content.
+    pub fn scroll_by(&mut self, rows: i64) {
+        let reverse_index = self.reverse_index as i64 + rows;
+        self.scroll_to(reverse_index);
+    }
+
+    pub fn scroll_to(&mut self, reverse_index: i64) {
+        let clamped = if reverse_index < 0 { 0 } else { reverse_index as usize };
+        self.reverse_index = min(clamped, self.max_reverse_index());
+    }
+
+    pub fn scroll_to_top(&mut self) {
+        self.reverse_index = self.max_reverse_index();
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.reverse_index = 0;
+    }
+
+    /// Scrolls so that the given row is shown at the top of the screen.
+    pub fn scroll_to_row(&mut self, row: usize) {
+        let reverse_index = self.content_height as i64 - self.visible_height as i64 - row as i64;
+        self.scroll_to(reverse_index);
+    }
+
+    /// Scrolls as little as needed for `row` to have `margin` rows shown
+    /// above and below it. The margin shrinks on screens too short for it,
+    /// and rows at either end of the content can't have it.
+    pub fn scroll_with_margin(&mut self, row: usize, margin: usize) {
+        let margin = min(margin, self.visible_height.saturating_sub(1) / 2);
+        let top_row = self.top_row();
+
+        if row < top_row + margin {
+            self.scroll_to_row(row.saturating_sub(margin));
+        } else if row + margin >= top_row + self.visible_height {
+            self.scroll_to_row(row + margin + 1 - self.visible_height);
+        }
+    }
+
+    /// Keeps the rows on screen after `rows` were appended to the content.
+    /// Bottom anchored viewports follow new content unless scrolled.
+    pub fn rows_appended(&mut self, rows: usize, anchor: Anchor) {
+        if anchor == Anchor::Bottom && self.reverse_index == 0 {
+            return;
+        }
+
+        let previous = Viewport::new(self.reverse_index,
+                                     self.visible_height,
+                                     self.content_height.saturating_sub(rows));
+        self.scroll_to_row(previous.top_row());
+    }
+
+    /// Anchor on the line shown at the top of the screen, given the id and
+    /// height of each line.
+    pub fn line_anchor<I>(&self, lines: I) -> Option<LineAnchor>
+        where I: IntoIterator<Item = (usize, usize)>
+    {
+        let top_row = self.top_row();
+        let mut row = 0;
+
+        for (line_id, height) in lines {
+            if row + height > top_row {
+                return Some(LineAnchor {
+                    line_id: line_id,
+                    offset: top_row - row,
+                });
+            }
+            row += height;
+        }
+
+        None
+    }
+
+    /// Scrolls back to the anchored line. Returns false when the line is gone.
+    pub fn scroll_to_anchor<I>(&mut self, anchor: &LineAnchor, lines: I) -> bool
+        where I: IntoIterator<Item = (usize, usize)>
+    {
+        let mut row = 0;
+
+        for (line_id, height) in lines {
+            if line_id == anchor.line_id {
+                self.scroll_to_row(row + anchor.offset.min(height.saturating_sub(1)));
+                return true;
+            }
+            row += height;
+        }
+
+        false
+    }
+
+    /// Rows from the bottom of the content up to the top of the screen.
+    pub fn limit(&self) -> usize {
+        self.reverse_index + self.visible_height
+    }
+
+    /// Content row displayed at the top of the screen. When the content is
+    /// shorter than the screen it's shown from the top.
+    pub fn top_row(&self) -> usize {
+        self.content_height.saturating_sub(self.limit())
+    }
+
+    pub fn visible_rows(&self) -> Range<usize> {
+        let start = self.top_row();
+        start..min(start + self.visible_height, self.content_height)
+    }
+
+    pub fn is_row_visible(&self, row: usize) -> bool {
+        let rows = self.visible_rows();
+        row >= rows.start && row < rows.end
+    }
+
+    /// Indexes of the lines having at least one visible row, based on the
+    /// height of each line.
+    pub fn line_range_visible<I>(&self, heights: I) -> Range<usize>
+        where I: IntoIterator<Item = usize>
+    {
+        let rows = self.visible_rows();
+        let mut start = None;
+        let mut end = 0;
+        let mut row = 0;
+
+        for (i, height) in heights.into_iter().enumerate() {
+            if row < rows.end && row + height > rows.start {
+                if start.is_none() {
+                    start = Some(i);
+                }
+                end = i + 1;
+            }
+            row += height;
+        }
+
+        match start {
+            Some(value) => value..end,
+            None => 0..0,
+        }
+    }
+}
@@ -17,7 +17,7 @@
  */
 
 use std::collections::HashMap;
-use regex::Regex;
+use regex::{Captures, Regex};
 use ncurses::*;
 
 use ui::color::COLOR_DEFAULT;
@@ -80,13 +80,32 @@ impl ComponentCollection {
         let mut components = ComponentCollection::new();
 
         lazy_static! {
-            static ref BREAK_ANSI_MATCHER: Regex = Regex::new(r"(\x1b\[\d+m)|([^\x1b]*)").unwrap();
+            static ref BREAK_ANSI_MATCHER: Regex = Regex::new(r"\x1b\[([\d;]*)m|([^\x1b]*)").unwrap();
         }
 
         for capture in BREAK_ANSI_MATCHER.captures_iter(value) {
-            if capture.get(1).is_some() {
-                if let Some(style) = ANSI_TO_NCURSES_MAPPING.get(capture.get(1).unwrap().as_str()) {
-                    components.push(Component::Style(style))
+            if let Some(parameters) = capture.get(1) {
+                // `ESC[1;31m` sets several styles at once, `ESC[m` resets
+                let codes = if parameters.as_str().is_empty() { "0" } else { parameters.as_str() };
+                let mut codes = codes.split(';');
+                while let Some(code) = codes.next() {
+                    // Extended colors, `38;5;n` or `38;2;r;g;b`, aren't shown
+                    // and their parameters aren't codes of their own
+                    if code == "38" || code == "48" {
+                        let skipped = match codes.next() {
+                            Some("5") => 1,
+                            Some("2") => 3,
+                            _ => 0,
+                        };
+                        for _ in 0..skipped {
+                            codes.next();
+                        }
+                        continue;
+                    }
+
+                    if let Some(style) = ANSI_TO_NCURSES_MAPPING.get(format!("\x1b[{}m", code).as_str()) {
+                        components.push(Component::Style(style))
+                    }
                 }
             }
             if capture.get(2).is_some() {
@@ -128,7 +147,7 @@ impl AnsiStr for str {
 
     fn strip_ansi(&self) -> String {
         lazy_static! {
-            static ref STRIP_ANSI_MATCHER: Regex = Regex::new(r"(\x1b\[\d+m)").unwrap();
+            static ref STRIP_ANSI_MATCHER: Regex = Regex::new(r"(\x1b\[[\d;]*m)").unwrap();
         }
         STRIP_ANSI_MATCHER.replace_all(self, "").to_string()
     }
@@ -137,3 +156,51 @@ impl AnsiStr for str {
         ComponentCollection::from_string(self)
     }
 }
+
+lazy_static! {
+    static ref CONTROL_SEQUENCE_MATCHER: Regex = Regex::new(concat!(
+        r"\x1b\[[0-?]*[ -/]*[@-~]|",         // CSI, such as cursor movement
+        r"\x9b[0-?]*[ -/]*[@-~]|",            // 8-bit CSI
+        r"\x1b\][^\x07\x1b]*(\x07|\x1b\\)?|", // OSC, such as window titles
+        r"\x1b[PX^_][^\x1b]*(\x1b\\)?|",     // DCS, SOS, PM and APC strings
+        r"\x1b[ -/]*[0-~]|",                // Other escapes, such as a full reset
+        r"\x1b|\x07"
+    )).unwrap();
+    static ref SGR_MATCHER: Regex = Regex::new(r"^\x1b\[[\d;]*m$").unwrap();
+}
+
+/// Removes terminal control sequences other than colors and attributes,
+/// such as cursor movement, screen clearing or alternate screen switches,
+/// which would otherwise garble the display. Also tells whether anything
+/// was removed.
+pub fn sanitize(value: &str) -> (String, bool) {
+    let mut stripped = false;
+
+    let result = CONTROL_SEQUENCE_MATCHER.replace_all(value, |captures: &Captures| {
+        let sequence = captures.get(0).unwrap().as_str();
+        if SGR_MATCHER.is_match(sequence) {
+            sequence.to_string()
+        } else {
+            stripped = true;
+            String::new()
+        }
+    });
+
+    (result.into_owned(), stripped)
+}
+
+/// Renders control characters literally, e.g. `\x1b[2J`, for inspection.
+pub fn escape_control(value: &str) -> String {
+    let mut result = String::new();
+
+    for c in value.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\t' => result.push_str("\\t"),
+            _ if c.is_control() => result.push_str(&format!("\\x{:02x}", c as u32)),
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
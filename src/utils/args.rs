@@ -19,18 +19,56 @@
 use std::process;
 use std::path::PathBuf;
 use std::fs;
+use std::sync::Arc;
+use std::fmt::Display;
 
-use utils::config_file::ConfigFile;
+use utils::completions::{self, Shell};
+use utils::config_file::{self, ConfigFile};
+use ui::color::ColorMode;
+use core::export::ExportFormat;
+use core::discovery::GlobPattern;
+use core::count::{self, Count};
+use core::line::SearchPattern;
+use core::query::Syntax;
+use core::log_format::Folder;
+use utils::output;
+use utils::profile::DEFAULT_PROFILE;
 
 const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+pub const USAGE: &'static str = include_str!("../etc/usage.txt");
 
 #[derive(Debug, RustcDecodable)]
 pub struct Args {
     pub flag_config: Option<String>,
+    pub flag_profile: Option<String>,
     pub flag_max: Option<usize>,
     pub flag_lines: Option<usize>,
+    pub flag_replay: bool,
+    pub flag_replay_rate: Option<usize>,
+    pub flag_no_unwrap: bool,
+    pub flag_record: Option<String>,
+    pub flag_hash_lines: bool,
+    pub flag_metrics_file: Option<String>,
+    pub flag_debug_log: Option<String>,
+    pub flag_control_socket: Option<String>,
+    pub flag_color: Option<String>,
+    pub flag_export_format: Option<String>,
+    pub flag_buffer_lines: Option<usize>,
     arg_input: Option<String>,
+    arg_files: Vec<String>,
+    flag_count: Option<String>,
+    flag_whole_word: bool,
+    flag_ignore_case: bool,
+    flag_regex: bool,
+    flag_expressions: bool,
+    flag_per_minute: bool,
+    cmd_completions: bool,
+    arg_shell: Option<String>,
+    flag_list_profiles: bool,
+    flag_print_config_path: bool,
+    flag_print_default_config: bool,
     flag_init: Option<String>,
+    flag_check: Option<String>,
     flag_version: bool,
 }
 
@@ -38,8 +76,20 @@ impl Args {
     pub fn process<F>(self, callback: F)
         where F: Fn(Args)
     {
-        if self.flag_init.is_some() {
+        if self.cmd_completions {
+            self.print_completions();
+        } else if self.flag_list_profiles {
+            self.list_profiles();
+        } else if self.flag_print_config_path {
+            self.print_config_path();
+        } else if self.flag_print_default_config {
+            quit!(config_file::default_contents().trim_end());
+        } else if self.flag_init.is_some() {
             self.write_config();
+        } else if self.flag_check.is_some() {
+            self.check_config();
+        } else if self.flag_count.is_some() {
+            self.count_matches();
         } else if self.flag_version {
             self.display_version();
         }
@@ -60,6 +110,132 @@ impl Args {
         quit!(message);
     }
 
+    /// Validates a config the same way it's loaded when running interactively.
+    fn check_config(&self) {
+        let path = PathBuf::from(self.flag_check.as_ref().unwrap());
+        assert_file_exists(&path);
+
+        match ConfigFile::load(&path) {
+            Ok(config) => {
+                let names = config.filters
+                    .iter()
+                    .map(|filter| format!("  {}", filter.name))
+                    .collect::<Vec<_>>();
+                let profiles = config.profiles();
+                let message = format!("{} is valid, defining {} filters:\n{}\nProfiles: {}",
+                                      path.display(),
+                                      names.len(),
+                                      names.join("\n"),
+                                      profiles.names().join(", "));
+                quit!(message);
+            }
+            Err(error) => {
+                let message = format!("{} is invalid.\n{}", path.display(), error);
+                critical_quit!(message);
+            }
+        }
+    }
+
+    /// Written to stdout without touching the terminal, to be sourced by the shell.
+    fn print_completions(&self) {
+        let name = self.arg_shell.as_ref().map_or("", String::as_str);
+        let shell = Shell::parse(name).unwrap_or_else(|| {
+            let message = format!("Unknown shell `{}`, expected {}",
+                                  name,
+                                  completions::SHELLS.join(", "));
+            critical_quit!(message);
+        });
+
+        let script = completions::script(shell, &completions::options(USAGE));
+        quit!(script.trim_end());
+    }
+
+    /// Profile names, one per line, for shell completions to offer.
+    fn list_profiles(&self) {
+        let config = ConfigFile::locate(self.get_config())
+            .and_then(ConfigFile::from_path)
+            .unwrap_or_else(ConfigFile::default);
+
+        quit!(config.profiles().names().join("\n"));
+    }
+
+    fn print_config_path(&self) {
+        match ConfigFile::locate(self.get_config()) {
+            Some(path) => {
+                let path = fs::canonicalize(&path).unwrap_or(path);
+                quit!(path.display());
+            }
+            None => {
+                critical_quit!("No config file found, the default config is used");
+            }
+        }
+    }
+
+    /// Counts matches of the first tab of the profile without the interface.
+    /// Errors exit with 2, since 1 means nothing matched.
+    fn count_matches(&self) {
+        let path = PathBuf::from(self.flag_config.as_ref().map_or("", String::as_str));
+        if self.flag_config.is_some() && !path.exists() {
+            count_failed(format!("No file exists at provided location `{:?}`", path));
+        }
+        let config = match ConfigFile::locate(path) {
+            Some(path) => ConfigFile::load(&path).unwrap_or_else(|error| count_failed(error)),
+            None => ConfigFile::default(),
+        };
+        let profile_name = self.flag_profile.clone().unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+        let filter = config.profiles()
+            .resolve(&profile_name)
+            .and_then(|profile| {
+                profile.filters
+                    .and_then(|filters| filters.into_iter().next())
+                    .ok_or_else(|| format!("Profile `{}` has no tabs", profile_name))
+            })
+            .unwrap_or_else(|message| count_failed(message));
+
+        let sources = config.sources.unwrap_or_default();
+        let text = self.flag_count.as_ref().unwrap();
+        let syntax = Syntax {
+            regex: self.flag_regex,
+            expressions: self.flag_expressions,
+        };
+        let mut pattern = SearchPattern::new(text, self.flag_whole_word).with_syntax(syntax);
+        if let Some(message) = pattern.invalid() {
+            count_failed(message);
+        }
+        if self.flag_ignore_case {
+            pattern = pattern.ignoring_case().unwrap_or_else(|message| count_failed(message));
+        }
+
+        // A single file may be taken for the input of the interactive usage
+        let counts = self.arg_input
+            .iter()
+            .chain(self.arg_files.iter())
+            .map(|path| {
+                let contents = fs::read(path).unwrap_or_else(|error| {
+                    count_failed(format!("Couldn't read `{}`: {}", path, error));
+                });
+                let lines = String::from_utf8_lossy(&contents)
+                    .lines()
+                    .map(|line| (0, Arc::from(line)))
+                    .collect::<Vec<_>>();
+
+                // Records of the format the config gives the file are counted once
+                let mut folder = Folder::new(sources.clone());
+                let mut lines = folder.fold(lines, |_| Some(path.clone()));
+                lines.extend(folder.finish());
+                let lines = lines.into_iter().map(|(_, line)| line);
+                let unwrap = !self.flag_no_unwrap;
+                (path.clone(), Count::from_lines(lines, filter.clone(), &pattern, unwrap))
+            })
+            .collect::<Vec<_>>();
+
+        let code = if count::total(&counts) > 0 { 0 } else { 1 };
+        if let Err(error) = output::print_line(&count::report(&counts, self.flag_per_minute)) {
+            count_failed(error);
+        }
+        process::exit(code);
+    }
+
     fn display_version(&self) {
         let message = format!("flow version {}", VERSION);
         quit!(message);
@@ -69,11 +245,46 @@ impl Args {
         let target = self.arg_input.as_ref().unwrap_or_else(|| {
             critical_quit!("No input file provided");
         });
-        assert_file_exists(&PathBuf::from(target));
+        // Files matching a pattern may only show up later on
+        if GlobPattern::is_glob(target) {
+            let pattern = GlobPattern::parse(target).unwrap_or_else(|message| {
+                critical_quit!(message);
+            });
+            assert_file_exists(&pattern.directory);
+        } else {
+            assert_file_exists(&PathBuf::from(target));
+        }
 
         target.to_string()
     }
 
+    pub fn get_color_mode(&self) -> ColorMode {
+        match self.flag_color {
+            Some(ref value) => {
+                ColorMode::parse(value).unwrap_or_else(|| {
+                    let message = format!("Invalid `--color` value `{}`, expected auto, always or never",
+                                          value);
+                    critical_quit!(message);
+                })
+            }
+            None => ColorMode::Auto,
+        }
+    }
+
+    pub fn get_export_format(&self) -> ExportFormat {
+        match self.flag_export_format {
+            Some(ref value) => {
+                ExportFormat::parse(value).unwrap_or_else(|| {
+                    let message = format!("Invalid `--export-format` value `{}`, expected plain, \
+                                           ansi or html",
+                                          value);
+                    critical_quit!(message);
+                })
+            }
+            None => ExportFormat::Plain,
+        }
+    }
+
     pub fn get_config(&self) -> PathBuf {
         if let Some(ref value) = self.flag_config {
             let pathbuf = PathBuf::from(value);
@@ -85,6 +296,11 @@ impl Args {
     }
 }
 
+fn count_failed<T: Display>(message: T) -> ! {
+    eprintln!("{}", message);
+    process::exit(2)
+}
+
 fn assert_file_exists(path: &PathBuf) {
     if !path.exists() {
         let message = format!("No file exists at provided location `{:?}`", path);
@@ -0,0 +1,151 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::iter::Peekable;
+use std::ops::Range;
+
+use unicode_segmentation::{GraphemeIndices, UnicodeSegmentation};
+use unicode_width::UnicodeWidthChar;
+
+static ZERO_WIDTH_JOINER: char = '\u{200d}';
+static EMOJI_PRESENTATION_SELECTOR: char = '\u{fe0f}';
+
+/// Splits text into the clusters of characters printed as a single glyph,
+/// along with their byte offsets. On top of the grapheme clusters found by
+/// `unicode-segmentation`, emoji joined with a zero width joiner or followed
+/// by a skin tone modifier are kept together, and runs of regional
+/// indicators are split into the flags they pair up into.
+pub fn clusters(text: &str) -> Clusters {
+    Clusters {
+        text: text,
+        graphemes: text.grapheme_indices(true).peekable(),
+        pending: None,
+    }
+}
+
+pub struct Clusters<'a> {
+    text: &'a str,
+    graphemes: Peekable<GraphemeIndices<'a>>,
+    /// Flags left over from a run of regional indicators.
+    pending: Option<(usize, &'a str)>,
+}
+
+impl<'a> Iterator for Clusters<'a> {
+    type Item = (usize, &'a str);
+
+    fn next(&mut self) -> Option<(usize, &'a str)> {
+        let (start, mut cluster) = match self.pending.take() {
+            Some(value) => value,
+            None => self.graphemes.next()?,
+        };
+
+        if let Some((offset, _)) = cluster.char_indices().nth(2) {
+            if cluster.chars().take(3).all(is_regional_indicator) {
+                self.pending = Some((start + offset, &cluster[offset..]));
+                return Some((start, &cluster[..offset]));
+            }
+        }
+
+        while self.pending.is_none() {
+            let joins = match self.graphemes.peek() {
+                Some(&(_, next)) => {
+                    cluster.ends_with(ZERO_WIDTH_JOINER) ||
+                    next.chars().next().map_or(false, is_skin_tone_modifier)
+                }
+                None => false,
+            };
+            if !joins {
+                break;
+            }
+
+            let (next_start, next) = self.graphemes.next().unwrap();
+            cluster = &self.text[start..next_start + next.len()];
+        }
+
+        Some((start, cluster))
+    }
+}
+
+/// Columns taken up by a cluster: joined emoji take up as much as a single
+/// one, and emoji presentation turns narrow symbols wide.
+pub fn width(cluster: &str) -> usize {
+    let mut chars = cluster.chars();
+    let first = match chars.next() {
+        Some(value) => value,
+        None => return 0,
+    };
+
+    if is_regional_indicator(first) {
+        return if chars.next().map_or(false, is_regional_indicator) { 2 } else { 1 };
+    }
+
+    let widest = cluster.chars().map(|value| value.width().unwrap_or(0)).max().unwrap_or(0);
+    if widest == 1 && cluster.contains(EMOJI_PRESENTATION_SELECTOR) {
+        2
+    } else {
+        widest
+    }
+}
+
+/// Width of the text, measured a cluster at a time.
+pub fn text_width(text: &str) -> usize {
+    // Each ASCII character is a cluster of its own, taking up a column unless
+    // it's a control character. Shifted by one, printable ones are the only
+    // bytes above a space as signed ones, which is counted in a single pass.
+    if text.is_ascii() {
+        return text.bytes().map(|byte| (byte.wrapping_add(1) as i8 > b' ' as i8) as usize).sum();
+    }
+
+    clusters(text).map(|(_, cluster)| width(cluster)).sum()
+}
+
+/// Widens a byte range so that it neither starts nor ends within a cluster,
+/// as a glyph can only be painted whole.
+pub fn snap(text: &str, range: Range<usize>) -> Range<usize> {
+    snap_to(&boundaries(text), range)
+}
+
+/// Offsets the clusters of the text start at, followed by its length, for
+/// snapping many ranges without going over the text for each.
+pub fn boundaries(text: &str) -> Vec<usize> {
+    let mut offsets = clusters(text).map(|(offset, _)| offset).collect::<Vec<_>>();
+    offsets.push(text.len());
+    offsets
+}
+
+/// Same as `snap`, given the boundaries of the text.
+pub fn snap_to(boundaries: &[usize], range: Range<usize>) -> Range<usize> {
+    let start = match boundaries.binary_search(&range.start) {
+        Ok(_) => range.start,
+        Err(index) => boundaries[index.saturating_sub(1)],
+    };
+    let end = match boundaries.binary_search(&range.end) {
+        Ok(_) => range.end,
+        Err(index) => boundaries.get(index).cloned().unwrap_or(range.end),
+    };
+
+    start..end.max(start)
+}
+
+fn is_regional_indicator(value: char) -> bool {
+    value >= '\u{1f1e6}' && value <= '\u{1f1ff}'
+}
+
+fn is_skin_tone_modifier(value: char) -> bool {
+    value >= '\u{1f3fb}' && value <= '\u{1f3ff}'
+}
@@ -0,0 +1,252 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use regex::Regex;
+
+use ui::color::COLOR_MODES;
+use core::export::EXPORT_FORMATS;
+
+pub static SHELLS: &'static [&'static str] = &["bash", "zsh", "fish"];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    pub fn parse(value: &str) -> Option<Shell> {
+        match value {
+            "bash" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            _ => None,
+        }
+    }
+}
+
+/// What an option's value is completed with.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Value {
+    Nothing,
+    Path,
+    Choices(&'static [&'static str]),
+    /// Names of the profiles of the config, asked for through `--list-profiles`.
+    Profiles,
+}
+
+/// An option listed in the usage text.
+#[derive(PartialEq, Debug)]
+pub struct CliOption {
+    pub long: String,
+    pub short: Option<char>,
+    /// Present when the option takes a value.
+    pub value: Option<Value>,
+    pub description: String,
+}
+
+/// Options listed in the usage text, which is what docopt parses them from,
+/// so that completions can't fall behind the flags actually accepted.
+pub fn options(usage: &str) -> Vec<CliOption> {
+    lazy_static! {
+        static ref OPTION: Regex =
+            Regex::new(r"^\s+(?:-(\w), )?--([\w-]+)(?:=<([\w-]+)>)?\s{2,}(.*)$").unwrap();
+    }
+
+    usage.lines()
+        .filter_map(|line| OPTION.captures(line))
+        .map(|captures| {
+            CliOption {
+                long: captures[2].to_string(),
+                short: captures.get(1).and_then(|value| value.as_str().chars().next()),
+                value: captures.get(3).map(|value| value_for(value.as_str())),
+                description: captures[4].to_string(),
+            }
+        })
+        .collect()
+}
+
+// Placeholders name what the value is
+fn value_for(placeholder: &str) -> Value {
+    match placeholder {
+        "path" | "config" => Value::Path,
+        "name" => Value::Profiles,
+        "when" => Value::Choices(COLOR_MODES),
+        "format" => Value::Choices(EXPORT_FORMATS),
+        "shell" => Value::Choices(SHELLS),
+        _ => Value::Nothing,
+    }
+}
+
+/// Completion script for the shell, covering the given options.
+pub fn script(shell: Shell, options: &[CliOption]) -> String {
+    match shell {
+        Shell::Bash => bash_script(options),
+        Shell::Zsh => zsh_script(options),
+        Shell::Fish => fish_script(options),
+    }
+}
+
+fn names(option: &CliOption) -> Vec<String> {
+    let mut names = vec![format!("--{}", option.long)];
+    names.extend(option.short.map(|value| format!("-{}", value)));
+    names
+}
+
+fn bash_case(patterns: &[String], reply: &str) -> String {
+    let mut case = format!("        {})\n", patterns.join("|"));
+    if !reply.is_empty() {
+        case.push_str(&format!("            {}\n", reply));
+    }
+
+    case + "            return ;;"
+}
+
+fn bash_words(words: &str) -> String {
+    format!("COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))", words)
+}
+
+fn bash_script(options: &[CliOption]) -> String {
+    let mut cases = vec![];
+
+    for option in options {
+        if let Some(Value::Choices(choices)) = option.value {
+            cases.push(bash_case(&names(option), &bash_words(&choices.join(" "))));
+        }
+    }
+
+    let grouped = [(Value::Profiles, bash_words("$(flow --list-profiles 2>/dev/null)")),
+                   (Value::Path, "COMPREPLY=($(compgen -f -- \"$cur\"))".to_string()),
+                   (Value::Nothing, String::new())];
+    for &(kind, ref reply) in &grouped {
+        let patterns = options.iter()
+            .filter(|option| option.value == Some(kind))
+            .flat_map(names)
+            .collect::<Vec<_>>();
+        if !patterns.is_empty() {
+            cases.push(bash_case(&patterns, reply));
+        }
+    }
+    cases.push(bash_case(&["completions".to_string()], &bash_words(&SHELLS.join(" "))));
+
+    let flags = options.iter().map(|option| format!("--{}", option.long)).collect::<Vec<_>>();
+
+    format!(r#"_flow() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    local prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    if [[ "$prev" == "=" ]]; then
+        prev="${{COMP_WORDS[COMP_CWORD-2]}}"
+    elif [[ "$cur" == "=" ]]; then
+        cur=""
+    fi
+
+    case "$prev" in
+{}
+    esac
+
+    if [[ "$cur" == -* ]]; then
+        COMPREPLY=($(compgen -W "{}" -- "$cur"))
+    else
+        COMPREPLY=($(compgen -f -- "$cur"))
+    fi
+}}
+complete -o filenames -F _flow flow
+"#,
+            cases.join("\n"),
+            flags.join(" "))
+}
+
+fn zsh_script(options: &[CliOption]) -> String {
+    let specs = options.iter()
+        .map(|option| {
+            let description = option.description.replace('\'', "'\\''").replace(']', "\\]");
+            let action = match option.value {
+                None => String::new(),
+                Some(Value::Nothing) => ":value: ".to_string(),
+                Some(Value::Path) => ":path:_files".to_string(),
+                Some(Value::Choices(choices)) => format!(":value:({})", choices.join(" ")),
+                Some(Value::Profiles) => ":profile:_flow_profiles".to_string(),
+            };
+            let equals = if option.value.is_some() { "=" } else { "" };
+
+            match option.short {
+                Some(short) => {
+                    format!("    '(-{short} --{long})'{{-{short},--{long}{equals}}}'[{}]{}'",
+                            description,
+                            action,
+                            short = short,
+                            long = option.long,
+                            equals = equals)
+                }
+                None => format!("    '--{}{}[{}]{}'", option.long, equals, description, action),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    format!(r#"#compdef flow
+
+_flow_profiles() {{
+    local -a profiles
+    profiles=(${{(f)"$(flow --list-profiles 2>/dev/null)"}})
+    _describe 'profile' profiles
+}}
+
+_flow() {{
+    if (( CURRENT == 3 )) && [[ "$words[2]" == completions ]]; then
+        _values 'shell' {}
+        return
+    fi
+
+    _arguments -s \
+{} \
+        '1:input:_files'
+}}
+
+_flow "$@"
+"#,
+            SHELLS.join(" "),
+            specs.join(" \\\n"))
+}
+
+fn fish_script(options: &[CliOption]) -> String {
+    let mut lines = vec!["complete -c flow -f -n '__fish_seen_subcommand_from completions' -a '{}'"
+                             .replace("{}", &SHELLS.join(" "))];
+
+    for option in options {
+        let mut line = format!("complete -c flow -l {}", option.long);
+        if let Some(short) = option.short {
+            line.push_str(&format!(" -s {}", short));
+        }
+
+        match option.value {
+            None => {}
+            Some(Value::Nothing) => line.push_str(" -x"),
+            Some(Value::Path) => line.push_str(" -r -F"),
+            Some(Value::Choices(choices)) => {
+                line.push_str(&format!(" -x -a '{}'", choices.join(" ")))
+            }
+            Some(Value::Profiles) => line.push_str(" -x -a '(flow --list-profiles 2>/dev/null)'"),
+        }
+
+        line.push_str(&format!(" -d '{}'", option.description.replace('\'', "\\'")));
+        lines.push(line);
+    }
+
+    lines.join("\n") + "\n"
+}
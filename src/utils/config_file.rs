@@ -17,12 +17,21 @@
  */
 
 use std::path::PathBuf;
+use std::collections::BTreeMap;
 use std::{env, process};
 use std::fs::{self, File};
 use std::io::{Read, Write};
 use toml;
+use rustc_serialize::Decodable;
 
 use core::filter::Filter;
+use core::actions::QuickAction;
+use core::log_format::SourceFormat;
+use core::eviction::Protection;
+use core::error::FlowError;
+use ui::color::Theme;
+use utils::wrap::validate_continuation_prefix;
+use utils::profile::{Profile, Profiles};
 
 const SAMPLE: &'static [u8] = include_bytes!("../etc/sample-config.toml");
 const DEFAULT: &'static str = include_str!("../etc/default-config.toml");
@@ -30,6 +39,45 @@ const DEFAULT: &'static str = include_str!("../etc/default-config.toml");
 #[derive(RustcDecodable)]
 pub struct ConfigFile {
     pub filters: Vec<Filter>,
+    pub wheel_scroll_lines: Option<usize>,
+    /// Whether held arrow keys scroll faster the longer they're held.
+    pub key_repeat_acceleration: Option<bool>,
+    /// Shows one of every this many lines while sampling, instead of a
+    /// ratio following the rate lines arrive at.
+    pub sample_every: Option<usize>,
+    /// Lines a second shown while sampling with a ratio that follows the rate.
+    pub sample_target_rate: Option<usize>,
+    /// Rows kept visible above and below the current match.
+    pub scrolloff: Option<usize>,
+    pub dim_lines_after: Option<i64>,
+    /// New lines are flashed while fewer than this many arrive a minute.
+    pub flash_new_lines: Option<usize>,
+    pub keymap: Option<String>,
+    pub actions: Option<Vec<QuickAction>>,
+    /// Formats of the files followed, as `[[sources]]`.
+    pub sources: Option<Vec<SourceFormat>>,
+    pub highlights: Option<Vec<String>>,
+    pub highlight_colors: Option<Vec<String>>,
+    pub theme: Option<Theme>,
+    pub buffer_lines: Option<i64>,
+    pub continuation_prefix: Option<String>,
+    pub min_lines_per_source: Option<usize>,
+    /// Whether runs of blank lines are shown as a single row: `always`,
+    /// `never`, or `merged` when following several files.
+    pub fold_blank_lines: Option<String>,
+    /// What happens to marked or pinned lines about to be evicted: `warn`,
+    /// `preserve` or `ignore`.
+    pub protected_lines: Option<String>,
+    /// Takes JSON commands on a Unix socket at the path, like `--control-socket`.
+    pub control_socket: Option<String>,
+    /// Named sets of tabs, highlights and colors, as `[profile.<name>]`.
+    pub profile: Option<BTreeMap<String, Profile>>,
+}
+
+/// The config used when there's no config file. Printing it rather than
+/// a copy keeps what's printed the same as what's parsed.
+pub fn default_contents() -> &'static str {
+    DEFAULT
 }
 
 impl ConfigFile {
@@ -38,36 +86,39 @@ impl ConfigFile {
             return None;
         }
 
-        let mut file_handle = match File::open(&path) {
-            Ok(value) => value,
+        match ConfigFile::load(&path) {
+            Ok(value) => Some(value),
             Err(message) => {
-                let message = format!("{:?} couldn't be opened - {}", path, message);
                 critical_quit!(message);
             }
-        };
+        }
+    }
 
+    /// Reads and validates the config at the given path.
+    pub fn load(path: &PathBuf) -> Result<ConfigFile, FlowError> {
         let contents = &mut String::new();
-        let _ = file_handle.read_to_string(contents);
+        let result = File::open(path).and_then(|mut file| file.read_to_string(contents));
 
-        Some(ConfigFile::new(contents))
-    }
-
-    pub fn from_current_dir() -> Option<ConfigFile> {
-        let mut path = env::current_dir().unwrap();
-        path.push(".flow");
+        if let Err(error) = result {
+            return Err(FlowError::from_io(path, &error));
+        }
 
-        ConfigFile::from_path(path)
+        ConfigFile::parse(contents).map_err(FlowError::Config)
     }
 
-    pub fn from_home_dir() -> Option<ConfigFile> {
-        let mut path = env::home_dir().unwrap();
-        path.push(".flow");
+    /// Where the config is read from: the given path, or else `.flow` in the
+    /// current directory or in the user's home, whichever exists first.
+    pub fn locate(path: PathBuf) -> Option<PathBuf> {
+        let mut current = env::current_dir().unwrap();
+        current.push(".flow");
+        let mut home = env::home_dir().unwrap();
+        home.push(".flow");
 
-        ConfigFile::from_path(path)
+        vec![path, current, home].into_iter().find(|path| path.exists())
     }
 
     pub fn default() -> ConfigFile {
-        ConfigFile::new(DEFAULT)
+        ConfigFile::new(default_contents())
     }
 
     pub fn write_sample(path: &PathBuf) {
@@ -85,18 +136,97 @@ impl ConfigFile {
         let _ = file_handle.write(SAMPLE);
     }
 
-    fn new(contents: &str) -> ConfigFile {
-        let parsed_contents = match toml::Parser::new(contents).parse() {
+    pub fn parse(contents: &str) -> Result<ConfigFile, String> {
+        let mut parser = toml::Parser::new(contents);
+
+        let parsed_contents = match parser.parse() {
             Some(value) => value,
             None => {
-                critical_quit!("Provided config file doesn't have a valid format.");
+                let errors = parser.errors
+                    .iter()
+                    .map(|error| {
+                        let (line, column) = parser.to_linecol(error.lo);
+                        format!("line {}, column {}: {}", line + 1, column + 1, error.desc)
+                    })
+                    .collect::<Vec<_>>();
+
+                return Err(format!("Provided config file doesn't have a valid format.\n{}",
+                                   errors.join("\n")));
             }
         };
 
-        match toml::decode(toml::Value::Table(parsed_contents)) {
-            Some(value) => value,
-            None => {
-                critical_quit!("Error deserializing config");
+        let mut decoder = toml::Decoder::new(toml::Value::Table(parsed_contents));
+        let config = match ConfigFile::decode(&mut decoder) {
+            Ok(value) => value,
+            Err(error) => return Err(format!("Error deserializing config - {}", error)),
+        };
+
+        config.base_profile().validate()?;
+
+        match config.keymap.as_ref().map(String::as_str) {
+            None | Some("default") | Some("vim") => {}
+            Some(value) => {
+                return Err(format!("Unknown keymap `{}`, expected `default` or `vim`.", value))
+            }
+        }
+
+        match config.fold_blank_lines.as_ref().map(String::as_str) {
+            None | Some("always") | Some("never") | Some("merged") => {}
+            Some(value) => {
+                return Err(format!("Unknown fold_blank_lines `{}`, expected `always`, `never` \
+                                    or `merged`.",
+                                   value))
+            }
+        }
+
+        if let Some(ref value) = config.protected_lines {
+            if Protection::parse(value).is_none() {
+                return Err(format!("Unknown protected_lines `{}`, expected `warn`, `preserve` \
+                                    or `ignore`.",
+                                   value));
+            }
+        }
+
+        if config.sample_every == Some(0) {
+            return Err("`sample_every` must be at least 1.".to_string());
+        }
+        if config.sample_target_rate == Some(0) {
+            return Err("`sample_target_rate` must be at least 1.".to_string());
+        }
+
+        if let Some(ref value) = config.continuation_prefix {
+            validate_continuation_prefix(value)?;
+        }
+
+        for (name, profile) in config.profile.iter().flat_map(|profiles| profiles.iter()) {
+            if let Err(message) = profile.validate() {
+                return Err(format!("Invalid profile `{}` - {}", name, message));
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Settings outside of any profile.
+    pub fn base_profile(&self) -> Profile {
+        Profile {
+            filters: Some(self.filters.clone()),
+            highlights: self.highlights.clone(),
+            highlight_colors: self.highlight_colors.clone(),
+            theme: self.theme.clone(),
+            buffer_lines: self.buffer_lines,
+        }
+    }
+
+    pub fn profiles(&self) -> Profiles {
+        Profiles::new(self.base_profile(), self.profile.clone().unwrap_or_default())
+    }
+
+    fn new(contents: &str) -> ConfigFile {
+        match ConfigFile::parse(contents) {
+            Ok(value) => value,
+            Err(message) => {
+                critical_quit!(message);
             }
         }
     }
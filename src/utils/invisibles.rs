@@ -0,0 +1,79 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::borrow::Cow;
+
+use memchr::memchr;
+
+use utils::clusters;
+
+pub static TAB_SYMBOL: &'static str = "→";
+pub static NO_BREAK_SPACE_SYMBOL: &'static str = "⍽";
+pub static TRAILING_SPACE_SYMBOL: &'static str = "·";
+pub static NUL_SYMBOL: &'static str = "␀";
+
+/// Visible replacement for a character that would otherwise be hidden.
+/// Every replacement takes up a single column.
+pub fn symbol_for(value: char, is_trailing: bool) -> Option<&'static str> {
+    match value {
+        '\t' => Some(TAB_SYMBOL),
+        '\u{a0}' => Some(NO_BREAK_SPACE_SYMBOL),
+        '\0' => Some(NUL_SYMBOL),
+        ' ' if is_trailing => Some(TRAILING_SPACE_SYMBOL),
+        _ => None,
+    }
+}
+
+/// Character index from where the text only contains whitespace.
+pub fn trailing_whitespace_offset(text: &str) -> usize {
+    text.trim_end().chars().count()
+}
+
+/// Replaces invisible characters within a slice of a line, `char_offset` being
+/// the position of the slice within the line.
+pub fn reveal(text: &str, char_offset: usize, trailing_offset: usize) -> String {
+    let mut result = String::with_capacity(text.len());
+
+    for (i, value) in text.chars().enumerate() {
+        match symbol_for(value, char_offset + i >= trailing_offset) {
+            Some(symbol) => result.push_str(symbol),
+            None => result.push(value),
+        }
+    }
+
+    result
+}
+
+/// NUL bytes can't be handed to ncurses, so they're always shown as a symbol.
+pub fn escape_nul(text: &str) -> Cow<str> {
+    if text.contains('\0') {
+        Cow::Owned(text.replace('\0', NUL_SYMBOL))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Width of the text once NUL bytes are escaped.
+pub fn display_width(text: &str) -> usize {
+    let width = clusters::text_width(text);
+
+    match memchr(0, text.as_bytes()) {
+        Some(_) => width + text.matches('\0').count(),
+        None => width,
+    }
+}
@@ -17,6 +17,13 @@
  */
 
 pub mod ansi_decoder;
+pub mod invisibles;
+pub mod wrap;
+pub mod clusters;
+pub mod offset_map;
 pub mod args;
 pub mod settings;
 pub mod config_file;
+pub mod profile;
+pub mod completions;
+pub mod output;
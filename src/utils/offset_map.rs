@@ -0,0 +1,103 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/// Maps byte offsets within a text to the matching ones within its displayed
+/// form. Every display transformation builds one as it goes, so that matches
+/// found in the original text can be painted at the right cells.
+#[derive(Clone, PartialEq, Debug)]
+pub struct OffsetMap {
+    // Pairs of original and displayed offsets, in increasing order
+    points: Vec<(usize, usize)>,
+}
+
+impl OffsetMap {
+    pub fn new() -> OffsetMap {
+        OffsetMap { points: vec![(0, 0)] }
+    }
+
+    /// Map of a text displayed as is.
+    pub fn identity(length: usize) -> OffsetMap {
+        let mut map = OffsetMap::new();
+        map.push(length, length);
+        map
+    }
+
+    /// Records that the next `original` bytes are displayed as `displayed`
+    /// bytes. Removed text is pushed with a displayed length of 0.
+    pub fn push(&mut self, original: usize, displayed: usize) {
+        let (last_original, last_displayed) = self.last();
+        self.points.push((last_original + original, last_displayed + displayed));
+    }
+
+    /// Displayed offset of the given original one. Offsets within a span
+    /// displayed as is keep their position in it, while offsets within a
+    /// replaced span map to its start.
+    pub fn map(&self, offset: usize) -> usize {
+        match self.points.binary_search_by(|point| point.0.cmp(&offset)) {
+            Ok(value) => {
+                // Removed spans share the same original offset, prefer the first one
+                let mut index = value;
+                while index > 0 && self.points[index - 1].0 == offset {
+                    index -= 1;
+                }
+                self.points[index].1
+            }
+            Err(value) => {
+                let (start, displayed_start) = self.points[value.saturating_sub(1)];
+
+                match self.points.get(value) {
+                    Some(&(end, displayed_end)) if end - start == displayed_end - displayed_start => {
+                        displayed_start + offset - start
+                    }
+                    Some(_) => displayed_start,
+                    None => self.last().1,
+                }
+            }
+        }
+    }
+
+    /// Map of applying this transformation, followed by `next` on its result.
+    pub fn then(&self, next: &OffsetMap) -> OffsetMap {
+        let mut points = self.points
+            .iter()
+            .map(|&(original, displayed)| (original, next.map(displayed)))
+            .collect::<Vec<_>>();
+
+        // Points of the second map falling within text kept as is by the first
+        // one are carried over, so that positions within that text stay exact
+        for pair in self.points.windows(2) {
+            let ((start, displayed_start), (end, displayed_end)) = (pair[0], pair[1]);
+            if end - start != displayed_end - displayed_start {
+                continue;
+            }
+
+            for &(middle, displayed) in &next.points {
+                if middle > displayed_start && middle < displayed_end {
+                    points.push((start + middle - displayed_start, displayed));
+                }
+            }
+        }
+
+        points.sort();
+        OffsetMap { points: points }
+    }
+
+    fn last(&self) -> (usize, usize) {
+        *self.points.last().unwrap()
+    }
+}
@@ -0,0 +1,36 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::io::{self, Write};
+
+/// Writes a line to stdout. A reader going away, as `head` does once it read
+/// enough, isn't an error: the rest of the output just isn't wanted.
+pub fn print_line(text: &str) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut handle = stdout.lock();
+
+    write_line(&mut handle, text)
+}
+
+/// Same as `print_line`, to any writer.
+pub fn write_line<W: Write>(out: &mut W, text: &str) -> io::Result<()> {
+    match writeln!(out, "{}", text).and_then(|_| out.flush()) {
+        Err(ref error) if error.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
@@ -0,0 +1,118 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::BTreeMap;
+
+use core::filter::Filter;
+use core::highlights::HighlightPattern;
+use ui::color::{ColorPair, Theme};
+use ui::content::validate_buffer_rows;
+
+pub static DEFAULT_PROFILE: &'static str = "default";
+
+/// Settings a profile replaces. Those it leaves out are taken from the rest
+/// of the config file.
+#[derive(RustcDecodable, Clone, Default)]
+pub struct Profile {
+    pub filters: Option<Vec<Filter>>,
+    pub highlights: Option<Vec<String>>,
+    pub highlight_colors: Option<Vec<String>>,
+    pub theme: Option<Theme>,
+    pub buffer_lines: Option<i64>,
+}
+
+impl Profile {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.filters.as_ref().map_or(false, |filters| filters.is_empty()) {
+            return Err("At least one filter needs to be defined.".to_string());
+        }
+
+        for source in self.highlights.iter().flat_map(|values| values.iter()) {
+            if let Err(error) = HighlightPattern::parse(source) {
+                return Err(format!("Invalid highlight - {}", error));
+            }
+        }
+
+        for value in self.highlight_colors.iter().flat_map(|values| values.iter()) {
+            ColorPair::parse(value)?;
+        }
+
+        if let Some(ref theme) = self.theme {
+            theme.pairs()?;
+        }
+
+        if let Some(value) = self.buffer_lines {
+            validate_buffer_rows(value)?;
+        }
+
+        Ok(())
+    }
+
+    /// The profile, with whatever it leaves out taken from `base`.
+    pub fn or(&self, base: &Profile) -> Profile {
+        Profile {
+            filters: self.filters.clone().or_else(|| base.filters.clone()),
+            highlights: self.highlights.clone().or_else(|| base.highlights.clone()),
+            highlight_colors: self.highlight_colors
+                .clone()
+                .or_else(|| base.highlight_colors.clone()),
+            theme: self.theme.clone().or_else(|| base.theme.clone()),
+            buffer_lines: self.buffer_lines.or(base.buffer_lines),
+        }
+    }
+
+    pub fn menu_item_names(&self) -> Vec<String> {
+        self.filters.iter().flat_map(|filters| filters.iter()).map(|tab| tab.name.clone()).collect()
+    }
+}
+
+/// Profiles of the config file, along with the settings outside of them,
+/// which make up the default profile.
+#[derive(Clone, Default)]
+pub struct Profiles {
+    base: Profile,
+    named: BTreeMap<String, Profile>,
+}
+
+impl Profiles {
+    pub fn new(base: Profile, named: BTreeMap<String, Profile>) -> Profiles {
+        Profiles {
+            base: base,
+            named: named,
+        }
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = vec![DEFAULT_PROFILE];
+        names.extend(self.named.keys().map(String::as_str).filter(|name| *name != DEFAULT_PROFILE));
+        names
+    }
+
+    /// The settings of the named profile, complete with the defaults.
+    pub fn resolve(&self, name: &str) -> Result<Profile, String> {
+        match self.named.get(name) {
+            Some(profile) => Ok(profile.or(&self.base)),
+            None if name == DEFAULT_PROFILE => Ok(self.base.clone()),
+            None => {
+                Err(format!("Unknown profile `{}`, expected one of: {}",
+                            name,
+                            self.names().join(", ")))
+            }
+        }
+    }
+}
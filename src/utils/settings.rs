@@ -16,36 +16,154 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::process;
+use std::{env, process};
+use std::path::PathBuf;
 
 use utils::args::Args;
 use utils::config_file::ConfigFile;
 use core::filter::Filter;
+use core::actions::QuickAction;
+use core::log_format::SourceFormat;
+use ui::color::{ColorMode, ColorPair};
+use core::export::ExportFormat;
+use ui::content::{validate_buffer_rows, DEFAULT_BUFFER_ROWS};
+use utils::wrap::DEFAULT_CONTINUATION_PREFIX;
+use utils::profile::{Profile, Profiles, DEFAULT_PROFILE};
+use core::discovery::GlobPattern;
+use core::debug_log;
+use core::sampling::DEFAULT_TARGET_RATE;
+use core::eviction::Protection;
 
 static DEFAULT_LAST_LINES_SHOWN: usize = 10;
 static DEFAULT_MAX_LINES_STORED: usize = 3000;
+static DEFAULT_WHEEL_SCROLL_LINES: usize = 3;
+static DEFAULT_REPLAY_RATE: usize = 10;
 
 pub struct Settings {
     pub path_to_target_file: String,
     pub last_lines_count: usize,
     pub max_lines_count: usize,
     pub filters: Vec<Filter>,
+    pub wheel_scroll_lines: usize,
+    pub key_repeat_acceleration: bool,
+    /// Fixed sampling ratio, the ratio following the rate otherwise.
+    pub sample_every: Option<usize>,
+    pub sample_target_rate: usize,
+    /// Rows kept visible above and below the current match.
+    pub scrolloff: usize,
+    pub dim_lines_after: Option<i64>,
+    /// New lines are flashed while fewer than this many arrive a minute.
+    pub flash_new_lines: Option<usize>,
+    pub replay: bool,
+    pub replay_rate: usize,
+    /// Lines wrapped in JSON by container runtimes are unwrapped.
+    pub unwrap: bool,
+    /// Where the keys and lines received are recorded, for playing them back.
+    pub record: Option<String>,
+    /// Recorded lines are hashed, keeping their layout.
+    pub hash_recorded_lines: bool,
+    pub metrics_file: Option<String>,
+    /// Where flow logs what it does, for debugging it.
+    pub debug_log: Option<String>,
+    /// Where commands are taken from, if anywhere.
+    pub control_socket: Option<PathBuf>,
+    pub vim_keymap: bool,
+    pub actions: Vec<QuickAction>,
+    /// Formats whose records are folded into single lines, by file name.
+    pub sources: Vec<SourceFormat>,
+    pub highlights: Vec<String>,
+    pub color: ColorMode,
+    pub highlight_colors: Vec<ColorPair>,
+    pub theme: Vec<(i16, ColorPair)>,
+    /// How exported and saved lines are written.
+    pub export_format: ExportFormat,
+    pub buffer_lines: usize,
+    pub continuation_prefix: String,
+    /// Lines of each followed file kept when others fill the buffer.
+    pub min_lines_per_source: Option<usize>,
+    /// Runs of blank lines are shown as a single row.
+    pub fold_blank_lines: bool,
+    pub protected_lines: Protection,
+    pub profiles: Profiles,
+    /// Profile started with.
+    pub profile: String,
+    /// Buffer size given with `--buffer-lines`, kept when switching profiles.
+    pub buffer_lines_arg: Option<usize>,
 }
 
 impl Settings {
     pub fn from_args(args: Args) -> Settings {
-        let config = ConfigFile::from_path(args.get_config())
-            .unwrap_or(ConfigFile::from_current_dir()
-                .unwrap_or(ConfigFile::from_home_dir().unwrap_or_else(ConfigFile::default)));
+        let config = ConfigFile::locate(args.get_config())
+            .and_then(ConfigFile::from_path)
+            .unwrap_or_else(ConfigFile::default);
 
-        assert_quit!(!config.filters.is_empty(),
-                     "At least one filter needs to be defined.");
+        let profiles = config.profiles();
+        let profile_name = args.flag_profile.clone().unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+        let profile = profiles.resolve(&profile_name).unwrap_or_else(|message| {
+            critical_quit!(message);
+        });
+
+        let buffer_lines_arg = args.flag_buffer_lines;
+        let buffer_lines = buffer_lines_arg
+            .map(|value| value as i64)
+            .or(profile.buffer_lines)
+            .map_or(Ok(DEFAULT_BUFFER_ROWS), validate_buffer_rows)
+            .unwrap_or_else(|message| {
+                critical_quit!(message);
+            });
+
+        let path_to_target_file = args.get_target();
+        let fold_blank_lines = match config.fold_blank_lines.as_ref().map(String::as_str) {
+            Some("always") => true,
+            Some("never") => false,
+            _ => GlobPattern::is_glob(&path_to_target_file),
+        };
 
         Settings {
-            path_to_target_file: args.get_target(),
+            path_to_target_file: path_to_target_file,
             last_lines_count: args.flag_lines.unwrap_or(DEFAULT_LAST_LINES_SHOWN),
             max_lines_count: args.flag_max.unwrap_or(DEFAULT_MAX_LINES_STORED),
-            filters: config.filters,
+            filters: profile.filters.clone().unwrap_or_default(),
+            wheel_scroll_lines: config.wheel_scroll_lines.unwrap_or(DEFAULT_WHEEL_SCROLL_LINES),
+            key_repeat_acceleration: config.key_repeat_acceleration.unwrap_or(true),
+            sample_every: config.sample_every,
+            sample_target_rate: config.sample_target_rate.unwrap_or(DEFAULT_TARGET_RATE),
+            scrolloff: config.scrolloff.unwrap_or(0),
+            dim_lines_after: config.dim_lines_after,
+            flash_new_lines: config.flash_new_lines,
+            replay: args.flag_replay,
+            replay_rate: args.flag_replay_rate.unwrap_or(DEFAULT_REPLAY_RATE),
+            unwrap: !args.flag_no_unwrap,
+            record: args.flag_record.clone(),
+            hash_recorded_lines: args.flag_hash_lines,
+            metrics_file: args.flag_metrics_file.clone(),
+            debug_log: args.flag_debug_log
+                .clone()
+                .or_else(|| env::var(debug_log::ENV_VARIABLE).ok()),
+            control_socket: args.flag_control_socket
+                .as_ref()
+                .or_else(|| config.control_socket.as_ref())
+                .map(PathBuf::from),
+            vim_keymap: config.keymap.as_ref().map_or(false, |value| value == "vim"),
+            actions: config.actions.unwrap_or_default(),
+            sources: config.sources.unwrap_or_default(),
+            highlights: profile.highlights.clone().unwrap_or_default(),
+            color: args.get_color_mode(),
+            highlight_colors: highlight_colors(&profile),
+            theme: theme_pairs(&profile),
+            export_format: args.get_export_format(),
+            buffer_lines: buffer_lines,
+            continuation_prefix: config.continuation_prefix
+                .unwrap_or_else(|| DEFAULT_CONTINUATION_PREFIX.to_string()),
+            min_lines_per_source: config.min_lines_per_source,
+            fold_blank_lines: fold_blank_lines,
+            protected_lines: config.protected_lines
+                .as_ref()
+                .and_then(|value| Protection::parse(value))
+                .unwrap_or(Protection::Warn),
+            profiles: profiles,
+            profile: profile_name,
+            buffer_lines_arg: buffer_lines_arg,
         }
     }
 
@@ -56,3 +174,16 @@ impl Settings {
             .collect()
     }
 }
+
+/// Colors of the highlights, validated along with the config.
+pub fn highlight_colors(profile: &Profile) -> Vec<ColorPair> {
+    profile.highlight_colors
+        .iter()
+        .flat_map(|values| values.iter())
+        .map(|value| ColorPair::parse(value).unwrap())
+        .collect()
+}
+
+pub fn theme_pairs(profile: &Profile) -> Vec<(i16, ColorPair)> {
+    profile.theme.as_ref().map_or(vec![], |theme| theme.pairs().unwrap())
+}
@@ -0,0 +1,299 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::ops::Range;
+
+use unicode_width::UnicodeWidthStr;
+
+use utils::clusters::{self, clusters};
+
+static TAB_WIDTH: usize = 8;
+
+/// Characters a long URL or path would rather wrap after than at an arbitrary
+/// one.
+static SOFT_BREAKS: &'static str = "/?&.-\\";
+
+/// Printed at the start of every row a line wraps onto.
+pub static DEFAULT_CONTINUATION_PREFIX: &'static str = "↪ ";
+pub static MAX_CONTINUATION_WIDTH: usize = 8;
+
+pub fn validate_continuation_prefix(value: &str) -> Result<(), String> {
+    if value.chars().any(|c| c.is_control()) {
+        return Err("The continuation prefix can't contain control characters".to_string());
+    }
+    if value.width() > MAX_CONTINUATION_WIDTH {
+        return Err(format!("The continuation prefix can be at most {} columns wide, got `{}`",
+                           MAX_CONTINUATION_WIDTH,
+                           value));
+    }
+
+    Ok(())
+}
+
+/// Follows the column clusters of characters are printed at, telling where
+/// rows break. Rows after the first start at `indent`, past the continuation
+/// prefix.
+pub struct RowBreaks {
+    width: usize,
+    indent: usize,
+    column: usize,
+    row_start: usize,
+    full: bool,
+}
+
+impl RowBreaks {
+    pub fn new(width: usize, indent: usize) -> RowBreaks {
+        let width = if width == 0 { 1 } else { width };
+
+        RowBreaks {
+            width: width,
+            indent: indent.min(width - 1),
+            column: 0,
+            row_start: 0,
+            full: false,
+        }
+    }
+
+    /// Column the next row starts at.
+    pub fn indent(&self) -> usize {
+        self.indent
+    }
+
+    /// Moves past the cluster, returning whether it's printed on a new row.
+    pub fn advance(&mut self, cluster: &str) -> bool {
+        let mut breaks = self.full;
+        if breaks {
+            self.next_row();
+        }
+
+        // Wide clusters that don't fit are moved to the next row
+        let mut value_width = cluster_width(cluster, self.column, self.width);
+        if self.column + value_width > self.width && self.column > self.row_start {
+            breaks = true;
+            self.next_row();
+            value_width = cluster_width(cluster, self.column, self.width);
+        }
+
+        self.column += value_width;
+        self.full = self.column >= self.width;
+
+        breaks
+    }
+
+    /// Starts a new row, for the clusters that follow to be printed on it.
+    pub fn next_row(&mut self) {
+        self.column = self.indent;
+        self.row_start = self.indent;
+        self.full = false;
+    }
+}
+
+/// Splits text into the rows it takes up when printed within `width` columns,
+/// following the terminal's behaviour. Each row is returned as a byte range,
+/// and clusters of characters printed as a single glyph are never split.
+pub fn wrap(text: &str, width: usize) -> Vec<Range<usize>> {
+    wrap_indented(text, width, 0)
+}
+
+/// Same as `wrap`, with rows after the first starting at column `indent`.
+/// A row ending within a long URL or path wraps at a soft break instead,
+/// when there's one.
+pub fn wrap_indented(text: &str, width: usize, indent: usize) -> Vec<Range<usize>> {
+    let mut breaks = RowBreaks::new(width, indent);
+    let mut rows = vec![];
+    let mut start = 0;
+    let mut resume = 0;
+
+    'rows: loop {
+        for (offset, cluster) in clusters(&text[resume..]) {
+            let offset = resume + offset;
+            if !breaks.advance(cluster) {
+                continue;
+            }
+
+            let start_column = if rows.is_empty() { 0 } else { breaks.indent() };
+            let row = start..offset;
+            start = soft_break(text, &row, breaks.width, start_column).unwrap_or(offset);
+            rows.push(row.start..start);
+
+            // Clusters past the soft break are moved to the next row
+            if start < offset {
+                breaks.next_row();
+                resume = start;
+                continue 'rows;
+            }
+        }
+        break;
+    }
+    rows.push(start..text.len());
+
+    rows
+}
+
+/// Where a row ending within a word had better end, right after the last
+/// soft break of the word, as long as the row stays at least half full. Long
+/// words without any are still broken at the last character that fits.
+pub fn soft_break(text: &str,
+                  row: &Range<usize>,
+                  width: usize,
+                  start_column: usize)
+                  -> Option<usize> {
+    let next = text[row.end..].chars().next();
+    let last = text[..row.end].chars().next_back();
+    let within_word = next.map_or(false, |c| !c.is_whitespace()) &&
+                      last.map_or(false, |c| !c.is_whitespace() && !SOFT_BREAKS.contains(c));
+    if !within_word {
+        return None;
+    }
+
+    let position = text[row.clone()]
+        .char_indices()
+        .rev()
+        .skip(1)
+        .take_while(|&(_, c)| !c.is_whitespace())
+        .find(|&(_, c)| SOFT_BREAKS.contains(c))
+        .map(|(index, c)| row.start + index + c.len_utf8());
+
+    position.filter(|&position| column_at(text, row, position, width, start_column) * 2 >= width)
+}
+
+/// Column where the cluster at `offset` is printed, `row` being one of the
+/// ranges returned by `wrap` and `start_column` the column it starts at.
+pub fn column_at(text: &str,
+                 row: &Range<usize>,
+                 offset: usize,
+                 width: usize,
+                 start_column: usize)
+                 -> usize {
+    let width = if width == 0 { 1 } else { width };
+
+    clusters(&text[row.start..offset])
+        .fold(start_column, |column, (_, cluster)| column + cluster_width(cluster, column, width))
+}
+
+/// Same as `column_at` for several increasing offsets within the row, going
+/// over it once.
+pub fn columns_at(text: &str,
+                  row: &Range<usize>,
+                  offsets: &[usize],
+                  width: usize,
+                  start_column: usize)
+                  -> Vec<usize> {
+    let width = if width == 0 { 1 } else { width };
+    let mut columns = Vec::with_capacity(offsets.len());
+    let mut column = start_column;
+
+    for (offset, cluster) in clusters(&text[row.clone()]) {
+        while columns.len() < offsets.len() && offsets[columns.len()] <= row.start + offset {
+            columns.push(column);
+        }
+        if columns.len() == offsets.len() {
+            break;
+        }
+        column += cluster_width(cluster, column, width);
+    }
+    while columns.len() < offsets.len() {
+        columns.push(column);
+    }
+
+    columns
+}
+
+// Tabs advance to the next stop, without going past the right margin
+fn cluster_width(cluster: &str, column: usize, width: usize) -> usize {
+    match cluster {
+        "\t" => (TAB_WIDTH - column % TAB_WIDTH).min(width - column),
+        "\0" => 1,
+        _ => clusters::width(cluster),
+    }
+}
+
+/// Index of the character printed at `column` on the given row of the wrapped
+/// text. Columns taken up by a wide character or tab all point to it.
+pub fn char_index_at(text: &str, width: usize, row: usize, column: usize) -> Option<usize> {
+    char_index_at_indented(text, width, 0, row, column)
+}
+
+/// Same as `char_index_at`, with rows after the first starting at `indent`.
+/// Columns of the continuation prefix point to no character.
+pub fn char_index_at_indented(text: &str,
+                              width: usize,
+                              indent: usize,
+                              row: usize,
+                              column: usize)
+                              -> Option<usize> {
+    glyph_at_indented(text, width, indent, row, column).map(|(index, _)| index)
+}
+
+/// Index of the character printed at `column` on the given row, along with
+/// every column it takes up, so that wide characters are handled whole.
+/// Within a cluster, the index is that of its first character.
+pub fn glyph_at_indented(text: &str,
+                         width: usize,
+                         indent: usize,
+                         row: usize,
+                         column: usize)
+                         -> Option<(usize, Range<usize>)> {
+    let rows = wrap_indented(text, width, indent);
+    let range = match rows.get(row) {
+        Some(value) => value,
+        None => return None,
+    };
+
+    let width = if width == 0 { 1 } else { width };
+    let mut current = if row > 0 { RowBreaks::new(width, indent).indent() } else { 0 };
+    if column < current {
+        return None;
+    }
+
+    for (offset, cluster) in clusters(&text[range.clone()]) {
+        let value_width = cluster_width(cluster, current, width);
+
+        if value_width > 0 && column < current + value_width {
+            let index = text[..range.start + offset].chars().count();
+            return Some((index, current..current + value_width));
+        }
+
+        current += value_width;
+    }
+
+    None
+}
+
+/// Character printed at `column` when the text isn't wrapped, the first
+/// one of its cluster.
+pub fn char_at_column(text: &str, column: usize) -> Option<char> {
+    let mut current = 0;
+
+    for (_, cluster) in clusters(text) {
+        current += cluster_width(cluster, current, usize::max_value());
+        if column < current {
+            return cluster.chars().next();
+        }
+    }
+
+    None
+}
+
+/// Column where the character at `offset` is printed when the text isn't
+/// wrapped, which is what fixed width formats are laid out against.
+pub fn line_column(text: &str, offset: usize) -> usize {
+    clusters(&text[..offset]).fold(0, |column, (_, cluster)| {
+        column + cluster_width(cluster, column, usize::max_value())
+    })
+}
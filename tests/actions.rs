@@ -0,0 +1,71 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::actions::{available, shell_quote};
+use flow::utils::config_file::ConfigFile;
+
+fn config(actions: &str) -> ConfigFile {
+    ConfigFile::parse(&format!("[[filters]]\nname = \"All\"\n\n{}", actions)).unwrap()
+}
+
+#[test]
+fn quotes_values_for_the_shell() {
+    assert_eq!("'abc'", shell_quote("abc"));
+    assert_eq!("'it'\\''s; rm -rf ~'", shell_quote("it's; rm -rf ~"));
+}
+
+#[test]
+fn substitutes_captures_into_the_command() {
+    let config = config("[[actions]]\nname = \"Open trace\"\npattern = 'trace_id=(\\w+) span=(?P<span>\\w+)'\n\
+                         command = 'xdg-open https://jaeger/trace/$1?span=${span}&cost=$$5 # $3'\n");
+    let actions = config.actions.unwrap();
+
+    let offered = available(&actions, "GET / trace_id=ab12 span=f00");
+    assert_eq!(1, offered.len());
+    assert_eq!("xdg-open https://jaeger/trace/'ab12'?span='f00'&cost=$5 # ''", offered[0].1);
+    assert!(!offered[0].0.interactive);
+
+    assert!(available(&actions, "GET / no trace").is_empty());
+}
+
+#[test]
+fn offers_every_matching_action() {
+    let config = config("[[actions]]\nname = \"Trace\"\npattern = 'trace_id=(\\w+)'\ncommand = 'echo $1'\n\n\
+                         [[actions]]\nname = \"User\"\npattern = 'user=(\\w+)'\ncommand = 'less $0'\n\
+                         interactive = true\n");
+    let actions = config.actions.unwrap();
+
+    let names = available(&actions, "trace_id=1 user=bob")
+        .iter()
+        .map(|&(action, _)| action.name.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["Trace", "User"], names);
+    assert!(actions[1].interactive);
+}
+
+#[test]
+fn reports_invalid_action_patterns() {
+    let error = ConfigFile::parse("[[filters]]\nname = \"All\"\n\n[[actions]]\nname = \"x\"\n\
+                                   pattern = \"(\"\ncommand = \"true\"\n")
+        .err()
+        .unwrap();
+
+    assert!(error.contains("invalid action `pattern` regex"), "{}", error);
+}
@@ -0,0 +1,140 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate toml;
+extern crate flow;
+
+use flow::core::alert::{self, Firing, JumpGuard, JUMP_INTERVAL};
+use flow::core::buffer::Buffer;
+use flow::core::filter::Filter;
+use flow::core::line::LineCollection;
+
+fn filter(config: &str) -> Filter {
+    let contents = toml::Parser::new(config).parse().unwrap();
+    toml::decode(toml::Value::Table(contents)).unwrap()
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[test]
+fn decodes_alerts_from_filter() {
+    let filter = filter(r##"
+        name = "Errors"
+
+        [[alert]]
+        pattern = "panic"
+        jump = true
+
+        [[alert]]
+        pattern = "timeout"
+    "##);
+
+    assert_eq!(filter.alerts.len(), 2);
+    assert!(filter.alerts[0].jump);
+    assert!(!filter.alerts[1].jump);
+    assert!(filter.alerts[1].regex.is_match("read timeout"));
+}
+
+#[test]
+fn reports_only_lines_received_after_the_given_id() {
+    let buffer = Buffer::new(filter(r##"
+        name = "All"
+
+        [[alert]]
+        pattern = "panic"
+    "##));
+    let mut lines = LineCollection::new(10);
+
+    lines.extend(strings(&["panic: old", "fine"]));
+    let received = lines.received();
+    lines.extend(strings(&["panic: new", "fine"]));
+
+    let firings = alert::fired(&buffer, &lines, received);
+    assert_eq!(firings,
+               vec![Firing {
+                        alert: 0,
+                        line_id: 3,
+                    }]);
+}
+
+#[test]
+fn checks_completed_lines_without_the_ones_after_them() {
+    let buffer = Buffer::new(filter(r##"
+        name = "All"
+
+        [[alert]]
+        pattern = "panic"
+    "##));
+    let mut lines = LineCollection::new(10);
+
+    lines.extend(strings(&["panic: completed", "panic: checked", "fine"]));
+    let received = lines.received();
+    lines.extend(strings(&["panic: new"]));
+
+    let ids: Vec<usize> = alert::fired_including(&buffer, &lines, received, &[1])
+        .iter()
+        .map(|f| f.line_id)
+        .collect();
+    assert_eq!(ids, vec![4, 1]);
+}
+
+#[test]
+fn ignores_lines_filtered_out_of_the_tab() {
+    let buffer = Buffer::new(filter(r##"
+        name = "Workers"
+        contains = "worker"
+
+        [[alert]]
+        pattern = "panic"
+    "##));
+    let mut lines = LineCollection::new(10);
+
+    lines.extend(strings(&["server panic", "worker panic"]));
+
+    let ids: Vec<usize> = alert::fired(&buffer, &lines, 0).iter().map(|f| f.line_id).collect();
+    assert_eq!(ids, vec![2]);
+}
+
+#[test]
+fn jumps_are_rate_limited() {
+    let mut guard = JumpGuard::default();
+    assert!(guard.may_jump(1_000));
+
+    guard.jumped(1_000);
+    assert!(!guard.may_jump(1_000 + JUMP_INTERVAL - 1));
+    assert!(guard.may_jump(1_000 + JUMP_INTERVAL));
+}
+
+#[test]
+fn snooze_stops_jumps_until_it_runs_out_or_is_cancelled() {
+    let mut guard = JumpGuard::default();
+
+    guard.snooze(1_000, 2);
+    assert!(!guard.may_jump(1_000));
+    assert_eq!(guard.snoozed_until(1_000), Some(121_000));
+    assert!(!guard.expire(120_999));
+    assert!(guard.expire(121_000));
+    assert!(guard.may_jump(121_000));
+
+    guard.snooze(200_000, 2);
+    guard.resume();
+    assert!(guard.may_jump(200_000));
+    assert!(!guard.expire(400_000));
+}
@@ -0,0 +1,118 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use flow::core::buffer::Buffer;
+use flow::core::filter::Filter;
+use flow::core::line::LineCollection;
+use flow::core::tail::Tail;
+
+// Counts allocations made anywhere in the process. There's a single test in
+// this file, so that no other test allocates while it measures.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+const LINES: usize = 2_000;
+
+fn allocations<F: FnOnce()>(callback: F) -> f64 {
+    let before = ALLOCATIONS.load(Ordering::SeqCst);
+    callback();
+
+    (ALLOCATIONS.load(Ordering::SeqCst) - before) as f64 / LINES as f64
+}
+
+fn filter() -> Filter {
+    Filter {
+        name: "All".to_string(),
+        content: None,
+        start: None,
+        end: None,
+        rewrites: vec![],
+        line_rules: vec![],
+        alerts: vec![],
+    }
+}
+
+#[test]
+fn allocations_per_line() {
+    let mut lines = LineCollection::new(LINES);
+    lines.highlights.toggle("ERROR").unwrap();
+    let buffer = Buffer::new(filter());
+
+    let path = env::temp_dir().join("flow-allocations.log");
+    let mut file = File::create(&path).unwrap();
+    for i in 0..LINES {
+        let level = if i % 10 == 0 { "ERROR" } else { "INFO" };
+        writeln!(file,
+                 "2016-03-01 14:30:{:02} {} request {} served in {}ms",
+                 i % 60,
+                 level,
+                 i,
+                 i % 250)
+            .unwrap();
+    }
+    let mut tail = Tail::new(path.to_string_lossy().into_owned());
+
+    let mut received = vec![];
+    let read = allocations(|| received = tail.read_to_end());
+    assert_eq!(received.len(), LINES);
+
+    // Handed over the way the reader does
+    let ingested = allocations(|| lines.extend(received.into_iter().map(|text| (0, text))));
+
+    // What printing does with every stored line on each redraw
+    let displayed = allocations(|| {
+        let buffer_lines = buffer.with_lines(&lines);
+        for line in buffer_lines.all() {
+            assert!(line.id > 0);
+        }
+    });
+
+    // The text is allocated once as it's read, and not again as it's stored
+    // or displayed
+    assert!(read < 1.1, "{:.2} allocations per read line", read);
+    assert!(ingested < 0.5, "{:.2} allocations per ingested line", ingested);
+    assert!(displayed < 0.1, "{:.2} allocations per displayed line", displayed);
+}
@@ -18,7 +18,7 @@
 
 extern crate flow;
 
-use flow::utils::ansi_decoder::AnsiStr;
+use flow::utils::ansi_decoder::{AnsiStr, Component, Style};
 
 #[test]
 fn detects_ansi_escape_sequence() {
@@ -63,3 +63,25 @@ fn breaks_text_into_ncurses_components() {
     let components = text_with_unknown_ansi.to_components();
     assert_eq!(7, components.items.len());
 }
+
+#[test]
+fn takes_extended_colors_as_a_whole() {
+    let is_style = |component: &Component| match *component {
+        Component::Style(_) => true,
+        Component::Content(_) => false,
+    };
+
+    // Neither `1` turning on bold nor `2` dimming
+    for text in &["\x1b[38;5;1mred", "\x1b[48;2;1;2;255mred", "\x1b[38;5;2;48;5;1mred"] {
+        let components = text.to_components();
+        assert_eq!(1, components.items.len());
+        assert!(!is_style(&components.items[0]));
+    }
+
+    let components = "\x1b[38;2;9;9;9;1mbold".to_components();
+    assert_eq!(2, components.items.len());
+    match components.items[0] {
+        Component::Style(&Style::Attribute(1, _, true)) => {}
+        _ => panic!("expected bold"),
+    }
+}
@@ -0,0 +1,96 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::line::{Line, LineCollection};
+use flow::utils::ansi_decoder::{AnsiStr, escape_control, sanitize};
+
+#[test]
+fn strips_hostile_sequences() {
+    // (input, displayed)
+    let corpus = [("\x1b[2Jcleared", "cleared"),
+                  ("\x1b[Hhome", "home"),
+                  ("\x1b[10;20Hmoved", "moved"),
+                  ("\x1b[3Aup \x1b[2Bdown \x1b[5Cright \x1b[1Dleft", "up down right left"),
+                  ("\x1b[?1049halternate\x1b[?1049l", "alternate"),
+                  ("\x1b[?47hold alternate\x1b[?47l", "old alternate"),
+                  ("\x1b[?25lhidden cursor\x1b[?25h", "hidden cursor"),
+                  ("\x1b[Kerase line", "erase line"),
+                  ("\x1b[1;24rscroll region", "scroll region"),
+                  ("\x1b[s\x1b[usaved", "saved"),
+                  ("\x1b]0;window title\x07text", "text"),
+                  ("\x1b]2;title\x1b\\text", "text"),
+                  ("\x1b]8;;http://example.com\x1b\\link\x1b]8;;\x1b\\", "link"),
+                  ("\x1bPq#0;2;0;0;0\x1b\\sixel", "sixel"),
+                  ("\x1b_application\x1b\\apc", "apc"),
+                  ("\x1bcreset", "reset"),
+                  ("\x1b7\x1b8saved cursor", "saved cursor"),
+                  ("\x1b(0line drawing\x1b(B", "line drawing"),
+                  ("\x1b=keypad\x1b>", "keypad"),
+                  ("\u{9b}2Jeight bit", "eight bit"),
+                  ("bell\x07", "bell"),
+                  ("trailing\x1b", "trailing"),
+                  ("\x1b[unterminated", "nterminated"),
+                  ("\x1b]0;never terminated", "")];
+
+    for &(input, expected) in &corpus {
+        let (result, stripped) = sanitize(input);
+        assert_eq!(expected, result, "{:?}", input);
+        assert!(stripped, "{:?}", input);
+    }
+}
+
+#[test]
+fn preserves_colors_and_attributes() {
+    for input in &["\x1b[1m\x1b[31mred\x1b[0m", "\x1b[1;31mred\x1b[m", "plain", "tab\tseparated"] {
+        assert_eq!((input.to_string(), false), sanitize(input));
+    }
+
+    let (result, stripped) = sanitize("\x1b[2J\x1b[32mgreen\x1b[0m\x1b[H");
+    assert_eq!("\x1b[32mgreen\x1b[0m", result);
+    assert!(stripped);
+}
+
+#[test]
+fn combined_color_codes_are_decoded() {
+    let value = "\x1b[1;31mred\x1b[m";
+
+    assert_eq!("red", value.strip_ansi());
+    // bold, red, text and reset
+    assert_eq!(4, value.to_components().items.len());
+}
+
+#[test]
+fn keeps_the_raw_content_of_sanitized_lines() {
+    let line = Line::new("\x1b[2J\x1b[31mboom\x1b[0m".to_string());
+    assert_eq!("boom", &*line.content_without_ansi);
+    assert_eq!(Some("\x1b[2J\x1b[31mboom\x1b[0m"), line.raw.as_ref().map(|raw| &**raw));
+
+    let line = Line::new("\x1b[31mcolored\x1b[0m".to_string());
+    assert!(line.raw.is_none());
+
+    let mut lines = LineCollection::new(10);
+    lines.extend(vec!["fine".to_string(), "\x1b[Hmoved".to_string()]);
+    assert_eq!(1, lines.sanitized);
+}
+
+#[test]
+fn escapes_control_characters_for_inspection() {
+    assert_eq!("\\x1b[2Jtext\\x07 \\t \\\\", escape_control("\x1b[2Jtext\x07 \t \\"));
+}
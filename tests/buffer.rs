@@ -0,0 +1,254 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+extern crate time;
+
+use std::sync::Arc;
+
+use flow::core::container::unwrap;
+use flow::core::filter::{Filter, LineRule};
+use flow::core::line::{Line, LineCollection, SearchPattern};
+use flow::core::query::Syntax;
+use flow::core::rewrite::RewriteRule;
+use flow::core::buffer::{Buffer, BufferCollection};
+use flow::ui::viewport::Anchor;
+
+fn filter() -> Filter {
+    Filter {
+        name: "All".to_string(),
+        content: None,
+        start: None,
+        end: None,
+        rewrites: vec![],
+        line_rules: vec![],
+        alerts: vec![],
+    }
+}
+
+fn contents(buffer: &Buffer, lines: &LineCollection) -> Vec<String> {
+    let mut buffer_lines = buffer.with_lines(lines);
+    buffer_lines.width = 80;
+
+    (&buffer_lines).into_iter().map(|line| line.content_without_ansi.to_string()).collect()
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[test]
+fn clear_hides_previous_lines_behind_a_marker() {
+    let mut lines = LineCollection::new(10);
+    let mut buffer = Buffer::new(filter());
+
+    lines.extend(strings(&["one", "two"]));
+    buffer.clear(&lines);
+    lines.extend(strings(&["three"]));
+
+    let result = contents(&buffer, &lines);
+
+    assert_eq!(result.len(), 2);
+    assert!(result[0].starts_with("--- buffer cleared at "));
+    assert_eq!(result[1], "three");
+}
+
+#[test]
+fn clear_keeps_working_after_eviction() {
+    let mut lines = LineCollection::new(2);
+    let mut buffer = Buffer::new(filter());
+
+    lines.extend(strings(&["one", "two"]));
+    buffer.clear(&lines);
+    lines.extend(strings(&["three", "four", "five"]));
+
+    let result = contents(&buffer, &lines);
+
+    assert_eq!(&result[1..], &["four".to_string(), "five".to_string()]);
+}
+
+#[test]
+fn entries_before_accounts_for_evicted_lines() {
+    let mut lines = LineCollection::new(3);
+
+    lines.extend(strings(&["a", "b", "c", "d", "e"]));
+
+    assert_eq!(lines.received(), 5);
+    assert_eq!(lines.entries_before(1), 0);
+    assert_eq!(lines.entries_before(4), 2);
+    assert_eq!(lines.entries_before(9), 3);
+}
+
+#[test]
+fn tabs_keep_their_own_state() {
+    let mut buffers = BufferCollection::from_filters(vec![filter(), filter()]);
+
+    buffers.selected_item().set_reverse_index(12);
+    buffers.selected_item().update_state(|state| state.anchor = Anchor::Top);
+    buffers.select_next();
+
+    assert_eq!(0, buffers.selected_item().reverse_index());
+    assert_eq!(Anchor::Bottom, buffers.selected_item().state.get().anchor);
+
+    buffers.select_previous();
+
+    assert_eq!(12, buffers.selected_item().reverse_index());
+    assert_eq!(Anchor::Top, buffers.selected_item().state.get().anchor);
+}
+
+#[test]
+fn inactive_items_exclude_the_selected_tab() {
+    let mut buffers = BufferCollection::from_filters(vec![filter(), filter(), filter()]);
+    buffers.select_next();
+
+    for buffer in buffers.inactive_items() {
+        buffer.update_state(|state| state.needs_rewrap = true);
+    }
+
+    assert!(!buffers.selected_item().state.get().needs_rewrap);
+    assert_eq!(2, buffers.iter().filter(|buffer| buffer.state.get().needs_rewrap).count());
+}
+
+#[test]
+fn frozen_tabs_keep_their_lines_through_ingestion_and_eviction() {
+    let mut lines = LineCollection::new(3);
+    let mut buffer = Buffer::new(filter());
+    buffer.add_rewrite(RewriteRule::parse("/two/2/").unwrap());
+
+    lines.extend(strings(&["one", "two", "three"]));
+    let frozen = buffer.freeze("All @ 10:00:00".to_string(), &lines);
+    lines.extend(strings(&["four", "five"]));
+
+    assert_eq!(contents(&frozen, &lines), strings(&["one", "two", "three"]));
+    assert_eq!(contents(&buffer, &lines), strings(&["three", "four", "five"]));
+    assert_eq!("All @ 10:00:00", frozen.filter.name);
+    assert_eq!(1, frozen.filter.rewrites.len());
+    assert!(frozen.filter.alerts.is_empty());
+}
+
+#[test]
+fn frozen_tabs_share_the_text_of_lines() {
+    let mut lines = LineCollection::new(10);
+    lines.extend(strings(&["one", "\x1b[2Jtwo"]));
+
+    let frozen = Buffer::new(filter()).freeze("Copy".to_string(), &lines);
+    let copies = &frozen.frozen.as_ref().unwrap().entries;
+
+    assert!(Arc::ptr_eq(&copies[0].content_without_ansi, &lines.entries[0].content_without_ansi));
+    assert!(Arc::ptr_eq(copies[1].raw.as_ref().unwrap(), lines.entries[1].raw.as_ref().unwrap()));
+}
+
+#[test]
+fn removing_a_tab_selects_the_one_before() {
+    let mut buffers = BufferCollection::from_filters(vec![filter(), filter()]);
+    buffers.select_last();
+    buffers.remove_selected();
+
+    assert_eq!(0, buffers.selected());
+    assert_eq!(1, buffers.iter().count());
+
+    buffers.remove_selected();
+    assert_eq!(1, buffers.iter().count());
+}
+
+#[test]
+fn rewritten_lines_keep_their_arrival_time() {
+    let mut buffer = Buffer::new(filter());
+    buffer.add_rewrite(RewriteRule::parse("/a/b/").unwrap());
+
+    let mut line = Line::with_id("a".to_string(), 3);
+    line.received_at = time::Timespec::new(42, 0);
+
+    let displayed = buffer.display_line(&line);
+    assert_eq!(&*displayed.content_without_ansi, "b");
+    assert_eq!(displayed.received_at, line.received_at);
+}
+
+#[test]
+fn raw_lines_skip_rewrites() {
+    let mut buffer = Buffer::new(filter());
+    buffer.add_rewrite(RewriteRule::parse("/a/b/").unwrap());
+    buffer.show_raw = true;
+
+    let line = Line::with_id("\x1b[31ma\x1b[0m".to_string(), 3);
+    let displayed = buffer.display_line(&line);
+
+    assert_eq!(&*displayed.content_without_ansi, "a");
+    assert!(displayed.components.is_none());
+    assert_eq!(3, displayed.id);
+}
+
+#[test]
+fn raw_lines_show_what_was_received() {
+    let mut buffer = Buffer::new(filter());
+    buffer.show_raw = true;
+
+    let json = r#"{"log":"GET / 200\n","stream":"stderr","time":"2016-03-01T14:32:05Z"}"#;
+    let (log, wrapper) = unwrap(json).unwrap();
+    let mut wrapped = Line::with_id(log, 1);
+    wrapped.wrapper = Some(Arc::new(wrapper));
+
+    let displayed = buffer.display_line(&wrapped);
+    assert_eq!(&*displayed.content_without_ansi, json);
+    assert!(!displayed.is_stderr());
+
+    let cleared = Line::with_id("\x1b[2Jready".to_string(), 2);
+    assert_eq!(&*buffer.display_line(&cleared).content_without_ansi, "\\x1b[2Jready");
+}
+
+fn rule(text: &str, exclude: bool) -> LineRule {
+    let syntax = Syntax { regex: true, ..Syntax::default() };
+    let pattern = SearchPattern::new(text, false).with_syntax(syntax);
+    LineRule::from_search(text, pattern, exclude)
+}
+
+#[test]
+fn line_rules_keep_only_the_lines_matching_a_search() {
+    let mut lines = LineCollection::new(10);
+    let mut buffer = Buffer::new(filter());
+    buffer.filter.line_rules.push(rule("/GET|POST/", false));
+
+    lines.extend(strings(&["GET /", "debug", "POST /login", "PUT /"]));
+
+    assert_eq!(contents(&buffer, &lines), strings(&["GET /", "POST /login"]));
+}
+
+#[test]
+fn line_rules_hide_the_lines_matching_an_excluding_search() {
+    let mut lines = LineCollection::new(10);
+    let mut buffer = Buffer::new(filter());
+    buffer.filter.line_rules.push(rule("/GET|POST/", false));
+    buffer.filter.line_rules.push(rule("login", true));
+
+    lines.extend(strings(&["GET /", "debug", "POST /login", "PUT /"]));
+
+    assert_eq!(contents(&buffer, &lines), strings(&["GET /"]));
+}
+
+#[test]
+fn disabled_line_rules_let_every_line_through() {
+    let mut lines = LineCollection::new(10);
+    let mut buffer = Buffer::new(filter());
+    let mut disabled = rule("debug", true);
+    disabled.enabled = false;
+    buffer.filter.line_rules.push(disabled);
+
+    lines.extend(strings(&["GET /", "debug"]));
+
+    assert_eq!(contents(&buffer, &lines), strings(&["GET /", "debug"]));
+}
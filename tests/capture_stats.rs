@@ -0,0 +1,93 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::capture_stats::{CaptureStats, format_number};
+use flow::core::line::SearchPattern;
+use flow::core::query::Syntax;
+
+const REGEX: Syntax = Syntax { regex: true, expressions: false };
+
+#[test]
+fn counts_captures_that_arent_numbers() {
+    let stats = CaptureStats::new(vec!["12", "7.5", "n/a", " 3 ", ""]);
+
+    assert_eq!(3, stats.count());
+    assert_eq!(2, stats.failures);
+    assert_eq!(Some(3.0), stats.min());
+    assert_eq!(Some(12.0), stats.max());
+    assert_eq!(Some(7.5), stats.mean());
+}
+
+#[test]
+fn picks_nearest_rank_percentiles() {
+    let values = (1..101).map(|value| value.to_string()).collect::<Vec<_>>();
+    let stats = CaptureStats::new(values.iter().map(String::as_str));
+
+    assert_eq!(Some(50.0), stats.percentile(50.0));
+    assert_eq!(Some(95.0), stats.percentile(95.0));
+    assert_eq!(Some(99.0), stats.percentile(99.0));
+    assert_eq!(Some(1.0), stats.percentile(0.0));
+    assert_eq!(None, CaptureStats::new(vec![]).percentile(50.0));
+}
+
+#[test]
+fn spreads_values_over_equal_buckets() {
+    let stats = CaptureStats::new(vec!["0", "1", "2", "9", "10"]);
+
+    assert_eq!(vec![(0.0, 3), (5.0, 2)], stats.histogram(2));
+    assert_eq!(vec![(4.0, 2), (4.0, 0)], CaptureStats::new(vec!["4", "4"]).histogram(2));
+    assert!(CaptureStats::new(vec![]).histogram(2).is_empty());
+}
+
+#[test]
+fn describes_stats_with_a_histogram() {
+    let rows = CaptureStats::new(vec!["10", "20", "x"]).describe(2, 4);
+
+    assert_eq!(vec!["count 2",
+                    "not numbers 1",
+                    "min 10  max 20  mean 15",
+                    "p50 10  p95 20  p99 20",
+                    "      10 ████ 1",
+                    "      15 ████ 1"],
+               rows);
+}
+
+#[test]
+fn formats_whole_numbers_without_decimals() {
+    assert_eq!("42", format_number(42.0));
+    assert_eq!("0.33", format_number(1.0 / 3.0));
+}
+
+#[test]
+fn extracts_the_first_captured_group() {
+    let pattern = SearchPattern::new(r"/duration=(\d+)ms/", false).with_syntax(REGEX);
+
+    assert!(pattern.has_captures());
+    assert_eq!(vec!["12", "7"], pattern.captures("duration=12ms retry duration=7ms"));
+    assert!(!SearchPattern::new(r"/\d+ms/", false).with_syntax(REGEX).has_captures());
+    assert!(!SearchPattern::new("(12)", false).has_captures());
+}
+
+#[test]
+fn extracts_captures_within_the_searched_field() {
+    let pattern = SearchPattern::new(r"took:/(\d+)/", false).with_syntax(REGEX);
+
+    assert_eq!(vec!["40"], pattern.captures("id=12 took=40"));
+}
@@ -0,0 +1,102 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::ui::chords::{Chords, Chord, ChordResult, Placement, CHORD_TIMEOUT, MAX_COUNT};
+use flow::ui::input::{Input, Key};
+
+fn feed(chords: &mut Chords, keys: &str, now: u64) -> Vec<ChordResult> {
+    keys.chars().map(|value| chords.feed(&Input::Kb(Key::Char(value), None), now)).collect()
+}
+
+fn last(chords: &mut Chords, keys: &str) -> ChordResult {
+    feed(chords, keys, 0).pop().unwrap()
+}
+
+#[test]
+fn completes_sequences() {
+    let mut chords = Chords::default();
+
+    assert_eq!(feed(&mut chords, "gg", 0),
+               vec![ChordResult::Pending("g".to_string()), ChordResult::Complete(Chord::Top)]);
+    assert_eq!(last(&mut chords, "G"), ChordResult::Complete(Chord::Bottom));
+    assert_eq!(last(&mut chords, "zt"), ChordResult::Complete(Chord::Reposition(Placement::Top)));
+    assert_eq!(last(&mut chords, "zz"), ChordResult::Complete(Chord::Reposition(Placement::Center)));
+    assert_eq!(last(&mut chords, "zb"), ChordResult::Complete(Chord::Reposition(Placement::Bottom)));
+}
+
+#[test]
+fn applies_counts() {
+    let mut chords = Chords::default();
+
+    assert_eq!(feed(&mut chords, "15j", 0),
+               vec![ChordResult::Pending("1".to_string()),
+                    ChordResult::Pending("15".to_string()),
+                    ChordResult::Complete(Chord::Lines(-15))]);
+    assert_eq!(last(&mut chords, "k"), ChordResult::Complete(Chord::Lines(1)));
+    assert_eq!(last(&mut chords, "120G"), ChordResult::Complete(Chord::GotoLine(120)));
+    assert_eq!(last(&mut chords, "7gg"), ChordResult::Complete(Chord::GotoLine(7)));
+}
+
+#[test]
+fn caps_large_counts() {
+    let mut chords = Chords::default();
+    let lines = MAX_COUNT as i32;
+
+    assert_eq!(last(&mut chords, "4294967296j"), ChordResult::Complete(Chord::Lines(-lines)));
+    assert_eq!(last(&mut chords, "99999999999999999999999k"),
+               ChordResult::Complete(Chord::Lines(lines)));
+    assert_eq!(last(&mut chords, "99999999999999999999999G"),
+               ChordResult::Complete(Chord::GotoLine(MAX_COUNT)));
+}
+
+#[test]
+fn leaves_other_keys_alone() {
+    let mut chords = Chords::default();
+
+    assert_eq!(last(&mut chords, "q"), ChordResult::Unhandled);
+    assert_eq!(last(&mut chords, "0"), ChordResult::Unhandled);
+    assert_eq!(chords.feed(&Input::Kb(Key::Up, None), 0), ChordResult::Unhandled);
+    assert_eq!(chords.feed(&Input::None, 0), ChordResult::Unhandled);
+}
+
+#[test]
+fn cancels_pending_keys() {
+    let mut chords = Chords::default();
+
+    assert_eq!(last(&mut chords, "gx"), ChordResult::Cancelled);
+    assert_eq!(last(&mut chords, "j"), ChordResult::Complete(Chord::Lines(-1)));
+
+    last(&mut chords, "3z");
+    assert_eq!(chords.feed(&Input::Kb(Key::Escape, None), 0), ChordResult::Cancelled);
+    assert_eq!(last(&mut chords, "t"), ChordResult::Unhandled);
+}
+
+#[test]
+fn expires_pending_keys() {
+    let mut chords = Chords::default();
+
+    feed(&mut chords, "g", 0);
+    assert_eq!(chords.feed(&Input::None, CHORD_TIMEOUT), ChordResult::Unhandled);
+    assert_eq!(chords.feed(&Input::None, CHORD_TIMEOUT + 1), ChordResult::Cancelled);
+
+    feed(&mut chords, "2", 0);
+    assert_eq!(feed(&mut chords, "j", CHORD_TIMEOUT + 1),
+               vec![ChordResult::Complete(Chord::Lines(-1))]);
+}
@@ -0,0 +1,61 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::chunked::ChunkedPass;
+
+#[test]
+fn goes_through_the_items_a_chunk_at_a_time() {
+    let mut pass = ChunkedPass::new(1200, 500);
+
+    assert_eq!(0, pass.percent());
+    assert_eq!(Some(0..500), pass.next_chunk());
+    assert_eq!(41, pass.percent());
+    assert_eq!(Some(500..1000), pass.next_chunk());
+    assert!(!pass.is_finished());
+    assert_eq!(Some(1000..1200), pass.next_chunk());
+    assert!(pass.is_finished());
+    assert_eq!(100, pass.percent());
+    assert_eq!(None, pass.next_chunk());
+}
+
+#[test]
+fn follows_items_added_or_dropped_midway() {
+    let mut pass = ChunkedPass::new(1000, 500);
+    pass.next_chunk();
+
+    pass.set_total(1100);
+    assert_eq!(Some(500..1000), pass.next_chunk());
+    assert_eq!(Some(1000..1100), pass.next_chunk());
+
+    pass.set_total(300);
+    assert!(pass.is_finished());
+    assert_eq!(None, pass.next_chunk());
+}
+
+#[test]
+fn only_large_runs_are_chunked() {
+    assert!(!ChunkedPass::is_needed(500, 500));
+    assert!(ChunkedPass::is_needed(501, 500));
+
+    let mut pass = ChunkedPass::new(0, 500);
+    assert!(pass.is_finished());
+    assert_eq!(None, pass.next_chunk());
+    assert_eq!(100, pass.percent());
+}
@@ -0,0 +1,108 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::ui::highlighter::match_segments;
+use flow::utils::clusters::{clusters, snap, text_width, width};
+use flow::utils::wrap::{char_index_at, line_column, wrap};
+
+static FAMILY: &'static str = "👨‍👩‍👧‍👦";
+static THUMBS_UP: &'static str = "👍🏽";
+static HEART: &'static str = "❤️";
+static FLAGS: &'static str = "🇫🇷🇩🇪";
+static COMBINED: &'static str = "e\u{301}";
+
+fn cluster_list(text: &str) -> Vec<&str> {
+    clusters(text).map(|(_, cluster)| cluster).collect()
+}
+
+#[test]
+fn keeps_joined_emoji_together() {
+    assert_eq!(vec![FAMILY, "a"], cluster_list(&format!("{}a", FAMILY)));
+    assert_eq!(vec![THUMBS_UP, "x"], cluster_list(&format!("{}x", THUMBS_UP)));
+    assert_eq!(vec![HEART, COMBINED], cluster_list(&format!("{}{}", HEART, COMBINED)));
+}
+
+#[test]
+fn splits_regional_indicators_into_flags() {
+    assert_eq!(vec!["🇫🇷", "🇩🇪"], cluster_list(FLAGS));
+    assert_eq!(vec!["🇫🇷", "🇩🇪", "🇮"], cluster_list("🇫🇷🇩🇪🇮"));
+}
+
+#[test]
+fn measures_clusters_as_single_glyphs() {
+    assert_eq!(2, width(FAMILY));
+    assert_eq!(2, width(THUMBS_UP));
+    assert_eq!(2, width(HEART));
+    assert_eq!(2, width("🇫🇷"));
+    assert_eq!(1, width(COMBINED));
+    assert_eq!(2, width("\u{1100}\u{1161}"));
+
+    assert_eq!(4, text_width(FLAGS));
+    assert_eq!(6, text_width(&format!("a{}{}{}", FAMILY, HEART, COMBINED)));
+}
+
+#[test]
+fn keeps_columns_after_clusters_stable() {
+    for cluster in &[FAMILY, THUMBS_UP, HEART, "🇫🇷"] {
+        let text = format!("{}abc", cluster);
+
+        assert_eq!(2, line_column(&text, cluster.len()));
+        assert_eq!(Some(cluster.chars().count()), char_index_at(&text, 80, 0, 2));
+        assert_eq!(Some(0), char_index_at(&text, 80, 0, 1));
+    }
+}
+
+#[test]
+fn wraps_without_splitting_clusters() {
+    // The family doesn't fit past `abc` and is moved whole to the next row
+    let text = format!("abc{}de", FAMILY);
+    let rows = wrap(&text, 4);
+
+    assert_eq!(vec![0..3, 3..text.len()], rows);
+
+    let text = format!("ab{}{}", FAMILY, FAMILY);
+    let rows = wrap(&text, 3);
+    assert_eq!(vec![0..2, 2..2 + FAMILY.len(), 2 + FAMILY.len()..text.len()], rows);
+}
+
+#[test]
+fn snaps_ranges_outward_to_clusters() {
+    let text = format!("a{}b", FAMILY);
+    let inside = 1 + "👨\u{200d}".len();
+
+    assert_eq!(1..1 + FAMILY.len(), snap(&text, inside..inside + 4));
+    assert_eq!(0..1, snap(&text, 0..1));
+    assert_eq!(1..text.len(), snap(&text, 3..text.len()));
+}
+
+#[test]
+fn paints_matches_after_clusters_at_stable_columns() {
+    let text = format!("{}{} error", FAMILY, COMBINED);
+    let offset = text.find("error").unwrap();
+    let segments = match_segments(&text, offset..offset + 5, 80);
+
+    assert_eq!(1, segments.len());
+    assert_eq!(4, segments[0].column);
+
+    let inside = "👨\u{200d}".len();
+    let segments = match_segments(&text, inside..inside + 4, 80);
+    assert_eq!(0, segments[0].column);
+    assert_eq!(0..FAMILY.len(), segments[0].range);
+}
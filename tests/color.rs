@@ -0,0 +1,96 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::ui::color::{COLOR_DEFAULT, ColorMode, ColorPair, PairRegistry, parse_color, use_colors};
+
+#[test]
+fn parses_modes() {
+    assert_eq!(Some(ColorMode::Auto), ColorMode::parse("auto"));
+    assert_eq!(Some(ColorMode::Always), ColorMode::parse("always"));
+    assert_eq!(Some(ColorMode::Never), ColorMode::parse("never"));
+    assert_eq!(None, ColorMode::parse("yes"));
+}
+
+#[test]
+fn decides_whether_to_use_colors() {
+    // (mode, NO_COLOR, tty, expected)
+    let cases = [(ColorMode::Auto, None, true, true),
+                 (ColorMode::Auto, None, false, false),
+                 (ColorMode::Auto, Some("1"), true, false),
+                 (ColorMode::Auto, Some("1"), false, false),
+                 (ColorMode::Auto, Some(""), true, true),
+                 (ColorMode::Always, None, false, true),
+                 (ColorMode::Always, Some("1"), true, true),
+                 (ColorMode::Always, Some("1"), false, true),
+                 (ColorMode::Never, None, true, false),
+                 (ColorMode::Never, Some(""), true, false),
+                 (ColorMode::Never, Some("1"), false, false)];
+
+    for &(mode, no_color, is_tty, expected) in &cases {
+        assert_eq!(expected,
+                   use_colors(mode, no_color, is_tty),
+                   "{:?} with NO_COLOR={:?} and tty={}",
+                   mode,
+                   no_color,
+                   is_tty);
+    }
+}
+
+#[test]
+fn parses_color_names_and_indexes() {
+    assert_eq!(Ok(1), parse_color("red"));
+    assert_eq!(Ok(12), parse_color("bright-blue"));
+    assert_eq!(Ok(208), parse_color("color208"));
+    assert_eq!(Ok(COLOR_DEFAULT), parse_color("default"));
+    assert!(parse_color("color256").unwrap_err().contains("`color256`"));
+    assert!(parse_color("bright-pink").unwrap_err().contains("`bright-pink`"));
+
+    assert_eq!(Ok(ColorPair::new(0, 6)), ColorPair::parse("black on cyan"));
+    assert_eq!(Ok(ColorPair::new(208, COLOR_DEFAULT)), ColorPair::parse("color208"));
+    assert!(ColorPair::parse("red on").is_err());
+}
+
+#[test]
+fn reuses_pairs_for_identical_colors() {
+    let mut registry = PairRegistry::new(16, 256, 256);
+
+    assert_eq!((16, true), registry.lookup(0, 6));
+    assert_eq!((17, true), registry.lookup(208, COLOR_DEFAULT));
+    assert_eq!((16, false), registry.lookup(0, 6));
+}
+
+#[test]
+fn reuses_the_nearest_pair_once_exhausted() {
+    let mut registry = PairRegistry::new(16, 18, 256);
+    registry.lookup(1, 0);
+    registry.lookup(4, 7);
+
+    // Bright red on black is closest to red on black.
+    assert_eq!((16, false), registry.lookup(9, 0));
+    assert_eq!((17, false), registry.lookup(12, 15));
+}
+
+#[test]
+fn falls_back_to_basic_colors_on_limited_terminals() {
+    let registry = PairRegistry::new(16, 64, 8);
+
+    assert_eq!((1, COLOR_DEFAULT), registry.available(196, COLOR_DEFAULT));
+    assert_eq!((4, 7), registry.available(4, 7));
+}
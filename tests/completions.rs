@@ -0,0 +1,62 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::utils::args::USAGE;
+use flow::utils::completions::{options, script, Shell, Value};
+
+#[test]
+fn reads_options_from_the_usage_text() {
+    let options = options(USAGE);
+
+    let profile = options.iter().find(|option| option.long == "profile").unwrap();
+    assert_eq!(Some('p'), profile.short);
+    assert_eq!(Some(Value::Profiles), profile.value);
+
+    let color = options.iter().find(|option| option.long == "color").unwrap();
+    assert_eq!(Some(Value::Choices(&["auto", "always", "never"])), color.value);
+
+    let replay = options.iter().find(|option| option.long == "replay").unwrap();
+    assert_eq!(None, replay.value);
+    assert!(replay.description.starts_with("Plays the file back"));
+
+    let config = options.iter().find(|option| option.long == "config").unwrap();
+    assert_eq!(Some(Value::Path), config.value);
+}
+
+#[test]
+fn lists_every_option_for_each_shell() {
+    let options = options(USAGE);
+    assert!(options.len() > 10);
+
+    for &shell in &[Shell::Bash, Shell::Zsh, Shell::Fish] {
+        let script = script(shell, &options);
+
+        for option in &options {
+            assert!(script.contains(&option.long), "{:?} misses --{}", shell, option.long);
+        }
+        assert!(script.contains("flow --list-profiles"));
+    }
+}
+
+#[test]
+fn parses_shell_names() {
+    assert_eq!(Some(Shell::Zsh), Shell::parse("zsh"));
+    assert_eq!(None, Shell::parse("tcsh"));
+}
@@ -0,0 +1,200 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::utils::config_file::{default_contents, ConfigFile};
+
+#[test]
+fn parses_valid_config() {
+    let config = ConfigFile::parse("[[filters]]\nname = \"All\"\n\n[[filters]]\nname = \"Errors\"\n\
+                                    contains = \"ERROR\"\n")
+        .unwrap();
+
+    let names = config.filters.iter().map(|filter| filter.name.clone()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["All", "Errors"]);
+}
+
+#[test]
+fn reports_toml_syntax_errors_with_position() {
+    let error = ConfigFile::parse("[[filters]]\nname = \"All\n").err().unwrap();
+
+    assert!(error.contains("line 2"), "{}", error);
+}
+
+#[test]
+fn reports_invalid_regexes() {
+    let error = ConfigFile::parse("[[filters]]\nname = \"Broken\"\ncontains = \"(unclosed\"\n")
+        .err()
+        .unwrap();
+
+    assert!(error.contains("invalid `contains` regex"), "{}", error);
+}
+
+#[test]
+fn reports_invalid_rewrite_patterns() {
+    let error = ConfigFile::parse("[[filters]]\nname = \"All\"\n\n[[filters.rewrite]]\n\
+                                   pattern = \"[\"\nreplace = \"x\"\n")
+        .err()
+        .unwrap();
+
+    assert!(error.contains("invalid rewrite `pattern` regex"), "{}", error);
+}
+
+#[test]
+fn requires_at_least_one_filter() {
+    assert!(ConfigFile::parse("").is_err());
+}
+
+#[test]
+fn reports_invalid_colors_by_name() {
+    let error = ConfigFile::parse("highlight_colors = [\"black on cyan\", \"purplish\"]\n\n\
+                                   [[filters]]\nname = \"All\"\n")
+        .err()
+        .unwrap();
+    assert!(error.contains("`purplish`"), "{}", error);
+
+    let error = ConfigFile::parse("[[filters]]\nname = \"All\"\n\n[theme]\nstatus = \"color300\"\n")
+        .err()
+        .unwrap();
+    assert!(error.contains("`color300`"), "{}", error);
+}
+
+#[test]
+fn validates_buffer_lines() {
+    let config = ConfigFile::parse("buffer_lines = 5000\n\n[[filters]]\nname = \"All\"\n").unwrap();
+    assert_eq!(Some(5000), config.buffer_lines);
+
+    let error = ConfigFile::parse("buffer_lines = 10\n\n[[filters]]\nname = \"All\"\n")
+        .err()
+        .unwrap();
+    assert!(error.contains("between 100 and 1000000"), "{}", error);
+}
+
+#[test]
+fn validates_continuation_prefix() {
+    let config = ConfigFile::parse("continuation_prefix = \"  \"\n\n[[filters]]\nname = \"All\"\n")
+        .unwrap();
+    assert_eq!(Some("  ".to_string()), config.continuation_prefix);
+
+    let error = ConfigFile::parse("continuation_prefix = \"----------\"\n\n\
+                                   [[filters]]\nname = \"All\"\n")
+        .err()
+        .unwrap();
+    assert!(error.contains("at most 8 columns"), "{}", error);
+}
+
+#[test]
+fn resolves_profiles_over_the_rest_of_the_config() {
+    let config = ConfigFile::parse("highlights = [\"ERROR\"]\nbuffer_lines = 500\n\n\
+                                    [[filters]]\nname = \"All\"\n\n\
+                                    [profile.incident]\nbuffer_lines = 8000\n\n\
+                                    [[profile.incident.filters]]\nname = \"Errors\"\n\
+                                    contains = \"ERROR\"\n")
+        .unwrap();
+    let profiles = config.profiles();
+
+    assert_eq!(profiles.names(), vec!["default", "incident"]);
+
+    let incident = profiles.resolve("incident").unwrap();
+    assert_eq!(incident.menu_item_names(), vec!["Errors"]);
+    assert_eq!(incident.buffer_lines, Some(8000));
+    assert_eq!(incident.highlights, Some(vec!["ERROR".to_string()]));
+
+    let default = profiles.resolve("default").unwrap();
+    assert_eq!(default.menu_item_names(), vec!["All"]);
+    assert_eq!(default.buffer_lines, Some(500));
+
+    let error = profiles.resolve("dev").err().unwrap();
+    assert!(error.contains("default, incident"), "{}", error);
+}
+
+#[test]
+fn validates_profiles() {
+    let error = ConfigFile::parse("[[filters]]\nname = \"All\"\n\n\
+                                   [profile.dev]\nhighlights = [\"/(/\"]\n")
+        .err()
+        .unwrap();
+
+    assert!(error.contains("Invalid profile `dev`"), "{}", error);
+}
+
+#[test]
+fn validates_blank_line_folding() {
+    let config = ConfigFile::parse("fold_blank_lines = \"always\"\n\n[[filters]]\nname = \"All\"\n")
+        .unwrap();
+    assert_eq!(Some("always".to_string()), config.fold_blank_lines);
+
+    let error = ConfigFile::parse("fold_blank_lines = \"yes\"\n\n[[filters]]\nname = \"All\"\n")
+        .err()
+        .unwrap();
+    assert!(error.contains("Unknown fold_blank_lines `yes`"), "{}", error);
+}
+
+#[test]
+fn validates_protected_lines() {
+    let contents = "protected_lines = \"preserve\"\n\n[[filters]]\nname = \"All\"\n";
+    let config = ConfigFile::parse(contents).unwrap();
+    assert_eq!(Some("preserve".to_string()), config.protected_lines);
+
+    let error = ConfigFile::parse("protected_lines = \"keep\"\n\n[[filters]]\nname = \"All\"\n")
+        .err()
+        .unwrap();
+    assert!(error.contains("Unknown protected_lines `keep`"), "{}", error);
+}
+
+#[test]
+fn takes_no_commands_unless_a_control_socket_is_set() {
+    assert_eq!(None, ConfigFile::default().control_socket);
+
+    let contents = "control_socket = \"/tmp/flow.sock\"\n\n[[filters]]\nname = \"All\"\n";
+    let config = ConfigFile::parse(contents).unwrap();
+    assert_eq!(Some("/tmp/flow.sock".to_string()), config.control_socket);
+}
+
+#[test]
+fn prints_the_default_config_as_parsed() {
+    let config = ConfigFile::parse(default_contents()).unwrap();
+
+    assert_eq!(ConfigFile::default().filters.len(), config.filters.len());
+}
+
+#[test]
+fn validates_sampling() {
+    let filters = "[[filters]]\nname = \"All\"\n";
+
+    assert!(ConfigFile::parse(&format!("sample_every = 50\n{}", filters)).is_ok());
+    assert!(ConfigFile::parse(&format!("sample_every = 0\n{}", filters)).is_err());
+    assert!(ConfigFile::parse(&format!("sample_target_rate = 0\n{}", filters)).is_err());
+}
+
+#[test]
+fn validates_source_formats() {
+    let filters = "[[filters]]\nname = \"All\"\n";
+    let source = |file: &str, format: &str| {
+        ConfigFile::parse(&format!("{}[[sources]]\nfile = \"{}\"\nformat = \"{}\"\n",
+                                   filters,
+                                   file,
+                                   format))
+    };
+
+    assert!(source("postgresql-*.log", "postgres-slow").is_ok());
+    let error = source("slow.log", "oracle").err().unwrap();
+    assert!(error.contains("unknown source format `oracle`"), "{}", error);
+    assert!(source("logs*/slow.log", "mysql-slow").is_err());
+}
@@ -0,0 +1,86 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::container::{unwrap, Stream, Unwrapper, SAMPLE_LINES};
+use flow::core::line::LineCollection;
+
+const WRAPPED: &'static str =
+    r#"{"log":"GET / 200\n","stream":"stderr","time":"2016-03-01T14:32:05.123Z"}"#;
+
+#[test]
+fn unwraps_container_lines() {
+    let (log, wrapper) = unwrap(WRAPPED).unwrap();
+
+    assert_eq!("GET / 200", log);
+    assert_eq!(Stream::Stderr, wrapper.stream);
+    assert_eq!(Some("2016-03-01T14:32:05.123Z".to_string()), wrapper.time);
+    assert_eq!(WRAPPED, wrapper.raw);
+}
+
+#[test]
+fn leaves_other_lines_alone() {
+    assert!(unwrap("GET / 200").is_none());
+    assert!(unwrap(r#"{"msg":"started"}"#).is_none());
+    assert!(unwrap(r#"{"log":"cut short"#).is_none());
+    assert_eq!(Stream::Stdout, unwrap(r#"{"log":"a"}"#).unwrap().1.stream);
+}
+
+#[test]
+fn stops_parsing_sources_found_to_be_plain() {
+    let mut unwrapper = Unwrapper::new();
+
+    assert!(unwrapper.unwrap(1, "plain").is_none());
+    assert!(unwrapper.unwrap(1, WRAPPED).is_none());
+    assert!(unwrapper.unwrap(2, WRAPPED).is_some());
+}
+
+#[test]
+fn keeps_unwrapping_wrapped_sources() {
+    let mut unwrapper = Unwrapper::new();
+    for _ in 0..SAMPLE_LINES {
+        unwrapper.unwrap(1, WRAPPED);
+    }
+
+    assert!(unwrapper.is_wrapped(1));
+    assert!(unwrapper.unwrap(1, "not wrapped").is_none());
+    assert!(unwrapper.unwrap(1, WRAPPED).is_some());
+}
+
+#[test]
+fn shows_the_logged_line_timed_by_its_wrapper() {
+    let mut lines = LineCollection::new(10);
+    lines.extend(vec![WRAPPED.to_string()]);
+
+    let line = &lines.entries[0];
+    assert_eq!("GET / 200", &*line.content_without_ansi);
+    assert!(line.is_stderr());
+    assert_eq!(Some(1_456_842_725_123), line.timestamp());
+    assert_eq!(WRAPPED, line.wrapper.as_ref().unwrap().raw);
+}
+
+#[test]
+fn keeps_lines_as_received_when_turned_off() {
+    let mut lines = LineCollection::new(10);
+    lines.set_unwrap(false);
+    lines.extend(vec![WRAPPED.to_string()]);
+
+    assert_eq!(WRAPPED, &*lines.entries[0].content_without_ansi);
+    assert!(lines.entries[0].wrapper.is_none());
+}
@@ -0,0 +1,152 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+extern crate rustc_serialize;
+
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+
+use rustc_serialize::json::Json;
+
+use flow::core::control::{Command, ControlSocket, Request};
+
+fn socket_path(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("flow-control-{}.sock", name));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+// Stands in for the ui thread: it keeps the search and the line scrolled
+// to, and tells them on `status`
+fn start(name: &str) -> (ControlSocket, UnixStream) {
+    let path = socket_path(name);
+    let (sender, receiver) = mpsc::channel::<Request>();
+    let socket = ControlSocket::bind(&path, sender, || {}).unwrap();
+
+    thread::spawn(move || {
+        let mut search = Json::Null;
+        let mut line = Json::Null;
+        for request in receiver {
+            let result = match request.command.clone() {
+                Command::Search(text) => {
+                    search = Json::String(text);
+                    Ok(vec![])
+                }
+                Command::Goto(number) => {
+                    line = Json::U64(number as u64);
+                    Ok(vec![])
+                }
+                Command::SelectTab(name) => Err(format!("No tab named `{}`", name)),
+                Command::Status => Ok(vec![("search", search.clone()), ("line", line.clone())]),
+            };
+            request.answer(result);
+        }
+    });
+
+    let stream = UnixStream::connect(&path).unwrap();
+    (socket, stream)
+}
+
+fn send(stream: &mut UnixStream, command: &str) -> Json {
+    writeln!(stream, "{}", command).unwrap();
+
+    let mut reply = String::new();
+    BufReader::new(stream.try_clone().unwrap()).read_line(&mut reply).unwrap();
+    Json::from_str(&reply).unwrap()
+}
+
+#[test]
+fn parses_commands() {
+    assert_eq!(Command::parse(r#"{"cmd":"search","q":"ERROR"}"#),
+               Ok(Command::Search("ERROR".to_string())));
+    assert_eq!(Command::parse(r#"{"cmd":"goto","line":1234}"#), Ok(Command::Goto(1234)));
+    assert_eq!(Command::parse(r#"{"cmd":"select_tab","name":"api"}"#),
+               Ok(Command::SelectTab("api".to_string())));
+    assert_eq!(Command::parse(r#"{"cmd":"status"}"#), Ok(Command::Status));
+
+    assert!(Command::parse(r#"{"cmd":"goto","line":0}"#).is_err());
+    assert!(Command::parse(r#"{"cmd":"search"}"#).is_err());
+    assert!(Command::parse(r#"["status"]"#).is_err());
+}
+
+#[test]
+fn answers_commands_through_the_receiving_thread() {
+    let (_socket, mut stream) = start("commands");
+
+    let reply = send(&mut stream, r#"{"cmd":"search","q":"ERROR"}"#);
+    assert_eq!(reply.find("ok"), Some(&Json::Boolean(true)));
+    send(&mut stream, r#"{"cmd":"goto","line":1234}"#);
+
+    let status = send(&mut stream, r#"{"cmd":"status"}"#);
+    assert_eq!(status.find("ok"), Some(&Json::Boolean(true)));
+    assert_eq!(status.find("search"), Some(&Json::String("ERROR".to_string())));
+    assert_eq!(status.find("line"), Some(&Json::U64(1234)));
+}
+
+#[test]
+fn reports_errors_without_closing_the_connection() {
+    let (_socket, mut stream) = start("errors");
+
+    let reply = send(&mut stream, r#"{"cmd":"explode"}"#);
+    assert_eq!(reply.find("ok"), Some(&Json::Boolean(false)));
+    assert_eq!(reply.find("error"),
+               Some(&Json::String("Unknown command `explode`".to_string())));
+
+    let reply = send(&mut stream, "not json");
+    assert_eq!(reply.find("ok"), Some(&Json::Boolean(false)));
+
+    let reply = send(&mut stream, r#"{"cmd":"select_tab","name":"api"}"#);
+    assert_eq!(reply.find("error"), Some(&Json::String("No tab named `api`".to_string())));
+
+    let status = send(&mut stream, r#"{"cmd":"status"}"#);
+    assert_eq!(status.find("ok"), Some(&Json::Boolean(true)));
+}
+
+#[test]
+fn keeps_the_socket_private_and_removes_it() {
+    let path = socket_path("private");
+    let (sender, _receiver) = mpsc::channel();
+    let socket = ControlSocket::bind(&path, sender, || {}).unwrap();
+
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let (other, _) = mpsc::channel();
+    assert!(ControlSocket::bind(&path, other, || {}).is_err());
+
+    drop(socket);
+    assert!(!path.exists());
+}
+
+#[test]
+fn replaces_sockets_left_over() {
+    let path = socket_path("left-over");
+    // Nothing listens on it anymore, as if flow was killed
+    drop(UnixListener::bind(&path).unwrap());
+    assert!(path.exists());
+
+    let (sender, _receiver) = mpsc::channel();
+    assert!(ControlSocket::bind(&path, sender, || {}).is_ok());
+}
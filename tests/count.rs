@@ -0,0 +1,172 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+extern crate regex;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{self, Command};
+
+use regex::Regex;
+
+use flow::core::count::{self, Count};
+use flow::core::filter::Filter;
+use flow::core::line::SearchPattern;
+use flow::core::query::Syntax;
+use flow::core::timestamp::format_minute;
+
+const SYNTAX: Syntax = Syntax { regex: true, expressions: true };
+
+fn filter(content: Option<&str>) -> Filter {
+    Filter {
+        name: "All".to_string(),
+        content: content.map(|value| Regex::new(value).unwrap()),
+        start: None,
+        end: None,
+        rewrites: vec![],
+        line_rules: vec![],
+        alerts: vec![],
+    }
+}
+
+fn lines(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+fn count(values: &[&str], pattern: &SearchPattern) -> Count {
+    Count::from_lines(lines(values), filter(None), pattern, true)
+}
+
+#[test]
+fn counts_every_occurrence() {
+    let pattern = SearchPattern::new("error", false);
+    let result = count(&["error and error", "fine", "errors"], &pattern);
+
+    assert_eq!(result.total, 3);
+    assert!(result.minutes.is_empty());
+}
+
+#[test]
+fn honors_the_whole_word_and_case_flags() {
+    let values = ["Error", "error", "errors"];
+
+    assert_eq!(count(&values, &SearchPattern::new("error", true)).total, 1);
+
+    let pattern = SearchPattern::new("error", true).ignoring_case().unwrap();
+    assert_eq!(count(&values, &pattern).total, 2);
+
+    let pattern = SearchPattern::new("/err.r/", false).with_syntax(SYNTAX).ignoring_case().unwrap();
+    assert_eq!(count(&values, &pattern).total, 3);
+}
+
+#[test]
+fn escapes_plain_text_ignoring_case() {
+    let pattern = SearchPattern::new("a.b", false).ignoring_case().unwrap();
+
+    assert_eq!(count(&["A.B", "axb"], &pattern).total, 1);
+}
+
+#[test]
+fn refuses_to_ignore_case_of_expressions() {
+    let pattern = SearchPattern::new("error AND db", false).with_syntax(SYNTAX);
+
+    assert!(pattern.ignoring_case().is_err());
+    assert!(SearchPattern::new("error AND db", false).ignoring_case().is_ok());
+}
+
+#[test]
+fn only_counts_lines_of_the_tab() {
+    let pattern = SearchPattern::new("timeout", false);
+    let values = lines(&["db timeout", "cache timeout"]);
+    let result = Count::from_lines(values, filter(Some("^db")), &pattern, true);
+
+    assert_eq!(result.total, 1);
+}
+
+#[test]
+fn buckets_by_minute() {
+    let pattern = SearchPattern::new("error", false);
+    let result = count(&["2016-03-01 14:32:05 error",
+                         "2016-03-01 14:32:59 error error",
+                         "2016-03-01 14:33:00 error",
+                         "error without a timestamp"],
+                       &pattern);
+
+    let minutes = result.minutes
+        .iter()
+        .map(|(minute, value)| (format_minute(*minute), *value))
+        .collect::<Vec<_>>();
+    assert_eq!(minutes,
+               vec![("2016-03-01 14:32".to_string(), 3), ("2016-03-01 14:33".to_string(), 1)]);
+    assert_eq!(result.total, 5);
+}
+
+#[test]
+fn reports_files_and_their_total() {
+    let pattern = SearchPattern::new("error", false);
+    let counts = vec![("a.log".to_string(), count(&["14:32:05 error"], &pattern)),
+                      ("b.log".to_string(), count(&["fine"], &pattern))];
+
+    assert_eq!(count::report(&counts, false), "a.log\t1\nb.log\t0\ntotal\t1");
+    assert_eq!(count::report(&counts, true),
+               "a.log\t1\na.log\t14:32\t1\nb.log\t0\ntotal\t1");
+}
+
+#[test]
+fn tells_why_patterns_are_searched_literally() {
+    let parsed = |text| SearchPattern::new(text, false).with_syntax(SYNTAX);
+
+    assert!(parsed("/err(/").invalid().unwrap().starts_with("Invalid regex"));
+    assert!(parsed("a AND /b(/").invalid().is_some());
+    let invalid = parsed("a AND (b").invalid().unwrap();
+    assert!(invalid.starts_with("Invalid expression: expected )"), "{}", invalid);
+    assert_eq!(parsed("/err(or)?/").invalid(), None);
+    assert_eq!(SearchPattern::new("err(", false).invalid(), None);
+}
+
+// Built along with the tests, next to their directory.
+fn flow_binary() -> PathBuf {
+    let mut path = env::current_exe().unwrap();
+    path.pop();
+    if path.ends_with("deps") {
+        path.pop();
+    }
+    path.join("flow")
+}
+
+#[test]
+fn exits_with_an_error_on_invalid_patterns() {
+    // Unique to this run, as other runs may be going on at the same time
+    let input = env::temp_dir().join(format!("flow-count-invalid-{}.log", process::id()));
+    fs::write(&input, "err(\n").unwrap();
+
+    let output = Command::new(flow_binary())
+        .args(&["--regex", "--count=/err(/"])
+        .arg(&input)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Invalid regex"));
+    assert!(output.stdout.is_empty());
+
+    let output = Command::new(flow_binary()).arg("--count=nothing").arg(&input).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let _ = fs::remove_file(&input);
+}
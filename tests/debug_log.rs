@@ -0,0 +1,74 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use std::sync::mpsc::sync_channel;
+
+use flow::core::debug_log;
+use flow::core::line::LineCollection;
+
+fn ingest() -> Vec<(usize, String)> {
+    let mut lines = LineCollection::new(50);
+    lines.extend((0..120).map(|i| (i % 3, format!("line {}", i))));
+
+    lines.entries.iter().map(|line| (line.id, line.content_without_ansi.to_string())).collect()
+}
+
+// Logging is global, so everything touching it is checked within one test.
+#[test]
+fn logging_leaves_behavior_unchanged_and_never_blocks() {
+    let without = ingest();
+
+    let receiver = debug_log::enable();
+    let with = ingest();
+    assert_eq!(without, with);
+
+    let entries = receiver.try_iter().collect::<Vec<_>>();
+    assert!(entries.iter().any(|entry| entry.contains("[ingestion] lines=120 stored=50")),
+            "{:?}",
+            entries);
+    assert!(entries.iter().any(|entry| entry.contains("[eviction] evicted=70 stored=50")),
+            "{:?}",
+            entries);
+
+    // Nothing reads the queue from here on, so it fills up
+    for i in 0..5_000 {
+        debug_log::write("test", format!("entry {}", i));
+    }
+    assert!(debug_log::dropped() >= 5_000 - 4_096);
+
+    debug_log::disable();
+    assert!(!debug_log::is_enabled());
+    drop(receiver);
+    assert_eq!(without, ingest());
+}
+
+#[test]
+fn writes_queued_entries_until_logging_stops() {
+    let (sender, receiver) = sync_channel(4);
+    sender.send("first".to_string()).unwrap();
+    sender.send("second".to_string()).unwrap();
+    drop(sender);
+
+    let mut output = vec![];
+    debug_log::write_until_stopped(receiver, &mut output);
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.starts_with("first\nsecond\n"), "{}", output);
+}
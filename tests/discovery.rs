@@ -0,0 +1,220 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use flow::core::discovery::{Discovery, GlobPattern, SourceChange};
+use flow::core::health::Health;
+
+// A fresh, empty directory for each test.
+fn temp_dir(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("flow-discovery-{}", name));
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).unwrap();
+
+    path
+}
+
+fn text(value: &str) -> Arc<str> {
+    Arc::from(value)
+}
+
+fn write(path: &PathBuf, contents: &str) {
+    let mut file = OpenOptions::new().create(true).append(true).open(path).unwrap();
+    file.write_all(contents.as_bytes()).unwrap();
+}
+
+fn pattern(directory: &PathBuf, name: &str) -> GlobPattern {
+    GlobPattern::parse(&directory.join(name).to_string_lossy()).unwrap()
+}
+
+#[test]
+fn tells_globs_from_plain_paths() {
+    assert!(GlobPattern::is_glob("/var/log/*.log"));
+    assert!(GlobPattern::is_glob("app-?.log"));
+    assert!(!GlobPattern::is_glob("/var/log/syslog"));
+    assert!(GlobPattern::parse("/var/*/app.log").is_err());
+}
+
+#[test]
+fn matches_file_names() {
+    let glob = GlobPattern::parse("/logs/app-[0-9]?.log").unwrap();
+
+    assert!(glob.matches(&PathBuf::from("/logs/app-1a.log")));
+    assert!(!glob.matches(&PathBuf::from("/logs/app-xa.log")));
+    assert!(!glob.matches(&PathBuf::from("/logs/app-1.log")));
+
+    let glob = GlobPattern::parse("/logs/[!.]*.log").unwrap();
+    assert!(glob.matches(&PathBuf::from("/logs/web.log")));
+    assert!(!glob.matches(&PathBuf::from("/logs/.hidden.log")));
+
+    let glob = GlobPattern::parse("/logs/a.b+[x.log").unwrap();
+    assert!(glob.matches(&PathBuf::from("/logs/a.b+[x.log")));
+    assert!(!glob.matches(&PathBuf::from("/logs/aXb+[x.log")));
+}
+
+#[test]
+fn scans_matching_files_only() {
+    let directory = temp_dir("scan");
+    File::create(directory.join("b.log")).unwrap();
+    File::create(directory.join("a.log")).unwrap();
+    File::create(directory.join("notes.txt")).unwrap();
+    fs::create_dir(directory.join("old.log")).unwrap();
+
+    let paths = pattern(&directory, "*.log").scan();
+
+    assert_eq!(paths, vec![directory.join("a.log"), directory.join("b.log")]);
+}
+
+#[test]
+fn follows_files_as_they_come_and_go() {
+    let directory = temp_dir("lifecycle");
+    let first = directory.join("first.log");
+    write(&first, "one\ntwo\n");
+
+    let mut discovery = Discovery::new(pattern(&directory, "*.log"), 10);
+    assert_eq!(discovery.paths(), vec![&first]);
+    assert_eq!(discovery.read_lines(1), vec![text("two")]);
+
+    let second = directory.join("second.log");
+    write(&second, "three\n");
+    assert_eq!(discovery.refresh(), vec![SourceChange::Added(second.clone())]);
+
+    write(&first, "four\n");
+    assert_eq!(discovery.read_to_end(), vec![text("four"), text("three")]);
+
+    fs::remove_file(&first).unwrap();
+    assert_eq!(discovery.refresh(), vec![SourceChange::Removed(first.clone())]);
+    assert_eq!(discovery.paths(), vec![&second]);
+    assert!(discovery.refresh().is_empty());
+}
+
+#[test]
+fn tells_lines_of_each_file_apart() {
+    let directory = temp_dir("sources");
+    let first = directory.join("a.log");
+    let second = directory.join("b.log");
+    write(&first, "one\n");
+    write(&second, "two\n");
+
+    let mut discovery = Discovery::new(pattern(&directory, "*.log"), 10);
+    assert_eq!(discovery.read_sourced_lines(1),
+               vec![(1, text("one")), (2, text("two"))]);
+
+    fs::remove_file(&first).unwrap();
+    discovery.refresh();
+    write(&first, "three\n");
+    discovery.refresh();
+
+    // Followed again under the same id
+    assert_eq!(discovery.read_sourced_to_end(), vec![(1, text("three"))]);
+}
+
+#[test]
+fn caps_the_number_of_followed_files() {
+    let directory = temp_dir("cap");
+    write(&directory.join("a.log"), "");
+    write(&directory.join("b.log"), "");
+
+    let mut discovery = Discovery::new(pattern(&directory, "*.log"), 2);
+
+    let extra = directory.join("c.log");
+    write(&extra, "");
+    assert_eq!(discovery.refresh(), vec![SourceChange::Skipped(extra.clone())]);
+    // Only warned about once
+    assert!(discovery.refresh().is_empty());
+
+    fs::remove_file(directory.join("a.log")).unwrap();
+    assert_eq!(discovery.refresh(),
+               vec![SourceChange::Removed(directory.join("a.log")), SourceChange::Added(extra.clone())]);
+    assert_eq!(discovery.paths(), vec![&directory.join("b.log"), &extra]);
+}
+
+#[test]
+fn keeps_files_left_out_at_startup_to_tell_about() {
+    let directory = temp_dir("startup-cap");
+    write(&directory.join("a.log"), "");
+    write(&directory.join("b.log"), "");
+    write(&directory.join("c.log"), "");
+
+    let mut discovery = Discovery::new(pattern(&directory, "*.log"), 2);
+
+    assert_eq!(discovery.take_unreported(),
+               vec![SourceChange::Skipped(directory.join("c.log"))]);
+    assert!(discovery.take_unreported().is_empty());
+}
+
+#[test]
+fn summarizes_every_change() {
+    let path = |name: &str| PathBuf::from(name);
+
+    assert_eq!(None, SourceChange::summarize(&[]));
+    assert_eq!(Some("Following new file `a.log`".to_string()),
+               SourceChange::summarize(&[SourceChange::Added(path("a.log"))]));
+    assert_eq!(Some("Following 2 new files, stopped following 1 removed file".to_string()),
+               SourceChange::summarize(&[SourceChange::Added(path("a.log")),
+                                         SourceChange::Removed(path("b.log")),
+                                         SourceChange::Added(path("c.log"))]));
+    assert_eq!(Some("Not following 2 more files - at most 32 files are followed".to_string()),
+               SourceChange::summarize(&[SourceChange::Skipped(path("a.log")),
+                                         SourceChange::Skipped(path("b.log"))]));
+}
+
+#[test]
+fn names_sources_by_their_file_names() {
+    let directory = temp_dir("names");
+    write(&directory.join("web.log"), "one\n");
+
+    let mut discovery = Discovery::new(pattern(&directory, "*.log"), 10);
+    write(&directory.join("db.log"), "two\n");
+    discovery.refresh();
+    fs::remove_file(directory.join("web.log")).unwrap();
+    discovery.refresh();
+
+    assert_eq!(discovery.source_names(),
+               vec![(2, "db.log".to_string()), (1, "web.log".to_string())]);
+}
+
+#[cfg(unix)]
+#[test]
+fn files_that_cannot_be_opened_show_as_failed() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let directory = temp_dir("unreadable");
+    let path = directory.join("secret.log");
+    write(&path, "hidden\n");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o000)).unwrap();
+    // Permissions don't stop the superuser
+    if File::open(&path).is_ok() {
+        return;
+    }
+
+    let discovery = Discovery::new(pattern(&directory, "*.log"), 10);
+    let state = discovery.health()
+        .into_iter()
+        .find(|change| change.source == path.to_string_lossy())
+        .unwrap();
+
+    assert_eq!(Health::Errored, state.health);
+}
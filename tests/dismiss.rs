@@ -0,0 +1,49 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::ui::dismiss::{Dismissals, Layer};
+
+#[test]
+fn dismisses_the_topmost_layer_first() {
+    let mut dismissals = Dismissals::default();
+    dismissals.set(Layer::SearchHighlights, true);
+    dismissals.set(Layer::DebugOverlay, true);
+    dismissals.set(Layer::PinnedView, true);
+
+    assert_eq!(Some(Layer::DebugOverlay), dismissals.top());
+    assert_eq!(Some("esc: close overlay"), dismissals.hint());
+
+    dismissals.set(Layer::DebugOverlay, false);
+    assert_eq!(Some(Layer::PinnedView), dismissals.top());
+
+    dismissals.set(Layer::PinnedView, false);
+    assert_eq!(Some(Layer::SearchHighlights), dismissals.top());
+}
+
+#[test]
+fn registering_twice_keeps_a_single_entry() {
+    let mut dismissals = Dismissals::default();
+    dismissals.set(Layer::Prompt, true);
+    dismissals.set(Layer::Prompt, true);
+    dismissals.set(Layer::Prompt, false);
+
+    assert_eq!(None, dismissals.top());
+    assert_eq!(None, dismissals.hint());
+}
@@ -0,0 +1,151 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use flow::core::error::{ErrorLog, FlowError};
+use flow::core::tail::Tail;
+use flow::core::replay::Replay;
+use flow::core::discovery::{Discovery, GlobPattern};
+use flow::core::highlights::HighlightPattern;
+use flow::utils::config_file::ConfigFile;
+
+// A fresh, empty directory for each test.
+fn temp_dir(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("flow-error-{}", name));
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).unwrap();
+
+    path
+}
+
+fn write_bytes(path: &PathBuf, contents: &[u8]) {
+    File::create(path).unwrap().write_all(contents).unwrap();
+}
+
+#[test]
+fn maps_io_errors_by_kind() {
+    let path = Path::new("/var/log/app.log");
+
+    assert_eq!(FlowError::NotFound(path.to_path_buf()),
+               FlowError::from_io(path, &io::Error::new(io::ErrorKind::NotFound, "gone")));
+    assert_eq!(FlowError::PermissionDenied(path.to_path_buf()),
+               FlowError::from_io(path, &io::Error::new(io::ErrorKind::PermissionDenied, "no")));
+    assert_eq!(FlowError::Io(path.to_path_buf(), "broken pipe".to_string()),
+               FlowError::from_io(path, &io::Error::new(io::ErrorKind::BrokenPipe, "broken pipe")));
+}
+
+#[test]
+fn tail_reports_missing_file() {
+    let path = temp_dir("tail-missing").join("missing.log");
+
+    match Tail::open(&path.to_string_lossy()) {
+        Err(error) => assert_eq!(FlowError::NotFound(path), error),
+        Ok(_) => panic!("expected an error"),
+    }
+}
+
+#[test]
+fn tail_reports_read_failure_once() {
+    let path = temp_dir("tail-read").join("binary.log");
+    write_bytes(&path, b"ok\n\xff\xfe\n");
+
+    let mut tail = Tail::open(&path.to_string_lossy()).unwrap();
+    assert!(tail.read_to_end().is_empty());
+    match tail.take_error() {
+        Some(FlowError::Io(ref error_path, _)) => assert_eq!(&path, error_path),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    tail.read_to_end();
+    assert_eq!(None, tail.take_error());
+}
+
+#[test]
+fn replay_reports_missing_file() {
+    let path = temp_dir("replay").join("missing.log");
+
+    match Replay::load(&path.to_string_lossy(), 100) {
+        Err(error) => assert_eq!(FlowError::NotFound(path), error),
+        Ok(_) => panic!("expected an error"),
+    }
+}
+
+#[test]
+fn discovery_reports_unreadable_files_once() {
+    let directory = temp_dir("discovery");
+    write_bytes(&directory.join("good.log"), b"fine\n");
+    write_bytes(&directory.join("bad.log"), b"\xff\n");
+
+    let pattern = GlobPattern::parse(&directory.join("*.log").to_string_lossy()).unwrap();
+    let mut discovery = Discovery::new(pattern, 10);
+
+    assert_eq!(vec![Arc::<str>::from("fine")], discovery.read_to_end());
+    let errors = discovery.take_errors();
+    assert_eq!(1, errors.len());
+    assert!(errors[0].to_string().contains("bad.log"));
+
+    discovery.read_to_end();
+    assert!(discovery.take_errors().is_empty());
+}
+
+#[test]
+fn config_reports_missing_and_invalid_files() {
+    let directory = temp_dir("config");
+    let path = directory.join("missing.toml");
+
+    match ConfigFile::load(&path) {
+        Err(error) => assert_eq!(FlowError::NotFound(path), error),
+        Ok(_) => panic!("expected an error"),
+    }
+
+    let path = directory.join("invalid.toml");
+    write_bytes(&path, b"keymap = \"emacs\"\n\n[[filters]]\nname = \"All\"\n");
+    match ConfigFile::load(&path) {
+        Err(FlowError::Config(message)) => assert!(message.contains("emacs")),
+        other => panic!("unexpected {:?}", other.err()),
+    }
+}
+
+#[test]
+fn highlights_report_invalid_regex() {
+    match HighlightPattern::parse("/(unclosed/") {
+        Err(FlowError::InvalidRegex(pattern, _)) => assert_eq!("/(unclosed/", pattern),
+        _ => panic!("expected an invalid regex"),
+    }
+}
+
+#[test]
+fn log_keeps_most_recent_errors() {
+    let mut log = ErrorLog::new(2);
+    log.push_at("10:00:00".to_string(), FlowError::Config("first".to_string()));
+    log.push_at("10:00:01".to_string(), FlowError::Config("second".to_string()));
+    log.push_at("10:00:02".to_string(), FlowError::ReaderFailed("third".to_string()));
+
+    assert_eq!(2, log.len());
+    assert_eq!(vec!["10:00:02 Reading stopped - third".to_string(),
+                    "10:00:01 second".to_string()],
+               log.rows());
+    assert_eq!("10:00:02", log.last().unwrap().timestamp);
+}
@@ -0,0 +1,192 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::eviction::{Protection, SourceFairness};
+use flow::core::line::LineCollection;
+use flow::core::range::LineRange;
+
+const CHATTY: usize = 1;
+const QUIET: usize = 2;
+
+// Every round, the chatty source logs 100 lines for each one of the quiet source.
+fn receive(lines: &mut LineCollection, rounds: usize) {
+    for round in 0..rounds {
+        let mut batch = (0..100)
+            .map(|i| (CHATTY, format!("chatty {} {}", round, i)))
+            .collect::<Vec<_>>();
+        batch.push((QUIET, format!("quiet {}", round)));
+
+        lines.extend(batch);
+    }
+}
+
+fn count(lines: &LineCollection, source: usize) -> usize {
+    lines.entries.iter().filter(|line| line.source == source).count()
+}
+
+#[test]
+fn evicts_oldest_lines_by_default() {
+    let mut lines = LineCollection::new(500);
+    receive(&mut lines, 50);
+
+    assert_eq!(lines.len(), 500);
+    assert_eq!(count(&lines, QUIET), 5);
+}
+
+#[test]
+fn keeps_reserved_lines_of_quiet_sources() {
+    let mut lines = LineCollection::new(500);
+    lines.set_eviction(Box::new(SourceFairness::new(20)));
+    receive(&mut lines, 50);
+
+    assert_eq!(lines.len(), 500);
+    assert_eq!(count(&lines, QUIET), 20);
+    assert_eq!(count(&lines, CHATTY), 480);
+
+    let quiet = lines.entries.iter().filter(|line| line.source == QUIET).collect::<Vec<_>>();
+    assert_eq!(&*quiet[0].content_without_ansi, "quiet 30");
+    assert_eq!(&*quiet[19].content_without_ansi, "quiet 49");
+}
+
+#[test]
+fn keeps_lines_ordered() {
+    let mut lines = LineCollection::new(300);
+    lines.set_eviction(Box::new(SourceFairness::new(10)));
+    receive(&mut lines, 20);
+
+    let ids = lines.entries.iter().map(|line| line.id).collect::<Vec<_>>();
+    let mut sorted = ids.clone();
+    sorted.sort();
+
+    assert_eq!(ids, sorted);
+}
+
+#[test]
+fn falls_back_to_oldest_lines_within_every_share() {
+    let mut lines = LineCollection::new(4);
+    lines.set_eviction(Box::new(SourceFairness::new(10)));
+    lines.extend(vec![(1, "a".to_string()), (2, "b".to_string()), (1, "c".to_string())]);
+    lines.extend(vec![(2, "d".to_string()), (1, "e".to_string())]);
+
+    let contents =
+        lines.entries.iter().map(|line| &*line.content_without_ansi).collect::<Vec<_>>();
+    assert_eq!(contents, vec!["b", "c", "d", "e"]);
+}
+
+#[test]
+fn counts_entries_before_a_position_after_uneven_eviction() {
+    let mut lines = LineCollection::new(3);
+    lines.set_eviction(Box::new(SourceFairness::new(1)));
+    lines.extend(vec![(1, "a".to_string()), (2, "b".to_string()), (1, "c".to_string())]);
+    lines.extend(vec![(1, "d".to_string())]);
+
+    // `a` was dropped, `b` kept as the only line of its source
+    assert_eq!(lines.entries.iter().map(|line| line.id).collect::<Vec<_>>(), vec![2, 3, 4]);
+    assert_eq!(lines.entries_before(1), 0);
+    assert_eq!(lines.entries_before(2), 1);
+    assert_eq!(lines.entries_before(3), 2);
+    assert_eq!(lines.entries_before(10), 3);
+}
+
+fn numbered(from: usize, to: usize) -> Vec<(usize, String)> {
+    (from..to).map(|i| (CHATTY, format!("line {}", i))).collect()
+}
+
+fn protected_lines(policy: Protection) -> LineCollection {
+    let mut lines = LineCollection::new(20);
+    lines.protected.policy = policy;
+    lines.extend(numbered(1, 11));
+    lines.protected.set(vec![LineRange::new(3, 3), LineRange::new(5, 6)]);
+    lines
+}
+
+#[test]
+fn warns_once_before_evicting_protected_lines() {
+    let mut lines = protected_lines(Protection::Warn);
+    assert_eq!(lines.endangered(0), 0);
+
+    // Lines are warned about once 2 more would evict them
+    lines.extend(numbered(11, 21));
+    assert_eq!(lines.endangered(0), 0);
+
+    lines.extend(numbered(21, 22));
+    assert_eq!(lines.endangered(0), 1);
+    assert_eq!(lines.endangered(0), 0);
+
+    lines.extend(numbered(22, 25));
+    assert_eq!(lines.endangered(0), 2);
+    assert_eq!(lines.endangered(0), 0);
+
+    lines.extend(numbered(22, 30));
+    assert!(lines.protected.preserved.is_empty());
+}
+
+#[test]
+fn warns_of_lines_the_incoming_ones_would_evict() {
+    let mut lines = protected_lines(Protection::Warn);
+    lines.extend(numbered(11, 21));
+
+    assert_eq!(lines.endangered(4), 3);
+    assert_eq!(lines.endangered(4), 0);
+}
+
+#[test]
+fn warns_of_the_lines_the_eviction_policy_picks() {
+    let mut lines = LineCollection::new(20);
+    lines.set_eviction(Box::new(SourceFairness::new(5)));
+    lines.protected.policy = Protection::Preserve;
+    lines.extend((1..4).map(|i| (QUIET, format!("quiet {}", i))).collect::<Vec<_>>());
+    lines.extend(numbered(4, 21));
+    lines.protected.set(vec![LineRange::new(2, 2), LineRange::new(4, 5)]);
+
+    // The oldest lines are quiet ones, kept as their source is under its share
+    assert_eq!(lines.endangered(0), 2);
+    lines.extend(numbered(21, 23));
+    let preserved = lines.protected.preserved.iter().map(|line| line.id).collect::<Vec<_>>();
+    assert_eq!(preserved, vec![4, 5]);
+    assert_eq!(lines.entries.front().unwrap().id, 1);
+}
+
+#[test]
+fn sets_aside_evicted_protected_lines() {
+    let mut lines = protected_lines(Protection::Preserve);
+    lines.extend(numbered(11, 30));
+
+    assert_eq!(lines.len(), 20);
+    let preserved = lines.protected
+        .preserved
+        .iter()
+        .map(|line| line.id)
+        .collect::<Vec<_>>();
+    assert_eq!(preserved, vec![3, 5, 6]);
+
+    let copy = lines.preserved_copy();
+    assert_eq!(copy.len(), 3);
+    assert_eq!(&*copy.entries[0].content_without_ansi, "line 3");
+}
+
+#[test]
+fn ignores_protected_lines_when_told_to() {
+    let mut lines = protected_lines(Protection::Ignore);
+    lines.extend(numbered(11, 30));
+
+    assert_eq!(lines.endangered(0), 0);
+    assert!(lines.protected.preserved.is_empty());
+}
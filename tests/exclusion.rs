@@ -0,0 +1,99 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::exclusion::{candidates, wildcard, Candidate};
+use flow::core::line::SearchPattern;
+use flow::core::query::Syntax;
+
+fn texts(line: &str) -> Vec<String> {
+    candidates(line).into_iter().map(|candidate| candidate.text).collect()
+}
+
+fn hides(candidate: &str, line: &str) -> bool {
+    let syntax = Syntax { regex: true, ..Syntax::default() };
+
+    SearchPattern::new(candidate, false).with_syntax(syntax).is_match(line)
+}
+
+#[test]
+fn wildcards_numbers_hex_values_and_uuids() {
+    let cases = [("took 42ms", r"took \d+ms"),
+                 ("GET /users/17 200 0.35s", r"GET /users/\d+ \d+ \d+\.\d+s"),
+                 ("session 3f2a9c1e7b6d started", "session [0-9a-fA-F]+ started"),
+                 ("ptr=0xDEADBEEF", "ptr=0x[0-9a-fA-F]+"),
+                 ("request 123e4567-e89b-12d3-a456-426614174000 done",
+                  "request [0-9a-fA-F-]{36} done"),
+                 ("deadbeefcafe is a word", "deadbeefcafe is a word"),
+                 ("a.b [x] (y)?", r"a\.b \[x\] \(y\)\?")];
+
+    for &(line, expected) in &cases {
+        assert_eq!(wildcard(line), expected, "for `{}`", line);
+    }
+}
+
+#[test]
+fn offers_exact_wildcarded_and_leading_words() {
+    let line = "2016-03-01 14:32:05 WARN pool exhausted after 30 retries";
+    let exact = r"/^2016\-03\-01 14:32:05 WARN pool exhausted after 30 retries$/";
+
+    assert_eq!(candidates(line),
+               vec![Candidate {
+                        label: "exact",
+                        text: exact.to_string(),
+                    },
+                    Candidate {
+                        label: "any numbers",
+                        text: r"/^\d+\-\d+\-\d+ \d+:\d+:\d+ WARN pool exhausted after \d+ retries$/"
+                            .to_string(),
+                    },
+                    Candidate {
+                        label: "first words",
+                        text: r"/^\d+\-\d+\-\d+ \d+:\d+:\d+ WARN/".to_string(),
+                    }]);
+}
+
+#[test]
+fn leaves_out_candidates_matching_like_others() {
+    assert_eq!(texts("healthcheck ok"), vec!["/^healthcheck ok$/"]);
+    assert_eq!(texts("   "), Vec::<String>::new());
+}
+
+#[test]
+fn candidates_hide_the_line_and_its_siblings() {
+    let line = "GET /api/v2/items/981 NOT FOUND in 12ms";
+    let sibling = "GET /api/v2/items/5 NOT FOUND in 3ms";
+    let found = texts(line);
+
+    assert!(found.iter().all(|candidate| hides(candidate, line)));
+    assert!(!hides(&found[0], sibling));
+    assert!(hides(&found[1], sibling));
+    assert!(hides(&found[2], "GET /api/v2/items/5 NOT ACCEPTABLE"));
+    assert!(!hides(&found[2], "POST /api/v2/items/5 NOT FOUND"));
+}
+
+#[test]
+fn candidates_hide_lines_with_trailing_whitespace() {
+    let line = "GET /api/v2/items/981 NOT FOUND in 12ms \t";
+    let found = texts(line);
+
+    assert_eq!(3, found.len());
+    assert!(found.iter().all(|candidate| hides(candidate, line)));
+    assert!(hides(&found[1], "GET /api/v2/items/5 NOT FOUND in 3ms \t"));
+}
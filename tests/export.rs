@@ -0,0 +1,154 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::export::{write_matches, ExportFormat};
+use flow::core::highlights::Highlights;
+use flow::core::line::{Line, LineCollection, SearchPattern};
+
+fn lines(values: &[&str]) -> Vec<Line> {
+    values.iter().enumerate().map(|(i, value)| Line::with_id(value.to_string(), i + 1)).collect()
+}
+
+fn export(values: &[&str], query: &str, context: usize) -> (String, usize) {
+    export_as(values, query, context, ExportFormat::Plain)
+}
+
+fn export_as(values: &[&str],
+             query: &str,
+             context: usize,
+             format: ExportFormat)
+             -> (String, usize) {
+    let mut out = vec![];
+    let count = write_matches(&mut out,
+                              |_| "app.log".to_string(),
+                              lines(values),
+                              &SearchPattern::new(query, false),
+                              context,
+                              format,
+                              &Highlights::new())
+        .unwrap();
+
+    (String::from_utf8(out).unwrap(), count)
+}
+
+#[test]
+fn writes_matches_like_grep() {
+    let (output, count) = export(&["GET /", "ERROR boom", "GET /a", "ERROR bang"], "ERROR", 0);
+
+    assert_eq!(output, "app.log:2:ERROR boom\napp.log:4:ERROR bang\n");
+    assert_eq!(count, 2);
+}
+
+#[test]
+fn writes_context_lines_with_separators() {
+    let values = ["a", "b", "ERROR 1", "c", "d", "e", "f", "ERROR 2", "ERROR 3", "g"];
+    let (output, count) = export(&values, "ERROR", 1);
+
+    assert_eq!(output,
+               "app.log-2-b\napp.log:3:ERROR 1\napp.log-4-c\n--\napp.log-7-f\napp.log:8:ERROR 2\n\
+                app.log:9:ERROR 3\napp.log-10-g\n");
+    assert_eq!(count, 3);
+}
+
+#[test]
+fn names_and_numbers_lines_by_the_file_they_came_from() {
+    let mut collection = LineCollection::new(10);
+    collection.extend(vec![(1, "ERROR a".to_string()),
+                           (2, "b".to_string()),
+                           (2, "c".to_string()),
+                           (1, "d".to_string()),
+                           (2, "ERROR e".to_string())]);
+
+    let mut out = vec![];
+    let count = write_matches(&mut out,
+                              |source| format!("app-{}.log", source),
+                              collection.entries.iter().cloned(),
+                              &SearchPattern::new("ERROR", false),
+                              1,
+                              ExportFormat::Plain,
+                              &Highlights::new())
+        .unwrap();
+
+    let expected = "app-1.log:1:ERROR a\napp-2.log-1-b\n--\napp-1.log-2-d\napp-2.log:3:ERROR e\n";
+    assert_eq!(String::from_utf8(out).unwrap(), expected);
+    assert_eq!(count, 2);
+}
+
+#[test]
+fn merges_overlapping_context() {
+    let (output, _) = export(&["ERROR 1", "a", "b", "ERROR 2"], "ERROR", 2);
+
+    assert_eq!(output, "app.log:1:ERROR 1\napp.log-2-a\napp.log-3-b\napp.log:4:ERROR 2\n");
+}
+
+#[test]
+fn supports_inverse_searches() {
+    let (output, count) = export(&["200 OK", "500 Error", "200 OK"], "!200", 0);
+
+    assert_eq!(output, "app.log:2:500 Error\n");
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn ansi_exports_keep_the_colors_of_lines() {
+    let values = ["\x1b[1;31mERROR\x1b[0m boom", "ok"];
+    let (output, _) = export_as(&values, "ERROR", 0, ExportFormat::Ansi);
+
+    assert_eq!(output, "app.log:1:\x1b[0;1;31mERROR\x1b[0m boom\n");
+}
+
+#[test]
+fn ansi_exports_paint_highlights() {
+    let mut highlights = Highlights::new();
+    highlights.toggle("boom").unwrap();
+    let line = Line::with_id("ERROR boom".to_string(), 1);
+
+    assert_eq!(ExportFormat::Ansi.render(&line, &highlights),
+               "ERROR \x1b[0;30;46mboom\x1b[0m");
+    assert_eq!(ExportFormat::Plain.render(&line, &highlights), "ERROR boom");
+}
+
+#[test]
+fn ansi_exports_tint_whole_lines_below_highlights() {
+    let mut highlights = Highlights::new();
+    highlights.toggle("boom").unwrap();
+    highlights.toggle("line:ERROR").unwrap();
+    let line = Line::with_id("ERROR boom".to_string(), 1);
+
+    assert_eq!(ExportFormat::Ansi.render(&line, &highlights),
+               "\x1b[0;30;42mERROR \x1b[0;30;46mboom\x1b[0m");
+}
+
+#[test]
+fn html_exports_escape_and_style_spans() {
+    let (output, _) = export_as(&["\x1b[4;32m<ok>\x1b[0m & done"], "done", 0, ExportFormat::Html);
+
+    assert_eq!(output,
+               "app.log:1:<span style=\"color:#00cd00;text-decoration:underline\">\
+                &lt;ok&gt;</span> &amp; done\n");
+}
+
+#[test]
+fn parses_export_formats() {
+    assert_eq!(Some(ExportFormat::Ansi), ExportFormat::parse("ansi"));
+    assert_eq!(Some(ExportFormat::Html), ExportFormat::parse("html"));
+    assert_eq!(None, ExportFormat::parse("pdf"));
+    assert_eq!("html", ExportFormat::Html.extension());
+}
@@ -0,0 +1,160 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use std::time::Instant;
+
+use flow::core::expression::{self, ParseError};
+use flow::core::line::{Line, SearchPattern};
+use flow::core::query::{CompiledQuery, Syntax};
+
+const SYNTAX: Syntax = Syntax { regex: true, expressions: true };
+
+#[test]
+fn leaves_text_without_operators_to_single_terms() {
+    assert!(expression::parse("payment failed (retrying)", false, SYNTAX).is_none());
+    assert!(expression::parse("ANDROID or not", false, SYNTAX).is_none());
+    assert!(!CompiledQuery::new("error", false, SYNTAX).is_expression());
+}
+
+#[test]
+fn combines_terms() {
+    let text = "error AND (payment OR checkout) NOT healthcheck";
+    let query = CompiledQuery::new(text, false, SYNTAX);
+
+    assert!(query.is_expression());
+    assert!(query.is_found("error in payment"));
+    assert!(query.is_found("checkout error"));
+    assert!(!query.is_found("error in search"));
+    assert!(!query.is_found("healthcheck error in payment"));
+}
+
+#[test]
+fn keeps_spaces_within_terms() {
+    let query = CompiledQuery::new("connection reset OR timed out", false, SYNTAX);
+
+    assert!(query.is_found("db: connection reset by peer"));
+    assert!(!query.is_found("reset connection"));
+}
+
+#[test]
+fn parses_regex_and_quoted_terms() {
+    let query = CompiledQuery::new(r#"/in \d+ (ms|s)/ AND "a (b)""#, false, SYNTAX);
+
+    assert!(query.is_found("a (b) in 12 ms"));
+    assert!(!query.is_found("a b in 12 ms"));
+}
+
+#[test]
+fn highlights_terms_that_are_not_negated() {
+    let query = CompiledQuery::new("error AND payment NOT debug", false, SYNTAX);
+
+    assert_eq!(vec![(0, "error"), (9, "payment")], query.occurrences("error in payment"));
+    assert!(query.occurrences("debug error in payment").is_empty());
+}
+
+#[test]
+fn spans_the_line_without_positive_terms() {
+    let query = CompiledQuery::new("NOT healthcheck", false, SYNTAX);
+
+    assert_eq!(vec![(0, "GET /")], query.occurrences("GET /"));
+}
+
+#[test]
+fn applies_whole_word_to_each_term() {
+    let query = CompiledQuery::new("id OR user", true, SYNTAX);
+
+    assert!(query.is_found("user 1"));
+    assert!(!query.is_found("valid users"));
+}
+
+#[test]
+fn reports_where_parsing_failed() {
+    let error = |text| expression::parse(text, false, SYNTAX).unwrap().err().unwrap();
+
+    assert_eq!(ParseError { position: 17, message: "expected )" },
+               error("error AND (a OR b"));
+    assert_eq!(ParseError { position: 9, message: "expected a term" }, error("error AND"));
+    assert_eq!(ParseError { position: 1, message: "unexpected )" }, error("a) OR b"));
+    assert_eq!("expected ) at column 18", error("error AND (a OR b").to_string());
+}
+
+#[test]
+fn counts_columns_in_characters() {
+    let error = expression::parse("café AND (thé", false, SYNTAX).unwrap().err().unwrap();
+
+    assert_eq!(ParseError { position: 13, message: "expected )" }, error);
+}
+
+#[test]
+fn searches_operators_literally_without_expressions() {
+    let pattern = SearchPattern::new("404 NOT FOUND", false);
+
+    assert!(expression::parse("404 NOT FOUND", false, Syntax::default()).is_none());
+    assert!(pattern.error().is_none());
+    assert!(pattern.is_match("GET /items 404 NOT FOUND"));
+    assert!(!pattern.is_match("GET /items 404"));
+}
+
+#[test]
+fn searches_literally_while_invalid() {
+    let pattern = SearchPattern::new("error AND", false).with_syntax(SYNTAX);
+
+    assert!(pattern.error().is_some());
+    assert!(pattern.is_match("error AND"));
+    assert!(!pattern.is_match("error"));
+}
+
+fn elapsed_nanos(started_at: Instant) -> u64 {
+    let elapsed = started_at.elapsed();
+
+    elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64
+}
+
+fn search(lines: &[Line], query: &str) -> (usize, u64) {
+    let pattern = SearchPattern::new(query, false).with_syntax(SYNTAX);
+    let started_at = Instant::now();
+    let matches = lines.iter().map(|line| line.matches_for(&pattern).len()).sum::<usize>();
+
+    (matches, elapsed_nanos(started_at))
+}
+
+// Compares searching 100k lines for a single term with searching for it
+// within an expression, so that single terms aren't slowed down by them. Run
+// with `cargo test --release --test expression -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn single_terms_are_not_slower_than_expressions() {
+    let lines = (0..100_000)
+        .map(|i| Line::new(format!("2016-03-01 10:20:30 [worker-{}] GET /api/items/{} {} in {}ms",
+                                   i % 8,
+                                   i,
+                                   if i % 13 == 0 { 500 } else { 200 },
+                                   i % 97)))
+        .collect::<Vec<_>>();
+
+    let (single_matches, single) = search(&lines, "GET");
+    let (expression_matches, expression) = search(&lines, "GET AND NOT healthcheck");
+
+    println!("single term: {}us, expression: {}us",
+             single / 1000,
+             expression / 1000);
+    assert_eq!(single_matches, expression_matches);
+    assert!(single <= expression);
+}
@@ -0,0 +1,41 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::fields::{self, FieldSpan};
+
+fn span(name: &str, start: usize, end: usize) -> FieldSpan {
+    FieldSpan {
+        name: name.to_string(),
+        start: start,
+        end: end,
+    }
+}
+
+#[test]
+fn finds_key_value_pairs() {
+    assert_eq!(fields::find("user=ann msg=\"a b\""),
+               vec![span("user", 5, 8), span("msg", 14, 17)]);
+}
+
+#[test]
+fn finds_json_members() {
+    assert_eq!(fields::find(r#"{"msg": "done", "count": 3}"#),
+               vec![span("msg", 9, 13), span("count", 25, 26)]);
+}
@@ -136,7 +136,7 @@ fn having_content_constraint_matches_against_provided_value() {
                      Line::new("Folklore".to_string())];
     let actual = lines.iter()
         .parse(filter)
-        .map(|line| line.content_without_ansi.clone())
+        .map(|line| line.content_without_ansi.to_string())
         .collect::<Vec<_>>();
     let expected = vec!["Folklore", "Lorem ipsum"];
     assert_eq!(expected, actual);
@@ -331,7 +331,7 @@ fn filters_entries_by_main_content_and_boundary_without_matcher() {
 fn assert_line_content(filter: Filter, expected: Vec<&str>) {
     let actual = LINES.iter()
         .parse(filter)
-        .map(|line| line.content_without_ansi.clone())
+        .map(|line| line.content_without_ansi.to_string())
         .collect::<Vec<_>>();
     assert_eq!(expected, actual);
 }
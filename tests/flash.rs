@@ -0,0 +1,72 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::ui::flash::{Flash, FLASH_DURATION};
+
+#[test]
+fn does_nothing_unless_enabled() {
+    let mut flash = Flash::default();
+
+    assert!(!flash.arrive(1, 0));
+}
+
+#[test]
+fn flashes_lines_on_quiet_logs() {
+    let mut flash = Flash::new(Some(5));
+
+    assert!(flash.arrive(1, 1_000));
+    flash.start(&[7], 1_000);
+    assert!(flash.is_flashed(7));
+    assert!(!flash.is_flashed(6));
+}
+
+#[test]
+fn stops_flashing_busy_logs() {
+    let mut flash = Flash::new(Some(5));
+
+    assert!(flash.arrive(3, 0));
+    assert!(flash.arrive(2, 10_000));
+    assert!(!flash.arrive(1, 20_000));
+    assert!(!flash.arrive(10, 30_000));
+}
+
+#[test]
+fn forgets_arrivals_older_than_a_minute() {
+    let mut flash = Flash::new(Some(5));
+
+    assert!(!flash.arrive(10, 0));
+    assert!(!flash.arrive(1, 59_999));
+    assert!(flash.arrive(1, 60_000));
+}
+
+#[test]
+fn expires_after_a_second() {
+    let mut flash = Flash::new(Some(5));
+    flash.start(&[1], 0);
+    flash.start(&[2], 500);
+
+    assert!(!flash.expire(FLASH_DURATION - 1));
+    assert!(flash.expire(FLASH_DURATION));
+    assert!(!flash.is_flashed(1));
+    assert!(flash.is_flashed(2));
+
+    assert!(flash.expire(500 + FLASH_DURATION));
+    assert!(!flash.expire(10_000));
+}
@@ -0,0 +1,141 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+// Runs the fixtures in tests/fixtures through the rendering math and compares
+// the result with the checked-in `.golden` files. Set FLOW_UPDATE_GOLDENS=1 to
+// write the current output as the new expectation.
+
+extern crate flow;
+
+use std::env;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use flow::core::buffer::Buffer;
+use flow::core::filter::Filter;
+use flow::core::line::{LineCollection, SearchPattern};
+use flow::ui::highlighter::{DisplayedText, match_segments};
+use flow::utils::invisibles::escape_nul;
+use flow::utils::wrap::wrap;
+
+static WIDTH: usize = 80;
+static UPDATE_VARIABLE: &'static str = "FLOW_UPDATE_GOLDENS";
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name)
+}
+
+fn read(path: &PathBuf) -> String {
+    let mut contents = String::new();
+    File::open(path).unwrap().read_to_string(&mut contents).unwrap();
+
+    contents
+}
+
+fn render(contents: &str, query: &str) -> String {
+    let mut lines = LineCollection::new(1000);
+    lines.extend(contents.lines().map(|line| line.to_string()));
+
+    let buffer = Buffer::new(Filter {
+        name: "All".to_string(),
+        content: None,
+        start: None,
+        end: None,
+        rewrites: vec![],
+        line_rules: vec![],
+        alerts: vec![],
+    });
+    let mut buffer_lines = buffer.with_lines(&lines);
+    buffer_lines.width = WIDTH;
+
+    let mut report = String::new();
+
+    for line in &buffer_lines {
+        let text = &line.content_without_ansi;
+        let rows = wrap(text, WIDTH);
+
+        report.push_str(&format!("line {} height {} width {}\n", line.id, rows.len(), line.width));
+        for row in &rows {
+            report.push_str(&format!("  |{}|\n", escape_nul(&text[row.clone()])));
+        }
+
+        let displayed = DisplayedText::new(text, false);
+        for (offset, value) in line.matches_for(&SearchPattern::new(query, false)) {
+            let range = displayed.range(offset..offset + value.len());
+
+            for segment in match_segments(&displayed.text, range, WIDTH) {
+                report.push_str(&format!("  match at byte {} row {} column {} {:?}\n",
+                                         offset,
+                                         segment.row,
+                                         segment.column,
+                                         &displayed.text[segment.range]));
+            }
+        }
+    }
+
+    report
+}
+
+fn assert_golden(name: &str, query: &str) {
+    let actual = render(&read(&fixture_path(&format!("{}.log", name))), query);
+    let golden_path = fixture_path(&format!("{}.golden", name));
+
+    if env::var_os(UPDATE_VARIABLE).is_some() {
+        File::create(&golden_path).unwrap().write_all(actual.as_bytes()).unwrap();
+        return;
+    }
+
+    let expected = read(&golden_path);
+    assert!(expected == actual,
+            "{} differs from {:?}, rerun with {}=1 if intended:\n{}",
+            name,
+            golden_path,
+            UPDATE_VARIABLE,
+            actual);
+}
+
+#[test]
+fn multibyte() {
+    assert_golden("multibyte", "error");
+}
+
+#[test]
+fn ansi() {
+    assert_golden("ansi", "error");
+}
+
+#[test]
+fn json() {
+    assert_golden("json", "\"error\"");
+}
+
+#[test]
+fn stacktrace() {
+    assert_golden("stacktrace", "in `");
+}
+
+#[test]
+fn long_line() {
+    assert_golden("long_line", "needle");
+}
+
+#[test]
+fn crlf() {
+    assert_golden("crlf", "line");
+}
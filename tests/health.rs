@@ -0,0 +1,70 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::health::{Health, HealthBoard, HealthChange};
+use flow::core::replay::Replay;
+
+fn change(source: &str, health: Health, detail: &str) -> HealthChange {
+    HealthChange::new(source, health, detail.to_string())
+}
+
+#[test]
+fn reports_only_changed_states() {
+    let mut board = HealthBoard::default();
+
+    assert_eq!(board.update(vec![change("a.log", Health::Active, "Tailing `a.log`")]).len(), 1);
+    assert!(board.update(vec![change("a.log", Health::Active, "Tailing `a.log`")]).is_empty());
+
+    let changed = board.update(vec![change("a.log", Health::Active, "Tailing `a.log`"),
+                                    change("b.log", Health::Errored, "`b.log` is gone")]);
+    assert_eq!(changed, vec![change("b.log", Health::Errored, "`b.log` is gone")]);
+    assert!(board.knows("b.log"));
+    assert!(!board.knows("c.log"));
+}
+
+#[test]
+fn tells_about_the_most_worrying_source() {
+    let mut board = HealthBoard::default();
+    assert_eq!(board.overall(), None);
+    assert_eq!(board.describe(), None);
+
+    board.update(vec![change("a.log", Health::Active, "Tailing `a.log`")]);
+    assert_eq!(board.overall(), Some(Health::Active));
+    assert_eq!(board.describe(), Some("Tailing `a.log`".to_string()));
+
+    board.update(vec![change("b.log", Health::Ended, "`b.log` was removed"),
+                      change("c.log", Health::Waiting, "Retrying `c.log`")]);
+    assert_eq!(board.overall(), Some(Health::Waiting));
+    assert_eq!(board.describe(),
+               Some("1 of 3 sources waiting - Retrying `c.log`".to_string()));
+
+    board.update(vec![change("c.log", Health::Errored, "`c.log` couldn't be read")]);
+    assert_eq!(board.overall(), Some(Health::Errored));
+}
+
+#[test]
+fn replays_end_once_played_back() {
+    let mut replay = Replay::new(vec!["first".to_string(), "second".to_string()], 1000);
+    assert_eq!(replay.health().health, Health::Active);
+
+    replay.tick(0);
+    replay.tick(10_000);
+    assert_eq!(replay.health().health, Health::Ended);
+}
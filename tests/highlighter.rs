@@ -0,0 +1,162 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::line::{Line, SearchPattern};
+use flow::ui::highlighter::{ByteOffset, DisplayedText, MatchSegment, MatchSpan, RowOffset,
+                            MAX_PAINTED_PER_ROW, match_segments, match_segments_indented,
+                            match_spans, merge_ranges, painted_matches};
+
+fn segments(text: &str, value: &str, width: usize) -> Vec<(usize, usize, String)> {
+    let start = text.find(value).unwrap();
+
+    match_segments(text, start..start + value.len(), width)
+        .into_iter()
+        .map(|MatchSegment { row, column, range }| (row, column, text[range].to_string()))
+        .collect()
+}
+
+fn span(start: usize, end: usize, row: usize) -> MatchSpan {
+    MatchSpan {
+        start: ByteOffset(start),
+        end: ByteOffset(end),
+        row: RowOffset(row),
+    }
+}
+
+#[test]
+fn match_starting_at_a_wrap_boundary() {
+    assert_eq!(vec![(1, 0, "efg".to_string())], segments("abcdefgh", "efg", 4));
+}
+
+#[test]
+fn match_ending_at_a_wrap_boundary() {
+    assert_eq!(vec![(0, 1, "bcd".to_string())], segments("abcdefgh", "bcd", 4));
+}
+
+#[test]
+fn match_straddling_a_wrap_boundary() {
+    assert_eq!(vec![(0, 2, "cd".to_string()), (1, 0, "ef".to_string())],
+               segments("abcdefgh", "cdef", 4));
+}
+
+#[test]
+fn match_spanning_several_rows() {
+    assert_eq!(vec![(0, 3, "d".to_string()), (1, 0, "efgh".to_string()), (2, 0, "ij".to_string())],
+               segments("abcdefghijkl", "defghij", 4));
+}
+
+#[test]
+fn wide_characters_moved_to_the_next_row() {
+    // The last column of the first row stays empty, as "日" doesn't fit
+    let text = "abc日本語";
+
+    assert_eq!(vec![(0, 2, "c".to_string()), (1, 0, "日".to_string())],
+               segments(text, "c日", 4));
+    assert_eq!(vec![(1, 2, "本".to_string()), (2, 0, "語".to_string())],
+               segments(text, "本語", 4));
+}
+
+#[test]
+fn tabs_advance_to_the_next_stop() {
+    assert_eq!(vec![(0, 8, "b".to_string())], segments("a\tb", "b", 20));
+}
+
+#[test]
+fn wrapped_occurrences_are_counted_once() {
+    let line = Line::new("..needle.. needle".to_string());
+    let pattern = SearchPattern::new("needle", false);
+
+    // The dots end the first row, so the first occurrence takes the next two
+    assert_eq!(2, segments(&line.content_without_ansi, "needle", 3).len());
+    assert_eq!(vec![span(2, 8, 1), span(11, 17, 4)],
+               match_spans(&line, &pattern, 3, false));
+}
+
+#[test]
+fn wrapped_occurrences_without_separators_are_counted_once() {
+    let line = Line::new("xxneedlexx needle".to_string());
+    let pattern = SearchPattern::new("needle", false);
+
+    // The first occurrence is painted across three rows
+    assert_eq!(3, segments(&line.content_without_ansi, "needle", 3).len());
+    assert_eq!(vec![span(2, 8, 0), span(11, 17, 3)],
+               match_spans(&line, &pattern, 3, false));
+}
+
+#[test]
+fn spans_keep_raw_offsets_when_invisibles_are_shown() {
+    let line = Line::new("\tkey value".to_string());
+    let pattern = SearchPattern::new("value", false);
+
+    assert_eq!(vec![span(5, 10, 0)],
+               match_spans(&line, &pattern, 80, true));
+}
+
+#[test]
+fn continuation_segments_start_after_the_prefix() {
+    let text = "abcdefgh";
+    let start = text.find("def").unwrap();
+
+    let segments = match_segments_indented(text, start..start + 3, 4, 2)
+        .into_iter()
+        .map(|MatchSegment { row, column, range }| (row, column, text[range].to_string()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(vec![(0, 3, "d".to_string()), (1, 2, "ef".to_string())], segments);
+}
+
+#[test]
+fn merges_touching_and_overlapping_ranges() {
+    assert_eq!(vec![0..5, 7..9], merge_ranges(vec![3..5, 0..2, 2..4, 7..9, 8..8]));
+    assert!(merge_ranges(vec![4..4]).is_empty());
+}
+
+#[test]
+fn paints_adjacent_matches_as_one_run() {
+    let text = "eeeeeeee";
+    let displayed = DisplayedText::new(text, false);
+    let matches = text.match_indices("e").collect::<Vec<_>>();
+
+    let painted = painted_matches(&displayed, &matches, 3, 0);
+
+    assert_eq!(8, painted.spans.len());
+    assert_eq!(vec![0, 0, 0, 1, 1, 1, 2, 2],
+               painted.spans.iter().map(|span| span.row.0).collect::<Vec<_>>());
+    assert_eq!(3, painted.segments.len());
+    assert_eq!(vec![0..3, 3..6, 6..8],
+               painted.segments.iter().map(|segment| segment.range.clone()).collect::<Vec<_>>());
+    assert_eq!(0, painted.truncated);
+}
+
+#[test]
+fn paints_dense_matches_with_calls_bounded_by_rows() {
+    let text = "e ".repeat(5_000);
+    let displayed = DisplayedText::new(&text, false);
+    let matches = text.match_indices("e").collect::<Vec<_>>();
+    let width = 1_000;
+
+    let painted = painted_matches(&displayed, &matches, width, 0);
+
+    // Each row holds 500 matches, of which only the first ones are painted
+    let rows = 10;
+    assert_eq!(5_000, painted.spans.len());
+    assert_eq!(rows * MAX_PAINTED_PER_ROW, painted.segments.len());
+    assert_eq!(5_000 - rows * MAX_PAINTED_PER_ROW, painted.truncated);
+}
@@ -0,0 +1,213 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Instant;
+
+use flow::core::highlights::{Highlights, HighlightPattern, HighlightSpan, Layers, Scope};
+use flow::core::line::LineCollection;
+use flow::core::tail::Tail;
+
+fn span(pattern: usize, start: usize, end: usize) -> HighlightSpan {
+    HighlightSpan {
+        pattern: pattern,
+        start: start,
+        end: end,
+        scope: Scope::Match,
+    }
+}
+
+#[test]
+fn finds_every_occurrence_of_each_pattern() {
+    let mut highlights = Highlights::new();
+    highlights.toggle("GET").unwrap();
+    highlights.toggle("/\\d{3} /").unwrap();
+    highlights.toggle("a.b").unwrap();
+
+    assert_eq!(vec![span(0, 0, 3), span(0, 9, 12), span(1, 13, 17)],
+               highlights.spans("GET /ab, GET 404 axb"));
+    assert_eq!(vec![span(2, 0, 3)], highlights.spans("a.b"));
+    assert!(highlights.spans("nothing here").is_empty());
+}
+
+#[test]
+fn plain_occurrences_of_a_pattern_do_not_overlap() {
+    let mut highlights = Highlights::new();
+    highlights.toggle("aa").unwrap();
+    highlights.toggle("/b+/").unwrap();
+    highlights.toggle("ab").unwrap();
+
+    assert_eq!(vec![span(0, 0, 2), span(0, 2, 4), span(1, 5, 6), span(2, 4, 6)],
+               highlights.spans("aaaaab"));
+}
+
+#[test]
+fn scopes_prefixed_patterns_to_whole_lines() {
+    let pattern = HighlightPattern::parse("line:/ERROR|FATAL/").unwrap();
+    assert_eq!(pattern.scope, Scope::Line);
+    assert_eq!(pattern.source, "line:/ERROR|FATAL/");
+    assert_eq!(HighlightPattern::parse("ERROR").unwrap().scope, Scope::Match);
+    assert!(HighlightPattern::parse("line:").is_err());
+
+    let mut highlights = Highlights::new();
+    highlights.toggle("line:FATAL").unwrap();
+    let spans = highlights.spans("FATAL disk full");
+    assert_eq!(spans[0].scope, Scope::Line);
+    assert_eq!((spans[0].start, spans[0].end), (0, 5));
+}
+
+#[test]
+fn tints_lines_below_matches() {
+    let mut highlights = Highlights::new();
+    highlights.toggle("disk").unwrap();
+    highlights.toggle("line:ERROR").unwrap();
+    highlights.toggle("line:/full/").unwrap();
+
+    let spans = highlights.spans("ERROR disk full");
+    let layers = Layers::new(&spans);
+
+    // The first pattern scoped to lines tints it, the others aren't painted
+    assert_eq!(layers.tint, Some(1));
+    assert_eq!(layers.spans, vec![&span(0, 6, 10)]);
+
+    let spans = highlights.spans("disk ok");
+    assert_eq!(Layers::new(&spans).tint, None);
+}
+
+#[test]
+fn toggling_removes_existing_patterns() {
+    let mut highlights = Highlights::new();
+
+    assert_eq!(Ok(true), highlights.toggle("error"));
+    assert_eq!(Ok(true), highlights.toggle("warn"));
+    assert_eq!(Ok(false), highlights.toggle("error"));
+    assert_eq!(vec!["warn"], highlights.sources());
+    assert_eq!(vec![span(0, 0, 4)], highlights.spans("warn"));
+
+    assert!(highlights.toggle("/(/").is_err());
+    assert!(highlights.toggle("").is_err());
+}
+
+#[test]
+fn disabled_patterns_keep_their_place() {
+    let mut highlights = Highlights::new();
+    highlights.toggle("GET").unwrap();
+    highlights.toggle("404").unwrap();
+    let version = highlights.version;
+
+    assert_eq!(Some(false), highlights.toggle_enabled(0));
+    assert!(highlights.version > version);
+    assert_eq!(vec![span(1, 4, 7)], highlights.spans("GET 404"));
+    assert_eq!(vec!["GET", "404"], highlights.sources());
+
+    assert_eq!(Some(false), highlights.toggle_enabled(1));
+    assert!(highlights.spans("GET 404").is_empty());
+
+    assert_eq!(Some(true), highlights.toggle_enabled(0));
+    assert_eq!(Some(true), highlights.toggle_enabled(1));
+    assert_eq!(vec![span(0, 0, 3), span(1, 4, 7)], highlights.spans("GET 404"));
+    assert_eq!(None, highlights.toggle_enabled(2));
+}
+
+#[test]
+fn finds_which_patterns_a_line_holds() {
+    let mut highlights = Highlights::new();
+    for source in &["one", "/\\d+/", "ERROR", "/x*/", "fine"] {
+        highlights.toggle(source).unwrap();
+    }
+
+    assert_eq!(vec![0, 1, 2], highlights.hits("ERROR one 42 one"));
+    // Patterns matching nothing but empty text aren't found, as in spans
+    assert_eq!(vec![4], highlights.hits("fine"));
+    assert!(highlights.hits("").is_empty());
+
+    highlights.toggle_enabled(0);
+    assert_eq!(vec![1, 2], highlights.hits("ERROR one 42 one"));
+}
+
+fn highlight(lines: &[Arc<str>], sources: &[&str]) -> u64 {
+    let mut highlights = Highlights::new();
+    for source in sources {
+        highlights.toggle(source).unwrap();
+    }
+
+    let started_at = Instant::now();
+    let found = lines.iter().map(|line| highlights.spans(line).len()).sum::<usize>();
+    let elapsed = started_at.elapsed();
+    assert!(found < lines.len());
+
+    elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64
+}
+
+fn ingest(path: &str, highlights: &[&str]) -> u64 {
+    let mut lines = LineCollection::new(3000);
+    for source in highlights {
+        lines.highlights.toggle(source).unwrap();
+    }
+
+    let started_at = Instant::now();
+    let batch = Tail::new(path.to_string()).read_to_end();
+    for chunk in batch.chunks(1000) {
+        lines.extend(chunk.iter().map(|line| (0, line.clone())));
+    }
+    let elapsed = started_at.elapsed();
+
+    elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64
+}
+
+// Reads 100k lines from a file and stores them the way they're tailed,
+// reporting how much 10 plain highlights add to it. Run with
+// `cargo test --release --test highlights -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn ingestion_cost_of_plain_highlights() {
+    let path = env::temp_dir().join("flow-highlights-stress.log");
+    let mut file = File::create(&path).unwrap();
+    for i in 0..100_000 {
+        writeln!(file,
+                 "2016-03-01 10:20:30 [worker-{}] INFO GET /api/items/{} 200 OK in {}ms",
+                 i % 8,
+                 i,
+                 i % 97)
+            .unwrap();
+    }
+
+    let path = path.to_str().unwrap();
+    let patterns = ["ERROR", "WARN", "timeout", "panic", "refused", "worker-9", "/admin", "500 ",
+                    "deadlock", "retrying"];
+
+    let without = (0..5).map(|_| ingest(path, &[])).min().unwrap();
+    let with = (0..5).map(|_| ingest(path, &patterns)).min().unwrap();
+
+    println!("without: {}us, with 10 highlights: {}us (+{}%)",
+             without / 1000,
+             with / 1000,
+             (with - without.min(with)) * 100 / without);
+
+    let lines = Tail::new(path.to_string()).read_to_end();
+    let regexes = patterns.iter().map(|pattern| format!("/{}/", pattern)).collect::<Vec<_>>();
+    let regexes = regexes.iter().map(|pattern| pattern.as_str()).collect::<Vec<_>>();
+    println!("looking for them alone: {}ns per line as text, {}ns as regexes",
+             (0..5).map(|_| highlight(&lines, &patterns)).min().unwrap() / lines.len() as u64,
+             (0..5).map(|_| highlight(&lines, &regexes)).min().unwrap() / lines.len() as u64);
+}
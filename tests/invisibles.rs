@@ -0,0 +1,74 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::line::Line;
+use flow::ui::highlighter::{DisplayedText, match_segments};
+use flow::utils::invisibles::{reveal, trailing_whitespace_offset, escape_nul};
+
+#[test]
+fn reveals_tabs_and_no_break_spaces() {
+    let text = "key\tvalue\u{a0}unit";
+    let offset = trailing_whitespace_offset(text);
+
+    assert_eq!("key→value⍽unit", reveal(text, 0, offset));
+}
+
+#[test]
+fn reveals_only_trailing_spaces() {
+    let text = "lorem ipsum  ";
+    let offset = trailing_whitespace_offset(text);
+
+    assert_eq!(11, offset);
+    assert_eq!("lorem ipsum··", reveal(text, 0, offset));
+}
+
+#[test]
+fn reveals_slices_relative_to_the_whole_line() {
+    let text = "a b \t";
+    let offset = trailing_whitespace_offset(text);
+
+    assert_eq!("b·→", reveal(&text[2..], 2, offset));
+}
+
+#[test]
+fn escapes_nul_bytes_keeping_the_raw_line() {
+    let line = Line::new("ab\0cd".to_string());
+
+    assert_eq!("ab\0cd", &*line.content_without_ansi);
+    assert_eq!(5, line.width);
+    assert_eq!("ab␀cd", escape_nul(&line.content_without_ansi));
+}
+
+#[test]
+fn places_matches_after_nul_bytes() {
+    let text = "ab\0\0cd";
+
+    for &show_invisibles in &[false, true] {
+        let displayed = DisplayedText::new(text, show_invisibles);
+        let (offset, value) = text.match_indices("\0c").next().unwrap();
+        let range = displayed.range(offset..offset + value.len());
+
+        let segments = match_segments(&displayed.text, range, 80);
+
+        assert_eq!(1, segments.len());
+        assert_eq!(3, segments[0].column);
+        assert_eq!("␀c", &displayed.text[segments[0].range.clone()]);
+    }
+}
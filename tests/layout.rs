@@ -0,0 +1,173 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::ui::layout::{Area, Layout, Widget, WidgetMap, MIN_WIDTH, scroll_to_show};
+use flow::ui::search::field_widths;
+
+#[test]
+fn lays_out_normal_terminals() {
+    let layout = Layout::new(80, 24, false);
+
+    assert_eq!(layout.content_rows, 23);
+    assert_eq!(layout.content_offset(), 0);
+    assert_eq!(layout.navigation_offset(), 23);
+    assert_eq!(layout.content_row(0), Some(0));
+    assert_eq!(layout.content_row(22), Some(22));
+    assert_eq!(layout.content_row(23), None);
+}
+
+#[test]
+fn takes_ruler_from_content() {
+    let layout = Layout::new(80, 24, true);
+
+    assert_eq!(layout.ruler_rows, 1);
+    assert_eq!(layout.content_rows, 22);
+    assert_eq!(layout.navigation_offset(), 23);
+    assert_eq!(layout.content_row(0), None);
+    assert_eq!(layout.content_row(1), Some(0));
+    assert_eq!(layout.content_row(23), None);
+}
+
+#[test]
+fn keeps_navigation_on_last_row_after_shrinking() {
+    let tall = Layout::new(80, 40, false);
+    let short = Layout::new(80, 10, false);
+
+    assert_eq!(tall.navigation_offset(), 39);
+    assert_eq!(short.navigation_offset(), 9);
+    assert_eq!(short.content_row(9), None);
+}
+
+#[test]
+fn lays_out_three_rows() {
+    let layout = Layout::new(80, 3, true);
+
+    assert_eq!(layout.ruler_rows, 1);
+    assert_eq!(layout.content_rows, 1);
+    assert_eq!(layout.navigation_offset(), 2);
+}
+
+#[test]
+fn drops_ruler_on_two_rows() {
+    let layout = Layout::new(80, 2, true);
+
+    assert_eq!(layout.ruler_rows, 0);
+    assert_eq!(layout.content_rows, 1);
+    assert_eq!(layout.navigation_offset(), 1);
+    assert_eq!(layout.content_row(0), Some(0));
+}
+
+#[test]
+fn leaves_single_row_to_navigation() {
+    let layout = Layout::new(80, 1, true);
+
+    assert_eq!(layout.ruler_rows, 0);
+    assert_eq!(layout.content_rows, 0);
+    assert_eq!(layout.navigation_offset(), 0);
+    assert_eq!(layout.content_row(0), None);
+}
+
+#[test]
+fn clamps_degenerate_sizes() {
+    let layout = Layout::new(-1, -5, true);
+
+    assert_eq!(layout.width, 1);
+    assert_eq!(layout.height, 1);
+    assert_eq!(layout.content_rows, 0);
+    assert_eq!(layout.navigation_offset(), 0);
+}
+
+#[test]
+fn takes_sidebar_from_content_width() {
+    let layout = Layout::new(80, 24, false).with_sidebar(30);
+
+    assert_eq!(layout.sidebar_cols, 30);
+    assert_eq!(layout.content_width, 50);
+    assert_eq!(layout.width, 80);
+}
+
+#[test]
+fn drops_sidebar_on_narrow_terminals() {
+    let layout = Layout::new(40, 24, false).with_sidebar(30);
+
+    assert_eq!(layout.sidebar_cols, 0);
+    assert_eq!(layout.content_width, 40);
+}
+
+#[test]
+fn shows_placeholder_below_minimum_size() {
+    for &width in &[1, 10, 19] {
+        assert!(Layout::new(width, 24, true).is_too_small());
+    }
+
+    assert!(!Layout::new(20, 24, true).is_too_small());
+    assert!(!Layout::new(21, 24, true).is_too_small());
+    assert!(Layout::new(80, 3, false).is_too_small());
+    assert!(Layout::new(0, 0, false).is_too_small());
+}
+
+#[test]
+fn keeps_search_fields_within_narrow_terminals() {
+    assert_eq!((20, 60), field_widths(80));
+
+    for &width in &[MIN_WIDTH, MIN_WIDTH + 1, 40, 73] {
+        let (input, options) = field_widths(width);
+
+        assert!(input >= 1 && options >= 1);
+        assert!(input + options <= width, "{} columns", width);
+    }
+}
+
+#[test]
+fn finds_widgets_under_the_pointer() {
+    let layout = Layout::new(80, 24, true).with_sidebar(30);
+    let widgets = WidgetMap::new(&layout);
+
+    assert_eq!(widgets.widget_at(0, 10), Some(Widget::Ruler));
+    assert_eq!(widgets.widget_at(5, 49), Some(Widget::Content));
+    assert_eq!(widgets.widget_at(5, 50), Some(Widget::Sidebar));
+    assert_eq!(widgets.widget_at(23, 79), Some(Widget::Navigation));
+    assert_eq!(widgets.widget_at(24, 0), None);
+    assert_eq!(widgets.widget_at(5, 80), None);
+}
+
+#[test]
+fn finds_overlays_shown_last_first() {
+    let mut widgets = WidgetMap::new(&Layout::new(80, 24, false));
+    widgets.show(Widget::Rules, Area::new(0, 0, 5, 80));
+    widgets.show(Widget::Overlay, Area::new(2, 40, 10, 20));
+
+    assert_eq!(widgets.widget_at(1, 50), Some(Widget::Rules));
+    assert_eq!(widgets.widget_at(3, 50), Some(Widget::Overlay));
+    assert_eq!(widgets.widget_at(3, 10), Some(Widget::Rules));
+    assert_eq!(widgets.widget_at(6, 10), Some(Widget::Content));
+
+    widgets.hide(Widget::Overlay);
+    assert_eq!(widgets.widget_at(3, 50), Some(Widget::Rules));
+}
+
+#[test]
+fn scrolls_lists_to_show_the_selection() {
+    assert_eq!(scroll_to_show(0, 3, 5), 0);
+    assert_eq!(scroll_to_show(0, 5, 5), 1);
+    assert_eq!(scroll_to_show(4, 9, 5), 5);
+    assert_eq!(scroll_to_show(4, 2, 5), 2);
+    assert_eq!(scroll_to_show(4, 2, 0), 2);
+}
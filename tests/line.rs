@@ -0,0 +1,217 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use flow::core::line::{Line, LineCollection, SearchPattern};
+use flow::core::query::Syntax;
+
+fn line(text: &str) -> Line {
+    Line::new(text.to_string())
+}
+
+fn pattern(query: &str) -> SearchPattern {
+    SearchPattern::new(query, false)
+}
+
+fn whole_word(query: &str) -> SearchPattern {
+    SearchPattern::new(query, true)
+}
+
+#[test]
+fn parses_inverse_searches() {
+    assert!(!pattern("200 OK").inverse);
+    assert_eq!(pattern("200 OK").text, "200 OK");
+
+    assert!(pattern("!200 OK").inverse);
+    assert_eq!(pattern("!200 OK").text, "200 OK");
+
+    assert!(!pattern("\\!important").inverse);
+    assert_eq!(pattern("\\!important").text, "!important");
+
+    assert!(!pattern("!").inverse);
+}
+
+#[test]
+fn inverse_search_matches_whole_lines_lacking_the_text() {
+    let failed = line("GET /missing 404 Not Found");
+    let succeeded = line("GET / 200 OK");
+
+    assert!(failed.contains(&pattern("!200 OK")));
+    assert!(!succeeded.contains(&pattern("!200 OK")));
+
+    assert_eq!(failed.matches_for(&pattern("!200 OK")),
+               vec![(0, "GET /missing 404 Not Found")]);
+    assert!(succeeded.matches_for(&pattern("!200 OK")).is_empty());
+}
+
+#[test]
+fn regular_search_finds_every_occurrence() {
+    assert_eq!(line("a-b-a").matches_for(&pattern("a")), vec![(0, "a"), (4, "a")]);
+}
+
+#[test]
+fn whole_word_search_skips_occurrences_within_words() {
+    let text = line("err: transferred, err_code error err");
+
+    assert_eq!(text.matches_for(&whole_word("err")), vec![(0, "err"), (33, "err")]);
+    assert!(!line("transferred").contains(&whole_word("err")));
+}
+
+#[test]
+fn whole_word_search_uses_unicode_word_characters() {
+    assert!(!line("größe").contains(&whole_word("gr")));
+    assert!(line("größe über").contains(&whole_word("über")));
+    assert!(!line("übertrag").contains(&whole_word("über")));
+}
+
+#[test]
+fn whole_word_boundaries_only_apply_next_to_word_characters() {
+    assert!(line("run -v now").contains(&whole_word("-v")));
+    assert!(line("run x-v now").contains(&whole_word("-v")));
+    assert!(!line("run -verbose").contains(&whole_word("-v")));
+}
+
+#[test]
+fn whole_word_combines_with_inverse_search() {
+    assert!(line("transferred").contains(&whole_word("!err")));
+    assert!(!line("an err here").contains(&whole_word("!err")));
+}
+
+#[test]
+fn searches_between_slashes_are_regexes() {
+    let text = line("took 15ms, then 230ms");
+    let syntax = Syntax { regex: true, ..Syntax::default() };
+    let pattern = |query| SearchPattern::new(query, false).with_syntax(syntax);
+
+    assert_eq!(text.matches_for(&pattern("/\\d+ms/")), vec![(5, "15ms"), (16, "230ms")]);
+    assert!(!text.contains(&pattern("/^then/")));
+    assert!(line("a /(/ b").contains(&pattern("/(/")));
+}
+
+#[test]
+fn searches_slashes_literally_without_regexes() {
+    let text = line("tail -f /var/log/syslog");
+
+    assert!(text.contains(&pattern("/var/log/")));
+    assert!(!line("42ms").contains(&pattern("/\\d+ms/")));
+}
+
+#[test]
+fn field_searches_only_look_within_the_field() {
+    let text = line("user=ann agent=user-agent/1.0 msg=\"read timeout\"");
+
+    assert_eq!(text.matches_for(&pattern("msg:timeout")), vec![(40, "timeout")]);
+    assert_eq!(text.matches_for(&pattern("agent:user")), vec![(15, "user")]);
+    assert!(!text.contains(&pattern("user:agent")));
+    assert!(!text.contains(&pattern("host:ann")));
+}
+
+#[test]
+fn field_searches_skip_or_fall_back_on_unparsed_lines() {
+    let text = line("read timeout");
+
+    assert!(!text.contains(&pattern("msg:timeout")));
+    assert!(text.contains(&pattern("msg:timeout").with_field_fallback(true)));
+}
+
+#[test]
+fn field_searches_look_for_the_whole_text_in_lines_without_the_field() {
+    let url = line("GET http://host/orders took=12ms");
+
+    assert_eq!(url.matches_for(&pattern("http://host")), vec![(4, "http://host")]);
+    assert!(line("ERROR: disk full").contains(&pattern("ERROR: disk")));
+    assert!(!line("level=error msg=ok").contains(&pattern("level:disk")));
+}
+
+#[test]
+fn field_searches_compare_numbers() {
+    let slow = line("path=/orders duration=1523.4ms status=500");
+    let fast = line("path=/health duration=0.3s status=200");
+
+    assert_eq!(slow.matches_for(&pattern("duration:>500ms")), vec![(22, "1523.4ms")]);
+    assert!(!fast.contains(&pattern("duration:>500ms")));
+    assert!(fast.contains(&pattern("duration:>=0.3s")));
+    assert!(fast.contains(&pattern("status:<300")));
+    assert!(!slow.contains(&pattern("path:>1")));
+    assert!(pattern("duration:>500ms").has_captures());
+    assert_eq!(pattern("duration:>500ms").captures(&slow.content_without_ansi), vec!["1523.4"]);
+    assert!(line(">500ms").contains(&pattern(">500ms")));
+}
+
+#[test]
+fn quoted_field_searches_are_searched_as_is() {
+    assert!(line("at:12").contains(&pattern("\"at:12\"")));
+    assert_eq!(pattern("\"at:12\"").field, None);
+    assert_eq!(pattern("\"error\"").text, "\"error\"");
+}
+
+fn partials(entries: &[(usize, &str)]) -> BTreeMap<usize, String> {
+    entries.iter().map(|&(source, text)| (source, text.to_string())).collect()
+}
+
+#[test]
+fn partial_lines_are_amended_in_place() {
+    let mut lines = LineCollection::new(10);
+    lines.extend(vec!["first".to_string()]);
+
+    assert_eq!(lines.show_partials(partials(&[(0, "sec")])), 1);
+    assert_eq!(lines.show_partials(partials(&[(0, "second ha")])), 0);
+    let shown = lines.entries.back().unwrap().clone();
+    assert_eq!(&*shown.content_without_ansi, "second ha…");
+    assert!(shown.partial);
+    assert!(!shown.contains(&pattern("second")));
+
+    assert!(lines.complete_partial(0, Arc::from("second half")));
+    assert!(!lines.complete_partial(0, Arc::from("third")));
+    let completed = lines.entries.back().unwrap();
+    assert_eq!(&*completed.content_without_ansi, "second half");
+    assert_eq!((completed.id, completed.partial), (2, false));
+    assert!(completed.contains(&pattern("second")));
+    assert_eq!((lines.len(), lines.received()), (2, 2));
+}
+
+#[test]
+fn partial_lines_no_longer_written_are_kept() {
+    let mut lines = LineCollection::new(10);
+
+    lines.show_partials(partials(&[(1, "a"), (2, "b")]));
+    lines.show_partials(partials(&[(2, "bc")]));
+
+    let shown = lines.entries.iter().map(|line| line.partial).collect::<Vec<_>>();
+    assert_eq!(shown, vec![false, true]);
+    assert_eq!(lines.partial_id(1), None);
+    assert_eq!(lines.partial_id(2), Some(2));
+}
+
+#[test]
+fn lines_left_out_by_sampling_keep_their_ids() {
+    let mut lines = LineCollection::new(10);
+    lines.extend(vec![(1, Some(Arc::<str>::from("kept"))),
+                      (1, None),
+                      (2, None),
+                      (1, Some(Arc::<str>::from("kept too")))]);
+
+    assert_eq!(4, lines.received());
+    assert_eq!(2, lines.entries.len());
+    let last = &lines.entries[1];
+    assert_eq!((4, 3), (last.id, last.number));
+}
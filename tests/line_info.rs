@@ -0,0 +1,88 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+extern crate regex;
+
+use regex::Regex;
+
+use flow::core::line::Line;
+use flow::core::filter::Filter;
+use flow::core::highlights::Highlights;
+use flow::core::line_info::{self, detect_level, extract_fields};
+
+fn filter(name: &str, content: Option<&str>) -> Filter {
+    Filter {
+        name: name.to_string(),
+        content: content.map(|value| Regex::new(value).unwrap()),
+        start: None,
+        end: None,
+        rewrites: vec![],
+        line_rules: vec![],
+        alerts: vec![],
+    }
+}
+
+#[test]
+fn detects_levels() {
+    assert_eq!(detect_level("2016-03-01 14:32:05 [error] disk full"), Some("ERROR"));
+    assert_eq!(detect_level("level=Warning retrying"), Some("WARN"));
+    assert_eq!(detect_level("terrible"), None);
+}
+
+#[test]
+fn extracts_key_value_fields() {
+    assert_eq!(extract_fields(r#"user=ann path="/a b" took=15ms"#),
+               vec![("user".to_string(), "ann".to_string()),
+                    ("path".to_string(), "/a b".to_string()),
+                    ("took".to_string(), "15ms".to_string())]);
+}
+
+#[test]
+fn extracts_json_fields() {
+    let fields = extract_fields(r#"{"msg": "done", "count": 3}"#);
+
+    assert_eq!(fields,
+               vec![("count".to_string(), "3".to_string()),
+                    ("msg".to_string(), "done".to_string())]);
+}
+
+#[test]
+fn describes_lines() {
+    let line = Line::with_id("2016-03-01 14:32:05 ERROR user=ann".to_string(), 7);
+    let all = filter("all", None);
+    let errors = filter("errors", Some("ERROR"));
+    let warnings = filter("warnings", Some("WARN"));
+    let mut highlights = Highlights::new();
+    highlights.toggle("user").unwrap();
+    highlights.toggle("missing").unwrap();
+
+    let rows = line_info::describe(&line, "app.log", &[&all, &errors, &warnings], &highlights);
+    let value = |label: &str| {
+        rows.iter().filter(|row| row.0 == label).map(|row| row.1.as_str()).collect::<Vec<_>>()
+    };
+
+    assert_eq!(value("source"), vec!["app.log"]);
+    assert_eq!(value("line"), vec!["7"]);
+    assert_eq!(value("timestamp"), vec!["2016-03-01 14:32:05"]);
+    assert_eq!(value("level"), vec!["ERROR"]);
+    assert_eq!(value("bytes"), vec!["34"]);
+    assert_eq!(value("filter"), vec!["errors"]);
+    assert_eq!(value("highlight"), vec!["user"]);
+    assert_eq!(value("field"), vec!["user=ann"]);
+}
@@ -0,0 +1,141 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use flow::core::line::{Line, SearchPattern};
+use flow::core::log_format::{Folder, LogFormat, SourceFormat};
+
+fn fixture(name: &str) -> Vec<(usize, Arc<str>)> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name);
+    let mut contents = String::new();
+    File::open(path).unwrap().read_to_string(&mut contents).unwrap();
+
+    contents.lines().map(|line| (0, Arc::from(line))).collect()
+}
+
+fn folder(file: &str, format: LogFormat) -> Folder {
+    Folder::new(vec![SourceFormat {
+                         file: file.to_string(),
+                         format: format,
+                     }])
+}
+
+fn read(folder: &mut Folder, lines: Vec<(usize, Arc<str>)>, name: &str) -> Vec<String> {
+    let mut folded = folder.fold(lines, |_| Some(name.to_string()));
+    folded.extend(folder.finish());
+
+    folded.into_iter().map(|(_, line)| line.to_string()).collect()
+}
+
+fn lines(values: &[&str]) -> Vec<(usize, Arc<str>)> {
+    values.iter().map(|&value| (0, Arc::from(value))).collect()
+}
+
+#[test]
+fn folds_postgres_statements() {
+    let mut folder = folder("postgresql-*.log", LogFormat::PostgresSlow);
+    let records = read(&mut folder, fixture("postgres_slow.log"), "/var/log/postgresql-9.5.log");
+
+    assert_eq!(records.len(), 4);
+    assert_eq!(records[0],
+               "2016-03-01 14:32:05.118 UTC [2171] LOG:  duration=1523.456ms  statement: SELECT \
+                o.id, o.total, c.email FROM orders o JOIN customers c ON c.id = o.customer_id \
+                WHERE o.created_at > now() - interval '1 day';");
+    assert!(records[1].ends_with("WHERE id = $2 2016-03-01 14:32:07.402 UTC [2174] DETAIL:  \
+                                  parameters: $1 = '2016-03-01 14:32:07', $2 = '918'"));
+    assert_eq!(records[2], "2016-03-01 14:33:12.009 UTC [2180] LOG:  checkpoint starting: time");
+    assert!(records[3].ends_with("DELETE FROM sessions WHERE expires_at < now();"));
+}
+
+#[test]
+fn folds_mysql_entries() {
+    let mut folder = folder("mysql-slow.log", LogFormat::MysqlSlow);
+    let records = read(&mut folder, fixture("mysql_slow.log"), "mysql-slow.log");
+
+    assert_eq!(records.len(), 5);
+    assert_eq!(records[2], "Time                 Id Command    Argument");
+    assert_eq!(records[3],
+               "# Time: 2016-03-01T14:32:05.118732Z # User@Host: shop[shop] @ localhost []  Id:    \
+                12 # duration=2503.123ms  Lock_time: 0.000112 Rows_sent: 1  Rows_examined: 184320 \
+                use shop; SET timestamp=1456842725; SELECT COUNT(*) FROM orders WHERE status = \
+                'pending';");
+    assert!(records[4].starts_with("# User@Host: shop[shop] @ localhost []  Id:    12 # \
+                                    duration=410ms "));
+}
+
+#[test]
+fn finds_slow_statements_by_duration() {
+    let mut folder = folder("postgresql-*.log", LogFormat::PostgresSlow);
+    let records = read(&mut folder, fixture("postgres_slow.log"), "postgresql-9.5.log");
+    let pattern = SearchPattern::new("duration:>500ms", false);
+
+    let slow = records.iter()
+        .map(|record| Line::new(record.clone()))
+        .filter(|line| line.contains(&pattern))
+        .count();
+    assert_eq!(slow, 2);
+}
+
+#[test]
+fn completes_records_once_the_source_has_nothing_more_to_read() {
+    let mut folder = folder("db.log", LogFormat::PostgresSlow);
+    let name = |_| Some("db.log".to_string());
+
+    let statement = lines(&["12:00:00 LOG:  duration: 1.5 ms  statement: SELECT 1"]);
+    assert!(folder.fold(statement, name).is_empty());
+    assert!(folder.fold(lines(&["\tFROM dual"]), name).is_empty());
+
+    let record = "12:00:00 LOG:  duration=1.5ms  statement: SELECT 1 FROM dual";
+    assert_eq!(folder.fold(vec![], name), vec![(0, Arc::from(record))]);
+}
+
+#[test]
+fn leaves_other_sources_as_they_are() {
+    let mut folder = folder("db.log", LogFormat::PostgresSlow);
+    let input = vec![(1, Arc::from("\tindented")), (2, Arc::from("\tstatement"))];
+    let folded = folder.fold(input.clone(), |id| {
+        match id {
+            1 => Some("app.log".to_string()),
+            _ => None,
+        }
+    });
+
+    assert_eq!(folded, input);
+}
+
+#[test]
+fn tells_which_sources_are_folded() {
+    let mut folder = folder("db.log", LogFormat::PostgresSlow);
+    assert!(folder.folds(1));
+
+    folder.fold(vec![(1, Arc::from("a")), (2, Arc::from("b"))], |id| {
+        match id {
+            1 => Some("app.log".to_string()),
+            _ => Some("db.log".to_string()),
+        }
+    });
+    assert!(!folder.folds(1));
+    assert!(folder.folds(2));
+    assert!(!Folder::new(vec![]).folds(1));
+}
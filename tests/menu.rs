@@ -0,0 +1,91 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+extern crate unicode_width;
+
+use unicode_width::UnicodeWidthStr;
+
+use flow::ui::menu::{label, middle_ellipsize, visible_items, MAX_NAME_WIDTH};
+
+#[test]
+fn keeps_short_names_whole() {
+    assert_eq!("Errors", middle_ellipsize("Errors", 10));
+    assert_eq!("Errors", middle_ellipsize("Errors", 6));
+}
+
+#[test]
+fn shortens_long_names_in_the_middle() {
+    assert_eq!("conn…eout", middle_ellipsize("connection timeout", 9));
+    assert_eq!("conne…eout", middle_ellipsize("connection timeout", 10));
+    assert_eq!("…", middle_ellipsize("connection timeout", 1));
+}
+
+#[test]
+fn shortens_wide_names_by_width() {
+    let shortened = middle_ellipsize("日本語のログファイル", 9);
+
+    assert!(shortened.width() <= 9);
+    assert!(shortened.starts_with("日本"));
+    assert!(shortened.ends_with("イル"));
+}
+
+#[test]
+fn limits_label_names() {
+    let name = "a".repeat(MAX_NAME_WIDTH * 2);
+
+    assert_eq!(MAX_NAME_WIDTH + 2, label(&name, None).width());
+    assert_eq!(" Errors·3 ", label("Errors", Some(Some(3))));
+    assert_eq!(" Errors·… ", label("Errors", Some(None)));
+}
+
+#[test]
+fn shows_all_items_that_fit() {
+    assert_eq!(0..3, visible_items(&[5, 5, 5], 2, 0, 80));
+    assert_eq!(0..0, visible_items(&[], 0, 0, 80));
+}
+
+#[test]
+fn scrolls_right_to_the_selected_item() {
+    let widths = vec![8; 10];
+
+    // Each item takes 9 columns, plus one for each marker
+    assert_eq!(0..3, visible_items(&widths, 0, 0, 30));
+    assert_eq!(3..6, visible_items(&widths, 5, 0, 30));
+    assert_eq!(7..10, visible_items(&widths, 9, 0, 30));
+}
+
+#[test]
+fn scrolls_left_to_the_selected_item() {
+    let widths = vec![8; 10];
+
+    assert_eq!(2..5, visible_items(&widths, 2, 7, 30));
+    assert_eq!(0..3, visible_items(&widths, 0, 7, 30));
+}
+
+#[test]
+fn stays_scrolled_while_the_selected_item_is_shown() {
+    let widths = vec![8; 10];
+
+    assert_eq!(3..6, visible_items(&widths, 4, 3, 30));
+}
+
+#[test]
+fn always_shows_the_selected_item() {
+    assert_eq!(1..2, visible_items(&[40, 40, 40], 1, 0, 30));
+}
@@ -0,0 +1,73 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::line::LineCollection;
+use flow::core::metrics::{METRICS, MetricsSnapshot, RateHistory};
+
+#[test]
+fn ingestion_updates_the_counters() {
+    let before = METRICS.snapshot();
+
+    let mut lines = LineCollection::new(2);
+    lines.extend(vec!["one".to_string(), "two".to_string(), "three".to_string()]);
+
+    let after = METRICS.snapshot();
+    assert!(after.lines_received >= before.lines_received + 3);
+    assert!(after.lines_dropped >= before.lines_dropped + 1);
+    assert!(after.max_batch_size >= 3);
+}
+
+#[test]
+fn describes_rates_against_the_previous_snapshot() {
+    let previous = MetricsSnapshot { renders: 10, lines_received: 100, ..Default::default() };
+    let current = MetricsSnapshot { renders: 15, lines_received: 300, ..Default::default() };
+
+    let rows = current.describe(&previous, 2_000);
+    assert!(rows[0].ends_with("(100.0/s)"), "{}", rows[0]);
+    assert!(rows[3].ends_with("(2.5/s)"), "{}", rows[3]);
+}
+
+#[test]
+fn encodes_snapshots_as_json() {
+    let snapshot = MetricsSnapshot { timestamp: 1, lines_dropped: 2, ..Default::default() };
+    let json = snapshot.to_json();
+
+    assert!(json.starts_with("{\"timestamp\":1,"), "{}", json);
+    assert!(json.contains("\"lines_dropped\":2"), "{}", json);
+}
+
+#[test]
+fn keeps_per_second_counts_over_a_sliding_window() {
+    let mut history = RateHistory::new(4);
+    history.record(100, 3);
+    history.record(100, 2);
+    history.record(101, 1);
+    assert_eq!(history.counts(), vec![0, 0, 5, 1]);
+
+    history.advance(103);
+    assert_eq!(history.counts(), vec![5, 1, 0, 0]);
+
+    // Stale seconds don't move the window back
+    history.record(102, 4);
+    assert_eq!(history.counts(), vec![5, 1, 0, 4]);
+
+    history.advance(200);
+    assert_eq!(history.counts(), vec![0, 0, 0, 0]);
+}
@@ -0,0 +1,111 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::ui::momentum::{Momentum, KeyRepeat};
+
+#[test]
+fn first_event_scrolls_by_base_step() {
+    let mut momentum = Momentum::new(3);
+
+    assert_eq!(momentum.step(1, 1000), 3);
+}
+
+#[test]
+fn consecutive_events_accelerate_up_to_a_cap() {
+    let mut momentum = Momentum::new(3);
+
+    let steps = (0..6).map(|i| momentum.step(-1, 1000 + i * 50)).collect::<Vec<_>>();
+
+    assert_eq!(steps, vec![3, 6, 9, 12, 12, 12]);
+}
+
+#[test]
+fn pause_decays_momentum() {
+    let mut momentum = Momentum::new(2);
+
+    for i in 0..4 {
+        momentum.step(1, 1000 + i * 50);
+    }
+
+    assert_eq!(momentum.step(1, 1150 + 250), 4);
+    assert_eq!(momentum.step(1, 5000), 2);
+}
+
+#[test]
+fn changing_direction_resets_momentum() {
+    let mut momentum = Momentum::new(3);
+
+    momentum.step(1, 1000);
+    momentum.step(1, 1050);
+
+    assert_eq!(momentum.step(-1, 1100), 3);
+}
+
+#[test]
+fn zero_base_step_is_raised_to_one() {
+    let mut momentum = Momentum::new(0);
+
+    assert_eq!(momentum.step(1, 1000), 1);
+}
+
+// Steps for presses arriving every `interval` milliseconds
+fn held(repeat: &mut KeyRepeat, presses: u64, interval: u64, start: u64) -> Vec<usize> {
+    (0..presses).map(|i| repeat.step(-1, start + i * interval)).collect()
+}
+
+#[test]
+fn held_keys_escalate_every_ten_repeats() {
+    let mut repeat = KeyRepeat::new(true);
+    let steps = held(&mut repeat, 32, 30, 1000);
+
+    assert_eq!(steps[..10].to_vec(), vec![1; 10]);
+    assert_eq!(steps[10..20].to_vec(), vec![5; 10]);
+    assert_eq!(steps[20..].to_vec(), vec![25; 12]);
+}
+
+#[test]
+fn slow_presses_stay_at_one_line() {
+    let mut repeat = KeyRepeat::new(true);
+
+    assert_eq!(held(&mut repeat, 30, 120, 1000), vec![1; 30]);
+}
+
+#[test]
+fn a_gap_or_another_direction_goes_back_to_one_line() {
+    let mut repeat = KeyRepeat::new(true);
+    held(&mut repeat, 15, 30, 1000);
+
+    assert_eq!(repeat.step(-1, 1000 + 14 * 30 + 200), 1);
+
+    held(&mut repeat, 15, 30, 5000);
+    assert_eq!(repeat.step(1, 5000 + 15 * 30), 1);
+}
+
+#[test]
+fn reset_and_disabling_keep_single_lines() {
+    let mut repeat = KeyRepeat::new(true);
+    held(&mut repeat, 15, 30, 1000);
+    repeat.reset();
+
+    assert_eq!(repeat.step(-1, 1000 + 15 * 30), 1);
+
+    let mut disabled = KeyRepeat::new(false);
+    assert_eq!(held(&mut disabled, 40, 10, 1000), vec![1; 40]);
+}
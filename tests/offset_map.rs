@@ -0,0 +1,112 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::ui::highlighter::DisplayedText;
+use flow::utils::offset_map::OffsetMap;
+
+const ALPHABET: &'static [char] = &['a', 'b', ' ', '\t', '\0', '\u{a0}', 'é', 'こ', '_'];
+
+// Small deterministic generator, so that failures can be reproduced
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self, limit: usize) -> usize {
+        self.0 = self.0.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+        ((self.0 >> 33) % limit as u64) as usize
+    }
+
+    fn text(&mut self) -> String {
+        let length = self.next(16);
+        (0..length).map(|_| ALPHABET[self.next(ALPHABET.len())]).collect()
+    }
+}
+
+// Byte offsets of every character boundary
+fn boundaries(text: &str) -> Vec<usize> {
+    text.char_indices().map(|(offset, _)| offset).chain(Some(text.len())).collect()
+}
+
+#[test]
+fn maps_offsets_of_text_displayed_as_is() {
+    let map = OffsetMap::identity(5);
+
+    assert_eq!(map.map(0), 0);
+    assert_eq!(map.map(5), 5);
+}
+
+#[test]
+fn maps_offsets_around_removed_text() {
+    let mut map = OffsetMap::new();
+    map.push(4, 0);
+    map.push(3, 3);
+
+    assert_eq!(map.map(0), 0);
+    assert_eq!(map.map(2), 0);
+    assert_eq!(map.map(4), 0);
+    assert_eq!(map.map(5), 1);
+    assert_eq!(map.map(7), 3);
+}
+
+#[test]
+fn displayed_matches_cover_the_same_characters() {
+    let mut random = Random(42);
+
+    for _ in 0..500 {
+        let text = random.text();
+        let show_invisibles = random.next(2) == 1;
+        let displayed = DisplayedText::new(&text, show_invisibles);
+
+        let original = boundaries(&text);
+        let shown = boundaries(&displayed.text);
+        assert_eq!(original.len(), shown.len(), "{:?}", text);
+
+        let start = random.next(original.len());
+        let end = start + random.next(original.len() - start);
+        let range = displayed.range(original[start]..original[end]);
+
+        assert_eq!(range, shown[start]..shown[end], "{:?} {}..{}", text, start, end);
+    }
+}
+
+#[test]
+fn composed_maps_follow_both_transformations() {
+    let mut random = Random(7);
+
+    for _ in 0..500 {
+        let text = random.text();
+        let original = boundaries(&text);
+
+        // Strip a random prefix, then display the rest with revealed invisibles
+        let stripped_chars = random.next(original.len());
+        let stripped = &text[original[stripped_chars]..];
+        let mut strip = OffsetMap::new();
+        strip.push(original[stripped_chars], 0);
+        strip.push(stripped.len(), stripped.len());
+
+        let displayed = DisplayedText::new(stripped, true);
+        let map = strip.then(&displayed.offsets);
+        let shown = boundaries(&displayed.text);
+
+        for (i, &offset) in original.iter().enumerate() {
+            let expected = if i <= stripped_chars { 0 } else { shown[i - stripped_chars] };
+            assert_eq!(map.map(offset), expected, "{:?} at {}", text, offset);
+        }
+    }
+}
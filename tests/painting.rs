@@ -0,0 +1,78 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+// Paints lines on a pad of a terminal writing to /dev/null, reading back the
+// colors of the cells.
+
+extern crate flow;
+extern crate libc;
+extern crate ncurses;
+
+use std::rc::Rc;
+
+use ncurses::*;
+
+use flow::core::highlights::{Highlights, SharedSpans};
+use flow::core::line::{Line, SearchPattern};
+use flow::ui::color;
+use flow::ui::content::Content;
+use flow::ui::frame::NORMAL_HIGHLIGHT_COLOR;
+use flow::ui::rendered_line::{RenderedLineCollection, WrappedRows};
+
+static WIDTH: i32 = 20;
+
+fn start_terminal() {
+    unsafe {
+        let null = libc::fopen(b"/dev/null\0".as_ptr() as *const libc::c_char,
+                               b"w\0".as_ptr() as *const libc::c_char);
+        newterm(Some("xterm-256color"), null as ll::FILE_p, null as ll::FILE_p);
+    }
+    start_color();
+    use_default_colors();
+    color::generate_pairs();
+}
+
+fn pair_at(content: &Content, column: i32) -> i16 {
+    PAIR_NUMBER((mvwinch(content.window, 0, column) & A_COLOR()) as i32) as i16
+}
+
+#[test]
+fn the_search_is_painted_over_line_tints() {
+    start_terminal();
+    let content = Content::new(WIDTH);
+    content.prepare(0..1);
+
+    let mut highlights = Highlights::new();
+    highlights.toggle("line:ERROR").unwrap();
+    let line = Line::with_id("ERROR disk full".to_string(), 1);
+    let spans = SharedSpans::new(highlights.spans(&line.content_without_ansi));
+    let rows = WrappedRows::new(&line, WIDTH as usize, content.continuation_width(), false);
+    let mut lines = RenderedLineCollection::default();
+    lines.create_wrapped(line, Rc::new(rows), spans);
+
+    assert!(lines.entries[0].search(&SearchPattern::new("disk", false), &content, WIDTH, 0));
+
+    let tint = color::highlight_color(0).calculate_id();
+    assert_eq!(tint, pair_at(&content, 0));
+    assert_eq!(NORMAL_HIGHLIGHT_COLOR, pair_at(&content, 6));
+    assert_eq!(NORMAL_HIGHLIGHT_COLOR, pair_at(&content, 9));
+    assert_eq!(tint, pair_at(&content, 11));
+    assert_eq!(tint, pair_at(&content, WIDTH - 1));
+
+    endwin();
+}
@@ -0,0 +1,124 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::highlights::Highlights;
+use flow::core::line::LineCollection;
+use flow::core::pattern_stats::{LastHit, PatternStats, SortColumn, RECENT_WINDOW};
+
+fn highlights(sources: &[&str]) -> Highlights {
+    let mut highlights = Highlights::new();
+    for source in sources {
+        highlights.toggle(source).unwrap();
+    }
+
+    highlights
+}
+
+fn totals(stats: &PatternStats, column: SortColumn, now: i64) -> Vec<(String, usize)> {
+    stats.sorted(column, now).into_iter().map(|entry| (entry.source.clone(), entry.total)).collect()
+}
+
+#[test]
+fn counts_lines_as_they_are_received() {
+    let mut lines = LineCollection::new(10);
+    lines.highlights.toggle("ERROR").unwrap();
+    lines.highlights.toggle("/time(out)?/").unwrap();
+
+    lines.extend(vec!["ERROR ERROR timeout".to_string(), "ok".to_string(), "ERROR".to_string()]);
+
+    let stats = lines.pattern_stats.sorted(SortColumn::Total, 0);
+    assert_eq!(stats[0].source, "ERROR");
+    assert_eq!(stats[0].total, 2);
+    assert_eq!(stats[0].last_hit.map(|hit| hit.line_id), Some(3));
+    assert_eq!(stats[1].source, "/time(out)?/");
+    assert_eq!(stats[1].total, 1);
+    assert_eq!(stats[1].last_hit.map(|hit| hit.line_id), Some(1));
+}
+
+#[test]
+fn keeps_counters_of_remaining_patterns() {
+    let mut highlights = highlights(&["a", "b"]);
+    let mut stats = PatternStats::new();
+    stats.sync(&highlights);
+    stats.record(&[0, 1], 1, 1_000);
+
+    highlights.toggle("a").unwrap();
+    highlights.toggle("c").unwrap();
+    stats.sync(&highlights);
+
+    assert_eq!(totals(&stats, SortColumn::Total, 1_000),
+               vec![("b".to_string(), 1), ("c".to_string(), 0)]);
+}
+
+#[test]
+fn counts_recent_hits_within_a_minute() {
+    let mut stats = PatternStats::new();
+    stats.sync(&highlights(&["a"]));
+    stats.record(&[0], 1, 0);
+    stats.record(&[0], 2, 30_000);
+    stats.record(&[0], 3, 50_000);
+
+    let entry = stats.sorted(SortColumn::Recent, 0)[0].clone();
+    assert_eq!(entry.recent(50_000), 3);
+    assert_eq!(entry.recent(RECENT_WINDOW + 10_000), 2);
+    assert_eq!(entry.recent(200_000), 0);
+    assert_eq!(entry.total, 3);
+}
+
+#[test]
+fn sorts_by_each_column() {
+    let mut stats = PatternStats::new();
+    stats.sync(&highlights(&["a", "b", "c"]));
+    stats.record(&[0], 1, 0);
+    stats.record(&[0], 2, 1_000);
+    stats.record(&[0], 3, 2_000);
+    stats.record(&[1], 4, 100_000);
+    stats.record(&[1], 5, 101_000);
+    stats.record(&[2], 6, 102_000);
+
+    let order = |column| {
+        stats.sorted(column, 110_000)
+            .into_iter()
+            .map(|entry| entry.source.clone())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(order(SortColumn::Total), vec!["a", "b", "c"]);
+    assert_eq!(order(SortColumn::Recent), vec!["b", "c", "a"]);
+    assert_eq!(order(SortColumn::Last), vec!["c", "b", "a"]);
+}
+
+#[test]
+fn resets_one_or_all_counters() {
+    let mut stats = PatternStats::new();
+    stats.sync(&highlights(&["a", "b"]));
+    stats.record(&[0, 1], 1, 0);
+
+    stats.reset("a");
+    let entries = stats.sorted(SortColumn::Total, 0);
+    assert_eq!(entries[0].source, "b");
+    assert_eq!(entries[0].last_hit, Some(LastHit { line_id: 1, at: 0 }));
+    assert_eq!(entries[1].total, 0);
+    assert_eq!(entries[1].last_hit, None);
+
+    stats.reset_all();
+    assert_eq!(totals(&stats, SortColumn::Total, 0),
+               vec![("a".to_string(), 0), ("b".to_string(), 0)]);
+}
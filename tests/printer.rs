@@ -0,0 +1,187 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::line::{Line, SearchPattern};
+use flow::ui::content::{pad_rows, screen_pad_row, validate_buffer_rows, PAD_MARGIN};
+use flow::core::range::LineRange;
+use flow::ui::printer::{line_height, line_markers, lines_over, fold_blank_runs};
+use flow::ui::rendered_line::Marker;
+use flow::ui::rendered_line::RenderedLineCollection;
+use flow::ui::viewport::Viewport;
+
+fn rendered(heights: &[usize]) -> RenderedLineCollection {
+    let mut collection = RenderedLineCollection::default();
+    for (i, &height) in heights.iter().enumerate() {
+        collection.create(Line::with_id(format!("line {}", i), i + 1), height, None);
+    }
+
+    collection
+}
+
+#[test]
+fn validates_buffer_rows_past_the_pad_size_limit() {
+    assert_eq!(Ok(100), validate_buffer_rows(100));
+    assert_eq!(Ok(40_000), validate_buffer_rows(40_000));
+    assert_eq!(Ok(1_000_000), validate_buffer_rows(1_000_000));
+    assert!(validate_buffer_rows(99).is_err());
+    assert!(validate_buffer_rows(1_000_001).is_err());
+}
+
+#[test]
+fn pads_the_screen_with_a_margin_of_rows() {
+    let lines = rendered(&vec![1; 10_000]);
+    let viewport = Viewport::new(5_000, 40, lines.height());
+
+    assert_eq!(4_760..5_200, pad_rows(&lines, &viewport));
+    assert_eq!(440, 2 * PAD_MARGIN + 40);
+}
+
+#[test]
+fn pads_from_the_start_of_a_line_wrapping_past_the_margin() {
+    let lines = rendered(&[10, 500, 10, 10]);
+    let viewport = Viewport::new(0, 20, lines.height());
+
+    assert_eq!(10..530, pad_rows(&lines, &viewport));
+}
+
+#[test]
+fn shows_the_last_rows_of_the_pad_past_its_end() {
+    assert_eq!(150, screen_pad_row(1_150, 1_000, 32_000, 40));
+    assert_eq!(31_960, screen_pad_row(40_000, 1_000, 32_000, 40));
+    assert_eq!(0, screen_pad_row(900, 1_000, 32_000, 40));
+    assert_eq!(0, screen_pad_row(100, 0, 20, 40));
+}
+
+#[test]
+fn pads_short_content_in_full() {
+    let lines = rendered(&[1, 2, 3]);
+
+    assert_eq!(0..6, pad_rows(&lines, &Viewport::new(0, 40, lines.height())));
+    assert_eq!(0..0, pad_rows(&rendered(&[]), &Viewport::new(0, 40, 0)));
+}
+
+#[test]
+fn pads_rows_past_the_range_of_shorts_and_ints() {
+    let row_count = i32::max_value() as usize + 1_000;
+    let lines = rendered(&[row_count, 1, row_count, 100]);
+    let viewport = Viewport::new(50, 40, lines.height());
+
+    let rows = pad_rows(&lines, &viewport);
+    assert_eq!(lines.height_up_to_index(2), rows.start);
+    assert_eq!(lines.height(), rows.end);
+    assert!(rows.start > i32::max_value() as usize);
+}
+
+#[test]
+fn measures_lines_with_their_continuation_indent() {
+    let line = Line::new("a".repeat(400));
+
+    assert_eq!(5, line.guess_height(80));
+    assert_eq!(5, line_height(&line, 80, 0, false));
+    assert_eq!(6, line_height(&line, 80, 2, false));
+}
+
+#[test]
+fn drops_oldest_lines_when_their_rows_overflow_the_buffer() {
+    let buffer_rows = 2_000;
+    let lines = (0..10_000).map(|_| Line::new("a".repeat(400))).collect::<Vec<_>>();
+    let heights = lines.iter().map(|line| line_height(line, 80, 2, false)).collect::<Vec<_>>();
+
+    let skipped = lines_over(&heights, buffer_rows);
+    let kept = &heights[skipped..];
+    let total = kept.iter().sum::<usize>();
+
+    assert_eq!(333, kept.len());
+    assert!(total <= buffer_rows);
+
+    let viewport = Viewport::new(0, 40, total);
+    assert_eq!(total, viewport.top_row() + 40);
+}
+
+#[test]
+fn counts_marker_rows_within_the_buffer() {
+    let lines = (1..7).map(|id| (Line::with_id(format!("line {}", id), id), 0)).collect::<Vec<_>>();
+    let markers = line_markers(&lines, None, Some(LineRange::new(3, 6)));
+
+    assert_eq!(7, markers.len());
+    assert_eq!(vec![Marker::RangeStart], markers[2]);
+    assert_eq!(vec![Marker::RangeEnd], markers[6]);
+
+    let rows = markers[..6].iter().map(|markers| 1 + markers.len()).collect::<Vec<_>>();
+    assert_eq!(2, lines_over(&rows, 6 - markers[6].len()));
+}
+
+#[test]
+fn keeps_wrapped_rows_past_the_range_of_shorts() {
+    let lines = (0..10_000).map(|_| Line::new("a".repeat(400))).collect::<Vec<_>>();
+    let heights = lines.iter().map(|line| line_height(line, 80, 2, false)).collect::<Vec<_>>();
+
+    assert_eq!(0, lines_over(&heights, 1_000_000));
+    assert_eq!(60_000, heights.iter().sum::<usize>());
+}
+
+#[test]
+fn keeps_every_line_fitting_the_pad() {
+    assert_eq!(0, lines_over(&[1, 2, 3], 6));
+    assert_eq!(1, lines_over(&[1, 2, 3], 5));
+    assert_eq!(3, lines_over(&[1, 2, 3], 0));
+}
+
+#[test]
+fn folds_runs_of_blank_lines_into_their_first_line() {
+    let lines = vec!["a", "", "  ", "\t", "b", "", "c", "", ""]
+        .into_iter()
+        .map(|text| Line::new(text.to_string()))
+        .collect::<Vec<_>>();
+
+    let folded = fold_blank_runs(lines, None)
+        .into_iter()
+        .map(|(line, count)| (line.content_without_ansi.to_string(), count))
+        .collect::<Vec<_>>();
+
+    assert_eq!(vec![("a".to_string(), 0),
+                    ("".to_string(), 3),
+                    ("b".to_string(), 0),
+                    ("".to_string(), 0),
+                    ("c".to_string(), 0),
+                    ("".to_string(), 2)],
+               folded);
+}
+
+#[test]
+fn leaves_blank_lines_found_by_the_search_out_of_their_runs() {
+    let lines = vec!["a", "", "", "  ", "", "b"]
+        .into_iter()
+        .map(|text| Line::new(text.to_string()))
+        .collect::<Vec<_>>();
+    let pattern = SearchPattern::new("  ", false);
+
+    let folded = fold_blank_runs(lines, Some(&pattern))
+        .into_iter()
+        .map(|(line, count)| (line.content_without_ansi.to_string(), count))
+        .collect::<Vec<_>>();
+
+    assert_eq!(vec![("a".to_string(), 0),
+                    ("".to_string(), 2),
+                    ("  ".to_string(), 0),
+                    ("".to_string(), 0),
+                    ("b".to_string(), 0)],
+               folded);
+}
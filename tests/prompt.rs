@@ -0,0 +1,125 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::ui::prompt::{PromptBuffer, PromptAction, PromptEvent};
+use flow::ui::input::sanitize_paste;
+
+#[test]
+fn inserts_at_cursor_position() {
+    let mut buffer = PromptBuffer::new("held");
+    buffer.apply(&PromptAction::Left);
+    buffer.apply(&PromptAction::Left);
+
+    assert_eq!(Some(PromptEvent::Changed), buffer.apply(&PromptAction::Insert('l')));
+    assert_eq!("helld", buffer.text());
+    assert_eq!(3, buffer.cursor());
+}
+
+#[test]
+fn moving_the_cursor_does_not_change_the_text() {
+    let mut buffer = PromptBuffer::new("text");
+
+    assert_eq!(None, buffer.apply(&PromptAction::Home));
+    assert_eq!(None, buffer.apply(&PromptAction::Left));
+    assert_eq!(0, buffer.cursor());
+    assert_eq!(None, buffer.apply(&PromptAction::End));
+    assert_eq!(None, buffer.apply(&PromptAction::Right));
+    assert_eq!(4, buffer.cursor());
+}
+
+#[test]
+fn deletes_around_cursor() {
+    let mut buffer = PromptBuffer::new("abc");
+    buffer.apply(&PromptAction::Left);
+
+    assert_eq!(Some(PromptEvent::Changed), buffer.apply(&PromptAction::Delete));
+    assert_eq!("ab", buffer.text());
+    assert_eq!(Some(PromptEvent::Changed), buffer.apply(&PromptAction::Backspace));
+    assert_eq!("a", buffer.text());
+
+    buffer.apply(&PromptAction::Home);
+    assert_eq!(None, buffer.apply(&PromptAction::Backspace));
+}
+
+#[test]
+fn kills_to_start_of_line() {
+    let mut buffer = PromptBuffer::new("lorem ipsum");
+    for _ in 0..5 {
+        buffer.apply(&PromptAction::Left);
+    }
+    buffer.apply(&PromptAction::KillToStart);
+
+    assert_eq!("ipsum", buffer.text());
+    assert_eq!(0, buffer.cursor());
+}
+
+#[test]
+fn kills_previous_word_and_trailing_whitespace() {
+    let mut buffer = PromptBuffer::new("lorem ipsum  ");
+    buffer.apply(&PromptAction::KillWord);
+
+    assert_eq!("lorem ", buffer.text());
+    buffer.apply(&PromptAction::KillWord);
+    assert_eq!("", buffer.text());
+    assert_eq!(None, buffer.apply(&PromptAction::KillWord));
+}
+
+#[test]
+fn submits_and_cancels() {
+    let mut buffer = PromptBuffer::new("42");
+
+    assert_eq!(Some(PromptEvent::Submitted("42".to_string())),
+               buffer.apply(&PromptAction::Submit));
+    assert_eq!(Some(PromptEvent::Cancelled), buffer.apply(&PromptAction::Cancel));
+}
+
+#[test]
+fn handles_multi_byte_characters() {
+    let mut buffer = PromptBuffer::new("żółw");
+    buffer.apply(&PromptAction::Backspace);
+    buffer.insert_str("ć");
+
+    assert_eq!("żółć", buffer.text());
+    assert_eq!(4, buffer.cursor());
+}
+
+#[test]
+fn scrolls_visible_text_to_keep_cursor_in_view() {
+    let buffer = PromptBuffer::new("0123456789");
+    let (text, cursor_column) = buffer.visible_text(5);
+
+    assert_eq!("6789", text);
+    assert_eq!(4, cursor_column);
+
+    let buffer = PromptBuffer::new("界界界");
+    let (text, cursor_column) = buffer.visible_text(4);
+
+    assert_eq!("界", text);
+    assert_eq!(2, cursor_column);
+}
+
+#[test]
+fn pastes_text_on_a_single_line() {
+    let mut buffer = PromptBuffer::new("");
+    let text = sanitize_paste("/var/log/\tapp.log\r\n");
+
+    assert_eq!(Some(PromptEvent::Changed), buffer.apply(&PromptAction::Paste(text)));
+    assert_eq!("/var/log/ app.log", buffer.text());
+}
@@ -0,0 +1,113 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use std::time::Instant;
+
+use flow::core::line::{Line, SearchPattern};
+use flow::core::query::{CompiledQuery, Syntax};
+
+const REGEX: Syntax = Syntax { regex: true, expressions: false };
+
+#[test]
+fn compiles_text_between_slashes_as_a_regex() {
+    assert!(CompiledQuery::new(r"/\d+ms/", false, REGEX).is_regex());
+    assert!(!CompiledQuery::new("timeout", false, REGEX).is_regex());
+    assert!(!CompiledQuery::new("/[/", false, REGEX).is_regex());
+}
+
+#[test]
+fn takes_slashes_literally_without_regexes() {
+    let query = CompiledQuery::new("/var/log/", false, Syntax::default());
+
+    assert!(!query.is_regex());
+    assert!(query.is_found("tail /var/log/syslog"));
+    assert!(!query.is_found("tail /var/lib/"));
+}
+
+#[test]
+fn finds_every_occurrence() {
+    let query = CompiledQuery::new(r"/\d+/", false, REGEX);
+
+    assert_eq!(vec![(4, "12"), (10, "3")], query.occurrences("GET 12 in 3"));
+    assert!(query.is_found("in 3ms"));
+    assert!(!query.is_found("in no time"));
+}
+
+#[test]
+fn skips_empty_regex_matches() {
+    let query = CompiledQuery::new("/x*/", false, REGEX);
+
+    assert!(!query.is_found("abc"));
+    assert_eq!(vec![(1, "xx")], query.occurrences("axxb"));
+}
+
+#[test]
+fn finds_empty_lines_by_matching_nothing() {
+    let query = CompiledQuery::new("/^$/", false, REGEX);
+
+    assert_eq!(vec![(0, "")], query.occurrences(""));
+    assert!(!query.is_found(" "));
+}
+
+#[test]
+fn looks_for_whole_words_lazily() {
+    let query = CompiledQuery::new("id", true, REGEX);
+
+    assert!(query.is_found("valid id"));
+    assert!(!query.is_found("valid ids"));
+    assert_eq!(vec![(6, "id")], query.occurrences("valid id"));
+}
+
+fn elapsed_nanos(started_at: Instant) -> u64 {
+    let elapsed = started_at.elapsed();
+
+    elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64
+}
+
+// Searches 10k lines with a regex, reporting how long compiling the query
+// takes next to matching it. Run with
+// `cargo test --release --test query -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn matching_dominates_regex_searches() {
+    let lines = (0..10_000)
+        .map(|i| {
+            Line::new(format!("2016-03-01 10:20:30 [worker-{}] GET /api/items/{} 200 OK in {}ms",
+                              i % 8,
+                              i,
+                              i % 97))
+        })
+        .collect::<Vec<_>>();
+
+    let started_at = Instant::now();
+    let pattern = SearchPattern::new(r"/in (\d{2})ms/", false).with_syntax(REGEX);
+    let compiling = elapsed_nanos(started_at);
+
+    let started_at = Instant::now();
+    let matches = lines.iter().map(|line| line.matches_for(&pattern).len()).sum::<usize>();
+    let matching = elapsed_nanos(started_at);
+
+    println!("compiling: {}us, matching {} lines: {}us ({} matches)",
+             compiling / 1000,
+             lines.len(),
+             matching / 1000,
+             matches);
+    assert!(compiling < matching);
+}
@@ -0,0 +1,73 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use std::ops::Range;
+
+use flow::core::line::{Line, SearchPattern};
+use flow::ui::quick_find::visible_matches;
+use flow::ui::rendered_line::RenderedLineCollection;
+use flow::ui::viewport::Viewport;
+
+// Lines of the given heights, with no search matches of their own.
+fn render(contents: &[(&str, usize)]) -> RenderedLineCollection {
+    let mut collection = RenderedLineCollection::default();
+
+    for &(text, height) in contents {
+        collection.create(Line::new(text.to_string()), height, None);
+    }
+
+    collection
+}
+
+fn find(collection: &RenderedLineCollection,
+        viewport: &Viewport,
+        text: &str)
+        -> Vec<Vec<(usize, Range<usize>)>> {
+    visible_matches(collection, viewport, &SearchPattern::new(text, false), 10, 0, false)
+}
+
+#[test]
+fn only_finds_matches_on_screen() {
+    let collection = render(&[("error a", 1), ("error b", 1), ("error c", 1), ("ok", 1)]);
+    // The last two rows are shown
+    let viewport = Viewport::new(0, 2, 4);
+
+    assert_eq!(vec![vec![(2, 0..5)]], find(&collection, &viewport, "error"));
+}
+
+#[test]
+fn follows_the_viewport_when_scrolled() {
+    let collection = render(&[("error a", 1), ("error b", 1), ("error c", 1), ("ok", 1)]);
+    let viewport = Viewport::new(2, 2, 4);
+
+    assert_eq!(vec![vec![(0, 0..5)], vec![(1, 0..5)]], find(&collection, &viewport, "error"));
+}
+
+#[test]
+fn marks_wrapped_rows_that_are_visible() {
+    // Wraps as `aaaa error` and ` here`
+    let collection = render(&[("aaaa error here", 2), ("ok", 1)]);
+    // Shows ` here` and `ok`
+    let viewport = Viewport::new(0, 2, 3);
+
+    assert_eq!(vec![vec![(1, 1..5)]], find(&collection, &viewport, "here"));
+    assert!(find(&collection, &viewport, "error").is_empty());
+    assert_eq!(vec![vec![(0, 5..10)]], find(&collection, &Viewport::new(1, 2, 3), "error"));
+}
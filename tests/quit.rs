@@ -0,0 +1,99 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::quit::{QuitGuard, Decision, CONFIRM_TIMEOUT};
+use flow::ui::event::{Event, EventBuilder};
+use flow::ui::input::{Input, Key};
+use flow::ui::navigation::State as NavigationState;
+
+fn construct(value: char, state: NavigationState) -> Event {
+    EventBuilder::new(Input::Kb(Key::Char(value), None)).construct(&state)
+}
+
+#[test]
+fn quits_right_away_with_nothing_to_lose() {
+    let mut guard = QuitGuard::default();
+
+    assert_eq!(Decision::Quit, guard.request(false, None, 1_000));
+}
+
+#[test]
+fn asks_again_before_losing_marks() {
+    let mut guard = QuitGuard::default();
+
+    let decision = guard.request(false, Some("A range is marked"), 1_000);
+    assert_eq!(Decision::Confirm("A range is marked, press q again to quit".to_string()),
+               decision);
+    assert!(guard.is_pending(1_500));
+    assert_eq!(Decision::Quit,
+               guard.request(false, Some("A range is marked"), 1_500));
+}
+
+#[test]
+fn confirmation_expires() {
+    let mut guard = QuitGuard::default();
+    guard.request(false, Some("A range is marked"), 1_000);
+
+    assert!(!guard.expire(1_000 + CONFIRM_TIMEOUT));
+    assert!(guard.expire(1_001 + CONFIRM_TIMEOUT));
+    assert!(!guard.expire(1_001 + CONFIRM_TIMEOUT));
+
+    let decision = guard.request(false, Some("A range is marked"), 2_000 + CONFIRM_TIMEOUT);
+    assert!(decision != Decision::Quit);
+}
+
+#[test]
+fn forcing_skips_the_confirmation() {
+    let mut guard = QuitGuard::default();
+
+    assert_eq!(Decision::Quit, guard.request(true, Some("A range is marked"), 1_000));
+}
+
+#[test]
+fn a_held_request_is_confirmed_by_quitting() {
+    // As after a first Ctrl-C
+    let mut guard = QuitGuard::default();
+    guard.hold(1_000);
+
+    assert!(guard.is_pending(2_000));
+    assert_eq!(Decision::Quit, guard.request(false, None, 2_000));
+}
+
+#[test]
+fn keys_map_to_quit_events() {
+    for state in vec![NavigationState::Menu, NavigationState::Rules, NavigationState::Patterns] {
+        match construct('q', state) {
+            Event::Quit => {}
+            _ => panic!("q doesn't quit"),
+        }
+    }
+    for state in vec![NavigationState::Menu, NavigationState::Rules, NavigationState::Patterns] {
+        match construct('Q', state) {
+            Event::ForceQuit => {}
+            _ => panic!("Q doesn't force quitting"),
+        }
+    }
+
+    // Typed in the search bar instead
+    match construct('q', NavigationState::Search) {
+        Event::Quit | Event::ForceQuit => panic!("q quits from the search bar"),
+        _ => {}
+    }
+}
@@ -0,0 +1,119 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::line::{Line, SearchPattern};
+use flow::core::range::{self, LineRange, RangeAction, RangeStats};
+
+fn lines(values: &[&str]) -> Vec<Line> {
+    values.iter().enumerate().map(|(i, value)| Line::with_id(value.to_string(), i + 1)).collect()
+}
+
+#[test]
+fn orders_marked_lines() {
+    let range = LineRange::new(9, 4);
+
+    assert_eq!(range, LineRange { start: 4, end: 9 });
+    assert!(range.contains(4));
+    assert!(range.contains(9));
+    assert!(!range.contains(3));
+    assert!(!range.contains(10));
+}
+
+#[test]
+fn clamps_to_lines_still_kept() {
+    let range = LineRange::new(4, 9);
+
+    assert_eq!(range.clamp(2), Some(range));
+    assert_eq!(range.clamp(6), Some(LineRange::new(6, 9)));
+    assert_eq!(range.clamp(9), Some(LineRange::new(9, 9)));
+    assert_eq!(range.clamp(10), None);
+}
+
+#[test]
+fn parses_actions() {
+    assert!(match RangeAction::parse("s") {
+        Ok(RangeAction::Save) => true,
+        _ => false,
+    });
+    assert!(match RangeAction::parse(" count ") {
+        Ok(RangeAction::CountMatches) => true,
+        _ => false,
+    });
+    assert!(match RangeAction::parse("i") {
+        Ok(RangeAction::Stats) => true,
+        _ => false,
+    });
+    assert!(match RangeAction::parse("| sort | uniq -c") {
+        Ok(RangeAction::Pipe(ref command)) => command == "sort | uniq -c",
+        _ => false,
+    });
+    assert!(RangeAction::parse("|").is_err());
+    assert!(RangeAction::parse("x").is_err());
+}
+
+#[test]
+fn times_stats_by_timestamps() {
+    let stats = RangeStats::new(&lines(&["2016-03-01 14:30:00 start",
+                                         "no time here",
+                                         "2016-03-01 14:30:04 stop"]));
+
+    assert_eq!(stats,
+               RangeStats {
+                   lines: 3,
+                   bytes: 64,
+                   duration: Some(4_000),
+               });
+    assert_eq!(stats.lines_per_second(), Some(0.75));
+    assert_eq!(stats.describe(), "3 lines, 64 bytes, over 4s, 0.8 lines/sec");
+}
+
+#[test]
+fn omits_rate_without_elapsed_time() {
+    let stats = RangeStats::new(&[]);
+
+    assert_eq!(stats.duration, None);
+    assert_eq!(stats.lines_per_second(), None);
+    assert_eq!(stats.describe(), "0 lines, 0 bytes");
+}
+
+#[test]
+fn counts_matches_and_matching_lines() {
+    let values = lines(&["ERROR a ERROR", "ok", "ERROR b"]);
+
+    assert_eq!(range::count_matches(&values, &SearchPattern::new("ERROR", false)), (3, 2));
+    assert_eq!(range::count_matches(&values, &SearchPattern::new("none", false)), (0, 0));
+}
+
+#[test]
+fn writes_one_line_each() {
+    let mut out = vec![];
+    range::write_lines(&mut out, &lines(&["a", "b"])).unwrap();
+
+    assert_eq!(String::from_utf8(out).unwrap(), "a\nb\n");
+}
+
+#[test]
+fn pipes_lines_to_commands() {
+    let values = lines(&["a"; 10_000]);
+
+    assert_eq!(range::pipe("cat > /dev/null", &values).unwrap(), true);
+    assert_eq!(range::pipe("head -1 > /dev/null", &values).unwrap(), true);
+    assert_eq!(range::pipe("exit 3", &values[..1]).unwrap(), false);
+}
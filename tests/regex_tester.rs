@@ -0,0 +1,57 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::regex_tester::{self, Preview, RegexUse};
+
+#[test]
+fn reports_invalid_regexes() {
+    assert!(regex_tester::compile("GET (/\\w+").is_err());
+    assert!(regex_tester::compile("").is_err());
+    assert!(regex_tester::compile("GET (/\\w+)").is_ok());
+}
+
+#[test]
+fn previews_matches_and_capture_groups() {
+    let regex = regex_tester::compile("(GET|POST) (/\\w*)").unwrap();
+    let preview = Preview::new(&regex, "GET /users 200, POST / 201");
+
+    assert_eq!(preview.matches, vec![(0, 10), (16, 22)]);
+    assert_eq!(preview.groups, vec![(0, 3), (4, 10), (16, 20), (21, 22)]);
+    assert!(preview.in_match(9));
+    assert!(!preview.in_match(10));
+    assert!(preview.in_group(4));
+    assert!(!preview.in_group(3));
+}
+
+#[test]
+fn skips_empty_matches() {
+    let regex = regex_tester::compile("x*").unwrap();
+
+    assert_eq!(Preview::new(&regex, "abc"), Preview::default());
+}
+
+#[test]
+fn parses_what_to_keep_a_regex_as() {
+    assert_eq!(RegexUse::parse("s"), Some(RegexUse::Search));
+    assert_eq!(RegexUse::parse(" filter "), Some(RegexUse::Filter));
+    assert_eq!(RegexUse::parse("h"), Some(RegexUse::Highlight));
+    assert_eq!(RegexUse::parse("x"), None);
+    assert_eq!(regex_tester::delimited("\\d+"), "/\\d+/");
+}
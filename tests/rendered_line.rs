@@ -0,0 +1,395 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::Instant;
+
+use flow::core::highlights::SharedSpans;
+use flow::core::line::{Line, LineCollection, SearchPattern};
+use flow::ui::highlighter::{match_spans, ByteOffset, MatchSpan, RowOffset};
+use flow::ui::rendered_line::{Marker, MatchedLine, RenderedLineCollection, WrappedRows};
+use flow::ui::viewport::Viewport;
+use flow::utils::wrap::wrap_indented;
+
+// Renders the stored lines the way a search does, marking the ones
+// containing the text as matches.
+fn render(lines: &LineCollection, text: &str) -> RenderedLineCollection {
+    let mut collection = RenderedLineCollection::default();
+    let pattern = SearchPattern::new(text, false);
+
+    for line in &lines.entries {
+        let found_matches = if line.contains(&pattern) {
+            Some(match_spans(line, &pattern, 80, false))
+        } else {
+            None
+        };
+        collection.create(line.clone(), 1, found_matches);
+    }
+
+    collection
+}
+
+// Same as `render`, with lines wrapped at the given width.
+fn render_wrapped(lines: &LineCollection, text: &str, width: usize) -> RenderedLineCollection {
+    let mut collection = RenderedLineCollection::default();
+    let pattern = SearchPattern::new(text, false);
+
+    for line in &lines.entries {
+        let found_matches = if line.contains(&pattern) {
+            Some(match_spans(line, &pattern, width, false))
+        } else {
+            None
+        };
+        let height = WrappedRows::new(line, width, 0, false).rows.len();
+        collection.create(line.clone(), height, found_matches);
+    }
+
+    collection
+}
+
+// Rows 0-1 match on 0, row 2 doesn't, rows 3-6 match on 5, row 7 doesn't
+// and rows 8-10 match on 8 and 9.
+fn wrapped_matches() -> RenderedLineCollection {
+    let lines = lines(10, &["needle", "hay", "xxxxxxxxneedle", "hay", "needleneedle"]);
+    render_wrapped(&lines, "needle", 4)
+}
+
+fn lines(capacity: usize, contents: &[&str]) -> LineCollection {
+    let mut lines = LineCollection::new(capacity);
+    lines.extend(contents.iter().map(|value| value.to_string()));
+
+    lines
+}
+
+#[test]
+fn assigns_increasing_ids_on_ingestion() {
+    let lines = lines(2, &["a", "b", "c"]);
+    let ids = lines.entries.iter().map(|line| line.id).collect::<Vec<_>>();
+
+    assert_eq!(vec![2, 3], ids);
+    assert_eq!(0, Line::new("x".to_string()).id);
+}
+
+#[test]
+fn resolves_the_same_line_after_new_ones_arrive() {
+    let mut lines = lines(10, &["needle", "hay", "needle needle"]);
+    let focused_id = lines.entries[2].id;
+
+    lines.extend(vec!["needle".to_string(), "hay".to_string()]);
+    let matched = render(&lines, "needle").resolve_match(focused_id, 1).unwrap();
+
+    assert_eq!(2, matched.line);
+    assert_eq!(1, matched.match_index);
+}
+
+#[test]
+fn falls_back_to_the_next_match_when_the_focused_line_is_evicted() {
+    let mut lines = lines(3, &["needle", "hay", "hay", "needle"]);
+    let evicted_id = 1;
+
+    lines.extend(vec!["hay".to_string()]);
+    let rendered = render(&lines, "needle");
+    let matched = rendered.resolve_match(evicted_id, 0).unwrap();
+
+    assert!(lines.entries.iter().all(|line| line.id != evicted_id));
+    assert_eq!(4, rendered[matched.line].line.id);
+    assert_eq!(0, matched.match_index);
+}
+
+#[test]
+fn falls_back_to_the_last_match_when_no_newer_one_exists() {
+    let lines = lines(10, &["needle", "needle", "hay"]);
+    let matched = render(&lines, "needle").resolve_match(3, 0).unwrap();
+
+    assert_eq!(1, matched.line);
+}
+
+#[test]
+fn clamps_the_match_index_when_the_line_has_fewer_matches() {
+    let lines = lines(10, &["needle"]);
+    let matched = render(&lines, "needle").resolve_match(1, 4).unwrap();
+
+    assert_eq!(0, matched.match_index);
+}
+
+#[test]
+fn resolves_nothing_without_matches() {
+    let lines = lines(10, &["hay"]);
+
+    assert!(render(&lines, "needle").resolve_match(1, 0).is_none());
+}
+
+#[test]
+fn acts_on_the_line_shown_when_the_key_was_read() {
+    let mut lines = lines(6, &["first", "second", "third"]);
+    let mut acted_on = vec![];
+
+    for batch in 0..3 {
+        // The key targets the last line on screen, captured by id
+        let rendered = render(&lines, "");
+        let row = rendered.height() as usize - 1;
+        let shown = rendered[rendered.index_at_row(row).unwrap()].line.clone();
+
+        lines.extend((0..2).map(|i| format!("batch {} line {}", batch, i)));
+        let rendered = render(&lines, "");
+
+        // The last row now shows a newer line, the targeted one is found by id
+        let last_row = rendered.height() as usize - 1;
+        assert!(rendered[rendered.index_at_row(last_row).unwrap()].line.id != shown.id);
+        let line = rendered.line_by_id(shown.id).unwrap();
+        assert_eq!(shown.content_without_ansi, line.content_without_ansi);
+        acted_on.push(line.content_without_ansi.to_string());
+    }
+
+    assert_eq!(vec!["third", "batch 0 line 1", "batch 1 line 1"], acted_on);
+}
+
+#[test]
+fn resolves_no_line_once_it_is_evicted() {
+    let mut lines = lines(3, &["a", "b", "c"]);
+    let shown_id = lines.entries[0].id;
+
+    lines.extend(vec!["d".to_string()]);
+
+    assert!(render(&lines, "").line_by_id(shown_id).is_none());
+    assert!(render(&lines, "").line_by_id(shown_id + 1).is_some());
+}
+
+#[test]
+fn finds_lines_by_row_and_id() {
+    let mut collection = RenderedLineCollection::default();
+    collection.create(Line::with_id("a".to_string(), 7), 2, None);
+    collection.create(Line::with_id("b".to_string(), 8), 3, None);
+
+    assert_eq!(Some(0), collection.index_at_row(1));
+    assert_eq!(Some(1), collection.index_at_row(2));
+    assert_eq!(None, collection.index_at_row(5));
+    assert_eq!(Some(1), collection.index_of_id(8));
+    assert_eq!(None, collection.index_of_id(9));
+}
+
+#[test]
+fn gaps_take_up_a_row_below_the_line() {
+    let lines = lines(10, &["one", "two", "three"]);
+    let mut rendered = render(&lines, "t");
+    rendered.entries.truncate(1);
+    rendered.create(lines.entries[1].clone(), 2, None);
+    rendered.add_marker(Marker::Gap(90_000));
+    rendered.create(lines.entries[2].clone(), 1, None);
+
+    assert_eq!(5, rendered.height());
+    assert_eq!(Some((3, 90_000)), rendered.next_gap(0));
+    assert_eq!(None, rendered.next_gap(3));
+
+    let filtered = rendered.matching(&SearchPattern::new("t", false));
+    assert_eq!(3, filtered.height());
+    assert_eq!(None, filtered.next_gap(0));
+}
+
+#[test]
+fn sums_heights_past_the_range_of_ints() {
+    let tall = i32::max_value() as usize;
+    let mut rendered = RenderedLineCollection::default();
+    rendered.create(Line::with_id("a".to_string(), 1), tall, None);
+    rendered.create(Line::with_id("b".to_string(), 2), tall, Some(vec![MatchSpan {
+                                                                      start: ByteOffset(0),
+                                                                      end: ByteOffset(1),
+                                                                      row: RowOffset(10),
+                                                                  }]));
+    rendered.create(Line::with_id("c".to_string(), 3), 5, None);
+
+    assert_eq!(2 * tall + 5, rendered.height());
+    assert_eq!(2 * tall, rendered.height_up_to_index(2));
+    assert_eq!(Some(1), rendered.index_at_row(tall + 1));
+    assert_eq!(tall as i64 + 5 - 10, rendered.buffer_reverse_index(1, 0));
+}
+
+#[test]
+fn reuses_wrapped_rows_until_the_width_changes() {
+    let mut collection = RenderedLineCollection::default();
+    let line = Line::with_id("abcdefghij".to_string(), 1);
+    collection.create_wrapped(line.clone(),
+                              Rc::new(WrappedRows::new(&line, 4, 0, false)),
+                              SharedSpans::default());
+    let entry = &collection[0];
+
+    assert_eq!(3, entry.height);
+    let first = entry.wrapped_rows(4, 0, false);
+    assert!(Rc::ptr_eq(&first, &entry.wrapped_rows(4, 0, false)));
+
+    for &width in &[3, 7, 4, 3, 10, 4] {
+        let rows = entry.wrapped_rows(width, 0, false);
+        assert_eq!(wrap_indented("abcdefghij", width, 0), rows.rows);
+        assert!(Rc::ptr_eq(&rows, &entry.wrapped_rows(width, 0, false)));
+    }
+    assert!(!Rc::ptr_eq(&first, &entry.wrapped_rows(4, 0, false)));
+}
+
+#[test]
+fn wraps_again_for_another_prefix_or_invisibles() {
+    let line = Line::with_id("a\tbcdefgh".to_string(), 1);
+    let rows = WrappedRows::new(&line, 4, 0, false);
+
+    assert!(rows.fits(&line, 4, 0, false));
+    assert!(!rows.fits(&line, 4, 2, false));
+    assert!(!rows.fits(&line, 4, 0, true));
+    assert!(!rows.fits(&line, 5, 0, false));
+    // Same text, but a line of its own, as rewrites produce
+    assert!(!rows.fits(&Line::with_id("a\tbcdefgh".to_string(), 1), 4, 0, false));
+    assert!(rows.fits(&line.clone(), 4, 0, false));
+}
+
+#[test]
+fn carries_wrapped_rows_over_by_line_id() {
+    let mut collection = RenderedLineCollection::default();
+    let line = Line::with_id("abcdefgh".to_string(), 7);
+    let rows = Rc::new(WrappedRows::new(&line, 4, 0, false));
+    collection.create_wrapped(line.clone(), rows.clone(), SharedSpans::default());
+    collection.create_folded(Line::with_id(String::new(), 8), 3);
+
+    let wrapped = collection.wrapped_rows_by_id();
+
+    assert_eq!(1, wrapped.len());
+    assert!(Rc::ptr_eq(&rows, &wrapped[&7]));
+}
+
+#[test]
+fn finds_the_lowest_match_starting_on_screen() {
+    let rendered = wrapped_matches();
+    assert_eq!(11, rendered.height());
+
+    // Reverse index, visible height and the match expected
+    let cases = [(0, 3, Some((4, 0))),
+                 (2, 3, Some((4, 0))),
+                 (3, 3, Some((2, 0))),
+                 (5, 3, Some((2, 0))),
+                 // Rows 2 to 4 show the first rows of a line matching below them
+                 (6, 3, None),
+                 (8, 3, Some((0, 0))),
+                 (1, 1, Some((4, 1))),
+                 (0, 1, None),
+                 (0, 20, Some((4, 0)))];
+
+    for &(reverse_index, visible_height, expected) in &cases {
+        let viewport = Viewport::new(reverse_index, visible_height, rendered.height());
+        let expected = expected.map(|(line, match_index)| MatchedLine::new(line, match_index));
+
+        assert_eq!(expected,
+                   rendered.viewport_match(&viewport),
+                   "reverse index {}, height {}",
+                   reverse_index,
+                   visible_height);
+    }
+}
+
+#[test]
+fn tells_whether_the_row_a_match_starts_on_is_shown() {
+    let rendered = wrapped_matches();
+
+    // Line, match, reverse index, visible height and whether it's shown
+    let cases = [(0, 0, 8, 3, true),
+                 (0, 0, 7, 3, false),
+                 (2, 0, 5, 3, true),
+                 (2, 0, 3, 3, true),
+                 (2, 0, 2, 3, false),
+                 (2, 0, 6, 3, false),
+                 (4, 0, 0, 1, false),
+                 (4, 1, 1, 1, true),
+                 (4, 0, 2, 1, true),
+                 (4, 1, 2, 1, false),
+                 (0, 0, 0, 20, true)];
+
+    for &(line, match_index, reverse_index, visible_height, expected) in &cases {
+        let viewport = Viewport::new(reverse_index, visible_height, rendered.height());
+        let matched_line = MatchedLine::new(line, match_index);
+
+        assert_eq!(expected,
+                   rendered.is_match_in_viewport(matched_line, &viewport),
+                   "match {} of line {}, reverse index {}, height {}",
+                   match_index,
+                   line,
+                   reverse_index,
+                   visible_height);
+    }
+}
+
+#[test]
+fn last_match_is_counted_from_the_top() {
+    let rendered = wrapped_matches();
+
+    assert_eq!(MatchedLine::new(4, 1), rendered.last_match());
+}
+
+fn elapsed_nanos(started_at: Instant) -> u64 {
+    let elapsed = started_at.elapsed();
+
+    elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64
+}
+
+// Rows of every line the way a redraw gets them, reusing those that still
+// fit, and how long it took
+fn redraw(lines: &[Line], cached: &HashMap<usize, Rc<WrappedRows>>, width: usize) -> (usize, u64) {
+    let started_at = Instant::now();
+    let rows = lines.iter()
+        .map(|line| {
+            match cached.get(&line.id) {
+                Some(rows) if rows.fits(line, width, 2, false) => rows.clone(),
+                _ => Rc::new(WrappedRows::new(line, width, 2, false)),
+            }
+        })
+        .map(|rows| rows.rows.len())
+        .sum();
+
+    (rows, elapsed_nanos(started_at))
+}
+
+// Compares redrawing 20k long lines with and without the rows they wrap
+// onto being kept, at the same width and after a resize. Lines used to be
+// wrapped twice per redraw, once to measure them and once to print them.
+// Run with `cargo test --release --test rendered_line -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn reusing_wrapped_rows_speeds_up_redraws() {
+    let lines = (0..20_000)
+        .map(|i| {
+            Line::with_id(format!("2016-03-01 10:20:30 [worker-{}] GET /api/items/{} {}",
+                                  i % 8,
+                                  i,
+                                  "field=value ".repeat(i % 40)),
+                          i + 1)
+        })
+        .collect::<Vec<_>>();
+    let none = HashMap::new();
+    let wrapped = lines.iter()
+        .map(|line| (line.id, Rc::new(WrappedRows::new(line, 80, 2, false))))
+        .collect::<HashMap<_, _>>();
+
+    let uncached = (0..5).map(|_| redraw(&lines, &none, 80).1 * 2).min().unwrap();
+    let steady = (0..5).map(|_| redraw(&lines, &wrapped, 80).1).min().unwrap();
+    let resized = (0..5).map(|_| redraw(&lines, &wrapped, 100).1).min().unwrap();
+
+    println!("wrapping twice: {}us, reusing rows: {}us, after a resize: {}us",
+             uncached / 1000,
+             steady / 1000,
+             resized / 1000);
+    assert_eq!(redraw(&lines, &none, 80).0, redraw(&lines, &wrapped, 80).0);
+    assert!(steady < uncached && resized < uncached);
+}
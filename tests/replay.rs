@@ -0,0 +1,86 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::replay::Replay;
+use flow::core::timestamp::parse_timestamp;
+
+fn lines(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[test]
+fn parses_common_timestamps() {
+    assert_eq!(parse_timestamp("1970-01-02 00:00:01 started"), Some(86_401_000));
+    assert_eq!(parse_timestamp("[2016-03-01T10:20:30.5Z] GET /"),
+               parse_timestamp("2016-03-01 10:20:30,500"));
+    assert_eq!(parse_timestamp("I, [10:00:00.25] INFO"), Some(36_000_250));
+    assert_eq!(parse_timestamp("no time here"), None);
+}
+
+#[test]
+fn paces_lines_by_their_timestamps() {
+    let mut replay = Replay::new(lines(&["12:00:00 a", "12:00:01 b", "  trace", "12:00:03 c"]), 10);
+
+    assert_eq!(replay.tick(0), lines(&["12:00:00 a"]));
+    assert!(replay.tick(999).is_empty());
+    assert_eq!(replay.tick(1_000), lines(&["12:00:01 b", "  trace"]));
+    assert!(replay.tick(2_999).is_empty());
+    assert_eq!(replay.tick(3_000), lines(&["12:00:03 c"]));
+    assert_eq!(replay.percentage(), 100);
+}
+
+#[test]
+fn plays_untimed_logs_at_a_fixed_rate() {
+    let mut replay = Replay::new(lines(&["a", "b", "c"]), 4);
+
+    assert_eq!(replay.tick(0), lines(&["a"]));
+    assert!(replay.tick(249).is_empty());
+    assert_eq!(replay.tick(500), lines(&["b", "c"]));
+}
+
+#[test]
+fn speed_and_pause_affect_pacing() {
+    let mut replay = Replay::new(lines(&["00:00:00 a", "00:00:02 b", "00:00:04 c"]), 10);
+
+    replay.faster();
+    assert_eq!(replay.tick(0).len(), 1);
+    assert_eq!(replay.tick(1_000).len(), 1);
+    assert!(replay.status_text().ends_with(" 2x"));
+
+    replay.toggle_pause();
+    assert!(replay.tick(10_000).is_empty());
+    assert!(replay.status_text().ends_with("paused"));
+
+    // The pending line is played right away when resuming
+    replay.toggle_pause();
+    assert_eq!(replay.tick(10_000).len(), 1);
+}
+
+#[test]
+fn skips_to_the_next_long_gap() {
+    let mut replay = Replay::new(lines(&["00:00:00 a", "00:00:01 b", "00:01:00 c", "00:01:01 d"]),
+                                 10);
+
+    assert_eq!(replay.tick(0).len(), 1);
+    replay.skip_gap(10_000);
+    assert_eq!(replay.tick(5), lines(&["00:00:01 b", "00:01:00 c"]));
+    assert!(replay.status_text().contains("00:01:00"));
+    assert_eq!(replay.tick(1_005), lines(&["00:01:01 d"]));
+}
@@ -0,0 +1,102 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate toml;
+extern crate flow;
+
+use flow::core::filter::Filter;
+use flow::core::rewrite::{RewriteRule, rewrite, count_hits};
+
+#[test]
+fn parses_delimited_rules() {
+    let rule = RewriteRule::parse("/^\\S+ (\\d+)/pid $1/").unwrap();
+
+    assert_eq!(rule.apply("host 42 started"), "pid 42 started");
+}
+
+#[test]
+fn accepts_other_delimiters_and_missing_trailing_one() {
+    let rule = RewriteRule::parse("#a/b#c").unwrap();
+
+    assert_eq!(rule.apply("a/b a/b"), "c c");
+}
+
+#[test]
+fn rejects_malformed_rules() {
+    assert!(RewriteRule::parse("pattern").is_err());
+    assert!(RewriteRule::parse("/only/").is_ok());
+    assert!(RewriteRule::parse("/a/b/c").is_err());
+    assert!(RewriteRule::parse("/(/x/").is_err());
+}
+
+#[test]
+fn applies_rules_in_order() {
+    let rules = vec![RewriteRule::parse("/foo/bar/").unwrap(),
+                     RewriteRule::parse("/bar/baz/").unwrap()];
+
+    assert_eq!(rewrite(&rules, "foo"), Some("baz".to_string()));
+    assert_eq!(rewrite(&rules, "qux"), None);
+}
+
+#[test]
+fn decodes_rules_from_filter() {
+    let contents = toml::Parser::new(r##"
+        name = "Short"
+
+        [[rewrite]]
+        pattern = "^\\d+ "
+        replace = ""
+    "##)
+        .parse()
+        .unwrap();
+    let filter: Filter = toml::decode(toml::Value::Table(contents)).unwrap();
+
+    assert_eq!(filter.rewrites.len(), 1);
+    assert_eq!(filter.rewrites[0].apply("123 text"), "text");
+}
+
+#[test]
+fn skips_disabled_rules() {
+    let mut rules = vec![RewriteRule::parse("/foo/bar/").unwrap(),
+                         RewriteRule::parse("/bar/baz/").unwrap()];
+    rules[1].enabled = false;
+
+    assert_eq!(rewrite(&rules, "foo"), Some("bar".to_string()));
+}
+
+#[test]
+fn counts_hits_of_stacked_rules() {
+    let mut rules = vec![RewriteRule::parse("/foo/bar/").unwrap(),
+                         RewriteRule::parse("/bar/baz/").unwrap(),
+                         RewriteRule::parse("/qux//").unwrap()];
+
+    assert_eq!(vec![1, 2, 0], count_hits(&rules, vec!["foo", "bar", "other"]));
+
+    rules[0].enabled = false;
+    assert_eq!(vec![0, 1, 0], count_hits(&rules, vec!["foo", "bar", "other"]));
+}
+
+#[test]
+fn source_round_trips_through_parse() {
+    let rule = RewriteRule::parse("#a/b#c#").unwrap();
+    let source = rule.source();
+
+    assert_eq!("#a/b#c#", source);
+    assert_eq!(RewriteRule::parse(&source).unwrap().apply("a/b"), "c");
+    assert_eq!("/x/y/", RewriteRule::parse("/x/y/").unwrap().source());
+}
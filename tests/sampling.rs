@@ -0,0 +1,105 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::sampling::{Sampler, grouped};
+
+fn shown(sampler: &mut Sampler, count: usize) -> usize {
+    (0..count).filter(|_| sampler.admit(false)).count()
+}
+
+#[test]
+fn shows_every_line_until_turned_on() {
+    let mut sampler = Sampler::new(Some(10), 1_000);
+
+    assert_eq!(shown(&mut sampler, 100), 100);
+    assert_eq!(sampler.banner(), None);
+
+    sampler.toggle();
+    assert_eq!(shown(&mut sampler, 100), 10);
+    assert_eq!(sampler.skipped, 90);
+}
+
+#[test]
+fn adapts_the_ratio_to_the_rate() {
+    let mut sampler = Sampler::new(None, 1_000);
+    sampler.toggle();
+
+    assert_eq!(sampler.arrived(100, 20_000), Some(0));
+    assert_eq!(sampler.every(), 1);
+    assert_eq!(sampler.arrived(100, 30_000), None);
+    assert_eq!(sampler.arrived(101, 10), Some(50_000));
+    assert_eq!(sampler.every(), 50);
+    assert_eq!(shown(&mut sampler, 500), 10);
+
+    assert_eq!(sampler.arrived(105, 10), Some(0));
+    assert_eq!(sampler.every(), 1);
+}
+
+#[test]
+fn keeps_fixed_ratios() {
+    let mut sampler = Sampler::new(Some(20), 1_000);
+
+    sampler.arrived(100, 50_000);
+    sampler.arrived(101, 0);
+
+    assert_eq!(sampler.every(), 20);
+}
+
+#[test]
+fn always_shows_important_lines() {
+    let mut sampler = Sampler::new(Some(100), 1_000);
+    sampler.toggle();
+    shown(&mut sampler, 1);
+
+    assert!(sampler.admit(true));
+    assert!(!sampler.admit(false));
+}
+
+#[test]
+fn offers_sampling_once() {
+    let mut sampler = Sampler::new(None, 1_000);
+
+    assert!(!sampler.should_offer(4_000));
+    assert!(sampler.should_offer(6_000));
+    assert!(!sampler.should_offer(6_000));
+}
+
+#[test]
+fn tells_skipped_lines_are_gone_once_off() {
+    let mut sampler = Sampler::new(Some(50), 1_000);
+    sampler.toggle();
+    shown(&mut sampler, 50_000);
+
+    assert_eq!(sampler.banner(),
+               Some("sampling 1/50 — 49,000 lines skipped".to_string()));
+
+    sampler.toggle();
+    assert_eq!(shown(&mut sampler, 10), 10);
+    assert_eq!(sampler.banner(),
+               Some("sampled — 49,000 lines skipped earlier are gone".to_string()));
+}
+
+#[test]
+fn groups_digits_by_thousands() {
+    assert_eq!(grouped(0), "0");
+    assert_eq!(grouped(999), "999");
+    assert_eq!(grouped(49321), "49,321");
+    assert_eq!(grouped(1234567), "1,234,567");
+}
@@ -0,0 +1,141 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::search_history::{self, HistoryEntry, SearchFlags, SearchHistory,
+                                 VERSION_HEADER};
+
+fn word() -> SearchFlags {
+    SearchFlags { whole_word: true, ..SearchFlags::default() }
+}
+
+#[test]
+fn unversioned_files_hold_plain_searches() {
+    let entries = search_history::parse("timeout\n\nmsg:error\n");
+
+    assert_eq!(entries,
+               vec![HistoryEntry::new("timeout", SearchFlags::default()),
+                    HistoryEntry::new("msg:error", SearchFlags::default())]);
+}
+
+#[test]
+fn versioned_files_keep_the_options_of_each_search() {
+    let contents = format!("{}\nw\ttimeout\nmu\tmsg:error\tcode\n-\tplain\n", VERSION_HEADER);
+    let entries = search_history::parse(&contents);
+
+    let all_but_word = SearchFlags {
+        filter: true,
+        field_fallback: true,
+        whole_word: false,
+        regex: false,
+        expressions: false,
+    };
+    assert_eq!(entries,
+               vec![HistoryEntry::new("timeout", word()),
+                    HistoryEntry::new("msg:error\tcode", all_but_word),
+                    HistoryEntry::new("plain", SearchFlags::default())]);
+}
+
+#[test]
+fn format_round_trips_and_keeps_the_last_entries() {
+    let entries = vec![HistoryEntry::new("first", SearchFlags::default()),
+                       HistoryEntry::new("second", word()),
+                       HistoryEntry::new("third", SearchFlags::default())];
+    let contents = search_history::format(&entries, 2);
+
+    assert_eq!(contents, format!("{}\nw\tsecond\n-\tthird\n", VERSION_HEADER));
+    assert_eq!(search_history::parse(&contents), entries[1..].to_vec());
+}
+
+#[test]
+fn line_breaks_in_searches_are_escaped() {
+    let entries = vec![HistoryEntry::new("one\ntwo\\n\r", SearchFlags::default()),
+                       HistoryEntry::new("after", word())];
+    let contents = search_history::format(&entries, 10);
+
+    assert_eq!(contents, format!("{}\n-\tone\\ntwo\\\\n\\r\nw\tafter\n", VERSION_HEADER));
+    assert_eq!(search_history::parse(&contents), entries);
+}
+
+#[test]
+fn files_written_before_escaping_are_read_as_they_are() {
+    let entries = search_history::parse("#flow-history 2\nr\t/took \\d+ms/\n");
+
+    let regex = SearchFlags { regex: true, ..SearchFlags::default() };
+    assert_eq!(entries, vec![HistoryEntry::new("/took \\d+ms/", regex)]);
+}
+
+#[test]
+fn badges_name_the_options_that_are_on() {
+    let flags = SearchFlags { filter: true, ..word() };
+
+    assert_eq!(flags.badges(), vec!["filter", "word"]);
+    assert!(SearchFlags::default().badges().is_empty());
+}
+
+#[test]
+fn keeps_how_the_text_was_read() {
+    let flags = SearchFlags { regex: true, expressions: true, ..word() };
+    let entries = vec![HistoryEntry::new("/took \\d+ms/ NOT GET", flags)];
+    let contents = search_history::format(&entries, 10);
+
+    assert_eq!(contents, format!("{}\nwre\t/took \\\\d+ms/ NOT GET\n", VERSION_HEADER));
+    assert_eq!(search_history::parse(&contents), entries);
+    assert_eq!(entries[0].flags.badges(), vec!["word", "regex", "expr"]);
+}
+
+fn history() -> SearchHistory {
+    SearchHistory::new(vec![HistoryEntry::new("timeout", word()),
+                            HistoryEntry::new("msg:error", SearchFlags::default()),
+                            HistoryEntry::new("timeout 30s", SearchFlags::default())])
+}
+
+#[test]
+fn recalling_goes_back_to_what_was_typed() {
+    let mut history = history();
+    let typed = HistoryEntry::new("msg", word());
+
+    assert_eq!(history.previous(&typed).unwrap().text, "timeout 30s");
+    assert_eq!(history.previous(&typed).unwrap().text, "msg:error");
+    assert_eq!(history.next().unwrap().text, "timeout 30s");
+    assert_eq!(history.next(), Some(typed));
+    assert_eq!(history.next(), None);
+}
+
+#[test]
+fn searching_back_finds_older_entries_with_the_same_text() {
+    let mut history = history();
+    let typed = HistoryEntry::new("time", SearchFlags::default());
+
+    let found = history.search_back(&typed).unwrap();
+    assert_eq!(found.text, "timeout 30s");
+    assert_eq!(history.search_back(&found), Some(HistoryEntry::new("timeout", word())));
+    assert_eq!(history.shown("timeout").map(|entry| entry.flags), Some(word()));
+    assert_eq!(history.shown("timeout 1m"), None);
+}
+
+#[test]
+fn searches_made_again_update_their_options() {
+    let mut history = history();
+    history.add("timeout 30s", word());
+    history.add("ab", SearchFlags::default());
+
+    assert_eq!(history.entries().len(), 3);
+    assert_eq!(history.entries()[2].flags, word());
+}
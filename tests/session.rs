@@ -0,0 +1,154 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+extern crate unicode_width;
+
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use flow::core::session::{Recorder, Player, Step, hash_line};
+use flow::ui::input::{Input, Key, Modifier};
+use unicode_width::UnicodeWidthStr;
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("flow-session-{}", name));
+    let _ = fs::remove_file(&path);
+
+    path
+}
+
+fn record(name: &str, hash_lines: bool) -> String {
+    let path = temp_path(name);
+    let path = path.to_string_lossy().into_owned();
+    let mut recorder = Recorder::create(&path, hash_lines).unwrap();
+
+    recorder.start(120, 40, 1_000).unwrap();
+    recorder.record_lines(&[(0, Arc::from("started worker"))], 1_000).unwrap();
+    recorder.record_input(&Input::None, -1, 1_100).unwrap();
+    recorder.record_input(&Input::Kb(Key::Char('f'), Some(Modifier::Ctrl)), 6, 1_200).unwrap();
+    recorder.record_input(&Input::Resize, 410, 1_500).unwrap();
+
+    path
+}
+
+#[test]
+fn plays_back_what_was_recorded() {
+    let path = record("roundtrip", false);
+    let mut player = Player::load(&path).unwrap().unwrap();
+
+    assert_eq!((120, 40, false),
+               (player.header.columns, player.header.rows, player.header.hashed));
+    assert_eq!(Step::Lines(vec![(0, "started worker".to_string())]), player.next(0));
+    assert_eq!(Step::Wait(200), player.next(0));
+    assert_eq!(Step::Input(Input::Kb(Key::Char('f'), Some(Modifier::Ctrl)), 6),
+               player.next(250));
+    assert_eq!(Step::Input(Input::Resize, 410), player.next(600));
+    assert_eq!(Step::Finished, player.next(600));
+}
+
+#[test]
+fn keeps_the_recorded_order_when_late() {
+    let path = record("late", false);
+    let mut player = Player::load(&path).unwrap().unwrap();
+
+    player.next(0);
+    assert_eq!(Step::Input(Input::Kb(Key::Char('f'), Some(Modifier::Ctrl)), 6),
+               player.next(5_000));
+    assert_eq!(Step::Input(Input::Resize, 410), player.next(5_000));
+}
+
+#[test]
+fn records_lines_hashed_on_request() {
+    let path = record("hashed", true);
+    let mut player = Player::load(&path).unwrap().unwrap();
+
+    assert!(player.header.hashed);
+    match player.next(0) {
+        Step::Lines(lines) => {
+            assert_eq!("started worker".len(), lines[0].1.len());
+            assert!(!lines[0].1.contains("worker"));
+        }
+        step => panic!("unexpected step {:?}", step),
+    }
+}
+
+#[test]
+fn hashing_keeps_the_layout_of_lines() {
+    let hashed = hash_line("GET /api/items 200 in 12ms, GET again", &RandomState::new());
+
+    assert_eq!("GET /api/items 200 in 12ms, GET again".len(), hashed.len());
+    assert_eq!(&hashed[..3], &hashed[28..31]);
+    assert_eq!(" /", &hashed[3..5]);
+    assert!(!hashed.contains("items"));
+}
+
+#[test]
+fn hashing_keeps_the_kind_and_width_of_characters() {
+    let hashed = hash_line("Took 250ms 東京", &RandomState::new());
+    let chars = hashed.chars().collect::<Vec<_>>();
+
+    assert!(chars[0].is_ascii_uppercase() && chars[1..4].iter().all(char::is_ascii_lowercase));
+    assert!(chars[5..8].iter().all(char::is_ascii_digit));
+    assert_eq!(15, UnicodeWidthStr::width(&*hashed));
+    assert!(!hashed.contains("東"));
+}
+
+#[test]
+fn hashing_keeps_escapes_timestamps_and_levels() {
+    let line = "\x1b[38;5;208m2016-03-01 10:20:30.120\x1b[0m ERROR payment 42 failed";
+    let hashed = hash_line(line, &RandomState::new());
+
+    assert!(hashed.starts_with("\x1b[38;5;208m2016-03-01 10:20:30.120\x1b[0m ERROR "));
+    assert!(!hashed.contains("payment"));
+}
+
+#[test]
+fn hashing_depends_on_the_salt() {
+    let line = "payment declined for customer";
+
+    assert!(hash_line(line, &RandomState::new()) != hash_line(line, &RandomState::new()));
+}
+
+#[test]
+fn leaves_log_files_to_be_replayed() {
+    let path = temp_path("log");
+    File::create(&path).unwrap().write_all(b"2016-03-01 10:20:30 started\n").unwrap();
+
+    assert!(Player::load(&path.to_string_lossy()).unwrap().is_none());
+}
+
+#[test]
+fn leaves_log_files_that_are_not_utf8_to_be_replayed() {
+    let path = temp_path("latin1");
+    File::create(&path).unwrap().write_all(b"caf\xe9 opened\n").unwrap();
+
+    assert!(Player::load(&path.to_string_lossy()).unwrap().is_none());
+}
+
+#[test]
+fn reports_invalid_entries() {
+    let path = record("invalid", false);
+    fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"{\n").unwrap();
+
+    assert!(Player::load(&path).is_err());
+}
@@ -0,0 +1,55 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::ui::size_probe::{SizeProbe, PROBE_INTERVAL};
+
+#[test]
+fn resizes_once_the_same_divergence_is_seen_twice() {
+    let mut probe = SizeProbe::default();
+
+    assert_eq!(probe.check(0, (24, 80), || Some((30, 100))), None);
+    assert_eq!(probe.check(PROBE_INTERVAL, (24, 80), || Some((30, 100))), Some((30, 100)));
+}
+
+#[test]
+fn ignores_sizes_that_keep_changing_or_match() {
+    let mut probe = SizeProbe::default();
+
+    assert_eq!(probe.check(0, (24, 80), || Some((30, 100))), None);
+    assert_eq!(probe.check(PROBE_INTERVAL, (24, 80), || Some((31, 100))), None);
+    assert_eq!(probe.check(PROBE_INTERVAL * 2, (24, 80), || Some((24, 80))), None);
+    assert_eq!(probe.check(PROBE_INTERVAL * 3, (24, 80), || Some((31, 100))), None);
+    assert_eq!(probe.check(PROBE_INTERVAL * 4, (24, 80), || None), None);
+}
+
+#[test]
+fn measures_at_most_once_per_interval() {
+    let mut probe = SizeProbe::default();
+    let mut measured = 0;
+
+    for now in 0..PROBE_INTERVAL * 2 {
+        probe.check(now, (24, 80), || {
+            measured += 1;
+            None
+        });
+    }
+
+    assert_eq!(measured, 2);
+}
@@ -0,0 +1,85 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::line::{Line, SearchPattern};
+use flow::ui::rendered_line::RenderedLineCollection;
+use flow::ui::snapshot::capture;
+use flow::ui::viewport::Viewport;
+use flow::utils::wrap::wrap;
+
+fn collection(lines: &[(&str, usize)]) -> RenderedLineCollection {
+    let mut collection = RenderedLineCollection::default();
+
+    for &(text, height) in lines {
+        collection.create(Line::new(text.to_string()), height, None);
+    }
+
+    collection
+}
+
+#[test]
+fn wraps_text_by_width() {
+    assert_eq!(wrap("abcdefgh", 3), vec![0..3, 3..6, 6..8]);
+    assert_eq!(wrap("abcdef", 3), vec![0..3, 3..6]);
+    assert_eq!(wrap("", 3), vec![0..0]);
+}
+
+#[test]
+fn wraps_wide_characters_that_dont_fit() {
+    let text = "ab日本";
+
+    assert_eq!(wrap(text, 3), vec![0..2, 2..5, 5..8]);
+}
+
+#[test]
+fn tabs_stop_at_the_right_margin() {
+    assert_eq!(wrap("a\tb", 4), vec![0..2, 2..3]);
+}
+
+#[test]
+fn captures_visible_rows() {
+    let lines = collection(&[("first", 1), ("second line", 2), ("third", 1)]);
+    let viewport = Viewport::new(1, 2, 4);
+
+    let rows = capture(&lines, &viewport, 6, 0, None);
+
+    assert_eq!(rows, vec!["second".to_string(), " line".to_string()]);
+}
+
+#[test]
+fn marks_rows_with_matches() {
+    let lines = collection(&[("first", 1), ("second line", 2)]);
+    let viewport = Viewport::new(0, 3, 3);
+
+    let rows = capture(&lines, &viewport, 6, 0, Some(&SearchPattern::new("lin", false)));
+
+    assert_eq!(rows,
+               vec!["    first".to_string(), "    second".to_string(), ">>>  line".to_string()]);
+}
+
+#[test]
+fn leaves_out_the_continuation_prefix() {
+    let lines = collection(&[("abcdefgh", 3)]);
+    let viewport = Viewport::new(0, 3, 3);
+
+    let rows = capture(&lines, &viewport, 4, 2, None);
+
+    assert_eq!(rows, vec!["abcd".to_string(), "ef".to_string(), "gh".to_string()]);
+}
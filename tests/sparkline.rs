@@ -0,0 +1,45 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::ui::sparkline::render;
+
+#[test]
+fn scales_to_the_largest_value() {
+    assert_eq!(render(&[0, 1, 4, 8, 16], 5), " ▁▂▃▇");
+    assert_eq!(render(&[5, 5, 5], 3), "▇▇▇");
+}
+
+#[test]
+fn averages_values_sharing_a_cell() {
+    let mut values = vec![0; 60];
+    values[59] = 10;
+    values[0] = 5;
+
+    let sparkline = render(&values, 12);
+    assert_eq!(sparkline.chars().count(), 12);
+    assert!(sparkline.starts_with('▃'));
+    assert!(sparkline.ends_with('▇'));
+    assert_eq!(sparkline.chars().filter(|c| *c == ' ').count(), 10);
+}
+
+#[test]
+fn renders_nothing_without_values() {
+    assert_eq!(render(&[], 12), "");
+}
@@ -0,0 +1,29 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::ui::status::truncate;
+
+#[test]
+fn truncates_long_patterns_with_an_ellipsis() {
+    assert_eq!("GET", truncate("GET", 5));
+    assert_eq!("error", truncate("error", 5));
+    assert_eq!("erro…", truncate("errors", 5));
+    assert_eq!("日…", truncate("日本語", 4));
+}
@@ -0,0 +1,126 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process;
+use std::time::{Duration, Instant};
+
+use flow::core::tail::{Tail, PARTIAL_TIMEOUT, SETTLE_TIMEOUT};
+
+// A fresh, empty file for each test, unique to this run.
+fn temp_file(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("flow-tail-{}-{}.log", name, process::id()));
+    fs::write(&path, "").unwrap();
+
+    path
+}
+
+fn write(path: &PathBuf, contents: &[u8]) {
+    let mut file = OpenOptions::new().append(true).open(path).unwrap();
+    file.write_all(contents).unwrap();
+}
+
+fn read(tail: &mut Tail) -> Vec<String> {
+    tail.read_to_end().iter().map(|line| line.to_string()).collect()
+}
+
+// Time at which the line being written is shown, as nothing more came.
+fn partial_shown_at() -> Instant {
+    Instant::now() + Duration::from_millis(PARTIAL_TIMEOUT)
+}
+
+#[test]
+fn keeps_lines_being_written_until_their_newline() {
+    let path = temp_file("newline");
+    let mut tail = Tail::open(&path.to_string_lossy()).unwrap();
+
+    write(&path, b"one\ntw");
+    assert_eq!(read(&mut tail), vec!["one"]);
+    assert_eq!(tail.partial(), None);
+
+    write(&path, b"o\r\nthr");
+    assert_eq!(read(&mut tail), vec!["two"]);
+    write(&path, b"ee\n");
+    assert_eq!(read(&mut tail), vec!["three"]);
+}
+
+#[test]
+fn shows_lines_written_slowly() {
+    let path = temp_file("slowly");
+    let mut tail = Tail::open(&path.to_string_lossy()).unwrap();
+
+    write(&path, b"wor");
+    assert!(tail.read_to_end().is_empty());
+    assert_eq!(tail.partial(), None);
+    assert_eq!(tail.partial_at(partial_shown_at()), Some("wor".to_string()));
+
+    // Once shown, it's told about as it grows
+    write(&path, b"ld");
+    assert!(tail.read_to_end().is_empty());
+    assert_eq!(tail.partial(), Some("world".to_string()));
+
+    write(&path, b"\nnext");
+    assert_eq!(read(&mut tail), vec!["world"]);
+    assert_eq!(tail.partial(), None);
+}
+
+#[test]
+fn waits_for_characters_cut_short() {
+    let path = temp_file("characters");
+    let mut tail = Tail::open(&path.to_string_lossy()).unwrap();
+    let text = "café\n".as_bytes();
+
+    write(&path, &text[..4]);
+    assert!(tail.read_to_end().is_empty());
+    assert_eq!(tail.partial_at(partial_shown_at()), Some("caf".to_string()));
+
+    write(&path, &text[4..]);
+    assert_eq!(read(&mut tail), vec!["café"]);
+}
+
+#[test]
+fn takes_lines_left_without_a_newline_as_complete() {
+    let path = temp_file("settle");
+    let mut tail = Tail::open(&path.to_string_lossy()).unwrap();
+
+    write(&path, b"one\nlast");
+    assert_eq!(read(&mut tail), vec!["one"]);
+    let settled_at = Instant::now() + Duration::from_millis(SETTLE_TIMEOUT);
+    assert_eq!(tail.read_to_end_at(settled_at), vec!["last".into()]);
+    assert_eq!(tail.partial(), None);
+
+    write(&path, b"more\n");
+    assert_eq!(read(&mut tail), vec!["more"]);
+}
+
+#[test]
+fn initial_reads_include_the_last_line_without_a_newline() {
+    let path = temp_file("initial");
+    write(&path, b"one\ntwo\nlast");
+    let mut tail = Tail::open(&path.to_string_lossy()).unwrap();
+
+    let lines = tail.read_lines(2);
+    assert_eq!(lines.iter().map(|line| line.to_string()).collect::<Vec<_>>(),
+               vec!["two", "last"]);
+    assert_eq!(tail.partial(), None);
+}
@@ -0,0 +1,136 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::timestamp::{DAY, first_at_or_after, parse_target, parse_timestamp, parse_duration,
+                            format_duration, format_minute, civil_from_days, days_from_civil,
+                            GapTracker};
+
+// 2016-03-01 12:00:00
+const NOW: i64 = 1_456_833_600_000;
+
+fn first(lines: &[&str], target: &str) -> Option<usize> {
+    let target = parse_target(target, NOW).unwrap();
+    first_at_or_after(lines.len(), |index| parse_timestamp(lines[index]), target)
+}
+
+#[test]
+fn parses_jump_targets() {
+    let midnight = NOW - 12 * 3_600_000;
+
+    assert_eq!(Ok(midnight + (14 * 3_600 + 32 * 60) * 1_000), parse_target("14:32", NOW));
+    assert_eq!(Ok(midnight + (9 * 3_600 + 5) * 1_000), parse_target("@9:00:05", NOW));
+    assert_eq!(Ok(midnight + 30_000), parse_target(":ts 00:00:30", NOW));
+    assert_eq!(Ok(NOW - 5 * 60_000), parse_target("-5m", NOW));
+    assert_eq!(Ok(NOW - 2 * DAY), parse_target("-2d", NOW));
+    assert_eq!(parse_timestamp("2016-02-29 23:59:59.5").ok_or(()),
+               parse_target("2016-02-29T23:59:59.500Z", NOW).map_err(|_| ()));
+    assert_eq!(parse_target("2016-03-01T14:32:05", NOW),
+               parse_target("2016-03-01T14:32:05+02:00", NOW));
+
+    assert!(parse_target("25:00", NOW).is_err());
+    assert!(parse_target("14:32 please", NOW).is_err());
+    assert!(parse_target("yesterday", NOW).is_err());
+}
+
+#[test]
+fn rejects_relative_targets_out_of_range() {
+    assert!(parse_target("-99999999999999999999s", NOW).is_err());
+    assert!(parse_target("-999999999999d", NOW).is_err());
+    assert!(parse_target("-9223372036854775807s", NOW).is_err());
+    assert!(parse_target("-1s", i64::min_value()).is_err());
+}
+
+#[test]
+fn finds_the_first_line_at_or_after_the_target() {
+    let lines = ["2016-03-01 14:30:00 start",
+                 "2016-03-01 14:31:59 a",
+                 "  at trace",
+                 "2016-03-01 14:32:05 b",
+                 "2016-03-01 14:32:05 c",
+                 "2016-03-01 14:40:00 d"];
+
+    assert_eq!(Some(3), first(&lines, "14:32"));
+    assert_eq!(Some(3), first(&lines, "14:32:05"));
+    assert_eq!(Some(5), first(&lines, "14:32:06"));
+    assert_eq!(Some(0), first(&lines, "10:00"));
+    assert_eq!(None, first(&lines, "15:00"));
+    assert_eq!(Some(0), first(&lines, "-5m"));
+}
+
+#[test]
+fn lines_without_dates_are_taken_as_the_same_day() {
+    let lines = ["08:00:00 a", "trace", "trace", "09:15:00 b", "10:00:00 c"];
+
+    assert_eq!(Some(3), first(&lines, "09:00"));
+    assert_eq!(Some(3), first(&lines, "2016-02-10T09:15:00"));
+    assert_eq!(Some(3), first(&lines, "08:00:01"));
+}
+
+#[test]
+fn parses_durations() {
+    assert_eq!(Ok(90_000), parse_duration("90"));
+    assert_eq!(Ok(90_000), parse_duration(" 90s "));
+    assert_eq!(Ok(300_000), parse_duration("5m"));
+    assert_eq!(Ok(7_200_000), parse_duration("2h"));
+    assert_eq!(Ok(0), parse_duration("0"));
+    assert!(parse_duration("soon").is_err());
+    assert!(parse_duration("-5m").is_err());
+    assert!(parse_duration("9223372036854775807h").is_err());
+    assert!(parse_duration("99999999999999999999").is_err());
+}
+
+#[test]
+fn formats_durations_with_two_units_at_most() {
+    assert_eq!("4m 12s", format_duration(252_000));
+    assert_eq!("45s", format_duration(45_900));
+    assert_eq!("2h", format_duration(7_230_000));
+    assert_eq!("1d 3h", format_duration(DAY + 3 * 3_600_000 + 5_000));
+    assert_eq!("0s", format_duration(300));
+}
+
+#[test]
+fn finds_gaps_between_timestamped_lines() {
+    let lines = ["12:00:00 start", "12:00:30 work", "    at trace", "12:05:00 resumed", "12:05:10 done"];
+    let mut tracker = GapTracker::new(60_000);
+
+    let gaps = lines.iter()
+        .map(|line| tracker.advance(parse_timestamp(line), 0))
+        .collect::<Vec<_>>();
+
+    assert_eq!(vec![None, None, None, Some(270_000), None], gaps);
+}
+
+#[test]
+fn times_lines_by_arrival_until_a_timestamp_shows_up() {
+    let mut tracker = GapTracker::new(1_000);
+
+    assert_eq!(None, tracker.advance(None, 10_000));
+    assert_eq!(Some(5_000), tracker.advance(None, 15_000));
+    assert_eq!(None, tracker.advance(parse_timestamp("12:00:00 first"), 30_000));
+    assert_eq!(None, tracker.advance(None, 90_000));
+}
+
+#[test]
+fn formats_minutes() {
+    assert_eq!("2016-03-01 12:00", format_minute(NOW + 59_999));
+    assert_eq!("14:32", format_minute(parse_timestamp("14:32:05 started").unwrap()));
+    assert_eq!((1999, 12, 31), civil_from_days(days_from_civil(1999, 12, 31)));
+    assert_eq!((1969, 7, 20), civil_from_days(days_from_civil(1969, 7, 20)));
+}
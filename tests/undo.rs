@@ -0,0 +1,170 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::undo::{Change, History, Setting, Step, TabRules, MAX_STEPS};
+
+fn search(before: &str, after: &str) -> Step {
+    let change = Change::Search(before.to_string(), after.to_string());
+    Step::new(format!("searched `{}`", after), vec![change])
+}
+
+fn rules_of(tab: usize) -> Step {
+    let rules = TabRules {
+        rewrites: vec![],
+        line_rules: vec![],
+    };
+    Step::new(format!("tab {}", tab), vec![Change::Rules(tab, rules.clone(), rules)])
+}
+
+fn texts(changes: &[Change]) -> Vec<(String, String)> {
+    changes.iter()
+        .map(|change| match *change {
+            Change::Search(ref before, ref after) => (before.clone(), after.clone()),
+            _ => panic!("not a search"),
+        })
+        .collect()
+}
+
+#[test]
+fn undoes_steps_in_reverse() {
+    let mut history = History::default();
+    history.record(search("", "a"));
+    history.record(search("a", "ab"));
+
+    let (description, changes) = history.undo().unwrap();
+    assert_eq!(description, "searched `ab`");
+    assert_eq!(texts(&changes), vec![("ab".to_string(), "a".to_string())]);
+
+    let (description, _) = history.undo().unwrap();
+    assert_eq!(description, "searched `a`");
+    assert!(history.undo().is_none());
+}
+
+#[test]
+fn reverses_changes_of_a_step_in_reverse_order() {
+    let mut history = History::default();
+    let changes = vec![Change::Search("".to_string(), "a".to_string()),
+                       Change::Search("a".to_string(), "b".to_string())];
+    history.record(Step::new("both".to_string(), changes));
+
+    let (_, undone) = history.undo().unwrap();
+    assert_eq!(texts(&undone),
+               vec![("b".to_string(), "a".to_string()), ("a".to_string(), "".to_string())]);
+
+    let (_, redone) = history.redo().unwrap();
+    assert_eq!(texts(&redone),
+               vec![("".to_string(), "a".to_string()), ("a".to_string(), "b".to_string())]);
+}
+
+#[test]
+fn redoes_until_something_else_changes() {
+    let mut history = History::default();
+    history.record(search("", "a"));
+    history.undo();
+
+    let (description, changes) = history.redo().unwrap();
+    assert_eq!(description, "searched `a`");
+    assert_eq!(texts(&changes), vec![("".to_string(), "a".to_string())]);
+    assert!(history.redo().is_none());
+
+    history.undo();
+    history.record(search("", "b"));
+    assert!(history.redo().is_none());
+    assert_eq!(history.last().unwrap().description, "searched `b`");
+}
+
+#[test]
+fn describes_toggles() {
+    let step = Step::toggle(Setting::WholeWord);
+    assert_eq!(step.description, "toggled whole word search");
+
+    match step.changes[0].reversed() {
+        Change::Toggle(setting) => assert_eq!(setting, Setting::WholeWord),
+        _ => panic!("not a toggle"),
+    }
+}
+
+#[test]
+fn forgets_the_oldest_steps() {
+    let mut history = History::default();
+    for i in 0..MAX_STEPS + 5 {
+        history.record(search("", &i.to_string()));
+    }
+
+    let mut count = 0;
+    while let Some((description, _)) = history.undo() {
+        count += 1;
+        if count == MAX_STEPS {
+            assert_eq!(description, "searched `5`");
+        }
+    }
+    assert_eq!(count, MAX_STEPS);
+}
+
+#[test]
+fn forgets_steps_of_closed_tabs() {
+    let mut history = History::default();
+    history.record(rules_of(1));
+    history.record(rules_of(2));
+    history.record(Step::toggle(Setting::Raw(1)));
+    history.record(Step::toggle(Setting::Ruler));
+
+    history.tab_closed(1);
+
+    assert_eq!(history.undo().unwrap().0, "toggled the ruler");
+    let (description, changes) = history.undo().unwrap();
+    assert_eq!(description, "tab 2");
+    assert_eq!(changes[0].tab(), Some(1));
+    assert!(history.undo().is_none());
+}
+
+#[test]
+fn edits_of_the_search_made_in_a_row_are_one_step() {
+    let mut history = History::default();
+    history.record_search("".to_string(), "t".to_string());
+    history.record_search("t".to_string(), "ti".to_string());
+    history.record_search("ti".to_string(), "tim".to_string());
+
+    let (description, changes) = history.undo().unwrap();
+    assert_eq!(description, "searched `tim`");
+    assert_eq!(texts(&changes), vec![("tim".to_string(), "".to_string())]);
+    assert!(history.undo().is_none());
+}
+
+#[test]
+fn edits_of_the_search_start_a_new_step_after_other_changes() {
+    let mut history = History::default();
+    history.record_search("".to_string(), "a".to_string());
+    history.record(Step::toggle(Setting::ColumnCursor));
+    history.record_search("a".to_string(), "".to_string());
+
+    assert_eq!(history.undo().unwrap().0, "cleared the search");
+    assert_eq!(history.undo().unwrap().0, "toggled the column cursor");
+    assert_eq!(history.undo().unwrap().0, "searched `a`");
+}
+
+#[test]
+fn edits_taking_the_search_back_leave_no_step() {
+    let mut history = History::default();
+    history.record_search("".to_string(), "a".to_string());
+    history.record_search("a".to_string(), "".to_string());
+
+    assert!(history.undo().is_none());
+}
@@ -0,0 +1,261 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::ui::viewport::{Viewport, Anchor, LineAnchor};
+
+#[test]
+fn content_shorter_than_screen_cannot_be_scrolled() {
+    let mut viewport = Viewport::new(0, 10, 4);
+
+    assert_eq!(0, viewport.max_reverse_index());
+    viewport.scroll_by(1);
+    assert_eq!(0, viewport.reverse_index);
+    viewport.scroll_to_top();
+    assert_eq!(0, viewport.reverse_index);
+
+    assert_eq!(0, viewport.top_row());
+    assert_eq!(0..4, viewport.visible_rows());
+    assert!(viewport.is_row_visible(3));
+    assert!(!viewport.is_row_visible(4));
+}
+
+#[test]
+fn content_as_tall_as_screen_cannot_be_scrolled() {
+    let mut viewport = Viewport::new(3, 10, 10);
+
+    assert_eq!(0, viewport.reverse_index);
+    viewport.scroll_by(-1);
+    assert_eq!(0, viewport.reverse_index);
+    assert_eq!(0..10, viewport.visible_rows());
+}
+
+#[test]
+fn content_one_row_taller_than_screen_scrolls_once() {
+    let mut viewport = Viewport::new(0, 10, 11);
+
+    assert_eq!(1, viewport.top_row());
+    assert!(!viewport.is_row_visible(0));
+    assert!(viewport.is_row_visible(10));
+
+    viewport.scroll_by(5);
+    assert_eq!(1, viewport.reverse_index);
+    assert_eq!(0, viewport.top_row());
+    assert!(viewport.is_row_visible(0));
+    assert!(!viewport.is_row_visible(10));
+
+    viewport.scroll_by(-5);
+    assert_eq!(0, viewport.reverse_index);
+}
+
+#[test]
+fn initial_position_is_clamped() {
+    let viewport = Viewport::new(100, 10, 30);
+
+    assert_eq!(20, viewport.reverse_index);
+    assert_eq!(0, viewport.top_row());
+}
+
+#[test]
+fn scrolls_to_top_and_bottom() {
+    let mut viewport = Viewport::new(0, 10, 30);
+
+    viewport.scroll_to_top();
+    assert_eq!(20, viewport.reverse_index);
+    assert_eq!(0..10, viewport.visible_rows());
+
+    viewport.scroll_to_bottom();
+    assert_eq!(0, viewport.reverse_index);
+    assert_eq!(20..30, viewport.visible_rows());
+}
+
+#[test]
+fn scrolls_to_row() {
+    let mut viewport = Viewport::new(0, 10, 30);
+
+    viewport.scroll_to_row(5);
+    assert_eq!(5, viewport.top_row());
+
+    viewport.scroll_to_row(25);
+    assert_eq!(20, viewport.top_row());
+}
+
+#[test]
+fn scrolls_early_to_keep_a_margin_around_the_row() {
+    // Rows 10 to 19 are visible
+    let mut viewport = Viewport::new(10, 10, 30);
+
+    viewport.scroll_with_margin(15, 3);
+    assert_eq!(10, viewport.top_row());
+
+    // Moving down near the bottom of the screen
+    viewport.scroll_with_margin(17, 3);
+    assert_eq!(11, viewport.top_row());
+
+    // Moving up near the top of the screen
+    viewport.scroll_with_margin(12, 3);
+    assert_eq!(9, viewport.top_row());
+
+    // Without a margin the row only has to be visible
+    viewport.scroll_with_margin(9, 0);
+    assert_eq!(9, viewport.top_row());
+    viewport.scroll_with_margin(18, 0);
+    assert_eq!(9, viewport.top_row());
+}
+
+#[test]
+fn margin_is_clamped_at_the_ends_of_the_content() {
+    let mut viewport = Viewport::new(10, 10, 30);
+
+    viewport.scroll_with_margin(1, 3);
+    assert_eq!(0, viewport.top_row());
+    viewport.scroll_with_margin(0, 3);
+    assert_eq!(0, viewport.top_row());
+
+    viewport.scroll_with_margin(28, 3);
+    assert_eq!(20, viewport.top_row());
+    viewport.scroll_with_margin(29, 3);
+    assert_eq!(20, viewport.top_row());
+}
+
+#[test]
+fn margin_shrinks_on_short_screens() {
+    // Only one row can be kept on either side of the middle one
+    let mut viewport = Viewport::new(0, 3, 30);
+    viewport.scroll_to_row(10);
+
+    viewport.scroll_with_margin(13, 5);
+    assert_eq!(12, viewport.top_row());
+    viewport.scroll_with_margin(12, 5);
+    assert_eq!(11, viewport.top_row());
+
+    // No margin fits on a single row
+    let mut viewport = Viewport::new(0, 1, 30);
+    viewport.scroll_to_row(10);
+
+    viewport.scroll_with_margin(11, 5);
+    assert_eq!(11, viewport.top_row());
+}
+
+#[test]
+fn finds_visible_line_range() {
+    let heights = vec![3, 1, 2, 4, 1];
+    let mut viewport = Viewport::new(0, 4, 11);
+
+    // Rows 7 to 10, the last row of the fourth line and the fifth line
+    assert_eq!(3..5, viewport.line_range_visible(heights.clone()));
+
+    viewport.scroll_to_top();
+    assert_eq!(0..2, viewport.line_range_visible(heights.clone()));
+
+    viewport.scroll_by(-2);
+    assert_eq!(0..3, viewport.line_range_visible(heights.clone()));
+}
+
+#[test]
+fn empty_content_has_no_visible_lines() {
+    let viewport = Viewport::new(0, 4, 0);
+
+    assert_eq!(0..0, viewport.visible_rows());
+    assert_eq!(0..0, viewport.line_range_visible(vec![]));
+}
+
+#[test]
+fn bottom_anchor_follows_new_rows_unless_scrolled() {
+    let mut following = Viewport::new(0, 10, 25);
+    following.rows_appended(5, Anchor::Bottom);
+    assert_eq!(0, following.reverse_index);
+
+    let mut scrolled = Viewport::new(3, 10, 25);
+    scrolled.rows_appended(5, Anchor::Bottom);
+    assert_eq!(8, scrolled.reverse_index);
+    assert_eq!(7, scrolled.top_row());
+}
+
+#[test]
+fn top_anchor_keeps_the_top_row() {
+    let mut viewport = Viewport::new(0, 10, 25);
+    viewport.rows_appended(5, Anchor::Top);
+
+    assert_eq!(5, viewport.reverse_index);
+    assert_eq!(10, viewport.top_row());
+}
+
+#[test]
+fn top_anchor_keeps_short_content_at_the_top() {
+    let mut viewport = Viewport::new(0, 10, 12);
+    viewport.rows_appended(8, Anchor::Top);
+
+    assert_eq!(0, viewport.top_row());
+}
+
+#[test]
+fn navigation_is_the_same_under_both_anchors() {
+    for anchor in &[Anchor::Bottom, Anchor::Top] {
+        let mut viewport = Viewport::new(4, 10, 50);
+        viewport.rows_appended(10, *anchor);
+        let top_row = viewport.top_row();
+
+        viewport.scroll_by(3);
+        assert_eq!(top_row - 3, viewport.top_row());
+        viewport.scroll_by(-3);
+        assert_eq!(top_row, viewport.top_row());
+
+        viewport.scroll_to_row(7);
+        assert_eq!(7, viewport.top_row());
+        viewport.scroll_to_top();
+        assert_eq!(0, viewport.top_row());
+        viewport.scroll_to_bottom();
+        assert_eq!(40, viewport.top_row());
+    }
+}
+
+#[test]
+fn anchors_on_the_line_at_the_top_of_the_screen() {
+    // Lines 1 to 5, the third one wrapped over three rows
+    let lines = vec![(1, 1), (2, 1), (3, 3), (4, 1), (5, 1)];
+    let viewport = Viewport::new(2, 3, 7);
+
+    assert_eq!(2, viewport.top_row());
+    assert_eq!(Some(LineAnchor { line_id: 3, offset: 0 }),
+               viewport.line_anchor(lines.clone()));
+
+    let viewport = Viewport::new(1, 3, 7);
+    assert_eq!(Some(LineAnchor { line_id: 3, offset: 1 }), viewport.line_anchor(lines));
+}
+
+#[test]
+fn keeps_the_anchored_line_in_place_as_lines_come_and_go() {
+    let anchor = LineAnchor { line_id: 3, offset: 1 };
+
+    // Line 1 was evicted and lines 6 and 7 were appended
+    let lines = vec![(2, 1), (3, 3), (4, 1), (5, 1), (6, 1), (7, 1)];
+    let mut viewport = Viewport::new(0, 3, 8);
+
+    assert!(viewport.scroll_to_anchor(&anchor, lines.clone()));
+    assert_eq!(2, viewport.top_row());
+    assert_eq!(Some(anchor), viewport.line_anchor(lines));
+
+    // The anchored line itself is gone
+    let lines = vec![(4, 1), (5, 1), (6, 1), (7, 1)];
+    let mut viewport = Viewport::new(0, 3, 4);
+
+    assert!(!viewport.scroll_to_anchor(&anchor, lines));
+    assert_eq!(0, viewport.reverse_index);
+}
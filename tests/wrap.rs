@@ -0,0 +1,151 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::utils::wrap::{char_index_at, char_index_at_indented, char_at_column, glyph_at_indented,
+                        line_column, wrap_indented};
+use flow::ui::ruler::ruler_text;
+
+fn rows(text: &str, width: usize, indent: usize) -> Vec<&str> {
+    wrap_indented(text, width, indent).into_iter().map(|row| &text[row]).collect()
+}
+
+#[test]
+fn maps_columns_of_plain_text() {
+    assert_eq!(char_index_at("abcdef", 80, 0, 3), Some(3));
+    assert_eq!(char_index_at("abcdef", 80, 0, 6), None);
+    assert_eq!(char_index_at("abcdef", 80, 1, 0), None);
+}
+
+#[test]
+fn maps_both_columns_of_wide_characters() {
+    let text = "aこんにちはb";
+
+    assert_eq!(char_index_at(text, 80, 0, 1), Some(1));
+    assert_eq!(char_index_at(text, 80, 0, 2), Some(1));
+    assert_eq!(char_index_at(text, 80, 0, 3), Some(2));
+    assert_eq!(char_index_at(text, 80, 0, 11), Some(6));
+}
+
+#[test]
+fn maps_every_column_of_a_tab() {
+    let text = "ab\tc";
+
+    assert_eq!(char_index_at(text, 80, 0, 2), Some(2));
+    assert_eq!(char_index_at(text, 80, 0, 7), Some(2));
+    assert_eq!(char_index_at(text, 80, 0, 8), Some(3));
+}
+
+#[test]
+fn maps_columns_on_wrapped_rows() {
+    // The wide character doesn't fit on the first row and is moved to the next
+    let text = "abcこd";
+
+    assert_eq!(char_index_at(text, 4, 0, 3), None);
+    assert_eq!(char_index_at(text, 4, 1, 0), Some(3));
+    assert_eq!(char_index_at(text, 4, 1, 1), Some(3));
+    assert_eq!(char_index_at(text, 4, 1, 2), Some(4));
+}
+
+#[test]
+fn continuation_rows_start_past_the_prefix() {
+    assert_eq!(wrap_indented("abcdefgh", 4, 2), vec![0..4, 4..6, 6..8]);
+    assert_eq!(wrap_indented("abcこd", 4, 1), vec![0..3, 3..7]);
+    assert_eq!(wrap_indented("abcdef", 3, 0), vec![0..3, 3..6]);
+}
+
+#[test]
+fn prefix_columns_map_to_no_character() {
+    let text = "abcdefgh";
+
+    assert_eq!(char_index_at_indented(text, 4, 2, 0, 1), Some(1));
+    assert_eq!(char_index_at_indented(text, 4, 2, 1, 0), None);
+    assert_eq!(char_index_at_indented(text, 4, 2, 1, 1), None);
+    assert_eq!(char_index_at_indented(text, 4, 2, 1, 2), Some(4));
+    assert_eq!(char_index_at_indented(text, 4, 2, 2, 3), Some(7));
+}
+
+#[test]
+fn computes_unwrapped_columns_from_byte_offsets() {
+    let text = "こん\tx";
+
+    assert_eq!(line_column(text, 0), 0);
+    assert_eq!(line_column(text, 3), 2);
+    assert_eq!(line_column(text, 6), 4);
+    assert_eq!(line_column(text, 7), 8);
+}
+
+#[test]
+fn labels_every_tenth_column() {
+    assert_eq!(ruler_text(24), "0....+....10...+....20..");
+    assert_eq!(ruler_text(11), "0....+....1");
+}
+
+#[test]
+fn finds_every_column_of_wide_glyphs() {
+    assert_eq!(glyph_at_indented("aこb", 10, 0, 0, 2), Some((1, 1..3)));
+    assert_eq!(glyph_at_indented("aこb", 10, 0, 0, 1), Some((1, 1..3)));
+    assert_eq!(glyph_at_indented("aこb", 10, 0, 0, 3), Some((2, 3..4)));
+    assert_eq!(glyph_at_indented("abcdef", 4, 2, 1, 3), Some((5, 3..4)));
+    assert_eq!(glyph_at_indented("ab", 10, 0, 0, 5), None);
+}
+
+#[test]
+fn finds_characters_of_unwrapped_columns() {
+    assert_eq!(char_at_column("こ\tx", 1), Some('こ'));
+    assert_eq!(char_at_column("こ\tx", 5), Some('\t'));
+    assert_eq!(char_at_column("こ\tx", 8), Some('x'));
+    assert_eq!(char_at_column("こ\tx", 9), None);
+}
+
+#[test]
+fn urls_wrap_after_separators() {
+    let url = "https://example.com/api/v1/users?id=42&sort=name";
+
+    assert_eq!(rows(url, 20, 0), vec!["https://example.com/", "api/v1/users?id=42&", "sort=name"]);
+    assert_eq!(rows(url, 20, 2), vec!["https://example.com/", "api/v1/users?", "id=42&sort=name"]);
+}
+
+#[test]
+fn windows_paths_wrap_after_backslashes() {
+    let path = r"C:\Users\dev\AppData\Local\Temp\build-output.log";
+
+    assert_eq!(rows(path, 18, 0),
+               vec![r"C:\Users\dev\", r"AppData\Local\", r"Temp\build-output.", "log"]);
+}
+
+#[test]
+fn blobs_without_separators_wrap_at_the_margin() {
+    let blob = "QUJDREVGR0hJSktMTU5PUFFSU1RVVldY+WVo=";
+
+    assert_eq!(rows(blob, 16, 0), vec!["QUJDREVGR0hJSktM", "TU5PUFFSU1RVVldY", "+WVo="]);
+}
+
+#[test]
+fn separators_leaving_a_row_less_than_half_full_are_ignored() {
+    let blob = "ab/cdefghijklmnopqrstuvwx";
+
+    assert_eq!(rows(blob, 16, 0), vec!["ab/cdefghijklmno", "pqrstuvwx"]);
+}
+
+#[test]
+fn separators_of_earlier_words_are_ignored() {
+    assert_eq!(rows("a.b cdefghij", 8, 0), vec!["a.b cdef", "ghij"]);
+    assert_eq!(rows("see /var/log now", 8, 0), vec!["see /", "var/log ", "now"]);
+}